@@ -0,0 +1,98 @@
+// src/app/availability_page.rs
+// "Generate availability page…": the fetched slots as a standalone HTML file, and
+// the `{{ availability_link }}` pointing at wherever the user hosts it.
+
+use super::MyApp;
+use crate::calendar;
+use crate::calendar::availability_page::{page_days_in, page_link, render_page};
+use crate::calendar::format::SlotFormat;
+use crate::calendar::timezone::LocalZone;
+use chrono::{Duration, Local};
+use log::{error, info};
+use std::fs;
+use std::path::Path;
+
+impl MyApp {
+    /// Where the page is written unless the user picks another path.
+    pub(super) fn default_availability_page_path() -> String {
+        let dir = directories_next::UserDirs::new()
+            .map(|dirs| dirs.home_dir().to_path_buf())
+            .unwrap_or_default();
+        dir.join("availability.html").display().to_string()
+    }
+
+    /// The page's URL for `{{ availability_link }}`; empty without a base URL.
+    pub(super) fn availability_link(&self) -> String {
+        page_link(
+            &self.availability_page.base_url,
+            Path::new(self.availability_page.path.trim()),
+        )
+        .unwrap_or_default()
+    }
+
+    /// Writes the last fetched slots to `availability_page.path`, laid out with the
+    /// same order and "no availability" days as the email.
+    pub(super) fn generate_availability_page(&mut self) {
+        let result = self.render_availability_page().and_then(|html| {
+            let path = self.availability_page.path.trim();
+            fs::write(path, html).map_err(|e| format!("Couldn't write {}: {}", path, e))?;
+            Ok(path.to_string())
+        });
+        match &result {
+            Ok(path) => info!("Wrote availability page to {}", path),
+            Err(e) => error!("Availability page not written: {}", e),
+        }
+        self.availability_page.result = Some(result.map(|path| match self.availability_link() {
+            link if link.is_empty() => format!("Saved to {}.", path),
+            link => format!("Saved to {}. Upload it so {} works.", path, link),
+        }));
+    }
+
+    fn render_availability_page(&self) -> Result<String, String> {
+        let fetched = self
+            .calendar
+            .last_fetch
+            .as_ref()
+            .ok_or("Fetch your slots first.")?;
+        if self.availability_page.path.trim().is_empty() {
+            return Err("Choose where to save the page.".to_string());
+        }
+        let min_len = Duration::minutes(calendar::MIN_SLOT_MINUTES);
+        let unavailable = if self.calendar_settings.show_unavailable_days {
+            calendar::free_busy::unavailable_days(
+                &fetched.windows,
+                min_len,
+                fetched.fetched_at,
+                fetched.range_end(),
+            )
+        } else {
+            Vec::new()
+        };
+        let order = if self.calendar_settings.reverse_slot_order {
+            calendar::free_busy::SlotOrder::LatestFirst
+        } else {
+            calendar::free_busy::SlotOrder::EarliestFirst
+        };
+        let days = page_days_in(
+            &fetched.windows,
+            min_len,
+            &unavailable,
+            order,
+            SlotFormat::default(),
+            &Local,
+        );
+        // Slots are grouped in the zone they were fetched in
+        let zone = fetched.zone.clone().unwrap_or_else(LocalZone::current);
+        render_page(
+            &self.compose.sender_name,
+            &zone.label(),
+            &days,
+            &fetched
+                .fetched_at
+                .with_timezone(&Local)
+                .format("%A %b %-d, %Y")
+                .to_string(),
+        )
+        .map_err(|e| format!("Couldn't render the page: {}", e))
+    }
+}
@@ -0,0 +1,467 @@
+// src/app/backup.rs
+// "Export backup…" / "Import backup…": the saved state (and optionally the
+// Google token cache) in one .tar.gz, for moving to another computer.
+
+use super::MyApp;
+use chrono::{DateTime, Local, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use log::{error, info, warn};
+use secrecy::ExposeSecret;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+/// Bumped when the archive layout changes in a way older versions can't read.
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE: &str = "manifest.json";
+const STATE_FILE: &str = "app_state.json";
+const TOKEN_CACHE_FILE: &str = "tokencache.json";
+
+// A backup is a few small JSON files; anything bigger isn't one of ours
+const MAX_BACKUP_BYTES: u64 = 64 * 1024 * 1024;
+
+/// What a backup says about itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub created_at: DateTime<Utc>,
+    /// The SMTP password and the token cache are in the archive
+    pub includes_credentials: bool,
+}
+
+/// A backup read from disk and checked, waiting for the user to confirm the restore.
+#[derive(Debug, Clone)]
+pub struct PendingImport {
+    pub source: PathBuf,
+    pub manifest: BackupManifest,
+    state_json: Vec<u8>,
+    token_cache: Option<Vec<u8>>,
+    /// Files the restore writes, and whether each one exists now
+    pub targets: Vec<(PathBuf, bool)>,
+}
+
+impl MyApp {
+    /// Where "Export backup…" writes unless the user picks another path.
+    pub(super) fn default_backup_path() -> String {
+        let dir = directories_next::UserDirs::new()
+            .map(|dirs| dirs.home_dir().to_path_buf())
+            .unwrap_or_default();
+        dir.join(format!(
+            "coffee_chat_backup_{}.tar.gz",
+            Local::now().format("%Y-%m-%d")
+        ))
+        .display()
+        .to_string()
+    }
+
+    /// Writes the current session (not just what's on disk) to `backup.export_path`.
+    pub(super) fn handle_export_backup(&mut self) {
+        let include_credentials = self.backup.include_credentials;
+        let path = PathBuf::from(self.backup.export_path.trim());
+        let result = self.export_backup(&path, include_credentials);
+        match &result {
+            Ok(()) => {
+                info!("Exported backup to {:?}", path);
+                self.status_message = format!("Backup saved to {}", path.display());
+            }
+            Err(e) => error!("Backup export failed: {}", e),
+        }
+        self.backup.result = Some(result.map(|()| format!("Saved to {}", path.display())));
+    }
+
+    fn export_backup(&self, path: &Path, include_credentials: bool) -> Result<(), String> {
+        if path.as_os_str().is_empty() {
+            return Err("Choose where to save the backup.".to_string());
+        }
+        let state = self
+            .state_json()
+            .ok_or("Couldn't serialize the current settings.")?;
+        let state = if include_credentials {
+            state
+        } else {
+            with_smtp_password(&state, "")?
+        };
+        let manifest = BackupManifest {
+            format_version: BACKUP_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_at: Utc::now(),
+            includes_credentials: include_credentials,
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?;
+        let mut files = vec![
+            (MANIFEST_FILE.to_string(), manifest),
+            (STATE_FILE.to_string(), state.into_bytes()),
+        ];
+        if include_credentials {
+            let token_cache = self.token_cache_file();
+            match fs::read(&token_cache) {
+                Ok(contents) => files.push((TOKEN_CACHE_FILE.to_string(), contents)),
+                Err(e) => warn!("No token cache at {:?} to back up: {}", token_cache, e),
+            }
+        }
+        let archive = write_archive(&files)?;
+        fs::write(path, archive).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+    }
+
+    /// Reads and checks `backup.import_path`, then waits for the user to confirm.
+    pub(super) fn handle_prepare_import(&mut self) {
+        let path = PathBuf::from(self.backup.import_path.trim());
+        self.backup.result = None;
+        self.backup.pending = match self.prepare_import(&path) {
+            Ok(pending) => Some(pending),
+            Err(e) => {
+                warn!("Backup {:?} can't be imported: {}", path, e);
+                self.backup.result = Some(Err(e));
+                None
+            }
+        };
+    }
+
+    fn prepare_import(&self, path: &Path) -> Result<PendingImport, String> {
+        let file =
+            fs::File::open(path).map_err(|e| format!("Couldn't open {}: {}", path.display(), e))?;
+        let files = read_archive(file)?;
+        let find = |name: &str| {
+            files
+                .iter()
+                .find(|(file_name, _)| file_name == name)
+                .map(|(_, contents)| contents.clone())
+        };
+        let manifest =
+            find(MANIFEST_FILE).ok_or("Not a Coffee Chat backup: manifest.json is missing.")?;
+        let manifest: BackupManifest = serde_json::from_slice(&manifest)
+            .map_err(|e| format!("Unreadable manifest.json: {}", e))?;
+        if manifest.format_version > BACKUP_FORMAT_VERSION {
+            return Err(format!(
+                "This backup was made by Coffee Chat {} (backup format {}); this version reads up to format {}. Update the app first.",
+                manifest.app_version, manifest.format_version, BACKUP_FORMAT_VERSION
+            ));
+        }
+        let state_json = find(STATE_FILE).ok_or("The backup has no app_state.json.")?;
+        Self::check_state_json(&state_json)?;
+        let token_cache = find(TOKEN_CACHE_FILE);
+        if let Some(token_cache) = &token_cache {
+            serde_json::from_slice::<serde_json::Value>(token_cache)
+                .map_err(|e| format!("The backed up token cache isn't valid JSON: {}", e))?;
+        }
+
+        let mut targets = vec![Self::state_file_path()?];
+        if token_cache.is_some() {
+            targets.push(self.token_cache_file());
+        }
+        Ok(PendingImport {
+            source: path.to_path_buf(),
+            manifest,
+            state_json,
+            token_cache,
+            targets: targets
+                .into_iter()
+                .map(|target| {
+                    let exists = target.exists();
+                    (target, exists)
+                })
+                .collect(),
+        })
+    }
+
+    /// Restores the confirmed backup and loads it in place of the current session.
+    pub(super) fn handle_import_backup(&mut self) {
+        let Some(pending) = self.backup.pending.take() else {
+            return;
+        };
+        let result = self.import_backup(&pending);
+        match &result {
+            Ok(()) => {
+                info!("Restored backup from {:?}", pending.source);
+                self.load_state();
+                self.status_message = format!("Restored backup from {}", pending.source.display());
+            }
+            Err(e) => error!("Backup import failed: {}", e),
+        }
+        self.backup.result = Some(result.map(|()| "Backup restored.".to_string()));
+    }
+
+    fn import_backup(&self, pending: &PendingImport) -> Result<(), String> {
+        let state_json = String::from_utf8(pending.state_json.clone())
+            .map_err(|_| "app_state.json isn't UTF-8.".to_string())?;
+        // A backup without credentials keeps the password this computer already has
+        let state_json = if pending.manifest.includes_credentials {
+            state_json
+        } else {
+            with_smtp_password(&state_json, self.smtp.password.expose_secret())?
+        };
+        let mut files = vec![(Self::state_file_path()?, state_json.into_bytes())];
+        if let Some(token_cache) = &pending.token_cache {
+            files.push((self.token_cache_file(), token_cache.clone()));
+        }
+        restore_files(&files)
+    }
+
+    fn token_cache_file(&self) -> PathBuf {
+        std::env::current_dir()
+            .unwrap_or_default()
+            .join(&self.calendar_settings.token_cache_path)
+    }
+}
+
+/// `state_json` with its SMTP password replaced.
+fn with_smtp_password(state_json: &str, password: &str) -> Result<String, String> {
+    let mut state: serde_json::Value =
+        serde_json::from_str(state_json).map_err(|e| format!("Unreadable settings: {}", e))?;
+    let fields = state
+        .as_object_mut()
+        .ok_or("Unreadable settings: not a JSON object")?;
+    fields.insert(
+        "smtp_password".to_string(),
+        serde_json::Value::String(password.to_string()),
+    );
+    serde_json::to_string_pretty(&state).map_err(|e| e.to_string())
+}
+
+/// Writes every file into a temporary directory next to its target first, and
+/// only then renames them into place. If any rename fails, the files already
+/// replaced are put back, so a restore either happens completely or not at all.
+fn restore_files(files: &[(PathBuf, Vec<u8>)]) -> Result<(), String> {
+    let staging_dir = |target: &Path| {
+        target
+            .parent()
+            .unwrap_or(Path::new("."))
+            .join(".coffee_chat_restore")
+    };
+    let staged_path = |target: &Path, suffix: &str| {
+        let name = target.file_name().unwrap_or_default().to_string_lossy();
+        staging_dir(target).join(format!("{}{}", name, suffix))
+    };
+    let clean_up = || {
+        for (target, _) in files {
+            fs::remove_dir_all(staging_dir(target)).ok();
+        }
+    };
+
+    for (target, contents) in files {
+        let staged = staged_path(target, "");
+        let written = fs::create_dir_all(staging_dir(target)).and_then(|()| {
+            let mut file = fs::File::create(&staged)?;
+            file.write_all(contents)?;
+            file.sync_all()
+        });
+        if let Err(e) = written {
+            clean_up();
+            return Err(format!("Couldn't stage {}: {}", staged.display(), e));
+        }
+    }
+
+    let mut swapped: Vec<(&Path, bool)> = Vec::new();
+    for (target, _) in files {
+        let existed = target.exists();
+        let swap = if existed {
+            fs::rename(target, staged_path(target, ".old"))
+        } else {
+            Ok(())
+        }
+        .and_then(|()| fs::rename(staged_path(target, ""), target));
+        if let Err(e) = swap {
+            for &(done, had_old) in swapped.iter().rev() {
+                fs::remove_file(done).ok();
+                if had_old {
+                    fs::rename(staged_path(done, ".old"), done).ok();
+                }
+            }
+            if existed && !target.exists() {
+                fs::rename(staged_path(target, ".old"), target).ok();
+            }
+            clean_up();
+            return Err(format!("Couldn't replace {}: {}", target.display(), e));
+        }
+        swapped.push((target, existed));
+    }
+    clean_up();
+    Ok(())
+}
+
+// --- Archive format ---
+// A plain ustar archive of flat, regular files, gzipped. Readable with `tar -xzf`.
+
+const BLOCK: usize = 512;
+
+fn write_archive(files: &[(String, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mtime = Utc::now().timestamp().max(0) as u64;
+    let mut tar = Vec::new();
+    for (name, contents) in files {
+        tar.extend_from_slice(&tar_header(name, contents.len() as u64, mtime)?);
+        tar.extend_from_slice(contents);
+        tar.resize(tar.len().next_multiple_of(BLOCK), 0);
+    }
+    tar.resize(tar.len() + 2 * BLOCK, 0);
+
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&tar).map_err(|e| e.to_string())?;
+    encoder.finish().map_err(|e| e.to_string())
+}
+
+fn tar_header(name: &str, size: u64, mtime: u64) -> Result<[u8; BLOCK], String> {
+    if name.len() > 99 || name.contains('/') {
+        return Err(format!("Can't archive a file named {:?}", name));
+    }
+    let mut header = [0u8; BLOCK];
+    let mut put = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    put(0, name.as_bytes());
+    put(100, b"0000600\0");
+    put(108, b"0000000\0");
+    put(116, b"0000000\0");
+    put(124, format!("{:011o}\0", size).as_bytes());
+    put(136, format!("{:011o}\0", mtime).as_bytes());
+    put(148, b"        ");
+    put(156, b"0");
+    put(257, b"ustar\0");
+    put(263, b"00");
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    Ok(header)
+}
+
+/// The regular files in a .tar.gz, by name. Anything else in it is an error.
+fn read_archive(reader: impl Read) -> Result<Vec<(String, Vec<u8>)>, String> {
+    let mut tar = Vec::new();
+    GzDecoder::new(reader)
+        .take(MAX_BACKUP_BYTES + 1)
+        .read_to_end(&mut tar)
+        .map_err(|e| format!("Not a .tar.gz backup: {}", e))?;
+    if tar.len() as u64 > MAX_BACKUP_BYTES {
+        return Err("The backup is too large to be a Coffee Chat backup.".to_string());
+    }
+
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + BLOCK <= tar.len() {
+        let header = &tar[offset..offset + BLOCK];
+        if header.iter().all(|&b| b == 0) {
+            return Ok(files);
+        }
+        let stored: u32 = parse_octal(&header[148..156])? as u32;
+        let actual: u32 = header
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    32
+                } else {
+                    u32::from(b)
+                }
+            })
+            .sum();
+        if stored != actual {
+            return Err("The backup is damaged (bad checksum).".to_string());
+        }
+        let name_end = header[..100].iter().position(|&b| b == 0).unwrap_or(100);
+        let name = String::from_utf8_lossy(&header[..name_end]).into_owned();
+        if !matches!(header[156], b'0' | 0) || name.contains('/') || name.contains("..") {
+            return Err(format!("Unexpected entry {:?} in the backup.", name));
+        }
+        let size = parse_octal(&header[124..136])? as usize;
+        let start = offset + BLOCK;
+        let contents = tar
+            .get(start..start + size)
+            .ok_or("The backup is truncated.")?
+            .to_vec();
+        files.push((name, contents));
+        offset = start + size.next_multiple_of(BLOCK);
+    }
+    Err("The backup is truncated.".to_string())
+}
+
+fn parse_octal(field: &[u8]) -> Result<u64, String> {
+    let text = String::from_utf8_lossy(field);
+    let digits = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    u64::from_str_radix(digits, 8).map_err(|_| "The backup is damaged (bad header).".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "coffee_chat_backup_test_{}_{}",
+            name,
+            std::process::id()
+        ));
+        fs::remove_dir_all(&dir).ok();
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn archive_round_trips() {
+        let files = vec![
+            ("manifest.json".to_string(), b"{}".to_vec()),
+            ("app_state.json".to_string(), vec![b'x'; 700]),
+            ("empty.json".to_string(), Vec::new()),
+        ];
+        let archive = write_archive(&files).unwrap();
+        assert_eq!(read_archive(&archive[..]).unwrap(), files);
+    }
+
+    #[test]
+    fn damaged_archives_are_rejected() {
+        let archive = write_archive(&[("a.json".to_string(), b"{}".to_vec())]).unwrap();
+        assert!(read_archive(&archive[..archive.len() / 2]).is_err());
+        assert!(read_archive(&b"not gzip"[..]).is_err());
+        assert!(tar_header("dir/a.json", 0, 0).is_err());
+    }
+
+    #[test]
+    fn password_is_replaced() {
+        let json = r#"{"smtp_host":"smtp.example.com","smtp_password":"hunter2"}"#;
+        let scrubbed = with_smtp_password(json, "").unwrap();
+        assert!(!scrubbed.contains("hunter2"));
+        assert!(scrubbed.contains("smtp.example.com"));
+    }
+
+    #[test]
+    fn restore_replaces_existing_and_creates_missing_files() {
+        let dir = scratch_dir("restore");
+        let existing = dir.join("app_state.json");
+        fs::write(&existing, "old").unwrap();
+        let missing = dir.join("sub").join("tokencache.json");
+        fs::create_dir_all(missing.parent().unwrap()).unwrap();
+
+        restore_files(&[
+            (existing.clone(), b"new".to_vec()),
+            (missing.clone(), b"token".to_vec()),
+        ])
+        .unwrap();
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new");
+        assert_eq!(fs::read_to_string(&missing).unwrap(), "token");
+        assert!(!dir.join(".coffee_chat_restore").exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn failed_restore_leaves_files_alone() {
+        let dir = scratch_dir("rollback");
+        let existing = dir.join("app_state.json");
+        fs::write(&existing, "old").unwrap();
+        // Its parent is a file, so nothing can be staged next to it
+        fs::write(dir.join("not_a_dir"), "").unwrap();
+        let unreachable = dir.join("not_a_dir").join("tokencache.json");
+
+        let result = restore_files(&[
+            (existing.clone(), b"new".to_vec()),
+            (unreachable, b"token".to_vec()),
+        ]);
+
+        assert!(result.is_err());
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!dir.join(".coffee_chat_restore").exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,157 @@
+// src/app/booking.rs
+// "Book meeting…": once someone picks a slot, put the event on the calendar and
+// invite them, and cancel it again from the app if plans change.
+
+use super::messages::Message;
+use super::MyApp;
+use crate::calendar;
+use crate::calendar::booking::{self, MeetingRequest};
+use crate::calendar::BusySource;
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info};
+
+impl MyApp {
+    /// Opens the dialog for `email`, starting in `slot` (an offered slot line) if given.
+    pub(super) fn open_booking(&mut self, name: &str, email: &str, slot: Option<&str>) {
+        let name = if name.is_empty() {
+            // The reply lookup only knows the address
+            self.compose
+                .recipients
+                .iter()
+                .find(|recipient| recipient.email.eq_ignore_ascii_case(email))
+                .map(|recipient| recipient.name.as_str())
+                .unwrap_or_default()
+        } else {
+            name
+        };
+        let booking = &mut self.booking;
+        booking.recipient_name = name.to_string();
+        booking.recipient_email = email.to_string();
+        booking.slot = slot
+            .or_else(|| self.calendar.available_slots.first().map(String::as_str))
+            .unwrap_or_default()
+            .to_string();
+        booking.start_offset_minutes = 0;
+        booking.result = None;
+        booking.open = true;
+    }
+
+    /// The free window behind the chosen slot line.
+    pub(super) fn booking_window(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let fetched = self.calendar.last_fetch.as_ref()?;
+        calendar::free_busy::window_for_line(
+            &fetched.windows,
+            Duration::minutes(calendar::MIN_SLOT_MINUTES),
+            &self.booking.slot,
+        )
+    }
+
+    /// Start and end of the meeting as set up in the dialog, or why it can't be booked.
+    pub(super) fn booking_times(&self) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+        let (window_start, window_end) = self
+            .booking_window()
+            .ok_or("Pick one of the fetched slots.")?;
+        if self.booking.meeting_minutes == 0 {
+            return Err("Set how long the meeting is.".to_string());
+        }
+        let start = window_start + Duration::minutes(self.booking.start_offset_minutes as i64);
+        let end = start + Duration::minutes(self.booking.meeting_minutes as i64);
+        if end > window_end {
+            return Err("The meeting runs past the end of that slot.".to_string());
+        }
+        Ok((start, end))
+    }
+
+    /// Creates the event in the background; the outcome arrives as `MeetingBooked`.
+    pub(super) fn book_meeting(&mut self) {
+        if self.booking.in_flight {
+            return;
+        }
+        let google = match &self.calendar.source {
+            Some(BusySource::Google(google)) => google.clone(),
+            Some(BusySource::Demo { .. }) => {
+                self.booking.result =
+                    Some(Err("Demo mode has no real calendar to book on.".to_string()));
+                return;
+            }
+            None => {
+                self.booking.result = Some(Err("Connect your calendar first.".to_string()));
+                return;
+            }
+        };
+        let email = self.booking.recipient_email.trim().to_string();
+        if !email.contains('@') {
+            self.booking.result = Some(Err("Enter the recipient's email.".to_string()));
+            return;
+        }
+        let (start, end) = match self.booking_times() {
+            Ok(times) => times,
+            Err(e) => {
+                self.booking.result = Some(Err(e));
+                return;
+            }
+        };
+        let title = match booking::render_title(
+            &self.booking.title_template,
+            &self.compose.sender_name,
+            &self.booking.recipient_name,
+            &email,
+        ) {
+            Ok(title) => title,
+            Err(e) => {
+                self.booking.result = Some(Err(e.to_string()));
+                return;
+            }
+        };
+        let request = MeetingRequest {
+            start,
+            end,
+            title,
+            attendee_name: self.booking.recipient_name.clone(),
+            attendee_email: email,
+            with_meet: self.booking.with_meet,
+        };
+
+        self.booking.in_flight = true;
+        self.booking.result = None;
+        self.status_message = format!("Booking a meeting with {}...", request.attendee_email);
+        let sender = self.background.sender.clone();
+        let rt_handle = self.ensure_runtime().handle().clone();
+        rt_handle.spawn(async move {
+            let result = booking::book(&google, &request).await.map_err(|e| {
+                error!("Booking failed: {}", e);
+                e.to_string()
+            });
+            sender.send(Message::MeetingBooked(result)).ok();
+        });
+    }
+
+    /// Deletes a booked event in the background; the outcome arrives as `MeetingCancelled`.
+    pub(super) fn cancel_meeting(&mut self, event_id: &str) {
+        if self.booking.in_flight {
+            return;
+        }
+        let Some(BusySource::Google(google)) = &self.calendar.source else {
+            self.booking.result = Some(Err(
+                "Connect your Google calendar to cancel the event.".to_string()
+            ));
+            return;
+        };
+        let google = google.clone();
+        let event_id = event_id.to_string();
+        info!("Cancelling booked meeting {}", event_id);
+        self.booking.in_flight = true;
+        self.booking.result = None;
+        let sender = self.background.sender.clone();
+        let rt_handle = self.ensure_runtime().handle().clone();
+        rt_handle.spawn(async move {
+            let result = booking::cancel(&google, &event_id).await.map_err(|e| {
+                error!("Cancelling {} failed: {}", event_id, e);
+                e.to_string()
+            });
+            sender
+                .send(Message::MeetingCancelled(event_id, result))
+                .ok();
+        });
+    }
+}
@@ -0,0 +1,250 @@
+// src/app/control.rs
+// Answering the control API from the UI thread. Requests arrive as messages and
+// run the same code as the buttons, minus the send confirmation: the script
+// calling `POST /send` is doing the confirming.
+
+use super::messages::Message;
+use super::{MyApp, SendOutcome};
+use crate::control::{self, ControlConfig, ControlReply, ControlRequest, Dispatch, Endpoint};
+use chrono::{DateTime, Utc};
+use eframe::egui;
+use hyper::StatusCode;
+use log::{error, info, warn};
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+#[derive(Default)]
+pub(super) struct ControlState {
+    pub config: ControlConfig, // From config.toml only; not saved with the session
+    pub listening: Option<SocketAddr>, // Set once the server is up
+    pub error: Option<String>, // Why it couldn't start
+    started: bool,             // Tried already; a second config load doesn't restart it
+    server: Option<JoinHandle<()>>,
+}
+
+impl MyApp {
+    /// Called from `update`: starts the server once config.toml has turned it on. Never
+    /// in demo or safe mode. `ctx` wakes the UI thread when a request comes in.
+    pub(super) fn start_control_api(&mut self, ctx: &egui::Context) {
+        if self.control.started
+            || !self.control.config.enabled
+            || self.demo_mode
+            || self.safe_mode.active
+        {
+            return;
+        }
+        self.control.started = true;
+        let port = self.control.config.port;
+        let listener = match control::bind(port) {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Control API couldn't listen on port {}: {}", port, e);
+                self.control.error = Some(format!("Couldn't listen on port {}: {}", port, e));
+                return;
+            }
+        };
+        let address = match listener.local_addr() {
+            Ok(address) => address,
+            Err(e) => {
+                self.control.error = Some(e.to_string());
+                return;
+            }
+        };
+        let token = match control::new_token() {
+            Ok(token) => token,
+            Err(e) => {
+                error!("{}", e);
+                self.control.error = Some(e);
+                return;
+            }
+        };
+        let endpoint = Endpoint {
+            url: format!("http://{}", address),
+            token,
+        };
+        match endpoint.save() {
+            Ok(path) => info!(
+                "Control API listening on {}; the token is in {}",
+                endpoint.url,
+                path.display()
+            ),
+            Err(e) => {
+                // Without the file nobody could use the token
+                error!("{}", e);
+                self.control.error = Some(e);
+                return;
+            }
+        }
+        let sender = self.background.sender.clone();
+        let ctx = ctx.clone();
+        let dispatch: Dispatch = Arc::new(move |request, reply_to| {
+            // If the app is gone, dropping `reply_to` answers the request
+            if sender.send(Message::Control(request, reply_to)).is_ok() {
+                ctx.request_repaint();
+            }
+        });
+        let server =
+            self.ensure_runtime()
+                .spawn(control::serve(listener, endpoint.token, dispatch));
+        self.control.server = Some(server);
+        self.control.listening = Some(address);
+    }
+
+    /// Stops the server, waiting until its port is free, before the app is reset for
+    /// demo mode. The next start listens again with a new token.
+    pub(super) fn stop_control_api(&mut self) {
+        let Some(server) = self.control.server.take() else {
+            return;
+        };
+        server.abort();
+        if let Some(runtime) = &self.background.tokio_rt {
+            runtime.block_on(server).ok(); // Cancelled, which dropped the listener
+        }
+        info!("Control API stopped.");
+        self.control.listening = None;
+        self.control.started = false;
+    }
+
+    pub(super) fn answer_control(&mut self, request: ControlRequest) -> ControlReply {
+        match request {
+            ControlRequest::Status => ControlReply::ok(self.control_status()),
+            ControlRequest::FetchSlots => self.control_fetch_slots(),
+            ControlRequest::Send { dry_run } => self.control_send(dry_run),
+            ControlRequest::History { since } => ControlReply::ok(self.control_history(since)),
+        }
+    }
+
+    fn control_status(&self) -> Value {
+        let calendar = if self.calendar.source.is_some() {
+            "connected"
+        } else if self.background.is_connecting_calendar {
+            "connecting"
+        } else {
+            "disconnected"
+        };
+        let count = |kind: fn(&SendOutcome) -> bool| {
+            self.compose
+                .send_results
+                .iter()
+                .filter(|result| !result.one_off && kind(&result.outcome))
+                .count()
+        };
+        json!({
+            "calendar": calendar,
+            "fetching_slots": self.background.is_fetching_slots,
+            "slots": self.email_availabilities(),
+            "slots_fetched_at": self.calendar.last_fetch.as_ref().map(|fetched| fetched.fetched_at),
+            "frozen": self.freeze.frozen.as_ref().map(|frozen| &frozen.label),
+            "recipients": self.compose.recipients.len(),
+            "sending": self.background.is_sending_email,
+            "last_batch": {
+                "sent": count(|outcome| matches!(outcome, SendOutcome::Accepted(_))),
+                "failed": count(|outcome| matches!(outcome, SendOutcome::Failed(_))),
+                "skipped": count(|outcome| matches!(outcome, SendOutcome::Skipped(_))),
+            },
+            "status": self.status_message,
+        })
+    }
+
+    fn control_fetch_slots(&mut self) -> ControlReply {
+        self.handle_fetch_slots();
+        if self.background.is_fetching_slots {
+            info!("Control API started a slot fetch.");
+            ControlReply::accepted(json!({ "fetching_slots": true }))
+        } else {
+            ControlReply::error(StatusCode::CONFLICT, self.status_message.clone())
+        }
+    }
+
+    /// A dry run goes through the send checks and lists who would get an email, with
+    /// the slots they'd be offered; nothing is sent and the status bar is left alone.
+    fn control_send(&mut self, dry_run: bool) -> ControlReply {
+        let status = self.status_message.clone();
+        if self.batch_transport().is_none() {
+            let problem = self.status_message.clone();
+            if dry_run {
+                self.status_message = status;
+            }
+            return ControlReply::error(StatusCode::CONFLICT, problem);
+        }
+        let (recipients, duplicates) = self.recipients_for_send();
+        let drip = self.drips(recipients.len());
+        if dry_run {
+            return ControlReply::ok(json!({
+                "dry_run": true,
+                "recipients": recipients.iter().map(|r| &r.email).collect::<Vec<_>>(),
+                "duplicates": duplicates.len(),
+                "role_addresses": self.role_flagged(&recipients),
+                "drip": drip,
+                "slots": self.email_availabilities(),
+                "readability_warning": self.send_readability_warning(),
+                "frozen_busy": self.frozen_busy_lines(),
+            }));
+        }
+        if let Some(warning) = self.send_readability_warning() {
+            warn!("{}", warning);
+        }
+        info!(
+            "Control API started a send to {} recipients.",
+            recipients.len()
+        );
+        self.handle_send_invitations();
+        if self.background.is_sending_email {
+            ControlReply::accepted(json!({
+                "sending": true,
+                "recipients": recipients.len(),
+                "drip": drip,
+            }))
+        } else {
+            ControlReply::error(StatusCode::CONFLICT, self.status_message.clone())
+        }
+    }
+
+    /// Every email in the history sent at or after `since`, oldest first.
+    fn control_history(&self, since: Option<DateTime<Utc>>) -> Value {
+        let entries: Vec<Value> = self
+            .compose
+            .history
+            .iter()
+            .filter(|entry| since.is_none_or(|since| entry.sent_at >= since))
+            .map(|entry| {
+                json!({
+                    "kind": entry.kind,
+                    "name": entry.name,
+                    "email": entry.email,
+                    "subject": entry.subject,
+                    "campaign": entry.campaign,
+                    "sent_at": entry.sent_at,
+                    "sent": entry.outcome.is_ok(),
+                    "outcome": match &entry.outcome {
+                        Ok(reply) | Err(reply) => reply,
+                    },
+                })
+            })
+            .collect();
+        Value::Array(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stopping_the_control_api_frees_its_port() {
+        let mut app = MyApp::default();
+        let listener = control::bind(0).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let dispatch: Dispatch = Arc::new(|_, _| {});
+        let server =
+            app.ensure_runtime()
+                .spawn(control::serve(listener, "token".to_string(), dispatch));
+        app.control.server = Some(server);
+        app.control.started = true;
+        app.stop_control_api();
+        assert!(!app.control.started);
+        assert!(control::bind(port).is_ok());
+    }
+}
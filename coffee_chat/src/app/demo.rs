@@ -0,0 +1,120 @@
+// src/app/demo.rs
+// Demo mode: sample recipients and settings, a made-up calendar and a mail
+// server that only pretends, for showing the app without real accounts.
+
+use super::effective_config::ConfigSource;
+use super::messages::Message;
+use super::{ComposeState, DripState, MyApp, SmtpSettings, UIRecipient};
+use crate::calendar::demo::{self, DEMO_DELAY, DEMO_SEED};
+use crate::calendar::BusySource;
+use log::info;
+use secrecy::SecretString;
+use std::collections::BTreeMap;
+
+impl MyApp {
+    /// Replaces the session with sample data. Nothing is saved while demo mode is on.
+    pub(super) fn enter_demo_mode(&mut self) {
+        info!("Entering demo mode.");
+        self.demo_mode = true;
+        self.clear_slots();
+        // The real schedule isn't sent from demo mode; it's reloaded on the way out
+        self.drip = DripState::default();
+        self.calendar.source = None;
+        self.calendar.calendars = None;
+        self.calendar.error = None;
+        self.calendar.diagnosis = None;
+        self.calendar.status = "Calendar: Not Connected".to_string();
+        self.smtp = SmtpSettings {
+            host: "smtp.demo.invalid".to_string(),
+            from_email: "alex.demo@example.com".to_string(),
+            password: SecretString::new("".to_string().into()),
+            no_auth: true,
+            ..SmtpSettings::default()
+        };
+        let recipient = |name: &str, email: &str, pinned: bool| UIRecipient {
+            name: name.to_string(),
+            email: email.to_string(),
+            fields: BTreeMap::new(),
+            pinned,
+        };
+        self.compose = ComposeState {
+            sender_name: "Alex Demo".to_string(),
+            template_source: "built-in demo template".to_string(),
+            recipients: vec![
+                recipient("Priya Raman", "priya@example.com", true),
+                recipient("Marcus Lee", "marcus.lee@example.org", false),
+                recipient("Sofía García", "sofia.garcia@example.net", false),
+                recipient("Jordan Kim", "jkim@example.com", false),
+            ],
+            ..ComposeState::default()
+        };
+        self.record_config_source(
+            ConfigSource::Demo,
+            Some(&[
+                "smtp.",
+                "webhook.",
+                "sender.",
+                "email.",
+                "recipients",
+                "drip.",
+                "send.",
+            ]),
+        );
+        // The real config.toml and template are ignored in demo mode
+        self.background.config_loaded = true;
+        self.background.template_loaded = true;
+        self.show_setup_checklist = false;
+        self.status_message =
+            "Demo mode: sample data loaded. Connect the calendar to see made-up meetings."
+                .to_string();
+    }
+
+    /// Ctrl+Shift+D. Entering saves the real session first; leaving reloads it.
+    pub(super) fn toggle_demo_mode(&mut self) {
+        if self.background.is_sending_email
+            || self.background.is_connecting_calendar
+            || self.background.is_fetching_slots
+            || self.background.is_diagnosing
+        {
+            self.status_message = "Wait for the current task to finish first.".to_string();
+            return;
+        }
+        if self.safe_mode.active {
+            self.status_message =
+                "Start normally first; demo mode reloads everything on the way out.".to_string();
+            return;
+        }
+        // Its requests go to this session's channel, which the reset replaces; and it
+        // never runs in demo mode. The fresh app on the way out starts it again.
+        self.stop_control_api();
+        if !self.demo_mode {
+            self.save_state();
+            self.enter_demo_mode();
+            return;
+        }
+        info!("Leaving demo mode.");
+        // A fresh app reloads config.toml and the saved state; the runtime is kept
+        let runtime = self.background.tokio_rt.take();
+        *self = Self::default();
+        self.background.tokio_rt = runtime;
+        self.record_config_source(ConfigSource::Default, None);
+        self.start_normally();
+    }
+
+    /// Demo mode's Connect: no browser, just a short pause and the made-up calendar.
+    pub(super) fn connect_demo_calendar(&mut self) {
+        let sender = self.background.sender.clone();
+        let rt_handle = self.ensure_runtime().handle().clone();
+        rt_handle.spawn(async move {
+            tokio::time::sleep(DEMO_DELAY).await;
+            sender
+                .send(Message::CalendarConnected(BusySource::Demo {
+                    seed: DEMO_SEED,
+                }))
+                .ok();
+            sender
+                .send(Message::CalendarsListed(Ok(demo::demo_calendars())))
+                .ok();
+        });
+    }
+}
@@ -0,0 +1,363 @@
+// src/app/drip.rs
+// Drip mode in the GUI: starting a schedule from a send, and sending each later
+// batch with freshly fetched slots once it's due, while the app is running.
+
+use super::{DripState, MyApp, DRIP_CHECK_INTERVAL};
+use crate::config::Recipient;
+use crate::drip::{self, DripSchedule, Invitation, Tried};
+use crate::email_sender::{history, Transport};
+use chrono::{Duration, Local, NaiveDate, Utc};
+use log::{error, info, warn};
+use std::time::Instant;
+
+/// Names this app's claims on batches in drip_schedule.json.
+const CLAIMANT: &str = "the app";
+
+/// A change to a batch this app is sending. It has to reach the file even when
+/// `send --due` holds the lock at that moment, so it's queued until it does.
+pub(super) enum DripEdit {
+    Record(usize, String, Tried),
+    Finish(usize, NaiveDate), // And the day anyone left over is carried to
+    Release(usize),
+}
+
+impl MyApp {
+    /// The invitation as it stands in the compose panel.
+    pub(super) fn compose_invitation(&self) -> Invitation {
+        Invitation {
+            sender_name: self.compose.sender_name.clone(),
+            subject: self.compose.subject.clone(),
+            body: self.compose.body.clone(),
+            cc: self.compose.cc.clone(),
+            bcc: self.compose.bcc.clone(),
+            no_slots_text: self.compose.no_slots_text.clone(),
+            priority: self.compose.priority,
+            availability_link: self.availability_link(),
+            archive_bcc: self.compose.archive_bcc.clone(),
+            campaign: history::campaign_name(&self.compose.campaign),
+        }
+    }
+
+    /// Whether a send to `recipients` people would be split into batches.
+    pub(super) fn drips(&self, recipients: usize) -> bool {
+        self.compose.drip_enabled && recipients > self.drip_batch_size()
+    }
+
+    /// At least one, whatever an edited state file says.
+    pub(super) fn drip_batch_size(&self) -> usize {
+        (self.compose.drip_batch_size as usize).max(1)
+    }
+
+    /// Reads drip_schedule.json, if there is one. Demo mode keeps its schedule in memory.
+    pub(super) fn load_drip_schedule(&mut self) {
+        self.drip = DripState::default();
+        if self.demo_mode {
+            return;
+        }
+        match DripSchedule::load() {
+            Ok(schedule) => {
+                if let Some(schedule) = &schedule {
+                    info!(
+                        "Loaded drip schedule with {} pending batches.",
+                        schedule.pending_count()
+                    );
+                }
+                self.drip.schedule = schedule;
+            }
+            Err(e) => {
+                error!("{}", e);
+                self.drip.notice = Some(e);
+            }
+        }
+    }
+
+    /// Applies `change` to the schedule file, re-read under its lock so batches
+    /// `coffee-chat send --due` marked off aren't overwritten, and shows the result.
+    /// Doesn't wait for the lock: while it's held, `change` isn't made and the status
+    /// bar says to try again. Demo mode changes the schedule in memory.
+    pub(super) fn change_drip_schedule<T>(
+        &mut self,
+        change: impl FnOnce(&mut Option<DripSchedule>) -> T,
+    ) -> Option<T> {
+        if self.demo_mode {
+            return Some(drip::apply(&mut self.drip.schedule, change));
+        }
+        match drip::try_update(change) {
+            Ok(Some((result, schedule))) => {
+                self.drip.schedule = schedule;
+                Some(result)
+            }
+            Ok(None) => {
+                self.status_message =
+                    "The drip schedule is busy (`send --due` is updating it); try again in a moment."
+                        .to_string();
+                None
+            }
+            Err(e) => {
+                error!("{}", e);
+                self.status_message = e.clone();
+                self.drip.notice = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Writes the queued edits, in order, once the schedule lock is free. Called
+    /// every frame while any are left.
+    pub(super) fn write_drip_edits(&mut self) {
+        if self.drip.queued.is_empty() {
+            return;
+        }
+        let edits = std::mem::take(&mut self.drip.queued);
+        let carried = if self.demo_mode {
+            Ok(Some(drip::apply(&mut self.drip.schedule, |schedule| {
+                apply_edits(schedule, &edits)
+            })))
+        } else {
+            drip::try_update(|schedule| apply_edits(schedule, &edits)).map(|written| {
+                written.map(|(carried, schedule)| {
+                    self.drip.schedule = schedule;
+                    carried
+                })
+            })
+        };
+        match carried {
+            Ok(Some(carried)) if carried > 0 => {
+                info!(
+                    "A drip batch stopped early; {} recipients moved to a batch tomorrow.",
+                    carried
+                );
+                self.drip.notice = Some(format!(
+                    "A batch stopped early. The {} recipients it didn't get to are due tomorrow.",
+                    carried
+                ));
+            }
+            Ok(Some(_)) => {}
+            Ok(None) => self.drip.queued = edits, // Locked; tried again next frame
+            Err(e) => {
+                error!("{}", e);
+                self.drip.notice = Some(e);
+            }
+        }
+    }
+
+    /// Splits the send into a schedule and sends its first batch now, with the slots on screen.
+    pub(super) fn start_drip(
+        &mut self,
+        transport: Transport,
+        recipients: Vec<Recipient>,
+        skipped: usize,
+    ) {
+        let schedule = DripSchedule::new(
+            self.compose_invitation(),
+            recipients,
+            self.drip_batch_size(),
+            self.compose.drip_interval_days,
+            Local::now().date_naive(),
+        );
+        let batches = schedule.batches.len();
+        let today = Local::now().date_naive();
+        let started = self.change_drip_schedule(|stored| {
+            if stored
+                .as_ref()
+                .is_some_and(|stored| stored.pending_count() > 0)
+            {
+                return None;
+            }
+            let schedule = stored.insert(schedule);
+            let index = schedule.claim_next_due(today, &drip::claimant(CLAIMANT), Utc::now())?;
+            Some((
+                index,
+                schedule.batches[index].recipients.clone(),
+                schedule.invitation.clone(),
+            ))
+        });
+        let Some(started) = started else {
+            self.status_message = format!("Drip schedule not started: {}", self.status_message);
+            return;
+        };
+        let Some((index, first, invitation)) = started else {
+            self.status_message =
+                "A drip schedule is still running. Cancel it in the schedule view first."
+                    .to_string();
+            return;
+        };
+        info!(
+            "Starting drip schedule: {} batches of up to {}.",
+            batches,
+            self.drip_batch_size()
+        );
+        self.drip.dispatching = Some(index);
+        self.spawn_send(transport, first, skipped, invitation);
+    }
+
+    /// Called from `update`: once a minute, starts the next due batch by refetching slots.
+    /// With the slots frozen, the refetch only checks whether any were booked since.
+    pub(super) fn dispatch_due_drip_batch(&mut self) {
+        self.write_drip_edits();
+        if !self.drip.queued.is_empty() {
+            return; // The batch before isn't written off yet
+        }
+        if self.drip.schedule.is_none()
+            || self
+                .drip
+                .checked_at
+                .is_some_and(|checked| checked.elapsed() < DRIP_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.drip.checked_at = Some(Instant::now());
+        if self.drip.dispatching.is_some()
+            || self.background.is_sending_email
+            || self.background.is_fetching_slots
+            || self.background.is_connecting_calendar
+        {
+            return;
+        }
+        // Without a calendar or frozen slots there's nothing to send; just see if one is due
+        let can_send = self.calendar.source.is_some() || self.freeze.frozen.is_some();
+        let today = Local::now().date_naive();
+        let due = self.change_drip_schedule(|schedule| {
+            let schedule = schedule.as_mut()?;
+            if can_send {
+                schedule.claim_next_due(today, &drip::claimant(CLAIMANT), Utc::now())
+            } else {
+                schedule.due_batches(today).first().copied()
+            }
+        });
+        let Some(Some(index)) = due else {
+            return;
+        };
+        if self.calendar.source.is_none() && self.freeze.frozen.is_some() {
+            // The frozen slots go out regardless; there's just nothing to check them against
+            info!("Drip batch {} is due; sending the frozen slots.", index + 1);
+            self.drip.notice = None;
+            self.drip.dispatching = Some(index);
+            self.send_drip_batch();
+            return;
+        }
+        if self.calendar.source.is_none() {
+            self.drip.notice = Some(
+                "A batch is due. Connect the calendar so it goes out with current slots."
+                    .to_string(),
+            );
+            return;
+        }
+        info!("Drip batch {} is due; refetching slots first.", index + 1);
+        self.drip.notice = None;
+        self.drip.dispatching = Some(index);
+        self.drip.awaiting_slots = true;
+        self.handle_fetch_slots();
+    }
+
+    /// Sends the batch `dispatch_due_drip_batch` fetched slots for.
+    pub(super) fn send_drip_batch(&mut self) {
+        self.drip.awaiting_slots = false;
+        let Some(index) = self.drip.dispatching else {
+            return;
+        };
+        let batch = self
+            .drip
+            .schedule
+            .as_ref()
+            .and_then(|schedule| Some((schedule.batches.get(index)?, &schedule.invitation)))
+            .map(|(batch, invitation)| (batch.remaining(), invitation.clone()));
+        let Some((recipients, invitation)) = batch else {
+            self.drip.dispatching = None;
+            return;
+        };
+        let Some(transport) = self.transport_for_send() else {
+            warn!("Drip batch {} not sent: {}", index + 1, self.status_message);
+            self.drip.notice = Some(format!("Batch not sent: {}", self.status_message));
+            self.release_drip_batch();
+            return;
+        };
+        self.spawn_send(transport, recipients, 0, invitation);
+    }
+
+    /// The slot fetch for a due batch failed; it's tried again at the next check.
+    pub(super) fn drip_slots_failed(&mut self, error: &str) {
+        self.drip.awaiting_slots = false;
+        self.release_drip_batch();
+        self.drip.notice = Some(format!(
+            "Batch not sent, slots couldn't be fetched: {}",
+            error
+        ));
+    }
+
+    /// Gives up the batch about to be sent, so it's picked up at a later check.
+    fn release_drip_batch(&mut self) {
+        let Some(index) = self.drip.dispatching.take() else {
+            return;
+        };
+        self.drip.queued.push(DripEdit::Release(index));
+        self.write_drip_edits();
+    }
+
+    /// Notes that the batch being sent reached (or failed for) `email`, so it isn't
+    /// sent to them again if the app stops partway.
+    pub(super) fn record_drip_email(&mut self, email: &str, tried: Tried) {
+        let Some(index) = self.drip.dispatching.filter(|_| !self.drip.awaiting_slots) else {
+            return;
+        };
+        self.drip
+            .queued
+            .push(DripEdit::Record(index, email.to_string(), tried));
+        self.write_drip_edits();
+    }
+
+    /// Records the batch being sent as done. Anyone it didn't get to because it
+    /// stopped early goes into a new batch for tomorrow.
+    pub(super) fn finish_drip_batch(&mut self) {
+        let Some(index) = self.drip.dispatching.take() else {
+            return;
+        };
+        let tomorrow = Local::now().date_naive() + Duration::days(1);
+        self.drip.queued.push(DripEdit::Finish(index, tomorrow));
+        self.write_drip_edits();
+    }
+
+    /// Moves pending batch `index` to `due`.
+    pub(super) fn move_drip_batch(&mut self, index: usize, due: NaiveDate) {
+        self.change_drip_schedule(|schedule| {
+            if let Some(batch) = schedule
+                .as_mut()
+                .and_then(|schedule| schedule.batches.get_mut(index))
+                .filter(|batch| batch.is_pending())
+            {
+                batch.due = due;
+            }
+        });
+    }
+
+    /// Cancels the batches that haven't gone out yet, except one being sent right now.
+    /// Not offered while this app is sending one.
+    pub(super) fn cancel_drip(&mut self) {
+        let now = Utc::now();
+        self.change_drip_schedule(|schedule| {
+            if let Some(schedule) = schedule {
+                let cancelled = schedule.cancel(now);
+                info!("Cancelled {} remaining drip batches.", cancelled);
+            }
+        });
+    }
+}
+
+/// Makes `edits` in order, returning how many recipients finished batches carried over.
+fn apply_edits(schedule: &mut Option<DripSchedule>, edits: &[DripEdit]) -> usize {
+    let Some(schedule) = schedule else {
+        return 0;
+    };
+    let now = Utc::now();
+    let mut carried = 0;
+    for edit in edits {
+        match edit {
+            DripEdit::Record(index, email, tried) => schedule.record(*index, email, *tried, now),
+            DripEdit::Finish(index, carry_over_to) => {
+                carried += schedule.finish(*index, now, *carry_over_to);
+            }
+            DripEdit::Release(index) => schedule.release(*index, &drip::claimant(CLAIMANT)),
+        }
+    }
+    carried
+}
@@ -0,0 +1,78 @@
+// src/app/field_check.rs
+// Keeps the list of recipients missing a custom field the template uses up to date,
+// and fills a default value in for them.
+
+use super::{recipient_search, MyApp};
+use crate::email_sender::field_check::{missing_fields, required_fields};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet};
+use std::hash::{Hash, Hasher};
+
+#[derive(Default)]
+pub(super) struct FieldCheck {
+    checked: Option<u64>,           // Fingerprint of the template and list below
+    pub required: BTreeSet<String>, // Custom fields the template needs
+    pub missing: BTreeMap<String, Vec<usize>>, // Who lacks each one, by list position
+    pub fill: BTreeMap<String, String>, // Value typed in to fill each gap with
+}
+
+impl MyApp {
+    /// Rechecks the recipients when the template or the list has changed. A template
+    /// that doesn't parse needs nothing until it's fixed; sending reports the error.
+    pub(super) fn refresh_field_check(&mut self) {
+        let compose = &self.compose;
+        let parts = [
+            compose.subject.as_str(),
+            &compose.body,
+            &compose.cc,
+            &compose.bcc,
+        ];
+        let mut hasher = DefaultHasher::new();
+        parts.hash(&mut hasher);
+        recipient_search::fingerprint(&compose.recipients).hash(&mut hasher);
+        let fingerprint = hasher.finish();
+        if compose.field_check.checked == Some(fingerprint) {
+            return;
+        }
+        let required = required_fields(&parts).unwrap_or_default();
+        let missing = missing_fields(
+            &required,
+            compose.recipients.iter().map(|recipient| &recipient.fields),
+        );
+        let check = &mut self.compose.field_check;
+        check.fill.retain(|field, _| missing.contains_key(field));
+        check.required = required;
+        check.missing = missing;
+        check.checked = Some(fingerprint);
+    }
+
+    /// Gives everyone without `field` the value typed in for it.
+    pub(super) fn fill_missing_field(&mut self, field: &str) {
+        let value = self
+            .compose
+            .field_check
+            .fill
+            .remove(field)
+            .unwrap_or_default()
+            .trim()
+            .to_string();
+        let Some(positions) = self.compose.field_check.missing.get(field).cloned() else {
+            return;
+        };
+        for &position in &positions {
+            if let Some(recipient) = self.compose.recipients.get_mut(position) {
+                recipient
+                    .fields
+                    .entry(field.to_string())
+                    .or_insert(value.clone());
+            }
+        }
+        self.status_message = format!(
+            "Set '{}' to \"{}\" for {} recipients.",
+            field,
+            value,
+            positions.len()
+        );
+        self.refresh_field_check();
+    }
+}
@@ -0,0 +1,274 @@
+// src/app/follow_up.rs
+// Cancellation and reschedule emails, sent as replies to an earlier email in the
+// history. A cancellation can also delete the meeting booked with that person; a
+// reschedule offers freshly fetched slots.
+
+use super::messages::Message;
+use super::{FollowUpDraft, MyApp};
+use crate::calendar::booking;
+use crate::calendar::format::{format_slot_in, SlotFormat};
+use crate::calendar::BusySource;
+use crate::config::Recipient;
+use crate::email_sender::history::{self, EmailKind, SentEmail};
+use crate::email_sender::post_process::NoopPostProcessor;
+use crate::email_sender::template::{self, EmailTemplate};
+use crate::email_sender::{prepare_email, send_prepared_email, PreparedEmail};
+use chrono::{Local, Utc};
+use log::{error, info};
+use std::collections::BTreeMap;
+
+impl MyApp {
+    /// Opens the follow-up dialog replying to history entry `index`. A reschedule
+    /// starts a slot fetch so it offers current times.
+    pub(super) fn open_follow_up(&mut self, index: usize, kind: EmailKind) {
+        let Some(original) = self.compose.history.get(index).cloned() else {
+            return;
+        };
+        // The latest meeting booked with them is the one being cancelled or moved
+        let meeting = self
+            .booking
+            .booked
+            .iter()
+            .rev()
+            .find(|meeting| meeting.attendee_email.eq_ignore_ascii_case(&original.email))
+            .cloned();
+        let awaiting_slots = kind == EmailKind::Reschedule && self.calendar.source.is_some();
+        self.follow_up.draft = Some(FollowUpDraft {
+            kind,
+            original,
+            delete_event: kind == EmailKind::Cancellation && meeting.is_some(),
+            meeting,
+            email: PreparedEmail::default(),
+            edited: false,
+            options: Vec::new(),
+            slots: Vec::new(),
+            awaiting_slots,
+            in_flight: false,
+            error: None,
+        });
+        if awaiting_slots {
+            self.handle_fetch_slots();
+        }
+        self.render_follow_up();
+    }
+
+    fn follow_up_recipient(draft: &FollowUpDraft) -> Recipient {
+        let mut fields = BTreeMap::new();
+        fields.insert(
+            "original_subject".to_string(),
+            history::original_subject(&draft.original.subject).to_string(),
+        );
+        // Always set, so `{% if meeting_time %}` works without a booked meeting
+        let meeting_time = draft
+            .meeting
+            .as_ref()
+            .map(|meeting| {
+                format_slot_in(meeting.start, meeting.end, SlotFormat::default(), &Local)
+            })
+            .unwrap_or_default();
+        fields.insert("meeting_time".to_string(), meeting_time);
+        Recipient {
+            name: draft.original.name.clone(),
+            email: draft.original.email.clone(),
+            fields,
+        }
+    }
+
+    /// Re-renders the draft from the follow-up template, replacing any hand edits.
+    pub(super) fn render_follow_up(&mut self) {
+        let Some(draft) = &self.follow_up.draft else {
+            return;
+        };
+        let kind = draft.kind;
+        let recipient = Self::follow_up_recipient(draft);
+        // Only a reschedule offers times
+        let availabilities = match kind {
+            EmailKind::Reschedule => self.email_availabilities(),
+            _ => Vec::new(),
+        };
+        let (subject, body) = self.follow_up.templates.for_kind(kind);
+        let rendered = EmailTemplate::from_content(subject, body, "follow_up")
+            .map(|template| {
+                template
+                    .with_unavailable_days(self.email_unavailable_days())
+                    .with_no_slots_text(&self.compose.no_slots_text)
+                    .with_priority(self.compose.priority)
+                    .with_availability_link(&self.availability_link())
+                    .with_archive_bcc(&self.compose.archive_bcc)
+                    .with_offered_slots(self.offered_slots())
+            })
+            .map_err(|e| e.to_string())
+            .and_then(|template| {
+                prepare_email(
+                    &recipient,
+                    &self.compose.sender_name,
+                    &availabilities,
+                    &template,
+                    &NoopPostProcessor,
+                )
+                .map_err(|e| e.to_string())
+            });
+        let slots = self.recorded_slots(&availabilities);
+        let Some(draft) = &mut self.follow_up.draft else {
+            return;
+        };
+        draft.edited = false;
+        draft.options = template::slot_options(&availabilities);
+        draft.slots = slots;
+        match rendered {
+            Ok(mut email) => {
+                email.in_reply_to = draft.original.message_id.clone();
+                draft.email = email;
+                draft.error = None;
+            }
+            Err(e) => draft.error = Some(e),
+        }
+    }
+
+    /// Called once the reschedule's slot fetch is back, or failed with `error`.
+    pub(super) fn follow_up_slots_ready(&mut self, error: Option<&str>) {
+        let Some(draft) = &mut self.follow_up.draft else {
+            return;
+        };
+        if !draft.awaiting_slots {
+            return;
+        }
+        draft.awaiting_slots = false;
+        match error {
+            Some(e) => draft.error = Some(format!("Slots couldn't be fetched again: {}", e)),
+            None if !draft.edited => self.render_follow_up(),
+            None => {}
+        }
+    }
+
+    /// Sends the draft, then deletes the booked event if asked to.
+    pub(super) fn send_follow_up(&mut self) {
+        let Some(draft) = &self.follow_up.draft else {
+            return;
+        };
+        if draft.in_flight || draft.awaiting_slots {
+            return;
+        }
+        let event = match (&draft.meeting, draft.delete_event) {
+            (Some(meeting), true) => match &self.calendar.source {
+                Some(BusySource::Google(google)) => {
+                    Some((google.clone(), meeting.event_id.clone()))
+                }
+                _ => {
+                    let problem = "Connect your Google calendar to delete the event, or untick \"Delete the calendar event\".";
+                    if let Some(draft) = &mut self.follow_up.draft {
+                        draft.error = Some(problem.to_string());
+                    }
+                    return;
+                }
+            },
+            _ => None,
+        };
+        let Some(transport) = self.transport_for_send() else {
+            return;
+        };
+        let Some(draft) = &mut self.follow_up.draft else {
+            return;
+        };
+        let kind = draft.kind;
+        let recipient = Self::follow_up_recipient(draft);
+        let email = draft.email.clone();
+        let options = draft.options.clone();
+        let slots = draft.slots.clone();
+        let campaign = draft.original.campaign.clone();
+        draft.in_flight = true;
+        draft.error = None;
+        let sender_name = self.compose.sender_name.clone();
+        self.background.is_sending_email = true;
+        self.status_message = format!("Sending {} to {}...", kind, recipient.email);
+        let rt = self.ensure_runtime().handle().clone();
+        let sender = self.background.sender.clone();
+        rt.spawn(async move {
+            info!("Sending {} to {}.", kind, recipient.email);
+            let result = send_prepared_email(&transport, &recipient, &sender_name, &email).await;
+            let receipt = result.as_ref().ok().cloned();
+            // Only once they've been told; a failed email leaves the event alone
+            let deleted = match (&result, event) {
+                (Ok(_), Some((google, event_id))) => Some(
+                    booking::cancel(&google, &event_id)
+                        .await
+                        .map(|()| event_id)
+                        .map_err(|e| {
+                            error!("Event not deleted: {}", e);
+                            e.to_string()
+                        }),
+                ),
+                _ => None,
+            };
+            let entry = SentEmail {
+                kind,
+                name: recipient.name,
+                email: recipient.email,
+                subject: email.subject,
+                sent_at: Utc::now(),
+                message_id: receipt.as_ref().and_then(|r| r.message_id.clone()),
+                outcome: result
+                    .map(|receipt| receipt.summary())
+                    .map_err(|e| e.to_string()),
+                event: deleted.as_ref().map(|deleted| match deleted {
+                    Ok(_) => "Calendar event deleted".to_string(),
+                    Err(e) => format!("Calendar event not deleted: {}", e),
+                }),
+                slots: Some(slots),
+                campaign,
+            };
+            sender
+                .send(Message::FollowUpSent {
+                    entry,
+                    receipt,
+                    options,
+                    deleted_event: deleted.and_then(Result::ok),
+                })
+                .ok();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::FollowUpTemplates;
+
+    fn render(kind: EmailKind, meeting_time: &str, slots: &[String]) -> (String, String) {
+        let templates = FollowUpTemplates::default();
+        let (subject, body) = templates.for_kind(kind);
+        let mut fields = BTreeMap::new();
+        fields.insert("original_subject".to_string(), "Coffee chat?".to_string());
+        fields.insert("meeting_time".to_string(), meeting_time.to_string());
+        let recipient = Recipient {
+            name: "Ana".to_string(),
+            email: "ana@example.com".to_string(),
+            fields,
+        };
+        EmailTemplate::from_content(subject, body, "follow_up")
+            .unwrap()
+            .render(&recipient, "Me", slots)
+            .unwrap()
+    }
+
+    #[test]
+    fn cancellation_names_the_booked_time_when_there_is_one() {
+        let (subject, body) = render(EmailKind::Cancellation, "Tuesday Apr 8: 2pm–2:30pm", &[]);
+        assert_eq!(subject, "Re: Coffee chat?");
+        assert!(body.contains("cancel our chat on Tuesday Apr 8: 2pm–2:30pm."));
+        let (_, body) = render(EmailKind::Cancellation, "", &[]);
+        assert!(body.contains("cancel our coffee chat."));
+    }
+
+    #[test]
+    fn reschedule_numbers_the_new_slots() {
+        let slots = vec![
+            "Monday Apr 7: 2pm–3pm".to_string(),
+            "Wednesday Apr 9: 10am–11am".to_string(),
+        ];
+        let (_, body) = render(EmailKind::Reschedule, "", &slots);
+        assert!(body.contains("1) Monday Apr 7: 2pm–3pm"));
+        assert!(body.contains("2) Wednesday Apr 9: 10am–11am"));
+        assert!(body.starts_with("Hi Ana,"));
+    }
+}
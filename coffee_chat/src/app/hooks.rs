@@ -0,0 +1,68 @@
+// src/app/hooks.rs
+// Running the config.toml hooks from the GUI: firing them as things happen, and the
+// "Test" button in the Hooks window.
+
+use super::messages::Message;
+use super::MyApp;
+use crate::hooks::{self, HookEvent};
+use log::info;
+
+impl MyApp {
+    /// Starts the hooks for `event` in the background, and returns how many. Nothing
+    /// waits for them. Demo mode's made-up sends never reach the real hooks.
+    pub(super) fn fire_hooks(&mut self, event: HookEvent) -> usize {
+        if self.hooks.configured.is_empty() || self.demo_mode {
+            return 0;
+        }
+        let rt = self.ensure_runtime().handle().clone();
+        hooks::fire(&self.hooks.configured, &event, &rt).len()
+    }
+
+    /// Runs one hook with a sample event marked as a test; the outcome arrives as
+    /// `HookTested`.
+    pub(super) fn test_hook(&mut self, index: usize) {
+        let Some(hook) = self.hooks.configured.get(index).cloned() else {
+            return;
+        };
+        if self.demo_mode {
+            self.hooks
+                .results
+                .insert(index, Err("Hooks don't run in demo mode.".to_string()));
+            return;
+        }
+        info!("Testing {} hook {}", hook.event.name(), hook.describe());
+        self.hooks.testing = Some(index);
+        self.hooks.results.remove(&index);
+        let sender = self.background.sender.clone();
+        let rt = self.ensure_runtime().handle().clone();
+        rt.spawn(async move {
+            let result = hooks::test(&hook).await.map_err(|e| e.to_string());
+            sender.send(Message::HookTested(index, result)).ok();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hooks::{HookConfig, HookEventKind};
+
+    #[test]
+    fn demo_mode_never_fires_the_real_hooks() {
+        let mut app = MyApp::default();
+        app.hooks.configured = vec![HookConfig {
+            event: HookEventKind::CalendarError,
+            url: None,
+            command: Some("true".to_string()),
+            timeout_seconds: None,
+        }];
+        let event = || HookEvent::CalendarError {
+            error: "offline".to_string(),
+        };
+        assert_eq!(app.fire_hooks(event()), 1);
+        app.enter_demo_mode();
+        assert_eq!(app.fire_hooks(event()), 0);
+        app.test_hook(0);
+        assert_eq!(app.hooks.testing, None);
+    }
+}
@@ -0,0 +1,662 @@
+// src/app/messages.rs
+// What background tasks report back to the UI thread.
+
+use super::tasks::NOT_SENT;
+use super::{FetchedSlots, MissingTemplate, MyApp, QuickSendState, SendOutcome, SendResult};
+use crate::calendar::booking::BookedMeeting;
+use crate::calendar::calendars::CalendarInfo;
+use crate::calendar::connect_error::CalendarConnectError;
+use crate::calendar::diagnose::Diagnosis;
+use crate::calendar::proxy;
+use crate::calendar::BusySource;
+use crate::config::{AppConfig, Recipient, SenderConfig};
+use crate::control::{ControlReply, ControlRequest};
+use crate::drip::Tried;
+use crate::email_sender::capabilities::ServerCapabilities;
+use crate::email_sender::history::{self, EmailKind, RecordedSlot, SentEmail};
+use crate::email_sender::receipt::SmtpReceipt;
+use crate::hooks::HookEvent;
+use chrono::Utc;
+use log::{debug, error, info};
+use std::path::PathBuf;
+use std::time::Instant;
+use tokio::sync::oneshot;
+
+pub(super) enum Message {
+    EmailSent {
+        recipient: Recipient,
+        subject: String,
+        receipt: SmtpReceipt,
+        options: Vec<String>,     // Slots the email numbered
+        slots: Vec<RecordedSlot>, // The availability list it went out with, for the history
+        campaign: Option<String>,
+    },
+    EmailFailed(String, String),
+    EmailSkipped(String, String), // Recently contacted by another send path, and why
+    FinishedSending {
+        sent: usize,
+        failed: usize,
+        duplicates: usize,           // Left out before sending
+        recently_contacted: usize,   // Skipped by the send registry
+        unsent: usize,               // Left after the batch stopped early
+        aborted: Option<String>,     // Why it stopped early
+        archived_to: Option<String>, // The archive BCC every email carried
+    },
+    QuickSendFinished(String, Result<SmtpReceipt, String>),
+    SpamTestSent(Result<String, String>), // Server reply to the test copy
+    ConfigLoaded(Result<Box<AppConfig>, String>), // Boxed: much bigger than the other messages
+    TemplateLoaded(Result<Option<(String, String)>, String>), // `None`: config.toml names no template
+    TemplateMissing(PathBuf, Box<SenderConfig>), // Absolute path; sent before TemplateLoaded
+    CalendarConnected(BusySource),
+    CalendarConnectionFailed(CalendarConnectError),
+    CalendarsListed(Result<Vec<CalendarInfo>, String>),
+    SlotsFetched(u64, FetchedSlots), // Fetch ID, and the windows with the settings they used
+    SlotsFetchFailed(u64, String),
+    /// The token couldn't be refreshed; the calendar has to be connected again.
+    CalendarSignInExpired(String),
+    SlotsDiagnosed(Result<Diagnosis, String>),
+    MeetingBooked(Result<BookedMeeting, String>),
+    MeetingCancelled(String, Result<(), String>), // Event ID
+    FollowUpSent {
+        entry: SentEmail,
+        receipt: Option<SmtpReceipt>, // Set when the email was accepted
+        options: Vec<String>,
+        deleted_event: Option<String>, // Event ID, when it was deleted
+    },
+    HookTested(usize, Result<String, String>), // Index in the configured hooks
+    SmtpServerChecked(Result<ServerCapabilities, String>),
+    Control(ControlRequest, oneshot::Sender<ControlReply>), // From the control API
+}
+
+impl MyApp {
+    /// Applies everything the background tasks have reported since the last frame.
+    pub(super) fn process_messages(&mut self) {
+        // --- Process Background Messages ---
+        while let Ok(message) = self.background.receiver.try_recv() {
+            match message {
+                // Demo data stays in place; only note that loading finished
+                Message::ConfigLoaded(_) if self.demo_mode => {
+                    self.background.config_loaded = true;
+                }
+                Message::TemplateLoaded(_) if self.demo_mode => {
+                    self.background.template_loaded = true;
+                }
+                Message::TemplateMissing(..) if self.demo_mode => {}
+                Message::TemplateMissing(path, sender) => {
+                    info!("Template file {} is missing.", path.display());
+                    self.compose.missing_template = Some(MissingTemplate {
+                        path,
+                        sender: *sender,
+                        error: None,
+                    });
+                }
+                Message::ConfigLoaded(Ok(config)) => {
+                    info!("Processing initial config load message.");
+                    let can_send = config.transport().is_some();
+                    self.apply_config(*config);
+                    if !self.background.state_loaded_from_file {
+                        // Nothing to send with yet: likely a first run, or calendar-only
+                        if !can_send {
+                            if !self.setup_checklist_dismissed {
+                                self.show_setup_checklist = true;
+                            }
+                            if self.status_message.contains("Using defaults") {
+                                self.status_message = "No [smtp] in config.toml. Fill in SMTP Settings to send; slots work without it.".to_string();
+                            }
+                        } else if self.status_message.contains("Using defaults") {
+                            self.status_message = "Applied defaults from config.toml.".to_string();
+                        }
+                    } else if self
+                        .status_message
+                        .contains("Loaded previous session state.")
+                    {
+                        self.status_message =
+                            "Loaded previous session. Initial config processed.".to_string();
+                    }
+                    self.background.config_loaded = true; // Mark config loading sequence step as done
+                    debug!("Config message processed.");
+                }
+                Message::ConfigLoaded(Err(e)) => {
+                    error!("Initial Config loading error message received: {}", e);
+                    // No saved state and no config: looks like a first run
+                    if !self.background.state_loaded_from_file && !self.setup_checklist_dismissed {
+                        self.show_setup_checklist = true;
+                    }
+                    if !self.background.state_loaded_from_file
+                        && self.status_message.contains("Using defaults")
+                    {
+                        self.status_message = format!("ERROR loading initial config: {}", e);
+                    }
+                    self.background.config_loaded = true; // Mark sequence step as done
+                }
+                Message::TemplateLoaded(Ok(None)) => {
+                    info!("No template in config.toml; the subject and body are typed in here.");
+                    self.background.template_loaded = true;
+                }
+                Message::TemplateLoaded(Ok(Some((subject, body)))) => {
+                    info!("Processing initial template load message.");
+                    // --- Apply template ONLY if state wasn't loaded ---
+                    if !self.background.state_loaded_from_file {
+                        info!("Applying template file content as no saved state was loaded.");
+                        self.compose.subject = subject;
+                        self.compose.body = body;
+                        if self
+                            .status_message
+                            .contains("Applied defaults from config.toml")
+                        {
+                            self.status_message =
+                                "Applied defaults from config and template.".to_string();
+                        } else if self.status_message.contains("Using defaults") {
+                            self.status_message = "Applied defaults from template.".to_string();
+                        }
+                    } else {
+                        info!(
+                            "Saved state already loaded, ignoring content from email_template.txt."
+                        );
+                        if self.status_message.contains("Initial config processed") {
+                            self.status_message =
+                                "Loaded previous session. Initial config/template processed."
+                                    .to_string();
+                        }
+                    }
+                    self.background.template_loaded = true; // Mark sequence step as done
+                    debug!("Template message processed.");
+                }
+                Message::TemplateLoaded(Err(e)) => {
+                    error!("Initial Template loading error message received: {}", e);
+                    if !self.background.state_loaded_from_file
+                        && self.status_message.contains("Using defaults")
+                    {
+                        self.status_message = format!("ERROR loading initial template: {}", e);
+                    }
+                    self.background.template_loaded = true; // Mark sequence step as done
+                }
+                Message::EmailSent {
+                    recipient,
+                    subject,
+                    receipt,
+                    options,
+                    slots,
+                    campaign,
+                } => {
+                    debug!(
+                        "UI Update: Email sent to {}: {}",
+                        recipient.email,
+                        receipt.summary()
+                    );
+                    self.record_invitation(
+                        &recipient.name,
+                        &recipient.email,
+                        subject,
+                        &receipt,
+                        slots,
+                        campaign,
+                    );
+                    if let Some(eta) = &mut self.compose.send_eta {
+                        eta.sent(Instant::now());
+                    }
+                    self.record_drip_email(&recipient.email, Tried::Sent);
+                    self.compose.send_results.push(SendResult {
+                        email: recipient.email,
+                        outcome: SendOutcome::Accepted(receipt),
+                        one_off: false,
+                        options,
+                    });
+                }
+                Message::EmailFailed(email, error) => {
+                    error!("UI Update: Email failed for {}: {}", email, error);
+                    self.status_message = format!("ERROR sending to {}: {}", email, error);
+                    self.fire_hooks(HookEvent::EmailFailed {
+                        email: email.clone(),
+                        error: error.clone(),
+                    });
+                    if let Some(eta) = &mut self.compose.send_eta {
+                        eta.sent(Instant::now());
+                    }
+                    self.record_drip_email(&email, Tried::Failed);
+                    self.compose.send_results.push(SendResult {
+                        email,
+                        outcome: SendOutcome::Failed(error),
+                        one_off: false,
+                        options: Vec::new(),
+                    });
+                }
+                Message::EmailSkipped(email, reason) => {
+                    info!("UI Update: Skipped {}: {}", email, reason);
+                    if let Some(eta) = &mut self.compose.send_eta {
+                        eta.skipped(Instant::now());
+                    }
+                    // Those left over by a stop stay due in a drip batch
+                    if reason != NOT_SENT {
+                        self.record_drip_email(&email, Tried::Skipped);
+                    }
+                    self.compose.send_results.push(SendResult {
+                        email,
+                        outcome: SendOutcome::Skipped(reason),
+                        one_off: false,
+                        options: Vec::new(),
+                    });
+                }
+                Message::QuickSendFinished(email, outcome) => {
+                    self.background.is_sending_email = false;
+                    // Taken before a successful send resets the dialog
+                    let options = match &outcome {
+                        Ok(_) => self.compose.quick_send.options.clone(),
+                        Err(_) => Vec::new(),
+                    };
+                    match &outcome {
+                        Ok(receipt) => {
+                            info!("UI Update: Quick send to {}: {}", email, receipt.summary());
+                            let quick_send = &self.compose.quick_send;
+                            let (name, subject, slots) = (
+                                quick_send.name.clone(),
+                                quick_send.draft.subject.clone(),
+                                quick_send.slots.clone(),
+                            );
+                            let campaign = history::campaign_name(&self.compose.campaign);
+                            self.record_invitation(
+                                name.trim(),
+                                &email,
+                                subject,
+                                receipt,
+                                slots,
+                                campaign,
+                            );
+                            self.status_message =
+                                format!("Sent to {}: {}", email, receipt.summary());
+                            // Done with this one; the next quick send starts blank
+                            self.compose.quick_send = QuickSendState::default();
+                        }
+                        Err(error) => {
+                            error!("UI Update: Quick send to {} failed: {}", email, error);
+                            self.status_message = format!("ERROR sending to {}: {}", email, error);
+                            self.compose.quick_send.send_error = Some(error.clone());
+                            self.fire_hooks(HookEvent::EmailFailed {
+                                email: email.clone(),
+                                error: error.clone(),
+                            });
+                        }
+                    }
+                    self.compose.send_results.push(SendResult {
+                        email,
+                        outcome: match outcome {
+                            Ok(receipt) => SendOutcome::Accepted(receipt),
+                            Err(error) => SendOutcome::Failed(error),
+                        },
+                        one_off: true,
+                        options,
+                    });
+                }
+                Message::SpamTestSent(outcome) => {
+                    self.background.is_sending_email = false;
+                    self.status_message = match &outcome {
+                        Ok(reply) => format!("Test copy sent: {}", reply),
+                        Err(error) => format!("ERROR sending the test copy: {}", error),
+                    };
+                    self.compose.spam_test = Some(outcome);
+                }
+                Message::FinishedSending {
+                    sent,
+                    failed,
+                    duplicates,
+                    recently_contacted,
+                    unsent,
+                    aborted,
+                    archived_to,
+                } => {
+                    info!(
+                        "UI Update: Finished sending emails (Success: {}, Failed: {}, Duplicates skipped: {}, Recently contacted: {})",
+                        sent, failed, duplicates, recently_contacted
+                    );
+                    self.background.is_sending_email = false;
+                    // Kept for the next batch's estimate
+                    if let Some(measured) =
+                        self.compose.send_eta.take().and_then(|eta| eta.measured())
+                    {
+                        self.compose.time_per_email = Some(measured);
+                    }
+                    self.status_message =
+                        format!("Finished sending. Success: {}, Failed: {}", sent, failed);
+                    self.finish_drip_batch();
+                    self.fire_hooks(HookEvent::BatchFinished {
+                        sent,
+                        failed,
+                        skipped: duplicates + recently_contacted,
+                        unsent,
+                        aborted: aborted.clone(),
+                    });
+                    if duplicates > 0 {
+                        self.status_message
+                            .push_str(&format!(", Duplicates skipped: {}", duplicates));
+                    }
+                    if recently_contacted > 0 {
+                        self.status_message.push_str(&format!(
+                            ", Recently contacted, skipped: {}",
+                            recently_contacted
+                        ));
+                    }
+                    if let Some(archive) = archived_to.filter(|_| sent > 0) {
+                        self.status_message
+                            .push_str(&format!(", Archived to: {}", archive));
+                    }
+                    if let Some(reason) = aborted {
+                        self.status_message = format!("{}. {}", reason, self.status_message);
+                    }
+                }
+                Message::CalendarConnected(source) => {
+                    info!("UI Update: Calendar connected.");
+                    self.background.is_connecting_calendar = false;
+                    if source.is_demo() {
+                        self.calendar.proxy = None;
+                        self.calendar.status = "Calendar: Connected (Demo)".to_string();
+                        self.status_message = "Connected to the demo calendar.".to_string();
+                    } else {
+                        self.calendar.proxy = proxy::google_api_proxy();
+                        self.calendar.status = "Calendar: Connected".to_string();
+                        self.status_message =
+                            "Successfully connected to Google Calendar.".to_string();
+                    }
+                    self.calendar.source = Some(source);
+                    info!("Triggering automatic slot fetch after connection.");
+                    self.handle_fetch_slots();
+                }
+                Message::CalendarConnectionFailed(connect_error) => {
+                    error!("UI Update: Calendar connection failed: {}", connect_error);
+                    self.background.is_connecting_calendar = false;
+                    self.calendar.source = None;
+                    self.calendar.status = "Calendar: Connection Failed".to_string();
+                    self.fire_hooks(HookEvent::CalendarError {
+                        error: connect_error.to_string(),
+                    });
+                    self.status_message = format!(
+                        "Calendar connection failed: {}. Click \"Details…\" for help.",
+                        connect_error
+                    );
+                    self.calendar.error = Some(connect_error);
+                }
+                Message::CalendarsListed(result) => {
+                    match &result {
+                        Ok(calendars) => info!("UI Update: {} calendars listed.", calendars.len()),
+                        Err(e) => error!("UI Update: Listing calendars failed: {}", e),
+                    }
+                    self.calendar.calendars = Some(result);
+                }
+                // A newer fetch replaced this one
+                Message::SlotsFetched(fetch_id, _) | Message::SlotsFetchFailed(fetch_id, _)
+                    if fetch_id != self.background.slot_fetch_id =>
+                {
+                    info!(
+                        "UI Update: Dropping the result of superseded fetch {}.",
+                        fetch_id
+                    );
+                }
+                // Computed with settings that have since changed; the list would say otherwise
+                Message::SlotsFetched(_, fetched)
+                    if self.calendar.source.is_some()
+                        && fetched.settings != self.current_slot_settings() =>
+                {
+                    info!("UI Update: Calendar settings changed during the fetch; fetching again.");
+                    self.background.is_fetching_slots = false;
+                    self.handle_fetch_slots();
+                    self.status_message =
+                        "Calendar settings changed while fetching. Fetching again…".to_string();
+                }
+                Message::SlotsFetched(_, fetched) => {
+                    info!(
+                        "UI Update: Slots fetched ({} windows).",
+                        fetched.windows.len()
+                    );
+                    self.background.is_fetching_slots = false;
+                    self.apply_fetched_slots(fetched);
+                    self.status_message = format!(
+                        "Fetched {} available time slots.",
+                        self.calendar.available_slots.len()
+                    );
+                    self.fire_hooks(HookEvent::SlotsFetched {
+                        slots: self.calendar.available_slots.len(),
+                    });
+                    if self.calendar.source.is_some() {
+                        self.calendar.status = "Calendar: Connected (Slots Loaded)".to_string();
+                    }
+                    if self.drip.awaiting_slots {
+                        self.send_drip_batch();
+                    }
+                    self.follow_up_slots_ready(None);
+                }
+                Message::SlotsFetchFailed(_, error_msg) => {
+                    error!("UI Update: Slot fetching failed: {}", error_msg);
+                    self.background.is_fetching_slots = false;
+                    self.clear_slots();
+                    if self.drip.awaiting_slots {
+                        self.drip_slots_failed(&error_msg);
+                    }
+                    self.follow_up_slots_ready(Some(&error_msg));
+                    self.fire_hooks(HookEvent::CalendarError {
+                        error: error_msg.clone(),
+                    });
+                    self.status_message = error_msg;
+                    if self.calendar.source.is_some() {
+                        self.calendar.status = "Calendar: Connected (Slot Error)".to_string();
+                    }
+                }
+                Message::CalendarSignInExpired(error_msg) => {
+                    error!("UI Update: Calendar sign-in expired: {}", error_msg);
+                    self.background.is_fetching_slots = false;
+                    self.clear_slots();
+                    if self.drip.awaiting_slots {
+                        self.drip_slots_failed(&error_msg);
+                    }
+                    self.follow_up_slots_ready(Some(&error_msg));
+                    // Dropping the source brings back the Connect button for a fresh sign-in
+                    self.calendar.source = None;
+                    self.calendar.status = "Calendar: Sign-in Expired".to_string();
+                    self.fire_hooks(HookEvent::CalendarError {
+                        error: error_msg.clone(),
+                    });
+                    self.status_message = error_msg;
+                }
+                Message::SlotsDiagnosed(result) => {
+                    self.background.is_diagnosing = false;
+                    self.status_message = match &result {
+                        Ok(diagnosis) => match diagnosis.first_empty_stage() {
+                            Some(stage) => {
+                                format!("Diagnosis: no slots left at \"{}\".", stage.label())
+                            }
+                            None => "Diagnosis: slots found at every step.".to_string(),
+                        },
+                        Err(e) => e.clone(),
+                    };
+                    self.calendar.diagnosis = Some(result);
+                }
+                Message::MeetingBooked(result) => {
+                    self.booking.in_flight = false;
+                    self.booking.result = Some(match result {
+                        Ok(meeting) => {
+                            info!(
+                                "Booked {} with {}",
+                                meeting.event_id, meeting.attendee_email
+                            );
+                            let summary = format!(
+                                "Booked \"{}\"; {} has been sent an invitation.",
+                                meeting.title, meeting.attendee_email
+                            );
+                            self.status_message = summary.clone();
+                            self.booking.booked.push(meeting);
+                            Ok(summary)
+                        }
+                        Err(e) => {
+                            self.status_message = format!("Booking failed: {}", e);
+                            Err(e)
+                        }
+                    });
+                }
+                Message::FollowUpSent {
+                    entry,
+                    receipt,
+                    options,
+                    deleted_event,
+                } => {
+                    self.background.is_sending_email = false;
+                    if let Some(event_id) = &deleted_event {
+                        self.booking
+                            .booked
+                            .retain(|meeting| &meeting.event_id != event_id);
+                    }
+                    let email = entry.email.clone();
+                    match &entry.outcome {
+                        Ok(summary) => {
+                            info!("UI Update: {} sent to {}: {}", entry.kind, email, summary);
+                            self.status_message = match &entry.event {
+                                Some(event) => {
+                                    format!("{} sent to {}. {}.", entry.kind, email, event)
+                                }
+                                None => format!("{} sent to {}.", entry.kind, email),
+                            };
+                            self.follow_up.draft = None;
+                        }
+                        Err(e) => {
+                            error!("UI Update: {} to {} failed: {}", entry.kind, email, e);
+                            self.status_message = format!("ERROR sending to {}: {}", email, e);
+                            if let Some(draft) = &mut self.follow_up.draft {
+                                draft.in_flight = false;
+                                draft.error = Some(e.clone());
+                            }
+                            self.fire_hooks(HookEvent::EmailFailed {
+                                email: email.clone(),
+                                error: e.clone(),
+                            });
+                        }
+                    }
+                    self.compose.send_results.push(SendResult {
+                        email,
+                        outcome: match (receipt, &entry.outcome) {
+                            (Some(receipt), _) => SendOutcome::Accepted(receipt),
+                            (None, outcome) => {
+                                SendOutcome::Failed(outcome.clone().err().unwrap_or_default())
+                            }
+                        },
+                        one_off: true,
+                        options,
+                    });
+                    history::record(&mut self.compose.history, entry);
+                }
+                Message::MeetingCancelled(event_id, result) => {
+                    self.booking.in_flight = false;
+                    self.booking.result = Some(match result {
+                        Ok(()) => {
+                            let cancelled = self
+                                .booking
+                                .booked
+                                .iter()
+                                .position(|meeting| meeting.event_id == event_id)
+                                .map(|index| self.booking.booked.remove(index));
+                            let summary = match cancelled {
+                                Some(meeting) => format!(
+                                    "Cancelled \"{}\"; {} has been told.",
+                                    meeting.title, meeting.attendee_email
+                                ),
+                                None => "Meeting cancelled.".to_string(),
+                            };
+                            self.status_message = summary.clone();
+                            Ok(summary)
+                        }
+                        Err(e) => {
+                            self.status_message = format!("Cancelling failed: {}", e);
+                            Err(e)
+                        }
+                    });
+                }
+                Message::HookTested(index, result) => {
+                    self.hooks.testing = None;
+                    match &result {
+                        Ok(summary) => info!("Hook test {}: {}", index + 1, summary),
+                        Err(e) => error!("Hook test {} failed: {}", index + 1, e),
+                    }
+                    self.hooks.results.insert(index, result);
+                }
+                Message::SmtpServerChecked(checked) => {
+                    if let Err(e) = &checked {
+                        error!("{}", e);
+                    }
+                    self.smtp.checking_server = false;
+                    self.smtp.server = Some(checked);
+                }
+                Message::Control(request, reply_to) => {
+                    // The request may have timed out meanwhile
+                    reply_to.send(self.answer_control(request)).ok();
+                }
+            }
+        }
+    }
+}
+
+impl MyApp {
+    /// Keeps an accepted invitation in the history, so it can be followed up later.
+    fn record_invitation(
+        &mut self,
+        name: &str,
+        email: &str,
+        subject: String,
+        receipt: &SmtpReceipt,
+        slots: Vec<RecordedSlot>,
+        campaign: Option<String>,
+    ) {
+        history::record(
+            &mut self.compose.history,
+            SentEmail {
+                kind: EmailKind::Invitation,
+                name: name.to_string(),
+                email: email.to_string(),
+                subject,
+                sent_at: Utc::now(),
+                message_id: receipt.message_id.clone(),
+                outcome: Ok(receipt.summary()),
+                event: None,
+                slots: Some(slots),
+                campaign,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn fetched(app: &MyApp) -> FetchedSlots {
+        let start = Utc::now() + Duration::days(1);
+        FetchedSlots {
+            windows: vec![(start, start + Duration::hours(1))],
+            fetched_at: Utc::now(),
+            settings: app.current_slot_settings(),
+            zone: None,
+            free_time: None,
+            busy: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn only_the_newest_fetch_is_applied() {
+        let mut app = MyApp::default();
+        app.background.slot_fetch_id = 2;
+        app.background.is_fetching_slots = true;
+        let sender = app.background.sender.clone();
+        sender
+            .send(Message::SlotsFetched(1, fetched(&app)))
+            .unwrap();
+        sender
+            .send(Message::SlotsFetchFailed(1, "too late".to_string()))
+            .unwrap();
+        app.process_messages();
+        assert!(app.calendar.last_fetch.is_none());
+        assert!(app.background.is_fetching_slots);
+
+        sender
+            .send(Message::SlotsFetched(2, fetched(&app)))
+            .unwrap();
+        app.process_messages();
+        assert!(app.calendar.last_fetch.is_some());
+        assert!(!app.background.is_fetching_slots);
+    }
+}
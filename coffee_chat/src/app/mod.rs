@@ -0,0 +1,1155 @@
+// src/app/mod.rs
+mod availability_page;
+mod backup;
+mod booking;
+mod control;
+mod demo;
+mod drip;
+mod effective_config;
+mod field_check;
+mod follow_up;
+mod hooks;
+mod messages;
+mod quick_send;
+mod recipient_search;
+mod reset_data;
+mod safe_mode;
+mod slot_freeze;
+mod spam_check;
+mod state;
+mod tasks;
+mod ui;
+
+use crate::calendar;
+use crate::calendar::booking::BookedMeeting;
+use crate::calendar::calendars::CalendarInfo;
+use crate::calendar::connect_error::CalendarConnectError;
+use crate::calendar::diagnose::Diagnosis;
+use crate::calendar::free_busy::WeeklyRules;
+use crate::calendar::free_time::FreeTime;
+use crate::calendar::proxy::ProxyConnector;
+use crate::calendar::timezone::LocalZone;
+use crate::calendar::units::{Buffer, HourOfDay, Minutes, Weekdays};
+use crate::calendar::{BusySource, CalendarBusy};
+use crate::config::{SenderConfig, SmtpTlsMode};
+use crate::email_sender::capabilities::ServerCapabilities;
+use crate::email_sender::failure_streak::DEFAULT_ABORT_AFTER_FAILURES;
+use crate::email_sender::history::{EmailKind, RecordedSlot, SentEmail};
+use crate::email_sender::priority::Priority;
+use crate::email_sender::readability;
+use crate::email_sender::receipt::SmtpReceipt;
+use crate::email_sender::registry::{self, SendRegistry};
+use crate::email_sender::role_account::RoleAccounts;
+use crate::email_sender::send_eta::SendEta;
+use crate::email_sender::spam_check::SpamReport;
+use crate::email_sender::template;
+use crate::email_sender::PreparedEmail;
+use crate::hooks::HookConfig;
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+use eframe::egui;
+use google_calendar3::CalendarHub;
+use hyper_rustls::HttpsConnector;
+use log::info;
+use messages::Message;
+use secrecy::SecretString;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::time::Instant;
+use tokio::runtime::Runtime;
+
+pub use reset_data::{data_files, delete_data_files, RESET_CONFIRMATION};
+pub use safe_mode::{has_started, StartMode};
+pub use state::SavedSlotSettings;
+
+// --- Define types based on yup-oauth2 feature ---
+
+// Common connector type used by hyper-rustls
+pub type HttpConnector = hyper_util::client::legacy::connect::HttpConnector;
+pub type TokioConnector = HttpsConnector<ProxyConnector>;
+
+// Define client and hub types - Adjust based on how client is created
+// If using yup-oauth2 hyper_client builder, the exact type might be simpler:
+// type CalendarClient = yup_oauth2::hyper_client::Client; <- Check yup_oauth2 docs
+// For now, assume manual build path OR yup-oauth2 handles it internally.
+// The key is that CalendarHub::new needs compatible types.
+// We define TokioConnector, and let CalendarHub handle the client generics if possible.
+pub type AppCalendarHub = Arc<CalendarHub<TokioConnector>>;
+pub type AppAuthenticator = yup_oauth2::authenticator::Authenticator<TokioConnector>;
+
+// Slots older than this get a staleness warning in the UI and when sending
+const SLOTS_STALE_AFTER_MINUTES: i64 = 60;
+
+// How often the drip schedule is checked for batches that are due
+const DRIP_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// How often the system timezone is compared against the one slots were fetched in
+const ZONE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+// Window title; " *" is added while there are unsaved changes
+pub const APP_TITLE: &str = "Coffee Chat Helper";
+
+// Default for the persisted autosave interval; 0 turns autosave off
+const DEFAULT_AUTOSAVE_SECONDS: u32 = 60;
+
+// Recipients per drip batch, until the user changes it
+const DEFAULT_DRIP_BATCH_SIZE: u32 = 10;
+
+// Rows in the Available Slots list before "Show all"; the email cap is separate
+const DEFAULT_SLOTS_SHOWN_IN_LIST: u32 = 20;
+
+// Removing a recipient asks first once the list is longer than this, until the user changes it
+const DEFAULT_CONFIRM_REMOVE_ABOVE: u32 = 10;
+
+// Length of a booked meeting, until the user changes it
+const DEFAULT_MEETING_MINUTES: u32 = 30;
+
+// What {{ availabilities }} says when no slots are offered, until the user changes it
+const DEFAULT_NO_SLOTS_TEXT: &str = "No times listed yet, so let me know what works for you.";
+
+// --- Fetched Slots ---
+// Calendar settings a slot list was computed under
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct SlotSettings {
+    buffer_minutes: Minutes, // Before meetings, and after too when `buffer_after_minutes` is unset
+    start_hour: HourOfDay,
+    end_hour: HourOfDay,
+    today_cutoff_hour: Option<u32>,
+    min_gap_minutes: u32,
+    max_per_day: u32,
+    #[serde(default)] // Added later; older saved slots didn't pad
+    pad_range_edges: bool,
+    #[serde(default)]
+    blackout_dates: Vec<NaiveDate>,
+    #[serde(default)]
+    buffer_longer_than: Option<Minutes>,
+    #[serde(default)]
+    weekly_rules: Option<WeeklyRules>,
+    #[serde(default)] // Older saved slots used one buffer for both sides
+    buffer_after_minutes: Option<Minutes>,
+    #[serde(default = "default_lookahead_days")] // Older saved slots always looked 14 days ahead
+    lookahead_days: u32,
+    #[serde(default)] // Empty is the primary calendar, all older saved slots came from
+    calendar_ids: Vec<String>,
+    #[serde(default)] // Every day, as older saved slots were
+    weekdays: Weekdays,
+}
+
+fn default_lookahead_days() -> u32 {
+    calendar::DEFAULT_LOOKAHEAD_DAYS
+}
+
+impl SlotSettings {
+    fn filters(&self) -> calendar::SlotFilters {
+        calendar::SlotFilters {
+            buffer: Buffer {
+                before: self.buffer_minutes,
+                after: self.buffer_after_minutes.unwrap_or(self.buffer_minutes),
+            },
+            start_hour: self.start_hour,
+            end_hour: self.end_hour,
+            today_cutoff_hour: self.today_cutoff_hour,
+            selection: calendar::free_busy::SlotSelection {
+                min_gap: Duration::minutes(self.min_gap_minutes as i64),
+                max_per_day: (self.max_per_day > 0).then_some(self.max_per_day as usize),
+            },
+            pad_range_edges: self.pad_range_edges,
+            blackout_dates: self.blackout_dates.clone(),
+            buffer_longer_than: self.buffer_longer_than,
+            weekly_rules: self.weekly_rules.clone(),
+            weekdays: self.weekdays,
+        }
+    }
+}
+
+// Structured result of the last fetch, persisted so slots survive a restart
+#[derive(Clone, Serialize, Deserialize)]
+struct FetchedSlots {
+    windows: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    fetched_at: DateTime<Utc>,
+    settings: SlotSettings,
+    #[serde(default)] // Older state files didn't record the timezone
+    zone: Option<LocalZone>,
+    #[serde(default)] // How much of the lookahead was free; older state files didn't have it
+    free_time: Option<FreeTime>,
+    #[serde(default)] // Busy periods with their calendars, for the heat map
+    busy: Vec<CalendarBusy>,
+}
+
+impl FetchedSlots {
+    /// Where the fetch's lookahead ended.
+    fn range_end(&self) -> DateTime<Utc> {
+        self.fetched_at + Duration::days(self.settings.lookahead_days as i64)
+    }
+}
+
+// --- UIRecipient ---
+// (Struct remains the same)
+#[derive(Clone, Serialize, Deserialize)]
+struct UIRecipient {
+    name: String,
+    email: String,
+    #[serde(default)] // Older state files have no custom fields
+    fields: BTreeMap<String, String>,
+    #[serde(default)] // Pinned recipients are sent to before everyone else
+    pinned: bool,
+}
+
+// --- MyApp Struct ---
+pub struct MyApp {
+    smtp: SmtpSettings,
+    compose: ComposeState,
+    calendar_settings: CalendarSettings,
+    calendar: CalendarState,
+    background: BackgroundState,
+
+    // Application Status
+    status_message: String,
+    show_setup_checklist: bool,
+    setup_checklist_dismissed: bool, // Persisted so the first-run checklist only opens once
+    demo_mode: bool,                 // Sample data, fake calendar and mail server; nothing saved
+    prefer_config_file: bool,        // Persisted: config.toml overrides the saved session on start
+    config_sources: effective_config::ConfigSources, // Where each setting came from
+    show_effective_config: bool,
+    save: SaveState,
+    backup: BackupState,
+    drip: DripState,
+    freeze: slot_freeze::FreezeState,
+    availability_page: AvailabilityPageState,
+    booking: BookingState,
+    follow_up: FollowUpState,
+    hooks: HookState,
+    control: control::ControlState,
+    safe_mode: safe_mode::SafeModeState,
+    reset_data: reset_data::ResetDataState,
+}
+
+// SMTP server and login, as edited in the settings panel
+struct SmtpSettings {
+    host: String,
+    port_str: String,
+    user: String,
+    password: SecretString,
+    from_email: String,
+    tls_mode: SmtpTlsMode,
+    proxy: Option<String>, // From config.toml only, like the TLS mode
+    no_auth: bool,         // Send without AUTH (internal relays)
+    use_webhook: bool,     // POST each email to `webhook_url` instead of using SMTP
+    webhook_url: String,
+    server: Option<Result<ServerCapabilities, String>>, // What the server advertised when last checked
+    checking_server: bool,
+}
+
+// The invitation being written and who it goes to
+struct ComposeState {
+    sender_name: String,
+    template_source: String, // Template file, or config.toml for inline templates
+    subject: String,
+    body: String,
+    cc: String,            // Tera template, rendered per recipient
+    bcc: String,           // Tera template, rendered per recipient
+    archive_bcc: String,   // Persisted; one shared mailbox BCC'd on every email, not rendered
+    no_slots_text: String, // Stands in for the slot list when there are none; empty = blank
+    drip_enabled: bool,    // Split long lists into batches sent on different days
+    drip_batch_size: u32,
+    drip_interval_days: u32,
+    send_cooldown_minutes: u32, // Skip anyone emailed this recently, by any send path
+    abort_after_failures: u32, // Persisted; stop a batch after this many failures in a row, 0 = never
+    priority: Priority,        // X-Priority / Importance headers on every email
+    campaign: String,          // Persisted; labels the history entries of each send, empty = none
+    campaign_rename: Option<(String, String)>, // Campaign being renamed, and the new name so far
+    recipients: Vec<UIRecipient>,
+    new_recipient_name: String,
+    new_recipient_email: String,
+    new_recipient_fields: String,               // "key=value; key=value"
+    confirm_remove_above: u32, // Persisted; "X" asks first on longer lists, 0 = never
+    confirming_remove: Option<usize>, // Row showing "Remove?"
+    last_removed: Option<(usize, UIRecipient)>, // For "Undo", with where it was
+    recipient_search: recipient_search::RecipientSearch, // Not saved
+    field_check: field_check::FieldCheck, // Recipients missing a field the template uses
+    skip_send_confirmation: bool, // Persisted "don't ask again" for the send confirmation
+    confirming_send: bool,     // Send confirmation dialog is open
+    role_accounts: RoleAccounts, // From config.toml; flags info@ and the like, advisory only
+    exclude_role_accounts: bool, // "Exclude all flagged" in the confirmation, for this send
+    readability_warning: Option<String>, // Worked out when the confirmation opens
+    spam_check: bool,          // Persisted; score the first email for spam signals before a send
+    spam_test_address: String, // Persisted; mail-tester style address for a test copy, empty = off
+    spam_report: Option<SpamReport>, // Worked out when the confirmation opens
+    spam_test: Option<Result<String, String>>, // Outcome of the last test copy
+    send_results: Vec<SendResult>, // Per-recipient outcome of the last send
+    send_eta: Option<SendEta>, // Progress and time left of the batch being sent
+    time_per_email: Option<std::time::Duration>, // Measured by the last batch, for the next estimate; not saved
+    quick_send: QuickSendState,                  // "Quick send…" dialog, separate from the list
+    reply_lookup: ReplyLookup,                   // "Resolve a reply" under the send results
+    history: Vec<SentEmail>, // Persisted; what cancellations and reschedules reply to
+    missing_template: Option<MissingTemplate>, // config.toml names a file that isn't there
+}
+
+// A template_path that doesn't exist, offered to be created from the default
+struct MissingTemplate {
+    path: PathBuf,         // Absolute
+    sender: SenderConfig,  // To load the template the same way once the file exists
+    error: Option<String>, // Why creating it failed
+}
+
+// One recipient's outcome
+struct SendResult {
+    email: String,
+    outcome: SendOutcome,
+    one_off: bool,        // From the quick send dialog, not the list
+    options: Vec<String>, // Slots the email numbered; option n is at n - 1
+}
+
+impl SendResult {
+    /// The slot a reply like "option 3 works" picked, with its number.
+    fn resolve_reply(&self, reply: &str) -> Result<(usize, &str), String> {
+        let number = template::option_number(reply)
+            .ok_or_else(|| "No option number in the reply.".to_string())?;
+        match self.options.get(number - 1) {
+            Some(slot) => Ok((number, slot)),
+            None => Err(format!(
+                "{} was offered options 1-{}, not {}.",
+                self.email,
+                self.options.len(),
+                number
+            )),
+        }
+    }
+}
+
+// Matching a reply's option number to the slot it means
+#[derive(Default)]
+struct ReplyLookup {
+    email: String, // Recipient picked from the send results
+    reply: String, // Their reply, or just the number
+}
+
+enum SendOutcome {
+    Accepted(SmtpReceipt), // The server's acceptance reply
+    Failed(String),
+    Skipped(String), // Emailed by another send path moments ago, or left out before sending
+}
+
+// How free slots are looked up and filtered
+struct CalendarSettings {
+    credentials_path: String,
+    token_cache_path: String,
+    buffer_before_minutes: Minutes,      // Buffer kept before meetings
+    buffer_after_minutes: Minutes,       // And after them
+    buffer_longer_than: Option<Minutes>, // Only around meetings longer than this
+    day_start_hour: HourOfDay,           // First hour slots may start
+    day_end_hour: HourOfDay,             // Slots end by this hour
+    today_cutoff_hour: Option<u32>,      // Skip today's slots from this hour on
+    slot_min_gap_minutes: u32,           // Min gap between offered slots on the same day
+    max_slots_per_day: u32,              // 0 = no limit
+    max_slots_in_email: u32,             // Slot lines sent per email, 0 = no limit
+    readable_slot_lines: u32,            // Warn before sending more slot lines than this, 0 = never
+    show_unavailable_days: bool,         // Add "<day>: no availability" lines for fully booked days
+    pad_range_edges: bool, // Keep the buffer clear after "now" and before the lookahead end
+    blackout_dates: Vec<NaiveDate>, // Days offered no slots, sorted, no duplicates
+    reverse_slot_order: bool, // List the last day of the lookahead first
+    label_timezone: bool,  // End the emailed slot list with "(all times ET)"
+    slots_shown_in_list: u32, // Rows in the Available Slots list until expanded, 0 = all
+    weekly_rules: WeeklyRules, // When chats happen at all, kept while turned off
+    weekly_rules_enabled: bool,
+    weekdays: Weekdays,  // Days slots are offered on at all, Monday first
+    lookahead_days: u32, // How many days ahead to look for slots
+    slot_calendar_ids: Vec<String>, // Calendars slots must be free on, empty = primary
+}
+
+// Calendar connection and the slots fetched through it
+struct CalendarState {
+    source: Option<BusySource>, // Set once connected (or in demo mode)
+    status: String,
+    error: Option<CalendarConnectError>, // Last connection failure, for the details dialog
+    proxy: Option<String>,               // host:port the Google client goes through, if any
+    show_error_details: bool,
+    available_slots: Vec<String>,
+    last_fetch: Option<FetchedSlots>, // What `available_slots` was summarized from
+    unavailable_days: Vec<String>,    // Fully booked days in `last_fetch`, for {{unavailable_days}}
+    pasted_availability: String,      // The other person's free times, "day HH-HH" per line
+    common_slots: Option<Result<Vec<String>, String>>, // Overlap with `last_fetch`, or a parse error
+    slots_restored: bool, // Slots came from the saved state, not this session
+    diagnosis: Option<Result<Diagnosis, String>>, // Last "Diagnose" run
+    calendars: Option<Result<Vec<CalendarInfo>, String>>, // The user's calendar list, once connected
+    zone_change: Option<String>, // Banner after the system timezone changed under the slots
+    zone_checked_at: Option<Instant>,
+    blackout_pick: Option<NaiveDate>, // Date chosen in the blackout picker, tomorrow if unset
+    weekly_rule_drafts: [Option<String>; 7], // Weekly Availability text as typed, Monday first
+    show_all_slots: bool,             // Available Slots list expanded past `slots_shown_in_list`
+    slot_notes: BTreeMap<String, String>, // Slot line -> note shown after it in emails
+    editing_note: Option<String>,     // Slot line whose note field is open
+}
+
+// One email to someone who isn't on the recipient list
+#[derive(Default)]
+struct QuickSendState {
+    open: bool,
+    name: String,
+    email: String,
+    note: String,                 // Optional line for this person only
+    draft: PreparedEmail,         // Rendered for the fields above, editable
+    edited: bool,                 // Draft changed by hand, so it's no longer re-rendered
+    render_error: Option<String>, // Template problem while rendering the draft
+    send_error: Option<String>,   // Last failed attempt; the dialog stays open
+    options: Vec<String>,         // Slots the draft numbered, for the send results
+    slots: Vec<RecordedSlot>,     // The draft's availability list, for the history
+}
+
+// Saving app_state.json: autosave, unsaved-change tracking and the exit prompt
+struct SaveState {
+    autosave_seconds: u32,             // Persisted, 0 = only save on exit
+    checked_at: Option<Instant>,       // Last autosave check (or load)
+    saved_json: Option<String>,        // What's on disk, to skip saves when nothing changed
+    saved_at: Option<DateTime<Local>>, // Last write this session, for the status bar
+    dirty: bool,                       // Session differs from `saved_json`, refreshed every frame
+    error: Option<String>,             // Last failed save, cleared by the next good one
+    confirming_exit: bool,             // "Save before exiting?" dialog is open
+    exit_confirmed: bool,              // Let the next close request through
+    discard_on_exit: bool,             // "Exit without saving" was chosen
+}
+
+impl SaveState {
+    fn autosave_interval(&self) -> Option<std::time::Duration> {
+        (self.autosave_seconds > 0)
+            .then(|| std::time::Duration::from_secs(u64::from(self.autosave_seconds)))
+    }
+}
+
+// Drip sending: the schedule on disk and the batch being sent from it
+#[derive(Default)]
+struct DripState {
+    schedule: Option<crate::drip::DripSchedule>, // Mirrors drip_schedule.json; None when nothing is left
+    open: bool,                                  // Schedule window
+    dispatching: Option<usize>,                  // Batch being fetched for or sent
+    awaiting_slots: bool,                        // Its slot refetch hasn't come back yet
+    checked_at: Option<Instant>,                 // Last look for due batches
+    notice: Option<String>,                      // Why a due batch hasn't gone out
+    queued: Vec<drip::DripEdit>,                 // Not written yet; the file was locked
+}
+
+// "Export backup…" / "Import backup…" dialog
+struct BackupState {
+    open: bool,
+    export_path: String,
+    include_credentials: bool, // SMTP password and token cache go in the archive
+    import_path: String,
+    pending: Option<backup::PendingImport>, // Read and checked, waiting for "Restore"
+    result: Option<Result<String, String>>, // Outcome of the last export or import
+}
+
+impl Default for BackupState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            export_path: MyApp::default_backup_path(),
+            include_credentials: false,
+            import_path: String::new(),
+            pending: None,
+            result: None,
+        }
+    }
+}
+
+// "Generate availability page…" dialog
+struct AvailabilityPageState {
+    open: bool,
+    path: String,     // Persisted; the file name is also the end of the link
+    base_url: String, // Persisted; where the file gets uploaded, empty = no {{ availability_link }}
+    result: Option<Result<String, String>>, // Outcome of the last generate
+}
+
+impl Default for AvailabilityPageState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            path: MyApp::default_availability_page_path(),
+            base_url: String::new(),
+            result: None,
+        }
+    }
+}
+
+// "Book meeting…" dialog and the events it put on the calendar
+struct BookingState {
+    open: bool,
+    recipient_name: String,
+    recipient_email: String,
+    slot: String,                           // Offered slot line the meeting falls in
+    start_offset_minutes: u32,              // From the start of that slot
+    title_template: String,                 // Persisted; Tera, like the email
+    meeting_minutes: u32,                   // Persisted
+    with_meet: bool,                        // Persisted; ask Google for a Meet link
+    in_flight: bool,                        // Booking or cancelling in the background
+    result: Option<Result<String, String>>, // Outcome of the last book or cancel
+    booked: Vec<BookedMeeting>, // Persisted, so they can be cancelled from the app later
+}
+
+impl Default for BookingState {
+    fn default() -> Self {
+        Self {
+            open: false,
+            recipient_name: String::new(),
+            recipient_email: String::new(),
+            slot: String::new(),
+            start_offset_minutes: 0,
+            title_template: calendar::booking::DEFAULT_TITLE_TEMPLATE.to_string(),
+            meeting_minutes: DEFAULT_MEETING_MINUTES,
+            with_meet: false,
+            in_flight: false,
+            result: None,
+            booked: Vec::new(),
+        }
+    }
+}
+
+// Hooks from config.toml, and the "Hooks" window for trying them out
+#[derive(Default)]
+struct HookState {
+    open: bool,
+    configured: Vec<HookConfig>, // From config.toml only; not saved with the session
+    testing: Option<usize>,      // Index of the hook being tried
+    results: BTreeMap<usize, Result<String, String>>, // Last test of each hook
+}
+
+// Cancellation and reschedule replies, sent from the history
+#[derive(Default)]
+struct FollowUpState {
+    templates: FollowUpTemplates, // Persisted
+    draft: Option<FollowUpDraft>, // The dialog, while open
+}
+
+/// Subject and body templates for each kind of follow-up.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct FollowUpTemplates {
+    cancel_subject: String,
+    cancel_body: String,
+    reschedule_subject: String,
+    reschedule_body: String,
+}
+
+impl FollowUpTemplates {
+    fn for_kind(&self, kind: EmailKind) -> (&str, &str) {
+        match kind {
+            EmailKind::Reschedule => (&self.reschedule_subject, &self.reschedule_body),
+            _ => (&self.cancel_subject, &self.cancel_body),
+        }
+    }
+
+    fn for_kind_mut(&mut self, kind: EmailKind) -> (&mut String, &mut String) {
+        match kind {
+            EmailKind::Reschedule => (&mut self.reschedule_subject, &mut self.reschedule_body),
+            _ => (&mut self.cancel_subject, &mut self.cancel_body),
+        }
+    }
+}
+
+impl Default for FollowUpTemplates {
+    fn default() -> Self {
+        Self {
+            cancel_subject: "Re: {{ original_subject }}".to_string(),
+            cancel_body: "Hi {{ recipient_name }},\n\nI'm sorry, but something has come up and I need to cancel{% if meeting_time %} our chat on {{ meeting_time }}{% else %} our coffee chat{% endif %}. Apologies for the change of plans.\n\nBest,\n{{ sender_name }}".to_string(),
+            reschedule_subject: "Re: {{ original_subject }}".to_string(),
+            reschedule_body: "Hi {{ recipient_name }},\n\nI'm sorry, but I need to move{% if meeting_time %} our chat on {{ meeting_time }}{% else %} our coffee chat{% endif %}. These times work for me instead (just reply with the option number):\n{% for slot in slots %}\n- {% if slot.index %}{{ slot.index }}) {% endif %}{{ slot.text }}\n{% endfor %}\n\nBest,\n{{ sender_name }}".to_string(),
+        }
+    }
+}
+
+// One cancellation or reschedule being written
+struct FollowUpDraft {
+    kind: EmailKind,
+    original: SentEmail,            // The email this replies to
+    meeting: Option<BookedMeeting>, // Booked with them from the app, if any
+    delete_event: bool,             // Delete `meeting` once the email is sent
+    email: PreparedEmail,           // Rendered, editable
+    edited: bool,                   // Changed by hand, so no longer re-rendered
+    options: Vec<String>,           // Slots a reschedule numbered
+    slots: Vec<RecordedSlot>,       // A reschedule's availability list, for the history
+    awaiting_slots: bool,           // Fresh slots for a reschedule are being fetched
+    in_flight: bool,
+    error: Option<String>,
+}
+
+impl Default for SaveState {
+    fn default() -> Self {
+        Self {
+            autosave_seconds: DEFAULT_AUTOSAVE_SECONDS,
+            checked_at: None,
+            saved_json: None,
+            saved_at: None,
+            dirty: false,
+            error: None,
+            confirming_exit: false,
+            exit_confirmed: false,
+            discard_on_exit: false,
+        }
+    }
+}
+
+// Background tasks and the channel they report back on
+struct BackgroundState {
+    tokio_rt: Option<Runtime>,
+    receiver: mpsc::Receiver<Message>,
+    sender: mpsc::Sender<Message>,
+    is_sending_email: bool,
+    is_connecting_calendar: bool,
+    connect_task: Option<tokio::task::JoinHandle<()>>, // Aborted by the Cancel button
+    is_fetching_slots: bool,
+    slot_fetch_id: u64, // The newest slot fetch; results from older ones are dropped
+    is_diagnosing: bool,
+    config_loaded: bool,
+    template_loaded: bool,
+    state_loaded_from_file: bool,
+    send_registry: SendRegistry, // Shared by every send task, so overlapping sends skip repeats
+    frame_seconds: Option<f32>,  // CPU time of the last frame, shown with --verbose
+}
+
+impl Default for SmtpSettings {
+    fn default() -> Self {
+        Self {
+            host: String::new(),
+            port_str: "587".to_string(),
+            user: String::new(),
+            password: SecretString::new("".to_string().into()),
+            from_email: String::new(),
+            tls_mode: SmtpTlsMode::default(),
+            proxy: None,
+            no_auth: false,
+            use_webhook: false,
+            webhook_url: String::new(),
+            server: None,
+            checking_server: false,
+        }
+    }
+}
+
+impl Default for ComposeState {
+    fn default() -> Self {
+        Self {
+            sender_name: String::new(),
+            template_source: "email_template.txt".to_string(), // Default path
+            subject: "Coffee Chat Invitation".to_string(),      // Default subject
+            body: "Hi {{recipient_name}},\n\nWould you be available for a brief coffee chat sometime soon?\n\nMy availability:\n{{availabilities}}\n\nBest,\n{{sender_name}}".to_string(), // Default body
+            cc: String::new(),
+            bcc: String::new(),
+            archive_bcc: String::new(),
+            no_slots_text: DEFAULT_NO_SLOTS_TEXT.to_string(),
+            drip_enabled: false,
+            drip_batch_size: DEFAULT_DRIP_BATCH_SIZE,
+            drip_interval_days: 1,
+            send_cooldown_minutes: registry::DEFAULT_SEND_COOLDOWN_MINUTES,
+            abort_after_failures: DEFAULT_ABORT_AFTER_FAILURES,
+            priority: Priority::Normal,
+            campaign: String::new(),
+            campaign_rename: None,
+            recipients: Vec::new(),
+            new_recipient_name: String::new(),
+            new_recipient_email: String::new(),
+            new_recipient_fields: String::new(),
+            confirm_remove_above: DEFAULT_CONFIRM_REMOVE_ABOVE,
+            confirming_remove: None,
+            last_removed: None,
+            recipient_search: Default::default(),
+            field_check: Default::default(),
+            skip_send_confirmation: false,
+            confirming_send: false,
+            role_accounts: RoleAccounts::default(),
+            exclude_role_accounts: false,
+            readability_warning: None,
+            spam_check: true,
+            spam_test_address: String::new(),
+            spam_report: None,
+            spam_test: None,
+            send_results: Vec::new(),
+            send_eta: None,
+            time_per_email: None,
+            quick_send: QuickSendState::default(),
+            reply_lookup: ReplyLookup::default(),
+            history: Vec::new(),
+            missing_template: None,
+        }
+    }
+}
+
+impl Default for CalendarSettings {
+    fn default() -> Self {
+        Self {
+            credentials_path: "credentials.json".to_string(),
+            token_cache_path: "tokencache.json".to_string(),
+            buffer_before_minutes: Minutes(15),
+            buffer_after_minutes: Minutes(15),
+            buffer_longer_than: None,
+            day_start_hour: HourOfDay::new(9).unwrap(),
+            day_end_hour: HourOfDay::new(17).unwrap(),
+            today_cutoff_hour: None,
+            slot_min_gap_minutes: 0,
+            max_slots_per_day: 0,
+            max_slots_in_email: calendar::DEFAULT_MAX_SLOTS_IN_EMAIL,
+            readable_slot_lines: readability::DEFAULT_MAX_SLOT_LINES,
+            show_unavailable_days: false,
+            pad_range_edges: false,
+            blackout_dates: Vec::new(),
+            reverse_slot_order: false,
+            label_timezone: false,
+            slots_shown_in_list: DEFAULT_SLOTS_SHOWN_IN_LIST,
+            weekly_rules: WeeklyRules::default(),
+            weekly_rules_enabled: false,
+            weekdays: Weekdays::default(),
+            lookahead_days: calendar::DEFAULT_LOOKAHEAD_DAYS,
+            slot_calendar_ids: Vec::new(),
+        }
+    }
+}
+
+impl Default for CalendarState {
+    fn default() -> Self {
+        Self {
+            source: None,
+            status: "Calendar: Not Connected".to_string(),
+            error: None,
+            proxy: None,
+            show_error_details: false,
+            available_slots: Vec::new(),
+            last_fetch: None,
+            unavailable_days: Vec::new(),
+            pasted_availability: String::new(),
+            common_slots: None,
+            slots_restored: false,
+            diagnosis: None,
+            calendars: None,
+            zone_change: None,
+            zone_checked_at: None,
+            blackout_pick: None,
+            weekly_rule_drafts: Default::default(),
+            show_all_slots: false,
+            slot_notes: BTreeMap::new(),
+            editing_note: None,
+        }
+    }
+}
+
+impl BackgroundState {
+    fn new(sender: mpsc::Sender<Message>, receiver: mpsc::Receiver<Message>) -> Self {
+        Self {
+            tokio_rt: None,
+            receiver,
+            sender,
+            is_sending_email: false,
+            is_connecting_calendar: false,
+            connect_task: None,
+            is_fetching_slots: false,
+            slot_fetch_id: 0,
+            is_diagnosing: false,
+            config_loaded: false,          // Not processed yet
+            template_loaded: false,        // Not processed yet
+            state_loaded_from_file: false, // Initialize flag to false
+            send_registry: SendRegistry::default(),
+            frame_seconds: None,
+        }
+    }
+}
+
+// --- Default Implementation ---
+impl Default for MyApp {
+    fn default() -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        // Set initial default values for the struct fields
+        Self {
+            smtp: SmtpSettings::default(),
+            compose: ComposeState::default(),
+            calendar_settings: CalendarSettings::default(),
+            calendar: CalendarState::default(),
+            background: BackgroundState::new(sender, receiver),
+            status_message: "Initializing...".to_string(), // Changed initial message
+            show_setup_checklist: false,
+            setup_checklist_dismissed: false,
+            demo_mode: false,
+            prefer_config_file: false,
+            config_sources: Default::default(),
+            show_effective_config: false,
+            save: SaveState::default(),
+            backup: BackupState::default(),
+            availability_page: AvailabilityPageState::default(),
+            drip: DripState::default(),
+            freeze: Default::default(),
+            booking: BookingState::default(),
+            follow_up: FollowUpState::default(),
+            hooks: HookState::default(),
+            control: Default::default(),
+            safe_mode: Default::default(),
+            reset_data: Default::default(),
+        }
+    }
+}
+
+// --- MyApp Implementation ---
+impl MyApp {
+    // --- Constructor `new` with Theme Fixes ---
+    pub fn new(cc: &eframe::CreationContext<'_>, mode: StartMode) -> Self {
+        Self::configure_visuals(cc);
+        let mut app = Self::default();
+        app.record_config_source(effective_config::ConfigSource::Default, None);
+        match mode {
+            StartMode::Demo => {
+                tasks::spawn_initial_load(app.background.sender.clone());
+                app.enter_demo_mode();
+            }
+            // Nothing in the background, not even the runtime, until it's left
+            StartMode::Safe => {
+                app.enter_safe_mode("--safe-mode");
+                return app;
+            }
+            StartMode::Normal => app.safe_mode.start_pending = true,
+        }
+        app.ensure_runtime();
+        info!("Tokio runtime ensured.");
+        app
+    }
+
+    // (ensure_runtime remains the same)
+    fn ensure_runtime(&mut self) -> &Runtime {
+        self.background.tokio_rt.get_or_insert_with(|| {
+            info!("Creating Tokio runtime.");
+            tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime")
+        })
+    }
+
+    fn current_slot_settings(&self) -> SlotSettings {
+        SlotSettings {
+            buffer_minutes: self.calendar_settings.buffer_before_minutes,
+            buffer_after_minutes: Some(self.calendar_settings.buffer_after_minutes),
+            start_hour: self.calendar_settings.day_start_hour,
+            end_hour: self.calendar_settings.day_end_hour,
+            today_cutoff_hour: self.calendar_settings.today_cutoff_hour,
+            min_gap_minutes: self.calendar_settings.slot_min_gap_minutes,
+            max_per_day: self.calendar_settings.max_slots_per_day,
+            pad_range_edges: self.calendar_settings.pad_range_edges,
+            blackout_dates: self.calendar_settings.blackout_dates.clone(),
+            buffer_longer_than: self.calendar_settings.buffer_longer_than,
+            weekly_rules: self
+                .calendar_settings
+                .weekly_rules_enabled
+                .then(|| self.calendar_settings.weekly_rules.clone()),
+            lookahead_days: self.calendar_settings.lookahead_days,
+            weekdays: self.calendar_settings.weekdays,
+            calendar_ids: self.slot_calendar_ids(),
+        }
+    }
+
+    /// The chosen calendars to read slots from, or none for the primary one alone.
+    /// Choices that aren't on the connected account's list are dropped.
+    fn slot_calendar_ids(&self) -> Vec<String> {
+        let chosen = &self.calendar_settings.slot_calendar_ids;
+        let Some(Ok(calendars)) = &self.calendar.calendars else {
+            return chosen.clone(); // Not listed yet; the query itself reports a bad id
+        };
+        let ids: Vec<String> = chosen
+            .iter()
+            .filter(|id| calendars.iter().any(|info| info.id == **id))
+            .cloned()
+            .collect();
+        // The primary on its own is what no choice reads too; keep the two equal
+        let primary_only = matches!(ids.as_slice(),
+            [id] if calendars.iter().any(|info| info.primary && info.id == *id));
+        if primary_only {
+            Vec::new()
+        } else {
+            ids
+        }
+    }
+
+    /// Summarizes a fetch result into `available_slots`, dropping windows that already ended.
+    fn apply_fetched_slots(&mut self, mut fetched: FetchedSlots) {
+        let now = Utc::now();
+        fetched.windows.retain(|&(_, end)| end > now);
+        for window in &mut fetched.windows {
+            window.0 = window.0.max(now);
+        }
+        self.calendar.last_fetch = Some(fetched);
+        self.summarize_last_fetch();
+        // Notes for times that are no longer offered would never be seen again
+        let slots = &self.calendar.available_slots;
+        self.calendar
+            .slot_notes
+            .retain(|slot, _| slots.contains(slot));
+        self.calendar.slots_restored = false;
+        self.calendar.zone_change = None;
+    }
+
+    /// Days in the last fetch's lookahead with no slots at all.
+    fn fetched_unavailable_days(fetched: &FetchedSlots) -> Vec<NaiveDate> {
+        calendar::free_busy::unavailable_days(
+            &fetched.windows,
+            Duration::minutes(calendar::MIN_SLOT_MINUTES),
+            fetched.fetched_at,
+            fetched.range_end(),
+        )
+    }
+
+    fn slot_order(&self) -> calendar::free_busy::SlotOrder {
+        if self.calendar_settings.reverse_slot_order {
+            calendar::free_busy::SlotOrder::LatestFirst
+        } else {
+            calendar::free_busy::SlotOrder::EarliestFirst
+        }
+    }
+
+    /// Rebuilds `available_slots` and `unavailable_days` from `last_fetch`.
+    fn summarize_last_fetch(&mut self) {
+        let Some(fetched) = &self.calendar.last_fetch else {
+            return;
+        };
+        let min_len = Duration::minutes(calendar::MIN_SLOT_MINUTES);
+        let unavailable = Self::fetched_unavailable_days(fetched);
+        let marked: &[NaiveDate] = if self.calendar_settings.show_unavailable_days {
+            &unavailable
+        } else {
+            &[]
+        };
+        let order = self.slot_order();
+        self.calendar.available_slots = calendar::free_busy::summarize_slots_marking_unavailable(
+            &fetched.windows,
+            min_len,
+            marked,
+            order,
+        );
+        self.calendar.unavailable_days = unavailable
+            .into_iter()
+            .map(calendar::format::format_day)
+            .collect();
+        info!(
+            "Summarized to {} displayable slots ({} days without availability).",
+            self.calendar.available_slots.len(),
+            self.calendar.unavailable_days.len()
+        );
+    }
+
+    /// Intersects the pasted availability with the last fetched windows.
+    fn handle_find_common_time(&mut self) {
+        let Some(fetched) = &self.calendar.last_fetch else {
+            return;
+        };
+        let now = Utc::now();
+        self.calendar.common_slots = Some(
+            calendar::pasted::parse_pasted_availability(&self.calendar.pasted_availability, now)
+                .map(|theirs| {
+                    let common = calendar::free_busy::intersect_windows(&fetched.windows, &theirs);
+                    info!("Found {} common windows.", common.len());
+                    calendar::free_busy::summarize_slots(
+                        &common,
+                        Duration::minutes(calendar::MIN_SLOT_MINUTES),
+                    )
+                })
+                .map_err(|e| e.to_string()),
+        );
+    }
+
+    fn clear_slots(&mut self) {
+        self.calendar.available_slots.clear();
+        self.calendar.last_fetch = None;
+        self.calendar.unavailable_days.clear();
+        self.calendar.common_slots = None;
+        self.calendar.slots_restored = false;
+    }
+
+    /// Drops the slot list once the calendar settings it was computed under change.
+    fn invalidate_slots_on_settings_change(&mut self) {
+        let Some(fetched) = &self.calendar.last_fetch else {
+            return;
+        };
+        if self.background.is_fetching_slots || fetched.settings == self.current_slot_settings() {
+            return;
+        }
+        info!("Calendar settings changed since the last fetch; clearing slots.");
+        self.clear_slots();
+        self.status_message = "Calendar settings changed. Fetch slots again.".to_string();
+    }
+
+    /// Drops the slot list if the system timezone changed since it was fetched (travel).
+    /// Slot times and the daily hours filter were computed in the old zone.
+    fn invalidate_slots_on_zone_change(&mut self) {
+        if self
+            .calendar
+            .zone_checked_at
+            .is_some_and(|checked| checked.elapsed() < ZONE_CHECK_INTERVAL)
+        {
+            return;
+        }
+        self.calendar.zone_checked_at = Some(Instant::now());
+        let Some(fetched_zone) = self
+            .calendar
+            .last_fetch
+            .as_ref()
+            .and_then(|fetched| fetched.zone.clone())
+        else {
+            return;
+        };
+        let current = LocalZone::current();
+        if self.background.is_fetching_slots || !fetched_zone.differs_from(&current) {
+            return;
+        }
+        info!(
+            "Timezone changed from {} to {}; clearing slots.",
+            fetched_zone.label(),
+            current.label()
+        );
+        self.clear_slots();
+        self.calendar.zone_change = Some(format!(
+            "Timezone changed from {} to {}. Fetch slots again.",
+            fetched_zone.label(),
+            current.label()
+        ));
+    }
+
+    /// Rows the Available Slots list shows before it's expanded.
+    fn slot_list_limit(&self) -> usize {
+        match self.calendar_settings.slots_shown_in_list {
+            0 => usize::MAX,
+            limit => limit as usize,
+        }
+    }
+
+    /// The part of the slot list on screen. Sending always uses every slot.
+    fn visible_slots(&self) -> &[String] {
+        let slots = &self.calendar.available_slots;
+        if self.calendar.show_all_slots {
+            return slots;
+        }
+        &slots[..slots.len().min(self.slot_list_limit())]
+    }
+
+    /// Warning shown next to the slot list and when sending, if the slots may be out of date.
+    fn slot_staleness_warning(&self) -> Option<String> {
+        // A frozen list is old on purpose; `frozen_busy_warning` covers it
+        if self.freeze.frozen.is_some() {
+            return None;
+        }
+        let fetched = self.calendar.last_fetch.as_ref()?;
+        let age = Utc::now() - fetched.fetched_at;
+        let age_text = if age.num_hours() >= 1 {
+            format!("{} h {} min", age.num_hours(), age.num_minutes() % 60)
+        } else {
+            format!("{} min", age.num_minutes())
+        };
+        if self.calendar.slots_restored {
+            Some(format!(
+                "Restored from last session, fetched {} ago",
+                age_text
+            ))
+        } else if age.num_minutes() >= SLOTS_STALE_AFTER_MINUTES {
+            Some(format!("Fetched {} ago and may be out of date", age_text))
+        } else {
+            None
+        }
+    }
+}
+
+// --- App::update Implementation ---
+impl eframe::App for MyApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.save.discard_on_exit {
+            info!("Exiting without saving, as asked.");
+            return;
+        }
+        self.save_state();
+    }
+
+    // FIX: Update margin calls
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.background.frame_seconds = frame.info().cpu_usage;
+        self.start_if_pending(ctx.input(|i| i.modifiers.shift));
+        if ctx.input_mut(|i| {
+            i.consume_key(
+                egui::Modifiers::COMMAND | egui::Modifiers::SHIFT,
+                egui::Key::D,
+            )
+        }) {
+            self.toggle_demo_mode();
+        }
+        let connect_finished = self.connect_task_finished();
+        self.process_messages();
+        self.start_control_api(ctx);
+        self.reap_connect_task(connect_finished);
+        self.invalidate_slots_on_settings_change();
+        self.invalidate_slots_on_zone_change();
+        self.dispatch_due_drip_batch();
+        self.ui_layout(ctx);
+        self.note_startup_progress();
+        self.autosave_if_due();
+        let was_dirty = self.save.dirty;
+        self.refresh_dirty_flag();
+        if self.save.dirty != was_dirty {
+            let marker = if self.save.dirty { " *" } else { "" };
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(format!(
+                "{}{}",
+                APP_TITLE, marker
+            )));
+        }
+        if ctx.input(|i| i.viewport().close_requested())
+            && self.save.dirty
+            && !self.save.exit_confirmed
+        {
+            ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+            self.save.confirming_exit = true;
+        }
+
+        if self.background.is_sending_email
+            || self.background.is_connecting_calendar
+            || self.background.is_fetching_slots
+            || self.background.is_diagnosing
+        {
+            ctx.request_repaint_after(std::time::Duration::from_millis(100));
+        } else if self.calendar.last_fetch.is_some() {
+            // Wake up now and then so a timezone change is noticed while idle
+            ctx.request_repaint_after(ZONE_CHECK_INTERVAL);
+        }
+        if let Some(interval) = self.save.autosave_interval() {
+            // Edits made just before going idle still get saved on time
+            ctx.request_repaint_after(interval);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(id: &str, primary: bool) -> CalendarInfo {
+        CalendarInfo {
+            id: id.to_string(),
+            label: id.to_string(),
+            color: None,
+            primary,
+        }
+    }
+
+    #[test]
+    fn slot_calendars_fall_back_to_the_primary() {
+        let mut app = MyApp::default();
+        let ids = |ids: &[&str]| ids.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+        app.calendar_settings.slot_calendar_ids = ids(&["me@example.com", "team@group.example"]);
+        // Not listed yet: taken as chosen, and the query reports a bad one
+        assert_eq!(
+            app.slot_calendar_ids(),
+            ids(&["me@example.com", "team@group.example"])
+        );
+        app.calendar.calendars = Some(Ok(vec![
+            calendar("me@example.com", true),
+            calendar("team@group.example", false),
+        ]));
+        assert_eq!(
+            app.slot_calendar_ids(),
+            ids(&["me@example.com", "team@group.example"])
+        );
+        // A calendar no longer on the account is dropped, leaving the primary alone,
+        // which reads the same as no choice
+        app.calendar.calendars = Some(Ok(vec![calendar("me@example.com", true)]));
+        assert_eq!(app.slot_calendar_ids(), Vec::<String>::new());
+        // Nothing chosen is on this account at all
+        app.calendar.calendars = Some(Ok(vec![calendar("other@example.com", true)]));
+        assert_eq!(app.slot_calendar_ids(), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,188 @@
+// src/app/quick_send.rs
+// Quick send: one personalized email to someone who isn't on the recipient
+// list, through the same transport and results list as a normal send.
+
+use super::messages::Message;
+use super::{MyApp, QuickSendState, SendOutcome, SendResult};
+use crate::config::Recipient;
+use crate::email_sender::post_process::NoopPostProcessor;
+use crate::email_sender::template::{self, EmailTemplate};
+use crate::email_sender::{prepare_email, send_prepared_email};
+use lettre::Address;
+use log::{error, info};
+use std::collections::BTreeMap;
+
+impl MyApp {
+    /// Opens the dialog with empty fields and a draft rendered from the current template.
+    pub(super) fn open_quick_send(&mut self) {
+        self.compose.quick_send = QuickSendState {
+            open: true,
+            ..QuickSendState::default()
+        };
+        self.render_quick_send_draft();
+    }
+
+    fn quick_send_recipient(&self) -> Recipient {
+        let quick_send = &self.compose.quick_send;
+        // Always set, so a template using {{ note }} renders without one too
+        let mut fields = BTreeMap::new();
+        fields.insert("note".to_string(), quick_send.note.trim().to_string());
+        Recipient {
+            name: quick_send.name.trim().to_string(),
+            email: quick_send.email.trim().to_string(),
+            fields,
+        }
+    }
+
+    /// Re-renders the draft from the compose template, replacing any hand edits.
+    pub(super) fn render_quick_send_draft(&mut self) {
+        let recipient = self.quick_send_recipient();
+        let availabilities = self.email_availabilities();
+        let rendered =
+            EmailTemplate::from_content(&self.compose.subject, &self.compose.body, "quick_send")
+                .and_then(|template| template.with_copies(&self.compose.cc, &self.compose.bcc))
+                .map(|template| {
+                    template
+                        .with_unavailable_days(self.email_unavailable_days())
+                        .with_no_slots_text(&self.compose.no_slots_text)
+                        .with_priority(self.compose.priority)
+                        .with_availability_link(&self.availability_link())
+                        .with_archive_bcc(&self.compose.archive_bcc)
+                        .with_offered_slots(self.offered_slots())
+                })
+                .map_err(|e| e.to_string())
+                .and_then(|template| {
+                    prepare_email(
+                        &recipient,
+                        &self.compose.sender_name,
+                        &availabilities,
+                        &template,
+                        &NoopPostProcessor,
+                    )
+                    .map_err(|e| e.to_string())
+                });
+        let slots = self.recorded_slots(&availabilities);
+        let quick_send = &mut self.compose.quick_send;
+        quick_send.edited = false;
+        quick_send.options = template::slot_options(&availabilities);
+        quick_send.slots = slots;
+        match rendered {
+            Ok(mut draft) => {
+                if !mentions_note(&self.compose.body) {
+                    draft.body = insert_note(&draft.body, &quick_send.note);
+                }
+                quick_send.draft = draft;
+                quick_send.render_error = None;
+            }
+            Err(e) => quick_send.render_error = Some(e),
+        }
+    }
+
+    /// Sends the draft as it stands. The recipient list is left alone.
+    pub(super) fn handle_quick_send(&mut self) {
+        if let Some(problem) = quick_send_address_problem(&self.compose.quick_send.email) {
+            self.compose.quick_send.send_error = Some(problem);
+            return;
+        }
+        let Some(transport) = self.transport_for_send() else {
+            return;
+        };
+        let recipient = self.quick_send_recipient();
+        let claim = match self
+            .background
+            .send_registry
+            .claim(recipient.key(), self.send_cooldown())
+        {
+            Ok(claim) => claim,
+            Err(recent) => {
+                info!("Quick send to {} skipped: {}", recipient.email, recent);
+                self.compose.quick_send.send_error = Some(recent.to_string());
+                self.compose.send_results.push(SendResult {
+                    email: recipient.email,
+                    outcome: SendOutcome::Skipped(recent.to_string()),
+                    one_off: true,
+                    options: Vec::new(),
+                });
+                return;
+            }
+        };
+        let draft = self.compose.quick_send.draft.clone();
+        let sender_name = self.compose.sender_name.clone();
+        self.compose.quick_send.send_error = None;
+        self.background.is_sending_email = true;
+        self.status_message = format!("Sending to {}...", recipient.email);
+        let rt = self.ensure_runtime().handle().clone();
+        let sender_clone = self.background.sender.clone();
+        rt.spawn(async move {
+            info!("Starting quick send to {}.", recipient.email);
+            let result = send_prepared_email(&transport, &recipient, &sender_name, &draft).await;
+            match &result {
+                Ok(_) => claim.sent(),
+                Err(e) => error!("Quick send to {} failed: {}", recipient.email, e),
+            }
+            sender_clone
+                .send(Message::QuickSendFinished(
+                    recipient.email,
+                    result.map_err(|e| e.to_string()),
+                ))
+                .ok();
+        });
+    }
+}
+
+/// Why the typed address can't be sent to, if it can't.
+pub(super) fn quick_send_address_problem(email: &str) -> Option<String> {
+    let email = email.trim();
+    if email.is_empty() {
+        return Some("Enter an email address.".to_string());
+    }
+    email
+        .parse::<Address>()
+        .err()
+        .map(|e| format!("Not a valid address: {}", e))
+}
+
+/// Whether the template places the note itself with `{{ note }}`.
+fn mentions_note(template: &str) -> bool {
+    template.split("{{").skip(1).any(|tag| {
+        tag.trim_start()
+            .strip_prefix("note")
+            .is_some_and(|rest| !rest.starts_with(|c: char| c.is_alphanumeric() || c == '_'))
+    })
+}
+
+/// Puts the note in its own paragraph after the greeting (the first paragraph).
+fn insert_note(body: &str, note: &str) -> String {
+    let note = note.trim();
+    if note.is_empty() {
+        return body.to_string();
+    }
+    match body.split_once("\n\n") {
+        Some((greeting, rest)) => format!("{}\n\n{}\n\n{}", greeting, note, rest),
+        None => format!("{}\n\n{}", body.trim_end(), note),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn note_goes_after_the_greeting() {
+        let body = "Hi Ana,\n\nWould you be free?\n\nBest,\nMe";
+        assert_eq!(
+            insert_note(body, " Great talk yesterday! "),
+            "Hi Ana,\n\nGreat talk yesterday!\n\nWould you be free?\n\nBest,\nMe"
+        );
+        assert_eq!(insert_note("One line", "Note"), "One line\n\nNote");
+        assert_eq!(insert_note(body, "  "), body);
+    }
+
+    #[test]
+    fn templates_that_place_the_note_are_detected() {
+        assert!(mentions_note("Hi,\n{{ note }}\nBye"));
+        assert!(mentions_note("{{note | default(value='')}}"));
+        assert!(!mentions_note("{{ notes }} and a note"));
+        assert!(!mentions_note("Hi {{ recipient_name }}"));
+    }
+}
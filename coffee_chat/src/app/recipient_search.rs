@@ -0,0 +1,143 @@
+// src/app/recipient_search.rs
+// The search box over the recipient list. Lists run into the thousands, so the
+// lowercased text of each recipient is kept between frames and only rebuilt when
+// the list changes, and the matches only when the list or the query does.
+
+use super::UIRecipient;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+#[derive(Default)]
+pub(super) struct RecipientSearch {
+    pub query: String,
+    indexed: Option<u64>, // Fingerprint of the list `lowered` was built from
+    lowered: Vec<String>, // Name, email and field values of each recipient, lowercased
+    matched: Option<(u64, String)>, // List fingerprint and query `matches` is for
+    matches: Vec<usize>,
+}
+
+impl RecipientSearch {
+    /// Whether the list is narrowed down; reordering is off while it is.
+    pub fn is_filtering(&self) -> bool {
+        !self.query.trim().is_empty()
+    }
+
+    /// Positions in `recipients` of everyone matching the query, in list order:
+    /// everyone for an empty query. A recipient matches when every word of the
+    /// query is somewhere in their name, email or fields.
+    pub fn matches(&mut self, recipients: &[UIRecipient]) -> &[usize] {
+        let fingerprint = fingerprint(recipients);
+        let query = self.query.trim().to_lowercase();
+        if self
+            .matched
+            .as_ref()
+            .is_some_and(|(list, matched)| *list == fingerprint && *matched == query)
+        {
+            return &self.matches;
+        }
+        self.matches = if query.is_empty() {
+            (0..recipients.len()).collect()
+        } else {
+            if self.indexed != Some(fingerprint) {
+                self.lowered = recipients.iter().map(searchable_text).collect();
+                self.indexed = Some(fingerprint);
+            }
+            let words: Vec<&str> = query.split_whitespace().collect();
+            self.lowered
+                .iter()
+                .enumerate()
+                .filter(|(_, text)| words.iter().all(|word| text.contains(word)))
+                .map(|(index, _)| index)
+                .collect()
+        };
+        self.matched = Some((fingerprint, query));
+        &self.matches
+    }
+}
+
+fn searchable_text(recipient: &UIRecipient) -> String {
+    let mut text = format!("{} {}", recipient.name, recipient.email);
+    for (key, value) in &recipient.fields {
+        text.push_str(&format!(" {}={}", key, value));
+    }
+    text.to_lowercase()
+}
+
+/// Cheap next to lowercasing every recipient, and changes with any edit that
+/// matters to the search: adding, removing, reordering or renaming.
+pub(super) fn fingerprint(recipients: &[UIRecipient]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    recipients.len().hash(&mut hasher);
+    for recipient in recipients {
+        recipient.name.hash(&mut hasher);
+        recipient.email.hash(&mut hasher);
+        recipient.fields.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn synthetic(count: usize) -> Vec<UIRecipient> {
+        (0..count)
+            .map(|i| UIRecipient {
+                name: format!("Person {}", i),
+                email: format!("person{}@{}.example", i, ["Acme", "Globex"][i % 2]),
+                fields: BTreeMap::from([("team".to_string(), format!("team-{}", i % 7))]),
+                pinned: false,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_word_has_to_match() {
+        let recipients = synthetic(20);
+        let mut search = RecipientSearch::default();
+        assert_eq!(search.matches(&recipients).len(), 20);
+        search.query = "  GLOBEX team-3 ".to_string();
+        // Odd positions are at Globex; team-3 is i % 7 == 3
+        assert_eq!(search.matches(&recipients), [3, 17]);
+        search.query = "nobody".to_string();
+        assert!(search.matches(&recipients).is_empty());
+    }
+
+    #[test]
+    fn edits_to_the_list_refresh_the_matches() {
+        let mut recipients = synthetic(5);
+        let mut search = RecipientSearch {
+            query: "person 4".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(search.matches(&recipients), [4]);
+        recipients.swap(0, 4);
+        assert_eq!(search.matches(&recipients), [0]);
+        recipients[2].name = "Person 4b".to_string();
+        assert_eq!(search.matches(&recipients), [0, 2]);
+        recipients.remove(0);
+        assert_eq!(search.matches(&recipients), [1]);
+    }
+
+    #[test]
+    fn five_thousand_recipients_are_indexed_once() {
+        let recipients = synthetic(5_000);
+        let mut search = RecipientSearch {
+            query: "acme team-2".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(search.matches(&recipients).len(), 357);
+        // Emptied behind its back, so a frame that reindexed or refiltered would show
+        let index = std::mem::take(&mut search.lowered);
+        for _ in 0..60 {
+            assert_eq!(search.matches(&recipients).len(), 357);
+        }
+        // A new query filters again, but over the same index
+        search.query = "acme".to_string();
+        assert!(search.matches(&recipients).is_empty());
+        search.lowered = index;
+        search.query = "globex".to_string();
+        assert_eq!(search.matches(&recipients).len(), 2_500);
+    }
+}
@@ -0,0 +1,225 @@
+// src/app/reset_data.rs
+// "Reset all data…" and `--reset-data`: deletes every file the app has written,
+// for starting over or handing the computer on. config.toml, the template and
+// credentials.json are the user's own files and are left alone.
+
+use super::MyApp;
+use eframe::egui;
+use log::{info, warn};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// What typing confirms the reset, in the dialog and on the command line.
+pub const RESET_CONFIRMATION: &str = "RESET";
+
+#[derive(Default)]
+pub(super) struct ResetDataState {
+    pub open: bool,
+    pub typed: String, // Has to be RESET_CONFIRMATION before anything is deleted
+    pub outcomes: Vec<(String, Result<String, String>)>, // Per file, once it ran
+    pub wiped: bool,   // Ran; nothing is saved again before the restart
+}
+
+/// One file the app wrote.
+pub struct DataFile {
+    pub label: &'static str,
+    pub path: Result<PathBuf, String>,
+}
+
+/// Every file the app has written, with the backups safe mode made of them. Paths
+/// come from the same functions that read and write the files.
+pub fn data_files(token_cache: &str) -> Vec<DataFile> {
+    let mut files = vec![
+        DataFile {
+            label: "Session and send history",
+            path: MyApp::state_file_path(),
+        },
+        DataFile {
+            label: "Drip schedule",
+            path: crate::drip::schedule_path(),
+        },
+        DataFile {
+            label: "Frozen slots",
+            path: crate::frozen_slots::frozen_slots_path(),
+        },
+        DataFile {
+            label: "Control API token",
+            path: crate::control::endpoint_path(),
+        },
+        DataFile {
+            label: "Google sign-in",
+            path: std::path::absolute(token_cache).map_err(|e| e.to_string()),
+        },
+    ];
+    let backups: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|file| file.path.as_ref().ok())
+        .flat_map(|path| safe_mode_backups(path))
+        .collect();
+    files.extend(backups.into_iter().map(|path| DataFile {
+        label: "Safe mode backup",
+        path: Ok(path),
+    }));
+    files
+}
+
+// "app_state.json.20250408-091500.bak" and the like, next to `path`
+fn safe_mode_backups(path: &Path) -> Vec<PathBuf> {
+    let (Some(folder), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let Ok(entries) = fs::read_dir(folder) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|backup| {
+            backup.file_name().is_some_and(|backup| {
+                let backup = backup.to_string_lossy();
+                backup.starts_with(&prefix) && backup.ends_with(".bak")
+            })
+        })
+        .collect();
+    backups.sort();
+    backups
+}
+
+/// Deletes each file, carrying on past failures. Returns what happened to each one,
+/// by label and path, then to the app's data folder once it's empty.
+pub fn delete_data_files(files: &[DataFile]) -> Vec<(String, Result<String, String>)> {
+    let mut outcomes: Vec<(String, Result<String, String>)> = files
+        .iter()
+        .map(|file| {
+            let outcome = file.path.clone().and_then(|path| delete_file(&path));
+            match &outcome {
+                Ok(done) => info!("{}: {}", file.label, done),
+                Err(e) => warn!("{}: {}", file.label, e),
+            }
+            (file.label.to_string(), outcome)
+        })
+        .collect();
+    if let Some(folder) = files
+        .first()
+        .and_then(|file| file.path.as_ref().ok())
+        .and_then(|path| path.parent())
+    {
+        let outcome = match fs::remove_dir(folder) {
+            Ok(()) => Ok(format!("Deleted {}", folder.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok("Not there".to_string()),
+            // Something else lives there; it isn't ours to delete
+            Err(_) => Ok(format!("Kept {}: other files are in it", folder.display())),
+        };
+        outcomes.push(("App data folder".to_string(), outcome));
+    }
+    outcomes
+}
+
+impl MyApp {
+    /// Deletes everything, then restarts the app so it comes up as on first run. If
+    /// a file couldn't be deleted, the dialog stays open to say which.
+    pub(super) fn handle_reset_data(&mut self, ctx: &egui::Context) {
+        let files = data_files(&self.calendar_settings.token_cache_path);
+        let outcomes = delete_data_files(&files);
+        self.reset_data.wiped = true;
+        self.reset_data.typed.clear();
+        let failed = outcomes
+            .iter()
+            .filter(|(_, outcome)| outcome.is_err())
+            .count();
+        self.reset_data.outcomes = outcomes;
+        if failed == 0 {
+            self.restart(ctx);
+        } else {
+            self.status_message = format!(
+                "Reset: {} file{} couldn't be deleted.",
+                failed,
+                if failed == 1 { "" } else { "s" }
+            );
+        }
+    }
+
+    /// Starts a new copy of the app and closes this one without saving.
+    pub(super) fn restart(&mut self, ctx: &egui::Context) {
+        let started = std::env::current_exe()
+            .and_then(|exe| std::process::Command::new(exe).spawn())
+            .map_err(|e| e.to_string());
+        if let Err(e) = started {
+            warn!("Couldn't restart: {}", e);
+            self.status_message =
+                format!("Couldn't restart ({}). Close and open the app again.", e);
+            return;
+        }
+        info!("Restarting with first-run defaults.");
+        self.save.discard_on_exit = true;
+        self.save.exit_confirmed = true;
+        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+    }
+}
+
+fn delete_file(path: &Path) -> Result<String, String> {
+    match fs::remove_file(path) {
+        Ok(()) => Ok(format!("Deleted {}", path.display())),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok("Not there".to_string()),
+        Err(e) => Err(format!("Couldn't delete {}: {}", path.display(), e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn each_file_is_deleted_or_reported_on_its_own() {
+        let dir = std::env::temp_dir().join(format!("coffee_chat_reset_{}", std::process::id()));
+        fs::create_dir_all(dir.join("in_use")).unwrap();
+        let state = dir.join("app_state.json");
+        fs::write(&state, "{}").unwrap();
+        fs::write(dir.join("app_state.json.20250408-091500.bak"), "{}").unwrap();
+        fs::write(dir.join("app_state.json.txt"), "not a backup").unwrap();
+        let files = vec![
+            DataFile {
+                label: "Session",
+                path: Ok(state.clone()),
+            },
+            DataFile {
+                label: "Missing",
+                path: Ok(dir.join("drip_schedule.json")),
+            },
+            DataFile {
+                label: "Folder in the way",
+                path: Ok(dir.join("in_use")),
+            },
+            DataFile {
+                label: "Unknown",
+                path: Err("No home folder".to_string()),
+            },
+        ];
+        assert_eq!(
+            safe_mode_backups(&state),
+            vec![dir.join("app_state.json.20250408-091500.bak")]
+        );
+
+        let outcomes = delete_data_files(&files);
+        let labels: Vec<&str> = outcomes.iter().map(|(label, _)| label.as_str()).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "Session",
+                "Missing",
+                "Folder in the way",
+                "Unknown",
+                "App data folder"
+            ]
+        );
+        assert!(outcomes[0].1.is_ok() && !state.exists());
+        assert_eq!(outcomes[1].1, Ok("Not there".to_string()));
+        assert!(outcomes[2].1.is_err());
+        assert_eq!(outcomes[3].1, Err("No home folder".to_string()));
+        // The backup and the other files are still there, so the folder stays
+        assert!(outcomes[4].1.as_ref().unwrap().starts_with("Kept"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
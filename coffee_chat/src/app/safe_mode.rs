@@ -0,0 +1,206 @@
+// src/app/safe_mode.rs
+// Starting without the saved session or config.toml (`--safe-mode`, or Shift held
+// at launch), for when one of them stops the app from starting at all. The files
+// are listed with buttons to back them up or move them aside.
+
+use super::MyApp;
+use crate::drip;
+use chrono::Local;
+use log::{info, warn};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set once the first frame is drawn and config.toml and the template have loaded
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+/// How the GUI was asked to start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StartMode {
+    Normal,
+    Demo, // Sample data, nothing is sent
+    Safe, // Nothing loaded, nothing saved
+}
+
+/// Whether startup got past loading the saved session, config.toml and the template.
+/// A panic before then is most likely a bad file, which safe mode gets around.
+pub fn has_started() -> bool {
+    STARTED.load(Ordering::Relaxed)
+}
+
+#[derive(Default)]
+pub(super) struct SafeModeState {
+    pub active: bool,
+    pub open: bool,                                      // The recovery window
+    pub reason: &'static str,                            // "--safe-mode" or "Shift was held"
+    pub start_pending: bool,                             // Normal start, on the first frame
+    pub outcomes: Vec<(String, Result<String, String>)>, // Latest action per file label
+}
+
+/// One file that can stop the app from starting.
+pub(super) struct RecoveryFile {
+    pub label: &'static str,
+    pub path: Result<PathBuf, String>,
+}
+
+/// The files read at startup, in the order they're read.
+pub(super) fn recovery_files(token_cache: &str) -> Vec<RecoveryFile> {
+    vec![
+        RecoveryFile {
+            label: "Session and send history",
+            path: MyApp::state_file_path(),
+        },
+        RecoveryFile {
+            label: "Drip schedule",
+            path: drip::schedule_path(),
+        },
+        RecoveryFile {
+            label: "Frozen slots",
+            path: crate::frozen_slots::frozen_slots_path(),
+        },
+        RecoveryFile {
+            label: "config.toml",
+            path: std::path::absolute("config.toml").map_err(|e| e.to_string()),
+        },
+        RecoveryFile {
+            label: "Google sign-in",
+            path: std::path::absolute(token_cache).map_err(|e| e.to_string()),
+        },
+    ]
+}
+
+/// Where `backup_file` and `reset_file` put `path`: next to it, with the time added,
+/// and a number too if that's taken.
+fn backup_path(path: &Path) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let stamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let mut backup = path.with_file_name(format!("{}.{}.bak", name, stamp));
+    let mut number = 1;
+    while backup.exists() {
+        number += 1;
+        backup = path.with_file_name(format!("{}.{}-{}.bak", name, stamp, number));
+    }
+    backup
+}
+
+/// Copies `path` next to itself and returns where the copy went.
+pub(super) fn backup_file(path: &Path) -> io::Result<PathBuf> {
+    let backup = backup_path(path);
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// Moves `path` aside, so the next start goes without it, and returns where it went.
+pub(super) fn reset_file(path: &Path) -> io::Result<PathBuf> {
+    let backup = backup_path(path);
+    fs::rename(path, &backup)?;
+    Ok(backup)
+}
+
+impl MyApp {
+    /// Starts without loading anything. Saving is off too, so the files on disk stay
+    /// as they are until they're reset here or the app starts normally.
+    pub(super) fn enter_safe_mode(&mut self, reason: &'static str) {
+        warn!("Starting in safe mode ({}).", reason);
+        self.safe_mode.active = true;
+        self.safe_mode.open = true;
+        self.safe_mode.reason = reason;
+        self.status_message =
+            "Safe mode: the saved session and config.toml weren't loaded.".to_string();
+        // Nothing is loading, so sending only waits on what's typed in
+        self.background.config_loaded = true;
+        self.background.template_loaded = true;
+        STARTED.store(true, Ordering::Relaxed);
+    }
+
+    /// Loads config.toml, the template and the saved session: a normal start.
+    pub(super) fn start_normally(&mut self) {
+        super::tasks::spawn_initial_load(self.background.sender.clone());
+        self.load_state();
+    }
+
+    /// A normal start runs on the first frame, once Shift can be seen.
+    pub(super) fn start_if_pending(&mut self, shift_held: bool) {
+        if !self.safe_mode.start_pending {
+            return;
+        }
+        self.safe_mode.start_pending = false;
+        if shift_held {
+            self.enter_safe_mode("Shift was held at launch");
+        } else {
+            self.start_normally();
+        }
+    }
+
+    /// Leaves safe mode by loading everything it skipped. Anything changed in the
+    /// meantime is replaced by what's on disk.
+    pub(super) fn leave_safe_mode(&mut self) {
+        info!("Leaving safe mode.");
+        self.safe_mode = Default::default();
+        self.background.config_loaded = false;
+        self.background.template_loaded = false;
+        self.start_normally();
+        self.ensure_runtime();
+    }
+
+    /// Marks startup as done once the first frame is drawn and config.toml and the
+    /// template have been processed, whether or not they loaded.
+    pub(super) fn note_startup_progress(&self) {
+        if !has_started() && self.background.config_loaded && self.background.template_loaded {
+            STARTED.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Runs "Back up" or "Reset" on one recovery file and keeps the outcome to show.
+    pub(super) fn recover_file(&mut self, label: &str, path: &Path, reset: bool) {
+        let outcome = if reset {
+            reset_file(path).map(|moved| format!("Moved aside to {}", moved.display()))
+        } else {
+            backup_file(path).map(|copy| format!("Copied to {}", copy.display()))
+        }
+        .map_err(|e| e.to_string());
+        match &outcome {
+            Ok(done) => info!("{}: {}", label, done),
+            Err(e) => warn!("{} ({}): {}", label, path.display(), e),
+        }
+        self.safe_mode.outcomes.retain(|(done, _)| done != label);
+        self.safe_mode.outcomes.push((label.to_string(), outcome));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_and_resets_keep_the_file_next_to_it() {
+        let dir = std::env::temp_dir().join(format!("coffee_chat_safe_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let state = dir.join("app_state.json");
+        fs::write(&state, "{ not json").unwrap();
+
+        let copy = backup_file(&state).unwrap();
+        let copied = fs::read_to_string(&copy);
+        let kept = state.exists();
+        // Usually within the same second, so it needs a name of its own
+        let moved = reset_file(&state).unwrap();
+        let moved_contents = fs::read_to_string(&moved);
+        let gone = !state.exists();
+        let missing = reset_file(&state);
+        fs::remove_dir_all(&dir).ok();
+
+        assert_eq!(copied.unwrap(), "{ not json");
+        assert!(kept);
+        assert_eq!(moved.parent(), Some(dir.as_path()));
+        assert_ne!(moved, copy);
+        let name = moved.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("app_state.json.") && name.ends_with(".bak"));
+        assert_eq!(moved_contents.unwrap(), "{ not json");
+        assert!(gone);
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,128 @@
+// src/app/slot_freeze.rs
+// Freezing the slot list for a campaign. While frozen, every send offers the
+// frozen list; fetching keeps going, and only shows which frozen slots have been
+// booked since.
+
+use super::MyApp;
+use crate::frozen_slots::FrozenSlots;
+use chrono::{Local, Utc};
+use log::{error, info};
+
+#[derive(Default)]
+pub(super) struct FreezeState {
+    pub frozen: Option<FrozenSlots>, // Mirrors frozen_slots.json
+    pub label: String,               // For the next freeze
+    pub notice: Option<String>,      // Why frozen_slots.json couldn't be read
+}
+
+impl MyApp {
+    /// Reads frozen_slots.json. Demo mode starts unfrozen and keeps a freeze in memory.
+    pub(super) fn load_frozen_slots(&mut self) {
+        self.freeze = FreezeState::default();
+        if self.demo_mode {
+            return;
+        }
+        match FrozenSlots::load() {
+            Ok(frozen) => {
+                if let Some(frozen) = &frozen {
+                    info!(
+                        "Slots are frozen as \"{}\" ({} lines).",
+                        frozen.label,
+                        frozen.lines.len()
+                    );
+                }
+                self.freeze.frozen = frozen;
+            }
+            Err(e) => {
+                error!("{}", e);
+                self.freeze.notice = Some(e);
+            }
+        }
+    }
+
+    /// Snapshots the slots every email would offer right now.
+    pub(super) fn freeze_slots(&mut self) {
+        let lines = self.email_availabilities();
+        if lines.is_empty() {
+            self.status_message = "Nothing to freeze: fetch your slots first.".to_string();
+            return;
+        }
+        let label = match self.freeze.label.trim() {
+            "" => format!("Slots of {}", Local::now().format("%b %-d")),
+            label => label.to_string(),
+        };
+        let frozen = FrozenSlots {
+            label,
+            frozen_at: Utc::now(),
+            lines,
+            unavailable_days: self.calendar.unavailable_days.clone(),
+            offered: self.offered_slots(),
+        };
+        if !self.demo_mode {
+            if let Err(e) = frozen.save() {
+                error!("{}", e);
+                self.status_message = format!("Couldn't freeze the slots: {}", e);
+                return;
+            }
+        }
+        info!(
+            "Froze {} slot lines as \"{}\".",
+            frozen.lines.len(),
+            frozen.label
+        );
+        self.status_message = format!(
+            "Slots frozen as \"{}\". Every send offers them until you unfreeze.",
+            frozen.label
+        );
+        self.freeze.frozen = Some(frozen);
+        self.freeze.label.clear();
+    }
+
+    pub(super) fn unfreeze_slots(&mut self) {
+        if !self.demo_mode {
+            if let Err(e) = FrozenSlots::unfreeze() {
+                error!("{}", e);
+                self.status_message = format!("Couldn't unfreeze the slots: {}", e);
+                return;
+            }
+        }
+        info!("Slots unfrozen.");
+        self.freeze.frozen = None;
+        self.status_message = "Slots unfrozen. Sends offer your fetched slots again.".to_string();
+    }
+
+    /// Frozen slot lines the latest fetch no longer has free. Empty until there's
+    /// been a fetch since freezing.
+    pub(super) fn frozen_busy_lines(&self) -> Vec<String> {
+        let (Some(frozen), Some(fetched)) = (&self.freeze.frozen, &self.calendar.last_fetch) else {
+            return Vec::new();
+        };
+        if fetched.fetched_at <= frozen.frozen_at {
+            return Vec::new();
+        }
+        frozen.busy_lines(&fetched.windows)
+    }
+
+    /// The warning shown before a send about frozen slots booked since. They're still
+    /// offered: the freeze holds until it's lifted.
+    pub(super) fn frozen_busy_warning(&self) -> Option<String> {
+        let busy = self.frozen_busy_lines();
+        if busy.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "{} frozen slot{} now busy on your calendar, still offered: {}",
+            busy.len(),
+            if busy.len() == 1 { " is" } else { "s are" },
+            busy.join("; ")
+        ))
+    }
+
+    /// `{{ unavailable_days }}`: the frozen ones while frozen.
+    pub(super) fn email_unavailable_days(&self) -> Vec<String> {
+        match &self.freeze.frozen {
+            Some(frozen) => frozen.unavailable_days.clone(),
+            None => self.calendar.unavailable_days.clone(),
+        }
+    }
+}
@@ -0,0 +1,83 @@
+// src/app/spam_check.rs
+// The spam check shown in the send confirmation: the local heuristics, and an
+// optional test copy to a mail-tester style address for a real score.
+
+use super::messages::Message;
+use super::MyApp;
+use crate::config::Recipient;
+use crate::email_sender::post_process::NoopPostProcessor;
+use crate::email_sender::spam_check::{self, CheckedEmail, SpamReport};
+use crate::email_sender::{prepare_email, send_prepared_email, PreparedEmail};
+use log::{error, info};
+
+impl MyApp {
+    /// The first recipient's email as the batch would send it. Everyone gets the
+    /// same template, so it stands in for the rest.
+    fn first_send_email(&self) -> Option<(Recipient, PreparedEmail)> {
+        let (recipients, _) = self.recipients_for_send();
+        let recipient = recipients.into_iter().next()?;
+        let template = self
+            .compose_invitation()
+            .template(self.email_unavailable_days())
+            .ok()? // A broken template fails the send with its own message
+            .with_offered_slots(self.offered_slots());
+        let email = prepare_email(
+            &recipient,
+            &self.compose.sender_name,
+            &self.email_availabilities(),
+            &template,
+            &NoopPostProcessor,
+        )
+        .ok()?;
+        Some((recipient, email))
+    }
+
+    /// Scores the first email, or `None` with the check turned off.
+    pub(super) fn send_spam_report(&self) -> Option<SpamReport> {
+        if !self.compose.spam_check {
+            return None;
+        }
+        let (_, email) = self.first_send_email()?;
+        Some(spam_check::check(&CheckedEmail::prepared(&email)))
+    }
+
+    /// Sends the first email to the test address, without its CC and BCC so nobody
+    /// else gets it; the outcome arrives as `SpamTestSent`.
+    pub(super) fn send_spam_test(&mut self) {
+        let address = self.compose.spam_test_address.trim().to_string();
+        if !address.contains('@') {
+            self.compose.spam_test = Some(Err("Enter the test address first.".to_string()));
+            return;
+        }
+        let Some((recipient, mut email)) = self.first_send_email() else {
+            self.compose.spam_test = Some(Err("The email couldn't be rendered.".to_string()));
+            return;
+        };
+        let Some(transport) = self.transport_for_send() else {
+            return;
+        };
+        email.cc.clear();
+        email.bcc.clear();
+        let test_recipient = Recipient {
+            email: address,
+            ..recipient
+        };
+        self.background.is_sending_email = true;
+        self.compose.spam_test = None;
+        self.status_message = format!("Sending a test copy to {}...", test_recipient.email);
+        let sender_name = self.compose.sender_name.clone();
+        let sender = self.background.sender.clone();
+        let rt = self.ensure_runtime().handle().clone();
+        rt.spawn(async move {
+            info!("Sending a spam test copy to {}.", test_recipient.email);
+            let result = send_prepared_email(&transport, &test_recipient, &sender_name, &email)
+                .await
+                .map(|receipt| receipt.summary())
+                .map_err(|e| {
+                    error!("Spam test copy failed: {}", e);
+                    e.to_string()
+                });
+            sender.send(Message::SpamTestSent(result)).ok();
+        });
+    }
+}
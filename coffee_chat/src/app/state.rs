@@ -0,0 +1,1432 @@
+// src/app/state.rs
+// Saving and restoring the editable settings between sessions (app_state.json).
+
+use super::effective_config::ConfigSource;
+use super::{
+    FetchedSlots, FollowUpTemplates, MyApp, SlotSettings, UIRecipient, DEFAULT_AUTOSAVE_SECONDS,
+    DEFAULT_CONFIRM_REMOVE_ABOVE, DEFAULT_DRIP_BATCH_SIZE, DEFAULT_MEETING_MINUTES,
+    DEFAULT_NO_SLOTS_TEXT, DEFAULT_SLOTS_SHOWN_IN_LIST,
+};
+use crate::calendar;
+use crate::calendar::booking::BookedMeeting;
+use crate::calendar::free_busy::WeeklyRules;
+use crate::calendar::units::{HourOfDay, Minutes, Weekdays};
+use crate::email_sender::failure_streak::DEFAULT_ABORT_AFTER_FAILURES;
+use crate::email_sender::history::SentEmail;
+use crate::email_sender::priority::Priority;
+use crate::email_sender::readability;
+use crate::email_sender::registry::{self, SendRegistry};
+use chrono::{Local, NaiveDate};
+use directories_next::ProjectDirs;
+use log::{error, info, warn};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufReader; // For efficient file reading
+use std::path::PathBuf;
+use std::time::Instant;
+
+struct SavedAppState {
+    smtp_host: String,
+    smtp_port_str: String,
+    smtp_user: String,
+    smtp_password: SecretString, // Requires 'serde' feature for secrecy crate
+    from_email: String,
+    sender_name: String,
+    email_subject: String,
+    email_body: String,
+    email_cc: String,
+    email_bcc: String,
+    recipients: Vec<UIRecipient>,
+    calendar_buffer_minutes: Minutes,
+    day_start_hour: HourOfDay,
+    day_end_hour: HourOfDay,
+    today_cutoff_hour: Option<u32>,
+    slot_min_gap_minutes: u32,
+    max_slots_per_day: u32,
+    setup_checklist_dismissed: bool,
+    last_fetch: Option<FetchedSlots>,
+    smtp_no_auth: bool,
+    show_unavailable_days: bool,
+    skip_send_confirmation: bool,
+    max_slots_in_email: u32,
+    autosave_seconds: u32,
+    pad_range_edges: bool,
+    no_slots_text: String,
+    blackout_dates: Vec<NaiveDate>,
+    reverse_slot_order: bool,
+    email_priority: Priority,
+    drip_enabled: bool,
+    drip_batch_size: u32,
+    drip_interval_days: u32,
+    smtp_use_webhook: bool,
+    webhook_url: String,
+    readable_slot_lines: u32,
+    availability_page_path: String,
+    availability_base_url: String,
+    send_cooldown_minutes: u32,
+    label_timezone: bool,
+    prefer_config_file: bool,
+    slots_shown_in_list: u32,
+    slot_notes: BTreeMap<String, String>,
+    booking_title_template: String,
+    booking_meeting_minutes: u32,
+    booking_with_meet: bool,
+    booked_meetings: Vec<BookedMeeting>,
+    confirm_remove_above: u32,
+    send_history: Vec<SentEmail>,
+    follow_up_templates: FollowUpTemplates,
+    spam_check: bool,
+    spam_test_address: String,
+    email_archive_bcc: String,
+    abort_after_failures: u32,
+    buffer_longer_than: Option<Minutes>,
+    weekly_rules: WeeklyRules,
+    weekly_rules_enabled: bool,
+    campaign: String,
+    lookahead_days: u32,
+    calendar_buffer_after_minutes: Minutes,
+    slot_calendar_ids: Vec<String>,
+    weekdays: Weekdays,
+    // Optional: Persist these if they should be remembered across sessions
+    // credentials_path: String,
+    // token_cache_path: String,
+}
+
+/// The Calendar Settings the GUI last saved, for fetching slots without it.
+pub struct SavedSlotSettings {
+    pub filters: calendar::SlotFilters,
+    pub lookahead_days: u32,
+    pub calendar_ids: Vec<String>,
+    pub max_in_email: usize,
+}
+
+// --- Manual Serialize Implementation ---
+impl Serialize for SavedAppState {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        // Define the number of fields
+        let mut state = serializer.serialize_struct("SavedAppState", 61)?; // Update count if fields change
+
+        state.serialize_field("smtp_host", &self.smtp_host)?;
+        state.serialize_field("smtp_port_str", &self.smtp_port_str)?;
+        state.serialize_field("smtp_user", &self.smtp_user)?;
+        // Expose the secret *before* serializing the inner String
+        state.serialize_field("smtp_password", self.smtp_password.expose_secret())?;
+        state.serialize_field("from_email", &self.from_email)?;
+        state.serialize_field("sender_name", &self.sender_name)?;
+        state.serialize_field("email_subject", &self.email_subject)?;
+        state.serialize_field("email_body", &self.email_body)?;
+        state.serialize_field("email_cc", &self.email_cc)?;
+        state.serialize_field("email_bcc", &self.email_bcc)?;
+        state.serialize_field("recipients", &self.recipients)?; // Vec<UIRecipient> needs UIRecipient to derive Serialize
+        state.serialize_field("calendar_buffer_minutes", &self.calendar_buffer_minutes)?;
+        state.serialize_field("day_start_hour", &self.day_start_hour)?;
+        state.serialize_field("day_end_hour", &self.day_end_hour)?;
+        state.serialize_field("today_cutoff_hour", &self.today_cutoff_hour)?;
+        state.serialize_field("slot_min_gap_minutes", &self.slot_min_gap_minutes)?;
+        state.serialize_field("max_slots_per_day", &self.max_slots_per_day)?;
+        state.serialize_field("setup_checklist_dismissed", &self.setup_checklist_dismissed)?;
+        state.serialize_field("last_fetch", &self.last_fetch)?;
+        state.serialize_field("smtp_no_auth", &self.smtp_no_auth)?;
+        state.serialize_field("show_unavailable_days", &self.show_unavailable_days)?;
+        state.serialize_field("skip_send_confirmation", &self.skip_send_confirmation)?;
+        state.serialize_field("max_slots_in_email", &self.max_slots_in_email)?;
+        state.serialize_field("autosave_seconds", &self.autosave_seconds)?;
+        state.serialize_field("pad_range_edges", &self.pad_range_edges)?;
+        state.serialize_field("no_slots_text", &self.no_slots_text)?;
+        state.serialize_field("blackout_dates", &self.blackout_dates)?;
+        state.serialize_field("reverse_slot_order", &self.reverse_slot_order)?;
+        state.serialize_field("email_priority", &self.email_priority)?;
+        state.serialize_field("drip_enabled", &self.drip_enabled)?;
+        state.serialize_field("drip_batch_size", &self.drip_batch_size)?;
+        state.serialize_field("drip_interval_days", &self.drip_interval_days)?;
+        state.serialize_field("smtp_use_webhook", &self.smtp_use_webhook)?;
+        state.serialize_field("webhook_url", &self.webhook_url)?;
+        state.serialize_field("readable_slot_lines", &self.readable_slot_lines)?;
+        state.serialize_field("availability_page_path", &self.availability_page_path)?;
+        state.serialize_field("availability_base_url", &self.availability_base_url)?;
+        state.serialize_field("send_cooldown_minutes", &self.send_cooldown_minutes)?;
+        state.serialize_field("label_timezone", &self.label_timezone)?;
+        state.serialize_field("prefer_config_file", &self.prefer_config_file)?;
+        state.serialize_field("slots_shown_in_list", &self.slots_shown_in_list)?;
+        state.serialize_field("slot_notes", &self.slot_notes)?;
+        state.serialize_field("booking_title_template", &self.booking_title_template)?;
+        state.serialize_field("booking_meeting_minutes", &self.booking_meeting_minutes)?;
+        state.serialize_field("booking_with_meet", &self.booking_with_meet)?;
+        state.serialize_field("booked_meetings", &self.booked_meetings)?;
+        state.serialize_field("confirm_remove_above", &self.confirm_remove_above)?;
+        state.serialize_field("send_history", &self.send_history)?;
+        state.serialize_field("follow_up_templates", &self.follow_up_templates)?;
+        state.serialize_field("spam_check", &self.spam_check)?;
+        state.serialize_field("spam_test_address", &self.spam_test_address)?;
+        state.serialize_field("email_archive_bcc", &self.email_archive_bcc)?;
+        state.serialize_field("abort_after_failures", &self.abort_after_failures)?;
+        state.serialize_field("buffer_longer_than", &self.buffer_longer_than)?;
+        state.serialize_field("weekly_rules", &self.weekly_rules)?;
+        state.serialize_field("weekly_rules_enabled", &self.weekly_rules_enabled)?;
+        state.serialize_field("campaign", &self.campaign)?;
+        state.serialize_field("lookahead_days", &self.lookahead_days)?;
+        state.serialize_field(
+            "calendar_buffer_after_minutes",
+            &self.calendar_buffer_after_minutes,
+        )?;
+        state.serialize_field("slot_calendar_ids", &self.slot_calendar_ids)?;
+        state.serialize_field("weekdays", &self.weekdays)?;
+        // Add optional fields here if saving them:
+        // state.serialize_field("credentials_path", &self.credentials_path)?;
+        // state.serialize_field("token_cache_path", &self.token_cache_path)?;
+
+        state.end()
+    }
+}
+
+// --- Manual Deserialize Implementation ---
+impl<'de> Deserialize<'de> for SavedAppState {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        // Define the fields we expect
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "snake_case")] // Match field names used in Serialize
+        enum Field {
+            SmtpHost,
+            SmtpPortStr,
+            SmtpUser,
+            SmtpPassword,
+            FromEmail,
+            SenderName,
+            EmailSubject,
+            EmailBody,
+            EmailCc,
+            EmailBcc,
+            Recipients,
+            CalendarBufferMinutes,
+            DayStartHour,
+            DayEndHour,
+            TodayCutoffHour,
+            SlotMinGapMinutes,
+            MaxSlotsPerDay,
+            SetupChecklistDismissed,
+            LastFetch,
+            SmtpNoAuth,
+            ShowUnavailableDays,
+            SkipSendConfirmation,
+            MaxSlotsInEmail,
+            AutosaveSeconds,
+            PadRangeEdges,
+            NoSlotsText,
+            BlackoutDates,
+            ReverseSlotOrder,
+            EmailPriority,
+            DripEnabled,
+            DripBatchSize,
+            DripIntervalDays,
+            SmtpUseWebhook,
+            WebhookUrl,
+            ReadableSlotLines,
+            AvailabilityPagePath,
+            AvailabilityBaseUrl,
+            SendCooldownMinutes,
+            LabelTimezone,
+            PreferConfigFile,
+            SlotsShownInList,
+            SlotNotes,
+            BookingTitleTemplate,
+            BookingMeetingMinutes,
+            BookingWithMeet,
+            BookedMeetings,
+            ConfirmRemoveAbove,
+            SendHistory,
+            FollowUpTemplates,
+            SpamCheck,
+            SpamTestAddress,
+            EmailArchiveBcc,
+            AbortAfterFailures,
+            BufferLongerThan,
+            WeeklyRules,
+            WeeklyRulesEnabled,
+            Campaign,
+            LookaheadDays,
+            CalendarBufferAfterMinutes,
+            SlotCalendarIds,
+            SlotCalendarId,
+            Weekdays, /* , CredentialsPath, TokenCachePath */
+        }
+
+        struct SavedAppStateVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for SavedAppStateVisitor {
+            type Value = SavedAppState;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("struct SavedAppState")
+            }
+
+            fn visit_map<V>(self, mut map: V) -> Result<SavedAppState, V::Error>
+            where
+                V: serde::de::MapAccess<'de>,
+            {
+                // Use Option for each field to track if it was seen
+                let mut smtp_host = None;
+                let mut smtp_port_str = None;
+                let mut smtp_user = None;
+                let mut smtp_password_str: Option<String> = None; // Deserialize password as String first
+                let mut from_email = None;
+                let mut sender_name = None;
+                let mut email_subject = None;
+                let mut email_body = None;
+                let mut email_cc = None;
+                let mut email_bcc = None;
+                let mut recipients = None;
+                let mut calendar_buffer_minutes = None;
+                let mut day_start_hour = None;
+                let mut day_end_hour = None;
+                let mut today_cutoff_hour = None;
+                let mut slot_min_gap_minutes = None;
+                let mut max_slots_per_day = None;
+                let mut setup_checklist_dismissed = None;
+                let mut last_fetch = None;
+                let mut smtp_no_auth = None;
+                let mut show_unavailable_days = None;
+                let mut skip_send_confirmation = None;
+                let mut max_slots_in_email = None;
+                let mut autosave_seconds = None;
+                let mut pad_range_edges = None;
+                let mut no_slots_text = None;
+                let mut blackout_dates = None;
+                let mut reverse_slot_order = None;
+                let mut email_priority = None;
+                let mut drip_enabled = None;
+                let mut drip_batch_size = None;
+                let mut drip_interval_days = None;
+                let mut smtp_use_webhook = None;
+                let mut webhook_url = None;
+                let mut readable_slot_lines = None;
+                let mut availability_page_path = None;
+                let mut availability_base_url = None;
+                let mut send_cooldown_minutes = None;
+                let mut label_timezone = None;
+                let mut prefer_config_file = None;
+                let mut slots_shown_in_list = None;
+                let mut slot_notes = None;
+                let mut booking_title_template = None;
+                let mut booking_meeting_minutes = None;
+                let mut booking_with_meet = None;
+                let mut booked_meetings = None;
+                let mut confirm_remove_above = None;
+                let mut send_history = None;
+                let mut follow_up_templates = None;
+                let mut spam_check = None;
+                let mut spam_test_address = None;
+                let mut email_archive_bcc = None;
+                let mut abort_after_failures = None;
+                let mut buffer_longer_than = None;
+                let mut weekly_rules = None;
+                let mut weekly_rules_enabled = None;
+                let mut campaign = None;
+                let mut lookahead_days = None;
+                let mut calendar_buffer_after_minutes = None;
+                let mut slot_calendar_ids = None;
+                let mut slot_calendar_id: Option<Option<String>> = None;
+                let mut weekdays = None;
+                // let mut credentials_path = None;
+                // let mut token_cache_path = None;
+
+                // Loop through the map data provided by the deserializer
+                while let Some(key) = map.next_key()? {
+                    match key {
+                        Field::SmtpHost => {
+                            if smtp_host.is_some() {
+                                return Err(serde::de::Error::duplicate_field("smtp_host"));
+                            }
+                            smtp_host = Some(map.next_value()?);
+                        }
+                        Field::SmtpPortStr => {
+                            if smtp_port_str.is_some() {
+                                return Err(serde::de::Error::duplicate_field("smtp_port_str"));
+                            }
+                            smtp_port_str = Some(map.next_value()?);
+                        }
+                        Field::SmtpUser => {
+                            if smtp_user.is_some() {
+                                return Err(serde::de::Error::duplicate_field("smtp_user"));
+                            }
+                            smtp_user = Some(map.next_value()?);
+                        }
+                        // Deserialize password as a String
+                        Field::SmtpPassword => {
+                            if smtp_password_str.is_some() {
+                                return Err(serde::de::Error::duplicate_field("smtp_password"));
+                            }
+                            smtp_password_str = Some(map.next_value()?);
+                        }
+                        Field::FromEmail => {
+                            if from_email.is_some() {
+                                return Err(serde::de::Error::duplicate_field("from_email"));
+                            }
+                            from_email = Some(map.next_value()?);
+                        }
+                        Field::SenderName => {
+                            if sender_name.is_some() {
+                                return Err(serde::de::Error::duplicate_field("sender_name"));
+                            }
+                            sender_name = Some(map.next_value()?);
+                        }
+                        Field::EmailSubject => {
+                            if email_subject.is_some() {
+                                return Err(serde::de::Error::duplicate_field("email_subject"));
+                            }
+                            email_subject = Some(map.next_value()?);
+                        }
+                        Field::EmailBody => {
+                            if email_body.is_some() {
+                                return Err(serde::de::Error::duplicate_field("email_body"));
+                            }
+                            email_body = Some(map.next_value()?);
+                        }
+                        Field::EmailCc => {
+                            if email_cc.is_some() {
+                                return Err(serde::de::Error::duplicate_field("email_cc"));
+                            }
+                            email_cc = Some(map.next_value()?);
+                        }
+                        Field::EmailBcc => {
+                            if email_bcc.is_some() {
+                                return Err(serde::de::Error::duplicate_field("email_bcc"));
+                            }
+                            email_bcc = Some(map.next_value()?);
+                        }
+                        Field::Recipients => {
+                            if recipients.is_some() {
+                                return Err(serde::de::Error::duplicate_field("recipients"));
+                            }
+                            recipients = Some(map.next_value()?);
+                        } // UIRecipient needs derive Deserialize
+                        Field::CalendarBufferMinutes => {
+                            if calendar_buffer_minutes.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "calendar_buffer_minutes",
+                                ));
+                            }
+                            calendar_buffer_minutes = Some(map.next_value()?);
+                        }
+                        Field::DayStartHour => {
+                            if day_start_hour.is_some() {
+                                return Err(serde::de::Error::duplicate_field("day_start_hour"));
+                            }
+                            day_start_hour = Some(map.next_value()?);
+                        }
+                        Field::DayEndHour => {
+                            if day_end_hour.is_some() {
+                                return Err(serde::de::Error::duplicate_field("day_end_hour"));
+                            }
+                            day_end_hour = Some(map.next_value()?);
+                        }
+                        Field::TodayCutoffHour => {
+                            if today_cutoff_hour.is_some() {
+                                return Err(serde::de::Error::duplicate_field("today_cutoff_hour"));
+                            }
+                            today_cutoff_hour = Some(map.next_value()?);
+                        }
+                        Field::SlotMinGapMinutes => {
+                            if slot_min_gap_minutes.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "slot_min_gap_minutes",
+                                ));
+                            }
+                            slot_min_gap_minutes = Some(map.next_value()?);
+                        }
+                        Field::MaxSlotsPerDay => {
+                            if max_slots_per_day.is_some() {
+                                return Err(serde::de::Error::duplicate_field("max_slots_per_day"));
+                            }
+                            max_slots_per_day = Some(map.next_value()?);
+                        }
+                        Field::SetupChecklistDismissed => {
+                            if setup_checklist_dismissed.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "setup_checklist_dismissed",
+                                ));
+                            }
+                            setup_checklist_dismissed = Some(map.next_value()?);
+                        }
+                        Field::LastFetch => {
+                            if last_fetch.is_some() {
+                                return Err(serde::de::Error::duplicate_field("last_fetch"));
+                            }
+                            last_fetch = Some(map.next_value()?);
+                        }
+                        Field::SmtpNoAuth => {
+                            if smtp_no_auth.is_some() {
+                                return Err(serde::de::Error::duplicate_field("smtp_no_auth"));
+                            }
+                            smtp_no_auth = Some(map.next_value()?);
+                        }
+                        Field::ShowUnavailableDays => {
+                            if show_unavailable_days.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "show_unavailable_days",
+                                ));
+                            }
+                            show_unavailable_days = Some(map.next_value()?);
+                        }
+                        Field::SkipSendConfirmation => {
+                            if skip_send_confirmation.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "skip_send_confirmation",
+                                ));
+                            }
+                            skip_send_confirmation = Some(map.next_value()?);
+                        }
+                        Field::MaxSlotsInEmail => {
+                            if max_slots_in_email.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "max_slots_in_email",
+                                ));
+                            }
+                            max_slots_in_email = Some(map.next_value()?);
+                        }
+                        Field::AutosaveSeconds => {
+                            if autosave_seconds.is_some() {
+                                return Err(serde::de::Error::duplicate_field("autosave_seconds"));
+                            }
+                            autosave_seconds = Some(map.next_value()?);
+                        }
+                        Field::PadRangeEdges => {
+                            if pad_range_edges.is_some() {
+                                return Err(serde::de::Error::duplicate_field("pad_range_edges"));
+                            }
+                            pad_range_edges = Some(map.next_value()?);
+                        }
+                        Field::NoSlotsText => {
+                            if no_slots_text.is_some() {
+                                return Err(serde::de::Error::duplicate_field("no_slots_text"));
+                            }
+                            no_slots_text = Some(map.next_value()?);
+                        }
+                        Field::BlackoutDates => {
+                            if blackout_dates.is_some() {
+                                return Err(serde::de::Error::duplicate_field("blackout_dates"));
+                            }
+                            blackout_dates = Some(map.next_value()?);
+                        }
+                        Field::ReverseSlotOrder => {
+                            if reverse_slot_order.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "reverse_slot_order",
+                                ));
+                            }
+                            reverse_slot_order = Some(map.next_value()?);
+                        }
+                        Field::EmailPriority => {
+                            if email_priority.is_some() {
+                                return Err(serde::de::Error::duplicate_field("email_priority"));
+                            }
+                            email_priority = Some(map.next_value()?);
+                        }
+                        Field::DripEnabled => {
+                            if drip_enabled.is_some() {
+                                return Err(serde::de::Error::duplicate_field("drip_enabled"));
+                            }
+                            drip_enabled = Some(map.next_value()?);
+                        }
+                        Field::DripBatchSize => {
+                            if drip_batch_size.is_some() {
+                                return Err(serde::de::Error::duplicate_field("drip_batch_size"));
+                            }
+                            drip_batch_size = Some(map.next_value()?);
+                        }
+                        Field::DripIntervalDays => {
+                            if drip_interval_days.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "drip_interval_days",
+                                ));
+                            }
+                            drip_interval_days = Some(map.next_value()?);
+                        }
+                        Field::SmtpUseWebhook => {
+                            if smtp_use_webhook.is_some() {
+                                return Err(serde::de::Error::duplicate_field("smtp_use_webhook"));
+                            }
+                            smtp_use_webhook = Some(map.next_value()?);
+                        }
+                        Field::WebhookUrl => {
+                            if webhook_url.is_some() {
+                                return Err(serde::de::Error::duplicate_field("webhook_url"));
+                            }
+                            webhook_url = Some(map.next_value()?);
+                        }
+                        Field::ReadableSlotLines => {
+                            if readable_slot_lines.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "readable_slot_lines",
+                                ));
+                            }
+                            readable_slot_lines = Some(map.next_value()?);
+                        }
+                        Field::AvailabilityPagePath => {
+                            if availability_page_path.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "availability_page_path",
+                                ));
+                            }
+                            availability_page_path = Some(map.next_value()?);
+                        }
+                        Field::AvailabilityBaseUrl => {
+                            if availability_base_url.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "availability_base_url",
+                                ));
+                            }
+                            availability_base_url = Some(map.next_value()?);
+                        }
+                        Field::SendCooldownMinutes => {
+                            if send_cooldown_minutes.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "send_cooldown_minutes",
+                                ));
+                            }
+                            send_cooldown_minutes = Some(map.next_value()?);
+                        }
+                        Field::LabelTimezone => {
+                            if label_timezone.is_some() {
+                                return Err(serde::de::Error::duplicate_field("label_timezone"));
+                            }
+                            label_timezone = Some(map.next_value()?);
+                        }
+                        Field::PreferConfigFile => {
+                            if prefer_config_file.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "prefer_config_file",
+                                ));
+                            }
+                            prefer_config_file = Some(map.next_value()?);
+                        }
+                        Field::SlotsShownInList => {
+                            if slots_shown_in_list.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "slots_shown_in_list",
+                                ));
+                            }
+                            slots_shown_in_list = Some(map.next_value()?);
+                        }
+                        Field::SlotNotes => {
+                            if slot_notes.is_some() {
+                                return Err(serde::de::Error::duplicate_field("slot_notes"));
+                            }
+                            slot_notes = Some(map.next_value()?);
+                        }
+                        Field::BookingTitleTemplate => {
+                            if booking_title_template.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "booking_title_template",
+                                ));
+                            }
+                            booking_title_template = Some(map.next_value()?);
+                        }
+                        Field::BookingMeetingMinutes => {
+                            if booking_meeting_minutes.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "booking_meeting_minutes",
+                                ));
+                            }
+                            booking_meeting_minutes = Some(map.next_value()?);
+                        }
+                        Field::BookingWithMeet => {
+                            if booking_with_meet.is_some() {
+                                return Err(serde::de::Error::duplicate_field("booking_with_meet"));
+                            }
+                            booking_with_meet = Some(map.next_value()?);
+                        }
+                        Field::BookedMeetings => {
+                            if booked_meetings.is_some() {
+                                return Err(serde::de::Error::duplicate_field("booked_meetings"));
+                            }
+                            booked_meetings = Some(map.next_value()?);
+                        }
+                        Field::ConfirmRemoveAbove => {
+                            if confirm_remove_above.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "confirm_remove_above",
+                                ));
+                            }
+                            confirm_remove_above = Some(map.next_value()?);
+                        }
+                        Field::SendHistory => {
+                            if send_history.is_some() {
+                                return Err(serde::de::Error::duplicate_field("send_history"));
+                            }
+                            send_history = Some(map.next_value()?);
+                        }
+                        Field::FollowUpTemplates => {
+                            if follow_up_templates.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "follow_up_templates",
+                                ));
+                            }
+                            follow_up_templates = Some(map.next_value()?);
+                        }
+                        Field::SpamCheck => {
+                            if spam_check.is_some() {
+                                return Err(serde::de::Error::duplicate_field("spam_check"));
+                            }
+                            spam_check = Some(map.next_value()?);
+                        }
+                        Field::SpamTestAddress => {
+                            if spam_test_address.is_some() {
+                                return Err(serde::de::Error::duplicate_field("spam_test_address"));
+                            }
+                            spam_test_address = Some(map.next_value()?);
+                        }
+                        Field::EmailArchiveBcc => {
+                            if email_archive_bcc.is_some() {
+                                return Err(serde::de::Error::duplicate_field("email_archive_bcc"));
+                            }
+                            email_archive_bcc = Some(map.next_value()?);
+                        }
+                        Field::AbortAfterFailures => {
+                            if abort_after_failures.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "abort_after_failures",
+                                ));
+                            }
+                            abort_after_failures = Some(map.next_value()?);
+                        }
+                        Field::BufferLongerThan => {
+                            if buffer_longer_than.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "buffer_longer_than",
+                                ));
+                            }
+                            buffer_longer_than = Some(map.next_value()?);
+                        }
+                        Field::WeeklyRules => {
+                            if weekly_rules.is_some() {
+                                return Err(serde::de::Error::duplicate_field("weekly_rules"));
+                            }
+                            weekly_rules = Some(map.next_value()?);
+                        }
+                        Field::WeeklyRulesEnabled => {
+                            if weekly_rules_enabled.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "weekly_rules_enabled",
+                                ));
+                            }
+                            weekly_rules_enabled = Some(map.next_value()?);
+                        }
+                        Field::Campaign => {
+                            if campaign.is_some() {
+                                return Err(serde::de::Error::duplicate_field("campaign"));
+                            }
+                            campaign = Some(map.next_value()?);
+                        }
+                        Field::LookaheadDays => {
+                            if lookahead_days.is_some() {
+                                return Err(serde::de::Error::duplicate_field("lookahead_days"));
+                            }
+                            lookahead_days = Some(map.next_value()?);
+                        }
+                        Field::CalendarBufferAfterMinutes => {
+                            if calendar_buffer_after_minutes.is_some() {
+                                return Err(serde::de::Error::duplicate_field(
+                                    "calendar_buffer_after_minutes",
+                                ));
+                            }
+                            calendar_buffer_after_minutes = Some(map.next_value()?);
+                        }
+                        Field::SlotCalendarIds => {
+                            if slot_calendar_ids.is_some() {
+                                return Err(serde::de::Error::duplicate_field("slot_calendar_ids"));
+                            }
+                            slot_calendar_ids = Some(map.next_value()?);
+                        }
+                        // Saved before several calendars could be picked
+                        Field::SlotCalendarId => {
+                            if slot_calendar_id.is_some() {
+                                return Err(serde::de::Error::duplicate_field("slot_calendar_id"));
+                            }
+                            slot_calendar_id = Some(map.next_value()?);
+                        }
+                        Field::Weekdays => {
+                            if weekdays.is_some() {
+                                return Err(serde::de::Error::duplicate_field("weekdays"));
+                            }
+                            weekdays = Some(map.next_value()?);
+                        } // Add optional fields here if saving them
+                          // Field::CredentialsPath => { if credentials_path.is_some() { return Err(serde::de::Error::duplicate_field("credentials_path")); } credentials_path = Some(map.next_value()?); }
+                          // Field::TokenCachePath => { if token_cache_path.is_some() { return Err(serde::de::Error::duplicate_field("token_cache_path")); } token_cache_path = Some(map.next_value()?); }
+                    }
+                }
+
+                // Check that all required fields were found and unwrap them
+                let smtp_host =
+                    smtp_host.ok_or_else(|| serde::de::Error::missing_field("smtp_host"))?;
+                let smtp_port_str = smtp_port_str
+                    .ok_or_else(|| serde::de::Error::missing_field("smtp_port_str"))?;
+                let smtp_user =
+                    smtp_user.ok_or_else(|| serde::de::Error::missing_field("smtp_user"))?;
+                let smtp_password_str = smtp_password_str
+                    .ok_or_else(|| serde::de::Error::missing_field("smtp_password"))?;
+                let from_email =
+                    from_email.ok_or_else(|| serde::de::Error::missing_field("from_email"))?;
+                let sender_name =
+                    sender_name.ok_or_else(|| serde::de::Error::missing_field("sender_name"))?;
+                let email_subject = email_subject
+                    .ok_or_else(|| serde::de::Error::missing_field("email_subject"))?;
+                let email_body =
+                    email_body.ok_or_else(|| serde::de::Error::missing_field("email_body"))?;
+                // Added later: default instead of failing so older state files still load
+                let email_cc = email_cc.unwrap_or_default();
+                let email_bcc = email_bcc.unwrap_or_default();
+                let today_cutoff_hour = today_cutoff_hour.unwrap_or_default();
+                let slot_min_gap_minutes = slot_min_gap_minutes.unwrap_or_default();
+                let max_slots_per_day = max_slots_per_day.unwrap_or_default();
+                let setup_checklist_dismissed = setup_checklist_dismissed.unwrap_or_default();
+                let last_fetch = last_fetch.unwrap_or_default();
+                let smtp_no_auth = smtp_no_auth.unwrap_or_default();
+                let show_unavailable_days = show_unavailable_days.unwrap_or_default();
+                let skip_send_confirmation = skip_send_confirmation.unwrap_or_default();
+                let max_slots_in_email =
+                    max_slots_in_email.unwrap_or(calendar::DEFAULT_MAX_SLOTS_IN_EMAIL);
+                let autosave_seconds = autosave_seconds.unwrap_or(DEFAULT_AUTOSAVE_SECONDS);
+                let pad_range_edges = pad_range_edges.unwrap_or_default();
+                let no_slots_text =
+                    no_slots_text.unwrap_or_else(|| DEFAULT_NO_SLOTS_TEXT.to_string());
+                let blackout_dates = blackout_dates.unwrap_or_default();
+                let reverse_slot_order = reverse_slot_order.unwrap_or_default();
+                let email_priority = email_priority.unwrap_or_default();
+                let drip_enabled = drip_enabled.unwrap_or_default();
+                let drip_batch_size = drip_batch_size.unwrap_or(DEFAULT_DRIP_BATCH_SIZE);
+                let drip_interval_days = drip_interval_days.unwrap_or(1);
+                let smtp_use_webhook = smtp_use_webhook.unwrap_or_default();
+                let webhook_url = webhook_url.unwrap_or_default();
+                let readable_slot_lines =
+                    readable_slot_lines.unwrap_or(readability::DEFAULT_MAX_SLOT_LINES);
+                let availability_page_path =
+                    availability_page_path.unwrap_or_else(MyApp::default_availability_page_path);
+                let availability_base_url = availability_base_url.unwrap_or_default();
+                let send_cooldown_minutes =
+                    send_cooldown_minutes.unwrap_or(registry::DEFAULT_SEND_COOLDOWN_MINUTES);
+                let label_timezone = label_timezone.unwrap_or_default();
+                let prefer_config_file = prefer_config_file.unwrap_or_default();
+                let slots_shown_in_list =
+                    slots_shown_in_list.unwrap_or(DEFAULT_SLOTS_SHOWN_IN_LIST);
+                let slot_notes = slot_notes.unwrap_or_default();
+                let booking_title_template = booking_title_template
+                    .unwrap_or_else(|| calendar::booking::DEFAULT_TITLE_TEMPLATE.to_string());
+                let booking_meeting_minutes =
+                    booking_meeting_minutes.unwrap_or(DEFAULT_MEETING_MINUTES);
+                let booking_with_meet = booking_with_meet.unwrap_or_default();
+                let booked_meetings = booked_meetings.unwrap_or_default();
+                let confirm_remove_above =
+                    confirm_remove_above.unwrap_or(DEFAULT_CONFIRM_REMOVE_ABOVE);
+                let send_history = send_history.unwrap_or_default();
+                let follow_up_templates = follow_up_templates.unwrap_or_default();
+                let spam_check = spam_check.unwrap_or(true);
+                let spam_test_address = spam_test_address.unwrap_or_default();
+                let email_archive_bcc = email_archive_bcc.unwrap_or_default();
+                let abort_after_failures =
+                    abort_after_failures.unwrap_or(DEFAULT_ABORT_AFTER_FAILURES);
+                let buffer_longer_than = buffer_longer_than.unwrap_or_default();
+                let weekly_rules = weekly_rules.unwrap_or_default();
+                let weekly_rules_enabled = weekly_rules_enabled.unwrap_or_default();
+                let campaign = campaign.unwrap_or_default();
+                let lookahead_days = lookahead_days.unwrap_or(calendar::DEFAULT_LOOKAHEAD_DAYS);
+                let slot_calendar_ids = slot_calendar_ids
+                    .unwrap_or_else(|| slot_calendar_id.flatten().into_iter().collect());
+                let weekdays = weekdays.unwrap_or_default();
+                let recipients =
+                    recipients.ok_or_else(|| serde::de::Error::missing_field("recipients"))?;
+                let calendar_buffer_minutes = calendar_buffer_minutes
+                    .ok_or_else(|| serde::de::Error::missing_field("calendar_buffer_minutes"))?;
+                // Older state files had one buffer for both sides
+                let calendar_buffer_after_minutes =
+                    calendar_buffer_after_minutes.unwrap_or(calendar_buffer_minutes);
+                let day_start_hour = day_start_hour
+                    .ok_or_else(|| serde::de::Error::missing_field("day_start_hour"))?;
+                let day_end_hour =
+                    day_end_hour.ok_or_else(|| serde::de::Error::missing_field("day_end_hour"))?;
+                // Unwrap optional fields here if saving them
+                // let credentials_path = credentials_path.ok_or_else(|| serde::de::Error::missing_field("credentials_path"))?;
+                // let token_cache_path = token_cache_path.ok_or_else(|| serde::de::Error::missing_field("token_cache_path"))?;
+
+                // Construct the SavedAppState, wrapping the password String in SecretString
+                Ok(SavedAppState {
+                    smtp_host,
+                    smtp_port_str,
+                    smtp_user,
+                    smtp_password: SecretString::new(smtp_password_str.into()), // Wrap here
+                    from_email,
+                    sender_name,
+                    email_subject,
+                    email_body,
+                    email_cc,
+                    email_bcc,
+                    recipients,
+                    calendar_buffer_minutes,
+                    day_start_hour,
+                    day_end_hour,
+                    today_cutoff_hour,
+                    slot_min_gap_minutes,
+                    max_slots_per_day,
+                    setup_checklist_dismissed,
+                    last_fetch,
+                    smtp_no_auth,
+                    show_unavailable_days,
+                    skip_send_confirmation,
+                    max_slots_in_email,
+                    autosave_seconds,
+                    pad_range_edges,
+                    no_slots_text,
+                    blackout_dates,
+                    reverse_slot_order,
+                    email_priority,
+                    drip_enabled,
+                    drip_batch_size,
+                    drip_interval_days,
+                    smtp_use_webhook,
+                    webhook_url,
+                    readable_slot_lines,
+                    availability_page_path,
+                    availability_base_url,
+                    send_cooldown_minutes,
+                    label_timezone,
+                    prefer_config_file,
+                    slots_shown_in_list,
+                    slot_notes,
+                    booking_title_template,
+                    booking_meeting_minutes,
+                    booking_with_meet,
+                    booked_meetings,
+                    confirm_remove_above,
+                    send_history,
+                    follow_up_templates,
+                    spam_check,
+                    spam_test_address,
+                    email_archive_bcc,
+                    abort_after_failures,
+                    buffer_longer_than,
+                    weekly_rules,
+                    weekly_rules_enabled,
+                    campaign,
+                    lookahead_days,
+                    calendar_buffer_after_minutes,
+                    slot_calendar_ids,
+                    weekdays,
+                    // Add optional fields here if saving them
+                    // credentials_path,
+                    // token_cache_path,
+                })
+            }
+        }
+
+        // Define the field names for the deserializer
+        const FIELDS: &'static [&'static str] = &[
+            "smtp_host",
+            "smtp_port_str",
+            "smtp_user",
+            "smtp_password",
+            "from_email",
+            "sender_name",
+            "email_subject",
+            "email_body",
+            "email_cc",
+            "email_bcc",
+            "recipients",
+            "calendar_buffer_minutes",
+            "day_start_hour",
+            "day_end_hour",
+            "today_cutoff_hour",
+            "slot_min_gap_minutes",
+            "max_slots_per_day",
+            "setup_checklist_dismissed",
+            "last_fetch",
+            "smtp_no_auth",
+            "show_unavailable_days",
+            "skip_send_confirmation",
+            "max_slots_in_email",
+            "autosave_seconds",
+            "pad_range_edges",
+            "no_slots_text",
+            "blackout_dates",
+            "reverse_slot_order",
+            "email_priority",
+            "drip_enabled",
+            "drip_batch_size",
+            "drip_interval_days",
+            "smtp_use_webhook",
+            "webhook_url",
+            "readable_slot_lines",
+            "availability_page_path",
+            "availability_base_url",
+            "send_cooldown_minutes",
+            "label_timezone",
+            "prefer_config_file",
+            "slots_shown_in_list",
+            "slot_notes",
+            "booking_title_template",
+            "booking_meeting_minutes",
+            "booking_with_meet",
+            "booked_meetings",
+            "confirm_remove_above",
+            "send_history",
+            "follow_up_templates",
+            "spam_check",
+            "spam_test_address",
+            "email_archive_bcc",
+            "abort_after_failures",
+            "buffer_longer_than",
+            "weekly_rules",
+            "weekly_rules_enabled",
+            "campaign",
+            "lookahead_days",
+            "calendar_buffer_after_minutes",
+            "slot_calendar_ids",
+            "slot_calendar_id",
+            "weekdays", /* "credentials_path", "token_cache_path" */
+        ];
+        deserializer.deserialize_struct("SavedAppState", FIELDS, SavedAppStateVisitor)
+    }
+}
+
+impl MyApp {
+    /// Overwrites the defaults with the state saved by the last session, if any.
+    pub(super) fn load_state(&mut self) {
+        // --- Load State Manually from File ---
+        if let Some(proj_dirs) = ProjectDirs::from("com", "YourOrg", "CoffeeChatHelper") {
+            // Adjust qualifier/org/app names
+            let config_dir = proj_dirs.config_dir();
+            let state_path = config_dir.join("app_state.json");
+
+            if state_path.exists() {
+                info!("Attempting to load state from: {:?}", state_path);
+                match fs::File::open(&state_path) {
+                    Ok(file) => {
+                        let reader = BufReader::new(file);
+                        match serde_json::from_reader::<_, SavedAppState>(reader) {
+                            Ok(loaded_state) => {
+                                info!("Successfully loaded saved application state from file.");
+                                // Overwrite default fields with loaded state
+                                self.smtp.host = loaded_state.smtp_host;
+                                self.smtp.port_str = loaded_state.smtp_port_str;
+                                self.smtp.user = loaded_state.smtp_user;
+                                self.smtp.password = loaded_state.smtp_password;
+                                self.smtp.from_email = loaded_state.from_email;
+                                self.compose.sender_name = loaded_state.sender_name;
+                                self.compose.subject = loaded_state.email_subject;
+                                self.compose.body = loaded_state.email_body;
+                                self.compose.cc = loaded_state.email_cc;
+                                self.compose.bcc = loaded_state.email_bcc;
+                                self.compose.recipients = loaded_state.recipients;
+                                self.calendar_settings.buffer_before_minutes =
+                                    loaded_state.calendar_buffer_minutes;
+                                self.calendar_settings.day_start_hour = loaded_state.day_start_hour;
+                                self.calendar_settings.day_end_hour = loaded_state.day_end_hour;
+                                self.calendar_settings.today_cutoff_hour =
+                                    loaded_state.today_cutoff_hour;
+                                self.calendar_settings.slot_min_gap_minutes =
+                                    loaded_state.slot_min_gap_minutes;
+                                self.calendar_settings.max_slots_per_day =
+                                    loaded_state.max_slots_per_day;
+                                self.setup_checklist_dismissed =
+                                    loaded_state.setup_checklist_dismissed;
+                                self.calendar_settings.show_unavailable_days =
+                                    loaded_state.show_unavailable_days;
+                                if let Some(fetched) = loaded_state.last_fetch {
+                                    self.apply_fetched_slots(fetched);
+                                    self.calendar.slots_restored =
+                                        self.calendar.last_fetch.is_some();
+                                }
+                                self.smtp.no_auth = loaded_state.smtp_no_auth;
+                                self.compose.skip_send_confirmation =
+                                    loaded_state.skip_send_confirmation;
+                                self.calendar_settings.max_slots_in_email =
+                                    loaded_state.max_slots_in_email;
+                                self.save.autosave_seconds = loaded_state.autosave_seconds;
+                                self.calendar_settings.pad_range_edges =
+                                    loaded_state.pad_range_edges;
+                                self.compose.no_slots_text = loaded_state.no_slots_text;
+                                self.calendar_settings.blackout_dates = loaded_state.blackout_dates;
+                                self.calendar_settings.reverse_slot_order =
+                                    loaded_state.reverse_slot_order;
+                                self.compose.priority = loaded_state.email_priority;
+                                self.compose.drip_enabled = loaded_state.drip_enabled;
+                                self.compose.drip_batch_size = loaded_state.drip_batch_size;
+                                self.compose.drip_interval_days = loaded_state.drip_interval_days;
+                                self.smtp.use_webhook = loaded_state.smtp_use_webhook;
+                                self.smtp.webhook_url = loaded_state.webhook_url;
+                                self.calendar_settings.readable_slot_lines =
+                                    loaded_state.readable_slot_lines;
+                                self.availability_page.path = loaded_state.availability_page_path;
+                                self.availability_page.base_url =
+                                    loaded_state.availability_base_url;
+                                self.compose.send_cooldown_minutes =
+                                    loaded_state.send_cooldown_minutes;
+                                self.calendar_settings.label_timezone = loaded_state.label_timezone;
+                                self.prefer_config_file = loaded_state.prefer_config_file;
+                                self.calendar_settings.slots_shown_in_list =
+                                    loaded_state.slots_shown_in_list;
+                                self.calendar.slot_notes = loaded_state.slot_notes;
+                                self.booking.title_template = loaded_state.booking_title_template;
+                                self.booking.meeting_minutes = loaded_state.booking_meeting_minutes;
+                                self.booking.with_meet = loaded_state.booking_with_meet;
+                                self.booking.booked = loaded_state.booked_meetings;
+                                self.compose.confirm_remove_above =
+                                    loaded_state.confirm_remove_above;
+                                self.compose.history = loaded_state.send_history;
+                                self.background.send_registry =
+                                    SendRegistry::from_history(&self.compose.history);
+                                self.follow_up.templates = loaded_state.follow_up_templates;
+                                self.compose.spam_check = loaded_state.spam_check;
+                                self.compose.spam_test_address = loaded_state.spam_test_address;
+                                self.compose.archive_bcc = loaded_state.email_archive_bcc;
+                                self.compose.abort_after_failures =
+                                    loaded_state.abort_after_failures;
+                                self.calendar_settings.buffer_longer_than =
+                                    loaded_state.buffer_longer_than;
+                                self.calendar_settings.weekly_rules = loaded_state.weekly_rules;
+                                self.calendar_settings.weekly_rules_enabled =
+                                    loaded_state.weekly_rules_enabled;
+                                self.calendar.weekly_rule_drafts = Default::default();
+                                self.compose.campaign = loaded_state.campaign;
+                                self.calendar_settings.lookahead_days = loaded_state.lookahead_days;
+                                self.calendar_settings.buffer_after_minutes =
+                                    loaded_state.calendar_buffer_after_minutes;
+                                self.calendar_settings.slot_calendar_ids =
+                                    loaded_state.slot_calendar_ids;
+                                self.calendar_settings.weekdays = loaded_state.weekdays;
+                                // Optional load paths
+                                // self.calendar_settings.credentials_path = loaded_state.credentials_path;
+                                // self.calendar_settings.token_cache_path = loaded_state.token_cache_path;
+                                self.status_message = "Loaded previous session state.".to_string();
+                                self.background.state_loaded_from_file = true;
+                                self.record_config_source(ConfigSource::SavedState, None);
+                            }
+                            Err(e) => {
+                                warn!(
+                                    "Failed to deserialize state file {:?}: {}. Using defaults.",
+                                    state_path, e
+                                );
+                                self.status_message =
+                                    "Failed to load saved state. Using defaults.".to_string();
+                                // Optionally delete the corrupt file?
+                                // fs::remove_file(state_path).ok();
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        warn!(
+                            "Failed to open state file {:?}: {}. Using defaults.",
+                            state_path, e
+                        );
+                        self.status_message =
+                            "Couldn't open state file. Using defaults.".to_string();
+                    }
+                }
+            } else {
+                info!("No state file found at {:?}. Using defaults.", state_path);
+                self.status_message = "No saved state found. Using defaults.".to_string();
+            }
+        } else {
+            warn!("Could not determine project directory for saving state. Using defaults.");
+            self.status_message = "State saving unavailable. Using defaults.".to_string();
+        }
+        // Blackout dates that have passed can't block anything any more
+        let today = Local::now().date_naive();
+        let blackout_dates = &mut self.calendar_settings.blackout_dates;
+        blackout_dates.retain(|&date| date >= today);
+        blackout_dates.sort_unstable();
+        blackout_dates.dedup();
+        self.load_drip_schedule();
+        self.load_frozen_slots();
+        // Nothing to autosave until something is edited
+        self.mark_state_saved();
+    }
+
+    /// The state file, creating its directory if needed.
+    pub(super) fn state_file_path() -> Result<PathBuf, String> {
+        // Use same identifiers as in load_state()
+        let proj_dirs = ProjectDirs::from("com", "YourOrg", "CoffeeChatHelper")
+            .ok_or("Could not determine project directory for saving state.")?;
+        let config_dir = proj_dirs.config_dir();
+        fs::create_dir_all(config_dir)
+            .map_err(|e| format!("Failed to create config directory {:?}: {}", config_dir, e))?;
+        Ok(config_dir.join("app_state.json"))
+    }
+
+    /// The slot settings in app_state.json, or `None` before the GUI has saved any.
+    pub fn saved_slot_settings() -> Result<Option<SavedSlotSettings>, String> {
+        let state_path = Self::state_file_path()?;
+        if !state_path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read(&state_path)
+            .map_err(|e| format!("Couldn't read {}: {}", state_path.display(), e))?;
+        let state: SavedAppState = serde_json::from_slice(&json)
+            .map_err(|e| format!("Couldn't parse {}: {}", state_path.display(), e))?;
+        let settings = SlotSettings {
+            buffer_minutes: state.calendar_buffer_minutes,
+            buffer_after_minutes: Some(state.calendar_buffer_after_minutes),
+            start_hour: state.day_start_hour,
+            end_hour: state.day_end_hour,
+            today_cutoff_hour: state.today_cutoff_hour,
+            min_gap_minutes: state.slot_min_gap_minutes,
+            max_per_day: state.max_slots_per_day,
+            pad_range_edges: state.pad_range_edges,
+            blackout_dates: state.blackout_dates,
+            buffer_longer_than: state.buffer_longer_than,
+            weekly_rules: state.weekly_rules_enabled.then_some(state.weekly_rules),
+            lookahead_days: state.lookahead_days,
+            weekdays: state.weekdays,
+            calendar_ids: state.slot_calendar_ids,
+        };
+        Ok(Some(SavedSlotSettings {
+            filters: settings.filters(),
+            lookahead_days: settings.lookahead_days,
+            calendar_ids: settings.calendar_ids,
+            max_in_email: state.max_slots_in_email as usize,
+        }))
+    }
+
+    /// Whether `json` is a state file this version can load.
+    pub(super) fn check_state_json(json: &[u8]) -> Result<(), String> {
+        serde_json::from_slice::<SavedAppState>(json)
+            .map(|_| ())
+            .map_err(|e| format!("The saved settings in the backup can't be read: {}", e))
+    }
+
+    /// The state file's contents for the current session.
+    pub(super) fn state_json(&self) -> Option<String> {
+        let state_to_save = SavedAppState {
+            smtp_host: self.smtp.host.clone(),
+            smtp_port_str: self.smtp.port_str.clone(),
+            smtp_user: self.smtp.user.clone(),
+            smtp_password: self.smtp.password.clone(),
+            from_email: self.smtp.from_email.clone(),
+            sender_name: self.compose.sender_name.clone(),
+            email_subject: self.compose.subject.clone(),
+            email_body: self.compose.body.clone(),
+            email_cc: self.compose.cc.clone(),
+            email_bcc: self.compose.bcc.clone(),
+            recipients: self.compose.recipients.clone(),
+            calendar_buffer_minutes: self.calendar_settings.buffer_before_minutes,
+            day_start_hour: self.calendar_settings.day_start_hour,
+            day_end_hour: self.calendar_settings.day_end_hour,
+            today_cutoff_hour: self.calendar_settings.today_cutoff_hour,
+            slot_min_gap_minutes: self.calendar_settings.slot_min_gap_minutes,
+            max_slots_per_day: self.calendar_settings.max_slots_per_day,
+            setup_checklist_dismissed: self.setup_checklist_dismissed,
+            last_fetch: self.calendar.last_fetch.clone(),
+            smtp_no_auth: self.smtp.no_auth,
+            show_unavailable_days: self.calendar_settings.show_unavailable_days,
+            skip_send_confirmation: self.compose.skip_send_confirmation,
+            max_slots_in_email: self.calendar_settings.max_slots_in_email,
+            autosave_seconds: self.save.autosave_seconds,
+            pad_range_edges: self.calendar_settings.pad_range_edges,
+            no_slots_text: self.compose.no_slots_text.clone(),
+            blackout_dates: self.calendar_settings.blackout_dates.clone(),
+            reverse_slot_order: self.calendar_settings.reverse_slot_order,
+            email_priority: self.compose.priority,
+            drip_enabled: self.compose.drip_enabled,
+            drip_batch_size: self.compose.drip_batch_size,
+            drip_interval_days: self.compose.drip_interval_days,
+            smtp_use_webhook: self.smtp.use_webhook,
+            webhook_url: self.smtp.webhook_url.clone(),
+            readable_slot_lines: self.calendar_settings.readable_slot_lines,
+            availability_page_path: self.availability_page.path.clone(),
+            availability_base_url: self.availability_page.base_url.clone(),
+            send_cooldown_minutes: self.compose.send_cooldown_minutes,
+            label_timezone: self.calendar_settings.label_timezone,
+            prefer_config_file: self.prefer_config_file,
+            slots_shown_in_list: self.calendar_settings.slots_shown_in_list,
+            slot_notes: self.calendar.slot_notes.clone(),
+            booking_title_template: self.booking.title_template.clone(),
+            booking_meeting_minutes: self.booking.meeting_minutes,
+            booking_with_meet: self.booking.with_meet,
+            booked_meetings: self.booking.booked.clone(),
+            confirm_remove_above: self.compose.confirm_remove_above,
+            send_history: self.compose.history.clone(),
+            follow_up_templates: self.follow_up.templates.clone(),
+            spam_check: self.compose.spam_check,
+            spam_test_address: self.compose.spam_test_address.clone(),
+            email_archive_bcc: self.compose.archive_bcc.clone(),
+            abort_after_failures: self.compose.abort_after_failures,
+            buffer_longer_than: self.calendar_settings.buffer_longer_than,
+            weekly_rules: self.calendar_settings.weekly_rules.clone(),
+            weekly_rules_enabled: self.calendar_settings.weekly_rules_enabled,
+            campaign: self.compose.campaign.clone(),
+            lookahead_days: self.calendar_settings.lookahead_days,
+            calendar_buffer_after_minutes: self.calendar_settings.buffer_after_minutes,
+            slot_calendar_ids: self.calendar_settings.slot_calendar_ids.clone(),
+            weekdays: self.calendar_settings.weekdays,
+            // Optional save paths
+            // credentials_path: self.calendar_settings.credentials_path.clone(),
+            // token_cache_path: self.calendar_settings.token_cache_path.clone(),
+        };
+        match serde_json::to_string_pretty(&state_to_save) {
+            Ok(json) => Some(json),
+            Err(e) => {
+                error!("Failed to serialize application state: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Remembers the current session as saved, so autosave only writes after a change.
+    pub(super) fn mark_state_saved(&mut self) {
+        self.save.saved_json = self.state_json();
+        self.save.checked_at = Some(Instant::now());
+        self.save.dirty = false;
+    }
+
+    /// Writes the session to app_state.json if it changed since the last save.
+    pub(super) fn save_state(&mut self) {
+        if self.demo_mode {
+            info!("Demo mode: not saving application state.");
+            return;
+        }
+        if self.reset_data.wiped {
+            info!("Data was reset: not saving application state.");
+            return;
+        }
+        // Nothing was loaded, so saving would write the defaults over the session
+        if self.safe_mode.active || self.safe_mode.start_pending {
+            info!("Safe mode: not saving application state.");
+            return;
+        }
+        let Some(json) = self.state_json() else {
+            return;
+        };
+        if self.save.saved_json.as_ref() == Some(&json) {
+            info!("Application state unchanged since the last save.");
+            return;
+        }
+        info!("Attempting to save application state...");
+        let written = Self::state_file_path().and_then(|state_path| {
+            fs::write(&state_path, &json)
+                .map(|()| state_path.clone())
+                .map_err(|e| format!("Failed to write state file {:?}: {}", state_path, e))
+        });
+        match written {
+            Ok(state_path) => {
+                info!("Application state saved successfully to {:?}", state_path);
+                self.save.saved_json = Some(json);
+                self.save.saved_at = Some(Local::now());
+                self.save.dirty = false;
+                self.save.error = None;
+            }
+            Err(e) => {
+                error!("{}", e);
+                self.save.error = Some(e);
+            }
+        }
+    }
+
+    /// Whether the session has changes app_state.json doesn't have yet.
+    /// Demo and safe mode never save, so they never have unsaved changes either.
+    pub(super) fn refresh_dirty_flag(&mut self) {
+        self.save.dirty =
+            !self.demo_mode && !self.safe_mode.active && self.state_json() != self.save.saved_json;
+    }
+
+    /// Saves from `update` every `save.autosave_seconds`, if anything changed.
+    pub(super) fn autosave_if_due(&mut self) {
+        let Some(interval) = self.save.autosave_interval() else {
+            return; // Off: only saved on exit
+        };
+        if self
+            .save
+            .checked_at
+            .is_some_and(|checked| checked.elapsed() < interval)
+        {
+            return;
+        }
+        self.save.checked_at = Some(Instant::now());
+        self.save_state();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Weekday;
+
+    // A state file from before CC/BCC, slot limits and the other later fields
+    const OLD_STATE: &str = r#"{
+        "smtp_host": "smtp.example.com",
+        "smtp_port_str": "587",
+        "smtp_user": "me",
+        "smtp_password": "hunter2",
+        "from_email": "me@example.com",
+        "sender_name": "Me",
+        "email_subject": "Coffee?",
+        "email_body": "Hi {{recipient_name}}",
+        "recipients": [{"name": "Ana", "email": "ana@example.com"}],
+        "calendar_buffer_minutes": 10,
+        "day_start_hour": 8,
+        "day_end_hour": 18
+    }"#;
+
+    #[test]
+    fn old_state_files_still_load() {
+        let state: SavedAppState = serde_json::from_str(OLD_STATE).unwrap();
+        assert_eq!(state.smtp_password.expose_secret(), "hunter2");
+        assert_eq!(state.recipients[0].email, "ana@example.com");
+        assert!(state.recipients[0].fields.is_empty());
+        assert_eq!(state.email_cc, "");
+        assert_eq!(state.today_cutoff_hour, None);
+        assert_eq!(state.buffer_longer_than, None);
+        assert!(state.last_fetch.is_none());
+        assert!(!state.smtp_no_auth);
+        assert_eq!(state.autosave_seconds, DEFAULT_AUTOSAVE_SECONDS);
+        assert_eq!(state.lookahead_days, calendar::DEFAULT_LOOKAHEAD_DAYS);
+        assert_eq!(state.calendar_buffer_minutes, Minutes(10));
+        assert_eq!(state.calendar_buffer_after_minutes, Minutes(10));
+        assert_eq!(state.day_start_hour, HourOfDay::new(8).unwrap());
+        assert!(state.slot_calendar_ids.is_empty());
+        assert!(state.weekdays.is_every_day());
+
+        // Saved while slots came from one calendar at most
+        let one_calendar = OLD_STATE.replacen('{', r#"{"slot_calendar_id": "work@acme.com","#, 1);
+        let state: SavedAppState = serde_json::from_str(&one_calendar).unwrap();
+        assert_eq!(state.slot_calendar_ids, vec!["work@acme.com".to_string()]);
+    }
+
+    #[test]
+    fn saved_state_round_trips() {
+        let mut state: SavedAppState = serde_json::from_str(OLD_STATE).unwrap();
+        state.email_bcc = "boss@example.com".to_string();
+        state.today_cutoff_hour = Some(15);
+        state.show_unavailable_days = true;
+        state.buffer_longer_than = Some(Minutes(20));
+        state.lookahead_days = 30;
+        state.calendar_buffer_after_minutes = Minutes(20);
+        state.slot_calendar_ids = vec!["a@x.com".to_string(), "b@x.com".to_string()];
+        state.weekdays.set(Weekday::Sat, false);
+        state.weekdays.set(Weekday::Sun, false);
+
+        let json = serde_json::to_string(&state).unwrap();
+        // Still plain numbers, so older builds can read the file back
+        assert!(json.contains(r#""calendar_buffer_minutes":10"#));
+        assert!(json.contains(r#""day_end_hour":18"#));
+        let reloaded: SavedAppState = serde_json::from_str(&json).unwrap();
+        assert_eq!(reloaded.smtp_password.expose_secret(), "hunter2");
+        assert_eq!(reloaded.email_bcc, "boss@example.com");
+        assert_eq!(reloaded.today_cutoff_hour, Some(15));
+        assert_eq!(u32::from(reloaded.day_end_hour), 18);
+        assert!(reloaded.show_unavailable_days);
+        assert_eq!(reloaded.buffer_longer_than, Some(Minutes(20)));
+        assert_eq!(reloaded.lookahead_days, 30);
+        assert_eq!(reloaded.calendar_buffer_minutes, Minutes(10));
+        assert_eq!(reloaded.calendar_buffer_after_minutes, Minutes(20));
+        assert_eq!(reloaded.slot_calendar_ids, state.slot_calendar_ids);
+        assert_eq!(reloaded.weekdays, state.weekdays);
+        assert!(json.contains(r#""weekdays":[true,true,true,true,true,false,false]"#));
+    }
+}
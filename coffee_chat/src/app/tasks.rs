@@ -0,0 +1,999 @@
+// src/app/tasks.rs
+// Work that runs off the UI thread: config loading, calendar auth, fetching and sending.
+
+use super::messages::Message;
+use super::{FetchedSlots, MyApp, SendOutcome, SendResult, UIRecipient};
+use crate::calendar;
+use crate::calendar::connect_error::{CalendarConnectError, ConnectErrorKind};
+use crate::calendar::offered::OfferedSlots;
+use crate::calendar::proxy::{self, ProxyConnector};
+use crate::calendar::timezone::LocalZone;
+use crate::calendar::token_refresh::{ReauthRequired, CALENDAR_READONLY_SCOPE};
+use crate::calendar::{BusySource, GoogleCalendar};
+use crate::config::{
+    dedupe_recipients, validate_archive_bcc, AppConfig, Recipient, SmtpConfig, WebhookConfig,
+};
+use crate::drip::{DripSchedule, Invitation};
+use crate::email_sender::{
+    capabilities,
+    failure_streak::FailureStreak,
+    history::{self, RecordedSlot},
+    post_process::NoopPostProcessor,
+    readability,
+    send_context::SendContext,
+    send_eta::{SendEta, DEFAULT_TIME_PER_EMAIL},
+    template::{self, write_default_template, EmailTemplate, TemplateError},
+    Transport,
+};
+use chrono::Utc;
+use google_calendar3::CalendarHub;
+// Use the yup_oauth2 hyper client if feature enabled, otherwise stick to manual build
+#[cfg(not(feature = "yup-oauth2-hyper-client"))] // Fallback if feature not enabled
+use hyper_util::client::legacy::Client;
+#[cfg(feature = "yup-oauth2-hyper-client")] // Conditional compilation can be used
+use yup_oauth2::hyper_client; // Only needed for manual client build
+
+use hyper_rustls::HttpsConnectorBuilder;
+use hyper_util::rt::TokioExecutor;
+use log::{debug, error, info, warn};
+use secrecy::ExposeSecret;
+use std::path::PathBuf;
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time::Instant;
+use yup_oauth2::client::CustomHyperClientBuilder;
+use yup_oauth2::{read_application_secret, InstalledFlowAuthenticator, InstalledFlowReturnMethod};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::process::Command;
+use yup_oauth2::authenticator_delegate::InstalledFlowDelegate;
+
+/// Why recipients left over when a batch stops are skipped.
+pub(super) const NOT_SENT: &str = "Not sent: the batch stopped";
+
+// Long enough to sign in and approve; a closed consent tab would otherwise be waited on forever
+const CONSENT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3 * 60);
+
+struct BrowserFlowDelegate;
+
+impl InstalledFlowDelegate for BrowserFlowDelegate {
+    fn present_user_url<'a>(
+        &'a self,
+        url: &'a str,
+        _need_code: bool,
+    ) -> Pin<Box<dyn Future<Output = Result<String, String>> + Send + 'a>> {
+        Box::pin(async move {
+            info!("Opening OAuth URL in browser: {}", url);
+
+            // Try to open browser - first attempt with xdg-open (Linux/WSL)
+            if let Err(e) = Command::new("xdg-open").arg(url).spawn() {
+                warn!("Failed to open browser with xdg-open: {}", e);
+
+                // Fallback to "open" for macOS
+                if let Err(e) = Command::new("open").arg(url).spawn() {
+                    warn!("Failed to open browser with open: {}", e);
+
+                    // Fallback to "cmd.exe /c start" for Windows
+                    if let Err(e) = Command::new("cmd.exe")
+                        .args(&["/c", "start", "", url])
+                        .spawn()
+                    {
+                        warn!("Failed to open browser with cmd.exe: {}", e);
+
+                        // Last resort: Print URL and instruct user
+                        println!("Please open this URL in your browser:");
+                        println!("{}", url);
+                    }
+                }
+            }
+
+            // Return empty string because we're using the HTTP redirect flow
+            // which doesn't need a manual code entry
+            Ok(String::new())
+        })
+    }
+}
+
+/// Loads config.toml and the email template in the background; results arrive as messages.
+pub(super) fn spawn_initial_load(initial_sender: mpsc::Sender<Message>) {
+    // --- Initial config/template loading task ---
+    // This still runs in the background after app starts
+    thread::spawn(move || {
+        match AppConfig::load() {
+            // Tries to load config.toml
+            Ok(config) => {
+                let config_clone = config.clone();
+                // Send message even if state loaded later, App::update decides how to use it
+                initial_sender
+                    .send(Message::ConfigLoaded(Ok(Box::new(config))))
+                    .ok();
+                if !config_clone.sender.has_template_source() {
+                    // The editor's subject and body are the template
+                    initial_sender.send(Message::TemplateLoaded(Ok(None))).ok();
+                    return;
+                }
+                match EmailTemplate::from_sender_config(&config_clone.sender) {
+                    // Tries to load template
+                    Ok(template) => {
+                        initial_sender
+                            .send(Message::TemplateLoaded(Ok(Some((
+                                template.subject_template,
+                                template.body_template,
+                            )))))
+                            .ok();
+                    }
+                    Err(e) => {
+                        if let TemplateError::Missing { resolved } = &e {
+                            initial_sender
+                                .send(Message::TemplateMissing(
+                                    resolved.clone(),
+                                    Box::new(config_clone.sender.clone()),
+                                ))
+                                .ok();
+                        }
+                        initial_sender
+                            .send(Message::TemplateLoaded(Err(format!(
+                                "Failed to load template initially: {}",
+                                e
+                            ))))
+                            .ok();
+                    }
+                }
+            }
+            Err(e) => {
+                initial_sender
+                    .send(Message::ConfigLoaded(Err(format!(
+                        "Failed to load config initially: {}",
+                        e
+                    ))))
+                    .ok();
+                initial_sender
+                    .send(Message::TemplateLoaded(Err(
+                        "Template not loaded (initial config error)".to_string(),
+                    )))
+                    .ok();
+            }
+        }
+    });
+    // --- End initial loading task ---
+}
+
+impl MyApp {
+    /// Writes the default template to the missing template_path and loads it the way
+    /// it would have been at start.
+    pub(super) fn create_missing_template(&mut self) {
+        let Some(missing) = &mut self.compose.missing_template else {
+            return;
+        };
+        if let Err(e) = write_default_template(&missing.path) {
+            error!("Creating {} failed: {}", missing.path.display(), e);
+            missing.error = Some(format!("Couldn't create it: {}", e));
+            return;
+        }
+        info!(
+            "Created {} from the default template.",
+            missing.path.display()
+        );
+        let Some(missing) = self.compose.missing_template.take() else {
+            return;
+        };
+        self.status_message = match EmailTemplate::from_sender_config(&missing.sender) {
+            // Like at start, a saved session's subject and body are kept
+            Ok(_) if self.background.state_loaded_from_file => format!(
+                "Created {}. Your saved subject and body are unchanged.",
+                missing.path.display()
+            ),
+            Ok(template) => {
+                self.compose.subject = template.subject_template;
+                self.compose.body = template.body_template;
+                format!("Created {} and loaded it.", missing.path.display())
+            }
+            Err(e) => format!(
+                "Created {}, but it couldn't be loaded: {}",
+                missing.path.display(),
+                e
+            ),
+        };
+    }
+
+    // (handle_connect_calendar remains the same)
+    pub(super) fn handle_connect_calendar(&mut self) {
+        if self.background.is_connecting_calendar {
+            return;
+        }
+        self.background.is_connecting_calendar = true;
+        self.calendar.error = None;
+        self.calendar.show_error_details = false;
+        self.calendar.calendars = None;
+        self.calendar.status = "Calendar: Connecting...".to_string();
+        self.status_message =
+            "Attempting to connect to Google Calendar... Check your browser.".to_string();
+        self.clear_slots();
+        if self.demo_mode {
+            self.connect_demo_calendar();
+            return;
+        }
+        let sender = self.background.sender.clone();
+        let rt_handle = self.ensure_runtime().handle().clone();
+        let creds_path = self.calendar_settings.credentials_path.clone();
+        let token_cache = self.calendar_settings.token_cache_path.clone();
+        let task = rt_handle.spawn(async move {
+            info!("Starting calendar connection task.");
+            match Self::setup_calendar_hub(&creds_path, &token_cache).await {
+                Ok(google) => {
+                    info!("Successfully connected to Google Calendar.");
+                    let hub = google.hub.clone();
+                    sender
+                        .send(Message::CalendarConnected(BusySource::Google(google)))
+                        .ok();
+                    // Names and colors for the Calendars section; not needed to fetch slots
+                    let listed = calendar::calendars::list_calendars(&hub)
+                        .await
+                        .map_err(|e| calendar::describe_fetch_error(&*e));
+                    sender.send(Message::CalendarsListed(listed)).ok();
+                }
+                Err(e) => {
+                    error!("Failed to connect to Google Calendar: {}", e.details);
+                    sender.send(Message::CalendarConnectionFailed(e)).ok();
+                }
+            }
+        });
+        self.background.connect_task = Some(task);
+    }
+
+    /// Cancel button while connecting: stops waiting for the browser consent.
+    pub(super) fn cancel_connect_calendar(&mut self) {
+        if let Some(task) = self.background.connect_task.take() {
+            task.abort();
+        }
+        info!("Calendar connection cancelled.");
+        self.background.is_connecting_calendar = false;
+        self.calendar.status = "Calendar: Not Connected".to_string();
+        self.status_message = "Calendar connection cancelled.".to_string();
+    }
+
+    /// Whether the connect task has ended. Checked before processing messages, so
+    /// anything it sent on the way out is handled in the same frame.
+    pub(super) fn connect_task_finished(&self) -> bool {
+        self.background
+            .connect_task
+            .as_ref()
+            .is_some_and(|task| task.is_finished())
+    }
+
+    /// Clears "Connecting..." if the task ended without reporting back (it panicked).
+    pub(super) fn reap_connect_task(&mut self, finished: bool) {
+        if !finished {
+            return;
+        }
+        self.background.connect_task = None;
+        if self.background.is_connecting_calendar {
+            error!("Calendar connection task ended without a result.");
+            self.background.is_connecting_calendar = false;
+            self.calendar.status = "Calendar: Connection Failed".to_string();
+            self.status_message =
+                "Calendar connection stopped unexpectedly. Try connecting again.".to_string();
+        }
+    }
+
+    // FIX: Use yup_oauth2::hyper_client Builder for correct client type
+    pub(crate) async fn setup_calendar_hub(
+        creds_path: &str,
+        token_cache: &str,
+    ) -> Result<GoogleCalendar, CalendarConnectError> {
+        info!("Reading application secret from: {}", creds_path);
+        let secret = read_application_secret(PathBuf::from(creds_path))
+            .await
+            .map_err(|e| CalendarConnectError::from_secret_error(creds_path, &e))?;
+
+        // Honors HTTPS_PROXY / ALL_PROXY / NO_PROXY for both the OAuth flow and Calendar calls
+        let https = HttpsConnectorBuilder::new()
+            .with_native_roots()
+            .map_err(|e| CalendarConnectError::new(ConnectErrorKind::Other, &e))?
+            .https_only()
+            .enable_http1()
+            .wrap_connector(ProxyConnector::from_env());
+        if let Some(proxy) = proxy::google_api_proxy() {
+            info!("Using proxy {} for Google API traffic.", proxy);
+        }
+
+        info!("Building authenticator (token cache: {})...", token_cache);
+
+        // Picked here rather than by the flow, so a listener that fails can name its port
+        let redirect_port = std::net::TcpListener::bind(("127.0.0.1", 0))
+            .and_then(|listener| listener.local_addr())
+            .map_err(|e| CalendarConnectError::from_listener_error(None, &e))?
+            .port();
+
+        // Create a custom auth flow that opens the browser automatically
+        let auth_client = Client::builder(TokioExecutor::new()).build(https.clone());
+        let auth = InstalledFlowAuthenticator::with_client(
+            secret,
+            InstalledFlowReturnMethod::HTTPPortRedirect(redirect_port),
+            CustomHyperClientBuilder::from(auth_client),
+        )
+        .persist_tokens_to_disk(PathBuf::from(token_cache))
+        .flow_delegate(Box::new(BrowserFlowDelegate {})) // Add custom flow delegate
+        .build()
+        .await
+        .map_err(|e| CalendarConnectError::from_token_cache_error(token_cache, &e))?;
+
+        info!("Authenticator built.");
+
+        // Run the consent flow now (instead of on the first API call) so auth
+        // problems surface here, where they can be classified.
+        tokio::time::timeout(CONSENT_TIMEOUT, auth.token(&[CALENDAR_READONLY_SCOPE]))
+            .await
+            .map_err(|e| {
+                let minutes = CONSENT_TIMEOUT.as_secs() / 60;
+                CalendarConnectError::new(ConnectErrorKind::TimedOut { minutes }, &e)
+            })?
+            .map_err(|e| CalendarConnectError::from_auth_error(&e, redirect_port))?;
+        info!("Authorization token obtained.");
+
+        // wrap in hyper-util client
+        let client = Client::builder(hyper_util::rt::TokioExecutor::new()).build(https);
+
+        // Use explicit typing to help with trait resolution
+        let hub: CalendarHub<_> = CalendarHub::new(client, auth.clone());
+
+        Ok(GoogleCalendar {
+            hub: Arc::new(hub),
+            auth,
+        })
+    }
+
+    /// Send button: validates the settings, then asks for confirmation unless that's turned off.
+    pub(super) fn request_send_invitations(&mut self) {
+        if self.batch_transport().is_none() {
+            return;
+        }
+        self.compose.readability_warning = self.send_readability_warning();
+        self.compose.spam_report = self.send_spam_report();
+        self.compose.spam_test = None;
+        self.compose.exclude_role_accounts = false;
+        if self.compose.skip_send_confirmation {
+            if let Some(warning) = &self.compose.readability_warning {
+                warn!("{}", warning);
+            }
+            if let Some(report) = &self.compose.spam_report {
+                if report.risk() != "low" {
+                    warn!("Spam check scored the email {:.1}.", report.score());
+                }
+            }
+            self.handle_send_invitations();
+        } else {
+            self.compose.confirming_send = true;
+        }
+    }
+
+    /// Renders every recipient's email with the slots it would get and warns if the
+    /// longest lists more slot lines than the readability setting allows.
+    pub(super) fn send_readability_warning(&self) -> Option<String> {
+        let (recipients, _) = self.recipients_for_send();
+        let template = self
+            .compose_invitation()
+            .template(self.email_unavailable_days())
+            .ok()? // A broken template fails the send with its own message
+            .with_offered_slots(self.offered_slots());
+        readability::send_warning(
+            &template,
+            &recipients,
+            &self.compose.sender_name,
+            &self.email_availabilities(),
+            self.calendar_settings.readable_slot_lines as usize,
+        )
+        .ok()?
+    }
+
+    /// The SMTP Settings fields, by their label, that still need filling in before
+    /// anything can be sent. The same checks `transport_for_send` makes.
+    pub(super) fn missing_send_settings(&self) -> Vec<&'static str> {
+        if self.smtp.use_webhook && !self.demo_mode {
+            return match self.smtp.webhook_url.trim() {
+                "" => vec!["Webhook URL"],
+                _ => Vec::new(),
+            };
+        }
+        let smtp = &self.smtp;
+        let (no_user, no_password) = (
+            smtp.user.is_empty(),
+            smtp.password.expose_secret().is_empty(),
+        );
+        // Both empty is fine: that sends without logging in
+        let partial_login = !smtp.no_auth && no_user != no_password;
+        [
+            ("Host", smtp.host.is_empty()),
+            ("Port", smtp.port_str.parse::<u16>().is_err()),
+            ("Username", partial_login && no_user),
+            ("Password", partial_login && no_password),
+            ("From Email", smtp.from_email.is_empty()),
+        ]
+        .into_iter()
+        .filter(|(_, missing)| *missing)
+        .map(|(label, _)| label)
+        .collect()
+    }
+
+    /// Where a send goes, from the settings, or `None` with the problem in the status bar.
+    pub(super) fn transport_for_send(&mut self) -> Option<Transport> {
+        if self.background.is_sending_email {
+            self.status_message = "Already sending emails...".to_string();
+            return None;
+        }
+        if let Err(e) = validate_archive_bcc(&self.compose.archive_bcc) {
+            self.status_message = format!("Error: The {}.", e);
+            error!("Attempted send with an invalid archive BCC.");
+            return None;
+        }
+        // Demo mode keeps its sample SMTP settings even if a webhook was picked
+        if self.smtp.use_webhook && !self.demo_mode {
+            let webhook = WebhookConfig {
+                url: self.smtp.webhook_url.trim().to_string(),
+            };
+            if let Err(e) = webhook.validate() {
+                self.status_message = format!("Error: The {}.", e);
+                error!("Attempted send with an invalid webhook URL.");
+                return None;
+            }
+            return Some(Transport::Webhook(webhook));
+        }
+        let port = match self.smtp.port_str.parse::<u16>() {
+            Ok(p) => p,
+            Err(_) => {
+                self.status_message = "Invalid SMTP Port number.".to_string();
+                error!("Invalid SMTP port entered: {}", self.smtp.port_str);
+                return None;
+            }
+        };
+        let smtp_config = SmtpConfig {
+            host: self.smtp.host.clone(),
+            port,
+            user: self.smtp.user.clone(),
+            password: self.smtp.password.clone(),
+            from_email: self.smtp.from_email.clone(),
+            tls_mode: self.smtp.tls_mode,
+            proxy: self.smtp.proxy.clone(),
+            no_auth: self.smtp.no_auth,
+        };
+        if smtp_config.host.is_empty() || smtp_config.from_email.is_empty() {
+            self.status_message = format!(
+                "Error: Missing required SMTP settings ({}).",
+                self.missing_send_settings().join(", ")
+            );
+            error!("Attempted send with incomplete SMTP config.");
+            return None;
+        }
+        // Leaving both user and password empty also means no authentication
+        if smtp_config.uses_auth()
+            && (smtp_config.user.is_empty() || smtp_config.get_password().is_empty())
+        {
+            self.status_message =
+                "Error: Enter both SMTP User and Password, or tick \"No authentication\"."
+                    .to_string();
+            error!("Attempted send with a partial SMTP login.");
+            return None;
+        }
+        if self.demo_mode {
+            Some(Transport::Demo(smtp_config))
+        } else {
+            Some(Transport::Smtp(smtp_config))
+        }
+    }
+
+    /// "Check" in the SMTP panel: connects and reads what the server supports, even if
+    /// it was checked before, e.g. after changing the host.
+    pub(super) fn check_smtp_server(&mut self) {
+        let config = match self.transport_for_send() {
+            Some(Transport::Smtp(config)) => config,
+            Some(_) => {
+                self.status_message = "Only an SMTP server can be checked.".to_string();
+                return;
+            }
+            None => return,
+        };
+        self.smtp.checking_server = true;
+        let sender = self.background.sender.clone();
+        self.ensure_runtime().spawn_blocking(move || {
+            let checked = capabilities::refresh(&config).map_err(|e| e.to_string());
+            sender.send(Message::SmtpServerChecked(checked)).ok();
+        });
+    }
+
+    /// Same as `transport_for_send`, but sending to the list needs someone on it.
+    pub(super) fn batch_transport(&mut self) -> Option<Transport> {
+        if self.compose.recipients.is_empty() {
+            self.status_message = "Cannot send: No recipients added.".to_string();
+            return None;
+        }
+        self.transport_for_send()
+    }
+
+    /// The slot lines for `{{ availabilities }}`, capped at the per-email limit.
+    pub(super) fn email_availabilities(&self) -> Vec<String> {
+        if let Some(frozen) = &self.freeze.frozen {
+            return frozen.lines.clone();
+        }
+        let annotated = calendar::free_busy::annotate_slots(
+            &self.calendar.available_slots,
+            &self.calendar.slot_notes,
+        );
+        let mut lines = calendar::free_busy::cap_summary(
+            &annotated,
+            self.calendar_settings.max_slots_in_email as usize,
+        );
+        if self.calendar_settings.label_timezone && !lines.is_empty() {
+            lines.extend(self.timezone_label());
+        }
+        lines
+    }
+
+    /// The slots behind [`Self::email_availabilities`], for writing them in a recipient's
+    /// language. `None` without a fetch, e.g. while slots are pasted in by hand.
+    pub(super) fn offered_slots(&self) -> Option<OfferedSlots> {
+        if let Some(frozen) = &self.freeze.frozen {
+            return frozen.offered.clone();
+        }
+        let fetched = self.calendar.last_fetch.as_ref()?;
+        let unavailable = if self.calendar_settings.show_unavailable_days {
+            Self::fetched_unavailable_days(fetched)
+        } else {
+            Vec::new()
+        };
+        Some(OfferedSlots {
+            windows: fetched.windows.clone(),
+            unavailable,
+            order: self.slot_order(),
+            notes: self.calendar.slot_notes.clone(),
+            max_in_email: self.calendar_settings.max_slots_in_email as usize,
+            label: self
+                .timezone_label()
+                .filter(|_| self.calendar_settings.label_timezone),
+        })
+    }
+
+    /// The lines an email lists, each with the window behind it, for the history.
+    pub(super) fn recorded_slots(&self, availabilities: &[String]) -> Vec<RecordedSlot> {
+        let windows = self
+            .offered_slots()
+            .map(|offered| offered.slot_windows())
+            .unwrap_or_default();
+        history::record_slots(availabilities, &windows)
+    }
+
+    /// "(all times ET)" for the zone the slots were fetched in.
+    pub(super) fn timezone_label(&self) -> Option<String> {
+        let fetched = self.calendar.last_fetch.as_ref()?;
+        let zone = fetched.zone.clone().unwrap_or_else(LocalZone::current);
+        Some(calendar::timezone::availability_label(
+            &zone.abbreviation(fetched.fetched_at),
+        ))
+    }
+
+    // (handle_send_invitations remains the same)
+    /// The recipients in send order (pinned first), and the exact duplicates left out.
+    pub(super) fn recipients_for_send(&self) -> (Vec<Recipient>, Vec<Recipient>) {
+        // The stable sort keeps list order within each group, so the first copy wins
+        let mut ordered: Vec<&UIRecipient> = self.compose.recipients.iter().collect();
+        ordered.sort_by_key(|ui_r| !ui_r.pinned);
+        let recipients = ordered
+            .into_iter()
+            .map(|ui_r| Recipient {
+                name: ui_r.name.clone(),
+                email: ui_r.email.clone(),
+                fields: ui_r.fields.clone(),
+            })
+            .collect();
+        dedupe_recipients(recipients)
+    }
+
+    /// Renames campaign `from` everywhere it's kept: the history, a running drip
+    /// schedule, and the name the next send uses. A blank `to` clears it.
+    pub(super) fn rename_campaign(&mut self, from: &str, to: &str) {
+        let renamed = history::rename_campaign(&mut self.compose.history, from, to);
+        let to = history::campaign_name(to);
+        let drip_campaign =
+            |schedule: &DripSchedule| schedule.invitation.campaign.as_deref() == Some(from);
+        if self.drip.schedule.as_ref().is_some_and(drip_campaign) {
+            self.change_drip_schedule(|schedule| {
+                if let Some(schedule) = schedule.as_mut().filter(|schedule| drip_campaign(schedule))
+                {
+                    schedule.invitation.campaign = to.clone();
+                }
+            });
+        }
+        if self.compose.campaign.trim() == from {
+            self.compose.campaign = to.clone().unwrap_or_default();
+        }
+        info!(
+            "Campaign \"{}\" renamed in {} history entries.",
+            from, renamed
+        );
+        self.status_message = match to {
+            Some(to) => format!(
+                "Renamed campaign \"{}\" to \"{}\" ({} history entries).",
+                from, to, renamed
+            ),
+            None => format!(
+                "Took {} history entries out of campaign \"{}\".",
+                renamed, from
+            ),
+        };
+    }
+
+    /// The role addresses (info@, noreply@, ...) among `recipients`.
+    pub(super) fn role_flagged<'a>(&self, recipients: &'a [Recipient]) -> Vec<&'a str> {
+        self.compose
+            .role_accounts
+            .flagged(recipients.iter().map(|r| r.email.as_str()))
+    }
+
+    pub(super) fn handle_send_invitations(&mut self) {
+        let Some(transport) = self.batch_transport() else {
+            return;
+        };
+        if self.email_availabilities().is_empty() {
+            if self.calendar.source.is_some() {
+                warn!("Proceeding to send email, but no available slots were fetched or found.");
+            } else {
+                warn!("Proceeding to send email without calendar connection/slots.");
+            }
+        }
+        let (mut recipients_to_send, duplicates) = self.recipients_for_send();
+        for duplicate in &duplicates {
+            info!("Skipping duplicate recipient {}", duplicate.email);
+        }
+        let skipped = duplicates.len();
+        let mut excluded = Vec::new();
+        if std::mem::take(&mut self.compose.exclude_role_accounts) {
+            let role_accounts = &self.compose.role_accounts;
+            (excluded, recipients_to_send) = recipients_to_send
+                .into_iter()
+                .partition(|r| role_accounts.is_role(&r.email));
+            if !excluded.is_empty() {
+                let emails: Vec<_> = excluded.iter().map(|r| r.email.as_str()).collect();
+                info!(
+                    "Excluded {} role addresses from this send: {}",
+                    emails.len(),
+                    emails.join(", ")
+                );
+            }
+        } else {
+            let flagged = self.role_flagged(&recipients_to_send);
+            if !flagged.is_empty() {
+                warn!("Sending to role addresses: {}", flagged.join(", "));
+            }
+        }
+        if self.drips(recipients_to_send.len()) {
+            self.start_drip(transport, recipients_to_send, skipped);
+        } else {
+            let invitation = self.compose_invitation();
+            self.spawn_send(transport, recipients_to_send, skipped, invitation);
+        }
+        // After the send cleared the last batch's results, so they're in this one's report
+        if self.background.is_sending_email {
+            for recipient in excluded {
+                self.compose.send_results.push(SendResult {
+                    email: recipient.email,
+                    outcome: SendOutcome::Skipped(
+                        "Role address, excluded before sending".to_string(),
+                    ),
+                    one_off: false,
+                    options: Vec::new(),
+                });
+            }
+        }
+    }
+
+    /// What each email of the next batch is expected to take: the last batch's
+    /// average this session, or a guess.
+    pub(super) fn time_per_email(&self) -> std::time::Duration {
+        self.compose
+            .time_per_email
+            .unwrap_or(DEFAULT_TIME_PER_EMAIL)
+    }
+
+    /// How long after an email the same person is skipped by every send path.
+    pub(super) fn send_cooldown(&self) -> std::time::Duration {
+        std::time::Duration::from_secs(u64::from(self.compose.send_cooldown_minutes) * 60)
+    }
+
+    /// Sends `invitation` to each of `recipients` in the background with the current
+    /// slots, reporting every outcome and then `FinishedSending`. Used by normal sends
+    /// and drip batches.
+    pub(super) fn spawn_send(
+        &mut self,
+        transport: Transport,
+        recipients_to_send: Vec<Recipient>,
+        skipped: usize,
+        invitation: Invitation,
+    ) {
+        let availabilities = self.email_availabilities();
+        let unavailable_days = self.email_unavailable_days();
+        let offered_slots = self.offered_slots();
+        let slot_windows = offered_slots
+            .as_ref()
+            .map(OfferedSlots::slot_windows)
+            .unwrap_or_default();
+        self.background.is_sending_email = true;
+        self.compose.send_results.clear();
+        self.compose.send_eta = Some(SendEta::start(
+            recipients_to_send.len(),
+            self.time_per_email(),
+            Instant::now(),
+        ));
+        self.status_message = format!(
+            "Sending emails to {} recipients...",
+            recipients_to_send.len()
+        );
+        if let Some(warning) = self.slot_staleness_warning() {
+            warn!("Sending with possibly outdated slots: {}", warning);
+            self.status_message = format!("{} (Warning: {})", self.status_message, warning);
+        }
+        if let Some(warning) = self.frozen_busy_warning() {
+            warn!("Sending with frozen slots: {}", warning);
+            self.status_message = format!("{} (Warning: {})", self.status_message, warning);
+        }
+        let rt = self.ensure_runtime().handle().clone();
+        let sender_clone = self.background.sender.clone();
+        let registry = self.background.send_registry.clone();
+        let cooldown = self.send_cooldown();
+        let mut streak = FailureStreak::new(self.compose.abort_after_failures);
+        // Same slots for everyone, so the same numbering
+        let options = template::slot_options(&availabilities);
+        let archive = invitation.archive_bcc.trim();
+        let archived_to = (!archive.is_empty()).then(|| archive.to_string());
+        rt.spawn(async move {
+            info!("Starting email sending task.");
+            let mut success_count = 0;
+            let mut error_count = 0;
+            let mut recent_count = 0;
+            let mut unsent = 0;
+            let mut aborted = None;
+            match invitation.template(unavailable_days) {
+                Ok(runtime_template) => {
+                    // Everything the emails share, set up once for the whole batch
+                    let context = SendContext::new(
+                        transport,
+                        &invitation.sender_name,
+                        availabilities,
+                        runtime_template.with_offered_slots(offered_slots),
+                    );
+                    debug!("Runtime template created from UI content.");
+                    if let Some(checked) = context.check_server() {
+                        sender_clone
+                            .send(Message::SmtpServerChecked(
+                                checked.map_err(|e| e.to_string()),
+                            ))
+                            .ok();
+                    }
+                    let total = recipients_to_send.len();
+                    let mut recipients = recipients_to_send.into_iter().enumerate();
+                    for (position, recipient) in recipients.by_ref() {
+                        // Checked before each email, so it only stops with someone left to send to
+                        if let Some(reason) = streak.abort_reason(total - position) {
+                            error!("{}", reason);
+                            unsent = total - position;
+                            aborted = Some(reason);
+                            sender_clone
+                                .send(Message::EmailSkipped(recipient.email, NOT_SENT.to_string()))
+                                .ok();
+                            break;
+                        }
+                        let claim = match registry.claim(recipient.key(), cooldown) {
+                            Ok(claim) => claim,
+                            Err(recent) => {
+                                recent_count += 1;
+                                info!("Skipping {}: {}", recipient.email, recent);
+                                sender_clone
+                                    .send(Message::EmailSkipped(
+                                        recipient.email,
+                                        recent.to_string(),
+                                    ))
+                                    .ok();
+                                continue;
+                            }
+                        };
+                        debug!("Attempting to send email to: {}", recipient.email);
+                        // Rendered here rather than with `send_to`, so the
+                        // subject can go in the history for follow-ups
+                        let result = match context.prepare(&recipient, &NoopPostProcessor) {
+                            Ok(prepared) => {
+                                // Their own list, in their language if they have one
+                                let slots = history::record_slots(
+                                    &context
+                                        .template
+                                        .availabilities_for(&recipient, &context.availabilities),
+                                    &slot_windows,
+                                );
+                                context
+                                    .send(&recipient, &prepared)
+                                    .await
+                                    .map(|receipt| (receipt, prepared.subject, slots))
+                            }
+                            Err(e) => Err(e),
+                        };
+                        match result {
+                            Ok((receipt, subject, slots)) => {
+                                claim.sent();
+                                streak.success();
+                                success_count += 1;
+                                info!(
+                                    "Email sent successfully to {}: {}",
+                                    recipient.email,
+                                    receipt.summary()
+                                );
+                                sender_clone
+                                    .send(Message::EmailSent {
+                                        recipient,
+                                        subject,
+                                        receipt,
+                                        options: options.clone(),
+                                        slots,
+                                        campaign: invitation.campaign.clone(),
+                                    })
+                                    .ok();
+                            }
+                            Err(e) => {
+                                streak.failure();
+                                error_count += 1;
+                                error!("Error sending email to {}: {}", recipient.email, e);
+                                sender_clone
+                                    .send(Message::EmailFailed(recipient.email, e.to_string()))
+                                    .ok();
+                            }
+                        }
+                    }
+                    // Listed so it's clear who still needs the invitation
+                    for (_, recipient) in recipients {
+                        sender_clone
+                            .send(Message::EmailSkipped(recipient.email, NOT_SENT.to_string()))
+                            .ok();
+                    }
+                }
+                Err(template_err) => {
+                    error!(
+                        "Failed to create template from UI content: {}",
+                        template_err
+                    );
+                    error_count = recipients_to_send.len();
+                    sender_clone
+                        .send(Message::EmailFailed(
+                            "All Recipients".to_string(),
+                            format!("Template Error (Subject/Body invalid): {}", template_err),
+                        ))
+                        .ok();
+                }
+            }
+            info!(
+                "Email sending task finished. Success: {}, Errors: {}",
+                success_count, error_count
+            );
+            sender_clone
+                .send(Message::FinishedSending {
+                    sent: success_count,
+                    failed: error_count,
+                    duplicates: skipped,
+                    recently_contacted: recent_count,
+                    unsent,
+                    aborted,
+                    archived_to,
+                })
+                .ok();
+        });
+    }
+
+    pub(super) fn handle_fetch_slots(&mut self) {
+        if self.background.is_fetching_slots {
+            return;
+        }
+        let lookahead_days = self.calendar_settings.lookahead_days;
+        if !(1..=calendar::MAX_LOOKAHEAD_DAYS).contains(&lookahead_days) {
+            self.status_message = format!(
+                "Error: Look Ahead must be 1 to {} days, not {}.",
+                calendar::MAX_LOOKAHEAD_DAYS,
+                lookahead_days
+            );
+            return;
+        }
+        if let Some(source) = self.calendar.source.clone() {
+            self.background.is_fetching_slots = true;
+            self.status_message = "Fetching available slots...".to_string();
+            self.clear_slots();
+            self.background.slot_fetch_id += 1;
+            let fetch_id = self.background.slot_fetch_id;
+
+            let sender = self.background.sender.clone();
+            let rt_handle = self.ensure_runtime().handle().clone();
+            let settings = self.current_slot_settings();
+            let filters = settings.filters();
+            let calendar_ids = settings.calendar_ids.clone();
+
+            rt_handle.spawn(async move {
+                info!(
+                    "Starting slot fetching task with buffer={} (min), hours={}-{}",
+                    filters.buffer, filters.start_hour, filters.end_hour
+                );
+                match calendar::find_available_slots(
+                    &source,
+                    &filters,
+                    lookahead_days,
+                    &calendar_ids,
+                )
+                .await
+                {
+                    Ok(found) => {
+                        info!(
+                            "Successfully found {} raw free slots (pre-filtering).",
+                            found.windows.len()
+                        );
+                        // Summarization happens in apply_fetched_slots so restored slots share it
+                        sender
+                            .send(Message::SlotsFetched(
+                                fetch_id,
+                                FetchedSlots {
+                                    windows: found.windows,
+                                    fetched_at: Utc::now(),
+                                    settings,
+                                    zone: Some(LocalZone::current()),
+                                    free_time: Some(found.free_time),
+                                    busy: found.busy,
+                                },
+                            ))
+                            .ok();
+                    }
+                    Err(e) if e.is::<ReauthRequired>() => {
+                        error!("Calendar sign-in expired: {}", e);
+                        sender
+                            .send(Message::CalendarSignInExpired(e.to_string()))
+                            .ok();
+                    }
+                    Err(e) => {
+                        error!("Failed to find available slots: {}", e);
+                        sender
+                            .send(Message::SlotsFetchFailed(
+                                fetch_id,
+                                format!(
+                                    "Failed to fetch slots: {}",
+                                    calendar::describe_fetch_error(&*e)
+                                ),
+                            ))
+                            .ok();
+                    }
+                }
+            });
+        } else {
+            self.status_message = "Cannot fetch slots: Calendar not connected.".to_string();
+            warn!("Attempted to fetch slots without calendar connection.");
+        }
+    }
+
+    pub(super) fn handle_diagnose_slots(&mut self) {
+        if self.background.is_diagnosing {
+            return;
+        }
+        let Some(source) = self.calendar.source.clone() else {
+            self.status_message = "Cannot diagnose slots: Calendar not connected.".to_string();
+            return;
+        };
+        self.background.is_diagnosing = true;
+        self.calendar.diagnosis = None;
+        self.status_message = "Diagnosing slots for the next 7 days...".to_string();
+
+        let sender = self.background.sender.clone();
+        let settings = self.current_slot_settings();
+        let filters = settings.filters();
+        let rt_handle = self.ensure_runtime().handle().clone();
+        rt_handle.spawn(async move {
+            let calendar_ids = &settings.calendar_ids;
+            let result = calendar::diagnose::diagnose_slots(&source, &filters, calendar_ids)
+                .await
+                .map_err(|e| {
+                    error!("Slot diagnosis failed: {}", e);
+                    format!("Diagnosis failed: {}", calendar::describe_fetch_error(&*e))
+                });
+            sender.send(Message::SlotsDiagnosed(result)).ok();
+        });
+    }
+}
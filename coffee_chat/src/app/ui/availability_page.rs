@@ -0,0 +1,68 @@
+// src/app/ui/availability_page.rs
+
+use crate::app::MyApp;
+use eframe::egui;
+
+impl MyApp {
+    /// "Availability page": write the fetched slots to an HTML file, and set the link emails use.
+    pub(super) fn ui_availability_page_dialog(&mut self, ctx: &egui::Context) {
+        if !self.availability_page.open {
+            return;
+        }
+        let mut open = true;
+        let mut generate = false;
+        let link = self.availability_link();
+        egui::Window::new("Availability Page")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                let page = &mut self.availability_page;
+                ui.weak("Your fetched slots, one card per day, as a single HTML file to host or attach.");
+                ui.horizontal(|ui| {
+                    ui.label("Save to:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut page.path).desired_width(f32::INFINITY),
+                    );
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Hosted at:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut page.base_url)
+                            .desired_width(f32::INFINITY)
+                            .hint_text("https://example.com/me (optional)"),
+                    );
+                })
+                .response
+                .on_hover_text("The folder you upload the file to. Emails get the full URL as {{ availability_link }}.");
+                if link.is_empty() {
+                    ui.weak("No base URL: {{ availability_link }} is empty.");
+                } else {
+                    ui.label(format!("{{{{ availability_link }}}} = {}", link));
+                }
+                generate = ui
+                    .add_enabled(
+                        self.calendar.last_fetch.is_some(),
+                        egui::Button::new("🌐 Generate availability page…"),
+                    )
+                    .on_disabled_hover_text("Fetch your slots first")
+                    .clicked();
+
+                if let Some(result) = &self.availability_page.result {
+                    ui.add_space(5.0);
+                    match result {
+                        Ok(message) => ui.label(format!("✅ {}", message)),
+                        Err(e) => ui.colored_label(ui.visuals().error_fg_color, e),
+                    };
+                }
+            });
+        if !open {
+            self.availability_page.open = false;
+            self.availability_page.result = None;
+        }
+        if generate {
+            self.generate_availability_page();
+        }
+    }
+}
@@ -0,0 +1,127 @@
+// src/app/ui/backup.rs
+
+use crate::app::MyApp;
+use eframe::egui;
+
+impl MyApp {
+    /// "Backup": export the session to a .tar.gz, or restore one after showing what it replaces.
+    pub(super) fn ui_backup_dialog(&mut self, ctx: &egui::Context) {
+        if !self.backup.open {
+            return;
+        }
+        let mut open = true;
+        let mut export = false;
+        let mut prepare_import = false;
+        let mut restore = false;
+        let mut cancel_import = false;
+        egui::Window::new("Backup")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                let backup = &mut self.backup;
+                ui.strong("Export");
+                ui.weak("Recipients, template and settings, as they are right now.");
+                ui.horizontal(|ui| {
+                    ui.label("Save to:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut backup.export_path)
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+                ui.checkbox(
+                    &mut backup.include_credentials,
+                    "Include credentials (sensitive)",
+                )
+                .on_hover_text("The SMTP password and the Google sign-in token cache");
+                if backup.include_credentials {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "⚠ Anyone with the file can send mail and read your calendar as you. Delete it once it's restored.",
+                    );
+                }
+                export = ui.button("📦 Export backup…").clicked();
+
+                ui.separator();
+                ui.strong("Import");
+                ui.horizontal(|ui| {
+                    ui.label("Backup file:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut backup.import_path)
+                            .desired_width(f32::INFINITY)
+                            .hint_text("coffee_chat_backup_….tar.gz"),
+                    );
+                });
+                match &backup.pending {
+                    None => {
+                        prepare_import = ui
+                            .add_enabled(
+                                !backup.import_path.trim().is_empty(),
+                                egui::Button::new("📂 Import backup…"),
+                            )
+                            .clicked();
+                    }
+                    Some(pending) => {
+                        let manifest = &pending.manifest;
+                        ui.label(format!(
+                            "Made {} by version {}{}.",
+                            manifest
+                                .created_at
+                                .with_timezone(&chrono::Local)
+                                .format("%Y-%m-%d %H:%M"),
+                            manifest.app_version,
+                            if manifest.includes_credentials {
+                                ", with credentials"
+                            } else {
+                                ", without credentials"
+                            }
+                        ));
+                        ui.label("Restoring writes:");
+                        for (target, exists) in &pending.targets {
+                            let action = if *exists { "Replaces" } else { "Creates" };
+                            ui.monospace(format!("{} {}", action, target.display()));
+                        }
+                        if !manifest.includes_credentials {
+                            ui.weak("The SMTP password on this computer is kept.");
+                        }
+                        if self.save.dirty {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "Unsaved changes in this session will be lost.",
+                            );
+                        }
+                        ui.horizontal(|ui| {
+                            restore = ui.button("Restore").clicked();
+                            cancel_import = ui.button("Cancel").clicked();
+                        });
+                    }
+                }
+
+                if let Some(result) = &self.backup.result {
+                    ui.add_space(5.0);
+                    match result {
+                        Ok(message) => ui.label(format!("✅ {}", message)),
+                        Err(e) => ui.colored_label(ui.visuals().error_fg_color, e),
+                    };
+                }
+            });
+        if !open {
+            self.backup.open = false;
+            self.backup.pending = None;
+            self.backup.result = None;
+        }
+        if cancel_import {
+            self.backup.pending = None;
+        }
+        if export {
+            self.handle_export_backup();
+        }
+        if prepare_import {
+            self.handle_prepare_import();
+        }
+        if restore {
+            self.handle_import_backup();
+        }
+    }
+}
@@ -0,0 +1,152 @@
+// src/app/ui/booking.rs
+
+use crate::app::MyApp;
+use crate::calendar::format::{format_slot_in, SlotFormat};
+use chrono::Local;
+use eframe::egui;
+
+impl MyApp {
+    /// "Book Meeting": put an agreed time on the calendar with the recipient invited,
+    /// and cancel meetings booked earlier.
+    pub(super) fn ui_booking_dialog(&mut self, ctx: &egui::Context) {
+        if !self.booking.open {
+            return;
+        }
+        let mut open = true;
+        let mut book = false;
+        let mut cancel = None;
+        let times = self.booking_times();
+        let when = |start, end| format_slot_in(start, end, SlotFormat::default(), &Local);
+        egui::Window::new("Book Meeting")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                ui.weak("Adds the event to your primary calendar and emails them an invitation.");
+                let slots = &self.calendar.available_slots;
+                let booking = &mut self.booking;
+                egui::Grid::new("booking_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        ui.text_edit_singleline(&mut booking.recipient_name);
+                        ui.end_row();
+                        ui.label("Email:");
+                        ui.text_edit_singleline(&mut booking.recipient_email);
+                        ui.end_row();
+                        ui.label("Slot:");
+                        egui::ComboBox::from_id_salt("booking_slot")
+                            .selected_text(booking.slot.as_str())
+                            .width(280.0)
+                            .show_ui(ui, |ui| {
+                                for slot in slots {
+                                    ui.selectable_value(&mut booking.slot, slot.clone(), slot);
+                                }
+                            });
+                        ui.end_row();
+                        ui.label("Starts:");
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::DragValue::new(&mut booking.start_offset_minutes)
+                                    .range(0..=24 * 60)
+                                    .speed(15.0)
+                                    .suffix(" min"),
+                            );
+                            ui.label("into the slot");
+                        });
+                        ui.end_row();
+                        ui.label("Length:");
+                        ui.add(
+                            egui::DragValue::new(&mut booking.meeting_minutes)
+                                .range(5..=8 * 60)
+                                .speed(5.0)
+                                .suffix(" min"),
+                        );
+                        ui.end_row();
+                        ui.label("Title:");
+                        ui.text_edit_singleline(&mut booking.title_template)
+                            .on_hover_text(
+                                "Uses {{sender_name}}, {{recipient_name}} and {{recipient_email}}",
+                            );
+                        ui.end_row();
+                    });
+                ui.checkbox(&mut booking.with_meet, "Add a Google Meet link");
+                match &times {
+                    Ok((start, end)) => {
+                        ui.strong(when(*start, *end));
+                    }
+                    Err(problem) => {
+                        ui.colored_label(ui.visuals().warn_fg_color, problem);
+                    }
+                }
+                ui.horizontal(|ui| {
+                    book = ui
+                        .add_enabled(
+                            times.is_ok() && !booking.in_flight,
+                            egui::Button::new("📅 Book meeting"),
+                        )
+                        .on_hover_text("The first booking asks Google for permission to add events")
+                        .clicked();
+                    if booking.in_flight {
+                        ui.spinner();
+                    }
+                });
+
+                if let Some(result) = &booking.result {
+                    ui.add_space(5.0);
+                    match result {
+                        Ok(message) => ui.label(format!("✅ {}", message)),
+                        Err(e) => ui.colored_label(ui.visuals().error_fg_color, e),
+                    };
+                }
+
+                if booking.booked.is_empty() {
+                    return;
+                }
+                ui.separator();
+                ui.label("Booked from here:");
+                egui::ScrollArea::vertical()
+                    .id_salt("booked_meetings")
+                    .max_height(150.0)
+                    .show(ui, |ui| {
+                        for meeting in &booking.booked {
+                            ui.horizontal(|ui| {
+                                ui.label(format!(
+                                    "{} with {}",
+                                    when(meeting.start, meeting.end),
+                                    meeting.attendee_email
+                                ))
+                                .on_hover_text(&meeting.title);
+                                if let Some(link) =
+                                    meeting.meet_link.as_ref().or(meeting.link.as_ref())
+                                {
+                                    ui.hyperlink_to("🔗", link);
+                                }
+                                if ui
+                                    .add_enabled(
+                                        !booking.in_flight,
+                                        egui::Button::new("Cancel").small(),
+                                    )
+                                    .on_hover_text("Delete the event and tell them it's cancelled")
+                                    .clicked()
+                                {
+                                    cancel = Some(meeting.event_id.clone());
+                                }
+                            });
+                        }
+                    });
+            });
+        if !open {
+            self.booking.open = false;
+            self.booking.result = None;
+        }
+        if book {
+            self.book_meeting();
+        }
+        if let Some(event_id) = cancel {
+            self.cancel_meeting(&event_id);
+        }
+    }
+}
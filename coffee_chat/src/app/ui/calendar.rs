@@ -0,0 +1,1117 @@
+// src/app/ui/calendar.rs
+
+use crate::app::MyApp;
+use crate::calendar;
+use crate::calendar::diagnose::Stage;
+use crate::calendar::free_busy::{DayRange, WeeklyRules};
+use crate::calendar::free_time::FreeTime;
+use crate::calendar::units::{HourOfDay, Minutes};
+use chrono::{Local, Weekday};
+use eframe::egui;
+use egui::{Color32, Vec2};
+use egui_double_slider::DoubleSlider;
+use std::path::Path;
+
+// How far ahead the blackout date picker reaches
+const BLACKOUT_PICKER_DAYS: usize = 90;
+
+impl MyApp {
+    /// Note field for the slot whose 📝 was clicked, e.g. "prefer phone".
+    fn ui_slot_note(&mut self, ui: &mut egui::Ui) {
+        let Some(slot) = self.calendar.editing_note.clone() else {
+            return;
+        };
+        let mut note = self
+            .calendar
+            .slot_notes
+            .get(&slot)
+            .cloned()
+            .unwrap_or_default();
+        let mut done = false;
+        ui.separator();
+        ui.label(format!("Note for {}:", slot));
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut note)
+                    .hint_text("prefer phone")
+                    .desired_width(180.0),
+            );
+            done |= response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+            done |= ui.small_button("✔").on_hover_text("Done").clicked();
+            if ui
+                .small_button("🗑")
+                .on_hover_text("Remove the note")
+                .clicked()
+            {
+                note.clear();
+                done = true;
+            }
+        });
+        if note.trim().is_empty() {
+            self.calendar.slot_notes.remove(&slot);
+        } else {
+            self.calendar.slot_notes.insert(slot, note);
+        }
+        if done {
+            self.calendar.editing_note = None;
+        }
+    }
+
+    /// Calendar connection, slot settings, fetched slots and the tools built on them.
+    pub(super) fn ui_calendar(&mut self, ui: &mut egui::Ui) {
+        // --- Calendar Connection ---
+        ui.horizontal(|ui| {
+            let connect_button_text = if self.calendar.source.is_some() {
+                "✅ Calendar Connected"
+            } else {
+                "📅 Connect Google Calendar"
+            };
+            let connect_button = egui::Button::new(connect_button_text);
+            if ui
+                .add_enabled(!self.background.is_connecting_calendar, connect_button)
+                .on_hover_text(if self.calendar.source.is_some() {
+                    "Calendar is connected"
+                } else {
+                    "Connect to Google Calendar to fetch availability"
+                })
+                .clicked()
+            {
+                if self.calendar.source.is_none() {
+                    self.handle_connect_calendar();
+                } else {
+                    self.status_message = "Calendar already connected.".to_string();
+                }
+            }
+            if self.background.is_connecting_calendar {
+                ui.add(egui::Spinner::new().size(16.0));
+                ui.label("Connecting...");
+                if ui
+                    .small_button("Cancel")
+                    .on_hover_text("Stop waiting for the browser sign-in")
+                    .clicked()
+                {
+                    self.cancel_connect_calendar();
+                }
+            } else {
+                ui.label(&self.calendar.status);
+                if let (Some(_), Some(proxy)) = (&self.calendar.source, &self.calendar.proxy) {
+                    ui.weak(format!("via proxy {}", proxy))
+                        .on_hover_text("From HTTPS_PROXY / ALL_PROXY");
+                }
+                if self.calendar.error.is_some()
+                    && ui
+                        .small_button("Details…")
+                        .on_hover_text("Show what went wrong and how to fix it")
+                        .clicked()
+                {
+                    self.calendar.show_error_details = true;
+                }
+            }
+        });
+        self.ui_calendar_list(ui);
+        ui.add_space(10.0);
+
+        // Emails go out fine without slots, so an unset calendar stays out of the way
+        let set_up = self.calendar.source.is_some()
+            || self.background.is_connecting_calendar
+            || self.calendar.last_fetch.is_some()
+            || Path::new(&self.calendar_settings.credentials_path).exists();
+        if set_up {
+            self.ui_calendar_details(ui);
+        } else {
+            ui.weak(format!(
+                "Calendar not set up: no {} found. Save a Google OAuth client (Desktop app) there to fetch slots. Until then emails go out without them, using the no-slots text.",
+                self.calendar_settings.credentials_path
+            ));
+            egui::CollapsingHeader::new("Calendar Settings and Slots")
+                .id_salt("calendar_not_set_up")
+                .default_open(false)
+                .show(ui, |ui| self.ui_calendar_details(ui));
+        }
+    }
+
+    /// Settings, slots and the tools built on them, below the connect button.
+    fn ui_calendar_details(&mut self, ui: &mut egui::Ui) {
+        // --- Calendar Settings (Collapsible Section) ---
+        ui.collapsing("Calendar Settings", |ui| {
+            egui::Grid::new("calendar_settings_grid")
+                .num_columns(3)
+                .spacing([10.0, 8.0])
+                .show(ui, |ui| {
+                    // --- Buffer Settings ---
+                    let buffers = [
+                        (
+                            "Buffer Before:",
+                            &mut self.calendar_settings.buffer_before_minutes,
+                            "Time kept clear before each meeting, e.g. to prepare",
+                        ),
+                        (
+                            "Buffer After:",
+                            &mut self.calendar_settings.buffer_after_minutes,
+                            "Time kept clear after each meeting, e.g. to wind down",
+                        ),
+                    ];
+                    for (label, buffer, hint) in buffers {
+                        ui.label(label).on_hover_text(hint);
+                        let mut buffer_minutes = buffer.get();
+                        ui.add(
+                            // Standard Slider
+                            egui::Slider::new(&mut buffer_minutes, 0..=60) // Range 0-60 mins
+                                .show_value(false), // Don't show value on slider itself
+                        );
+                        ui.add(
+                            // Text input (DragValue) for precise control
+                            egui::DragValue::new(&mut buffer_minutes)
+                                .speed(1.0)
+                                .range(0..=120)
+                                .suffix(" min"), // Add units
+                        );
+                        *buffer = Minutes::from(buffer_minutes);
+                        ui.end_row();
+                    }
+
+                    ui.label("");
+                    ui.checkbox(
+                        &mut self.calendar_settings.pad_range_edges,
+                        "Also after now and before the last day ends",
+                    )
+                    .on_hover_text(
+                        "Off: the first slot can start right now. On: it starts one buffer from now, \
+                         and the last one ends one buffer before the lookahead ends",
+                    );
+                    ui.end_row();
+
+                    ui.label("");
+                    ui.horizontal(|ui| {
+                        let mut only_long = self.calendar_settings.buffer_longer_than.is_some();
+                        if ui
+                            .checkbox(&mut only_long, "Only around meetings longer than")
+                            .on_hover_text(
+                                "Skip the buffer around short meetings like a standup. \
+                                 Overlapping or back-to-back meetings count as one",
+                            )
+                            .changed()
+                        {
+                            self.calendar_settings.buffer_longer_than =
+                                only_long.then_some(Minutes(30));
+                        }
+                        if let Some(minutes) = self.calendar_settings.buffer_longer_than.as_mut() {
+                            let mut value = minutes.get();
+                            ui.add(
+                                egui::DragValue::new(&mut value)
+                                    .speed(1.0)
+                                    .range(1..=240)
+                                    .suffix(" min"),
+                            );
+                            *minutes = Minutes(value);
+                        }
+                    });
+                    ui.end_row();
+
+                    // --- Day Start/End Time Setting ---
+                    ui.label("Daily Availability:");
+
+                    // Combine Slider and Text Edits horizontally
+                    ui.horizontal(|ui| {
+                        // The widgets edit plain numbers; they're turned back into hours below
+                        let mut start_hour = u32::from(self.calendar_settings.day_start_hour);
+                        let mut end_hour = u32::from(self.calendar_settings.day_end_hour);
+
+                        // Use DoubleSlider
+                        ui.add(DoubleSlider::new(
+                            // Takes two mutable references and the full range
+                            &mut start_hour,
+                            &mut end_hour,
+                            0..=23, // The total possible range
+                        ));
+
+                        // Add some spacing
+                        ui.add_space(10.0);
+
+                        // Text boxes (DragValue) for precise start/end hour input
+                        ui.label("From:");
+                        let start_resp = ui.add(
+                            egui::DragValue::new(&mut start_hour)
+                                .speed(1.0)
+                                .range(0..=22)
+                                .suffix(":00"),
+                        );
+                        ui.label(" To:");
+                        let end_resp = ui.add(
+                            egui::DragValue::new(&mut end_hour)
+                                .speed(1.0)
+                                .range(1..=23)
+                                .suffix(":00"),
+                        );
+
+                        // Re-validate if text boxes or slider changed, ensuring start < end
+                        if (start_resp.changed() || end_resp.changed()) && start_hour >= end_hour {
+                            end_hour = (start_hour + 1).min(23);
+                        }
+                        // Every widget above is limited to 0-23
+                        if let (Ok(start), Ok(end)) =
+                            (HourOfDay::try_from(start_hour), HourOfDay::try_from(end_hour))
+                        {
+                            self.calendar_settings.day_start_hour = start;
+                            self.calendar_settings.day_end_hour = end;
+                        }
+                    });
+                    ui.end_row();
+
+                    // --- Weekdays Setting ---
+                    ui.label("Weekdays:");
+                    ui.horizontal(|ui| {
+                        let weekdays = &mut self.calendar_settings.weekdays;
+                        for day in (0..7).filter_map(|i| Weekday::try_from(i).ok()) {
+                            let mut included = weekdays.includes(day);
+                            if ui.checkbox(&mut included, day.to_string()).changed() {
+                                weekdays.set(day, included);
+                            }
+                        }
+                    })
+                    .response
+                    .on_hover_text("Offer slots only on the ticked days, e.g. untick Sat and Sun to keep weekends free");
+                    ui.end_row();
+
+                    // --- Weekly Availability Setting ---
+                    ui.label("Weekly Availability:");
+                    self.ui_weekly_rules(ui);
+                    ui.end_row();
+
+                    // --- Today Cutoff Setting ---
+                    ui.label("Today Cutoff:");
+                    let mut cutoff_enabled = self.calendar_settings.today_cutoff_hour.is_some();
+                    if ui
+                        .checkbox(&mut cutoff_enabled, "Skip today after")
+                        .on_hover_text("Don't offer slots later today once this hour has passed")
+                        .changed()
+                    {
+                        self.calendar_settings.today_cutoff_hour = cutoff_enabled.then_some(12);
+                    }
+                    if let Some(hour) = self.calendar_settings.today_cutoff_hour.as_mut() {
+                        ui.add(
+                            egui::DragValue::new(hour)
+                                .speed(1.0)
+                                .range(0..=23)
+                                .suffix(":00"),
+                        );
+                    }
+                    ui.end_row();
+
+                    ui.label("Look Ahead:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.calendar_settings.lookahead_days)
+                            .speed(0.2)
+                            .range(1..=calendar::MAX_LOOKAHEAD_DAYS)
+                            .suffix(" days"),
+                    )
+                    .on_hover_text("How many days from now to look for free slots");
+                    ui.end_row();
+
+                    // --- Slot Spacing Settings ---
+                    ui.label("Min Gap:");
+                    ui.add(
+                        egui::Slider::new(&mut self.calendar_settings.slot_min_gap_minutes, 0..=120)
+                            .show_value(false),
+                    )
+                    .on_hover_text("Skip slots that start this soon after another offered slot on the same day");
+                    ui.add(
+                        egui::DragValue::new(&mut self.calendar_settings.slot_min_gap_minutes)
+                            .speed(1.0)
+                            .range(0..=240)
+                            .suffix(" min"),
+                    );
+                    ui.end_row();
+
+                    ui.label("Max Per Day:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.calendar_settings.max_slots_per_day)
+                            .speed(0.1)
+                            .range(0..=20)
+                            .custom_formatter(|n, _| {
+                                if n == 0.0 {
+                                    "No limit".to_string()
+                                } else {
+                                    format!("{}", n)
+                                }
+                            }),
+                    );
+                    ui.end_row();
+
+                    ui.label("Slots In Email:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.calendar_settings.max_slots_in_email)
+                            .speed(0.1)
+                            .range(0..=100)
+                            .custom_formatter(|n, _| {
+                                if n == 0.0 {
+                                    "No limit".to_string()
+                                } else {
+                                    format!("{}", n)
+                                }
+                            }),
+                    )
+                    .on_hover_text("Only the first this many slots go in the email, followed by \"+N more on request\"");
+                    ui.end_row();
+
+                    ui.label("Slots In List:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.calendar_settings.slots_shown_in_list)
+                            .speed(0.1)
+                            .range(0..=200)
+                            .custom_formatter(|n, _| {
+                                if n == 0.0 {
+                                    "All".to_string()
+                                } else {
+                                    format!("{}", n)
+                                }
+                            }),
+                    )
+                    .on_hover_text("Rows shown under Available Slots before \"Show all\". Only changes this list; every slot is still sent");
+                    ui.end_row();
+
+                    ui.label("Readable Up To:");
+                    ui.add(
+                        egui::DragValue::new(&mut self.calendar_settings.readable_slot_lines)
+                            .speed(0.1)
+                            .range(0..=100)
+                            .custom_formatter(|n, _| {
+                                if n == 0.0 {
+                                    "Never warn".to_string()
+                                } else {
+                                    format!("{} lines", n)
+                                }
+                            }),
+                    )
+                    .on_hover_text("Warn before sending an email that lists more slots than this");
+                    ui.end_row();
+
+                    ui.label("Booked Days:");
+                    if ui
+                        .checkbox(&mut self.calendar_settings.show_unavailable_days, "Say \"no availability\"")
+                        .on_hover_text(
+                            "List fully booked days in the slots instead of leaving them out",
+                        )
+                        .changed()
+                    {
+                        self.summarize_last_fetch();
+                    }
+                    ui.end_row();
+
+                    ui.label("Slot Order:");
+                    if ui
+                        .checkbox(&mut self.calendar_settings.reverse_slot_order, "Latest first")
+                        .on_hover_text(
+                            "List the last day first. Times within a day stay earliest-first",
+                        )
+                        .changed()
+                    {
+                        self.summarize_last_fetch();
+                    }
+                    ui.end_row();
+
+                    ui.label("Time Zone:");
+                    let label = self
+                        .timezone_label()
+                        .unwrap_or_else(|| "(all times ET)".to_string());
+                    ui.checkbox(
+                        &mut self.calendar_settings.label_timezone,
+                        format!("Add \"{}\" after the slots", label),
+                    )
+                    .on_hover_text("Once, after the last slot in the email, for recipients in other zones");
+                    ui.end_row();
+
+                    ui.label("Blackout Dates:");
+                    self.ui_blackout_dates(ui);
+                    ui.end_row();
+                });
+        });
+        ui.add_space(10.0);
+
+        // --- Fetch Slots Button ---
+        ui.horizontal(|ui| {
+            let fetch_button = egui::Button::new("🔄 Fetch Slots");
+            if ui
+                .add_enabled(
+                    self.calendar.source.is_some() && !self.background.is_fetching_slots,
+                    fetch_button,
+                )
+                .on_hover_text("Fetch available time slots using current settings")
+                .clicked()
+            {
+                self.handle_fetch_slots(); // Ensure only one definition of this exists
+            }
+            if self.background.is_fetching_slots {
+                ui.add(egui::Spinner::new().size(16.0));
+                ui.label("Fetching...");
+            }
+            let diagnose_button = egui::Button::new("🩺 Diagnose");
+            if ui
+                .add_enabled(
+                    self.calendar.source.is_some() && !self.background.is_diagnosing,
+                    diagnose_button,
+                )
+                .on_hover_text("Count what's left after each filter over the next 7 days")
+                .clicked()
+            {
+                self.handle_diagnose_slots();
+            }
+            if self.background.is_diagnosing {
+                ui.add(egui::Spinner::new().size(16.0));
+            }
+        });
+        self.ui_diagnosis(ui);
+
+        // --- Available Slots Display ---
+        ui.add_space(10.0);
+        ui.label("Available Slots:");
+        if let Some(fetched) = &self.calendar.last_fetch {
+            if let Some(free) = fetched.free_time {
+                let range = format!("Next {} days", fetched.settings.lookahead_days);
+                free_time_bar(ui, free, &range);
+            }
+        }
+        self.ui_slot_freeze(ui);
+        if let Some(change) = &self.calendar.zone_change {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("🌐 {}", change));
+        }
+        if let Some(warning) = self.slot_staleness_warning() {
+            ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", warning));
+        }
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            if !self.calendar.available_slots.is_empty() {
+                // Only the visible rows are laid out, so long lookaheads stay smooth
+                let row_height = ui.spacing().interact_size.y;
+                let slots = self.visible_slots();
+                let notes = &self.calendar.slot_notes;
+                let mut open_note = None;
+                egui::ScrollArea::vertical()
+                    .id_salt("slots_scroll_area")
+                    .max_height(120.0)
+                    .auto_shrink([false, false])
+                    .show_rows(ui, row_height, slots.len(), |ui, rows| {
+                        for slot in &slots[rows] {
+                            ui.horizontal(|ui| {
+                                ui.label(slot);
+                                if !calendar::free_busy::is_slot_line(slot) {
+                                    return;
+                                }
+                                if let Some(note) = notes.get(slot) {
+                                    ui.weak(format!("({})", note));
+                                }
+                                if ui
+                                    .small_button("📝")
+                                    .on_hover_text("Add a note shown after this time in emails")
+                                    .clicked()
+                                {
+                                    open_note = Some(slot.clone());
+                                }
+                            });
+                        }
+                    });
+                if open_note.is_some() {
+                    self.calendar.editing_note = open_note;
+                }
+                self.ui_slot_note(ui);
+                return;
+            }
+            egui::ScrollArea::vertical()
+                .id_salt("slots_scroll_area")
+                .max_height(120.0)
+                .auto_shrink([false, false])
+                .show(ui, |ui| {
+                    if self.calendar.source.is_some()
+                        && !self.background.is_fetching_slots
+                        && !self.background.is_connecting_calendar
+                    {
+                        ui.colored_label(
+                            ui.style().visuals.widgets.inactive.fg_stroke.color,
+                            "(No slots fetched or none available with current filters)",
+                        );
+                    } else if self.calendar.source.is_none() {
+                        ui.colored_label(
+                            ui.style().visuals.widgets.inactive.fg_stroke.color,
+                            "(Connect calendar and fetch slots)",
+                        );
+                    } else if self.background.is_fetching_slots {
+                        ui.colored_label(
+                            ui.style().visuals.widgets.inactive.fg_stroke.color,
+                            "(Fetching...)",
+                        );
+                    }
+                });
+        });
+        let slot_count = self.calendar.available_slots.len();
+        let hidden = slot_count - self.visible_slots().len();
+        if hidden > 0 {
+            ui.horizontal(|ui| {
+                ui.weak(format!("{} more not shown", hidden));
+                if ui
+                    .small_button(format!("Show all {}", slot_count))
+                    .on_hover_text("The list only; the email isn't affected")
+                    .clicked()
+                {
+                    self.calendar.show_all_slots = true;
+                }
+            });
+        } else if self.calendar.show_all_slots
+            && self.slot_list_limit() < slot_count
+            && ui
+                .small_button(format!("Show first {}", self.slot_list_limit()))
+                .clicked()
+        {
+            self.calendar.show_all_slots = false;
+        }
+        let cap = self.calendar_settings.max_slots_in_email as usize;
+        if cap > 0 && slot_count > cap {
+            ui.weak(format!(
+                "{} slots; the email lists the first {} and \"+{} more on request\"",
+                slot_count,
+                cap,
+                slot_count - cap
+            ));
+        }
+        if ui
+            .add_enabled(
+                self.calendar.last_fetch.is_some(),
+                egui::Button::new("🌐 Availability page…"),
+            )
+            .on_hover_text("Save your slots as a web page to host or attach")
+            .on_disabled_hover_text("Fetch your slots first")
+            .clicked()
+        {
+            self.availability_page.open = true;
+        }
+
+        // --- Availability Heat Map ---
+        ui.add_space(10.0);
+        egui::CollapsingHeader::new("📊 Heat Map")
+            .id_salt("heat_map")
+            .show(ui, |ui| self.ui_availability_heat_map(ui));
+
+        // --- Common Time With Pasted Availability ---
+        ui.add_space(10.0);
+        egui::CollapsingHeader::new("🤝 Find Common Time")
+            .id_salt("common_time")
+            .show(ui, |ui| {
+                ui.label("Paste their availability, one \"day HH-HH\" per line:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.calendar.pasted_availability)
+                        .desired_width(f32::INFINITY)
+                        .desired_rows(4)
+                        .hint_text("Mon 14-16\nTue 9:30-11\ntomorrow 13-15"),
+                );
+                let find_button = egui::Button::new("Find Overlap");
+                if ui
+                    .add_enabled(self.calendar.last_fetch.is_some(), find_button)
+                    .on_disabled_hover_text("Fetch your slots first")
+                    .clicked()
+                {
+                    self.handle_find_common_time();
+                }
+                match &self.calendar.common_slots {
+                    Some(Ok(slots)) if slots.is_empty() => {
+                        ui.colored_label(
+                            ui.style().visuals.widgets.inactive.fg_stroke.color,
+                            "(No overlap of at least 30 minutes)",
+                        );
+                    }
+                    Some(Ok(slots)) => {
+                        for slot in slots {
+                            ui.label(slot);
+                        }
+                    }
+                    Some(Err(e)) => {
+                        ui.colored_label(Color32::RED, e);
+                    }
+                    None => {}
+                }
+            });
+    }
+
+    /// "Freeze", or while frozen, what every send offers and the way out.
+    fn ui_slot_freeze(&mut self, ui: &mut egui::Ui) {
+        if let Some(notice) = &self.freeze.notice {
+            ui.colored_label(ui.visuals().error_fg_color, notice);
+        }
+        let Some(frozen) = &self.freeze.frozen else {
+            let mut freeze = false;
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.freeze.label)
+                        .hint_text("Campaign name (optional)")
+                        .desired_width(180.0),
+                );
+                freeze = ui
+                    .add_enabled(
+                        !self.calendar.available_slots.is_empty(),
+                        egui::Button::new("🧊 Freeze slots"),
+                    )
+                    .on_hover_text("Offer exactly these slots in every send, drip batches included, until you unfreeze")
+                    .on_disabled_hover_text("Fetch your slots first")
+                    .clicked();
+            });
+            if freeze {
+                self.freeze_slots();
+            }
+            return;
+        };
+        let busy = self.frozen_busy_lines();
+        let mut unfreeze = false;
+        egui::Frame::group(ui.style())
+            .stroke(egui::Stroke::new(1.5, super::FROZEN_COLOR))
+            .show(ui, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(
+                        egui::RichText::new(format!("🧊 Frozen: {}", frozen.label))
+                            .color(super::FROZEN_COLOR)
+                            .strong(),
+                    );
+                    unfreeze = ui
+                        .button("Unfreeze")
+                        .on_hover_text("Go back to offering the slots fetched when each email goes out")
+                        .clicked();
+                });
+                ui.weak(format!(
+                    "Since {}. Every send offers this list; the one below is your calendar now.",
+                    frozen
+                        .frozen_at
+                        .with_timezone(&Local)
+                        .format("%b %-d, %-I:%M %p")
+                ));
+                egui::CollapsingHeader::new(format!("Frozen list ({} lines)", frozen.lines.len()))
+                    .id_salt("frozen_slots")
+                    .show(ui, |ui| {
+                        for line in &frozen.lines {
+                            if busy.contains(line) {
+                                ui.colored_label(ui.visuals().warn_fg_color, format!("{} (busy now)", line));
+                            } else {
+                                ui.label(line);
+                            }
+                        }
+                    });
+                if !busy.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "⚠ {} of the frozen slots {} been booked since. They're still offered until you unfreeze.",
+                            busy.len(),
+                            if busy.len() == 1 { "has" } else { "have" }
+                        ),
+                    );
+                }
+            });
+        if unfreeze {
+            self.unfreeze_slots();
+        }
+    }
+
+    /// The connected account's calendars, each with its Google color and name, and
+    /// a box to tick for each one slots have to be free on.
+    fn ui_calendar_list(&mut self, ui: &mut egui::Ui) {
+        let in_use = self.slot_calendar_ids();
+        let Some(calendars) = &self.calendar.calendars else {
+            return;
+        };
+        let chosen = &mut self.calendar_settings.slot_calendar_ids;
+        ui.collapsing("📅 Calendars", |ui| match calendars {
+            Ok(calendars) if calendars.is_empty() => {
+                ui.weak("(No calendars on this account)");
+            }
+            Ok(calendars) => {
+                // Nothing chosen reads the primary calendar
+                let ticked: Vec<&str> = if in_use.is_empty() {
+                    calendars
+                        .iter()
+                        .filter(|info| info.primary)
+                        .map(|info| info.id.as_str())
+                        .collect()
+                } else {
+                    in_use.iter().map(String::as_str).collect()
+                };
+                ui.weak("Slots have to be free on every ticked calendar. Fetch again after changing them.");
+                let missing: Vec<&str> = chosen
+                    .iter()
+                    .filter(|id| calendars.iter().all(|info| info.id != **id))
+                    .map(String::as_str)
+                    .collect();
+                if !missing.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!(
+                            "⚠ {} {} on this account, so {} left out.",
+                            missing.join(", "),
+                            if missing.len() == 1 { "isn't" } else { "aren't" },
+                            if missing.len() == 1 { "it's" } else { "they're" }
+                        ),
+                    );
+                }
+                let mut toggled = None;
+                for info in calendars {
+                    ui.horizontal(|ui| {
+                        let (rect, _) =
+                            ui.allocate_exact_size(Vec2::new(12.0, 12.0), egui::Sense::hover());
+                        match info.color {
+                            Some((r, g, b)) => {
+                                ui.painter()
+                                    .rect_filled(rect, 2.0, Color32::from_rgb(r, g, b));
+                            }
+                            // No color from Google: an outline keeps the rows lined up
+                            None => {
+                                ui.painter().rect_stroke(
+                                    rect,
+                                    2.0,
+                                    ui.visuals().widgets.inactive.fg_stroke,
+                                    egui::StrokeKind::Inside,
+                                );
+                            }
+                        }
+                        let mut used = ticked.contains(&info.id.as_str());
+                        // The last ticked calendar stays ticked; slots need one to come from
+                        let last = used && ticked.len() == 1;
+                        if ui
+                            .add_enabled(!last, egui::Checkbox::new(&mut used, &info.label))
+                            .on_hover_text(&info.id)
+                            .changed()
+                        {
+                            toggled = Some(info.id.as_str());
+                        }
+                        if info.primary {
+                            ui.weak("(primary)");
+                        }
+                    });
+                }
+                if let Some(id) = toggled {
+                    let mut ids: Vec<String> = ticked
+                        .iter()
+                        .filter(|ticked| **ticked != id)
+                        .map(|ticked| ticked.to_string())
+                        .collect();
+                    if ids.len() == ticked.len() {
+                        ids.push(id.to_string());
+                    }
+                    *chosen = ids;
+                }
+            }
+            Err(e) => {
+                ui.colored_label(Color32::RED, format!("Couldn't list calendars: {}", e));
+            }
+        });
+    }
+
+    /// The name and color a calendar id is shown with, from the calendar list.
+    fn calendar_label_and_color(&self, calendar_id: &str) -> (String, Color32) {
+        let info = match &self.calendar.calendars {
+            Some(Ok(calendars)) => calendars.iter().find(|info| info.id == calendar_id),
+            _ => None,
+        };
+        let label = info.map_or_else(|| calendar_id.to_string(), |info| info.label.clone());
+        let color = info
+            .and_then(|info| info.color)
+            .map_or(Color32::GRAY, |(r, g, b)| Color32::from_rgb(r, g, b));
+        (label, color)
+    }
+
+    /// Days × hours grid over the lookahead window, shaded by how much of each hour is free.
+    /// A strip along the bottom of a cell is the color of the calendar busiest in it.
+    fn ui_availability_heat_map(&self, ui: &mut egui::Ui) {
+        let Some(fetched) = &self.calendar.last_fetch else {
+            ui.colored_label(
+                ui.style().visuals.widgets.inactive.fg_stroke.color,
+                "(Fetch slots to see the heat map)",
+            );
+            return;
+        };
+        let (start_hour, end_hour) = (fetched.settings.start_hour, fetched.settings.end_hour);
+        let first_day = fetched.fetched_at.with_timezone(&Local).date_naive();
+        let grid = calendar::free_busy::hourly_free_minutes(
+            &fetched.windows,
+            first_day,
+            fetched.settings.lookahead_days as usize + 1,
+            start_hour,
+            end_hour,
+        );
+        let busy_grid = calendar::free_busy::hourly_busy_by_calendar(
+            &fetched.busy,
+            first_day,
+            fetched.settings.lookahead_days as usize + 1,
+            start_hour,
+            end_hour,
+        );
+        let hours = u32::from(start_hour)..u32::from(end_hour);
+        let busy_color = Color32::from_rgb(190, 80, 80);
+        let free_color = Color32::from_rgb(80, 170, 90);
+
+        egui::ScrollArea::horizontal()
+            .id_salt("heat_map_scroll")
+            .show(ui, |ui| {
+                egui::Grid::new("heat_map_grid")
+                    .spacing([2.0, 2.0])
+                    .show(ui, |ui| {
+                        ui.label("");
+                        for hour in hours.clone() {
+                            ui.small(hour.to_string());
+                        }
+                        ui.end_row();
+
+                        for ((day, row), busy_row) in
+                            first_day.iter_days().zip(&grid).zip(&busy_grid)
+                        {
+                            let day_label = day.format("%a %b %-d").to_string();
+                            ui.small(&day_label);
+                            for ((hour, &free_minutes), busy) in
+                                hours.clone().zip(row).zip(busy_row)
+                            {
+                                let (rect, response) = ui.allocate_exact_size(
+                                    Vec2::new(18.0, 14.0),
+                                    egui::Sense::hover(),
+                                );
+                                let color = busy_color
+                                    .lerp_to_gamma(free_color, free_minutes as f32 / 60.0);
+                                ui.painter().rect_filled(rect, 2.0, color);
+                                let mut hover = format!(
+                                    "{} {}:00 – {} min free",
+                                    day_label, hour, free_minutes
+                                );
+                                for (i, (calendar_id, minutes)) in busy.iter().enumerate() {
+                                    let (label, calendar_color) =
+                                        self.calendar_label_and_color(calendar_id);
+                                    if i == 0 {
+                                        let strip = egui::Rect::from_min_max(
+                                            egui::pos2(rect.left(), rect.bottom() - 3.0),
+                                            rect.max,
+                                        );
+                                        ui.painter().rect_filled(strip, 1.0, calendar_color);
+                                    }
+                                    hover.push_str(&format!("\n{}: {} min busy", label, minutes));
+                                }
+                                response.on_hover_text(hover);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        ui.small("Green is free, red is busy or outside the offered hours.");
+        let busy_counts = calendar::diagnose::count_by_calendar(&fetched.busy);
+        if busy_counts.len() > 1 {
+            ui.horizontal_wrapped(|ui| {
+                ui.small("Busiest calendar per hour:");
+                for (calendar_id, _) in &busy_counts {
+                    let (label, color) = self.calendar_label_and_color(calendar_id);
+                    ui.colored_label(color, "■");
+                    ui.small(label);
+                }
+            });
+        }
+    }
+
+    /// A switch, then one line of time ranges per weekday, e.g. "10-12, 15-17".
+    fn ui_weekly_rules(&mut self, ui: &mut egui::Ui) {
+        ui.vertical(|ui| {
+            let settings = &mut self.calendar_settings;
+            if ui
+                .checkbox(&mut settings.weekly_rules_enabled, "Only at these times")
+                .on_hover_text("Offer slots only in these ranges, whatever the calendar says")
+                .changed()
+                && settings.weekly_rules_enabled
+                && settings.weekly_rules.days.iter().all(Vec::is_empty)
+            {
+                // Start from the daily hours on weekdays
+                let range = DayRange {
+                    start: u32::from(settings.day_start_hour) * 60,
+                    end: u32::from(settings.day_end_hour) * 60,
+                };
+                for day in &mut settings.weekly_rules.days[..5] {
+                    *day = vec![range];
+                }
+                self.calendar.weekly_rule_drafts = Default::default();
+            }
+            if !settings.weekly_rules_enabled {
+                return;
+            }
+            egui::Grid::new("weekly_rules_grid")
+                .num_columns(2)
+                .spacing([6.0, 2.0])
+                .show(ui, |ui| {
+                    for (i, draft) in self.calendar.weekly_rule_drafts.iter_mut().enumerate() {
+                        let day = &mut settings.weekly_rules.days[i];
+                        let mut text = draft
+                            .clone()
+                            .unwrap_or_else(|| WeeklyRules::format_day(day));
+                        ui.label(format!(
+                            "{}",
+                            Weekday::try_from(i as u8).unwrap_or(Weekday::Mon)
+                        ));
+                        ui.horizontal(|ui| {
+                            let edited = ui
+                                .add(
+                                    egui::TextEdit::singleline(&mut text)
+                                        .hint_text("off")
+                                        .desired_width(160.0),
+                                )
+                                .changed();
+                            match WeeklyRules::parse_day(&text) {
+                                Ok(ranges) => {
+                                    if edited {
+                                        *day = ranges;
+                                    }
+                                }
+                                Err(e) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, e);
+                                }
+                            }
+                            if edited {
+                                *draft = Some(text);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                });
+            ui.small("Ranges like 10-12, 15:30-17. Leave a day empty to take it off.");
+        });
+    }
+
+    /// A date picker and one removable chip per blackout date.
+    fn ui_blackout_dates(&mut self, ui: &mut egui::Ui) {
+        let today = Local::now().date_naive();
+        let pick = self
+            .calendar
+            .blackout_pick
+            .filter(|&date| date >= today)
+            .unwrap_or(today + chrono::Duration::days(1));
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                let mut picked = pick;
+                egui::ComboBox::from_id_salt("blackout_pick")
+                    .selected_text(calendar::format::format_day(picked))
+                    .height(240.0)
+                    .show_ui(ui, |ui| {
+                        for date in today.iter_days().take(BLACKOUT_PICKER_DAYS) {
+                            ui.selectable_value(
+                                &mut picked,
+                                date,
+                                calendar::format::format_day(date),
+                            );
+                        }
+                    });
+                self.calendar.blackout_pick = Some(picked);
+                let already = self.calendar_settings.blackout_dates.contains(&picked);
+                if ui
+                    .add_enabled(!already, egui::Button::new("➕ Add"))
+                    .on_hover_text("Offer no slots on this day, even if the calendar is free")
+                    .clicked()
+                {
+                    let dates = &mut self.calendar_settings.blackout_dates;
+                    dates.push(picked);
+                    dates.sort_unstable();
+                }
+            });
+            let mut removed = None;
+            ui.horizontal_wrapped(|ui| {
+                for &date in &self.calendar_settings.blackout_dates {
+                    if ui
+                        .small_button(format!("{} ✕", calendar::format::format_day(date)))
+                        .on_hover_text("Remove this blackout date")
+                        .clicked()
+                    {
+                        removed = Some(date);
+                    }
+                }
+            });
+            if let Some(date) = removed {
+                self.calendar_settings.blackout_dates.retain(|&d| d != date);
+            }
+        });
+    }
+
+    /// Per-stage counts from the last "Diagnose" run, with a hint at the first empty stage.
+    fn ui_diagnosis(&mut self, ui: &mut egui::Ui) {
+        let Some(result) = &self.calendar.diagnosis else {
+            return;
+        };
+        let mut close = false;
+        ui.add_space(5.0);
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.strong("Slot diagnosis (next 7 days)");
+                close = ui.small_button("✖").on_hover_text("Hide").clicked();
+            });
+            let diagnosis = match result {
+                Ok(diagnosis) => diagnosis,
+                Err(e) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                    return;
+                }
+            };
+            let first_empty = diagnosis.first_empty_stage();
+            egui::Grid::new("diagnosis_grid")
+                .num_columns(2)
+                .spacing([20.0, 2.0])
+                .show(ui, |ui| {
+                    for &(stage, count) in &diagnosis.counts {
+                        if Some(stage) == first_empty {
+                            ui.colored_label(ui.visuals().warn_fg_color, stage.label());
+                            ui.colored_label(ui.visuals().warn_fg_color, count.to_string());
+                        } else {
+                            ui.label(stage.label());
+                            ui.label(count.to_string());
+                        }
+                        ui.end_row();
+                    }
+                });
+            if let Some(removed) = diagnosis
+                .removed_by(Stage::BlackoutDates)
+                .filter(|&n| n > 0)
+            {
+                ui.label(format!("Blackout dates removed {} slot(s).", removed));
+            }
+            free_time_bar(ui, diagnosis.free_time, "Working hours");
+            if !diagnosis.busy_by_calendar.is_empty() {
+                ui.label("Busy periods by calendar:");
+                for (calendar_id, count) in &diagnosis.busy_by_calendar {
+                    let (label, color) = self.calendar_label_and_color(calendar_id);
+                    ui.horizontal(|ui| {
+                        ui.colored_label(color, "■");
+                        ui.label(format!("{}: {}", label, count))
+                            .on_hover_text(calendar_id);
+                    });
+                }
+            }
+            match first_empty {
+                Some(stage) => {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("⚠ {}", stage.suggestion()),
+                    );
+                }
+                None => {
+                    if diagnosis.count(Stage::BusyPeriods) == Some(0) {
+                        ui.label(Stage::BusyPeriods.suggestion());
+                    } else {
+                        ui.label("Every step found slots, so your settings look fine.");
+                    }
+                }
+            }
+        });
+        if close {
+            self.calendar.diagnosis = None;
+        }
+    }
+}
+
+/// The share of working hours that's free, as a bar with the hours on it.
+fn free_time_bar(ui: &mut egui::Ui, free: FreeTime, label: &str) {
+    let Some(percent) = free.percent_free() else {
+        ui.small(format!("{}: {}", label, free.summary()));
+        return;
+    };
+    ui.horizontal(|ui| {
+        ui.label(format!("{}:", label));
+        ui.add(
+            egui::ProgressBar::new(percent as f32 / 100.0)
+                .desired_width(260.0)
+                .text(free.summary()),
+        )
+        .on_hover_text("Busy time includes the buffer; blackout dates aren't working hours");
+    });
+}
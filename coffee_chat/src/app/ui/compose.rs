@@ -0,0 +1,237 @@
+// src/app/ui/compose.rs
+
+use crate::app::MyApp;
+use crate::config::validate_archive_bcc;
+use crate::email_sender::priority::Priority;
+use crate::email_sender::template::BUILT_IN_VARIABLES;
+use eframe::egui;
+use egui::text::{CCursor, CCursorRange};
+use std::collections::BTreeMap;
+
+impl MyApp {
+    // (ui_email_message remains the same)
+    pub(super) fn ui_email_message(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Email Message & Calendar");
+        ui.add_space(5.0);
+
+        if let Some(missing) = &self.compose.missing_template {
+            let mut create = false;
+            egui::Frame::group(ui.style()).show(ui, |ui| {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "⚠ The template file in config.toml doesn't exist: {}",
+                        missing.path.display()
+                    ),
+                );
+                ui.horizontal(|ui| {
+                    create = ui
+                        .button("📝 Create it from the default template")
+                        .on_hover_text("Writes a starter template there and loads it. Edit the file, or sender.template_path, any time.")
+                        .clicked();
+                    if let Some(error) = &missing.error {
+                        ui.colored_label(ui.visuals().error_fg_color, error);
+                    }
+                });
+            });
+            if create {
+                self.create_missing_template();
+            }
+            ui.add_space(5.0);
+        }
+
+        // --- Email Subject ---
+        ui.horizontal(|ui| {
+            ui.label("Subject:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.compose.subject).desired_width(f32::INFINITY),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("CC:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.compose.cc)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Optional, comma-separated. Recipient fields work, e.g. {{assistant_email}}"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("BCC:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.compose.bcc)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Optional, comma-separated"),
+            );
+        });
+        ui.horizontal(|ui| {
+            ui.label("Archive BCC:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.compose.archive_bcc)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Optional, one shared mailbox"),
+            )
+            .on_hover_text("BCC'd on every email the app sends, on top of the BCC above. Each email is still sent to one recipient and personalized for them.");
+        });
+        if let Err(e) = validate_archive_bcc(&self.compose.archive_bcc) {
+            ui.colored_label(ui.visuals().error_fg_color, format!("The {}", e));
+        }
+        ui.horizontal(|ui| {
+            ui.label("No slots:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.compose.no_slots_text)
+                    .desired_width(f32::INFINITY)
+                    .hint_text("Leave empty to send {{availabilities}} blank when there are no slots"),
+            )
+            .on_hover_text("Used as the only {{availabilities}} line when no slots are offered. Templates can also check {{has_availabilities}}.");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Priority:");
+            egui::ComboBox::from_id_salt("email_priority")
+                .selected_text(self.compose.priority.label())
+                .show_ui(ui, |ui| {
+                    for priority in Priority::ALL {
+                        ui.selectable_value(&mut self.compose.priority, priority, priority.label());
+                    }
+                })
+                .response
+                .on_hover_text("High or Low adds X-Priority and Importance headers, which most mail clients show as a flag");
+        });
+        ui.add_space(8.0);
+
+        // --- Email Body ---
+        let body_id = egui::Id::new("email_body");
+        let mut insert = None;
+        ui.horizontal(|ui| {
+            ui.label("Body:");
+            ui.menu_button("➕ Insert variable", |ui| {
+                for variable in BUILT_IN_VARIABLES {
+                    if ui
+                        .button(format!("{{{{ {} }}}}", variable.name))
+                        .on_hover_text(variable.description)
+                        .clicked()
+                    {
+                        insert = Some(variable.snippet.to_string());
+                        ui.close_menu();
+                    }
+                }
+                let fields = self.custom_field_names();
+                if !fields.is_empty() {
+                    ui.separator();
+                    ui.weak("Recipient fields");
+                    let total = self.compose.recipients.len();
+                    for (name, count) in fields {
+                        let coverage = if count == total {
+                            format!("Set for all {} recipients", total)
+                        } else {
+                            format!(
+                                "Set for {} of {} recipients. Emails to the others fail \
+                                 unless you add | default(value=\"\")",
+                                count, total
+                            )
+                        };
+                        if ui
+                            .button(format!("{{{{ {} }}}}", name))
+                            .on_hover_text(coverage)
+                            .clicked()
+                        {
+                            insert = Some(format!("{{{{ {} }}}}", name));
+                            ui.close_menu();
+                        }
+                    }
+                }
+            });
+        });
+        egui::ScrollArea::vertical()
+        .id_salt("email_body_scroll")
+        .max_height(200.0)
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            ui.add(
+                egui::TextEdit::multiline(&mut self.compose.body)
+                    .id(body_id)
+                    .desired_width(f32::INFINITY)
+                    .desired_rows(8)
+                    .hint_text("Enter email body here. Use {{recipient_name}}, {{sender_name}}, and {{availabilities}} as placeholders.")
+                    .frame(true),
+            );
+        });
+        if let Some(snippet) = insert {
+            self.insert_into_body(ui.ctx(), body_id, &snippet);
+        }
+        ui.add_space(8.0);
+        ui.separator();
+        ui.add_space(10.0);
+
+        self.ui_calendar(ui);
+        ui.add_space(10.0);
+        ui.separator();
+    }
+
+    /// Custom field names across the recipient list, with how many recipients set each.
+    /// Fields named like a built-in variable are left out; they can't override it.
+    fn custom_field_names(&self) -> BTreeMap<&str, usize> {
+        let mut names = BTreeMap::new();
+        for recipient in &self.compose.recipients {
+            for key in recipient.fields.keys() {
+                if !BUILT_IN_VARIABLES.iter().any(|v| v.name == key) {
+                    *names.entry(key.as_str()).or_insert(0) += 1;
+                }
+            }
+        }
+        names
+    }
+
+    /// Puts `snippet` at the body's cursor (over any selection), or at the end if it was never focused.
+    fn insert_into_body(&mut self, ctx: &egui::Context, id: egui::Id, snippet: &str) {
+        let mut state = egui::TextEdit::load_state(ctx, id).unwrap_or_default();
+        let len = self.compose.body.chars().count();
+        let range = state
+            .cursor
+            .char_range()
+            .map(|range| {
+                let [start, end] = range.sorted();
+                start.index.min(len)..end.index.min(len)
+            })
+            .unwrap_or(len..len);
+        let cursor = insert_snippet(&mut self.compose.body, range, snippet);
+        state
+            .cursor
+            .set_char_range(Some(CCursorRange::one(CCursor::new(cursor))));
+        state.store(ctx, id);
+        ctx.memory_mut(|memory| memory.request_focus(id));
+    }
+}
+
+/// Replaces the characters in `range` (char indices, like egui's cursor) with `snippet`.
+/// Returns the char index just after the inserted text.
+fn insert_snippet(text: &mut String, range: std::ops::Range<usize>, snippet: &str) -> usize {
+    let byte = |chars: usize| {
+        text.char_indices()
+            .nth(chars)
+            .map_or(text.len(), |(index, _)| index)
+    };
+    let (start, end) = (byte(range.start), byte(range.end));
+    text.replace_range(start..end, snippet);
+    range.start + snippet.chars().count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snippets_go_in_at_character_positions() {
+        let mut text = "Hi Zoë, bye".to_string();
+        let cursor = insert_snippet(&mut text, 6..6, " {{ x }}");
+        assert_eq!(text, "Hi Zoë {{ x }}, bye");
+        assert_eq!(cursor, 14);
+
+        // A selection is replaced, and the end of the text works too
+        let mut text = "Hi NAME".to_string();
+        assert_eq!(insert_snippet(&mut text, 3..7, "{{ n }}"), 10);
+        assert_eq!(text, "Hi {{ n }}");
+        let mut text = "é".to_string();
+        insert_snippet(&mut text, 1..1, "!");
+        assert_eq!(text, "é!");
+    }
+}
@@ -0,0 +1,441 @@
+// src/app/ui/dialogs.rs
+
+use crate::app::MyApp;
+use crate::config::SmtpTlsMode;
+use crate::email_sender::send_eta;
+use crate::email_sender::spam_check::{SpamReport, LIKELY_SPAM_SCORE};
+use directories_next::ProjectDirs;
+use eframe::egui;
+use std::path::Path;
+
+impl MyApp {
+    pub(super) fn ui_calendar_error_dialog(&mut self, ctx: &egui::Context) {
+        let Some(connect_error) = &self.calendar.error else {
+            return;
+        };
+        let mut open = self.calendar.show_error_details;
+        egui::Window::new("Calendar Connection Failed")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(egui::RichText::new(connect_error.summary()).strong());
+                ui.add_space(5.0);
+                ui.label(format!("Suggested fix: {}", connect_error.suggestion()));
+                ui.add_space(8.0);
+                ui.label("Error chain:");
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    egui::ScrollArea::vertical()
+                        .max_height(150.0)
+                        .show(ui, |ui| {
+                            ui.monospace(&connect_error.details);
+                        });
+                });
+                ui.add_space(8.0);
+                if ui
+                    .button("📋 Copy details")
+                    .on_hover_text("Copy the full report for a bug report")
+                    .clicked()
+                {
+                    ui.ctx().copy_text(connect_error.report());
+                }
+            });
+        self.calendar.show_error_details = open;
+    }
+
+    pub(super) fn ui_setup_checklist(&mut self, ctx: &egui::Context) {
+        if !self.show_setup_checklist {
+            return;
+        }
+        let smtp_done = if self.smtp.use_webhook {
+            !self.smtp.webhook_url.trim().is_empty()
+        } else {
+            !self.smtp.host.trim().is_empty()
+                && (self.smtp.no_auth || !self.smtp.user.trim().is_empty())
+                && !self.smtp.from_email.trim().is_empty()
+        };
+        let credentials_done = Path::new(&self.calendar_settings.credentials_path).exists();
+        let recipients_done = !self.compose.recipients.is_empty();
+
+        let mut open = true;
+        let mut dismissed = false;
+        egui::Window::new("Getting Started")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .default_width(440.0)
+            .show(ctx, |ui| {
+                ui.label("A few things are needed before you can send invitations:");
+                ui.add_space(8.0);
+
+                let step = |ui: &mut egui::Ui, done: bool, title: &str, hint: &str| {
+                    ui.horizontal(|ui| {
+                        ui.label(if done { "✅" } else { "⬜" });
+                        ui.label(egui::RichText::new(title).strong());
+                    });
+                    ui.indent(title, |ui| ui.label(hint));
+                    ui.add_space(4.0);
+                };
+                step(
+                    ui,
+                    smtp_done,
+                    "1. SMTP settings",
+                    "Fill in host, user, password and From address in the SMTP panel, or put them in config.toml. Relays that don't need a login can tick \"No authentication\". To send through an internal API instead, pick \"Webhook\" and enter its URL.",
+                );
+                step(
+                    ui,
+                    credentials_done,
+                    "2. Google credentials",
+                    "Download an OAuth client (Desktop app) from Google Cloud Console and save it as credentials.json.",
+                );
+                step(
+                    ui,
+                    recipients_done,
+                    "3. Add a recipient",
+                    "Use the Recipients panel on the right.",
+                );
+
+                ui.add_space(6.0);
+                ui.collapsing("Where files live", |ui| {
+                    let cwd = std::env::current_dir().unwrap_or_default();
+                    egui::Grid::new("setup_paths_grid")
+                        .num_columns(2)
+                        .spacing([10.0, 4.0])
+                        .show(ui, |ui| {
+                            ui.label("Config:");
+                            ui.monospace(cwd.join("config.toml").display().to_string());
+                            ui.end_row();
+                            ui.label("Credentials:");
+                            ui.monospace(cwd.join(&self.calendar_settings.credentials_path).display().to_string());
+                            ui.end_row();
+                            ui.label("Token cache:");
+                            ui.monospace(cwd.join(&self.calendar_settings.token_cache_path).display().to_string());
+                            ui.end_row();
+                            ui.label("Saved state:");
+                            ui.monospace(
+                                ProjectDirs::from("com", "YourOrg", "CoffeeChatHelper")
+                                    .map(|dirs| {
+                                        dirs.config_dir().join("app_state.json").display().to_string()
+                                    })
+                                    .unwrap_or_else(|| "unavailable".to_string()),
+                            );
+                            ui.end_row();
+                        });
+                });
+
+                ui.add_space(8.0);
+                if ui.button("Got it, don't show again").clicked() {
+                    dismissed = true;
+                }
+            });
+        if !open || dismissed {
+            self.show_setup_checklist = false;
+            self.setup_checklist_dismissed = true;
+        }
+    }
+
+    /// One summary of what's about to be sent, shown before the send starts.
+    pub(super) fn ui_send_confirmation(&mut self, ctx: &egui::Context) {
+        if !self.compose.confirming_send {
+            return;
+        }
+        let (to_send, duplicates) = self.recipients_for_send();
+        let role_groups = self
+            .compose
+            .role_accounts
+            .grouped(to_send.iter().map(|r| r.email.as_str()));
+        let flagged: usize = role_groups.values().map(Vec::len).sum();
+        let recipients = if self.compose.exclude_role_accounts {
+            to_send.len() - flagged
+        } else {
+            to_send.len()
+        };
+        let pinned = self.compose.recipients.iter().filter(|r| r.pinned).count();
+        let slots = self.calendar.available_slots.len();
+        let staleness = self.slot_staleness_warning();
+        let frozen_busy = self.frozen_busy_warning();
+        let security = match self.smtp.tls_mode {
+            SmtpTlsMode::Starttls => "STARTTLS",
+            SmtpTlsMode::Implicit => "TLS",
+            SmtpTlsMode::PlaintextLocalhost => "unencrypted",
+        };
+        let (via_label, via) = if self.smtp.use_webhook && !self.demo_mode {
+            ("Webhook:", self.smtp.webhook_url.trim().to_string())
+        } else {
+            let mut smtp = format!(
+                "{} via {}:{} ({}",
+                self.smtp.from_email, self.smtp.host, self.smtp.port_str, security
+            );
+            if self.smtp.no_auth {
+                smtp.push_str(", no login");
+            }
+            if self.smtp.proxy.is_some() {
+                smtp.push_str(", through proxy");
+            }
+            smtp.push(')');
+            ("SMTP:", smtp)
+        };
+
+        let mut send = false;
+        let mut cancel = false;
+        let mut test = false;
+        let mut open = true;
+        egui::Window::new("Send Invitations?")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                egui::Grid::new("send_confirmation_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Recipients:");
+                        if pinned > 0 {
+                            ui.label(format!("{} ({} pinned, sent first)", recipients, pinned));
+                        } else {
+                            ui.label(recipients.to_string());
+                        }
+                        ui.end_row();
+                        if !duplicates.is_empty() {
+                            ui.label("Duplicates:");
+                            let emails: Vec<&str> =
+                                duplicates.iter().map(|r| r.email.as_str()).collect();
+                            ui.label(format!("{} skipped", duplicates.len()))
+                                .on_hover_text(emails.join("\n"));
+                            ui.end_row();
+                        }
+                        if flagged > 0 {
+                            ui.label("Role addresses:");
+                            ui.vertical(|ui| {
+                                ui.colored_label(
+                                    ui.visuals().warn_fg_color,
+                                    format!(
+                                        "{} shared mailboxes, which rarely reach a person",
+                                        flagged
+                                    ),
+                                );
+                                for (role, emails) in &role_groups {
+                                    ui.label(format!("{}@: {}", role, emails.join(", ")));
+                                }
+                                ui.toggle_value(
+                                    &mut self.compose.exclude_role_accounts,
+                                    "Exclude all flagged",
+                                )
+                                .on_hover_text("Leave them out of this send; the results list them as skipped. Sending to them anyway is fine.");
+                            });
+                            ui.end_row();
+                        }
+                        if self.drips(recipients) {
+                            let batch = self.drip_batch_size();
+                            let later = recipients.div_ceil(batch) - 1;
+                            ui.label("Batches:");
+                            ui.label(format!(
+                                "{} now, the rest in {} more batches, one every {} days",
+                                batch, later, self.compose.drip_interval_days
+                            ));
+                            ui.end_row();
+                        }
+                        let sending_now = if self.drips(recipients) {
+                            recipients.min(self.drip_batch_size())
+                        } else {
+                            recipients
+                        };
+                        ui.label("Estimated time:");
+                        ui.label(send_eta::describe(send_eta::estimate(
+                            sending_now,
+                            self.time_per_email(),
+                        )))
+                        .on_hover_text(if self.compose.time_per_email.is_some() {
+                            "At the pace of the last batch; updated as this one goes"
+                        } else {
+                            "A guess until the first emails have been timed"
+                        });
+                        ui.end_row();
+                        if !self.compose.cc.trim().is_empty() {
+                            ui.label("CC:");
+                            ui.label(&self.compose.cc);
+                            ui.end_row();
+                        }
+                        if !self.compose.bcc.trim().is_empty() {
+                            ui.label("BCC:");
+                            ui.label(&self.compose.bcc);
+                            ui.end_row();
+                        }
+                        let archive = self.compose.archive_bcc.trim();
+                        if !archive.is_empty() {
+                            ui.label("Archive BCC:");
+                            ui.label(format!("{} (a copy of every email)", archive));
+                            ui.end_row();
+                        }
+                        ui.label("Slots offered:");
+                        if let Some(frozen) = &self.freeze.frozen {
+                            ui.label(
+                                egui::RichText::new(format!(
+                                    "🧊 Frozen \"{}\" ({} lines)",
+                                    frozen.label,
+                                    frozen.lines.len()
+                                ))
+                                .color(super::FROZEN_COLOR),
+                            );
+                        } else if slots == 0 {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                "None ({{availabilities}} will be empty)",
+                            );
+                        } else {
+                            let cap = self.calendar_settings.max_slots_in_email as usize;
+                            if cap > 0 && slots > cap {
+                                ui.label(format!(
+                                    "{} of {} (\"+{} more on request\")",
+                                    cap,
+                                    slots,
+                                    slots - cap
+                                ));
+                            } else {
+                                ui.label(slots.to_string());
+                            }
+                        }
+                        ui.end_row();
+                        ui.label("Subject:");
+                        ui.label(&self.compose.subject);
+                        ui.end_row();
+                        ui.label("Template:");
+                        ui.label(format!("{} (as edited here)", self.compose.template_source));
+                        ui.end_row();
+                        ui.label(via_label);
+                        ui.label(&via);
+                        ui.end_row();
+                    });
+                if let Some(warning) = staleness.as_ref().or(frozen_busy.as_ref()) {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", warning));
+                }
+                if let Some(warning) = &self.compose.readability_warning {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", warning));
+                }
+                if let Some(report) = &self.compose.spam_report {
+                    self.ui_spam_report(ui, report);
+                }
+                if !self.compose.spam_test_address.trim().is_empty() {
+                    ui.horizontal(|ui| {
+                        let sending = self.background.is_sending_email;
+                        test = ui
+                            .add_enabled(!sending, egui::Button::new("✉ Send test copy"))
+                            .on_hover_text(format!(
+                                "Send the first email to {} to get its real score; CC and BCC are left off",
+                                self.compose.spam_test_address.trim()
+                            ))
+                            .clicked();
+                        match &self.compose.spam_test {
+                            Some(Ok(reply)) => {
+                                ui.weak(format!("Sent ({}). Check the tester's page.", reply));
+                            }
+                            Some(Err(error)) => {
+                                ui.colored_label(ui.visuals().error_fg_color, error);
+                            }
+                            None if sending => {
+                                ui.add(egui::Spinner::new().size(14.0));
+                            }
+                            None => {}
+                        }
+                    });
+                }
+                ui.add_space(8.0);
+                ui.checkbox(&mut self.compose.skip_send_confirmation, "Don't ask again")
+                    .on_hover_text("Turn back on with \"Confirm before sending\"");
+                ui.horizontal(|ui| {
+                    send = ui
+                        .add_enabled(!self.background.is_sending_email, egui::Button::new("🚀 Send"))
+                        .clicked();
+                    cancel = ui.button("Cancel").clicked();
+                });
+            });
+
+        if test {
+            self.send_spam_test();
+        }
+        if send {
+            self.compose.confirming_send = false;
+            self.handle_send_invitations();
+        } else if cancel || !open {
+            self.compose.confirming_send = false;
+            self.status_message = "Send cancelled.".to_string();
+        }
+    }
+
+    /// The spam score, with what to change when it isn't low.
+    fn ui_spam_report(&self, ui: &mut egui::Ui, report: &SpamReport) {
+        let color = match report.risk() {
+            "high" => ui.visuals().error_fg_color,
+            "borderline" => ui.visuals().warn_fg_color,
+            _ => ui.visuals().text_color(),
+        };
+        let header = format!(
+            "Spam check: {:.1} ({} risk, {:.1} or more is likely spam)",
+            report.score(),
+            report.risk(),
+            LIKELY_SPAM_SCORE
+        );
+        if report.findings.is_empty() {
+            ui.label(header)
+                .on_hover_text("A local guess; sender reputation and DNS setup matter more");
+            return;
+        }
+        egui::CollapsingHeader::new(egui::RichText::new(header).color(color))
+            .id_salt("spam_report")
+            .default_open(report.risk() != "low")
+            .show(ui, |ui| {
+                for finding in &report.findings {
+                    ui.label(format!("+{:.1}  {}", finding.points, finding.suggestion));
+                }
+                ui.weak("A local guess; sender reputation and DNS setup matter more.");
+            });
+    }
+
+    /// "Save before exiting?" when the window is closed with unsaved changes.
+    pub(super) fn ui_exit_prompt(&mut self, ctx: &egui::Context) {
+        if !self.save.confirming_exit {
+            return;
+        }
+        let mut exit = false;
+        let mut open = true;
+        egui::Window::new("Save before exiting?")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label("Your settings, recipients or template changed since the last save.");
+                if let Some(error) = &self.save.error {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("Saving failed: {}", error),
+                    );
+                }
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("💾 Save and exit").clicked() {
+                        self.save_state();
+                        // Stay open on failure so the error can be read
+                        exit = !self.save.dirty;
+                    }
+                    if ui.button("Exit without saving").clicked() {
+                        self.save.discard_on_exit = true;
+                        exit = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.save.confirming_exit = false;
+                    }
+                });
+            });
+        if !open {
+            self.save.confirming_exit = false;
+        }
+        if exit {
+            self.save.confirming_exit = false;
+            self.save.exit_confirmed = true;
+            ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+        }
+    }
+}
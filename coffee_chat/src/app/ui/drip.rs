@@ -0,0 +1,163 @@
+// src/app/ui/drip.rs
+
+use crate::app::MyApp;
+use crate::calendar::format::format_day;
+use crate::drip::BatchStatus;
+use chrono::{Duration, Local, Utc};
+use eframe::egui;
+
+impl MyApp {
+    /// "Send in batches of N every M days", under the Send button.
+    pub(super) fn ui_drip_settings(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut self.compose.drip_enabled, "Send in batches of")
+                .on_hover_text(
+                    "Send the first batch now and the rest on later days, each with freshly fetched slots",
+                );
+            ui.add_enabled(
+                self.compose.drip_enabled,
+                egui::DragValue::new(&mut self.compose.drip_batch_size)
+                    .speed(0.2)
+                    .range(1..=500),
+            );
+            ui.label("every");
+            ui.add_enabled(
+                self.compose.drip_enabled,
+                egui::DragValue::new(&mut self.compose.drip_interval_days)
+                    .speed(0.1)
+                    .range(1..=30)
+                    .suffix(" days"),
+            );
+        });
+    }
+
+    /// Batches of the running drip schedule, with their dates, to move, send early or cancel.
+    pub(super) fn ui_drip_schedule(&mut self, ctx: &egui::Context) {
+        if !self.drip.open {
+            return;
+        }
+        let today = Local::now().date_naive();
+        let now = Utc::now();
+        let dispatching = self.drip.dispatching;
+        let mut open = true;
+        let mut moved = None;
+        let mut send_now = false;
+        let mut cancel = false;
+        egui::Window::new("Drip Schedule")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                let Some(schedule) = &self.drip.schedule else {
+                    ui.label("No batches are waiting to be sent.");
+                    return;
+                };
+                ui.weak(format!(
+                    "\"{}\", started {}. Due batches go out while the app is running, or with `coffee-chat send --due`.",
+                    schedule.invitation.subject,
+                    schedule.created_at.with_timezone(&Local).format("%b %-d")
+                ));
+                if let Some(campaign) = &schedule.invitation.campaign {
+                    ui.label(format!("Campaign: {}", campaign));
+                }
+                ui.add_space(5.0);
+                egui::Grid::new("drip_schedule_grid")
+                    .num_columns(3)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        for (index, batch) in schedule.batches.iter().enumerate() {
+                            let names: Vec<&str> =
+                                batch.recipients.iter().map(|r| r.email.as_str()).collect();
+                            ui.label(format_day(batch.due));
+                            ui.label(format!("{} recipients", batch.recipients.len()))
+                                .on_hover_text(names.join("\n"));
+                            match &batch.status {
+                                BatchStatus::Sent { sent, failed, .. } => {
+                                    if *failed > 0 {
+                                        ui.colored_label(
+                                            ui.visuals().warn_fg_color,
+                                            format!("Sent {}, {} failed", sent, failed),
+                                        );
+                                    } else {
+                                        ui.label(format!("✅ Sent {}", sent));
+                                    }
+                                }
+                                BatchStatus::Cancelled { .. } => {
+                                    ui.weak("Cancelled");
+                                }
+                                BatchStatus::Pending if dispatching == Some(index) => {
+                                    ui.horizontal(|ui| {
+                                        ui.add(egui::Spinner::new().size(12.0));
+                                        ui.label("Sending…");
+                                    });
+                                }
+                                BatchStatus::Pending if batch.is_claimed(now) => {
+                                    let owner = batch
+                                        .claim
+                                        .as_ref()
+                                        .map(|claim| claim.owner.as_str())
+                                        .unwrap_or_default();
+                                    ui.label("Being sent elsewhere")
+                                        .on_hover_text(format!("By {}", owner));
+                                }
+                                BatchStatus::Pending => {
+                                    ui.horizontal(|ui| {
+                                        if ui
+                                            .add_enabled(
+                                                batch.due > today,
+                                                egui::Button::new("◀").small(),
+                                            )
+                                            .on_hover_text("A day earlier")
+                                            .clicked()
+                                        {
+                                            moved = Some((index, batch.due - Duration::days(1)));
+                                        }
+                                        if ui
+                                            .small_button("▶")
+                                            .on_hover_text("A day later")
+                                            .clicked()
+                                        {
+                                            moved = Some((index, batch.due + Duration::days(1)));
+                                        }
+                                        if ui
+                                            .add_enabled(
+                                                batch.due > today,
+                                                egui::Button::new("Send today").small(),
+                                            )
+                                            .clicked()
+                                        {
+                                            moved = Some((index, today));
+                                            send_now = true;
+                                        }
+                                    });
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(notice) = &self.drip.notice {
+                    ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", notice));
+                }
+                ui.add_space(5.0);
+                cancel = ui
+                    .add_enabled(
+                        dispatching.is_none(),
+                        egui::Button::new("Cancel remaining batches"),
+                    )
+                    .clicked();
+            });
+        if !open {
+            self.drip.open = false;
+        }
+        if let Some((index, due)) = moved {
+            self.move_drip_batch(index, due);
+        }
+        if send_now {
+            self.drip.checked_at = None;
+        }
+        if cancel {
+            self.cancel_drip();
+        }
+    }
+}
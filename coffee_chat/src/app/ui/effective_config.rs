@@ -0,0 +1,72 @@
+// src/app/ui/effective_config.rs
+
+use crate::app::effective_config::effective_config_text;
+use crate::app::MyApp;
+use eframe::egui;
+
+impl MyApp {
+    /// "Effective configuration": every setting, its value and where it came from.
+    pub(super) fn ui_effective_config(&mut self, ctx: &egui::Context) {
+        if !self.show_effective_config {
+            return;
+        }
+        let settings = self.effective_settings();
+        let mut open = true;
+        egui::Window::new("Effective Configuration")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                ui.weak("What the app is using right now. Passwords are hidden.");
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("📋 Copy as text")
+                        .on_hover_text("For bug reports")
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(effective_config_text(&settings));
+                    }
+                    ui.checkbox(
+                        &mut self.prefer_config_file,
+                        "config.toml overrides the saved session",
+                    )
+                    .on_hover_text(
+                        "From the next start, config.toml values replace what was saved. Off: config.toml only fills in a first run.",
+                    );
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .id_salt("effective_config_scroll")
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("effective_config_grid")
+                            .num_columns(3)
+                            .striped(true)
+                            .spacing([12.0, 4.0])
+                            .show(ui, |ui| {
+                                ui.strong("Setting");
+                                ui.strong("Value");
+                                ui.strong("Source");
+                                ui.end_row();
+                                for setting in &settings {
+                                    ui.monospace(setting.key);
+                                    ui.label(&setting.value);
+                                    if setting.changed {
+                                        ui.label(format!(
+                                            "{} (changed this session)",
+                                            setting.source.label()
+                                        ));
+                                    } else {
+                                        ui.label(setting.source.label());
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+        if !open {
+            self.show_effective_config = false;
+        }
+    }
+}
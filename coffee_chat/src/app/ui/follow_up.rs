@@ -0,0 +1,158 @@
+// src/app/ui/follow_up.rs
+
+use crate::app::MyApp;
+use crate::calendar::format::{format_slot_in, SlotFormat};
+use crate::email_sender::history::EmailKind;
+use crate::email_sender::subject::line_break_position;
+use chrono::Local;
+use eframe::egui;
+
+impl MyApp {
+    /// "Cancel…" / "Reschedule…" from the history: the reply as it will go out,
+    /// its template, and whether to delete the booked event.
+    pub(super) fn ui_follow_up_dialog(&mut self, ctx: &egui::Context) {
+        let Some(draft) = &self.follow_up.draft else {
+            return;
+        };
+        let title = match draft.kind {
+            EmailKind::Reschedule => "Reschedule",
+            _ => "Cancel Coffee Chat",
+        };
+        let sending = self.background.is_sending_email;
+        let connected = self.calendar.source.is_some();
+        let mut open = true;
+        let mut rerender = false;
+        let mut send = false;
+        let mut close = false;
+        egui::Window::new(title)
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                let Some(draft) = &mut self.follow_up.draft else {
+                    return;
+                };
+                ui.label(format!(
+                    "To {} <{}>, replying to \"{}\"",
+                    draft.original.name, draft.original.email, draft.original.subject
+                ));
+                if draft.original.message_id.is_none() {
+                    ui.weak("The original's Message-ID wasn't recorded, so this starts a new thread.");
+                }
+                match &draft.meeting {
+                    Some(meeting) => {
+                        let when =
+                            format_slot_in(meeting.start, meeting.end, SlotFormat::default(), &Local);
+                        ui.checkbox(
+                            &mut draft.delete_event,
+                            format!("Delete the calendar event ({})", when),
+                        )
+                        .on_hover_text("Deleted after the email is sent; Google tells them too");
+                    }
+                    None => {
+                        ui.weak("No meeting was booked with them from the app.");
+                    }
+                }
+                if draft.kind == EmailKind::Reschedule {
+                    if draft.awaiting_slots {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Fetching fresh slots...");
+                        });
+                    } else if !connected {
+                        ui.colored_label(
+                            ui.visuals().warn_fg_color,
+                            "Calendar not connected; offering the slots already on screen.",
+                        );
+                    }
+                }
+
+                egui::CollapsingHeader::new("Template")
+                    .id_salt("follow_up_template")
+                    .show(ui, |ui| {
+                        ui.weak("Also has {{ original_subject }} and {{ meeting_time }}, empty without a booked meeting.");
+                        let (subject, body) = self.follow_up.templates.for_kind_mut(draft.kind);
+                        let subject = ui.add(
+                            egui::TextEdit::singleline(subject).desired_width(f32::INFINITY),
+                        );
+                        let body = ui.add(
+                            egui::TextEdit::multiline(body)
+                                .desired_width(f32::INFINITY)
+                                .desired_rows(6)
+                                .code_editor(),
+                        );
+                        // Template changes show up unless the email was edited by hand
+                        rerender |= (subject.changed() || body.changed()) && !draft.edited;
+                    });
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Email");
+                    if draft.edited {
+                        ui.weak("(edited)");
+                        if ui
+                            .small_button("↺ Reset to template")
+                            .on_hover_text("Discard your edits and render the template again")
+                            .clicked()
+                        {
+                            rerender = true;
+                        }
+                    }
+                });
+                let email = &mut draft.email;
+                let subject = ui.add(
+                    egui::TextEdit::singleline(&mut email.subject)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("Subject"),
+                );
+                let body = egui::ScrollArea::vertical()
+                    .id_salt("follow_up_body")
+                    .max_height(260.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut email.body)
+                                .desired_width(f32::INFINITY)
+                                .desired_rows(10),
+                        )
+                    })
+                    .inner;
+                if subject.changed() || body.changed() {
+                    draft.edited = true;
+                }
+                let line_break = line_break_position(&draft.email.subject);
+                if let Some(position) = line_break {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("Subject contains a line break at position {}", position),
+                    );
+                }
+                if let Some(error) = &draft.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    let can_send = !sending
+                        && !draft.in_flight
+                        && !draft.awaiting_slots
+                        && line_break.is_none();
+                    send = ui
+                        .add_enabled(can_send, egui::Button::new("🚀 Send"))
+                        .clicked();
+                    close = ui.button("Close").clicked();
+                    if draft.in_flight {
+                        ui.add(egui::Spinner::new().size(14.0));
+                    }
+                });
+            });
+        if !open || close {
+            self.follow_up.draft = None;
+        }
+        if rerender {
+            self.render_follow_up();
+        }
+        if send {
+            self.send_follow_up();
+        }
+    }
+}
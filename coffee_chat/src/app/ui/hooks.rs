@@ -0,0 +1,79 @@
+// src/app/ui/hooks.rs
+
+use crate::app::MyApp;
+use eframe::egui;
+
+impl MyApp {
+    /// "Hooks": the hooks in config.toml, each with a button to try it.
+    pub(super) fn ui_hooks_dialog(&mut self, ctx: &egui::Context) {
+        if !self.hooks.open {
+            return;
+        }
+        let mut open = true;
+        let mut test = None;
+        egui::Window::new("Hooks")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(460.0)
+            .show(ctx, |ui| {
+                let hooks = &self.hooks;
+                if hooks.configured.is_empty() {
+                    ui.label("No hooks yet.");
+                    ui.weak("Add [[hooks]] entries to config.toml and restart. See RUN.md.");
+                    return;
+                }
+                ui.weak(
+                    "From config.toml. \"Test\" runs one with made-up values and \"test\": true.",
+                );
+                ui.separator();
+                egui::Grid::new("hooks_grid")
+                    .num_columns(3)
+                    .striped(true)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        for (index, hook) in hooks.configured.iter().enumerate() {
+                            ui.monospace(hook.event.name());
+                            ui.label(hook.describe());
+                            let testing = hooks.testing == Some(index);
+                            if ui
+                                .add_enabled(
+                                    hooks.testing.is_none(),
+                                    egui::Button::new(if testing {
+                                        "Testing…"
+                                    } else {
+                                        "▶ Test"
+                                    }),
+                                )
+                                .clicked()
+                            {
+                                test = Some(index);
+                            }
+                            ui.end_row();
+                            match hooks.results.get(&index) {
+                                Some(Ok(summary)) => {
+                                    ui.label("");
+                                    ui.label(format!("✔ {}", summary));
+                                    ui.end_row();
+                                }
+                                Some(Err(e)) => {
+                                    ui.label("");
+                                    ui.colored_label(
+                                        ui.visuals().error_fg_color,
+                                        format!("✖ {}", e),
+                                    );
+                                    ui.end_row();
+                                }
+                                None => {}
+                            }
+                        }
+                    });
+            });
+        if let Some(index) = test {
+            self.test_hook(index);
+        }
+        if !open {
+            self.hooks.open = false;
+        }
+    }
+}
@@ -0,0 +1,354 @@
+// src/app/ui/mod.rs
+mod availability_page;
+mod backup;
+mod booking;
+mod calendar;
+mod compose;
+mod dialogs;
+mod drip;
+mod effective_config;
+mod follow_up;
+mod hooks;
+mod quick_send;
+mod recipients;
+mod reset_data;
+mod results;
+mod safe_mode;
+mod smtp;
+
+use crate::app::MyApp;
+use crate::email_sender::send_eta;
+use eframe::egui;
+// Import necessary egui types for styling
+use egui::{Color32, Margin, Stroke, Vec2, Visuals};
+use log::info;
+
+// The frozen-slots indicators, in the status bar and above the slot list
+const FROZEN_COLOR: Color32 = Color32::from_rgb(40, 120, 190);
+
+impl MyApp {
+    pub(super) fn configure_visuals(cc: &eframe::CreationContext<'_>) {
+        let mut style = (*cc.egui_ctx.style()).clone();
+
+        // Define Ayu Light theme colors (same as before)
+        let bg_color = Color32::from_rgb(250, 250, 250);
+        let panel_color = Color32::from_rgb(252, 252, 252);
+        let accent_color = Color32::from_rgb(255, 184, 108);
+        let text_color = Color32::from_rgb(75, 80, 92);
+        let faint_text_color = Color32::from_rgb(138, 143, 153);
+        let border_color = Color32::from_rgb(224, 224, 224);
+        let widget_bg_inactive = Color32::from_rgb(240, 240, 240);
+        let widget_bg_hovered = Color32::from_rgb(230, 230, 230);
+        let widget_bg_active = Color32::from_rgb(220, 220, 220);
+        let selection_color = accent_color.linear_multiply(0.3);
+        let error_color = Color32::from_rgb(255, 77, 77);
+
+        // Create custom light visuals based on Ayu
+        let mut visuals = Visuals::light();
+        visuals.override_text_color = Some(text_color);
+        visuals.widgets.noninteractive.fg_stroke = Stroke::new(1.0, text_color);
+        visuals.widgets.inactive.fg_stroke = Stroke::new(1.0, faint_text_color);
+        visuals.widgets.hovered.fg_stroke = Stroke::new(1.0, text_color);
+        visuals.widgets.active.fg_stroke = Stroke::new(1.0, text_color);
+
+        // Backgrounds
+        visuals.window_fill = bg_color;
+        visuals.panel_fill = panel_color;
+        visuals.extreme_bg_color = bg_color;
+        visuals.faint_bg_color = Color32::from_rgb(245, 245, 245);
+
+        // Widget backgrounds & strokes
+        visuals.widgets.noninteractive.bg_fill = panel_color;
+        visuals.widgets.noninteractive.bg_stroke = Stroke::NONE;
+        visuals.widgets.inactive.bg_fill = widget_bg_inactive;
+        visuals.widgets.inactive.bg_stroke = Stroke::new(1.0, border_color);
+        visuals.widgets.hovered.bg_fill = widget_bg_hovered;
+        visuals.widgets.hovered.bg_stroke = Stroke::new(1.0, border_color.gamma_multiply(1.2));
+        visuals.widgets.active.bg_fill = widget_bg_active;
+        visuals.widgets.active.bg_stroke = Stroke::new(1.0, border_color.gamma_multiply(1.5));
+
+        // Selection, Links, Errors
+        visuals.selection.bg_fill = selection_color;
+        visuals.selection.stroke = Stroke::new(1.0, accent_color);
+        visuals.hyperlink_color = Color32::from_rgb(51, 153, 255);
+        visuals.error_fg_color = error_color;
+        visuals.warn_fg_color = accent_color;
+
+        // Window & Panel Appearance
+        visuals.window_stroke = Stroke::new(1.0, border_color);
+        // FIX: Replace Shadow::small_light()
+        visuals.window_shadow = egui::epaint::Shadow::NONE;
+        visuals.popup_shadow = egui::epaint::Shadow::NONE; // Also fix popup shadow
+
+        // Apply the custom visuals to the style FIRST
+        style.visuals = visuals; // Assign fixed visuals to style
+
+        // Spacing adjustments (using f32)
+        style.spacing.item_spacing = Vec2::new(8.0, 6.0);
+        style.spacing.button_padding = Vec2::new(10.0, 5.0);
+        style.spacing.interact_size = Vec2::new(40.0, 20.0);
+
+        // Apply the fully customized style to the context
+        cc.egui_ctx.set_style(style);
+        info!("Visuals configured.")
+    }
+
+    /// Status bar, side panel, main panel and any open dialogs.
+    pub(super) fn ui_layout(&mut self, ctx: &egui::Context) {
+        // --- UI Layout ---
+        egui::TopBottomPanel::bottom("status_panel")
+            // FIX: Use f32 for Margin methods
+            .frame(
+                egui::Frame::new()
+                    .inner_margin(Margin::symmetric(10, 5))
+                    .fill(ctx.style().visuals.panel_fill),
+            )
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if self.background.is_sending_email
+                        || self.background.is_connecting_calendar
+                        || self.background.is_fetching_slots
+                        || self.background.is_diagnosing
+                    {
+                        ui.add(egui::Spinner::new().size(14.0));
+                        ui.add_space(5.0);
+                    }
+                    if let Some(eta) = self
+                        .compose
+                        .send_eta
+                        .as_ref()
+                        .filter(|_| self.background.is_sending_email)
+                    {
+                        ui.add(
+                            egui::ProgressBar::new(eta.fraction())
+                                .desired_width(120.0)
+                                .text(format!("{}/{}", eta.done(), eta.total())),
+                        );
+                        ui.weak(format!("{} left", send_eta::describe(eta.remaining())))
+                            .on_hover_text("From how long the emails so far took");
+                        ui.add_space(5.0);
+                    }
+                    ui.label(&self.status_message);
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .small_button("❔ Setup")
+                            .on_hover_text("Show the setup checklist")
+                            .clicked()
+                        {
+                            self.show_setup_checklist = true;
+                        }
+                        let mut saved = match self.save.saved_at {
+                            Some(at) => format!("Last saved at {}", at.format("%H:%M:%S")),
+                            None => "Not saved yet this session".to_string(),
+                        };
+                        if self.save.dirty {
+                            saved.push_str("\nUnsaved changes");
+                        }
+                        let save_label = if self.save.dirty { "💾*" } else { "💾" };
+                        ui.menu_button(save_label, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label("Autosave every");
+                                ui.add(
+                                    egui::DragValue::new(&mut self.save.autosave_seconds)
+                                        .speed(1.0)
+                                        .range(0..=3600)
+                                        .suffix(" s"),
+                                );
+                            });
+                            ui.weak("0 = only save on exit");
+                            if ui
+                                .add_enabled(self.save.dirty, egui::Button::new("Save now"))
+                                .clicked()
+                            {
+                                self.save_state();
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui
+                                .add_enabled(
+                                    !self.demo_mode,
+                                    egui::Button::new("Export / import backup…"),
+                                )
+                                .on_hover_text(
+                                    "Move your recipients and settings to another computer",
+                                )
+                                .clicked()
+                            {
+                                self.backup.open = true;
+                                ui.close_menu();
+                            }
+                            if ui
+                                .button("Effective configuration…")
+                                .on_hover_text("Every setting and where its value came from")
+                                .clicked()
+                            {
+                                self.show_effective_config = true;
+                                ui.close_menu();
+                            }
+                            if ui
+                                .button("Hooks…")
+                                .on_hover_text(
+                                    "Commands and URLs from config.toml run when things happen",
+                                )
+                                .clicked()
+                            {
+                                self.hooks.open = true;
+                                ui.close_menu();
+                            }
+                            ui.separator();
+                            if ui
+                                .add_enabled(
+                                    !self.demo_mode,
+                                    egui::Button::new("Reset all data…"),
+                                )
+                                .on_hover_text(
+                                    "Delete everything the app has saved and start over",
+                                )
+                                .clicked()
+                            {
+                                self.reset_data.open = true;
+                                ui.close_menu();
+                            }
+                        })
+                        .response
+                        .on_hover_text(saved);
+                        if let Some(error) = &self.save.error {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠ Not saved")
+                                .on_hover_text(error);
+                        }
+                        if let Some(seconds) = self
+                            .background
+                            .frame_seconds
+                            .filter(|_| log::log_enabled!(log::Level::Debug))
+                        {
+                            // --verbose: spots a slow panel, e.g. with thousands of recipients
+                            ui.weak(format!("{:.1} ms/frame", seconds * 1000.0));
+                        }
+                        if self.safe_mode.active
+                            && ui
+                                .button(
+                                    egui::RichText::new("🛟 SAFE MODE — nothing loaded or saved")
+                                        .color(ui.visuals().warn_fg_color)
+                                        .strong(),
+                                )
+                                .on_hover_text("Show the files it skipped")
+                                .clicked()
+                        {
+                            self.safe_mode.open = true;
+                        }
+                        if let Some(frozen) = &self.freeze.frozen {
+                            ui.label(
+                                egui::RichText::new(format!("🧊 SLOTS FROZEN — {}", frozen.label))
+                                    .color(FROZEN_COLOR)
+                                    .strong(),
+                            )
+                            .on_hover_text("Every send offers the frozen list. Unfreeze it under Available Slots.");
+                        }
+                        if let Some(address) = self.control.listening {
+                            ui.weak(format!("🔌 API on {}", address)).on_hover_text(
+                                "The control API is on. Scripts find the token in control_endpoint.json.",
+                            );
+                        } else if let Some(error) = &self.control.error {
+                            ui.colored_label(ui.visuals().error_fg_color, "⚠ Control API off")
+                                .on_hover_text(error);
+                        }
+                        if self.demo_mode {
+                            ui.colored_label(
+                                ui.visuals().warn_fg_color,
+                                egui::RichText::new("DEMO MODE — nothing is actually sent")
+                                    .strong(),
+                            )
+                            .on_hover_text(
+                                "Sample data and a made-up calendar. Ctrl+Shift+D to leave.",
+                            );
+                        }
+                    });
+                });
+            });
+
+        egui::SidePanel::right("side_panel")
+            .resizable(true)
+            .default_width(300.0)
+            .width_range(250.0..=450.0)
+            // FIX: Use f32 for Margin methods
+            .frame(
+                egui::Frame::new()
+                    .inner_margin(Margin::same(15))
+                    .fill(ctx.style().visuals.panel_fill),
+            )
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.ui_recipient_list(ui);
+                    ui.add_space(20.0);
+                    ui.separator();
+                    ui.add_space(20.0);
+                    self.ui_smtp_settings(ui);
+                    if !self.compose.send_results.is_empty() {
+                        ui.add_space(20.0);
+                        ui.separator();
+                        ui.add_space(20.0);
+                        self.ui_send_results(ui);
+                    }
+                    if !self.compose.history.is_empty() {
+                        ui.add_space(10.0);
+                        self.ui_send_history(ui);
+                    }
+                });
+            });
+
+        egui::CentralPanel::default()
+             // FIX: Use f32 for Margin methods
+             .frame(egui::Frame::new().inner_margin(Margin::same(15)).fill(ctx.style().visuals.panel_fill))
+            .show(ctx, |ui| {
+                ui.heading("Coffee Chat Helper"); ui.separator(); ui.add_space(10.0);
+                // FIX: Replace Align::stretch with Align::Min
+                ui.with_layout(egui::Layout::top_down(egui::Align::Min), |ui| {
+                     egui::ScrollArea::vertical().id_salt("main_scroll").show(ui, |ui| { // Use id_salt if id_source deprecated
+                        self.ui_email_message(ui);
+                    });
+                    ui.add_space(ui.available_height() * 0.05);
+                     ui.with_layout(egui::Layout::bottom_up(egui::Align::Center), |ui| {
+                         ui.add_space(10.0);
+                         let mut confirm = !self.compose.skip_send_confirmation;
+                         if ui.checkbox(&mut confirm, "Confirm before sending").on_hover_text("Show a summary of what will be sent and ask first").changed() { self.compose.skip_send_confirmation = !confirm; }
+                         ui.horizontal(|ui| { ui.checkbox(&mut self.compose.spam_check, "Check for spam signals").on_hover_text("Score the first email in the confirmation, with suggestions"); ui.add(egui::TextEdit::singleline(&mut self.compose.spam_test_address).desired_width(180.0).hint_text("Test address (optional)")).on_hover_text("A mail-tester style address; the confirmation can send it a copy of the first email for a real score"); });
+                         self.ui_drip_settings(ui);
+                         ui.horizontal(|ui| { ui.label("Skip anyone emailed in the last"); ui.add(egui::DragValue::new(&mut self.compose.send_cooldown_minutes).speed(0.5).range(0..=1440).suffix(" min")).on_hover_text("Applies across the list, drip batches and quick send, for this session. 0 only skips someone an email is already on its way to."); });
+                         ui.horizontal(|ui| { ui.label("Stop after"); ui.add(egui::DragValue::new(&mut self.compose.abort_after_failures).speed(0.2).range(0..=100)); ui.label("failures in a row").on_hover_text("A batch stops early when this many emails fail back to back, e.g. after the SMTP password changed. Scattered failures don't count. 0 never stops."); });
+                         self.ui_campaign_field(ui);
+                         let send_button = egui::Button::new("🚀 Send Invitations").min_size(Vec2::new(200.0, 35.0));
+                         let missing = self.missing_send_settings();
+                         let missing_hint = format!("Fill in {} under SMTP Settings first", missing.join(", "));
+                         let send_enabled = !self.background.is_sending_email && !self.background.is_connecting_calendar && !self.background.is_fetching_slots && self.background.config_loaded && self.background.template_loaded && missing.is_empty();
+                         if ui.add_enabled(send_enabled, send_button).on_hover_text("Send emails based on current settings, template, and fetched slots").on_disabled_hover_text(if missing.is_empty() { "Busy, or still loading config.toml" } else { missing_hint.as_str() }).clicked() { self.request_send_invitations(); }
+                         if ui.add_enabled(send_enabled, egui::Button::new("✉ Quick send…")).on_hover_text("Send one email to someone who isn't on the list").clicked() { self.open_quick_send(); }
+                         if !missing.is_empty() && self.background.config_loaded { ui.colored_label(ui.visuals().warn_fg_color, format!("⚠ {}", missing_hint)); }
+                         if let Some(schedule) = &self.drip.schedule {
+                             let label = format!("📅 Drip schedule ({} batches left)", schedule.pending_count());
+                             let button = if self.drip.notice.is_some() { egui::Button::new(egui::RichText::new(label).color(ui.visuals().warn_fg_color)) } else { egui::Button::new(label) };
+                             if ui.add(button).clicked() { self.drip.open = true; }
+                         }
+                         if !self.background.config_loaded || !self.background.template_loaded {
+                             ui.add_space(5.0);
+                              ui.horizontal(|ui| { ui.add(egui::Spinner::new().size(12.0)); ui.colored_label(ctx.style().visuals.widgets.inactive.fg_stroke.color, "Waiting for initial config/template..."); });
+                         }
+                     });
+                });
+            });
+
+        self.ui_calendar_error_dialog(ctx);
+        self.ui_setup_checklist(ctx);
+        self.ui_send_confirmation(ctx);
+        self.ui_quick_send_dialog(ctx);
+        self.ui_backup_dialog(ctx);
+        self.ui_availability_page_dialog(ctx);
+        self.ui_booking_dialog(ctx);
+        self.ui_follow_up_dialog(ctx);
+        self.ui_effective_config(ctx);
+        self.ui_hooks_dialog(ctx);
+        self.ui_safe_mode_dialog(ctx);
+        self.ui_reset_data_dialog(ctx);
+        self.ui_drip_schedule(ctx);
+        self.ui_exit_prompt(ctx);
+    }
+}
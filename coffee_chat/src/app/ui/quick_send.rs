@@ -0,0 +1,183 @@
+// src/app/ui/quick_send.rs
+
+use crate::app::quick_send::quick_send_address_problem;
+use crate::app::MyApp;
+use crate::email_sender::readability::{body_warning, BodyMeasure};
+use crate::email_sender::subject::{line_break_position, subject_warnings};
+use eframe::egui;
+
+impl MyApp {
+    /// "Quick send…": name, address and note for one person, and their email as it will go out.
+    pub(super) fn ui_quick_send_dialog(&mut self, ctx: &egui::Context) {
+        if !self.compose.quick_send.open {
+            return;
+        }
+        let sending = self.background.is_sending_email;
+        let availabilities = self.email_availabilities();
+        let slot_count = availabilities.len();
+        let readable_slot_lines = self.calendar_settings.readable_slot_lines as usize;
+        let address_problem = quick_send_address_problem(&self.compose.quick_send.email);
+        let mut open = true;
+        let mut rerender = false;
+        let mut send = false;
+        let mut cancel = false;
+        egui::Window::new("Quick Send")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(480.0)
+            .show(ctx, |ui| {
+                let quick_send = &mut self.compose.quick_send;
+                ui.weak("One email to someone who isn't on your list. The list isn't changed.");
+                ui.add_space(5.0);
+                egui::Grid::new("quick_send_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 8.0])
+                    .show(ui, |ui| {
+                        ui.label("Name:");
+                        rerender |= ui.text_edit_singleline(&mut quick_send.name).changed();
+                        ui.end_row();
+                        ui.label("Email:");
+                        ui.vertical(|ui| {
+                            rerender |= ui.text_edit_singleline(&mut quick_send.email).changed();
+                            // Nothing typed yet isn't worth a red warning
+                            if let Some(problem) = address_problem
+                                .as_ref()
+                                .filter(|_| !quick_send.email.trim().is_empty())
+                            {
+                                ui.colored_label(ui.visuals().error_fg_color, problem);
+                            }
+                        });
+                        ui.end_row();
+                        ui.label("Note:");
+                        rerender |= ui
+                            .add(
+                                egui::TextEdit::multiline(&mut quick_send.note)
+                                    .desired_rows(2)
+                                    .hint_text("Optional, just for this person"),
+                            )
+                            .on_hover_text(
+                                "Added after the greeting, or wherever the template uses {{ note }}",
+                            )
+                            .changed();
+                        ui.end_row();
+                    });
+                // Hand edits win over changes to the fields above
+                rerender &= !quick_send.edited;
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.strong("Email");
+                    if quick_send.edited {
+                        ui.weak("(edited)");
+                        if ui
+                            .small_button("↺ Reset to template")
+                            .on_hover_text("Discard your edits and render the template again")
+                            .clicked()
+                        {
+                            rerender = true;
+                        }
+                    }
+                });
+                if let Some(error) = &quick_send.render_error {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("Template error: {}", error),
+                    );
+                }
+                let draft = &mut quick_send.draft;
+                if !draft.cc.trim().is_empty() {
+                    ui.weak(format!("CC: {}", draft.cc.trim()));
+                }
+                if !draft.bcc.trim().is_empty() {
+                    ui.weak(format!("BCC: {}", draft.bcc.trim()));
+                }
+                let subject = ui.add(
+                    egui::TextEdit::singleline(&mut draft.subject)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("Subject"),
+                );
+                let line_break = line_break_position(&draft.subject);
+                let mut joined = false;
+                if let Some(position) = line_break {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(
+                            ui.visuals().error_fg_color,
+                            format!("Subject contains a line break at position {}", position),
+                        );
+                        joined = ui.small_button("Join into one line").clicked();
+                    });
+                }
+                if joined {
+                    draft.subject = draft
+                        .subject
+                        .split(['\r', '\n'])
+                        .map(str::trim)
+                        .filter(|part| !part.is_empty())
+                        .collect::<Vec<_>>()
+                        .join(" ");
+                }
+                for warning in subject_warnings(&draft.subject) {
+                    ui.colored_label(ui.visuals().warn_fg_color, warning);
+                }
+                let body = egui::ScrollArea::vertical()
+                    .id_salt("quick_send_body")
+                    .max_height(260.0)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut draft.body)
+                                .desired_width(f32::INFINITY)
+                                .desired_rows(10),
+                        )
+                    })
+                    .inner;
+                if subject.changed() || body.changed() || joined {
+                    quick_send.edited = true;
+                }
+                if slot_count == 0 {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "No slots fetched; {{ availabilities }} is empty.",
+                    );
+                } else {
+                    ui.weak(format!("Includes {} slot lines.", slot_count));
+                }
+                let measure = BodyMeasure::of(&draft.body, &availabilities);
+                if let Some(warning) = body_warning(measure, readable_slot_lines) {
+                    ui.colored_label(ui.visuals().warn_fg_color, warning);
+                }
+
+                if let Some(error) = &quick_send.send_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    let can_send = !sending
+                        && address_problem.is_none()
+                        && quick_send.render_error.is_none()
+                        && line_break.is_none();
+                    if ui
+                        .add_enabled(can_send, egui::Button::new("🚀 Send"))
+                        .clicked()
+                    {
+                        send = true;
+                    }
+                    if ui.button("Cancel").clicked() {
+                        cancel = true;
+                    }
+                    if sending {
+                        ui.add(egui::Spinner::new().size(14.0));
+                    }
+                });
+            });
+        if !open || cancel {
+            self.compose.quick_send.open = false;
+        }
+        if rerender {
+            self.render_quick_send_draft();
+        }
+        if send {
+            self.handle_quick_send();
+        }
+    }
+}
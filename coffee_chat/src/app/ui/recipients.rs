@@ -0,0 +1,451 @@
+// src/app/ui/recipients.rs
+
+use crate::app::{MyApp, UIRecipient};
+use crate::calendar::format;
+use crate::email_sender::address::{needs_smtputf8, split_mailbox};
+use crate::email_sender::template::LANGUAGE_FIELD;
+use eframe::egui;
+use egui::Stroke;
+use std::collections::BTreeMap;
+
+// Drag-and-drop payload: the index of the recipient being dragged
+struct DraggedRecipient(usize);
+
+impl MyApp {
+    // (ui_recipient_list remains the same)
+    pub(super) fn ui_recipient_list(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Recipients");
+        ui.add_space(5.0);
+        egui::Grid::new("add_recipient_grid")
+            .num_columns(2)
+            .spacing([10.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut self.compose.new_recipient_name)
+                    .on_hover_text("Enter recipient's first name");
+                ui.end_row();
+                ui.label("Fields:");
+                ui.text_edit_singleline(&mut self.compose.new_recipient_fields)
+                    .on_hover_text("Optional template variables, e.g. assistant_email=sam@example.com; company=Acme");
+                ui.end_row();
+                ui.label("Email:");
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut self.compose.new_recipient_email)
+                        .on_hover_text("Enter recipient's email address. \"Doe, Jane\" <jane@example.com> works too; the name is used if the Name box is empty.");
+                    if ui
+                        .add_sized([60.0, 25.0], egui::Button::new("Add"))
+                        .on_hover_text("Add recipient to the list")
+                        .clicked()
+                    {
+                        // Pasted from an address book, it may be `"Doe, Jane" <jane@x>`
+                        let (display_name, email) =
+                            split_mailbox(&self.compose.new_recipient_email);
+                        let name = if self.compose.new_recipient_name.trim().is_empty() {
+                            display_name.unwrap_or_default()
+                        } else {
+                            self.compose.new_recipient_name.clone()
+                        };
+                        if !email.is_empty() && !name.is_empty() {
+                            if email.contains('@') {
+                                let fields = parse_custom_fields(&self.compose.new_recipient_fields);
+                                let lacking: Vec<&str> = self
+                                    .compose
+                                    .field_check
+                                    .required
+                                    .iter()
+                                    .filter(|field| !fields.contains_key(*field))
+                                    .map(String::as_str)
+                                    .collect();
+                                self.status_message =
+                                    if needs_smtputf8(&email) {
+                                        "Recipient added. Its address has non-ASCII characters before the @, so it only sends through servers with SMTPUTF8.".to_string()
+                                    } else if self.compose.role_accounts.is_role(&email) {
+                                        "Recipient added, though it looks like a shared mailbox (info@, noreply@, ...) that rarely reaches a person. The send confirmation can leave such addresses out.".to_string()
+                                    } else if !lacking.is_empty() {
+                                        format!(
+                                            "Recipient added, without {}, which the template uses. Add it under Fields, or fill it in below the list.",
+                                            lacking.join(", ")
+                                        )
+                                    } else {
+                                        "Recipient added.".to_string()
+                                    };
+                                self.compose.recipients.push(UIRecipient {
+                                    name,
+                                    email,
+                                    fields,
+                                    pinned: false,
+                                });
+                                self.compose.new_recipient_name.clear();
+                                self.compose.new_recipient_email.clear();
+                                self.compose.new_recipient_fields.clear();
+                            } else {
+                                self.status_message = "Invalid email format.".to_string();
+                            }
+                        } else {
+                            self.status_message = "Please enter both name and email.".to_string();
+                        }
+                    }
+                });
+                ui.end_row();
+            });
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            ui.label("Current List:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.compose.recipient_search.query)
+                    .hint_text("🔍 Search name, email or fields")
+                    .desired_width(f32::INFINITY),
+            );
+        });
+        let filtering = self.compose.recipient_search.is_filtering();
+        let total = self.compose.recipients.len();
+        let visible = self
+            .compose
+            .recipient_search
+            .matches(&self.compose.recipients);
+        if filtering {
+            ui.weak(format!(
+                "{} of {} shown. Clear the search to reorder.",
+                visible.len(),
+                total
+            ));
+        }
+        let mut recipient_to_remove = None;
+        let mut recipient_to_move = None;
+        let mut recipient_to_book = None;
+        let mut confirm_change = None;
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            if total == 0 {
+                ui.colored_label(
+                    ui.style().visuals.widgets.inactive.fg_stroke.color,
+                    "(No recipients added)",
+                );
+            } else if visible.is_empty() {
+                ui.colored_label(
+                    ui.style().visuals.widgets.inactive.fg_stroke.color,
+                    "(No one matches the search)",
+                );
+            }
+            // Fixed-height rows, so only the ones scrolled into view are built each frame
+            let row_height = ui.spacing().interact_size.y;
+            egui::ScrollArea::vertical()
+                .max_height(150.0)
+                .auto_shrink([false, false])
+                .show_rows(ui, row_height, visible.len(), |ui, rows| {
+                    // Buttons are disabled mid-drag so a drop can't land on "X"
+                    let dragging =
+                        egui::DragAndDrop::has_payload_of_type::<DraggedRecipient>(ui.ctx());
+                    let confirming = self.compose.confirming_remove;
+                    let ask_before_removing =
+                        needs_remove_confirmation(total, self.compose.confirm_remove_above);
+                    for index in visible[rows].iter().copied() {
+                        let recipient = &mut self.compose.recipients[index];
+                        let row = ui
+                            .horizontal(|ui| {
+                                ui.set_height(row_height);
+                                if filtering {
+                                    ui.weak("☰").on_hover_text("Clear the search to reorder");
+                                } else {
+                                    ui.dnd_drag_source(
+                                        egui::Id::new(("recipient_drag", index)),
+                                        DraggedRecipient(index),
+                                        |ui| {
+                                            ui.weak("☰");
+                                        },
+                                    )
+                                    .response
+                                    .on_hover_text("Drag to reorder; list order is send order");
+                                }
+                                let mut hover = format!("{} <{}>", recipient.name, recipient.email);
+                                for (key, value) in &recipient.fields {
+                                    hover.push_str(&format!("\n{} = {}", key, value));
+                                }
+                                ui.label(format!("{} ({})", recipient.name, recipient.email))
+                                    .on_hover_text(hover);
+                                ui.with_layout(
+                                    egui::Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        if confirming == Some(index) {
+                                            // Right to left: reads "Remove? Yes No"
+                                            if ui.small_button("No").clicked() {
+                                                confirm_change = Some(None);
+                                            }
+                                            let yes = egui::RichText::new("Yes")
+                                                .color(ui.style().visuals.error_fg_color);
+                                            if ui.small_button(yes).clicked() {
+                                                recipient_to_remove = Some(index);
+                                            }
+                                            ui.label("Remove?");
+                                        } else {
+                                            let remove_button = egui::Button::new(
+                                                egui::RichText::new("X")
+                                                    .color(ui.style().visuals.error_fg_color)
+                                                    .small(),
+                                            )
+                                            .frame(false)
+                                            .small();
+                                            if ui
+                                                .add_enabled(!dragging, remove_button)
+                                                .on_hover_text("Remove recipient")
+                                                .clicked()
+                                            {
+                                                if ask_before_removing {
+                                                    confirm_change = Some(Some(index));
+                                                } else {
+                                                    recipient_to_remove = Some(index);
+                                                }
+                                            }
+                                        }
+                                        ui.add_enabled_ui(!dragging, |ui| {
+                                            ui.toggle_value(&mut recipient.pinned, "📌")
+                                                .on_hover_text("Pinned recipients are sent first");
+                                        });
+                                        if ui
+                                            .add_enabled(
+                                                !dragging,
+                                                egui::Button::new("📅").frame(false).small(),
+                                            )
+                                            .on_hover_text("Book a meeting with them")
+                                            .clicked()
+                                        {
+                                            recipient_to_book = Some((
+                                                recipient.name.clone(),
+                                                recipient.email.clone(),
+                                            ));
+                                        }
+                                    },
+                                );
+                            })
+                            .response;
+                        // Dropping on the top half of a row inserts above it, the bottom half below
+                        if let (Some(_), Some(pointer)) = (
+                            row.dnd_hover_payload::<DraggedRecipient>(),
+                            ui.input(|i| i.pointer.interact_pos()),
+                        ) {
+                            let insert_at = if pointer.y < row.rect.center().y {
+                                index
+                            } else {
+                                index + 1
+                            };
+                            let y = if insert_at == index {
+                                row.rect.top()
+                            } else {
+                                row.rect.bottom()
+                            };
+                            ui.painter().hline(
+                                row.rect.x_range(),
+                                y,
+                                Stroke::new(2.0, ui.visuals().selection.stroke.color),
+                            );
+                            if let Some(dragged) = row.dnd_release_payload::<DraggedRecipient>() {
+                                recipient_to_move = Some((dragged.0, insert_at));
+                            }
+                        }
+                    }
+                });
+        });
+        if let Some(confirming) = confirm_change {
+            self.compose.confirming_remove = confirming;
+        }
+        if let Some((from, insert_at)) = recipient_to_move {
+            move_item(&mut self.compose.recipients, from, insert_at);
+            self.compose.confirming_remove = None; // Its index may have changed
+            self.status_message = "Recipient moved.".to_string();
+        }
+        if let Some((name, email)) = recipient_to_book {
+            self.open_booking(&name, &email, None);
+        }
+        if let Some(index) = recipient_to_remove {
+            let removed = self.compose.recipients.remove(index);
+            self.compose.last_removed = Some((index, removed));
+            self.compose.confirming_remove = None;
+            self.status_message = "Recipient removed.".to_string();
+        }
+        ui.horizontal(|ui| {
+            if let Some((_, removed)) = &self.compose.last_removed {
+                ui.weak(format!("Removed {}.", removed.name));
+                if ui
+                    .small_button("↶ Undo")
+                    .on_hover_text("Put them back where they were")
+                    .clicked()
+                {
+                    self.undo_recipient_removal();
+                }
+            }
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                ui.add(
+                    egui::DragValue::new(&mut self.compose.confirm_remove_above).range(0..=10_000),
+                )
+                .on_hover_text("0 = never ask");
+                ui.label("Ask before removing when the list has more than");
+            });
+        });
+        self.refresh_field_check();
+        self.ui_field_gaps(ui);
+        self.ui_unknown_languages(ui);
+    }
+
+    /// "⚠ 14 recipients missing 'company'", with a value to give them all.
+    fn ui_field_gaps(&mut self, ui: &mut egui::Ui) {
+        let mut fill = None;
+        let check = &mut self.compose.field_check;
+        for (field, positions) in &check.missing {
+            let mut who: Vec<&str> = positions
+                .iter()
+                .take(10)
+                .filter_map(|&position| self.compose.recipients.get(position))
+                .map(|recipient| recipient.name.as_str())
+                .collect();
+            if positions.len() > who.len() {
+                who.push("…");
+            }
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!(
+                    "⚠ {} missing '{}', which the template uses",
+                    recipient_count(positions.len()),
+                    field
+                ),
+            )
+            .on_hover_text(format!(
+                "Their emails would fail to render:\n{}",
+                who.join("\n")
+            ));
+            ui.horizontal(|ui| {
+                ui.label("Give them:");
+                ui.add(
+                    egui::TextEdit::singleline(check.fill.entry(field.clone()).or_default())
+                        .desired_width(160.0)
+                        .hint_text("e.g. your company"),
+                );
+                if ui
+                    .button("Fill in")
+                    .on_hover_text("Sets this value for everyone without the field; an empty one sends it blank")
+                    .clicked()
+                {
+                    fill = Some(field.clone());
+                }
+            });
+        }
+        if let Some(field) = fill {
+            self.fill_missing_field(&field);
+        }
+    }
+
+    /// "⚠ 2 recipients have a language that isn't recognized", since theirs fall back
+    /// to English.
+    fn ui_unknown_languages(&self, ui: &mut egui::Ui) {
+        let unknown: Vec<String> = self
+            .compose
+            .recipients
+            .iter()
+            .filter_map(|recipient| {
+                let language = recipient.fields.get(LANGUAGE_FIELD)?.trim();
+                (!language.is_empty() && !format::recognizes_language(language))
+                    .then(|| format!("{}: {}", recipient.name, language))
+            })
+            .collect();
+        if unknown.is_empty() {
+            return;
+        }
+        let mut who: Vec<&str> = unknown.iter().take(10).map(String::as_str).collect();
+        if unknown.len() > who.len() {
+            who.push("…");
+        }
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!(
+                "⚠ {} with a language that isn't recognized",
+                recipient_count(unknown.len())
+            ),
+        )
+        .on_hover_text(format!(
+            "Their slots will be written in English. Use a code like fr, de or pt_BR:\n{}",
+            who.join("\n")
+        ));
+    }
+
+    /// Puts the last removed recipient back at its old position.
+    fn undo_recipient_removal(&mut self) {
+        if let Some((index, recipient)) = self.compose.last_removed.take() {
+            let recipients = &mut self.compose.recipients;
+            recipients.insert(index.min(recipients.len()), recipient);
+            self.compose.confirming_remove = None;
+            self.status_message = "Recipient restored.".to_string();
+        }
+    }
+}
+
+fn recipient_count(count: usize) -> String {
+    match count {
+        1 => "1 recipient".to_string(),
+        count => format!("{} recipients", count),
+    }
+}
+
+/// Whether "X" should ask first: only once the list is longer than `threshold`, and never for 0.
+fn needs_remove_confirmation(len: usize, threshold: u32) -> bool {
+    threshold > 0 && len > threshold as usize
+}
+
+/// Parses "key=value; key=value" into custom recipient fields, skipping malformed pairs.
+fn parse_custom_fields(input: &str) -> BTreeMap<String, String> {
+    input
+        .split(';')
+        .filter_map(|pair| {
+            let (key, value) = pair.split_once('=')?;
+            let key = key.trim();
+            if key.is_empty() {
+                return None;
+            }
+            Some((key.to_string(), value.trim().to_string()))
+        })
+        .collect()
+}
+
+/// Moves the item at `from` to `insert_at`, an index into the list as it was
+/// before the move (so `len` means "to the end").
+fn move_item<T>(items: &mut Vec<T>, from: usize, insert_at: usize) {
+    if from >= items.len() {
+        return;
+    }
+    let item = items.remove(from);
+    let insert_at = if insert_at > from {
+        insert_at - 1
+    } else {
+        insert_at
+    };
+    items.insert(insert_at.min(items.len()), item);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(from: usize, insert_at: usize) -> Vec<char> {
+        let mut items = vec!['a', 'b', 'c', 'd'];
+        move_item(&mut items, from, insert_at);
+        items
+    }
+
+    #[test]
+    fn items_move_up_and_down() {
+        assert_eq!(moved(0, 4), vec!['b', 'c', 'd', 'a']);
+        assert_eq!(moved(3, 0), vec!['d', 'a', 'b', 'c']);
+        assert_eq!(moved(1, 3), vec!['a', 'c', 'b', 'd']);
+    }
+
+    #[test]
+    fn dropping_next_to_itself_changes_nothing() {
+        assert_eq!(moved(1, 1), vec!['a', 'b', 'c', 'd']);
+        assert_eq!(moved(1, 2), vec!['a', 'b', 'c', 'd']);
+        assert_eq!(moved(9, 0), vec!['a', 'b', 'c', 'd']);
+    }
+
+    #[test]
+    fn only_long_lists_ask_before_removing() {
+        assert!(!needs_remove_confirmation(10, 10));
+        assert!(needs_remove_confirmation(11, 10));
+        assert!(!needs_remove_confirmation(500, 0));
+    }
+}
@@ -0,0 +1,91 @@
+// src/app/ui/reset_data.rs
+
+use crate::app::reset_data::{data_files, RESET_CONFIRMATION};
+use crate::app::MyApp;
+use eframe::egui;
+
+impl MyApp {
+    /// "Reset all data": every file the app has written, deleted once RESET is typed.
+    pub(super) fn ui_reset_data_dialog(&mut self, ctx: &egui::Context) {
+        if !self.reset_data.open {
+            return;
+        }
+        let mut open = true;
+        let mut reset = false;
+        let mut restart = false;
+        let busy = self.background.is_sending_email || self.background.is_fetching_slots;
+        egui::Window::new("Reset all data")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(560.0)
+            .show(ctx, |ui| {
+                if self.reset_data.wiped {
+                    ui.label("Reset ran, but not everything could be deleted:");
+                    for (label, outcome) in &self.reset_data.outcomes {
+                        match outcome {
+                            Ok(done) => ui.label(format!("✔ {}: {}", label, done)),
+                            Err(e) => ui.colored_label(
+                                ui.visuals().error_fg_color,
+                                format!("✖ {}: {}", label, e),
+                            ),
+                        };
+                    }
+                    ui.weak("Delete those by hand, or close the app first if it's holding them.");
+                    restart = ui.button("🔄 Restart anyway").clicked();
+                    return;
+                }
+                ui.label("This deletes everything the app has saved on this computer:");
+                egui::Grid::new("reset_data_files")
+                    .num_columns(2)
+                    .striped(true)
+                    .spacing([12.0, 4.0])
+                    .show(ui, |ui| {
+                        for file in data_files(&self.calendar_settings.token_cache_path) {
+                            ui.strong(file.label);
+                            match &file.path {
+                                Ok(path) if path.exists() => {
+                                    ui.monospace(path.display().to_string())
+                                }
+                                Ok(path) => ui.weak(format!("{} (not there)", path.display())),
+                                Err(e) => ui.colored_label(ui.visuals().error_fg_color, e),
+                            };
+                            ui.end_row();
+                        }
+                    });
+                ui.weak("config.toml, your template and credentials.json are yours and stay. The app then restarts as on first run.");
+                ui.add_space(5.0);
+                ui.horizontal(|ui| {
+                    ui.label(format!("Type {} to confirm:", RESET_CONFIRMATION));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.reset_data.typed).desired_width(80.0),
+                    );
+                });
+                let confirmed = self.reset_data.typed.trim() == RESET_CONFIRMATION;
+                reset = ui
+                    .add_enabled(
+                        confirmed && !busy,
+                        egui::Button::new(
+                            egui::RichText::new("🗑 Delete and restart")
+                                .color(ui.visuals().error_fg_color),
+                        ),
+                    )
+                    .on_disabled_hover_text(if busy {
+                        "Wait for the send or slot fetch to finish"
+                    } else {
+                        "Type the word above first"
+                    })
+                    .clicked();
+            });
+        if !open {
+            self.reset_data.open = false;
+            self.reset_data.typed.clear();
+        }
+        if reset {
+            self.handle_reset_data(ctx);
+        }
+        if restart {
+            self.restart(ctx);
+        }
+    }
+}
@@ -0,0 +1,397 @@
+// src/app/ui/results.rs
+
+use crate::app::{MyApp, SendOutcome};
+use crate::email_sender::history::{self, EmailKind, SentEmail};
+use chrono::{Local, Utc};
+use eframe::egui;
+
+impl MyApp {
+    /// Per-recipient outcome of the last send, with the server's reply for each accepted email.
+    pub(super) fn ui_send_results(&mut self, ui: &mut egui::Ui) {
+        let count = |matches: fn(&SendOutcome) -> bool| {
+            self.compose
+                .send_results
+                .iter()
+                .filter(|result| matches(&result.outcome))
+                .count()
+        };
+        let mut heading = format!(
+            "📬 Last Send ({} accepted, {} failed",
+            count(|outcome| matches!(outcome, SendOutcome::Accepted(_))),
+            count(|outcome| matches!(outcome, SendOutcome::Failed(_)))
+        );
+        match count(|outcome| matches!(outcome, SendOutcome::Skipped(_))) {
+            0 => heading.push(')'),
+            skipped => heading.push_str(&format!(", {} skipped)", skipped)),
+        }
+        egui::CollapsingHeader::new(heading)
+            .id_salt("send_results")
+            .show(ui, |ui| {
+                ui.weak("The server's reply is the record to quote if an email goes missing.");
+                egui::ScrollArea::vertical()
+                    .id_salt("send_results_scroll")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for result in &self.compose.send_results {
+                            let (icon, detail) = match &result.outcome {
+                                SendOutcome::Accepted(receipt) => ("✅", receipt.summary()),
+                                SendOutcome::Failed(error) => ("❌", error.clone()),
+                                SendOutcome::Skipped(reason) => ("⏭", reason.clone()),
+                            };
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{} {}", icon, result.email));
+                                if result.one_off {
+                                    ui.weak("(quick send)");
+                                }
+                                if ui
+                                    .small_button("📋")
+                                    .on_hover_text("Copy the server reply")
+                                    .clicked()
+                                {
+                                    ui.ctx().copy_text(format!("{}: {}", result.email, detail));
+                                }
+                            });
+                            let detail_label = ui.weak(&detail);
+                            if let SendOutcome::Accepted(receipt) = &result.outcome {
+                                if let Some(queue_id) = receipt.queue_id() {
+                                    detail_label.on_hover_text(format!("Queue ID: {}", queue_id));
+                                }
+                            }
+                            ui.add_space(4.0);
+                        }
+                    });
+                self.ui_reply_lookup(ui);
+            });
+    }
+
+    /// Pick a recipient and paste their reply to see which numbered slot they chose.
+    fn ui_reply_lookup(&mut self, ui: &mut egui::Ui) {
+        let numbered: Vec<&str> = self
+            .compose
+            .send_results
+            .iter()
+            .filter(|result| !result.options.is_empty())
+            .map(|result| result.email.as_str())
+            .collect();
+        if numbered.is_empty() {
+            return;
+        }
+        ui.separator();
+        ui.label("🔢 Resolve a reply");
+        let lookup = &mut self.compose.reply_lookup;
+        if !numbered.contains(&lookup.email.as_str()) {
+            lookup.email = numbered[0].to_string();
+        }
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("reply_lookup_recipient")
+                .selected_text(lookup.email.as_str())
+                .show_ui(ui, |ui| {
+                    for email in &numbered {
+                        ui.selectable_value(&mut lookup.email, email.to_string(), *email);
+                    }
+                });
+            ui.add(
+                egui::TextEdit::singleline(&mut lookup.reply)
+                    .hint_text("option 3 works")
+                    .desired_width(160.0),
+            )
+            .on_hover_text("Their reply, or just the option number");
+        });
+        if lookup.reply.trim().is_empty() {
+            return;
+        }
+        let Some(result) = self
+            .compose
+            .send_results
+            .iter()
+            .rev() // A later send to the same person wins
+            .find(|result| result.email == lookup.email && !result.options.is_empty())
+        else {
+            return;
+        };
+        let mut book = None;
+        match result.resolve_reply(&lookup.reply) {
+            Ok((number, slot)) => {
+                ui.horizontal(|ui| {
+                    ui.strong(format!("Option {}: {}", number, slot));
+                    if ui
+                        .small_button("📋")
+                        .on_hover_text("Copy the slot")
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(slot.to_string());
+                    }
+                    if ui
+                        .small_button("📅 Book…")
+                        .on_hover_text("Put this slot on your calendar and invite them")
+                        .clicked()
+                    {
+                        book = Some((result.email.clone(), slot.to_string()));
+                    }
+                });
+            }
+            Err(problem) => {
+                ui.colored_label(ui.visuals().warn_fg_color, problem);
+            }
+        }
+        if let Some((email, slot)) = book {
+            self.open_booking("", &email, Some(&slot));
+        }
+    }
+
+    /// Emails sent from the app, newest first, with cancel and reschedule replies for each.
+    pub(super) fn ui_send_history(&mut self, ui: &mut egui::Ui) {
+        let mut follow_up = None;
+        let mut recheck = false;
+        egui::CollapsingHeader::new(format!("🗂 History ({})", self.compose.history.len()))
+            .id_salt("send_history")
+            .show(ui, |ui| {
+                ui.weak(
+                    "Cancel or reschedule replies to the email, so it stays in one conversation.",
+                );
+                self.ui_campaign_stats(ui);
+                egui::ScrollArea::vertical()
+                    .id_salt("send_history_scroll")
+                    .max_height(250.0)
+                    .show(ui, |ui| {
+                        for (index, entry) in self.compose.history.iter().enumerate().rev() {
+                            let icon = match entry.kind {
+                                EmailKind::Invitation => "✉",
+                                EmailKind::Cancellation => "🚫",
+                                EmailKind::Reschedule => "🔁",
+                            };
+                            ui.horizontal(|ui| {
+                                let mut details = format!("{}\n{}", entry.name, entry.subject);
+                                if let Some(campaign) = &entry.campaign {
+                                    details.push_str(&format!("\nCampaign: {}", campaign));
+                                }
+                                ui.label(format!("{} {}", icon, entry.email))
+                                    .on_hover_text(details);
+                                ui.weak(
+                                    entry
+                                        .sent_at
+                                        .with_timezone(&Local)
+                                        .format("%b %-d %H:%M")
+                                        .to_string(),
+                                );
+                                if !entry.can_follow_up() {
+                                    return;
+                                }
+                                if ui
+                                    .small_button("Cancel…")
+                                    .on_hover_text("Reply calling the chat off")
+                                    .clicked()
+                                {
+                                    follow_up = Some((index, EmailKind::Cancellation));
+                                }
+                                if ui
+                                    .small_button("Reschedule…")
+                                    .on_hover_text("Reply with freshly fetched slots")
+                                    .clicked()
+                                {
+                                    follow_up = Some((index, EmailKind::Reschedule));
+                                }
+                            });
+                            let outcome = match &entry.outcome {
+                                Ok(summary) => format!("{}: {}", entry.kind, summary),
+                                Err(e) => format!("{} failed: {}", entry.kind, e),
+                            };
+                            match &entry.event {
+                                Some(event) => ui.weak(format!("{}. {}.", outcome, event)),
+                                None => ui.weak(outcome),
+                            };
+                            if self.ui_sent_slots(ui, index, entry) {
+                                recheck = true;
+                            }
+                            ui.add_space(4.0);
+                        }
+                    });
+            });
+        if let Some((index, kind)) = follow_up {
+            self.open_follow_up(index, kind);
+        }
+        if recheck {
+            self.handle_fetch_slots();
+        }
+    }
+
+    /// Counts per campaign in the history, each campaign with a rename.
+    fn ui_campaign_stats(&mut self, ui: &mut egui::Ui) {
+        let stats = history::campaign_stats(
+            &self.compose.history,
+            self.booking
+                .booked
+                .iter()
+                .map(|meeting| meeting.attendee_email.as_str()),
+        );
+        if stats.keys().all(Option::is_none) {
+            return;
+        }
+        let mut rename = None;
+        egui::CollapsingHeader::new("📈 Campaigns")
+            .id_salt("campaign_stats")
+            .show(ui, |ui| {
+                egui::Grid::new("campaign_stats_grid")
+                    .num_columns(3)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        for (campaign, stats) in &stats {
+                            ui.label(campaign.as_deref().unwrap_or("(no campaign)"));
+                            let booked = match stats.booked_percent() {
+                                Some(percent) => format!("{} booked ({}%)", stats.booked, percent),
+                                None => "none booked".to_string(),
+                            };
+                            ui.label(format!(
+                                "{} invited, {}, {} follow-ups, {} failed",
+                                stats.invited, booked, stats.follow_ups, stats.failed
+                            ))
+                            .on_hover_text(
+                                "Booked counts the invited people with a meeting booked from the app, the nearest thing to a reply rate it sees",
+                            );
+                            let Some(name) = campaign else {
+                                ui.label("");
+                                ui.end_row();
+                                continue;
+                            };
+                            match &mut self.compose.campaign_rename {
+                                Some((from, draft)) if from == name => {
+                                    ui.horizontal(|ui| {
+                                        ui.add(
+                                            egui::TextEdit::singleline(draft)
+                                                .desired_width(140.0),
+                                        );
+                                        if ui
+                                            .small_button("Rename")
+                                            .on_hover_text("Every history entry of the campaign gets the new name. Blank takes them out of it")
+                                            .clicked()
+                                        {
+                                            rename = Some((from.clone(), draft.clone()));
+                                        }
+                                        if ui.small_button("Cancel").clicked() {
+                                            rename = Some((from.clone(), from.clone()));
+                                        }
+                                    });
+                                }
+                                _ => {
+                                    if ui.small_button("Rename…").clicked() {
+                                        self.compose.campaign_rename =
+                                            Some((name.clone(), name.clone()));
+                                    }
+                                }
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        if let Some((from, to)) = rename {
+            self.compose.campaign_rename = None;
+            if from != to.trim() {
+                self.rename_campaign(&from, &to);
+            }
+        }
+    }
+
+    /// "Campaign" by the send button, with the names used before to pick from.
+    pub(super) fn ui_campaign_field(&mut self, ui: &mut egui::Ui) {
+        let past = history::campaigns(&self.compose.history);
+        ui.horizontal(|ui| {
+            ui.label("Campaign:");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.compose.campaign)
+                    .desired_width(180.0)
+                    .hint_text("e.g. Spring alumni outreach"),
+            )
+            .on_hover_text("Labels this send in the history and the campaign stats. Empty leaves it out of any campaign");
+            if !past.is_empty() {
+                ui.menu_button("▾", |ui| {
+                    for name in &past {
+                        if ui.button(name).clicked() {
+                            self.compose.campaign = name.clone();
+                            ui.close_menu();
+                        }
+                    }
+                })
+                .response
+                .on_hover_text("Campaigns used before");
+            }
+            // What's typed so far, completed from past campaigns
+            let typed = self.compose.campaign.trim().to_string();
+            if typed.is_empty() {
+                return;
+            }
+            let lowercase = typed.to_lowercase();
+            for name in past
+                .iter()
+                .filter(|name| name.to_lowercase().starts_with(&lowercase) && **name != typed)
+                .take(3)
+            {
+                if ui.small_button(name).clicked() {
+                    self.compose.campaign = name.clone();
+                }
+            }
+        });
+    }
+
+    /// The availability list an email went out with, each slot marked free or taken
+    /// when slots were fetched after it was sent. True when "Check now" was clicked.
+    fn ui_sent_slots(&self, ui: &mut egui::Ui, index: usize, entry: &SentEmail) -> bool {
+        let Some(slots) = &entry.slots else {
+            if entry.kind != EmailKind::Cancellation {
+                ui.weak("Slots weren't kept for emails sent before this version.");
+            }
+            return false;
+        };
+        if slots.is_empty() {
+            return false;
+        }
+        let fresh = self
+            .calendar
+            .last_fetch
+            .as_ref()
+            .filter(|fetch| fetch.fetched_at > entry.sent_at);
+        let mut recheck = false;
+        egui::CollapsingHeader::new(format!("Slots offered ({})", slots.len()))
+            .id_salt(("sent_slots", index))
+            .show(ui, |ui| {
+                for slot in slots {
+                    ui.horizontal(|ui| {
+                        ui.label(&slot.text);
+                        let Some(window) = slot.window else {
+                            return;
+                        };
+                        if window.0 <= Utc::now() {
+                            ui.weak("past");
+                        } else if let Some(fetch) = fresh {
+                            if history::still_free(window, &fetch.windows) {
+                                ui.colored_label(egui::Color32::from_rgb(80, 170, 90), "✔ free");
+                            } else {
+                                ui.colored_label(ui.visuals().warn_fg_color, "✖ taken");
+                            }
+                        }
+                    });
+                }
+                ui.horizontal(|ui| {
+                    let fetching = self.background.is_fetching_slots;
+                    if ui
+                        .add_enabled(
+                            self.calendar.source.is_some() && !fetching,
+                            egui::Button::new(if fetching { "Checking…" } else { "🔄 Check now" }),
+                        )
+                        .on_hover_text("Fetch your slots again to see which of these are still free. The slots on screen are replaced too.")
+                        .on_disabled_hover_text("Connect your calendar first")
+                        .clicked()
+                    {
+                        recheck = true;
+                    }
+                    match fresh {
+                        Some(fetch) => ui.weak(format!(
+                            "Checked {}, with your current Calendar Settings",
+                            fetch.fetched_at.with_timezone(&Local).format("%b %-d %H:%M")
+                        )),
+                        None => ui.weak("Not checked since this was sent"),
+                    };
+                });
+            });
+        recheck
+    }
+}
@@ -0,0 +1,125 @@
+// src/app/ui/safe_mode.rs
+
+use crate::app::safe_mode::recovery_files;
+use crate::app::MyApp;
+use eframe::egui;
+use std::path::PathBuf;
+
+enum Action {
+    OpenFolder(PathBuf),
+    Recover(&'static str, PathBuf, bool), // Label, file, reset rather than back up
+    StartNormally,
+}
+
+impl MyApp {
+    /// "Safe mode": the files a normal start reads, each with a way to get it out of
+    /// the way, and a button to start normally once they're fixed.
+    pub(super) fn ui_safe_mode_dialog(&mut self, ctx: &egui::Context) {
+        if !self.safe_mode.active || !self.safe_mode.open {
+            return;
+        }
+        let mut open = true;
+        let mut action = None;
+        egui::Window::new("🛟 Safe mode")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(true)
+            .default_width(620.0)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Started in safe mode ({}). The saved session, config.toml and the template weren't loaded, nothing runs in the background, and nothing is saved.",
+                    self.safe_mode.reason
+                ));
+                ui.weak("If the app crashed at startup, back up and reset the file that's likely at fault, then start normally.");
+                ui.separator();
+                egui::Grid::new("safe_mode_files")
+                    .num_columns(3)
+                    .striped(true)
+                    .spacing([12.0, 6.0])
+                    .show(ui, |ui| {
+                        for file in recovery_files(&self.calendar_settings.token_cache_path) {
+                            ui.strong(file.label);
+                            let path = match file.path {
+                                Ok(path) => path,
+                                Err(e) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, e);
+                                    ui.label("");
+                                    ui.end_row();
+                                    continue;
+                                }
+                            };
+                            let size = std::fs::metadata(&path).ok().map(|meta| meta.len());
+                            ui.vertical(|ui| {
+                                ui.monospace(path.display().to_string());
+                                match size {
+                                    Some(bytes) => ui.weak(format!("{} bytes", bytes)),
+                                    None => ui.weak("Not there; a normal start goes without it"),
+                                };
+                            });
+                            ui.horizontal(|ui| {
+                                if ui
+                                    .small_button("📂")
+                                    .on_hover_text("Open the folder it's in")
+                                    .clicked()
+                                {
+                                    if let Some(folder) = path.parent() {
+                                        action = Some(Action::OpenFolder(folder.to_path_buf()));
+                                    }
+                                }
+                                if ui
+                                    .add_enabled(size.is_some(), egui::Button::new("Back up").small())
+                                    .on_hover_text("Copy it next to itself, with the time in the name")
+                                    .clicked()
+                                {
+                                    action = Some(Action::Recover(file.label, path.clone(), false));
+                                }
+                                if ui
+                                    .add_enabled(size.is_some(), egui::Button::new("Reset").small())
+                                    .on_hover_text("Move it aside with the time in the name, so the next start goes without it")
+                                    .clicked()
+                                {
+                                    action = Some(Action::Recover(file.label, path.clone(), true));
+                                }
+                            });
+                            ui.end_row();
+                            if let Some((_, outcome)) = self
+                                .safe_mode
+                                .outcomes
+                                .iter()
+                                .find(|(label, _)| label == file.label)
+                            {
+                                ui.label("");
+                                match outcome {
+                                    Ok(done) => ui.label(format!("✔ {}", done)),
+                                    Err(e) => {
+                                        ui.colored_label(ui.visuals().error_fg_color, format!("✖ {}", e))
+                                    }
+                                };
+                                ui.end_row();
+                            }
+                        }
+                    });
+                ui.separator();
+                if ui
+                    .button("▶ Start normally")
+                    .on_hover_text("Load everything safe mode skipped. Changes made here are replaced by what's on disk.")
+                    .clicked()
+                {
+                    action = Some(Action::StartNormally);
+                }
+            });
+        match action {
+            Some(Action::OpenFolder(folder)) => {
+                if let Err(e) = open::that(&folder) {
+                    self.status_message = format!("Couldn't open {}: {}", folder.display(), e);
+                }
+            }
+            Some(Action::Recover(label, path, reset)) => self.recover_file(label, &path, reset),
+            Some(Action::StartNormally) => self.leave_safe_mode(),
+            None => {}
+        }
+        if !open {
+            self.safe_mode.open = false;
+        }
+    }
+}
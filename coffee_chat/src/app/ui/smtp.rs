@@ -0,0 +1,113 @@
+// src/app/ui/smtp.rs
+
+use crate::app::MyApp;
+use crate::email_sender::proxy::SmtpProxy;
+use eframe::egui;
+use egui::Color32;
+use secrecy::{ExposeSecret, SecretString};
+
+impl MyApp {
+    // FIX: Second SecretString::new type mismatch
+    pub(super) fn ui_smtp_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading("SMTP Settings");
+        let missing = self.missing_send_settings();
+        if !missing.is_empty() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("Not set up yet: {} needed to send", missing.join(", ")),
+            )
+            .on_hover_text("Slots can be fetched and copied without SMTP");
+        }
+        ui.add_space(5.0);
+        egui::Grid::new("smtp_grid")
+            .num_columns(2)
+            .spacing([10.0, 8.0])
+            .show(ui, |ui| {
+                ui.label("Send via:");
+                ui.horizontal(|ui| {
+                    ui.radio_value(&mut self.smtp.use_webhook, false, "SMTP");
+                    ui.radio_value(&mut self.smtp.use_webhook, true, "Webhook")
+                        .on_hover_text("POST each email as JSON to an HTTP endpoint, e.g. an internal mail API");
+                });
+                ui.end_row();
+                if self.smtp.use_webhook {
+                    ui.label("Webhook URL:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.smtp.webhook_url)
+                            .hint_text("https://mail.internal/api/send"),
+                    )
+                    .on_hover_text("Gets {\"to\", \"subject\", \"body\", \"cc\", \"bcc\"}; any 2xx reply counts as sent");
+                    ui.end_row();
+                    ui.label("Sender Name:");
+                    ui.text_edit_singleline(&mut self.compose.sender_name);
+                    ui.end_row();
+                    return;
+                }
+                ui.label("Host:");
+                ui.text_edit_singleline(&mut self.smtp.host);
+                ui.end_row();
+                ui.label("Port:");
+                ui.text_edit_singleline(&mut self.smtp.port_str);
+                ui.end_row();
+                ui.label("");
+                ui.checkbox(&mut self.smtp.no_auth, "No authentication")
+                    .on_hover_text("For internal relays that accept mail without logging in");
+                ui.end_row();
+                ui.label("Username:");
+                ui.add_enabled(
+                    !self.smtp.no_auth,
+                    egui::TextEdit::singleline(&mut self.smtp.user),
+                );
+                ui.end_row();
+                ui.label("Password:");
+                let mut password_string = self.smtp.password.expose_secret();
+                let response = ui.add_enabled(
+                    !self.smtp.no_auth,
+                    egui::TextEdit::singleline(&mut password_string)
+                        .password(true)
+                        .hint_text("Enter SMTP password"),
+                );
+                if response.changed() {
+                    // FIX: Use .into() here as well
+                    self.smtp.password = SecretString::new(password_string.into());
+                }
+                ui.end_row();
+                ui.label("From Email:");
+                ui.text_edit_singleline(&mut self.smtp.from_email);
+                ui.end_row();
+                ui.label("Sender Name:");
+                ui.text_edit_singleline(&mut self.compose.sender_name);
+                ui.end_row();
+                if let Some(url) = &self.smtp.proxy {
+                    ui.label("Proxy:");
+                    // Display drops any credentials in the URL
+                    match SmtpProxy::parse(url) {
+                        Ok(proxy) => ui.label(proxy.to_string()),
+                        Err(e) => ui.colored_label(Color32::RED, format!("Invalid: {}", e)),
+                    }
+                    .on_hover_text("Set by smtp.proxy in config.toml");
+                    ui.end_row();
+                }
+                ui.label("Server:");
+                ui.horizontal(|ui| {
+                    match &self.smtp.server {
+                        Some(Ok(capabilities)) => ui.label(capabilities.summary()),
+                        Some(Err(e)) => {
+                            ui.colored_label(Color32::RED, "Check failed").on_hover_text(e)
+                        }
+                        None => ui.weak("Not checked yet"),
+                    };
+                    if self.smtp.checking_server {
+                        ui.spinner();
+                    } else if ui
+                        .button("🔎 Check")
+                        .on_hover_text("Connect and read what the server supports: 8-bit text, the largest email it takes, login methods. Sends check it too.")
+                        .clicked()
+                    {
+                        self.check_smtp_server();
+                    }
+                });
+                ui.end_row();
+            });
+    }
+}
@@ -0,0 +1,148 @@
+// src/calendar/availability_page.rs
+// A standalone HTML page of the fetched slots, one card per day, to host somewhere
+// or attach. Built from the same merged windows as the email summary, so both agree.
+
+use super::format::{format_day, format_window_in, SlotFormat};
+use super::free_busy::{merge_slots_by_day_in, SlotOrder};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use serde::Serialize;
+use std::path::Path;
+use tera::{Context, Tera};
+
+const PAGE_TEMPLATE: &str = include_str!("availability_page.html");
+
+/// One day's card on the page. No slots means "No availability".
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PageDay {
+    pub heading: String,
+    pub slots: Vec<String>,
+}
+
+/// The days to show, in `order`. Days in `unavailable` get an empty card.
+pub fn page_days_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    unavailable: &[NaiveDate],
+    order: SlotOrder,
+    format: SlotFormat,
+    tz: &Tz,
+) -> Vec<PageDay>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let mut days: Vec<PageDay> = merge_slots_by_day_in(slots, min_len, unavailable, tz)
+        .into_iter()
+        // Days whose only windows were too short aren't worth a card
+        .filter(|(day, merged)| !merged.is_empty() || unavailable.contains(day))
+        .map(|(day, merged)| PageDay {
+            heading: format_day(day),
+            slots: merged
+                .into_iter()
+                .map(|(start, end)| format_window_in(start, end, format, tz))
+                .collect(),
+        })
+        .collect();
+    if order == SlotOrder::LatestFirst {
+        days.reverse();
+    }
+    days
+}
+
+/// Renders the page. Everything is escaped, and the styles are inline so the file
+/// works wherever it's opened.
+pub fn render_page(
+    sender_name: &str,
+    zone_label: &str,
+    days: &[PageDay],
+    generated: &str,
+) -> Result<String, tera::Error> {
+    let title = match sender_name.trim() {
+        "" => "Availability".to_string(),
+        name => format!("{}'s availability", name),
+    };
+    let mut context = Context::new();
+    context.insert("title", &title);
+    context.insert("zone", zone_label);
+    context.insert("days", days);
+    context.insert("generated", generated);
+    Tera::one_off(PAGE_TEMPLATE, &context, true)
+}
+
+/// Where the page will be reachable: `base_url` plus the file's name. `None` without a base URL.
+pub fn page_link(base_url: &str, path: &Path) -> Option<String> {
+    let base = base_url.trim().trim_end_matches('/');
+    if base.is_empty() {
+        return None;
+    }
+    let file_name = path.file_name()?.to_string_lossy().replace(' ', "%20");
+    Some(format!("{}/{}", base, file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::New_York;
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        New_York
+            .with_ymd_and_hms(2025, 4, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn days(order: SlotOrder) -> Vec<PageDay> {
+        let slots = [
+            (at(7, 9, 0), at(7, 10, 0)),
+            (at(7, 10, 0), at(7, 10, 30)),
+            (at(7, 14, 0), at(7, 15, 0)),
+            (at(9, 16, 30), at(9, 17, 0)),
+        ];
+        let unavailable = [NaiveDate::from_ymd_opt(2025, 4, 8).unwrap()];
+        page_days_in(
+            &slots,
+            Duration::minutes(30),
+            &unavailable,
+            order,
+            SlotFormat::default(),
+            &New_York,
+        )
+    }
+
+    #[test]
+    fn days_follow_the_slot_order() {
+        let earliest = days(SlotOrder::EarliestFirst);
+        let headings: Vec<&str> = earliest.iter().map(|d| d.heading.as_str()).collect();
+        assert_eq!(
+            headings,
+            ["Monday Apr 7", "Tuesday Apr 8", "Wednesday Apr 9"]
+        );
+        assert_eq!(earliest[0].slots, ["9am–10:30am", "2pm–3pm"]);
+        assert!(earliest[1].slots.is_empty());
+
+        let latest = days(SlotOrder::LatestFirst);
+        assert_eq!(latest[0].heading, "Wednesday Apr 9");
+        assert_eq!(latest[0].slots, earliest[2].slots);
+    }
+
+    #[test]
+    fn page_matches_snapshot() {
+        let html = render_page(
+            "Ana <Barista>",
+            "America/New_York (UTC-04:00)",
+            &days(SlotOrder::EarliestFirst),
+            "Sunday Apr 6",
+        )
+        .unwrap();
+        assert_eq!(html, include_str!("testdata/availability_page.html"));
+    }
+
+    #[test]
+    fn link_is_the_base_url_and_file_name() {
+        let path = Path::new("/home/ana/My availability.html");
+        assert_eq!(
+            page_link("https://example.com/ana/", path).as_deref(),
+            Some("https://example.com/ana/My%20availability.html")
+        );
+        assert_eq!(page_link("  ", path), None);
+    }
+}
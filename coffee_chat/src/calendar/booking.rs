@@ -0,0 +1,302 @@
+// src/calendar/booking.rs
+// Putting an agreed time on the user's calendar with the other person invited.
+
+use super::token_refresh::{retry_after_refresh, ReauthRequired};
+use super::{describe_fetch_error, GoogleCalendar};
+use chrono::{DateTime, Utc};
+use google_calendar3::api::{
+    ConferenceData, ConferenceSolutionKey, CreateConferenceRequest, Event, EventAttendee,
+    EventDateTime,
+};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use tera::{Context, Tera};
+
+/// Lets the app add and remove events. Asked for the first time something is
+/// booked, on top of the read-only access used for slots.
+pub const CALENDAR_EVENTS_SCOPE: &str = "https://www.googleapis.com/auth/calendar.events";
+
+/// Event title until the user changes it.
+pub const DEFAULT_TITLE_TEMPLATE: &str = "Coffee chat: {{sender_name}} × {{recipient_name}}";
+
+// Where events go; the same calendar slots are read from
+const CALENDAR_ID: &str = "primary";
+
+/// A meeting to put on the calendar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MeetingRequest {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub title: String,
+    pub attendee_name: String,
+    pub attendee_email: String,
+    pub with_meet: bool, // Ask Google to attach a Meet link
+}
+
+/// A meeting the app put on the calendar, kept so it can be cancelled later.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookedMeeting {
+    pub event_id: String,
+    pub title: String,
+    pub attendee_email: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    #[serde(default)]
+    pub link: Option<String>, // The event in Google Calendar
+    #[serde(default)]
+    pub meet_link: Option<String>,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BookingError {
+    #[error("Permission to add events to your calendar wasn't granted. Book again and allow it to continue.")]
+    ScopeDeclined,
+    #[error("Couldn't sign in to change your calendar: {0}")]
+    Auth(String),
+    #[error("Google Calendar refused: {0}")]
+    Api(String),
+    #[error("Title template error: {0}")]
+    Title(String),
+}
+
+/// Renders the event title from a template using `sender_name`, `recipient_name`
+/// and `recipient_email`.
+pub fn render_title(
+    template: &str,
+    sender_name: &str,
+    recipient_name: &str,
+    recipient_email: &str,
+) -> Result<String, BookingError> {
+    let mut context = Context::new();
+    context.insert("sender_name", sender_name);
+    context.insert("recipient_name", recipient_name);
+    context.insert("recipient_email", recipient_email);
+    Tera::one_off(template, &context, false)
+        .map(|title| title.trim().to_string())
+        .map_err(|e| BookingError::Title(e.to_string()))
+}
+
+/// The event sent to Google for `request`.
+pub fn event_for(request: &MeetingRequest) -> Event {
+    let at = |time: DateTime<Utc>| EventDateTime {
+        date_time: Some(time),
+        ..Default::default()
+    };
+    let display_name = request.attendee_name.trim();
+    let conference_data = request.with_meet.then(|| ConferenceData {
+        create_request: Some(CreateConferenceRequest {
+            conference_solution_key: Some(ConferenceSolutionKey {
+                type_: Some("hangoutsMeet".to_string()),
+            }),
+            // Must differ per request, or Google reuses the earlier conference
+            request_id: Some(format!(
+                "coffee-chat-{}-{}",
+                request.start.timestamp(),
+                Utc::now().timestamp_millis()
+            )),
+        }),
+        ..Default::default()
+    });
+    Event {
+        summary: Some(request.title.clone()),
+        start: Some(at(request.start)),
+        end: Some(at(request.end)),
+        attendees: Some(vec![EventAttendee {
+            email: Some(request.attendee_email.trim().to_string()),
+            display_name: (!display_name.is_empty()).then(|| display_name.to_string()),
+            ..Default::default()
+        }]),
+        conference_data,
+        ..Default::default()
+    }
+}
+
+/// Creates the event on the primary calendar and emails the invitation to the attendee.
+pub async fn book(
+    google: &GoogleCalendar,
+    request: &MeetingRequest,
+) -> Result<BookedMeeting, BookingError> {
+    authorize(google).await?;
+    let hub = &google.hub;
+    let event = event_for(request);
+    info!(
+        "Booking '{}' with {} at {}",
+        request.title, request.attendee_email, request.start
+    );
+    let created = retry_after_refresh(
+        move || {
+            let event = event.clone();
+            async move {
+                let mut call = hub
+                    .events()
+                    .insert(event, CALENDAR_ID)
+                    .send_updates("all")
+                    .add_scope(CALENDAR_EVENTS_SCOPE);
+                if request.with_meet {
+                    call = call.conference_data_version(1);
+                }
+                let (_, created) = call.doit().await?;
+                Ok::<_, Box<dyn Error>>(created)
+            }
+        },
+        || google.refresh_token(CALENDAR_EVENTS_SCOPE),
+    )
+    .await
+    .map_err(|e| classify_api_error(&*e))?;
+
+    let event_id = created
+        .id
+        .ok_or_else(|| BookingError::Api("the created event has no ID".to_string()))?;
+    info!("Booked event {}", event_id);
+    Ok(BookedMeeting {
+        event_id,
+        title: request.title.clone(),
+        attendee_email: request.attendee_email.trim().to_string(),
+        start: request.start,
+        end: request.end,
+        link: created.html_link,
+        meet_link: created.hangout_link,
+    })
+}
+
+/// Deletes an event the app booked and tells the attendee it's cancelled.
+/// An event that's already gone counts as cancelled.
+pub async fn cancel(google: &GoogleCalendar, event_id: &str) -> Result<(), BookingError> {
+    authorize(google).await?;
+    let hub = &google.hub;
+    info!("Cancelling event {}", event_id);
+    let result = retry_after_refresh(
+        || async move {
+            hub.events()
+                .delete(CALENDAR_ID, event_id)
+                .send_updates("all")
+                .add_scope(CALENDAR_EVENTS_SCOPE)
+                .doit()
+                .await?;
+            Ok::<_, Box<dyn Error>>(())
+        },
+        || google.refresh_token(CALENDAR_EVENTS_SCOPE),
+    )
+    .await;
+    match result {
+        Err(e) if is_gone(&*e) => {
+            warn!("Event {} was already deleted", event_id);
+            Ok(())
+        }
+        other => other.map_err(|e| classify_api_error(&*e)),
+    }
+}
+
+/// Gets a token that can write events, running the consent flow if the user
+/// hasn't allowed that yet.
+async fn authorize(google: &GoogleCalendar) -> Result<(), BookingError> {
+    match google.auth.token(&[CALENDAR_EVENTS_SCOPE]).await {
+        Ok(_) => Ok(()),
+        Err(yup_oauth2::Error::AuthError(auth_error))
+            if auth_error.error == yup_oauth2::AuthErrorCode::AccessDenied =>
+        {
+            warn!("Calendar events permission declined");
+            Err(BookingError::ScopeDeclined)
+        }
+        Err(e) => Err(BookingError::Auth(e.to_string())),
+    }
+}
+
+fn classify_api_error(error: &(dyn Error + 'static)) -> BookingError {
+    if error.is::<ReauthRequired>() {
+        return BookingError::Auth(error.to_string());
+    }
+    match error.downcast_ref::<google_calendar3::Error>() {
+        Some(google_calendar3::Error::BadRequest(body)) if lacks_permission(body) => {
+            BookingError::ScopeDeclined
+        }
+        _ => BookingError::Api(describe_fetch_error(error)),
+    }
+}
+
+// A token without the events scope gets 403 "insufficientPermissions"
+fn lacks_permission(body: &serde_json::Value) -> bool {
+    body["error"]["errors"].as_array().is_some_and(|errors| {
+        errors
+            .iter()
+            .any(|e| e["reason"] == "insufficientPermissions")
+    })
+}
+
+fn is_gone(error: &(dyn Error + 'static)) -> bool {
+    matches!(
+        error.downcast_ref::<google_calendar3::Error>(),
+        Some(google_calendar3::Error::BadRequest(body)) if body["error"]["code"] == 410
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn request(with_meet: bool) -> MeetingRequest {
+        MeetingRequest {
+            start: Utc.with_ymd_and_hms(2025, 4, 8, 18, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2025, 4, 8, 18, 30, 0).unwrap(),
+            title: "Coffee chat".to_string(),
+            attendee_name: " Ana ".to_string(),
+            attendee_email: "ana@example.com ".to_string(),
+            with_meet,
+        }
+    }
+
+    #[test]
+    fn event_invites_the_attendee_at_the_agreed_time() {
+        let request = request(false);
+        let event = event_for(&request);
+        assert_eq!(event.summary.as_deref(), Some("Coffee chat"));
+        assert_eq!(event.start.unwrap().date_time, Some(request.start));
+        assert_eq!(event.end.unwrap().date_time, Some(request.end));
+        let attendees = event.attendees.unwrap();
+        assert_eq!(attendees[0].email.as_deref(), Some("ana@example.com"));
+        assert_eq!(attendees[0].display_name.as_deref(), Some("Ana"));
+        assert!(event.conference_data.is_none());
+    }
+
+    #[test]
+    fn meet_is_requested_only_when_asked() {
+        let event = event_for(&request(true));
+        let create = event.conference_data.unwrap().create_request.unwrap();
+        assert_eq!(
+            create.conference_solution_key.unwrap().type_.as_deref(),
+            Some("hangoutsMeet")
+        );
+        assert!(create.request_id.is_some());
+    }
+
+    #[test]
+    fn title_template_uses_both_names() {
+        let title = render_title(DEFAULT_TITLE_TEMPLATE, "Me", "Ana", "ana@example.com").unwrap();
+        assert_eq!(title, "Coffee chat: Me × Ana");
+        assert!(matches!(
+            render_title("{{ oops", "Me", "Ana", "ana@example.com"),
+            Err(BookingError::Title(_))
+        ));
+    }
+
+    #[test]
+    fn missing_scope_and_deleted_events_are_recognized() {
+        let forbidden = google_calendar3::Error::BadRequest(serde_json::json!({
+            "error": {
+                "code": 403,
+                "errors": [{ "reason": "insufficientPermissions" }]
+            }
+        }));
+        assert!(matches!(
+            classify_api_error(&forbidden),
+            BookingError::ScopeDeclined
+        ));
+        let gone = google_calendar3::Error::BadRequest(serde_json::json!({
+            "error": { "code": 410, "errors": [{ "reason": "deleted" }] }
+        }));
+        assert!(is_gone(&gone));
+        assert!(matches!(classify_api_error(&gone), BookingError::Api(_)));
+    }
+}
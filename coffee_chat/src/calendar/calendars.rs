@@ -0,0 +1,100 @@
+// src/calendar/calendars.rs
+// The user's calendar list, with the names and colors Google shows for them.
+
+use crate::app::TokioConnector;
+use google_calendar3::{api::CalendarListEntry, CalendarHub};
+use std::error::Error;
+
+/// One calendar from `calendar_list`, as shown in the Calendars section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarInfo {
+    pub id: String,
+    pub label: String,
+    pub color: Option<(u8, u8, u8)>, // None when Google sent no usable color
+    pub primary: bool,
+}
+
+impl CalendarInfo {
+    /// Entries without an id can't be queried, so they're skipped.
+    pub fn from_entry(entry: CalendarListEntry) -> Option<Self> {
+        let id = entry.id?;
+        // The user's own rename wins over the calendar's title
+        let label = entry
+            .summary_override
+            .or(entry.summary)
+            .filter(|label| !label.trim().is_empty())
+            .unwrap_or_else(|| id.clone());
+        Some(Self {
+            label,
+            color: entry.background_color.as_deref().and_then(parse_hex_color),
+            primary: entry.primary.unwrap_or(false),
+            id,
+        })
+    }
+}
+
+/// Every calendar on the user's list, primary first.
+pub async fn list_calendars(
+    hub: &CalendarHub<TokioConnector>,
+) -> Result<Vec<CalendarInfo>, Box<dyn Error>> {
+    let (_, list) = hub.calendar_list().list().doit().await?;
+    let mut calendars: Vec<CalendarInfo> = list
+        .items
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(CalendarInfo::from_entry)
+        .collect();
+    calendars.sort_by_key(|calendar| !calendar.primary);
+    Ok(calendars)
+}
+
+/// "#9fe1e7" → (0x9f, 0xe1, 0xe7). Anything else is None.
+pub fn parse_hex_color(text: &str) -> Option<(u8, u8, u8)> {
+    let hex = text.trim().strip_prefix('#')?;
+    if hex.len() != 6 || !hex.is_ascii() {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_google_hex_colors() {
+        assert_eq!(parse_hex_color("#9fe1e7"), Some((0x9f, 0xe1, 0xe7)));
+        assert_eq!(parse_hex_color(" #FFAD46 "), Some((0xff, 0xad, 0x46)));
+        assert_eq!(parse_hex_color("9fe1e7"), None);
+        assert_eq!(parse_hex_color("#fff"), None);
+        assert_eq!(parse_hex_color("#zzzzzz"), None);
+    }
+
+    #[test]
+    fn entries_fall_back_when_fields_are_missing() {
+        let entry = CalendarListEntry {
+            id: Some("team@group.calendar.google.com".to_string()),
+            summary: Some("Team".to_string()),
+            summary_override: Some("Work".to_string()),
+            ..Default::default()
+        };
+        let info = CalendarInfo::from_entry(entry).unwrap();
+        assert_eq!(info.label, "Work");
+        assert_eq!(info.color, None);
+        assert!(!info.primary);
+
+        let bare = CalendarListEntry {
+            id: Some("me@example.com".to_string()),
+            primary: Some(true),
+            background_color: Some("not a color".to_string()),
+            ..Default::default()
+        };
+        let info = CalendarInfo::from_entry(bare).unwrap();
+        assert_eq!(info.label, "me@example.com");
+        assert_eq!(info.color, None);
+        assert!(info.primary);
+
+        assert!(CalendarInfo::from_entry(CalendarListEntry::default()).is_none());
+    }
+}
@@ -0,0 +1,251 @@
+// src/calendar/connect_error.rs
+
+use super::proxy::find_proxy_error;
+use std::error::Error;
+use std::fmt;
+use std::io::ErrorKind;
+
+/// What went wrong while connecting to Google Calendar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectErrorKind {
+    /// credentials.json doesn't exist at the configured path.
+    CredentialsMissing { path: String },
+    /// credentials.json exists but isn't a valid OAuth client secret.
+    CredentialsInvalid { path: String },
+    /// The token cache couldn't be read or written.
+    TokenCache { path: String },
+    /// The local HTTP listener for the OAuth redirect couldn't be started on `port`,
+    /// or (`None`) no localhost port could be opened for it at all.
+    RedirectListener { port: Option<u16>, in_use: bool },
+    /// Google rejected the authorization (e.g. `access_denied` when consent is cancelled).
+    OAuth { code: String },
+    /// The proxy from HTTPS_PROXY/ALL_PROXY couldn't be reached or refused the tunnel.
+    Proxy { proxy: String },
+    /// Consent wasn't finished in time, e.g. the browser tab was closed.
+    TimedOut { minutes: u64 },
+    /// Anything we don't have a specific suggestion for.
+    Other,
+}
+
+/// A classified calendar connection failure, carried through `Message::CalendarConnectionFailed`.
+#[derive(Debug, Clone)]
+pub struct CalendarConnectError {
+    pub kind: ConnectErrorKind,
+    /// Full error chain, one cause per line, for bug reports.
+    pub details: String,
+}
+
+impl CalendarConnectError {
+    pub fn new(kind: ConnectErrorKind, error: &(dyn Error + 'static)) -> Self {
+        Self {
+            kind,
+            details: error_chain(error),
+        }
+    }
+
+    /// Failure reading `credentials.json`.
+    pub fn from_secret_error(path: &str, error: &std::io::Error) -> Self {
+        let kind = match error.kind() {
+            ErrorKind::NotFound => ConnectErrorKind::CredentialsMissing {
+                path: path.to_string(),
+            },
+            ErrorKind::InvalidData | ErrorKind::InvalidInput | ErrorKind::UnexpectedEof => {
+                ConnectErrorKind::CredentialsInvalid {
+                    path: path.to_string(),
+                }
+            }
+            _ => ConnectErrorKind::Other,
+        };
+        Self::new(kind, error)
+    }
+
+    /// Failure building the authenticator (token cache access).
+    pub fn from_token_cache_error(path: &str, error: &std::io::Error) -> Self {
+        Self::new(
+            ConnectErrorKind::TokenCache {
+                path: path.to_string(),
+            },
+            error,
+        )
+    }
+
+    /// Failure listening on localhost `port` for the OAuth redirect.
+    pub fn from_listener_error(port: Option<u16>, error: &std::io::Error) -> Self {
+        let kind = ConnectErrorKind::RedirectListener {
+            port,
+            in_use: error.kind() == ErrorKind::AddrInUse,
+        };
+        Self::new(kind, error)
+    }
+
+    /// Failure while running the consent flow / fetching the first token, with the
+    /// redirect listener on `redirect_port`.
+    pub fn from_auth_error(error: &yup_oauth2::Error, redirect_port: u16) -> Self {
+        if let Some(proxy_error) = find_proxy_error(error) {
+            let kind = ConnectErrorKind::Proxy {
+                proxy: proxy_error.proxy.clone(),
+            };
+            return Self::new(kind, error);
+        }
+        let kind = match error {
+            yup_oauth2::Error::AuthError(auth_error) => ConnectErrorKind::OAuth {
+                code: auth_error.error.as_str().to_string(),
+            },
+            yup_oauth2::Error::LowLevelError(io_error)
+                if matches!(
+                    io_error.kind(),
+                    ErrorKind::AddrInUse
+                        | ErrorKind::AddrNotAvailable
+                        | ErrorKind::PermissionDenied
+                ) =>
+            {
+                ConnectErrorKind::RedirectListener {
+                    port: Some(redirect_port),
+                    in_use: io_error.kind() == ErrorKind::AddrInUse,
+                }
+            }
+            _ => ConnectErrorKind::Other,
+        };
+        Self::new(kind, error)
+    }
+
+    /// One-line description of the cause, including the path or port involved.
+    pub fn summary(&self) -> String {
+        match &self.kind {
+            ConnectErrorKind::CredentialsMissing { path } => {
+                format!("Credentials file not found: {}", path)
+            }
+            ConnectErrorKind::CredentialsInvalid { path } => {
+                format!(
+                    "Credentials file is not a valid OAuth client secret: {}",
+                    path
+                )
+            }
+            ConnectErrorKind::TokenCache { path } => {
+                format!("Couldn't read or write the token cache: {}", path)
+            }
+            ConnectErrorKind::RedirectListener {
+                port: Some(port),
+                in_use: true,
+            } => {
+                format!(
+                    "Couldn't start the local redirect listener: port {} is already in use",
+                    port
+                )
+            }
+            ConnectErrorKind::RedirectListener {
+                port: Some(port), ..
+            } => {
+                format!(
+                    "Couldn't start the local redirect listener on localhost port {}",
+                    port
+                )
+            }
+            ConnectErrorKind::RedirectListener { port: None, .. } => {
+                "Couldn't open any localhost port for the redirect listener".to_string()
+            }
+            ConnectErrorKind::OAuth { code } => {
+                format!("Google rejected the authorization ({})", code)
+            }
+            ConnectErrorKind::Proxy { proxy } => {
+                format!("Couldn't reach Google through proxy {}", proxy)
+            }
+            ConnectErrorKind::TimedOut { minutes } => {
+                format!("Authorization timed out after {} minutes", minutes)
+            }
+            ConnectErrorKind::Other => "Calendar connection failed".to_string(),
+        }
+    }
+
+    /// What the user should try next.
+    pub fn suggestion(&self) -> &'static str {
+        match &self.kind {
+            ConnectErrorKind::CredentialsMissing { .. } => {
+                "Download the OAuth client JSON (Desktop app) from Google Cloud Console and save it as credentials.json next to Cargo.toml."
+            }
+            ConnectErrorKind::CredentialsInvalid { .. } => {
+                "Re-download the OAuth client JSON. Make sure it's a \"Desktop app\" client and that the file wasn't edited."
+            }
+            ConnectErrorKind::TokenCache { .. } => {
+                "Check that the folder is writable. Deleting the token cache file forces a fresh sign-in."
+            }
+            ConnectErrorKind::RedirectListener { in_use: true, .. } => {
+                "Another program took the port just as the app picked it. Click Connect again to use a different one."
+            }
+            ConnectErrorKind::RedirectListener { .. } => {
+                "A firewall or security tool may be blocking localhost listeners. Allow the app, then try again."
+            }
+            ConnectErrorKind::OAuth { code } if code == "access_denied" => {
+                "The consent screen was cancelled or denied. Click Connect again and approve calendar access."
+            }
+            ConnectErrorKind::OAuth { .. } => {
+                "Check the OAuth consent screen setup (test users, enabled Calendar API) in Google Cloud Console."
+            }
+            ConnectErrorKind::Proxy { .. } => {
+                "Check the HTTPS_PROXY / ALL_PROXY setting and proxy credentials, or add googleapis.com to NO_PROXY if it's reachable directly."
+            }
+            ConnectErrorKind::TimedOut { .. } => {
+                "Click Connect again and finish signing in in the browser tab that opens. Closing the tab doesn't cancel the wait; use Cancel for that."
+            }
+            ConnectErrorKind::Other => "Check your network connection and the details below, then try again.",
+        }
+    }
+
+    /// Plain-text report for the clipboard.
+    pub fn report(&self) -> String {
+        format!(
+            "{}\nSuggestion: {}\n\nError chain:\n{}",
+            self.summary(),
+            self.suggestion(),
+            self.details
+        )
+    }
+}
+
+impl fmt::Display for CalendarConnectError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.summary())
+    }
+}
+
+impl Error for CalendarConnectError {}
+
+/// Formats an error and all of its sources, one per line.
+fn error_chain(error: &(dyn Error + 'static)) -> String {
+    let mut chain = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        chain.push_str(&format!("\ncaused by: {}", cause));
+        source = cause.source();
+    }
+    chain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_busy_redirect_port_is_named() {
+        let in_use = std::io::Error::from(ErrorKind::AddrInUse);
+        let error =
+            CalendarConnectError::from_auth_error(&yup_oauth2::Error::LowLevelError(in_use), 8080);
+        assert_eq!(
+            error.kind,
+            ConnectErrorKind::RedirectListener {
+                port: Some(8080),
+                in_use: true
+            }
+        );
+        assert_eq!(
+            error.summary(),
+            "Couldn't start the local redirect listener: port 8080 is already in use"
+        );
+
+        let denied = std::io::Error::from(ErrorKind::PermissionDenied);
+        let error = CalendarConnectError::from_listener_error(Some(53682), &denied);
+        assert!(error.summary().ends_with("on localhost port 53682"));
+        let error = CalendarConnectError::from_listener_error(None, &denied);
+        assert!(error.report().contains("firewall"));
+    }
+}
@@ -0,0 +1,212 @@
+// src/calendar/demo.rs
+// Made-up calendars for demo mode: plausible weekday meetings, the same every
+// time for a given seed, so screenshots and walkthroughs are repeatable.
+
+use super::calendars::CalendarInfo;
+use super::CalendarBusy;
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use google_calendar3::api::TimePeriod;
+
+/// Seed used by `--demo` and the demo mode shortcut.
+pub const DEMO_SEED: u64 = 128;
+
+/// How long a fake connection or send "takes", so the spinners show.
+pub const DEMO_DELAY: std::time::Duration = std::time::Duration::from_millis(600);
+
+// Meetings start on the half hour between these hours and end by `DAY_END_HOUR`
+const DAY_START_HOUR: u32 = 9;
+const DAY_END_HOUR: u32 = 17;
+
+/// Busy periods for weekdays between `time_min` and `time_max`, in local time.
+pub fn demo_busy(time_min: DateTime<Utc>, time_max: DateTime<Utc>, seed: u64) -> Vec<TimePeriod> {
+    demo_busy_in(time_min, time_max, seed, &Local)
+}
+
+/// [`demo_busy`], with the meetings dealt out across the [`demo_calendars`] in turn,
+/// kept for the calendars in `calendar_ids` (the primary one when there are none,
+/// as with Google).
+pub fn demo_busy_by_calendar(
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    seed: u64,
+    calendar_ids: &[String],
+) -> Vec<CalendarBusy> {
+    let calendars = demo_calendars();
+    let selected = |calendar: &CalendarInfo| {
+        if calendar_ids.is_empty() {
+            calendar.primary
+        } else {
+            calendar_ids.contains(&calendar.id)
+        }
+    };
+    demo_busy(time_min, time_max, seed)
+        .into_iter()
+        .zip(calendars.iter().cycle())
+        .filter(|(_, calendar)| selected(calendar))
+        .map(|(period, calendar)| (calendar.id.clone(), period))
+        .collect()
+}
+
+/// Same as [`demo_busy`], but with working hours in an explicit timezone.
+pub fn demo_busy_in<Tz: TimeZone>(
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    seed: u64,
+    tz: &Tz,
+) -> Vec<TimePeriod> {
+    let first_day = time_min.with_timezone(tz).date_naive();
+    let last_day = time_max.with_timezone(tz).date_naive();
+    let mut busy = Vec::new();
+    for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+        if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) {
+            continue;
+        }
+        for (start, end) in day_meetings(day, seed) {
+            let at = |time: NaiveTime| {
+                tz.from_local_datetime(&day.and_time(time))
+                    .earliest()
+                    .map(|dt| dt.with_timezone(&Utc))
+            };
+            let (Some(start), Some(end)) = (at(start), at(end)) else {
+                continue;
+            };
+            // Keep only what overlaps the requested range, like the real API
+            if end > time_min && start < time_max {
+                busy.push(TimePeriod {
+                    start: Some(start.max(time_min)),
+                    end: Some(end.min(time_max)),
+                });
+            }
+        }
+    }
+    busy
+}
+
+/// Two to four meetings for one day. Each date gets its own sequence, so
+/// fetching a different range doesn't reshuffle the days already shown.
+fn day_meetings(day: NaiveDate, seed: u64) -> Vec<(NaiveTime, NaiveTime)> {
+    let mut rng = SplitMix64(seed ^ (day.num_days_from_ce() as u64).wrapping_mul(0x9e37_79b9));
+    let half_hours = (DAY_END_HOUR - DAY_START_HOUR) * 2;
+    let count = 2 + rng.below(3);
+    let mut meetings: Vec<(NaiveTime, NaiveTime)> = (0..count)
+        .filter_map(|_| {
+            let start_slot = rng.below(half_hours as u64 - 1) as u32;
+            let length = 1 + rng.below(3) as u32; // 30, 60 or 90 minutes
+            let end_slot = (start_slot + length).min(half_hours);
+            let time =
+                |slot: u32| NaiveTime::from_hms_opt(DAY_START_HOUR + slot / 2, (slot % 2) * 30, 0);
+            Some((time(start_slot)?, time(end_slot)?))
+        })
+        .collect();
+    meetings.sort();
+    meetings
+}
+
+/// A few calendars with Google-style colors for the Calendars section.
+pub fn demo_calendars() -> Vec<CalendarInfo> {
+    [
+        ("demo@example.com", "Demo User", (0x9f, 0xe1, 0xe7), true),
+        ("work@demo.example.com", "Work", (0xff, 0xad, 0x46), false),
+        (
+            "club@demo.example.com",
+            "Student Org",
+            (0x7b, 0xd1, 0x48),
+            false,
+        ),
+    ]
+    .into_iter()
+    .map(|(id, label, color, primary)| CalendarInfo {
+        id: id.to_string(),
+        label: label.to_string(),
+        color: Some(color),
+        primary,
+    })
+    .collect()
+}
+
+// Small, dependency-free generator; quality only needs to look random on a calendar
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, FixedOffset, Timelike};
+
+    // Fixed UTC-5 so results don't depend on the machine's timezone
+    fn tz() -> FixedOffset {
+        FixedOffset::west_opt(5 * 3600).unwrap()
+    }
+
+    fn week() -> (DateTime<Utc>, DateTime<Utc>) {
+        // Monday April 7 through Sunday April 13, 2025
+        let start = tz().with_ymd_and_hms(2025, 4, 7, 0, 0, 0).unwrap();
+        (
+            start.with_timezone(&Utc),
+            (start + Duration::days(7)).with_timezone(&Utc),
+        )
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_week() {
+        let (min, max) = week();
+        let spans = |seed| -> Vec<_> {
+            demo_busy_in(min, max, seed, &tz())
+                .into_iter()
+                .map(|p| (p.start, p.end))
+                .collect()
+        };
+        assert_eq!(spans(DEMO_SEED), spans(DEMO_SEED));
+        assert_ne!(spans(DEMO_SEED), spans(DEMO_SEED + 1));
+    }
+
+    #[test]
+    fn meetings_are_on_weekdays_within_working_hours() {
+        let (min, max) = week();
+        let busy = demo_busy_in(min, max, DEMO_SEED, &tz());
+        assert!(
+            busy.len() >= 10,
+            "expected 2+ meetings on each of 5 weekdays"
+        );
+        for period in busy {
+            let start = period.start.unwrap().with_timezone(&tz());
+            let end = period.end.unwrap().with_timezone(&tz());
+            assert!(!matches!(start.weekday(), Weekday::Sat | Weekday::Sun));
+            assert!(start.hour() >= DAY_START_HOUR);
+            assert!(end.hour() < DAY_END_HOUR || (end.hour() == DAY_END_HOUR && end.minute() == 0));
+            assert!(start < end);
+        }
+    }
+
+    #[test]
+    fn no_calendars_selected_reads_the_primary_one() {
+        let (min, max) = week();
+        let calendars = |ids: &[String]| -> Vec<String> {
+            let mut ids: Vec<_> = demo_busy_by_calendar(min, max, DEMO_SEED, ids)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            ids.dedup();
+            ids
+        };
+        assert_eq!(calendars(&[]), ["demo@example.com"]);
+        let work = ["work@demo.example.com".to_string()];
+        assert_eq!(calendars(&work), work);
+        let all: Vec<_> = demo_calendars().into_iter().map(|c| c.id).collect();
+        assert_eq!(calendars(&all).len(), 3);
+    }
+}
@@ -0,0 +1,273 @@
+// src/calendar/diagnose.rs
+// Runs the slot pipeline over a sample week and counts what survives each step,
+// so "the app shows no slots" comes with a reason.
+
+use super::{free_busy, free_time, BusySource, CalendarBusy, SlotFilters, MIN_SLOT_MINUTES};
+use chrono::{DateTime, Duration, Utc};
+use google_calendar3::api::TimePeriod;
+use std::error::Error;
+
+/// How many days ahead the diagnosis looks.
+pub const DIAGNOSE_DAYS: i64 = 7;
+
+/// A step of the slot pipeline, in the order they run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    BusyPeriods,
+    FreeWindows,
+    SplitAtMidnight,
+    Weekdays,
+    DailyHours,
+    WeeklyRules,
+    TodayCutoff,
+    BlackoutDates,
+    Spacing,
+    MinimumLength,
+}
+
+impl Stage {
+    pub fn label(self) -> &'static str {
+        match self {
+            Stage::BusyPeriods => "Busy periods fetched",
+            Stage::FreeWindows => "Free windows",
+            Stage::SplitAtMidnight => "After splitting at midnight",
+            Stage::Weekdays => "On offered weekdays",
+            Stage::DailyHours => "Within daily hours",
+            Stage::WeeklyRules => "Within weekly availability",
+            Stage::TodayCutoff => "After today's cutoff",
+            Stage::BlackoutDates => "Outside blackout dates",
+            Stage::Spacing => "After min gap / max per day",
+            Stage::MinimumLength => "At least 30 minutes long",
+        }
+    }
+
+    /// What to try when this stage is the one that leaves nothing.
+    pub fn suggestion(self) -> &'static str {
+        match self {
+            Stage::BusyPeriods => {
+                "Your calendar has no busy time this week. If that's wrong, reconnect with the Google account you actually use."
+            }
+            Stage::FreeWindows => {
+                "The week is fully booked once buffers are added. Try smaller buffers."
+            }
+            Stage::SplitAtMidnight => "Free time was lost splitting days. Please report this.",
+            Stage::Weekdays => {
+                "Free time only falls on weekdays you left out. Tick more days in Calendar Settings."
+            }
+            Stage::DailyHours => {
+                "No free time falls inside Daily Availability. Widen the hours, and check your computer's timezone is right."
+            }
+            Stage::WeeklyRules => {
+                "No free time falls inside your Weekly Availability. Add days or ranges, or turn it off."
+            }
+            Stage::TodayCutoff => {
+                "Only today had free time and it's past the cutoff. Turn off Today Cutoff or check again tomorrow."
+            }
+            Stage::BlackoutDates => {
+                "Every day with free time is a blackout date. Remove some in Calendar Settings."
+            }
+            Stage::Spacing => "Min Gap or Max Per Day removed every slot. Lower them.",
+            Stage::MinimumLength => {
+                "Free time only comes in gaps under 30 minutes. Try smaller buffers or wider hours."
+            }
+        }
+    }
+}
+
+/// How many windows were left after each stage of one pipeline run.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Diagnosis {
+    pub counts: Vec<(Stage, usize)>,
+    pub free_time: free_time::FreeTime, // Of the working hours in the same days
+    pub busy_by_calendar: Vec<(String, usize)>, // Busy periods per calendar id, most first
+}
+
+impl Diagnosis {
+    pub fn record(&mut self, stage: Stage, count: usize) {
+        self.counts.push((stage, count));
+    }
+
+    pub fn count(&self, stage: Stage) -> Option<usize> {
+        self.counts
+            .iter()
+            .find(|(s, _)| *s == stage)
+            .map(|&(_, count)| count)
+    }
+
+    /// How many windows `stage` dropped, compared with the stage before it.
+    pub fn removed_by(&self, stage: Stage) -> Option<usize> {
+        let index = self.counts.iter().position(|(s, _)| *s == stage)?;
+        let before = self.counts[..index].last()?.1;
+        Some(before.saturating_sub(self.counts[index].1))
+    }
+
+    /// The first stage that left no windows. An empty calendar isn't a failure,
+    /// so busy periods are skipped here.
+    pub fn first_empty_stage(&self) -> Option<Stage> {
+        self.counts
+            .iter()
+            .find(|&&(stage, count)| stage != Stage::BusyPeriods && count == 0)
+            .map(|&(stage, _)| stage)
+    }
+}
+
+/// Fetches the next [`DIAGNOSE_DAYS`] days of `calendar_ids` (the primary calendar
+/// when there are none) and runs them through the pipeline.
+pub async fn diagnose_slots(
+    source: &BusySource,
+    filters: &SlotFilters,
+    calendar_ids: &[String],
+) -> Result<Diagnosis, Box<dyn Error>> {
+    let time_min = Utc::now();
+    let time_max = time_min + Duration::days(DIAGNOSE_DAYS);
+    let by_calendar = source
+        .busy_by_calendar(time_min, time_max, calendar_ids)
+        .await?;
+    let mut diagnosis = diagnose_busy(&super::periods(&by_calendar), time_min, time_max, filters);
+    diagnosis.busy_by_calendar = count_by_calendar(&by_calendar);
+    Ok(diagnosis)
+}
+
+/// How many busy periods each calendar has, most first.
+pub fn count_by_calendar(busy: &[CalendarBusy]) -> Vec<(String, usize)> {
+    let mut counts: Vec<(String, usize)> = Vec::new();
+    for (calendar_id, _) in busy {
+        match counts.iter_mut().find(|(id, _)| id == calendar_id) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((calendar_id.clone(), 1)),
+        }
+    }
+    // Stable, so ties stay in the order the calendars came back
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+    counts
+}
+
+/// Same as [`diagnose_slots`], for busy periods already in hand.
+pub fn diagnose_busy(
+    busy: &[TimePeriod],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    filters: &SlotFilters,
+) -> Diagnosis {
+    let mut diagnosis = Diagnosis::default();
+    let windows = super::slots_from_busy(busy, time_min, time_max, filters, &mut |stage, count| {
+        diagnosis.record(stage, count)
+    });
+    // Summarizing merges adjacent windows and drops short ones; one line per slot offered
+    let offered = free_busy::summarize_slots(&windows, Duration::minutes(MIN_SLOT_MINUTES));
+    diagnosis.record(Stage::MinimumLength, offered.len());
+    diagnosis.free_time = free_time::free_time(busy, time_min, time_max, filters);
+    diagnosis
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::units::{Buffer, HourOfDay};
+    use chrono::TimeZone;
+
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 4, 7, 0, 0, 0).unwrap()
+    }
+
+    fn filters() -> SlotFilters {
+        SlotFilters {
+            buffer: Buffer::default(),
+            start_hour: HourOfDay::MIDNIGHT,
+            end_hour: HourOfDay::LAST,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fully_booked_week_stops_at_free_windows() {
+        let end = start() + Duration::days(DIAGNOSE_DAYS);
+        let busy = vec![TimePeriod {
+            start: Some(start()),
+            end: Some(end),
+        }];
+        let diagnosis = diagnose_busy(&busy, start(), end, &filters());
+
+        assert_eq!(diagnosis.count(Stage::BusyPeriods), Some(1));
+        assert_eq!(diagnosis.count(Stage::FreeWindows), Some(0));
+        assert_eq!(diagnosis.first_empty_stage(), Some(Stage::FreeWindows));
+        assert_eq!(diagnosis.free_time.free_minutes, 0);
+        assert_eq!(diagnosis.free_time.percent_free(), Some(0));
+    }
+
+    #[test]
+    fn empty_calendar_is_not_reported_as_the_problem() {
+        let end = start() + Duration::days(DIAGNOSE_DAYS);
+        let diagnosis = diagnose_busy(&[], start(), end, &filters());
+
+        let stages: Vec<Stage> = diagnosis.counts.iter().map(|&(stage, _)| stage).collect();
+        assert_eq!(
+            stages,
+            vec![
+                Stage::BusyPeriods,
+                Stage::FreeWindows,
+                Stage::SplitAtMidnight,
+                Stage::DailyHours,
+                Stage::TodayCutoff,
+                Stage::BlackoutDates,
+                Stage::Spacing,
+                Stage::MinimumLength,
+            ]
+        );
+        assert_eq!(diagnosis.count(Stage::BusyPeriods), Some(0));
+        assert_eq!(diagnosis.count(Stage::FreeWindows), Some(1));
+        assert_eq!(diagnosis.first_empty_stage(), None);
+    }
+
+    #[test]
+    fn first_empty_stage_is_the_earliest_zero() {
+        let diagnosis = Diagnosis {
+            counts: vec![
+                (Stage::BusyPeriods, 4),
+                (Stage::FreeWindows, 5),
+                (Stage::DailyHours, 0),
+                (Stage::MinimumLength, 0),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(diagnosis.first_empty_stage(), Some(Stage::DailyHours));
+    }
+
+    #[test]
+    fn busy_periods_are_counted_per_calendar() {
+        let period = |hour| TimePeriod {
+            start: Some(start() + Duration::hours(hour)),
+            end: Some(start() + Duration::hours(hour + 1)),
+        };
+        let busy: Vec<CalendarBusy> = vec![
+            ("me@example.com".into(), period(9)),
+            ("work@example.com".into(), period(10)),
+            ("work@example.com".into(), period(11)),
+            ("club@example.com".into(), period(12)),
+        ];
+        assert_eq!(
+            count_by_calendar(&busy),
+            vec![
+                ("work@example.com".to_string(), 2),
+                ("me@example.com".to_string(), 1),
+                ("club@example.com".to_string(), 1),
+            ]
+        );
+        assert!(count_by_calendar(&[]).is_empty());
+    }
+
+    #[test]
+    fn removed_by_compares_with_the_previous_stage() {
+        let diagnosis = Diagnosis {
+            counts: vec![
+                (Stage::BusyPeriods, 4),
+                (Stage::TodayCutoff, 9),
+                (Stage::BlackoutDates, 6),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(diagnosis.removed_by(Stage::BlackoutDates), Some(3));
+        assert_eq!(diagnosis.removed_by(Stage::BusyPeriods), None);
+        assert_eq!(diagnosis.removed_by(Stage::Spacing), None);
+    }
+}
@@ -0,0 +1,242 @@
+// src/calendar/format.rs
+// How days and times are written, in one place so the slot list on screen, the
+// email and the availability page can't drift apart.
+
+use chrono::{DateTime, Locale, NaiveDate, TimeZone, Timelike, Utc};
+
+/// How slot times are written. The default is the 12-hour "2pm" / "2:30pm" style.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlotFormat {
+    /// "14:00" / "14:30" instead of "2pm" / "2:30pm"
+    pub twenty_four_hour: bool,
+    /// Day and month names in this language instead of English
+    pub locale: Option<Locale>,
+}
+
+impl SlotFormat {
+    /// The format for a recipient's language, e.g. "fr", "de-AT" or "pt_BR".
+    /// English, and anything unrecognized, gives `None`: the sender's own format.
+    pub fn for_language(language: &str) -> Option<Self> {
+        let locale = parse_locale(language)?;
+        if locale_language(locale) == "en" {
+            return None;
+        }
+        // Outside English, slot times are nearly always written 24-hour
+        Some(Self {
+            twenty_four_hour: true,
+            locale: Some(locale),
+        })
+    }
+}
+
+// Region used when only a language is given
+const DEFAULT_REGIONS: &[(&str, &str)] = &[
+    ("cs", "CZ"),
+    ("da", "DK"),
+    ("en", "US"),
+    ("ja", "JP"),
+    ("ko", "KR"),
+    ("nb", "NO"),
+    ("sv", "SE"),
+    ("uk", "UA"),
+    ("zh", "CN"),
+];
+
+/// Whether `language` names a locale slots can be written in. Anything else is
+/// written in English, so the app can warn about it.
+pub fn recognizes_language(language: &str) -> bool {
+    parse_locale(language).is_some()
+}
+
+fn parse_locale(language: &str) -> Option<Locale> {
+    let mut parts = language.trim().split(['-', '_']);
+    let lang = parts.next()?.to_lowercase();
+    let region = match parts.next() {
+        Some(region) => region.to_uppercase(),
+        None => DEFAULT_REGIONS
+            .iter()
+            .find(|(code, _)| *code == lang)
+            .map(|(_, region)| region.to_string())
+            .unwrap_or_else(|| lang.to_uppercase()), // fr_FR, de_DE, it_IT, ...
+    };
+    format!("{}_{}", lang, region).parse().ok()
+}
+
+// "fr" for fr_FR
+fn locale_language(locale: Locale) -> String {
+    let name = locale.to_string();
+    name.split('_').next().unwrap_or_default().to_string()
+}
+
+/// "Wednesday Apr 9", the day prefix of every summary line.
+pub fn format_day(day: NaiveDate) -> String {
+    day.format("%A %b %-d").to_string()
+}
+
+/// The day prefix in `format`'s language: "mardi 8 avril", "Dienstag, 8. April".
+pub fn format_day_as(day: NaiveDate, format: SlotFormat) -> String {
+    let Some(locale) = format.locale else {
+        return format_day(day);
+    };
+    let pattern = match locale_language(locale).as_str() {
+        "de" => "%A, %-d. %B",
+        "es" => "%A %-d de %B",
+        "pt" => "%A, %-d de %B",
+        _ => "%A %-d %B",
+    };
+    day.format_localized(pattern, locale).to_string()
+}
+
+/// One time of day. 12-hour times leave out ":00" on the hour; 24-hour times always have minutes.
+pub fn format_time<Tz: TimeZone>(dt: &DateTime<Tz>, format: SlotFormat) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    if format.twenty_four_hour {
+        dt.format("%H:%M").to_string()
+    } else if dt.minute() == 0 {
+        dt.format("%-I%P").to_string()
+    } else {
+        dt.format("%-I:%M%P").to_string()
+    }
+}
+
+/// "2pm–3:30pm", or "11pm–Thursday Apr 10: 1am" when the window ends on a later day.
+pub fn format_window_in<Tz: TimeZone>(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    format: SlotFormat,
+    tz: &Tz,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let start = start.with_timezone(tz);
+    let end = end.with_timezone(tz);
+    if start.date_naive() != end.date_naive() {
+        format!(
+            "{}–{}: {}",
+            format_time(&start, format),
+            format_day_as(end.date_naive(), format),
+            format_time(&end, format)
+        )
+    } else {
+        format!(
+            "{}–{}",
+            format_time(&start, format),
+            format_time(&end, format)
+        )
+    }
+}
+
+/// A whole summary line: "Monday Apr 7: 2pm–3pm".
+pub fn format_slot_in<Tz: TimeZone>(
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    format: SlotFormat,
+    tz: &Tz,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    format!(
+        "{}: {}",
+        format_day_as(start.with_timezone(tz).date_naive(), format),
+        format_window_in(start, end, format, tz)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::America::New_York;
+
+    const TWELVE: SlotFormat = SlotFormat {
+        twenty_four_hour: false,
+        locale: None,
+    };
+    const TWENTY_FOUR: SlotFormat = SlotFormat {
+        twenty_four_hour: true,
+        locale: None,
+    };
+
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        New_York
+            .with_ymd_and_hms(2025, 4, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn twelve_hour_times_drop_zero_minutes() {
+        let local = |day, hour, minute| at(day, hour, minute).with_timezone(&New_York);
+        assert_eq!(format_time(&local(7, 14, 0), TWELVE), "2pm");
+        assert_eq!(format_time(&local(7, 14, 30), TWELVE), "2:30pm");
+        assert_eq!(format_time(&local(7, 0, 5), TWELVE), "12:05am");
+        assert_eq!(SlotFormat::default(), TWELVE);
+    }
+
+    #[test]
+    fn twenty_four_hour_times_always_have_minutes() {
+        let local = |day, hour, minute| at(day, hour, minute).with_timezone(&New_York);
+        assert_eq!(format_time(&local(7, 14, 0), TWENTY_FOUR), "14:00");
+        assert_eq!(format_time(&local(7, 9, 30), TWENTY_FOUR), "09:30");
+        assert_eq!(format_time(&local(7, 0, 5), TWENTY_FOUR), "00:05");
+    }
+
+    #[test]
+    fn windows_are_written_in_the_given_zone() {
+        assert_eq!(
+            format_slot_in(at(7, 9, 0), at(7, 10, 30), TWELVE, &New_York),
+            "Monday Apr 7: 9am–10:30am"
+        );
+        assert_eq!(
+            format_slot_in(at(7, 23, 0), at(8, 1, 0), TWENTY_FOUR, &New_York),
+            "Monday Apr 7: 23:00–Tuesday Apr 8: 01:00"
+        );
+        // The same instants an hour west
+        assert_eq!(
+            format_window_in(
+                at(7, 9, 0),
+                at(7, 10, 30),
+                TWELVE,
+                &chrono_tz::America::Chicago
+            ),
+            "8am–9:30am"
+        );
+    }
+
+    #[test]
+    fn french_and_german_slots_use_their_names_and_24_hour_times() {
+        let french = SlotFormat::for_language("fr").unwrap();
+        assert_eq!(
+            format_slot_in(at(8, 14, 0), at(8, 15, 30), french, &New_York),
+            "mardi 8 avril: 14:00–15:30"
+        );
+        let german = SlotFormat::for_language("de-DE").unwrap();
+        assert_eq!(
+            format_slot_in(at(8, 14, 0), at(8, 15, 30), german, &New_York),
+            "Dienstag, 8. April: 14:00–15:30"
+        );
+        // The end day is written in the same language
+        assert_eq!(
+            format_slot_in(at(7, 23, 0), at(8, 1, 0), german, &New_York),
+            "Montag, 7. April: 23:00–Dienstag, 8. April: 01:00"
+        );
+    }
+
+    #[test]
+    fn languages_are_parsed_loosely() {
+        let locale = |language| SlotFormat::for_language(language).and_then(|f| f.locale);
+        assert_eq!(locale("FR"), Some(Locale::fr_FR));
+        assert_eq!(locale("fr-ca"), Some(Locale::fr_CA));
+        assert_eq!(locale("pt_BR"), Some(Locale::pt_BR));
+        assert_eq!(locale("sv"), Some(Locale::sv_SE));
+        // English keeps the sender's format; unknown codes are ignored
+        assert_eq!(SlotFormat::for_language("en-GB"), None);
+        assert_eq!(SlotFormat::for_language("klingon"), None);
+        assert_eq!(SlotFormat::for_language(""), None);
+        assert!(recognizes_language("en-GB") && recognizes_language("es-MX"));
+        assert!(!recognizes_language("klingon") && !recognizes_language("fr-XX"));
+    }
+}
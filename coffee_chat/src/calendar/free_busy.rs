@@ -1,29 +1,47 @@
 // src/calendar/free_busy.rs
 
+use super::format::{format_day_as, format_slot_in, SlotFormat};
+use super::units::{Buffer, HourOfDay, Minutes, Weekdays};
+use super::CalendarBusy;
 use crate::app::TokioConnector; // your concrete connector type
-use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone, Timelike, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Timelike, Utc, Weekday,
+};
 use google_calendar3::{
-    api::{FreeBusyRequest, FreeBusyRequestItem, TimePeriod},
+    api::{FreeBusyCalendar, FreeBusyRequest, FreeBusyRequestItem, TimePeriod},
     CalendarHub,
 };
 use log::{debug, error, trace};
-use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::error::Error;
+use std::fmt;
+
+// Endings of the summary lines that aren't slots; see `is_slot_line`
+const NO_AVAILABILITY_SUFFIX: &str = ": no availability";
+const MORE_ON_REQUEST_SUFFIX: &str = " more on request";
 
-/// Fetch busy periods from the FreeBusy API for a calendar.
+/// Fetch busy periods from the FreeBusy API for several calendars in one query,
+/// each period with the calendar it came from. Overlaps between calendars are left
+/// for `normalize_busy_periods` to merge.
 pub async fn get_busy_slots(
     hub: &CalendarHub<TokioConnector>,
-    calendar_id: &str,
+    calendar_ids: &[String],
     time_min: DateTime<Utc>,
     time_max: DateTime<Utc>,
-) -> Result<Vec<TimePeriod>, Box<dyn Error>> {
+) -> Result<Vec<CalendarBusy>, Box<dyn Error>> {
     let req = FreeBusyRequest {
         time_min: Some(time_min),
         time_max: Some(time_max),
         time_zone: Some("UTC".to_string()),
-        items: Some(vec![FreeBusyRequestItem {
-            id: Some(calendar_id.to_string()),
-        }]),
+        items: Some(
+            calendar_ids
+                .iter()
+                .map(|calendar_id| FreeBusyRequestItem {
+                    id: Some(calendar_id.clone()),
+                })
+                .collect(),
+        ),
         calendar_expansion_max: None,
         group_expansion_max: None,
     };
@@ -32,55 +50,130 @@ pub async fn get_busy_slots(
     let (_, resp) = hub.freebusy().query(req).doit().await?;
     trace!("Received FreeBusy response");
 
-    let busy = resp
-        .calendars
-        .and_then(|m| m.get(calendar_id).cloned())
-        .and_then(|c| c.busy)
-        .unwrap_or_default();
+    busy_from_response(&resp.calendars.unwrap_or_default(), calendar_ids).map_err(Into::into)
+}
 
-    debug!("Busy periods for {}: {:?}", calendar_id, busy);
+/// Each calendar's busy periods from a FreeBusy response, tagged with the calendar.
+/// A calendar that can't be read (e.g. "notFound") comes back empty, which would look
+/// free, so its errors fail the whole query instead.
+fn busy_from_response(
+    calendars: &HashMap<String, FreeBusyCalendar>,
+    calendar_ids: &[String],
+) -> Result<Vec<CalendarBusy>, String> {
+    let mut busy = Vec::new();
+    for calendar_id in calendar_ids {
+        let calendar = calendars.get(calendar_id);
+        if let Some(error) = calendar
+            .and_then(|c| c.errors.as_ref())
+            .and_then(|errors| errors.first())
+        {
+            return Err(format!(
+                "Calendar '{}' can't be read: {}",
+                calendar_id,
+                error.reason.as_deref().unwrap_or("unknown error")
+            ));
+        }
+        let periods = calendar.and_then(|c| c.busy.clone()).unwrap_or_default();
+        debug!("Busy periods for {}: {:?}", calendar_id, periods);
+        busy.extend(
+            periods
+                .into_iter()
+                .map(|period| (calendar_id.clone(), period)),
+        );
+    }
     Ok(busy)
 }
 
+/// Sort busy periods and merge duplicates, overlaps, and periods that start within
+/// `epsilon` of the previous one ending. Periods missing a start or end are dropped;
+/// one ending before it starts is kept as a point at its start.
+///
+/// The same event seen through two calendars often comes back twice, sometimes a
+/// minute apart; merging before buffering keeps buffers from being applied twice.
+pub fn normalize_busy_periods(busy: &[TimePeriod], epsilon: Duration) -> Vec<TimePeriod> {
+    let mut periods: Vec<(DateTime<Utc>, DateTime<Utc>)> = busy
+        .iter()
+        .filter_map(|p| Some((p.start?, p.end?.max(p.start?))))
+        .collect();
+    periods.sort();
+
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(periods.len());
+    for (start, end) in periods {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end + epsilon => {
+                *last_end = (*last_end).max(end);
+            }
+            _ => merged.push((start, end)),
+        }
+    }
+
+    if merged.len() != busy.len() {
+        debug!(
+            "Normalized {} busy periods into {}",
+            busy.len(),
+            merged.len()
+        );
+    }
+    merged
+        .into_iter()
+        .map(|(start, end)| TimePeriod {
+            start: Some(start),
+            end: Some(end),
+        })
+        .collect()
+}
+
 /// Compute full free windows with a buffer **before** and **after** each busy slot.
+/// `buffer` takes a [`Buffer`] for different amounts on each side, or plain
+/// [`Minutes`] for the same amount on both.
+///
+/// Boundary rules:
+/// - Free windows never leave `window_start..window_end`. Busy periods partly or
+///   entirely outside the range only matter through the part of their buffered
+///   span that reaches into it.
+/// - A busy period touching an edge (starting at `window_start` or ending at
+///   `window_end`) still blocks its buffer on the inner side only.
+/// - The range edges themselves aren't busy, so by default the first window can
+///   start right at `window_start` ("now") and the last can run to `window_end`.
+///   With `pad_edges`, the after-buffer is also kept clear after `window_start`
+///   and the before-buffer before `window_end`.
+///
+/// With `buffer_longer_than`, only meetings longer than that get the buffer.
+/// Overlapping or back-to-back meetings count as one meeting, so a standup
+/// during a workshop leaves the workshop's buffer as it is.
+///
+/// A zero-length busy period (Google returns them for instant reminders) is a
+/// point: it's buffered like any meeting, and blocks nothing without a buffer.
+/// Every window returned is at least a moment long.
 pub fn find_free_windows(
     busy: &[TimePeriod],
     window_start: DateTime<Utc>,
     window_end: DateTime<Utc>,
-    buffer: Duration, // Use the passed buffer duration
+    buffer: impl Into<Buffer>,
+    buffer_longer_than: Option<Minutes>,
+    pad_edges: bool,
 ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
-    let mut windows = Vec::new();
-    let mut periods = busy.to_vec();
-    // Ensure sorting by start time
-    periods.sort_by_key(|p| p.start);
+    let buffer = buffer.into();
+    // "Now" is treated like a meeting that just ended, the range end like one about to start
+    let (window_start, window_end) = if pad_edges {
+        (
+            window_start + buffer.after.duration(),
+            window_end - buffer.before.duration(),
+        )
+    } else {
+        (window_start, window_end)
+    };
+    let blocked = blocked_periods(busy, buffer, buffer_longer_than);
 
+    let mut windows = Vec::new();
     let mut cursor = window_start;
-
-    for p in periods {
-        // Gracefully handle missing start/end, though FreeBusy usually provides them
-        let busy_start = match p.start {
-            Some(dt) => dt,
-            None => {
-                debug!("Skipping busy period with no start time");
-                continue; // Skip if no start time
-            }
-        };
-        let busy_end = match p.end {
-            Some(dt) => dt,
-            None => {
-                debug!("Skipping busy period with no end time");
-                continue; // Skip if no end time
-            }
-        };
-
-        // Calculate the start of the 'blocked' period (including buffer)
-        let blocked_start = busy_start - buffer;
-        // Calculate the end of the 'blocked' period (including buffer)
-        let blocked_end = busy_end + buffer;
-
+    for (blocked_start, blocked_end) in blocked {
         // If there's a gap between the current cursor and the start of the blocked period
-        if blocked_start > cursor {
-            windows.push((cursor, blocked_start));
+        // (a period after the range only cuts the last window at the range end)
+        let gap_end = blocked_start.min(window_end);
+        // Strictly after: a block starting where the last one ended leaves no window
+        if gap_end > cursor {
+            windows.push((cursor, gap_end));
         }
 
         // Advance the cursor to the end of the blocked period, ensuring it only moves forward
@@ -97,28 +190,81 @@ pub fn find_free_windows(
         windows.push((cursor, window_end));
     }
 
+    debug_assert!(
+        windows.iter().all(|(start, end)| start < end),
+        "empty free window in {:?}",
+        windows
+    );
     debug!("Raw free windows (with buffer): {:?}", windows);
     windows
 }
 
-/// Split windows at local midnight so each window stays on one date.
-pub fn split_at_midnight(
+// Busy periods merged into blocks and widened by their buffers, sorted by start
+fn blocked_periods(
+    busy: &[TimePeriod],
+    buffer: Buffer,
+    buffer_longer_than: Option<Minutes>,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut periods: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for p in busy {
+        // Gracefully handle missing start/end, though FreeBusy usually provides them
+        let (Some(start), Some(end)) = (p.start, p.end) else {
+            debug!("Skipping busy period with no start or end time");
+            continue;
+        };
+        // Backwards periods are points, like zero-length ones
+        periods.push((start, end.max(start)));
+    }
+    periods.sort();
+
+    let mut blocks: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::new();
+    for (start, end) in periods {
+        match blocks.last_mut() {
+            Some((_, block_end)) if start <= *block_end => *block_end = (*block_end).max(end),
+            _ => blocks.push((start, end)),
+        }
+    }
+
+    let mut blocked: Vec<_> = blocks
+        .into_iter()
+        .map(|(start, end)| {
+            let buffered = buffer_longer_than.is_none_or(|min| end - start > min.duration());
+            if buffered {
+                (
+                    start - buffer.before.duration(),
+                    end + buffer.after.duration(),
+                )
+            } else {
+                (start, end)
+            }
+        })
+        // A point with no buffer would only split the free time around it in two
+        .filter(|(start, end)| start < end)
+        .collect();
+    // A buffered meeting can reach back past a short one just before it
+    blocked.sort();
+    blocked
+}
+
+/// Split windows at midnight in `tz` so each window stays on one date.
+pub fn split_at_midnight_in<Tz: TimeZone>(
     windows: &[(DateTime<Utc>, DateTime<Utc>)],
+    tz: &Tz,
 ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
     let mut out = Vec::new();
     for &(s_utc, e_utc) in windows {
         let mut cur_start = s_utc;
-        let mut cur_date = s_utc.with_timezone(&Local).date_naive();
-        let end_date = e_utc.with_timezone(&Local).date_naive();
+        let mut cur_date = s_utc.with_timezone(tz).date_naive();
+        let end_date = e_utc.with_timezone(tz).date_naive();
 
         trace!("Splitting window: {:?}–{:?}", s_utc, e_utc);
 
         while cur_date < end_date {
             let nm_naive = cur_date.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
 
-            let nm_utc = match Local.from_local_datetime(&nm_naive).single() {
+            let nm_utc = match tz.from_local_datetime(&nm_naive).single() {
                 Some(dt) => dt.with_timezone(&Utc),
-                None => match Local.from_local_datetime(&nm_naive).earliest() {
+                None => match tz.from_local_datetime(&nm_naive).earliest() {
                     Some(dt) => dt.with_timezone(&Utc),
                     None => {
                         error!("Could not resolve midnight {:?}", nm_naive);
@@ -135,7 +281,7 @@ pub fn split_at_midnight(
             trace!("  Added split: {:?}–{:?}", cur_start, nm_utc);
 
             cur_start = nm_utc;
-            cur_date = cur_start.with_timezone(&Local).date_naive();
+            cur_date = cur_start.with_timezone(tz).date_naive();
         }
 
         if e_utc > cur_start {
@@ -147,154 +293,1634 @@ pub fn split_at_midnight(
     out
 }
 
-pub fn filter_slots_by_time_of_day(
+/// Drop slots on the weekdays left out of `weekdays`, by their local date in `tz`.
+/// Expects slots already split at midnight, so a window running from Friday into
+/// Saturday keeps its Friday part.
+pub fn filter_slots_by_weekday_in<Tz: TimeZone>(
     slots: &[(DateTime<Utc>, DateTime<Utc>)],
-    start_hour: u32,
-    end_hour: u32,
+    weekdays: Weekdays,
+    tz: &Tz,
 ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
-    let mut filtered = Vec::new();
+    if weekdays.is_every_day() {
+        return slots.to_vec();
+    }
+    let kept: Vec<_> = slots
+        .iter()
+        .copied()
+        .filter(|(start, _)| weekdays.includes(start.with_timezone(tz).weekday()))
+        .collect();
+    debug!(
+        "Weekdays ({}) dropped {} slot(s)",
+        weekdays,
+        slots.len() - kept.len()
+    );
+    kept
+}
 
-    // Ensure valid hour range (basic check)
-    if start_hour >= end_hour || start_hour > 23 || end_hour > 23 {
+/// Trim slots to `start_hour`..`end_hour` on their own day, in `tz`. Expects slots
+/// already split at midnight; each is clamped to the hours of the day it starts on.
+pub fn filter_slots_by_time_of_day_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    start_hour: HourOfDay,
+    end_hour: HourOfDay,
+    tz: &Tz,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    // Both hours are 0-23 by construction; only their order needs checking
+    if start_hour >= end_hour {
         error!("Invalid start/end hour range: {}-{}", start_hour, end_hour);
         return slots.to_vec(); // Return original if range is invalid
     }
 
-    let start_time = NaiveTime::from_hms_opt(start_hour, 0, 0).unwrap();
-    let end_time = NaiveTime::from_hms_opt(end_hour, 0, 0).unwrap(); // End is exclusive usually, but let's treat HH:00 as inclusive start of hour
+    let mut filtered = Vec::new();
+    for &(slot_start, slot_end) in slots {
+        let date = slot_start.with_timezone(tz).date_naive();
+        let (Some(allowed_start), Some(allowed_end)) = (
+            hour_on(date, start_hour.get().into(), tz),
+            hour_on(date, end_hour.get().into(), tz),
+        ) else {
+            error!(
+                "Could not place hours {}-{} on {}",
+                start_hour, end_hour, date
+            );
+            continue;
+        };
+        let start = slot_start.max(allowed_start);
+        let end = slot_end.min(allowed_end);
+        if start < end {
+            trace!("Kept/Trimmed slot: {:?} - {:?}", start, end);
+            filtered.push((start, end));
+        } else {
+            trace!("Discarded slot: {:?} - {:?}", slot_start, slot_end);
+        }
+    }
 
-    for &(slot_start_utc, slot_end_utc) in slots {
-        // Convert slot times to local time
-        let slot_start_local = slot_start_utc.with_timezone(&Local);
-        let slot_end_local = slot_end_utc.with_timezone(&Local);
+    debug!(
+        "Filtered slots by time ({} to {}): {:?}",
+        start_hour, end_hour, filtered
+    );
+    filtered
+}
 
-        // Get the date part for comparison
-        let slot_date = slot_start_local.date_naive();
+/// `hour`:00 on `date` in `tz`, where 24 is the next midnight. When the clocks go
+/// back it's the first of the two; when they skip the hour, the moment they resume.
+fn hour_on<Tz: TimeZone>(date: NaiveDate, hour: u32, tz: &Tz) -> Option<DateTime<Utc>> {
+    minute_on(date, hour * 60, tz)
+}
 
-        // Define the valid time range for this specific date in Local time
-        let valid_start_local = Local
-            .from_local_datetime(&slot_date.and_time(start_time))
-            .single() // Handle potential DST ambiguity simply
-            .unwrap_or_else(|| slot_start_local); // Fallback
-        let valid_end_local = Local
-            .from_local_datetime(&slot_date.and_time(end_time))
-            .single()
-            .unwrap_or_else(|| slot_end_local); // Fallback
+/// Same as [`hour_on`], `minute` minutes after the start of `date`.
+fn minute_on<Tz: TimeZone>(date: NaiveDate, minute: u32, tz: &Tz) -> Option<DateTime<Utc>> {
+    if minute > MINUTES_PER_DAY {
+        return None;
+    }
+    let naive = date.and_time(NaiveTime::MIN) + Duration::minutes(minute.into());
+    tz.from_local_datetime(&naive)
+        .earliest()
+        // In a gap the clocks skipped ahead, nearly always by an hour
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|time| time.with_timezone(&Utc))
+}
 
-        // If the valid range spans midnight due to DST or timezone shifts, adjust (simple approach)
-        // This part might need refinement for complex timezone edge cases near midnight
-        let valid_end_local = if valid_end_local <= valid_start_local {
-            valid_end_local + Duration::days(1)
-        } else {
-            valid_end_local
-        };
+/// Drop slots that fall on today's date once the time in `tz` has reached `cutoff_hour`.
+/// `None` disables the cutoff. Expects slots already split at midnight.
+pub fn filter_today_after_cutoff_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    now: DateTime<Utc>,
+    cutoff_hour: Option<u32>,
+    tz: &Tz,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let Some(cutoff_hour) = cutoff_hour else {
+        return slots.to_vec();
+    };
 
-        // Calculate the intersection of the slot and the valid time range for that day
-        let effective_start_local = slot_start_local.max(valid_start_local);
-        let effective_end_local = slot_end_local.min(valid_end_local);
+    let now_local = now.with_timezone(tz);
+    if now_local.hour() < cutoff_hour {
+        return slots.to_vec();
+    }
 
-        // If there is a valid intersection (start < end)
-        if effective_start_local < effective_end_local {
-            // Convert back to UTC and add to filtered list
-            filtered.push((
-                effective_start_local.with_timezone(&Utc),
-                effective_end_local.with_timezone(&Utc),
-            ));
-            trace!(
-                "Kept/Trimmed slot: {:?} - {:?}",
-                effective_start_local,
-                effective_end_local
-            );
+    let today = now_local.date_naive();
+    let kept: Vec<_> = slots
+        .iter()
+        .copied()
+        .filter(|(start, _)| start.with_timezone(tz).date_naive() != today)
+        .collect();
+    debug!(
+        "Past today's cutoff ({}:00), dropped {} slot(s) on {}",
+        cutoff_hour,
+        slots.len() - kept.len(),
+        today
+    );
+    kept
+}
+
+/// Drop slots whose start falls on one of `dates` in `tz`. Expects slots already split at midnight.
+pub fn filter_blackout_dates_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    dates: &[NaiveDate],
+    tz: &Tz,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    if dates.is_empty() {
+        return slots.to_vec();
+    }
+    let kept: Vec<_> = slots
+        .iter()
+        .copied()
+        .filter(|(start, _)| !dates.contains(&start.with_timezone(tz).date_naive()))
+        .collect();
+    debug!(
+        "Blackout dates dropped {} slot(s)",
+        slots.len() - kept.len()
+    );
+    kept
+}
+
+const MINUTES_PER_DAY: u32 = 24 * 60;
+
+/// Part of a day, in minutes since midnight; `end` can be 1440, the next midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct DayRange {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl fmt::Display for DayRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time = |minute: u32| format!("{}:{:02}", minute / 60, minute % 60);
+        write!(f, "{}-{}", time(self.start), time(self.end))
+    }
+}
+
+/// When chats happen at all, week after week: time ranges for each weekday,
+/// Monday first. A weekday without ranges offers no slots.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeeklyRules {
+    pub days: [Vec<DayRange>; 7],
+}
+
+impl WeeklyRules {
+    pub fn ranges(&self, weekday: Weekday) -> &[DayRange] {
+        &self.days[weekday.num_days_from_monday() as usize]
+    }
+
+    /// Reads one day's ranges, e.g. `10-12, 15:30-17`. Overlapping ranges are
+    /// merged; blank text is a day off.
+    pub fn parse_day(text: &str) -> Result<Vec<DayRange>, String> {
+        let mut ranges = Vec::new();
+        for part in text
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+        {
+            let part: String = part.chars().filter(|c| !c.is_whitespace()).collect();
+            let (start, end) = part
+                .split_once(['-', '–'])
+                .ok_or_else(|| format!("\"{}\" isn't a range like 10-12", part))?;
+            let minute = |time: &str| -> Option<u32> {
+                let (hour, minute) = match time.split_once(':') {
+                    Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse().ok()?),
+                    None => (time.parse::<u32>().ok()?, 0),
+                };
+                let total = hour * 60 + minute;
+                (minute < 60 && total <= MINUTES_PER_DAY).then_some(total)
+            };
+            let range = match (minute(start), minute(end)) {
+                (Some(start), Some(end)) if start < end => DayRange { start, end },
+                (Some(_), Some(_)) => return Err(format!("{} ends before it starts", part)),
+                _ => return Err(format!("\"{}\" has a time that isn't 0:00-24:00", part)),
+            };
+            ranges.push(range);
+        }
+        ranges.sort();
+        let mut merged: Vec<DayRange> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+        Ok(merged)
+    }
+
+    /// "10:00-12:00, 15:00-17:00", the text [`parse_day`](Self::parse_day) reads.
+    pub fn format_day(ranges: &[DayRange]) -> String {
+        ranges
+            .iter()
+            .map(DayRange::to_string)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// "Tue 10:00-12:00, 15:00-17:00; Wed 10:00-12:00", skipping days off.
+    pub fn summary(&self) -> String {
+        let days: Vec<String> = (0..7)
+            .filter_map(|i| {
+                let weekday = Weekday::try_from(i as u8).ok()?;
+                let ranges = self.ranges(weekday);
+                (!ranges.is_empty()).then(|| format!("{} {}", weekday, Self::format_day(ranges)))
+            })
+            .collect();
+        if days.is_empty() {
+            "no days".to_string()
         } else {
-            trace!(
-                "Discarded slot: {:?} - {:?}",
-                slot_start_local,
-                slot_end_local
-            );
+            days.join("; ")
+        }
+    }
+
+    /// The parts of `windows` inside the rules, with weekdays and times in `tz`.
+    /// Windows may run past midnight; each day they touch is cut by its own
+    /// weekday's ranges.
+    pub fn intersect<Tz: TimeZone>(
+        &self,
+        windows: &[(DateTime<Utc>, DateTime<Utc>)],
+        tz: &Tz,
+    ) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut kept = Vec::new();
+        for &(start, end) in windows {
+            let first_day = start.with_timezone(tz).date_naive();
+            let last_day = end.with_timezone(tz).date_naive();
+            for day in first_day.iter_days().take_while(|day| *day <= last_day) {
+                for range in self.ranges(day.weekday()) {
+                    let (Some(open), Some(close)) = (
+                        minute_on(day, range.start, tz),
+                        minute_on(day, range.end, tz),
+                    ) else {
+                        continue;
+                    };
+                    let (start, end) = (start.max(open), end.min(close));
+                    if start < end {
+                        kept.push((start, end));
+                    }
+                }
+            }
+        }
+        debug!("Weekly rules kept {:?}", kept);
+        kept
+    }
+}
+
+/// Rules for picking which free windows get offered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SlotSelection {
+    /// Minimum time between two offered slots on the same day.
+    pub min_gap: Duration,
+    /// Most slots offered on a single day; `None` means no limit.
+    pub max_per_day: Option<usize>,
+}
+
+/// Pick slots from `candidates`, which are in preference order (best first).
+/// A candidate is skipped if its day is already full or it lies within
+/// `min_gap` of a slot already chosen that day, with days taken in `tz`.
+/// The result keeps the input order.
+pub fn select_slots_in<Tz: TimeZone>(
+    candidates: &[(DateTime<Utc>, DateTime<Utc>)],
+    selection: &SlotSelection,
+    tz: &Tz,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut chosen_by_day: BTreeMap<_, Vec<(DateTime<Utc>, DateTime<Utc>)>> = BTreeMap::new();
+    let mut selected = Vec::new();
+
+    for &(start, end) in candidates {
+        let day = start.with_timezone(tz).date_naive();
+        let chosen = chosen_by_day.entry(day).or_default();
+
+        if selection.max_per_day.is_some_and(|max| chosen.len() >= max) {
+            trace!("Skipping {:?}–{:?}: {} already full", start, end, day);
+            continue;
         }
 
-        // Note: This simple approach assumes slots don't span across the valid/invalid boundary *multiple* times
-        // within a single original slot (e.g., valid 9-12, slot is 8-13 -> keeps 9-12).
-        // Handling slots that start before start_hour AND end after end_hour on the *same day*
-        // correctly creates a single segment. A slot spanning midnight AND the filter times
-        // requires careful handling based on the `split_at_midnight` output.
+        // Distance between the two windows; zero if they overlap
+        let too_close = chosen.iter().any(|&(s, e)| {
+            let gap = if start >= e {
+                start - e
+            } else if s >= end {
+                s - end
+            } else {
+                Duration::zero()
+            };
+            gap < selection.min_gap
+        });
+        if too_close {
+            trace!("Skipping {:?}–{:?}: within minimum gap", start, end);
+            continue;
+        }
+
+        chosen.push((start, end));
+        selected.push((start, end));
     }
 
     debug!(
-        "Filtered slots by time ({} to {}): {:?}",
-        start_hour, end_hour, filtered
+        "Selected {} of {} slots ({:?})",
+        selected.len(),
+        candidates.len(),
+        selection
     );
-    filtered
+    selected
+}
+
+/// Times free in both `mine` and `theirs`. Inputs may be unsorted or overlap;
+/// the result is sorted and non-overlapping.
+pub fn intersect_windows(
+    mine: &[(DateTime<Utc>, DateTime<Utc>)],
+    theirs: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    let mut mine = mine.to_vec();
+    let mut theirs = theirs.to_vec();
+    mine.sort();
+    theirs.sort();
+
+    let mut overlap = Vec::new();
+    for &(start, end) in &mine {
+        for &(their_start, their_end) in &theirs {
+            if their_start >= end {
+                break;
+            }
+            let (s, e) = (start.max(their_start), end.min(their_end));
+            if s < e {
+                overlap.push((s, e));
+            }
+        }
+    }
+
+    // Overlapping inputs can produce overlapping pieces; merge them
+    overlap.sort();
+    let mut merged: Vec<(DateTime<Utc>, DateTime<Utc>)> = Vec::with_capacity(overlap.len());
+    for (start, end) in overlap {
+        match merged.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = (*last_end).max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    debug!("Common free windows: {:?}", merged);
+    merged
+}
+
+/// Full days in the lookahead window (the partial first and last days are skipped)
+/// that have no slot of at least `min_len`.
+pub fn unavailable_days(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+) -> Vec<NaiveDate> {
+    unavailable_days_in(slots, min_len, window_start, window_end, &Local)
+}
+
+/// Same as [`unavailable_days`], but in an explicit timezone.
+pub fn unavailable_days_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    window_start: DateTime<Utc>,
+    window_end: DateTime<Utc>,
+    tz: &Tz,
+) -> Vec<NaiveDate> {
+    let last_day = window_end.with_timezone(tz).date_naive();
+    let days_with_slots: HashSet<NaiveDate> = slots
+        .iter()
+        .filter(|(start, end)| *end - *start >= min_len)
+        .map(|(start, _)| start.with_timezone(tz).date_naive())
+        .collect();
+
+    let unavailable: Vec<NaiveDate> = window_start
+        .with_timezone(tz)
+        .date_naive()
+        .iter_days()
+        .skip(1)
+        .take_while(|day| *day < last_day)
+        .filter(|day| !days_with_slots.contains(day))
+        .collect();
+    debug!("Days without availability: {:?}", unavailable);
+    unavailable
+}
+
+/// Free minutes in each hour for the availability heat map. Row `i` is
+/// `first_day + i`; column `j` is the hour starting at `start_hour + j`.
+pub fn hourly_free_minutes(
+    windows: &[(DateTime<Utc>, DateTime<Utc>)],
+    first_day: NaiveDate,
+    days: usize,
+    start_hour: HourOfDay,
+    end_hour: HourOfDay,
+) -> Vec<Vec<u32>> {
+    hourly_free_minutes_in(windows, first_day, days, start_hour, end_hour, &Local)
+}
+
+/// Same as [`hourly_free_minutes`], but in an explicit timezone.
+pub fn hourly_free_minutes_in<Tz: TimeZone>(
+    windows: &[(DateTime<Utc>, DateTime<Utc>)],
+    first_day: NaiveDate,
+    days: usize,
+    start_hour: HourOfDay,
+    end_hour: HourOfDay,
+    tz: &Tz,
+) -> Vec<Vec<u32>> {
+    first_day
+        .iter_days()
+        .take(days)
+        .map(|day| {
+            (u32::from(start_hour)..u32::from(end_hour))
+                .map(|hour| {
+                    let Some(cell_start) = NaiveTime::from_hms_opt(hour, 0, 0)
+                        .and_then(|time| tz.from_local_datetime(&day.and_time(time)).earliest())
+                        .map(|dt| dt.with_timezone(&Utc))
+                    else {
+                        return 0; // Skipped by a DST change
+                    };
+                    let cell_end = cell_start + Duration::hours(1);
+                    windows
+                        .iter()
+                        .map(|&(start, end)| {
+                            (end.min(cell_end) - start.max(cell_start)).num_minutes()
+                        })
+                        .filter(|minutes| *minutes > 0)
+                        .sum::<i64>()
+                        .min(60) as u32
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Busy minutes per calendar in each heat map cell, busiest calendar first. Cells
+/// line up with [`hourly_free_minutes`].
+pub fn hourly_busy_by_calendar(
+    busy: &[CalendarBusy],
+    first_day: NaiveDate,
+    days: usize,
+    start_hour: HourOfDay,
+    end_hour: HourOfDay,
+) -> Vec<Vec<Vec<(String, u32)>>> {
+    hourly_busy_by_calendar_in(busy, first_day, days, start_hour, end_hour, &Local)
+}
+
+/// Same as [`hourly_busy_by_calendar`], but in an explicit timezone.
+pub fn hourly_busy_by_calendar_in<Tz: TimeZone>(
+    busy: &[CalendarBusy],
+    first_day: NaiveDate,
+    days: usize,
+    start_hour: HourOfDay,
+    end_hour: HourOfDay,
+    tz: &Tz,
+) -> Vec<Vec<Vec<(String, u32)>>> {
+    first_day
+        .iter_days()
+        .take(days)
+        .map(|day| {
+            (u32::from(start_hour)..u32::from(end_hour))
+                .map(|hour| {
+                    let Some(cell_start) = NaiveTime::from_hms_opt(hour, 0, 0)
+                        .and_then(|time| tz.from_local_datetime(&day.and_time(time)).earliest())
+                        .map(|dt| dt.with_timezone(&Utc))
+                    else {
+                        return Vec::new(); // Skipped by a DST change
+                    };
+                    let cell_end = cell_start + Duration::hours(1);
+                    let mut minutes: Vec<(String, u32)> = Vec::new();
+                    for (calendar_id, period) in busy {
+                        let (Some(start), Some(end)) = (period.start, period.end) else {
+                            continue;
+                        };
+                        let overlap = (end.min(cell_end) - start.max(cell_start)).num_minutes();
+                        if overlap <= 0 {
+                            continue;
+                        }
+                        match minutes.iter_mut().find(|(id, _)| id == calendar_id) {
+                            Some((_, total)) => *total = (*total + overlap as u32).min(60),
+                            None => minutes.push((calendar_id.clone(), overlap as u32)),
+                        }
+                    }
+                    minutes.sort_by_key(|&(_, total)| std::cmp::Reverse(total));
+                    minutes
+                })
+                .collect()
+        })
+        .collect()
 }
 
 /// Collapse contiguous same-day slots & format them into user-readable strings.
 pub fn summarize_slots(slots: &[(DateTime<Utc>, DateTime<Utc>)], min_len: Duration) -> Vec<String> {
+    summarize_slots_marking_unavailable(slots, min_len, &[], SlotOrder::EarliestFirst)
+}
+
+/// Which end of the lookahead a summary starts from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SlotOrder {
+    #[default]
+    EarliestFirst,
+    /// Days from last to first; the times within a day stay earliest-first
+    LatestFirst,
+}
+
+/// Like [`summarize_slots`], plus a "<day>: no availability" line, in date order,
+/// for each of `unavailable` that ends up with no slots.
+pub fn summarize_slots_marking_unavailable(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    unavailable: &[NaiveDate],
+    order: SlotOrder,
+) -> Vec<String> {
+    summarize_slots_marking_unavailable_in(
+        slots,
+        min_len,
+        unavailable,
+        order,
+        SlotFormat::default(),
+        &Local,
+    )
+}
+
+/// Same as [`summarize_slots_marking_unavailable`], but in an explicit timezone.
+pub fn summarize_slots_marking_unavailable_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    unavailable: &[NaiveDate],
+    order: SlotOrder,
+    format: SlotFormat,
+    tz: &Tz,
+) -> Vec<String>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    // One group of lines per day, so the days can be reversed as a whole
+    let mut days = Vec::new();
+    for (day, merged) in merge_slots_by_day_in(slots, min_len, unavailable, tz) {
+        let mut out = Vec::new();
+        if merged.is_empty() && unavailable.contains(&day) {
+            out.push(format!(
+                "{}{}",
+                format_day_as(day, format),
+                NO_AVAILABILITY_SUFFIX
+            ));
+        }
+        for (s_utc, e_utc) in merged {
+            out.push(format_slot_in(s_utc, e_utc, format, tz));
+        }
+        days.push(out);
+    }
+    if order == SlotOrder::LatestFirst {
+        days.reverse();
+    }
+    let out: Vec<String> = days.into_iter().flatten().collect();
+    debug!("Summarized slots ({}): {:?}", out.len(), out);
+    out
+}
+
+/// One local day and its merged free windows.
+pub type DayWindows = (NaiveDate, Vec<(DateTime<Utc>, DateTime<Utc>)>);
+
+/// Slots grouped by local date, earliest day first, with contiguous slots merged and
+/// windows shorter than `min_len` dropped. Days in `unavailable` are included even
+/// when they end up with no windows.
+pub fn merge_slots_by_day_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    unavailable: &[NaiveDate],
+    tz: &Tz,
+) -> Vec<DayWindows> {
     let mut by_day: BTreeMap<_, Vec<_>> = BTreeMap::new();
     for &(s, e) in slots {
         // group by local date
-        let d = s.with_timezone(&Local).date_naive();
+        let d = s.with_timezone(tz).date_naive();
         by_day.entry(d).or_default().push((s, e));
     }
+    for &day in unavailable {
+        by_day.entry(day).or_default();
+    }
     debug!("Grouped slots for {} days", by_day.len());
 
-    let mut out = Vec::new();
-    for (day, mut day_slots) in by_day {
-        day_slots.sort_by_key(|(s, _)| *s);
-
-        // merge contiguous & filter
-        let mut merged = Vec::new();
-        let mut iter = day_slots.into_iter();
-        if let Some((mut cs, mut ce)) = iter.next() {
-            for (s, e) in iter {
-                if s == ce {
-                    ce = e;
-                } else {
-                    if ce - cs >= min_len {
-                        merged.push((cs, ce));
+    by_day
+        .into_iter()
+        .map(|(day, mut day_slots)| {
+            day_slots.sort_by_key(|(s, _)| *s);
+
+            // merge contiguous & filter
+            let mut merged = Vec::new();
+            let mut iter = day_slots.into_iter();
+            if let Some((mut cs, mut ce)) = iter.next() {
+                for (s, e) in iter {
+                    if s == ce {
+                        ce = e;
+                    } else {
+                        if ce - cs >= min_len {
+                            merged.push((cs, ce));
+                        }
+                        cs = s;
+                        ce = e;
                     }
-                    cs = s;
-                    ce = e;
+                }
+                if ce - cs >= min_len {
+                    merged.push((cs, ce));
                 }
             }
-            if ce - cs >= min_len {
-                merged.push((cs, ce));
-            }
+            trace!("Day {:?} merged: {:?}", day, merged);
+            (day, merged)
+        })
+        .collect()
+}
+
+/// The window a summary line was made from, so a slot someone picked can be booked.
+/// A line with a note after it ("... (prefer phone)") still matches.
+pub fn window_for_line(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    line: &str,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    window_for_line_in(slots, min_len, line, SlotFormat::default(), &Local)
+}
+
+/// Same as [`window_for_line`], but with times formatted in an explicit timezone.
+pub fn window_for_line_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    line: &str,
+    format: SlotFormat,
+    tz: &Tz,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    merge_slots_by_day_in(slots, min_len, &[], tz)
+        .into_iter()
+        .flat_map(|(_, merged)| merged)
+        .find(|&(start, end)| {
+            let text = format_slot_in(start, end, format, tz);
+            line.strip_prefix(text.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(" ("))
+        })
+}
+
+/// The slots on one line, for a subject or a short message: "Tue 2–3p, Wed 10–11a,
+/// Fri 1–2p". Takes the first `max` (`0` means all) and ends with "+2 more" when
+/// some were left out. Later slots on the same day share its prefix ("Tue 2–3p,
+/// 4–5p"), and a slot running past midnight names the day it ends on.
+pub fn compact_slots_in<Tz: TimeZone>(
+    slots: &[(DateTime<Utc>, DateTime<Utc>)],
+    min_len: Duration,
+    order: SlotOrder,
+    max: usize,
+    tz: &Tz,
+) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let mut days = merge_slots_by_day_in(slots, min_len, &[], tz);
+    if order == SlotOrder::LatestFirst {
+        days.reverse();
+    }
+    let windows: Vec<(NaiveDate, DateTime<Utc>, DateTime<Utc>)> = days
+        .into_iter()
+        .flat_map(|(day, merged)| merged.into_iter().map(move |(s, e)| (day, s, e)))
+        .collect();
+    let shown = if max == 0 {
+        windows.len()
+    } else {
+        max.min(windows.len())
+    };
+    let mut parts = Vec::new();
+    let mut previous_day = None;
+    for &(day, start, end) in &windows[..shown] {
+        let range = compact_range(&start.with_timezone(tz), &end.with_timezone(tz));
+        if previous_day == Some(day) {
+            parts.push(range);
+        } else {
+            parts.push(format!("{} {}", day.format("%a"), range));
         }
-        trace!("Day {:?} merged: {:?}", day, merged);
+        previous_day = Some(day);
+    }
+    if shown < windows.len() {
+        parts.push(format!("+{} more", windows.len() - shown));
+    }
+    parts.join(", ")
+}
 
-        // format each window
-        for (s_utc, e_utc) in merged {
-            let s_loc = s_utc.with_timezone(&Local);
-            let e_loc = e_utc.with_timezone(&Local);
-
-            fn fmt_time(dt: DateTime<Local>) -> String {
-                if dt.minute() == 0 {
-                    dt.format("%-I%P").to_string()
-                } else {
-                    dt.format("%-I:%M%P").to_string()
-                }
+// "2–3p", "11a–12:30p", or "11p–Wed 1a" past midnight. The start's a/p is left
+// off when the end has the same one; ending at midnight stays on the same day.
+fn compact_range<Tz: TimeZone>(start: &DateTime<Tz>, end: &DateTime<Tz>) -> String
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let same_day = end.date_naive() == start.date_naive()
+        || (end.time() == NaiveTime::MIN
+            && end.date_naive().pred_opt() == Some(start.date_naive()));
+    if !same_day {
+        return format!(
+            "{}–{} {}",
+            compact_time(start, true),
+            end.format("%a"),
+            compact_time(end, true)
+        );
+    }
+    let shared_suffix = start.hour12().0 == end.hour12().0;
+    format!(
+        "{}–{}",
+        compact_time(start, !shared_suffix),
+        compact_time(end, true)
+    )
+}
+
+// "2p", "2:30p", or "2" / "2:30" without the a/p
+fn compact_time<Tz: TimeZone>(time: &DateTime<Tz>, with_suffix: bool) -> String {
+    let (pm, hour) = time.hour12();
+    let mut text = hour.to_string();
+    if time.minute() != 0 {
+        text.push_str(&format!(":{:02}", time.minute()));
+    }
+    if with_suffix {
+        text.push(if pm { 'p' } else { 'a' });
+    }
+    text
+}
+
+/// Keeps the first `max` summary lines for an email, ending with a "+N more on
+/// request" line when some were left out. `0` means no cap.
+pub fn cap_summary(lines: &[String], max: usize) -> Vec<String> {
+    if max == 0 || lines.len() <= max {
+        return lines.to_vec();
+    }
+    let mut capped = lines[..max].to_vec();
+    capped.push(format!("+{}{}", lines.len() - max, MORE_ON_REQUEST_SUFFIX));
+    capped
+}
+
+/// Adds each slot's note after it, e.g. "Tuesday Apr 8: 2pm–3pm (prefer phone)".
+/// Notes are keyed by the slot's line; blank notes and other lines are left alone.
+pub fn annotate_slots(lines: &[String], notes: &BTreeMap<String, String>) -> Vec<String> {
+    lines
+        .iter()
+        .map(|line| match notes.get(line).map(|note| note.trim()) {
+            Some(note) if !note.is_empty() && is_slot_line(line) => {
+                format!("{} ({})", line, note)
             }
+            _ => line.clone(),
+        })
+        .collect()
+}
 
-            let wk = s_loc.format("%A");
-            let date = s_loc.format("%b %-d");
-            let start = fmt_time(s_loc);
-            let end = fmt_time(e_loc);
-
-            if s_loc.date_naive() != e_loc.date_naive() {
-                out.push(format!(
-                    "{} {}: {}–{} {}: {}",
-                    wk,
-                    date,
-                    start,
-                    e_loc.format("%A"),
-                    e_loc.format("%b %-d"),
-                    end
-                ));
-            } else {
-                out.push(format!("{} {}: {}–{}", wk, date, start, end));
+/// Whether a summary line offers a time, as opposed to a fully booked day,
+/// the "+N more on request" line or the timezone label after the list.
+pub fn is_slot_line(line: &str) -> bool {
+    !(line.ends_with(NO_AVAILABILITY_SUFFIX)
+        || (line.starts_with('+') && line.ends_with(MORE_ON_REQUEST_SUFFIX))
+        || super::timezone::is_availability_label(line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    // Fixed UTC-5 so results don't depend on the machine's timezone
+    fn hour(hour: u8) -> HourOfDay {
+        HourOfDay::new(hour).unwrap()
+    }
+
+    fn tz() -> FixedOffset {
+        FixedOffset::west_opt(5 * 3600).unwrap()
+    }
+
+    fn local(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        tz().with_ymd_and_hms(2025, 4, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn slot(day: u32, start_hour: u32, end_hour: u32) -> (DateTime<Utc>, DateTime<Utc>) {
+        (local(day, start_hour, 0), local(day, end_hour, 0))
+    }
+
+    fn busy(start: DateTime<Utc>, end: DateTime<Utc>) -> TimePeriod {
+        TimePeriod {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
+
+    fn spans(periods: &[TimePeriod]) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        periods
+            .iter()
+            .map(|p| (p.start.unwrap(), p.end.unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn normalize_drops_exact_duplicates() {
+        let periods = vec![
+            busy(local(7, 13, 0), local(7, 14, 0)),
+            busy(local(7, 10, 0), local(7, 11, 0)),
+            busy(local(7, 13, 0), local(7, 14, 0)),
+        ];
+        let normalized = normalize_busy_periods(&periods, Duration::minutes(1));
+        assert_eq!(spans(&normalized), vec![slot(7, 10, 11), slot(7, 13, 14)]);
+    }
+
+    #[test]
+    fn normalize_merges_one_minute_offsets() {
+        let periods = vec![
+            // Same meeting from two calendars, one copy a minute late
+            busy(local(7, 10, 0), local(7, 11, 0)),
+            busy(local(7, 10, 1), local(7, 11, 1)),
+            // Starts a minute after the previous one ends
+            busy(local(7, 11, 2), local(7, 11, 30)),
+        ];
+        let normalized = normalize_busy_periods(&periods, Duration::minutes(1));
+        assert_eq!(
+            spans(&normalized),
+            vec![(local(7, 10, 0), local(7, 11, 30))]
+        );
+    }
+
+    #[test]
+    fn normalize_keeps_separate_periods_and_absorbs_nested_ones() {
+        let periods = vec![
+            busy(local(7, 9, 0), local(7, 12, 0)),
+            busy(local(7, 10, 0), local(7, 10, 30)), // nested
+            busy(local(7, 12, 5), local(7, 13, 0)),  // five minutes later: separate
+            TimePeriod {
+                start: Some(local(7, 15, 0)),
+                end: None,
+            },
+        ];
+        let normalized = normalize_busy_periods(&periods, Duration::minutes(1));
+        assert_eq!(
+            spans(&normalized),
+            vec![slot(7, 9, 12), (local(7, 12, 5), local(7, 13, 0))]
+        );
+    }
+
+    #[test]
+    fn busy_at_the_range_edges_blocks_only_the_inner_buffer() {
+        let buffer = Minutes(15);
+        // Starts exactly at the range start, ends exactly at the range end
+        let periods = vec![
+            busy(local(7, 9, 0), local(7, 10, 0)),
+            busy(local(7, 16, 0), local(7, 17, 0)),
+        ];
+        let windows = find_free_windows(
+            &periods,
+            local(7, 9, 0),
+            local(7, 17, 0),
+            buffer,
+            None,
+            false,
+        );
+        assert_eq!(windows, vec![(local(7, 10, 15), local(7, 15, 45))]);
+    }
+
+    #[test]
+    fn busy_outside_the_range_only_reaches_in_with_its_buffer() {
+        let buffer = Minutes(15);
+        let periods = vec![
+            busy(local(7, 7, 0), local(7, 8, 55)), // Buffer reaches past 9am
+            busy(local(7, 17, 5), local(7, 18, 0)), // Buffer reaches back before 5pm
+            busy(local(7, 20, 0), local(7, 21, 0)), // Nowhere near
+        ];
+        let windows = find_free_windows(
+            &periods,
+            local(7, 9, 0),
+            local(7, 17, 0),
+            buffer,
+            None,
+            false,
+        );
+        assert_eq!(windows, vec![(local(7, 9, 10), local(7, 16, 50))]);
+
+        // Entirely after the range: the window still stops at the range end
+        let later = vec![busy(local(8, 9, 0), local(8, 10, 0))];
+        let windows =
+            find_free_windows(&later, local(7, 9, 0), local(7, 17, 0), buffer, None, false);
+        assert_eq!(windows, vec![slot(7, 9, 17)]);
+    }
+
+    // Busy periods as a FreeBusy response gave them for one Tuesday (UTC-5): two
+    // instant reminders, meetings that start as the previous ones end, and one
+    // period whose end came back before its start.
+    fn reminder_day() -> Vec<TimePeriod> {
+        [
+            ("2025-04-08T14:00:00Z", "2025-04-08T14:00:00Z"), // 9:00 reminder
+            ("2025-04-08T15:00:00Z", "2025-04-08T15:30:00Z"),
+            ("2025-04-08T15:30:00Z", "2025-04-08T16:00:00Z"), // Starts as the last one ends
+            ("2025-04-08T16:00:00Z", "2025-04-08T16:00:00Z"), // Reminder at that same end
+            ("2025-04-08T18:00:00Z", "2025-04-08T17:59:00Z"), // Ends before it starts
+            ("2025-04-08T20:00:00Z", "2025-04-08T21:00:00Z"),
+        ]
+        .iter()
+        .map(|(start, end)| TimePeriod {
+            start: Some(DateTime::parse_from_rfc3339(start).unwrap().to_utc()),
+            end: Some(DateTime::parse_from_rfc3339(end).unwrap().to_utc()),
+        })
+        .collect()
+    }
+
+    #[test]
+    fn reminders_and_touching_periods_leave_no_empty_windows() {
+        let day = (local(8, 9, 0), local(8, 17, 0));
+        let normalized = normalize_busy_periods(&reminder_day(), Duration::minutes(1));
+        assert_eq!(
+            spans(&normalized),
+            vec![
+                (local(8, 9, 0), local(8, 9, 0)),
+                slot(8, 10, 11),
+                (local(8, 13, 0), local(8, 13, 0)),
+                slot(8, 15, 16),
+            ]
+        );
+
+        // Without a buffer the points block nothing and split no window
+        let windows = find_free_windows(&normalized, day.0, day.1, Minutes(0), None, false);
+        assert_eq!(
+            windows,
+            vec![slot(8, 9, 10), slot(8, 11, 15), slot(8, 16, 17)]
+        );
+
+        // With one, each point is buffered like a meeting
+        let windows = find_free_windows(&normalized, day.0, day.1, Minutes(15), None, false);
+        assert_eq!(
+            windows,
+            vec![
+                (local(8, 9, 15), local(8, 9, 45)),
+                (local(8, 11, 15), local(8, 12, 45)),
+                (local(8, 13, 15), local(8, 14, 45)),
+                (local(8, 16, 15), day.1),
+            ]
+        );
+        for buffer in [0, 15, 60, 240] {
+            for pad in [false, true] {
+                let windows =
+                    find_free_windows(&reminder_day(), day.0, day.1, Minutes(buffer), None, pad);
+                assert!(
+                    windows.iter().all(|(start, end)| start < end),
+                    "{:?}",
+                    windows
+                );
             }
         }
     }
-    debug!("Summarized slots ({}): {:?}", out.len(), out);
-    out
+
+    #[test]
+    fn padded_edges_keep_the_buffer_clear_at_both_ends() {
+        let buffer = Minutes(15);
+        let windows = find_free_windows(&[], local(7, 9, 0), local(7, 17, 0), buffer, None, true);
+        assert_eq!(windows, vec![(local(7, 9, 15), local(7, 16, 45))]);
+
+        // A range shorter than two buffers has nothing left
+        let windows = find_free_windows(&[], local(7, 9, 0), local(7, 9, 20), buffer, None, true);
+        assert!(windows.is_empty());
+    }
+
+    #[test]
+    fn before_and_after_buffers_apply_separately() {
+        let buffer = Buffer {
+            before: Minutes(5),
+            after: Minutes(20),
+        };
+        let periods = vec![busy(local(7, 11, 0), local(7, 12, 0))];
+        let windows = find_free_windows(
+            &periods,
+            local(7, 9, 0),
+            local(7, 17, 0),
+            buffer,
+            None,
+            false,
+        );
+        assert_eq!(
+            windows,
+            vec![
+                (local(7, 9, 0), local(7, 10, 55)),
+                (local(7, 12, 20), local(7, 17, 0))
+            ]
+        );
+
+        // Padding: after-buffer from "now", before-buffer up to the range end
+        let windows = find_free_windows(&[], local(7, 9, 0), local(7, 17, 0), buffer, None, true);
+        assert_eq!(windows, vec![(local(7, 9, 20), local(7, 16, 55))]);
+
+        // Plain minutes are the same on both sides
+        assert_eq!(
+            find_free_windows(
+                &periods,
+                local(7, 9, 0),
+                local(7, 17, 0),
+                Minutes(15),
+                None,
+                false
+            ),
+            find_free_windows(
+                &periods,
+                local(7, 9, 0),
+                local(7, 17, 0),
+                Buffer::even(Minutes(15)),
+                None,
+                false
+            )
+        );
+    }
+
+    #[test]
+    fn only_long_meetings_get_the_buffer() {
+        let buffer = Minutes(15);
+        let longer_than = Some(Minutes(30));
+        let day = (local(7, 9, 0), local(7, 17, 0));
+        let cases = [
+            (
+                "short meeting has no buffer",
+                vec![busy(local(7, 10, 0), local(7, 10, 10))],
+                vec![(day.0, local(7, 10, 0)), (local(7, 10, 10), day.1)],
+            ),
+            (
+                "long meeting keeps it",
+                vec![busy(local(7, 10, 0), local(7, 12, 0))],
+                vec![(day.0, local(7, 9, 45)), (local(7, 12, 15), day.1)],
+            ),
+            (
+                "exactly the threshold isn't longer",
+                vec![busy(local(7, 10, 0), local(7, 10, 30))],
+                vec![(day.0, local(7, 10, 0)), (local(7, 10, 30), day.1)],
+            ),
+            (
+                "standup overlapping a workshop's start",
+                vec![
+                    busy(local(7, 10, 0), local(7, 10, 10)),
+                    busy(local(7, 10, 5), local(7, 12, 0)),
+                ],
+                vec![(day.0, local(7, 9, 45)), (local(7, 12, 15), day.1)],
+            ),
+            (
+                "standup inside a workshop",
+                vec![
+                    busy(local(7, 10, 0), local(7, 12, 0)),
+                    busy(local(7, 11, 0), local(7, 11, 10)),
+                ],
+                vec![(day.0, local(7, 9, 45)), (local(7, 12, 15), day.1)],
+            ),
+            (
+                "back-to-back short meetings add up",
+                vec![
+                    busy(local(7, 10, 0), local(7, 10, 20)),
+                    busy(local(7, 10, 20), local(7, 10, 40)),
+                ],
+                vec![(day.0, local(7, 9, 45)), (local(7, 10, 55), day.1)],
+            ),
+            (
+                "long meeting's buffer reaches back past a short one",
+                vec![
+                    busy(local(7, 10, 0), local(7, 10, 10)),
+                    busy(local(7, 10, 20), local(7, 12, 0)),
+                ],
+                vec![(day.0, local(7, 10, 0)), (local(7, 12, 15), day.1)],
+            ),
+        ];
+        for (name, periods, expected) in cases {
+            let windows = find_free_windows(&periods, day.0, day.1, buffer, longer_than, false);
+            assert_eq!(windows, expected, "{}", name);
+        }
+    }
+
+    // Tue/Wed/Thu 10-12 and 15-17, nothing else
+    fn coffee_hours() -> WeeklyRules {
+        let ranges = WeeklyRules::parse_day("10-12, 15-17").unwrap();
+        let mut rules = WeeklyRules::default();
+        for weekday in [Weekday::Tue, Weekday::Wed, Weekday::Thu] {
+            rules.days[weekday.num_days_from_monday() as usize] = ranges.clone();
+        }
+        rules
+    }
+
+    #[test]
+    fn weekly_rules_parse_and_merge_ranges() {
+        let range = |start, end| DayRange { start, end };
+        assert_eq!(
+            WeeklyRules::parse_day("15-17, 10 - 12,9:30-10:30"),
+            Ok(vec![range(570, 720), range(900, 1020)])
+        );
+        assert_eq!(WeeklyRules::parse_day("  "), Ok(vec![]));
+        assert_eq!(WeeklyRules::parse_day("22-24"), Ok(vec![range(1320, 1440)]));
+        for bad in ["10", "12-10", "10-25", "9:75-10", "ten-12"] {
+            assert!(WeeklyRules::parse_day(bad).is_err(), "{}", bad);
+        }
+        assert_eq!(
+            WeeklyRules::format_day(&[range(570, 720), range(1320, 1440)]),
+            "9:30-12:00, 22:00-24:00"
+        );
+        assert_eq!(
+            coffee_hours().summary(),
+            "Tue 10:00-12:00, 15:00-17:00; Wed 10:00-12:00, 15:00-17:00; Thu 10:00-12:00, 15:00-17:00"
+        );
+        assert_eq!(WeeklyRules::default().summary(), "no days");
+    }
+
+    #[test]
+    fn weekly_rules_cut_windows_to_each_days_ranges() {
+        let rules = coffee_hours();
+        let cases = [
+            (
+                "two ranges in one free day",
+                vec![slot(8, 9, 18)], // Tuesday
+                vec![slot(8, 10, 12), slot(8, 15, 17)],
+            ),
+            (
+                "partly inside a range",
+                vec![(local(9, 11, 30), local(9, 15, 30))], // Wednesday
+                vec![
+                    (local(9, 11, 30), local(9, 12, 0)),
+                    (local(9, 15, 0), local(9, 15, 30)),
+                ],
+            ),
+            ("day without ranges", vec![slot(7, 9, 18)], vec![]), // Monday
+            ("between ranges", vec![slot(10, 12, 15)], vec![]),   // Thursday
+            (
+                "one window over several days",
+                vec![(local(7, 12, 0), local(9, 11, 0))], // Monday noon to Wednesday 11am
+                vec![
+                    slot(8, 10, 12),
+                    slot(8, 15, 17),
+                    (local(9, 10, 0), local(9, 11, 0)),
+                ],
+            ),
+        ];
+        for (name, windows, expected) in cases {
+            assert_eq!(rules.intersect(&windows, &tz()), expected, "{}", name);
+        }
+    }
+
+    #[test]
+    fn weekly_rules_meet_at_midnight() {
+        // Late Monday and early Tuesday, as one window and as split ones
+        let mut rules = WeeklyRules::default();
+        rules.days[0] = WeeklyRules::parse_day("22-24").unwrap();
+        rules.days[1] = WeeklyRules::parse_day("0-1").unwrap();
+        let overnight = (local(7, 21, 0), local(8, 3, 0));
+        let expected = vec![
+            (local(7, 22, 0), local(8, 0, 0)),
+            (local(8, 0, 0), local(8, 1, 0)),
+        ];
+        assert_eq!(rules.intersect(&[overnight], &tz()), expected);
+        let split = split_at_midnight_in(&[overnight], &tz());
+        assert_eq!(rules.intersect(&split, &tz()), expected);
+    }
+
+    #[test]
+    fn hour_filter_clamps_each_slot_to_its_day() {
+        let whole_day = (local(7, 0, 0), local(8, 0, 0));
+        let cases = [
+            ("fully inside", slot(7, 10, 12), Some(slot(7, 10, 12))),
+            ("fully before", slot(7, 6, 8), None),
+            ("fully after", slot(7, 18, 20), None),
+            (
+                "overlapping the start",
+                slot(7, 7, 11),
+                Some(slot(7, 9, 11)),
+            ),
+            (
+                "overlapping the end",
+                slot(7, 15, 19),
+                Some(slot(7, 15, 17)),
+            ),
+            ("covering the whole day", whole_day, Some(slot(7, 9, 17))),
+            ("ending at the start hour", slot(7, 8, 9), None),
+            ("starting at the end hour", slot(7, 17, 19), None),
+        ];
+        for (case, window, expected) in cases {
+            let filtered = filter_slots_by_time_of_day_in(&[window], hour(9), hour(17), &tz());
+            assert_eq!(filtered.first().copied(), expected, "{}", case);
+            assert!(filtered.len() <= 1, "{}", case);
+        }
+    }
+
+    #[test]
+    fn hours_land_on_the_right_side_of_clock_changes() {
+        use chrono_tz::America::New_York;
+        // 2am doesn't exist on March 9, 2025: the clocks go from 2:00 to 3:00
+        let spring = NaiveDate::from_ymd_opt(2025, 3, 9).unwrap();
+        let three_am = New_York.with_ymd_and_hms(2025, 3, 9, 3, 0, 0).unwrap();
+        assert_eq!(hour_on(spring, 2, &New_York), Some(three_am.to_utc()));
+        // 1am happens twice on November 2; the day's hours start at the first
+        let fall = NaiveDate::from_ymd_opt(2025, 11, 2).unwrap();
+        let first_one_am = New_York.with_ymd_and_hms(2025, 11, 2, 1, 0, 0).earliest();
+        assert_eq!(
+            hour_on(fall, 1, &New_York),
+            first_one_am.map(|time| time.to_utc())
+        );
+        assert_eq!(
+            hour_on(fall, 24, &New_York),
+            Some(
+                New_York
+                    .with_ymd_and_hms(2025, 11, 3, 0, 0, 0)
+                    .unwrap()
+                    .to_utc()
+            )
+        );
+
+        // A whole spring-forward day, filtered to 2-17, starts when the clocks resume
+        let midnight = |day| {
+            New_York
+                .with_ymd_and_hms(2025, 3, day, 0, 0, 0)
+                .unwrap()
+                .to_utc()
+        };
+        let filtered = filter_slots_by_time_of_day_in(
+            &[(midnight(9), midnight(10))],
+            hour(2),
+            hour(17),
+            &New_York,
+        );
+        let five_pm = New_York.with_ymd_and_hms(2025, 3, 9, 17, 0, 0).unwrap();
+        assert_eq!(filtered, [(three_am.to_utc(), five_pm.to_utc())]);
+    }
+
+    #[test]
+    fn today_cutoff_keeps_today_before_cutoff() {
+        let slots = vec![slot(7, 18, 19), slot(8, 9, 10)];
+        let kept = filter_today_after_cutoff_in(&slots, local(7, 16, 59), Some(17), &tz());
+        assert_eq!(kept, slots);
+    }
+
+    #[test]
+    fn today_cutoff_drops_today_at_cutoff() {
+        let slots = vec![slot(7, 18, 19), slot(8, 9, 10)];
+        let kept = filter_today_after_cutoff_in(&slots, local(7, 17, 0), Some(17), &tz());
+        assert_eq!(kept, vec![slot(8, 9, 10)]);
+    }
+
+    #[test]
+    fn today_cutoff_disabled_keeps_everything() {
+        let slots = vec![slot(7, 22, 23)];
+        let kept = filter_today_after_cutoff_in(&slots, local(7, 23, 30), None, &tz());
+        assert_eq!(kept, slots);
+    }
+
+    #[test]
+    fn unreadable_calendars_fail_instead_of_looking_free() {
+        let ids = ["primary".to_string(), "team@group.example".to_string()];
+        let mut calendars = HashMap::new();
+        calendars.insert(
+            "primary".to_string(),
+            FreeBusyCalendar {
+                busy: Some(vec![busy(local(8, 10, 0), local(8, 11, 0))]),
+                ..Default::default()
+            },
+        );
+        calendars.insert(
+            "team@group.example".to_string(),
+            FreeBusyCalendar {
+                busy: Some(Vec::new()),
+                errors: Some(vec![google_calendar3::api::Error {
+                    reason: Some("notFound".to_string()),
+                    ..Default::default()
+                }]),
+            },
+        );
+        assert_eq!(
+            busy_from_response(&calendars, &ids).unwrap_err(),
+            "Calendar 'team@group.example' can't be read: notFound"
+        );
+        // Without the error it's just a free calendar
+        calendars.get_mut("team@group.example").unwrap().errors = None;
+        let found = busy_from_response(&calendars, &ids).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "primary");
+    }
+
+    #[test]
+    fn blackout_dates_drop_slots_on_that_local_day() {
+        let slots = vec![slot(8, 9, 10), slot(9, 9, 10), slot(10, 9, 10)];
+        let blackout = [NaiveDate::from_ymd_opt(2025, 4, 9).unwrap()];
+        assert_eq!(
+            filter_blackout_dates_in(&slots, &blackout, &tz()),
+            vec![slot(8, 9, 10), slot(10, 9, 10)]
+        );
+        assert_eq!(filter_blackout_dates_in(&slots, &[], &tz()), slots);
+    }
+
+    #[test]
+    fn left_out_weekdays_drop_their_part_of_a_window() {
+        let mut weekdays = Weekdays::default();
+        weekdays.set(Weekday::Sat, false);
+        weekdays.set(Weekday::Sun, false);
+        // Friday Apr 11, 6pm, into Saturday morning: only Friday's part stays
+        let friday_evening = split_at_midnight_in(&[(local(11, 18, 0), local(12, 10, 0))], &tz());
+        assert_eq!(
+            filter_slots_by_weekday_in(&friday_evening, weekdays, &tz()),
+            vec![(local(11, 18, 0), local(12, 0, 0))]
+        );
+        // All of it on Sunday Apr 13
+        let sunday = [slot(13, 9, 17)];
+        assert!(filter_slots_by_weekday_in(&sunday, weekdays, &tz()).is_empty());
+        assert_eq!(
+            filter_slots_by_weekday_in(&sunday, Weekdays::default(), &tz()),
+            sunday
+        );
+    }
+
+    /// 30-minute candidates every 45 minutes from 9:00, i.e. separated by 15-minute meetings.
+    fn adjacent_candidates(day: u32, count: u32) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        (0..count)
+            .map(|i| {
+                let start = local(day, 9, 0) + Duration::minutes(45 * i as i64);
+                (start, start + Duration::minutes(30))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn select_default_keeps_everything() {
+        let candidates = adjacent_candidates(7, 8);
+        let selected = select_slots_in(&candidates, &SlotSelection::default(), &tz());
+        assert_eq!(selected, candidates);
+    }
+
+    #[test]
+    fn select_skips_adjacent_candidates_within_gap() {
+        let candidates = adjacent_candidates(7, 8);
+        let selection = SlotSelection {
+            min_gap: Duration::minutes(30),
+            max_per_day: None,
+        };
+        let selected = select_slots_in(&candidates, &selection, &tz());
+        // 9:00, 10:30, 12:00, 13:30
+        let expected: Vec<_> = candidates.iter().copied().step_by(2).collect();
+        assert_eq!(selected, expected);
+    }
+
+    #[test]
+    fn select_gap_equal_to_spacing_keeps_all() {
+        let candidates = adjacent_candidates(7, 4);
+        let selection = SlotSelection {
+            min_gap: Duration::minutes(15),
+            max_per_day: None,
+        };
+        assert_eq!(select_slots_in(&candidates, &selection, &tz()), candidates);
+    }
+
+    #[test]
+    fn select_applies_max_per_day_after_gap() {
+        let mut candidates = adjacent_candidates(7, 8);
+        candidates.extend(adjacent_candidates(8, 8));
+        let selection = SlotSelection {
+            min_gap: Duration::minutes(30),
+            max_per_day: Some(2),
+        };
+        let selected = select_slots_in(&candidates, &selection, &tz());
+        assert_eq!(
+            selected,
+            vec![candidates[0], candidates[2], candidates[8], candidates[10]]
+        );
+    }
+
+    #[test]
+    fn select_gap_follows_preference_order() {
+        let candidates = adjacent_candidates(7, 5);
+        // Prefer the 10:30 slot; its neighbours at 9:45 and 11:15 are then too close
+        let preferred = vec![
+            candidates[2],
+            candidates[0],
+            candidates[1],
+            candidates[3],
+            candidates[4],
+        ];
+        let selection = SlotSelection {
+            min_gap: Duration::minutes(30),
+            max_per_day: None,
+        };
+        let selected = select_slots_in(&preferred, &selection, &tz());
+        assert_eq!(selected, vec![candidates[2], candidates[0], candidates[4]]);
+    }
+
+    #[test]
+    fn intersect_keeps_only_shared_time() {
+        let mine = vec![slot(7, 9, 12), slot(7, 14, 17), slot(8, 9, 10)];
+        let theirs = vec![slot(7, 11, 15), slot(9, 9, 17)];
+        assert_eq!(
+            intersect_windows(&mine, &theirs),
+            vec![slot(7, 11, 12), slot(7, 14, 15)]
+        );
+    }
+
+    #[test]
+    fn intersect_handles_unsorted_and_overlapping_input() {
+        let mine = vec![slot(7, 13, 17), slot(7, 9, 11)];
+        let theirs = vec![
+            slot(7, 10, 14),
+            slot(7, 9, 12),
+            (local(7, 16, 30), local(7, 18, 0)),
+        ];
+        assert_eq!(
+            intersect_windows(&mine, &theirs),
+            vec![
+                slot(7, 9, 11),
+                slot(7, 13, 14),
+                (local(7, 16, 30), local(7, 17, 0))
+            ]
+        );
+        assert!(intersect_windows(&mine, &[]).is_empty());
+    }
+
+    #[test]
+    fn unavailable_days_skip_partial_ends_and_short_slots() {
+        let date = |day| NaiveDate::from_ymd_opt(2025, 4, day).unwrap();
+        let slots = vec![
+            slot(8, 9, 10),
+            (local(9, 9, 0), local(9, 9, 15)), // Too short to count
+            slot(11, 14, 16),
+        ];
+        // Window runs from Monday the 7th 3pm to Saturday the 12th 10am
+        let days = unavailable_days_in(
+            &slots,
+            Duration::minutes(30),
+            local(7, 15, 0),
+            local(12, 10, 0),
+            &tz(),
+        );
+        assert_eq!(days, vec![date(9), date(10)]);
+    }
+
+    #[test]
+    fn summary_marks_unavailable_days_in_date_order() {
+        let day = NaiveDate::from_ymd_opt(2025, 4, 9).unwrap();
+        assert_eq!(
+            summarize_slots_marking_unavailable(
+                &[],
+                Duration::minutes(30),
+                &[day],
+                SlotOrder::EarliestFirst
+            ),
+            vec!["Wednesday Apr 9: no availability".to_string()]
+        );
+        assert!(summarize_slots(&[], Duration::minutes(30)).is_empty());
+    }
+
+    #[test]
+    fn latest_first_reverses_days_but_not_times_within_a_day() {
+        let slots = vec![slot(7, 9, 10), slot(7, 14, 15), slot(9, 11, 12)];
+        let unavailable = [NaiveDate::from_ymd_opt(2025, 4, 8).unwrap()];
+        let lines = summarize_slots_marking_unavailable_in(
+            &slots,
+            Duration::minutes(30),
+            &unavailable,
+            SlotOrder::LatestFirst,
+            SlotFormat::default(),
+            &tz(),
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "Wednesday Apr 9: 11am–12pm",
+                "Tuesday Apr 8: no availability",
+                "Monday Apr 7: 9am–10am",
+                "Monday Apr 7: 2pm–3pm",
+            ]
+        );
+    }
+
+    #[test]
+    fn hourly_free_minutes_shades_partial_hours() {
+        let windows = vec![
+            (local(7, 9, 30), local(7, 11, 0)),
+            (local(8, 10, 0), local(8, 10, 20)),
+        ];
+        let first_day = NaiveDate::from_ymd_opt(2025, 4, 7).unwrap();
+        let grid = hourly_free_minutes_in(&windows, first_day, 3, hour(9), hour(12), &tz());
+        assert_eq!(grid, vec![vec![30, 60, 0], vec![0, 20, 0], vec![0, 0, 0]]);
+    }
+
+    #[test]
+    fn hourly_busy_is_split_by_calendar() {
+        let busy: Vec<CalendarBusy> = vec![
+            ("me".into(), busy(local(7, 9, 0), local(7, 9, 20))),
+            ("work".into(), busy(local(7, 9, 15), local(7, 10, 30))),
+            ("work".into(), busy(local(8, 10, 0), local(8, 10, 10))),
+        ];
+        let first_day = NaiveDate::from_ymd_opt(2025, 4, 7).unwrap();
+        let grid = hourly_busy_by_calendar_in(&busy, first_day, 2, hour(9), hour(11), &tz());
+        let cell = |pairs: &[(&str, u32)]| -> Vec<(String, u32)> {
+            pairs.iter().map(|&(id, m)| (id.to_string(), m)).collect()
+        };
+        assert_eq!(
+            grid,
+            vec![
+                vec![cell(&[("work", 45), ("me", 20)]), cell(&[("work", 30)])],
+                vec![cell(&[]), cell(&[("work", 10)])],
+            ]
+        );
+    }
+
+    #[test]
+    fn summary_lines_lead_back_to_their_window() {
+        let slots = vec![slot(7, 9, 10), slot(8, 14, 15)];
+        let lines = summarize_slots_marking_unavailable_in(
+            &slots,
+            Duration::minutes(30),
+            &[],
+            SlotOrder::EarliestFirst,
+            SlotFormat::default(),
+            &tz(),
+        );
+        let find = |line: &str| {
+            window_for_line_in(
+                &slots,
+                Duration::minutes(30),
+                line,
+                SlotFormat::default(),
+                &tz(),
+            )
+        };
+        assert_eq!(find(&lines[1]), Some(slot(8, 14, 15)));
+        assert_eq!(
+            find(&format!("{} (prefer phone)", lines[0])),
+            Some(slot(7, 9, 10))
+        );
+        assert_eq!(find("Friday Apr 11: 1pm–2pm"), None);
+    }
+
+    #[test]
+    fn notes_follow_their_slot() {
+        let lines: Vec<String> = ["Tuesday Apr 8: 2pm–3pm", "Wednesday Apr 9: 9am–10am"]
+            .map(String::from)
+            .to_vec();
+        let notes = BTreeMap::from([
+            (
+                "Tuesday Apr 8: 2pm–3pm".to_string(),
+                " prefer phone ".to_string(),
+            ),
+            ("Wednesday Apr 9: 9am–10am".to_string(), "  ".to_string()),
+            ("Friday Apr 11: 1pm–2pm".to_string(), "gone".to_string()),
+        ]);
+        assert_eq!(
+            annotate_slots(&lines, &notes),
+            [
+                "Tuesday Apr 8: 2pm–3pm (prefer phone)",
+                "Wednesday Apr 9: 9am–10am"
+            ]
+        );
+    }
+
+    #[test]
+    fn only_offered_times_are_slot_lines() {
+        assert!(is_slot_line("Tuesday Apr 8: 2pm–3pm"));
+        assert!(!is_slot_line("Wednesday Apr 9: no availability"));
+        assert!(!is_slot_line("+3 more on request"));
+        assert!(!is_slot_line("(all times ET)"));
+    }
+
+    fn compact(slots: &[(DateTime<Utc>, DateTime<Utc>)], max: usize) -> String {
+        compact_slots_in(
+            slots,
+            Duration::minutes(30),
+            SlotOrder::EarliestFirst,
+            max,
+            &tz(),
+        )
+    }
+
+    #[test]
+    fn compact_slots_abbreviate_days_and_times() {
+        // Apr 8 2025 is a Tuesday
+        let slots = vec![slot(8, 14, 15), slot(9, 10, 11), slot(11, 13, 14)];
+        assert_eq!(compact(&slots, 3), "Tue 2–3p, Wed 10–11a, Fri 1–2p");
+        let slots = vec![
+            (local(8, 9, 30), local(8, 10, 15)),
+            (local(8, 11, 0), local(8, 12, 30)),
+            (local(9, 12, 0), local(9, 13, 0)),
+        ];
+        assert_eq!(compact(&slots, 0), "Tue 9:30–10:15a, 11a–12:30p, Wed 12–1p");
+    }
+
+    #[test]
+    fn compact_slots_count_what_was_left_out() {
+        let slots: Vec<_> = (7..=11).map(|day| slot(day, 9, 10)).collect();
+        assert_eq!(
+            compact(&slots, 3),
+            "Mon 9–10a, Tue 9–10a, Wed 9–10a, +2 more"
+        );
+        assert_eq!(compact(&slots[..2], 3), "Mon 9–10a, Tue 9–10a");
+        assert_eq!(compact(&[], 3), "");
+        let latest_first = compact_slots_in(
+            &slots,
+            Duration::minutes(30),
+            SlotOrder::LatestFirst,
+            2,
+            &tz(),
+        );
+        assert_eq!(latest_first, "Fri 9–10a, Thu 9–10a, +3 more");
+    }
+
+    #[test]
+    fn compact_slots_past_midnight_name_the_next_day() {
+        let slots = vec![
+            (local(8, 23, 0), local(9, 1, 0)),
+            (local(10, 22, 0), local(11, 0, 0)),
+        ];
+        assert_eq!(compact(&slots, 0), "Tue 11p–Wed 1a, Thu 10p–12a");
+    }
+
+    #[test]
+    fn cap_summary_notes_what_was_left_out() {
+        let lines: Vec<String> = (1..=5).map(|n| format!("slot {}", n)).collect();
+        assert_eq!(
+            cap_summary(&lines, 2),
+            vec!["slot 1", "slot 2", "+3 more on request"]
+        );
+        assert_eq!(cap_summary(&lines, 5), lines);
+        assert_eq!(cap_summary(&lines, 0), lines);
+    }
 }
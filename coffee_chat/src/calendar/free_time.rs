@@ -0,0 +1,274 @@
+// src/calendar/free_time.rs
+// How much of the working hours in a range are free, as a number to glance at
+// next to the slots. Working hours are the daily hours, cut to the weekly rules
+// when there are some, on every day that isn't a blackout date; busy time counts
+// with its buffer, the way slots see it.
+
+use super::{free_busy, SlotFilters, BUSY_MERGE_EPSILON_MINUTES};
+use chrono::{DateTime, Duration, Local, TimeZone, Utc};
+use google_calendar3::api::TimePeriod;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FreeTime {
+    pub working_minutes: i64,
+    pub free_minutes: i64,
+}
+
+impl FreeTime {
+    /// Whole percent of the working time that's free; `None` when there's none.
+    pub fn percent_free(&self) -> Option<i64> {
+        (self.working_minutes > 0)
+            .then(|| (self.free_minutes * 100 + self.working_minutes / 2) / self.working_minutes)
+    }
+
+    /// "62% free (41 of 66 working hours)"
+    pub fn summary(&self) -> String {
+        match self.percent_free() {
+            Some(percent) => format!(
+                "{}% free ({} of {} working hours)",
+                percent,
+                hours(self.free_minutes),
+                hours(self.working_minutes)
+            ),
+            None => "No working hours in range".to_string(),
+        }
+    }
+}
+
+// 41 or 41.5, without a trailing ".0"
+fn hours(minutes: i64) -> String {
+    if minutes % 60 == 0 {
+        (minutes / 60).to_string()
+    } else {
+        format!("{:.1}", minutes as f64 / 60.0)
+    }
+}
+
+/// Free and working time between `time_min` and `time_max`, with days and hours in
+/// local time.
+pub fn free_time(
+    busy: &[TimePeriod],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    filters: &SlotFilters,
+) -> FreeTime {
+    free_time_in(busy, time_min, time_max, filters, &Local)
+}
+
+/// Same as [`free_time`], in an explicit timezone. The today cutoff and slot
+/// spacing are left out: they change what's offered, not what's free.
+pub fn free_time_in<Tz: TimeZone>(
+    busy: &[TimePeriod],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    filters: &SlotFilters,
+    tz: &Tz,
+) -> FreeTime {
+    let working_minutes = within_working_hours(&[(time_min, time_max)], filters, tz);
+    let busy =
+        free_busy::normalize_busy_periods(busy, Duration::minutes(BUSY_MERGE_EPSILON_MINUTES));
+    let free = free_busy::find_free_windows(
+        &busy,
+        time_min,
+        time_max,
+        filters.buffer,
+        filters.buffer_longer_than,
+        filters.pad_range_edges,
+    );
+    FreeTime {
+        working_minutes,
+        free_minutes: within_working_hours(&free, filters, tz),
+    }
+}
+
+// Minutes of `windows` inside the daily hours and weekly rules, skipping left-out
+// weekdays and blackout dates
+fn within_working_hours<Tz: TimeZone>(
+    windows: &[(DateTime<Utc>, DateTime<Utc>)],
+    filters: &SlotFilters,
+    tz: &Tz,
+) -> i64 {
+    let windows = free_busy::split_at_midnight_in(windows, tz);
+    let windows = free_busy::filter_slots_by_weekday_in(&windows, filters.weekdays, tz);
+    let windows = free_busy::filter_slots_by_time_of_day_in(
+        &windows,
+        filters.start_hour,
+        filters.end_hour,
+        tz,
+    );
+    let windows = match &filters.weekly_rules {
+        Some(rules) => rules.intersect(&windows, tz),
+        None => windows,
+    };
+    free_busy::filter_blackout_dates_in(&windows, &filters.blackout_dates, tz)
+        .iter()
+        .map(|(start, end)| (*end - *start).num_minutes())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::units::{HourOfDay, Minutes};
+    use chrono::NaiveDate;
+    use chrono_tz::America::New_York;
+
+    // Monday, April 7 2025, in New York
+    fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        New_York
+            .with_ymd_and_hms(2025, 4, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn busy(start: DateTime<Utc>, end: DateTime<Utc>) -> TimePeriod {
+        TimePeriod {
+            start: Some(start),
+            end: Some(end),
+        }
+    }
+
+    fn nine_to_five(buffer: u32) -> SlotFilters {
+        SlotFilters {
+            buffer: Minutes(buffer).into(),
+            start_hour: HourOfDay::new(9).unwrap(),
+            end_hour: HourOfDay::new(17).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn synthetic_weeks_add_up() {
+        let week = (at(7, 0, 0), at(14, 0, 0)); // 7 days of 8 hours
+        let blackout = SlotFilters {
+            blackout_dates: vec![NaiveDate::from_ymd_opt(2025, 4, 9).unwrap()],
+            ..nine_to_five(0)
+        };
+        let cases: [(&str, Vec<TimePeriod>, SlotFilters, FreeTime); 6] = [
+            (
+                "empty calendar",
+                vec![],
+                nine_to_five(0),
+                FreeTime {
+                    working_minutes: 56 * 60,
+                    free_minutes: 56 * 60,
+                },
+            ),
+            (
+                "two meetings",
+                vec![
+                    busy(at(7, 10, 0), at(7, 11, 0)),
+                    busy(at(8, 14, 0), at(8, 16, 0)),
+                ],
+                nine_to_five(0),
+                FreeTime {
+                    working_minutes: 56 * 60,
+                    free_minutes: 53 * 60,
+                },
+            ),
+            (
+                "buffer counts as busy",
+                vec![busy(at(7, 10, 0), at(7, 11, 0))],
+                nine_to_five(15),
+                FreeTime {
+                    working_minutes: 56 * 60,
+                    free_minutes: 56 * 60 - 90,
+                },
+            ),
+            (
+                "busy outside the hours doesn't count",
+                vec![
+                    busy(at(7, 6, 0), at(7, 9, 0)),
+                    busy(at(8, 17, 0), at(8, 22, 0)),
+                ],
+                nine_to_five(0),
+                FreeTime {
+                    working_minutes: 56 * 60,
+                    free_minutes: 56 * 60,
+                },
+            ),
+            (
+                "blackout day is neither working nor free",
+                vec![
+                    busy(at(9, 9, 0), at(9, 17, 0)),
+                    busy(at(10, 9, 0), at(10, 13, 0)),
+                ],
+                blackout,
+                FreeTime {
+                    working_minutes: 48 * 60,
+                    free_minutes: 44 * 60,
+                },
+            ),
+            (
+                "overlapping busy periods count once",
+                vec![
+                    busy(at(7, 9, 0), at(7, 12, 0)),
+                    busy(at(7, 11, 0), at(7, 13, 0)),
+                ],
+                nine_to_five(0),
+                FreeTime {
+                    working_minutes: 56 * 60,
+                    free_minutes: 52 * 60,
+                },
+            ),
+        ];
+        for (name, busy, filters, expected) in cases {
+            assert_eq!(
+                free_time_in(&busy, week.0, week.1, &filters, &New_York),
+                expected,
+                "{}",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn weekly_rules_narrow_the_working_hours() {
+        let mut rules = free_busy::WeeklyRules::default();
+        let ranges = free_busy::WeeklyRules::parse_day("8-12, 15-18").unwrap();
+        rules.days[1] = ranges.clone(); // Tuesday
+        rules.days[3] = ranges; // Thursday
+        let filters = SlotFilters {
+            weekly_rules: Some(rules),
+            ..nine_to_five(0)
+        };
+        // 9-12 and 15-17 inside 9-5: 5 hours on each of two days
+        let free = free_time_in(
+            &[busy(at(8, 11, 0), at(8, 16, 0))],
+            at(7, 0, 0),
+            at(14, 0, 0),
+            &filters,
+            &New_York,
+        );
+        assert_eq!(free.working_minutes, 10 * 60);
+        assert_eq!(free.free_minutes, 8 * 60); // 11-12 and 15-16 on Tuesday are busy
+    }
+
+    #[test]
+    fn the_range_starting_mid_day_only_counts_what_is_left() {
+        // From 1pm Monday to midnight: 4 working hours, 1 of them busy
+        let free = free_time_in(
+            &[busy(at(7, 15, 0), at(7, 16, 0))],
+            at(7, 13, 0),
+            at(8, 0, 0),
+            &nine_to_five(0),
+            &New_York,
+        );
+        assert_eq!(free.working_minutes, 4 * 60);
+        assert_eq!(free.free_minutes, 3 * 60);
+        assert_eq!(free.summary(), "75% free (3 of 4 working hours)");
+    }
+
+    #[test]
+    fn summary_rounds_and_handles_no_working_time() {
+        let free = FreeTime {
+            working_minutes: 66 * 60,
+            free_minutes: 41 * 60 + 30,
+        };
+        assert_eq!(free.percent_free(), Some(63));
+        assert_eq!(free.summary(), "63% free (41.5 of 66 working hours)");
+        assert_eq!(FreeTime::default().percent_free(), None);
+        assert_eq!(FreeTime::default().summary(), "No working hours in range");
+    }
+}
@@ -1,25 +1,195 @@
 // src/calendar/mod.rs
+pub mod availability_page;
+pub mod booking;
+pub mod calendars;
+pub mod connect_error;
+pub mod demo;
+pub mod diagnose;
+pub mod format;
 pub mod free_busy;
+pub mod free_time;
+pub mod offered;
+pub mod pasted;
+#[cfg(test)]
+mod pipeline_tests;
+pub mod proxy;
+pub mod timezone;
+pub mod token_refresh;
+pub mod units;
 
-use crate::app::TokioConnector; // Import the type alias from app.rs
-use chrono::{DateTime, Duration, Utc};
+use crate::app::{AppAuthenticator, AppCalendarHub, TokioConnector}; // Type aliases from the app module
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use diagnose::Stage;
 use google_calendar3::{api::TimePeriod, CalendarHub}; // Remove Connector import
 use log::{debug, info}; // <-- Add this
 use std::error::Error;
 use std::io::{Error as IoError, ErrorKind};
+use units::{Buffer, HourOfDay, Minutes, Weekdays};
 
-// Change the function signature to use the concrete Hub type
+/// How far ahead slots are looked for, until the user changes it.
+pub const DEFAULT_LOOKAHEAD_DAYS: u32 = 14;
+
+/// The longest lookahead a fetch accepts; past that the FreeBusy query gets slow
+/// and nobody is offered a slot two months out anyway.
+pub const MAX_LOOKAHEAD_DAYS: u32 = 60;
+
+/// Free time shorter than this isn't offered.
+pub const MIN_SLOT_MINUTES: i64 = 30;
+
+/// Default cap on slot lines in one email.
+pub const DEFAULT_MAX_SLOTS_IN_EMAIL: u32 = 12;
+
+/// Slots in `{{ availabilities_compact }}` before it ends with "+N more".
+pub const COMPACT_SLOTS: usize = 3;
+
+/// A busy period and the id of the calendar it came from.
+pub type CalendarBusy = (String, TimePeriod);
+
+// Busy periods this close together are treated as one (duplicate invites, copies)
+const BUSY_MERGE_EPSILON_MINUTES: i64 = 1;
+
+/// User-facing text for a slot fetch failure; proxy problems get their own wording.
+pub fn describe_fetch_error(error: &(dyn Error + 'static)) -> String {
+    match proxy::find_proxy_error(error) {
+        Some(proxy_error) => format!("{} (check HTTPS_PROXY / ALL_PROXY)", proxy_error),
+        None => error.to_string(),
+    }
+}
+
+/// Filters applied to free time before it's offered as slots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlotFilters {
+    pub buffer: Buffer,
+    /// Only buffer meetings longer than this; `None` buffers every one
+    pub buffer_longer_than: Option<Minutes>,
+    pub start_hour: HourOfDay,
+    pub end_hour: HourOfDay,
+    /// Skip today's slots once it's this late
+    pub today_cutoff_hour: Option<u32>,
+    /// Min gap / max per day among offered slots
+    pub selection: free_busy::SlotSelection,
+    /// Also keep the buffer clear after the range start ("now") and before its end
+    pub pad_range_edges: bool,
+    /// Days with no slots at all, whatever the calendar says
+    pub blackout_dates: Vec<NaiveDate>,
+    /// Weekdays and times chats happen at all, on top of the daily hours
+    pub weekly_rules: Option<free_busy::WeeklyRules>,
+    /// Weekdays slots are offered on at all
+    pub weekdays: Weekdays,
+}
+
+/// A signed-in Google calendar. The authenticator is kept next to the hub so an
+/// expired token can be refreshed without reconnecting.
+#[derive(Clone)]
+pub struct GoogleCalendar {
+    pub hub: AppCalendarHub,
+    pub auth: AppAuthenticator,
+}
+
+impl GoogleCalendar {
+    /// Gets a new access token for `scope` even if the cached one hasn't expired yet.
+    pub async fn refresh_token(&self, scope: &str) -> Result<(), String> {
+        self.auth
+            .force_refreshed_token(&[scope])
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Where busy time comes from: the user's Google calendar, or made-up meetings in demo mode.
+#[derive(Clone)]
+pub enum BusySource {
+    Google(GoogleCalendar),
+    Demo { seed: u64 },
+}
+
+impl BusySource {
+    pub fn is_demo(&self) -> bool {
+        matches!(self, BusySource::Demo { .. })
+    }
+
+    /// Busy periods between `time_min` and `time_max`, each with its calendar.
+    /// `calendar_ids` picks the calendars to read; none reads the primary one.
+    pub async fn busy_by_calendar(
+        &self,
+        time_min: DateTime<Utc>,
+        time_max: DateTime<Utc>,
+        calendar_ids: &[String],
+    ) -> Result<Vec<CalendarBusy>, Box<dyn Error>> {
+        match self {
+            BusySource::Google(google) => {
+                token_refresh::retry_after_refresh(
+                    || fetch_busy(&google.hub, calendar_ids, time_min, time_max),
+                    || google.refresh_token(token_refresh::CALENDAR_READONLY_SCOPE),
+                )
+                .await
+            }
+            BusySource::Demo { seed } => Ok(demo::demo_busy_by_calendar(
+                time_min,
+                time_max,
+                *seed,
+                calendar_ids,
+            )),
+        }
+    }
+}
+
+/// Busy periods without their calendars, for the window math.
+pub fn periods(busy: &[CalendarBusy]) -> Vec<TimePeriod> {
+    busy.iter().map(|(_, period)| period.clone()).collect()
+}
+
+/// What a slot fetch found over the lookahead.
+pub struct AvailableSlots {
+    pub windows: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    pub free_time: free_time::FreeTime, // Of the working hours in the lookahead
+    pub busy: Vec<CalendarBusy>,        // What the windows were cut from
+}
+
+/// Free slots from now to `lookahead_days` ahead, free on all of `calendar_ids`
+/// (the primary calendar when there are none). The FreeBusy query covers the same
+/// range, so busy time past it is never fetched.
 pub async fn find_available_slots(
+    source: &BusySource,
+    filters: &SlotFilters,
+    lookahead_days: u32,
+    calendar_ids: &[String],
+) -> Result<AvailableSlots, Box<dyn Error>> {
+    let now = Utc::now();
+    let time_min = now;
+    let time_max = now + Duration::days(lookahead_days as i64);
+
+    let by_calendar = source
+        .busy_by_calendar(time_min, time_max, calendar_ids)
+        .await?;
+    let busy = periods(&by_calendar);
+    let windows = slots_from_busy(&busy, time_min, time_max, filters, &mut |stage, count| {
+        info!("{}: {}", stage.label(), count)
+    });
+    let free = free_time::free_time(&busy, time_min, time_max, filters);
+    info!("Lookahead window: {}", free.summary());
+
+    // Summarization happens in the app module (and headless), not here
+    Ok(AvailableSlots {
+        windows,
+        free_time: free,
+        busy: by_calendar,
+    })
+}
+
+/// Busy periods on `calendar_ids`, or the user's primary calendar when there are
+/// none, between `time_min` and `time_max`.
+pub async fn fetch_busy(
     hub: &CalendarHub<TokioConnector>,
-    buffer_minutes: u32, // New: Buffer parameter
-    start_hour: u32,     // New: Start hour
-    end_hour: u32,       // New: End hour
-) -> Result<Vec<(DateTime<Utc>, DateTime<Utc>)>, Box<dyn Error>> {
-    info!("Fetching primary calendar ID...");
-    // ... (find primary_id logic remains the same) ...
-    let primary_id = {
+    calendar_ids: &[String],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+) -> Result<Vec<CalendarBusy>, Box<dyn Error>> {
+    let calendar_ids = if calendar_ids.is_empty() {
+        info!("Fetching primary calendar ID...");
         let (_, list) = hub.calendar_list().list().doit().await?;
-        let id = list
+        let primary_id = list
             .items
             .and_then(|items| {
                 items
@@ -27,64 +197,94 @@ pub async fn find_available_slots(
                     .find(|c| c.primary.unwrap_or(false))
                     .and_then(|c| c.id)
             })
-            .ok_or_else(|| {
-                // Use a more specific error type if desired, but Box<dyn Error> handles it
-                Box::<dyn Error>::from("Primary calendar not found")
-            })?; // Propagate error if not found
-        id // This is the String value returned by the block
+            .ok_or_else(|| Box::<dyn Error>::from("Primary calendar not found"))?;
+        info!("Found primary calendar ID: {}", primary_id);
+        vec![primary_id]
+    } else {
+        calendar_ids.to_vec()
     };
-    info!("Found primary calendar ID: {}", primary_id); // Now primary_id is String
-
-    let now = Utc::now();
-    let time_min = now;
-    let time_max = now + Duration::days(14); // Look ahead 14 days
 
     info!(
-        "Fetching busy slots for calendar '{}' between {} and {}",
-        primary_id, time_min, time_max
+        "Fetching busy slots for {} between {} and {}",
+        calendar_ids.join(", "),
+        time_min,
+        time_max
     );
-    let busy: Vec<TimePeriod> =
-        free_busy::get_busy_slots(hub, &primary_id, time_min, time_max).await?;
-    info!("Found {} busy periods.", busy.len());
+    free_busy::get_busy_slots(hub, &calendar_ids, time_min, time_max).await
+}
 
-    info!(
-        "Calculating free windows with {} minute buffer...",
-        buffer_minutes
-    );
-    // Convert minutes to Duration
-    let buffer = Duration::minutes(buffer_minutes as i64);
-    // Pass the buffer to find_free_windows
-    let raw_windows = free_busy::find_free_windows(&busy, time_min, time_max, buffer);
-    info!("Found {} raw free windows.", raw_windows.len());
-
-    info!("Splitting windows at midnight...");
-    let split_windows = free_busy::split_at_midnight(&raw_windows);
-    info!(
-        "Found {} free windows after splitting.",
-        split_windows.len()
-    );
+/// Turns busy periods into offerable windows, calling `on_stage` with how many
+/// windows are left after each step. `time_min` doubles as "now" for the today cutoff.
+pub fn slots_from_busy(
+    busy: &[TimePeriod],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    filters: &SlotFilters,
+    on_stage: &mut dyn FnMut(Stage, usize),
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    slots_from_busy_in(busy, time_min, time_max, filters, on_stage, &Local)
+}
 
-    // --- NEW: Filter by time of day ---
-    info!(
-        "Filtering windows between hours {} and {}...",
-        start_hour, end_hour
+/// Same as [`slots_from_busy`], but with days and hours in an explicit timezone.
+pub fn slots_from_busy_in<Tz: TimeZone>(
+    busy: &[TimePeriod],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    filters: &SlotFilters,
+    on_stage: &mut dyn FnMut(Stage, usize),
+    tz: &Tz,
+) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+    on_stage(Stage::BusyPeriods, busy.len());
+    let busy =
+        free_busy::normalize_busy_periods(busy, Duration::minutes(BUSY_MERGE_EPSILON_MINUTES));
+
+    let windows = free_busy::find_free_windows(
+        &busy,
+        time_min,
+        time_max,
+        filters.buffer,
+        filters.buffer_longer_than,
+        filters.pad_range_edges,
     );
-    let filtered_windows =
-        free_busy::filter_slots_by_time_of_day(&split_windows, start_hour, end_hour);
-    info!(
-        "Found {} windows after time filtering.",
-        filtered_windows.len()
+    on_stage(Stage::FreeWindows, windows.len());
+
+    let windows = free_busy::split_at_midnight_in(&windows, tz);
+    on_stage(Stage::SplitAtMidnight, windows.len());
+
+    let windows = if filters.weekdays.is_every_day() {
+        windows
+    } else {
+        let windows = free_busy::filter_slots_by_weekday_in(&windows, filters.weekdays, tz);
+        on_stage(Stage::Weekdays, windows.len());
+        windows
+    };
+
+    let windows = free_busy::filter_slots_by_time_of_day_in(
+        &windows,
+        filters.start_hour,
+        filters.end_hour,
+        tz,
     );
-    // --- End Filtering ---
+    on_stage(Stage::DailyHours, windows.len());
+
+    let windows = match &filters.weekly_rules {
+        Some(rules) => {
+            let windows = rules.intersect(&windows, tz);
+            on_stage(Stage::WeeklyRules, windows.len());
+            windows
+        }
+        None => windows,
+    };
 
-    // Summarization will use the filtered slots, but it's called by the App after this returns
-    // Ok(filtered_windows) // Return the filtered but unsummarized slots
+    let windows =
+        free_busy::filter_today_after_cutoff_in(&windows, time_min, filters.today_cutoff_hour, tz);
+    on_stage(Stage::TodayCutoff, windows.len());
 
-    // If you prefer find_available_slots to return the *summarized* strings directly:
-    // let min_summarize_duration = Duration::minutes(30); // Or make configurable
-    // let summarized = free_busy::summarize_slots(&filtered_windows, min_summarize_duration);
-    // Ok(summarized) // <-- Change return type to Result<Vec<String>, Box<dyn Error>> if doing this
+    let windows = free_busy::filter_blackout_dates_in(&windows, &filters.blackout_dates, tz);
+    on_stage(Stage::BlackoutDates, windows.len());
 
-    // Let's return the filtered slots for now, summarization happens in app.rs
-    Ok(filtered_windows)
+    // Candidates are chronological for now, so earlier slots win
+    let windows = free_busy::select_slots_in(&windows, &filters.selection, tz);
+    on_stage(Stage::Spacing, windows.len());
+    windows
 }
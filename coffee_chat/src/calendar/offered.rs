@@ -0,0 +1,214 @@
+// src/calendar/offered.rs
+// The slots an email offers, kept as windows rather than finished lines so each
+// recipient's copy can be written in their language when it's rendered.
+
+use super::format::SlotFormat;
+use super::free_busy::{self, SlotOrder};
+use super::{COMPACT_SLOTS, MIN_SLOT_MINUTES};
+use chrono::{DateTime, Duration, Local, NaiveDate, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Everything that goes into the `availabilities` lines, before formatting.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OfferedSlots {
+    pub windows: Vec<(DateTime<Utc>, DateTime<Utc>)>,
+    pub unavailable: Vec<NaiveDate>, // Fully booked days to list, if marked
+    pub order: SlotOrder,
+    pub notes: BTreeMap<String, String>, // Keyed by the English line, as on screen
+    pub max_in_email: usize,             // 0 = no cap
+    pub label: Option<String>,           // Timezone line after the list
+}
+
+impl OfferedSlots {
+    /// The lines in `format`, in the machine's timezone.
+    pub fn lines(&self, format: SlotFormat) -> Vec<String> {
+        self.lines_in(format, &Local)
+    }
+
+    /// Same as [`OfferedSlots::lines`], but in an explicit timezone.
+    pub fn lines_in<Tz: TimeZone>(&self, format: SlotFormat, tz: &Tz) -> Vec<String>
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        let summarize = |format| {
+            free_busy::summarize_slots_marking_unavailable_in(
+                &self.windows,
+                Duration::minutes(MIN_SLOT_MINUTES),
+                &self.unavailable,
+                self.order,
+                format,
+                tz,
+            )
+        };
+        // Both come from the same windows in the same order, so line i is the same slot
+        let english = summarize(SlotFormat::default());
+        let written = if format == SlotFormat::default() {
+            english.clone()
+        } else {
+            summarize(format)
+        };
+        let annotated: Vec<String> = english
+            .iter()
+            .zip(written)
+            .map(
+                |(key, line)| match self.notes.get(key).map(|note| note.trim()) {
+                    Some(note) if !note.is_empty() && free_busy::is_slot_line(key) => {
+                        format!("{} ({})", line, note)
+                    }
+                    _ => line,
+                },
+            )
+            .collect();
+        let mut lines = free_busy::cap_summary(&annotated, self.max_in_email);
+        if !lines.is_empty() {
+            lines.extend(self.label.clone());
+        }
+        lines
+    }
+
+    /// The windows behind the slot lines, in the same order, in the machine's timezone.
+    pub fn slot_windows(&self) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        self.slot_windows_in(&Local)
+    }
+
+    /// Same as [`OfferedSlots::slot_windows`], but in an explicit timezone.
+    pub fn slot_windows_in<Tz: TimeZone>(&self, tz: &Tz) -> Vec<(DateTime<Utc>, DateTime<Utc>)> {
+        let mut days = free_busy::merge_slots_by_day_in(
+            &self.windows,
+            Duration::minutes(MIN_SLOT_MINUTES),
+            &[],
+            tz,
+        );
+        if self.order == SlotOrder::LatestFirst {
+            days.reverse();
+        }
+        days.into_iter().flat_map(|(_, merged)| merged).collect()
+    }
+
+    /// `{{ availabilities_compact }}`: the first few slots on one line, in the
+    /// machine's timezone. Always English, since it's meant for subject lines.
+    pub fn compact(&self) -> String {
+        self.compact_in(&Local)
+    }
+
+    /// Same as [`OfferedSlots::compact`], but in an explicit timezone.
+    pub fn compact_in<Tz: TimeZone>(&self, tz: &Tz) -> String
+    where
+        Tz::Offset: std::fmt::Display,
+    {
+        free_busy::compact_slots_in(
+            &self.windows,
+            Duration::minutes(MIN_SLOT_MINUTES),
+            self.order,
+            COMPACT_SLOTS,
+            tz,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Paris;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Paris
+            .with_ymd_and_hms(2025, 4, day, hour, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn offered() -> OfferedSlots {
+        let mut notes = BTreeMap::new();
+        notes.insert(
+            "Tuesday Apr 8: 2pm–3pm".to_string(),
+            "par téléphone".to_string(),
+        );
+        OfferedSlots {
+            windows: vec![
+                (at(8, 14), at(8, 15)),
+                (at(9, 10), at(9, 12)),
+                (at(10, 9), at(10, 10)),
+            ],
+            notes,
+            max_in_email: 2,
+            label: Some("(all times CEST)".to_string()),
+            ..OfferedSlots::default()
+        }
+    }
+
+    #[test]
+    fn default_format_matches_the_lines_on_screen() {
+        assert_eq!(
+            offered().lines_in(SlotFormat::default(), &Paris),
+            vec![
+                "Tuesday Apr 8: 2pm–3pm (par téléphone)",
+                "Wednesday Apr 9: 10am–12pm",
+                "+1 more on request",
+                "(all times CEST)",
+            ]
+        );
+    }
+
+    #[test]
+    fn french_and_german_recipients_get_their_own_lines() {
+        let french = SlotFormat::for_language("fr").unwrap();
+        assert_eq!(
+            offered().lines_in(french, &Paris)[..2],
+            [
+                "mardi 8 avril: 14:00–15:00 (par téléphone)",
+                "mercredi 9 avril: 10:00–12:00",
+            ]
+        );
+        let german = SlotFormat::for_language("de").unwrap();
+        assert_eq!(
+            offered().lines_in(german, &Paris)[..2],
+            [
+                "Dienstag, 8. April: 14:00–15:00 (par téléphone)",
+                "Mittwoch, 9. April: 10:00–12:00",
+            ]
+        );
+    }
+
+    #[test]
+    fn slot_windows_follow_the_lines() {
+        let slots = OfferedSlots {
+            order: SlotOrder::LatestFirst,
+            ..offered()
+        };
+        assert_eq!(
+            slots.slot_windows_in(&Paris),
+            [
+                (at(10, 9), at(10, 10)),
+                (at(9, 10), at(9, 12)),
+                (at(8, 14), at(8, 15))
+            ]
+        );
+        assert!(slots.lines_in(SlotFormat::default(), &Paris)[0].starts_with("Thursday Apr 10"));
+    }
+
+    #[test]
+    fn compact_line_ignores_the_email_cap() {
+        let mut slots = offered();
+        slots.windows.push((at(11, 16), at(11, 18)));
+        // max_in_email is 2, but the compact line has its own limit
+        assert_eq!(
+            slots.compact_in(&Paris),
+            "Tue 2–3p, Wed 10a–12p, Thu 9–10a, +1 more"
+        );
+    }
+
+    #[test]
+    fn booked_days_are_written_in_the_language_too() {
+        let slots = OfferedSlots {
+            unavailable: vec![NaiveDate::from_ymd_opt(2025, 4, 11).unwrap()],
+            max_in_email: 0,
+            label: None,
+            ..offered()
+        };
+        let lines = slots.lines_in(SlotFormat::for_language("fr").unwrap(), &Paris);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[3], "vendredi 11 avril: no availability");
+    }
+}
@@ -0,0 +1,163 @@
+// src/calendar/pasted.rs
+// Parses availability someone else sent, one `day HH-HH` line per window.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, TimeZone, Utc, Weekday};
+use thiserror::Error;
+
+type Window = (DateTime<Utc>, DateTime<Utc>);
+
+/// A pasted line that couldn't be understood.
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error("Line {line}: {reason} (\"{text}\")")]
+pub struct PastedAvailabilityError {
+    /// 1-based line number in the pasted text.
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+/// Parse lines like `Mon 14-16`, `tuesday 9:30-11`, `tomorrow 13-15` or
+/// `2025-04-22 10-12` into windows. Weekdays mean the next such day on or
+/// after today. Blank lines and lines starting with `#` are ignored.
+pub fn parse_pasted_availability(
+    text: &str,
+    now: DateTime<Utc>,
+) -> Result<Vec<Window>, PastedAvailabilityError> {
+    parse_pasted_availability_in(text, now, &Local)
+}
+
+/// Same as [`parse_pasted_availability`], but reading times in an explicit timezone.
+pub fn parse_pasted_availability_in<Tz: TimeZone>(
+    text: &str,
+    now: DateTime<Utc>,
+    tz: &Tz,
+) -> Result<Vec<Window>, PastedAvailabilityError> {
+    let today = now.with_timezone(tz).date_naive();
+    let mut windows = Vec::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |reason: &str| PastedAvailabilityError {
+            line: index + 1,
+            text: line.to_string(),
+            reason: reason.to_string(),
+        };
+
+        let (day, range) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| error("expected a day followed by a time range, e.g. \"Mon 14-16\""))?;
+        let date = parse_day(day, today).ok_or_else(|| error("unrecognized day"))?;
+
+        // Allow "14 - 16" and en dashes as well as "14-16"
+        let range: String = range.chars().filter(|c| !c.is_whitespace()).collect();
+        let (start, end) = range
+            .split_once(['-', '–'])
+            .ok_or_else(|| error("expected a time range like 14-16"))?;
+        let start = parse_time(date, start).ok_or_else(|| error("invalid start time"))?;
+        let end = parse_time(date, end).ok_or_else(|| error("invalid end time"))?;
+        if end <= start {
+            return Err(error("end time must be after start time"));
+        }
+
+        let to_utc = |naive: chrono::NaiveDateTime| {
+            tz.from_local_datetime(&naive)
+                .earliest()
+                .map(|dt| dt.with_timezone(&Utc))
+                .ok_or_else(|| error("time doesn't exist in the local timezone"))
+        };
+        windows.push((to_utc(start)?, to_utc(end)?));
+    }
+
+    Ok(windows)
+}
+
+fn parse_day(day: &str, today: NaiveDate) -> Option<NaiveDate> {
+    let day = day.trim_end_matches([',', ':']).to_ascii_lowercase();
+    match day.as_str() {
+        "today" => return Some(today),
+        "tomorrow" => return today.succ_opt(),
+        _ => {}
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(&day, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let weekday: Weekday = day.parse().ok()?;
+    let days_ahead =
+        (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today.checked_add_signed(Duration::days(days_ahead as i64))
+}
+
+/// `HH` or `HH:MM`; `24` means midnight at the end of the day.
+fn parse_time(date: NaiveDate, time: &str) -> Option<chrono::NaiveDateTime> {
+    let (hour, minute) = match time.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (time.parse::<u32>().ok()?, 0),
+    };
+    if hour == 24 && minute == 0 {
+        return Some(date.succ_opt()?.and_time(NaiveTime::MIN));
+    }
+    Some(date.and_time(NaiveTime::from_hms_opt(hour, minute, 0)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    // Fixed UTC-5 so results don't depend on the machine's timezone
+    fn tz() -> FixedOffset {
+        FixedOffset::west_opt(5 * 3600).unwrap()
+    }
+
+    fn local(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        tz().with_ymd_and_hms(2025, 4, day, hour, minute, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    // Wednesday, April 9th 2025, 8am local
+    fn now() -> DateTime<Utc> {
+        local(9, 8, 0)
+    }
+
+    #[test]
+    fn parses_weekdays_relative_to_today() {
+        let text = "Wed 14-16\nfriday 9:30-11\nMon 13 - 15";
+        assert_eq!(
+            parse_pasted_availability_in(text, now(), &tz()).unwrap(),
+            vec![
+                (local(9, 14, 0), local(9, 16, 0)),
+                (local(11, 9, 30), local(11, 11, 0)),
+                (local(14, 13, 0), local(14, 15, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_relative_and_iso_days() {
+        let text = "# their times\n\ntomorrow 10-12\n2025-04-22 22-24\n";
+        assert_eq!(
+            parse_pasted_availability_in(text, now(), &tz()).unwrap(),
+            vec![
+                (local(10, 10, 0), local(10, 12, 0)),
+                (local(22, 22, 0), local(23, 0, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_the_offending_line() {
+        let bad = |text: &str| parse_pasted_availability_in(text, now(), &tz()).unwrap_err();
+        assert_eq!(bad("Mon 9-10\nsomeday 9-10").line, 2);
+        assert_eq!(
+            bad("Mon").reason,
+            "expected a day followed by a time range, e.g. \"Mon 14-16\""
+        );
+        assert_eq!(bad("Mon 9").reason, "expected a time range like 14-16");
+        assert_eq!(bad("Mon 9-25").reason, "invalid end time");
+        assert_eq!(bad("Mon 16-14").reason, "end time must be after start time");
+    }
+}
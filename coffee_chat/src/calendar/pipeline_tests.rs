@@ -0,0 +1,140 @@
+// src/calendar/pipeline_tests.rs
+// End-to-end runs of the slot pipeline on hand-built busy periods, from
+// `slots_from_busy_in` to the summary lines that go into the email.
+
+use super::format::SlotFormat;
+use super::free_busy::{self, SlotSelection};
+use super::units::{HourOfDay, Minutes};
+use super::{slots_from_busy_in, SlotFilters, MIN_SLOT_MINUTES};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::America::New_York;
+use google_calendar3::api::TimePeriod;
+
+fn at(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    New_York
+        .with_ymd_and_hms(2025, month, day, hour, minute, 0)
+        .unwrap()
+        .with_timezone(&Utc)
+}
+
+fn busy(start: DateTime<Utc>, end: DateTime<Utc>) -> TimePeriod {
+    TimePeriod {
+        start: Some(start),
+        end: Some(end),
+    }
+}
+
+fn office_hours(buffer_minutes: u32) -> SlotFilters {
+    SlotFilters {
+        buffer: Minutes(buffer_minutes).into(),
+        start_hour: HourOfDay::new(9).unwrap(),
+        end_hour: HourOfDay::new(17).unwrap(),
+        ..Default::default()
+    }
+}
+
+/// What the email would list, with fully booked days marked.
+fn summary(
+    busy: &[TimePeriod],
+    time_min: DateTime<Utc>,
+    time_max: DateTime<Utc>,
+    filters: &SlotFilters,
+) -> Vec<String> {
+    let windows = slots_from_busy_in(busy, time_min, time_max, filters, &mut |_, _| {}, &New_York);
+    let min_len = Duration::minutes(MIN_SLOT_MINUTES);
+    let unavailable =
+        free_busy::unavailable_days_in(&windows, min_len, time_min, time_max, &New_York);
+    free_busy::summarize_slots_marking_unavailable_in(
+        &windows,
+        min_len,
+        &unavailable,
+        free_busy::SlotOrder::EarliestFirst,
+        SlotFormat::default(),
+        &New_York,
+    )
+}
+
+#[test]
+fn busy_week_summarizes_to_buffered_office_hours() {
+    let periods = vec![
+        busy(at(4, 7, 10, 0), at(4, 7, 11, 0)),
+        // Same meeting seen through a second calendar
+        busy(at(4, 7, 10, 0), at(4, 7, 11, 0)),
+        busy(at(4, 7, 13, 0), at(4, 7, 14, 30)),
+        busy(at(4, 8, 9, 0), at(4, 8, 17, 0)),
+        busy(at(4, 9, 12, 0), at(4, 9, 12, 20)),
+    ];
+
+    assert_eq!(
+        summary(&periods, at(4, 7, 8, 0), at(4, 10, 0, 0), &office_hours(15)),
+        vec![
+            "Monday Apr 7: 9am–9:45am",
+            "Monday Apr 7: 11:15am–12:45pm",
+            "Monday Apr 7: 2:45pm–5pm",
+            "Tuesday Apr 8: no availability",
+            "Wednesday Apr 9: 9am–11:45am",
+            "Wednesday Apr 9: 12:35pm–5pm",
+        ]
+    );
+}
+
+#[test]
+fn daylight_saving_start_keeps_local_hours() {
+    // Clocks go forward at 2am on Sunday March 9
+    assert_eq!(
+        summary(&[], at(3, 8, 0, 0), at(3, 11, 0, 0), &office_hours(0)),
+        vec![
+            "Saturday Mar 8: 9am–5pm",
+            "Sunday Mar 9: 9am–5pm",
+            "Monday Mar 10: 9am–5pm",
+        ]
+    );
+}
+
+#[test]
+fn cutoff_and_daily_limit_apply_after_hours() {
+    let filters = SlotFilters {
+        today_cutoff_hour: Some(14),
+        selection: SlotSelection {
+            min_gap: Duration::zero(),
+            max_per_day: Some(1),
+        },
+        ..office_hours(0)
+    };
+    let periods = vec![busy(at(4, 8, 12, 0), at(4, 8, 13, 0))];
+
+    // It's 3pm Monday, so Monday's remaining time is past the cutoff
+    assert_eq!(
+        summary(&periods, at(4, 7, 15, 0), at(4, 9, 0, 0), &filters),
+        vec!["Tuesday Apr 8: 9am–12pm"]
+    );
+}
+
+#[test]
+fn busy_time_on_several_calendars_is_merged() {
+    // Work and personal overlap on Monday morning; each one alone leaves a gap
+    let by_calendar = vec![
+        (
+            "work@acme.com".to_string(),
+            busy(at(4, 7, 9, 0), at(4, 7, 11, 0)),
+        ),
+        (
+            "me@gmail.com".to_string(),
+            busy(at(4, 7, 10, 30), at(4, 7, 12, 0)),
+        ),
+        (
+            "me@gmail.com".to_string(),
+            busy(at(4, 7, 15, 0), at(4, 7, 16, 0)),
+        ),
+    ];
+
+    assert_eq!(
+        summary(
+            &super::periods(&by_calendar),
+            at(4, 7, 8, 0),
+            at(4, 8, 0, 0),
+            &office_hours(0)
+        ),
+        vec!["Monday Apr 7: 12pm–3pm", "Monday Apr 7: 4pm–5pm"]
+    );
+}
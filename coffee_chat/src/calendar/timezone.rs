@@ -0,0 +1,182 @@
+// src/calendar/timezone.rs
+// The machine's local timezone, recorded with fetched slots so a change (travel)
+// can be noticed: the hour filter and day grouping all use `Local`.
+
+use chrono::{DateTime, Local, Offset, TimeZone, Utc};
+use chrono_tz::OffsetName;
+use serde::{Deserialize, Serialize};
+
+/// The local timezone at one moment: its UTC offset and, when the OS says, its IANA name.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LocalZone {
+    pub offset_minutes: i32,
+    pub name: Option<String>,
+}
+
+impl LocalZone {
+    pub fn current() -> Self {
+        let offset_seconds = Local::now().offset().fix().local_minus_utc();
+        Self {
+            offset_minutes: offset_seconds / 60,
+            name: system_zone_name(),
+        }
+    }
+
+    /// Whether slots computed in `self` are wrong in `other`. With names on both
+    /// sides only the name counts, so a daylight saving shift isn't a change.
+    pub fn differs_from(&self, other: &LocalZone) -> bool {
+        match (&self.name, &other.name) {
+            (Some(a), Some(b)) => a != b,
+            _ => self.offset_minutes != other.offset_minutes,
+        }
+    }
+
+    /// "America/New_York (UTC-05:00)", or just the offset without a name.
+    pub fn label(&self) -> String {
+        let offset = self.offset_label();
+        match &self.name {
+            Some(name) => format!("{} ({})", name, offset),
+            None => offset,
+        }
+    }
+
+    /// A short name for the zone at `at`, e.g. "ET" for New York in summer or winter.
+    /// Zones without a lettered abbreviation, or without a known name, get their UTC offset.
+    pub fn abbreviation(&self, at: DateTime<Utc>) -> String {
+        let abbreviation = self
+            .name
+            .as_deref()
+            .and_then(|name| name.parse::<chrono_tz::Tz>().ok())
+            .and_then(|tz| {
+                tz.offset_from_utc_datetime(&at.naive_utc())
+                    .abbreviation()
+                    .map(str::to_string)
+            });
+        match abbreviation {
+            Some(abbreviation) if abbreviation.chars().all(|c| c.is_ascii_alphabetic()) => {
+                generic_abbreviation(&abbreviation, self.name.as_deref().unwrap_or_default())
+            }
+            _ => self.offset_label(),
+        }
+    }
+
+    fn offset_label(&self) -> String {
+        let sign = if self.offset_minutes < 0 { '-' } else { '+' };
+        let minutes = self.offset_minutes.abs();
+        format!("UTC{}{:02}:{:02}", sign, minutes / 60, minutes % 60)
+    }
+}
+
+/// "EST" and "EDT" both become "ET", so the label stays right across a daylight
+/// saving change. Only North American zones are written that way; others ("CEST",
+/// "JST", "IST", and China's "CST") are kept as they are.
+fn generic_abbreviation(abbreviation: &str, zone_name: &str) -> String {
+    let north_american = ["America/", "US/", "Canada/", "Pacific/Honolulu"]
+        .iter()
+        .any(|prefix| zone_name.starts_with(prefix));
+    let zone = abbreviation
+        .strip_suffix("ST")
+        .or_else(|| abbreviation.strip_suffix("DT"));
+    match zone {
+        Some(zone @ ("E" | "C" | "M" | "P" | "AK" | "H" | "A" | "N")) if north_american => {
+            format!("{}T", zone)
+        }
+        _ => abbreviation.to_string(),
+    }
+}
+
+/// The line that follows the slot list, e.g. "(all times ET)".
+pub fn availability_label(abbreviation: &str) -> String {
+    format!("(all times {})", abbreviation)
+}
+
+/// Whether `line` is a label from [`availability_label`].
+pub fn is_availability_label(line: &str) -> bool {
+    line.starts_with("(all times ") && line.ends_with(')')
+}
+
+/// IANA name from `TZ`, or from where `/etc/localtime` points on Unix.
+fn system_zone_name() -> Option<String> {
+    if let Ok(tz) = std::env::var("TZ") {
+        let tz = tz.trim_start_matches(':').trim();
+        if !tz.is_empty() {
+            return Some(tz.to_string());
+        }
+    }
+    let target = std::fs::read_link("/etc/localtime").ok()?;
+    let target = target.to_string_lossy();
+    let (_, name) = target.split_once("zoneinfo/")?;
+    Some(name.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zone(offset_minutes: i32, name: Option<&str>) -> LocalZone {
+        LocalZone {
+            offset_minutes,
+            name: name.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn daylight_saving_is_not_a_zone_change() {
+        let winter = zone(-300, Some("America/New_York"));
+        let summer = zone(-240, Some("America/New_York"));
+        assert!(!winter.differs_from(&summer));
+        assert!(winter.differs_from(&zone(-480, Some("America/Los_Angeles"))));
+    }
+
+    #[test]
+    fn offsets_decide_when_a_name_is_missing() {
+        assert!(zone(-300, None).differs_from(&zone(-480, Some("America/Los_Angeles"))));
+        assert!(!zone(60, None).differs_from(&zone(60, None)));
+    }
+
+    #[test]
+    fn labels_show_name_and_offset() {
+        assert_eq!(
+            zone(-300, Some("America/New_York")).label(),
+            "America/New_York (UTC-05:00)"
+        );
+        assert_eq!(zone(330, None).label(), "UTC+05:30");
+    }
+
+    #[test]
+    fn abbreviations_are_generic_across_daylight_saving() {
+        let winter = Utc.with_ymd_and_hms(2025, 1, 15, 12, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2025, 7, 15, 12, 0, 0).unwrap();
+        let new_york = zone(-300, Some("America/New_York"));
+        assert_eq!(new_york.abbreviation(winter), "ET");
+        assert_eq!(new_york.abbreviation(summer), "ET");
+        assert_eq!(
+            zone(120, Some("Europe/Berlin")).abbreviation(summer),
+            "CEST"
+        );
+        assert_eq!(
+            zone(-480, Some("America/Anchorage")).abbreviation(winter),
+            "AKT"
+        );
+        // Standard-time names elsewhere in the world stay as they are
+        assert_eq!(zone(540, Some("Asia/Tokyo")).abbreviation(winter), "JST");
+        assert_eq!(zone(330, Some("Asia/Kolkata")).abbreviation(winter), "IST");
+        assert_eq!(zone(540, Some("Asia/Seoul")).abbreviation(winter), "KST");
+        assert_eq!(zone(480, Some("Asia/Shanghai")).abbreviation(winter), "CST");
+        assert_eq!(
+            zone(-360, Some("America/Chicago")).abbreviation(winter),
+            "CT"
+        );
+        // Zones without a lettered name, and unknown zones, fall back to the offset
+        assert_eq!(
+            zone(-180, Some("America/Sao_Paulo")).abbreviation(summer),
+            "UTC-03:00"
+        );
+        assert_eq!(zone(330, None).abbreviation(summer), "UTC+05:30");
+        assert_eq!(zone(0, Some("Not/AZone")).abbreviation(summer), "UTC+00:00");
+        assert_eq!(
+            availability_label(&new_york.abbreviation(summer)),
+            "(all times ET)"
+        );
+    }
+}
@@ -0,0 +1,192 @@
+// src/calendar/token_refresh.rs
+
+use hyper::StatusCode;
+use log::{error, info, warn};
+use std::error::Error;
+use std::future::Future;
+
+/// The scope the app signs in with; a forced refresh renews the token for it.
+pub const CALENDAR_READONLY_SCOPE: &str = "https://www.googleapis.com/auth/calendar.readonly";
+
+/// The sign-in couldn't be renewed, so the user has to go through consent again.
+#[derive(Debug, thiserror::Error)]
+#[error("Google sign-in has expired ({details}). Reconnect the calendar to sign in again.")]
+pub struct ReauthRequired {
+    pub details: String,
+}
+
+/// Whether Google rejected a request because the access token was missing, expired or revoked.
+pub fn is_unauthorized(error: &(dyn Error + 'static)) -> bool {
+    match error.downcast_ref::<google_calendar3::Error>() {
+        Some(google_calendar3::Error::BadRequest(body)) => is_unauthorized_body(body),
+        Some(google_calendar3::Error::Failure(response)) => {
+            response.status() == StatusCode::UNAUTHORIZED
+        }
+        Some(google_calendar3::Error::MissingToken(_)) => true,
+        _ => false,
+    }
+}
+
+// Google's JSON error body: {"error": {"code": 401, "status": "UNAUTHENTICATED", ...}}
+fn is_unauthorized_body(body: &serde_json::Value) -> bool {
+    let error = &body["error"];
+    error["code"] == 401 || error["status"] == "UNAUTHENTICATED"
+}
+
+/// Runs `attempt`, and if Google answers 401, calls `refresh` once and tries again.
+///
+/// A token can expire between being handed to the hub and reaching Google, which
+/// long-running sessions hit now and then. Anything other than a 401 is returned as-is;
+/// a failed refresh, or a second 401, comes back as [`ReauthRequired`].
+pub async fn retry_after_refresh<T, A, AFut, R, RFut>(
+    mut attempt: A,
+    refresh: R,
+) -> Result<T, Box<dyn Error>>
+where
+    A: FnMut() -> AFut,
+    AFut: Future<Output = Result<T, Box<dyn Error>>>,
+    R: FnOnce() -> RFut,
+    RFut: Future<Output = Result<(), String>>,
+{
+    let rejection = match attempt().await {
+        Err(e) if is_unauthorized(&*e) => e.to_string(),
+        other => return other,
+    };
+    warn!(
+        "Calendar request was unauthorized ({}); refreshing the token and retrying once.",
+        rejection
+    );
+
+    if let Err(details) = refresh().await {
+        error!("Token refresh failed: {}", details);
+        return Err(Box::new(ReauthRequired { details }));
+    }
+    info!("Token refreshed; retrying the calendar request.");
+
+    match attempt().await {
+        Err(e) if is_unauthorized(&*e) => {
+            error!("Calendar request still unauthorized after refresh: {}", e);
+            Err(Box::new(ReauthRequired {
+                details: "still unauthorized after refreshing the token".to_string(),
+            }))
+        }
+        other => {
+            if other.is_ok() {
+                info!("Calendar request succeeded after token refresh.");
+            }
+            other
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    fn unauthorized() -> Box<dyn Error> {
+        Box::new(google_calendar3::Error::BadRequest(serde_json::json!({
+            "error": {
+                "code": 401,
+                "message": "Request had invalid authentication credentials.",
+                "status": "UNAUTHENTICATED"
+            }
+        })))
+    }
+
+    fn forbidden() -> Box<dyn Error> {
+        Box::new(google_calendar3::Error::BadRequest(serde_json::json!({
+            "error": { "code": 403, "status": "PERMISSION_DENIED" }
+        })))
+    }
+
+    #[test]
+    fn recognizes_401_bodies_and_missing_tokens() {
+        assert!(is_unauthorized(&*unauthorized()));
+        let status_only = google_calendar3::Error::BadRequest(serde_json::json!({
+            "error": { "status": "UNAUTHENTICATED" }
+        }));
+        assert!(is_unauthorized(&status_only));
+        let missing = google_calendar3::Error::MissingToken("token expired".into());
+        assert!(is_unauthorized(&missing));
+    }
+
+    #[test]
+    fn other_errors_are_not_unauthorized() {
+        assert!(!is_unauthorized(&*forbidden()));
+        assert!(!is_unauthorized(&google_calendar3::Error::Cancelled));
+        let io = std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out");
+        assert!(!is_unauthorized(&io));
+    }
+
+    #[tokio::test]
+    async fn retries_once_after_a_successful_refresh() {
+        let attempts = Cell::new(0);
+        let refreshes = Cell::new(0);
+        let result = retry_after_refresh(
+            || async {
+                attempts.set(attempts.get() + 1);
+                if attempts.get() == 1 {
+                    Err(unauthorized())
+                } else {
+                    Ok("slots")
+                }
+            },
+            || async {
+                refreshes.set(refreshes.get() + 1);
+                Ok(())
+            },
+        )
+        .await;
+        assert_eq!(result.unwrap(), "slots");
+        assert_eq!((attempts.get(), refreshes.get()), (2, 1));
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_asks_for_reauthentication() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry_after_refresh(
+            || async {
+                attempts.set(attempts.get() + 1);
+                Err(unauthorized())
+            },
+            || async { Err("invalid_grant".to_string()) },
+        )
+        .await;
+        let error = result.unwrap_err();
+        let reauth = error.downcast_ref::<ReauthRequired>().unwrap();
+        assert_eq!(reauth.details, "invalid_grant");
+        assert!(error.to_string().contains("Reconnect the calendar"));
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[tokio::test]
+    async fn second_401_is_not_retried_again() {
+        let attempts = Cell::new(0);
+        let result: Result<(), _> = retry_after_refresh(
+            || async {
+                attempts.set(attempts.get() + 1);
+                Err(unauthorized())
+            },
+            || async { Ok(()) },
+        )
+        .await;
+        assert!(result.unwrap_err().is::<ReauthRequired>());
+        assert_eq!(attempts.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn other_errors_skip_the_refresh() {
+        let refreshes = Cell::new(0);
+        let result: Result<(), _> = retry_after_refresh(
+            || async { Err(forbidden()) },
+            || async {
+                refreshes.set(refreshes.get() + 1);
+                Ok(())
+            },
+        )
+        .await;
+        assert!(!result.unwrap_err().is::<ReauthRequired>());
+        assert_eq!(refreshes.get(), 0);
+    }
+}
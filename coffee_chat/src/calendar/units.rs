@@ -0,0 +1,221 @@
+// src/calendar/units.rs
+// Small wrappers so a buffer in minutes can't be passed where an hour of the day goes.
+
+use chrono::{Duration, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A whole number of minutes, e.g. the buffer kept around meetings.
+///
+/// Serializes as a plain number, so saved settings from before it existed still load.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(transparent)]
+pub struct Minutes(pub u32);
+
+impl Minutes {
+    pub const fn get(self) -> u32 {
+        self.0
+    }
+
+    pub fn duration(self) -> Duration {
+        Duration::minutes(self.0 as i64)
+    }
+}
+
+impl From<u32> for Minutes {
+    fn from(minutes: u32) -> Self {
+        Minutes(minutes)
+    }
+}
+
+impl From<Minutes> for u32 {
+    fn from(minutes: Minutes) -> Self {
+        minutes.0
+    }
+}
+
+impl fmt::Display for Minutes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Time kept clear before and after each meeting, e.g. 5 minutes to prepare and
+/// 20 to wind down.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Buffer {
+    pub before: Minutes,
+    pub after: Minutes,
+}
+
+impl Buffer {
+    /// The same buffer on both sides, like the single buffer before there were two.
+    pub const fn even(minutes: Minutes) -> Self {
+        Buffer {
+            before: minutes,
+            after: minutes,
+        }
+    }
+}
+
+impl From<Minutes> for Buffer {
+    fn from(minutes: Minutes) -> Self {
+        Buffer::even(minutes)
+    }
+}
+
+/// "15" when both sides match, otherwise "5 before / 20 after".
+impl fmt::Display for Buffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.before == self.after {
+            write!(f, "{}", self.before)
+        } else {
+            write!(f, "{} before / {} after", self.before, self.after)
+        }
+    }
+}
+
+/// An hour outside 0–23.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("{0} isn't an hour of the day (0-23)")]
+pub struct InvalidHour(pub u32);
+
+/// An hour of the day, 0–23, meaning HH:00 local time.
+///
+/// Serializes as a plain number; anything outside 0–23 is rejected when loading.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(try_from = "u32", into = "u32")]
+pub struct HourOfDay(u8);
+
+impl HourOfDay {
+    pub const MIDNIGHT: HourOfDay = HourOfDay(0);
+    pub const LAST: HourOfDay = HourOfDay(23);
+
+    /// `None` unless `hour` is 0–23.
+    pub const fn new(hour: u8) -> Option<Self> {
+        if hour < 24 {
+            Some(HourOfDay(hour))
+        } else {
+            None
+        }
+    }
+
+    pub const fn get(self) -> u8 {
+        self.0
+    }
+
+    /// The start of this hour.
+    pub fn time(self) -> NaiveTime {
+        NaiveTime::from_hms_opt(self.0 as u32, 0, 0).expect("hour is 0-23")
+    }
+}
+
+impl TryFrom<u32> for HourOfDay {
+    type Error = InvalidHour;
+
+    fn try_from(hour: u32) -> Result<Self, Self::Error> {
+        u8::try_from(hour)
+            .ok()
+            .and_then(HourOfDay::new)
+            .ok_or(InvalidHour(hour))
+    }
+}
+
+impl From<HourOfDay> for u32 {
+    fn from(hour: HourOfDay) -> Self {
+        hour.0 as u32
+    }
+}
+
+impl fmt::Display for HourOfDay {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// The weekdays slots are offered on, Monday first. Every day by default.
+///
+/// Serializes as a plain array of seven booleans.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Weekdays(pub [bool; 7]);
+
+impl Default for Weekdays {
+    fn default() -> Self {
+        Self([true; 7])
+    }
+}
+
+impl Weekdays {
+    pub fn includes(&self, day: Weekday) -> bool {
+        self.0[day.num_days_from_monday() as usize]
+    }
+
+    pub fn set(&mut self, day: Weekday, included: bool) {
+        self.0[day.num_days_from_monday() as usize] = included;
+    }
+
+    pub fn is_every_day(&self) -> bool {
+        self.0.iter().all(|&included| included)
+    }
+}
+
+impl fmt::Display for Weekdays {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_every_day() {
+            return write!(f, "every day");
+        }
+        let days: Vec<String> = (0..7)
+            .filter_map(|n| Weekday::try_from(n).ok())
+            .filter(|&day| self.includes(day))
+            .map(|day| day.to_string())
+            .collect();
+        if days.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", days.join(", "))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hours_outside_the_day_are_rejected() {
+        assert_eq!(HourOfDay::try_from(23).map(u32::from), Ok(23));
+        assert_eq!(HourOfDay::try_from(24), Err(InvalidHour(24)));
+        assert_eq!(HourOfDay::try_from(300), Err(InvalidHour(300)));
+        assert_eq!(
+            HourOfDay::new(9).unwrap().time(),
+            NaiveTime::from_hms_opt(9, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn both_serialize_as_plain_numbers() {
+        let hour: HourOfDay = serde_json::from_str("17").unwrap();
+        assert_eq!(serde_json::to_string(&hour).unwrap(), "17");
+        assert!(serde_json::from_str::<HourOfDay>("24").is_err());
+
+        let minutes: Minutes = serde_json::from_str("15").unwrap();
+        assert_eq!(minutes.duration(), Duration::minutes(15));
+        assert_eq!(serde_json::to_string(&minutes).unwrap(), "15");
+    }
+
+    #[test]
+    fn weekdays_read_as_a_list() {
+        let mut weekdays = Weekdays::default();
+        assert_eq!(weekdays.to_string(), "every day");
+        weekdays.set(Weekday::Sat, false);
+        weekdays.set(Weekday::Sun, false);
+        assert_eq!(weekdays.to_string(), "Mon, Tue, Wed, Thu, Fri");
+        assert!(weekdays.includes(Weekday::Fri) && !weekdays.includes(Weekday::Sun));
+        assert_eq!(Weekdays([false; 7]).to_string(), "none");
+    }
+}
@@ -0,0 +1,433 @@
+// src/control.rs
+// The control API: a small HTTP server on 127.0.0.1 for scripts that drive the
+// GUI, e.g. "fetch slots, then send" from cron. It's off unless config.toml has
+// `[control] enabled = true`. Each request needs the token from
+// control_endpoint.json and is answered by the UI thread, so it gets the same
+// checks as the buttons.
+
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use directories_next::ProjectDirs;
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use log::{debug, warn};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::net::{Ipv4Addr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+// How long a request waits for the UI thread before giving up
+const REPLY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// `[control]` in config.toml.
+#[derive(Debug, Deserialize, Clone, Default, PartialEq, Eq)]
+pub struct ControlConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub port: u16, // 0 = any free port; control_endpoint.json says which
+}
+
+/// What a request asks the app for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlRequest {
+    Status,
+    FetchSlots,
+    Send { dry_run: bool },
+    History { since: Option<DateTime<Utc>> },
+}
+
+/// An HTTP status with a JSON body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ControlReply {
+    pub status: StatusCode,
+    pub body: Value,
+}
+
+impl ControlReply {
+    pub fn ok(body: Value) -> Self {
+        ControlReply {
+            status: StatusCode::OK,
+            body,
+        }
+    }
+
+    /// A background task was started; it reports through `GET /status`.
+    pub fn accepted(body: Value) -> Self {
+        ControlReply {
+            status: StatusCode::ACCEPTED,
+            body,
+        }
+    }
+
+    pub fn error(status: StatusCode, message: impl Into<String>) -> Self {
+        ControlReply {
+            status,
+            body: json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Hands a request to the UI thread, which answers on the channel.
+pub type Dispatch = Arc<dyn Fn(ControlRequest, oneshot::Sender<ControlReply>) + Send + Sync>;
+
+/// Where the API listens and the token it takes, written to control_endpoint.json
+/// for scripts to read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Endpoint {
+    pub url: String,
+    pub token: String,
+}
+
+impl Endpoint {
+    /// Writes control_endpoint.json, readable only by the user where that's possible.
+    pub fn save(&self) -> Result<PathBuf, String> {
+        let path = endpoint_path()?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        write_private(&path, json.as_bytes())
+            .map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// Writes a new file at `path` that's created readable only by the user, so the token
+/// is never readable by others, not even between creating and restricting it. An
+/// older file is removed first, since opening it would keep its permissions.
+fn write_private(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+        _ => {}
+    }
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(contents)
+}
+
+/// control_endpoint.json, next to app_state.json, creating the directory if needed.
+pub fn endpoint_path() -> Result<PathBuf, String> {
+    let proj_dirs = ProjectDirs::from("com", "YourOrg", "CoffeeChatHelper")
+        .ok_or("Could not determine project directory for the control endpoint.")?;
+    let config_dir = proj_dirs.config_dir();
+    fs::create_dir_all(config_dir)
+        .map_err(|e| format!("Failed to create config directory {:?}: {}", config_dir, e))?;
+    Ok(config_dir.join("control_endpoint.json"))
+}
+
+/// A fresh token: 128 bits from the operating system's secure random source.
+pub fn new_token() -> Result<String, String> {
+    let mut bytes = [0u8; 16];
+    SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "Couldn't get random bytes for the control API token".to_string())?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Binds 127.0.0.1:`port`. Done up front, so a taken port is reported right away.
+pub fn bind(port: u16) -> std::io::Result<TcpListener> {
+    let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+    listener.set_nonblocking(true)?;
+    Ok(listener)
+}
+
+/// Answers requests on `listener` until the runtime shuts down. Needs a tokio runtime.
+pub async fn serve(listener: TcpListener, token: String, dispatch: Dispatch) {
+    let listener = match tokio::net::TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(e) => {
+            warn!("Control API couldn't start listening: {}", e);
+            return;
+        }
+    };
+    let token: Arc<str> = token.into();
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("Control API couldn't accept a connection: {}", e);
+                continue;
+            }
+        };
+        let token = token.clone();
+        let dispatch = dispatch.clone();
+        tokio::spawn(async move {
+            let service = service_fn(move |request| {
+                let token = token.clone();
+                let dispatch = dispatch.clone();
+                async move { Ok::<_, Infallible>(respond(request, &token, &dispatch).await) }
+            });
+            if let Err(e) = http1::Builder::new()
+                .serve_connection(TokioIo::new(stream), service)
+                .await
+            {
+                debug!("Control API connection ended: {}", e);
+            }
+        });
+    }
+}
+
+async fn respond(
+    request: Request<Incoming>,
+    token: &str,
+    dispatch: &Dispatch,
+) -> Response<Full<Bytes>> {
+    let reply = if !authorized(request.headers().get(AUTHORIZATION), token) {
+        ControlReply::error(StatusCode::UNAUTHORIZED, "Missing or wrong bearer token.")
+    } else {
+        match route(
+            request.method(),
+            request.uri().path(),
+            request.uri().query(),
+        ) {
+            Ok(control) => {
+                debug!("Control API request: {:?}", control);
+                let (reply_to, reply) = oneshot::channel();
+                dispatch(control, reply_to);
+                match tokio::time::timeout(REPLY_TIMEOUT, reply).await {
+                    Ok(Ok(reply)) => reply,
+                    Ok(Err(_)) => {
+                        ControlReply::error(StatusCode::SERVICE_UNAVAILABLE, "The app is closing.")
+                    }
+                    Err(_) => ControlReply::error(
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "The app didn't answer in time.",
+                    ),
+                }
+            }
+            Err(reply) => reply,
+        }
+    };
+    let mut response = Response::new(Full::new(Bytes::from(reply.body.to_string())));
+    *response.status_mut() = reply.status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, "application/json".parse().unwrap());
+    response
+}
+
+/// Whether the `Authorization` header is `Bearer <token>`. Compares every byte, so
+/// the time taken doesn't give away how much of a guess was right.
+fn authorized(header: Option<&hyper::header::HeaderValue>, token: &str) -> bool {
+    let Some(given) = header
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |differences, (a, b)| differences | (a ^ b))
+            == 0
+}
+
+/// The request a method and path stand for, or the error reply.
+fn route(method: &Method, path: &str, query: Option<&str>) -> Result<ControlRequest, ControlReply> {
+    let request = match path.trim_end_matches('/') {
+        "/status" => ControlRequest::Status,
+        "/fetch-slots" => ControlRequest::FetchSlots,
+        "/send" => ControlRequest::Send {
+            dry_run: match query_value(query, "dry_run") {
+                None => false,
+                Some("" | "1" | "true") => true,
+                Some("0" | "false") => false,
+                Some(other) => {
+                    return Err(ControlReply::error(
+                        StatusCode::BAD_REQUEST,
+                        format!("dry_run must be true or false, not \"{}\".", other),
+                    ))
+                }
+            },
+        },
+        "/history" => ControlRequest::History {
+            since: match query_value(query, "since") {
+                None => None,
+                Some(since) => Some(parse_since(since).ok_or_else(|| {
+                    ControlReply::error(
+                        StatusCode::BAD_REQUEST,
+                        format!(
+                            "since must be a date (2025-04-01) or an RFC 3339 time, not \"{}\".",
+                            since
+                        ),
+                    )
+                })?),
+            },
+        },
+        _ => {
+            return Err(ControlReply::error(
+                StatusCode::NOT_FOUND,
+                "No such endpoint.",
+            ))
+        }
+    };
+    let expected = match request {
+        ControlRequest::Status | ControlRequest::History { .. } => Method::GET,
+        ControlRequest::FetchSlots | ControlRequest::Send { .. } => Method::POST,
+    };
+    if *method != expected {
+        return Err(ControlReply::error(
+            StatusCode::METHOD_NOT_ALLOWED,
+            format!("{} {} is the only method here.", expected, path),
+        ));
+    }
+    Ok(request)
+}
+
+/// The value of `key` in a query string; "" for a bare `?dry_run`. Values here are
+/// dates and flags, so percent-decoding only has `%3A` and `%2B` to handle.
+fn query_value<'a>(query: Option<&'a str>, key: &str) -> Option<&'a str> {
+    query?
+        .split('&')
+        .find_map(|pair| match pair.split_once('=') {
+            Some((name, value)) if name == key => Some(value),
+            None if pair == key => Some(""),
+            _ => None,
+        })
+}
+
+/// `2025-04-01` (local midnight) or an RFC 3339 time.
+fn parse_since(since: &str) -> Option<DateTime<Utc>> {
+    let since = since.replace("%3A", ":").replace("%2B", "+");
+    if let Ok(time) = DateTime::parse_from_rfc3339(&since) {
+        return Some(time.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(&since, "%Y-%m-%d")
+        .ok()?
+        .and_hms_opt(0, 0, 0)?
+        .and_local_timezone(Local)
+        .earliest()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn paths_and_methods_are_routed() {
+        assert_eq!(
+            route(&Method::GET, "/status", None).unwrap(),
+            ControlRequest::Status
+        );
+        assert_eq!(
+            route(&Method::POST, "/send/", Some("dry_run=true")).unwrap(),
+            ControlRequest::Send { dry_run: true }
+        );
+        assert_eq!(
+            route(&Method::POST, "/send", None).unwrap(),
+            ControlRequest::Send { dry_run: false }
+        );
+        assert_eq!(
+            route(
+                &Method::GET,
+                "/history",
+                Some("since=2025-04-01T09%3A00%3A00Z")
+            )
+            .unwrap(),
+            ControlRequest::History {
+                since: Some(Utc.with_ymd_and_hms(2025, 4, 1, 9, 0, 0).unwrap())
+            }
+        );
+        let status = |method, path, query| route(&method, path, query).unwrap_err().status;
+        assert_eq!(
+            status(Method::GET, "/send", None),
+            StatusCode::METHOD_NOT_ALLOWED
+        );
+        assert_eq!(status(Method::GET, "/nope", None), StatusCode::NOT_FOUND);
+        assert_eq!(
+            status(Method::GET, "/history", Some("since=yesterday")),
+            StatusCode::BAD_REQUEST
+        );
+        assert_eq!(
+            status(Method::POST, "/send", Some("dry_run=maybe")),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn the_endpoint_file_is_private_from_the_start() {
+        use std::os::unix::fs::PermissionsExt;
+        let path =
+            std::env::temp_dir().join(format!("coffee_chat_endpoint_{}", std::process::id()));
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        write_private(&path, b"{}").unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{}");
+        fs::remove_file(&path).ok();
+    }
+
+    #[test]
+    fn tokens_are_fresh() {
+        let token = new_token().unwrap();
+        assert_eq!(token.len(), 32);
+        assert!(token.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_ne!(token, new_token().unwrap());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn requests_need_the_token_and_reach_the_app() {
+        let listener = bind(0).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        // Stands in for the UI thread
+        let dispatch: Dispatch = Arc::new(|request, reply_to: oneshot::Sender<ControlReply>| {
+            let reply = match request {
+                ControlRequest::Status => ControlReply::ok(json!({ "slots": 3 })),
+                other => ControlReply::accepted(json!({ "request": format!("{:?}", other) })),
+            };
+            reply_to.send(reply).ok();
+        });
+        tokio::spawn(serve(listener, "secret".to_string(), dispatch));
+        let client = reqwest::Client::new();
+
+        let anonymous = client.get(format!("{}/status", url)).send().await.unwrap();
+        assert_eq!(anonymous.status(), 401);
+        let wrong = client
+            .get(format!("{}/status", url))
+            .bearer_auth("secreT")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(wrong.status(), 401);
+
+        let status = client
+            .get(format!("{}/status", url))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(status.status(), 200);
+        assert_eq!(status.json::<Value>().await.unwrap(), json!({ "slots": 3 }));
+
+        let send = client
+            .post(format!("{}/send?dry_run", url))
+            .bearer_auth("secret")
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(send.status(), 202);
+        assert_eq!(
+            send.json::<Value>().await.unwrap()["request"],
+            "Send { dry_run: true }"
+        );
+    }
+}
@@ -0,0 +1,644 @@
+// src/drip.rs
+// Drip sending: a long recipient list split into batches sent on different days.
+// The schedule lives in drip_schedule.json next to the GUI's saved state, so the
+// GUI and `coffee-chat send --due` both see (and mark off) the same batches. Every
+// change re-reads the file under a lock file, and a batch is claimed before it's
+// sent, so two of them never send the same batch.
+
+use crate::config::Recipient;
+use crate::email_sender::priority::Priority;
+use crate::email_sender::template::{EmailTemplate, TemplateError};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use directories_next::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+/// How long [`update`] waits for another process to let go of the schedule.
+const LOCK_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+/// The lock is only held to read and write the file; one older than this was left
+/// by a process that died.
+const STALE_LOCK: std::time::Duration = std::time::Duration::from_secs(30);
+/// A claim lapses after this long without an email going out, so a batch whose
+/// sender crashed is picked up again.
+const CLAIM_TIMEOUT: Duration = Duration::minutes(10);
+
+/// The invitation a schedule sends: the compose fields as they were when it started,
+/// so editing the template afterwards doesn't change later batches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Invitation {
+    pub sender_name: String,
+    pub subject: String,
+    pub body: String,
+    pub cc: String,
+    pub bcc: String,
+    pub no_slots_text: String,
+    pub priority: Priority,
+    #[serde(default)] // Schedules started before availability pages existed
+    pub availability_link: String,
+    #[serde(default)] // Schedules started before the archive BCC existed
+    pub archive_bcc: String,
+    #[serde(default)] // History entries of every batch get it
+    pub campaign: Option<String>,
+}
+
+impl Invitation {
+    pub fn template(&self, unavailable_days: Vec<String>) -> Result<EmailTemplate, TemplateError> {
+        Ok(
+            EmailTemplate::from_content(&self.subject, &self.body, "drip_template")?
+                .with_copies(&self.cc, &self.bcc)?
+                .with_unavailable_days(unavailable_days)
+                .with_no_slots_text(&self.no_slots_text)
+                .with_priority(self.priority)
+                .with_availability_link(&self.availability_link)
+                .with_archive_bcc(&self.archive_bcc),
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BatchStatus {
+    Pending,
+    Sent {
+        at: DateTime<Utc>,
+        sent: usize,
+        failed: usize,
+    },
+    /// Called off before it went out. Kept, so the indices of the others don't move
+    /// under a sender that has claimed one.
+    Cancelled {
+        at: DateTime<Utc>,
+    },
+}
+
+/// What became of one recipient of a batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tried {
+    Sent,
+    Failed,
+    Skipped, // Emailed recently by another send
+}
+
+/// Who is sending a batch, and when they last got an email out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Claim {
+    pub owner: String,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DripBatch {
+    /// Local date the batch goes out on (or the first day after it the app runs)
+    pub due: NaiveDate,
+    pub recipients: Vec<Recipient>,
+    pub status: BatchStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claim: Option<Claim>,
+    /// Addresses already sent to (or failed), so a batch cut short goes on with the rest
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tried: Vec<String>,
+    #[serde(default)]
+    pub failed: usize, // Of `tried`
+    #[serde(default)]
+    pub skipped: usize, // Of `tried`
+}
+
+impl DripBatch {
+    pub fn is_pending(&self) -> bool {
+        self.status == BatchStatus::Pending
+    }
+
+    /// Whether someone is sending it and was still at it recently.
+    pub fn is_claimed(&self, now: DateTime<Utc>) -> bool {
+        self.claim
+            .as_ref()
+            .is_some_and(|claim| now - claim.at < CLAIM_TIMEOUT)
+    }
+
+    /// The recipients not tried yet.
+    pub fn remaining(&self) -> Vec<Recipient> {
+        self.recipients
+            .iter()
+            .filter(|r| !self.tried.contains(&r.email))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DripSchedule {
+    pub created_at: DateTime<Utc>,
+    pub invitation: Invitation,
+    pub batches: Vec<DripBatch>,
+}
+
+impl DripSchedule {
+    /// Splits `recipients`, in order, into batches of `batch_size`, the first due on
+    /// `start` and each later one `interval_days` after the one before.
+    pub fn new(
+        invitation: Invitation,
+        recipients: Vec<Recipient>,
+        batch_size: usize,
+        interval_days: u32,
+        start: NaiveDate,
+    ) -> Self {
+        let batches = recipients
+            .chunks(batch_size.max(1))
+            .enumerate()
+            .map(|(i, chunk)| DripBatch {
+                due: start + Duration::days(i as i64 * i64::from(interval_days)),
+                recipients: chunk.to_vec(),
+                status: BatchStatus::Pending,
+                claim: None,
+                tried: Vec::new(),
+                failed: 0,
+                skipped: 0,
+            })
+            .collect();
+        Self {
+            created_at: Utc::now(),
+            invitation,
+            batches,
+        }
+    }
+
+    /// Pending batches due on or before `today`, earliest first.
+    pub fn due_batches(&self, today: NaiveDate) -> Vec<usize> {
+        let mut due: Vec<usize> = (0..self.batches.len())
+            .filter(|&i| self.batches[i].is_pending() && self.batches[i].due <= today)
+            .collect();
+        due.sort_by_key(|&i| self.batches[i].due);
+        due
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.batches
+            .iter()
+            .filter(|batch| batch.is_pending())
+            .count()
+    }
+
+    /// Claims the earliest due batch nobody else is sending, for `owner`.
+    pub fn claim_next_due(
+        &mut self,
+        today: NaiveDate,
+        owner: &str,
+        now: DateTime<Utc>,
+    ) -> Option<usize> {
+        let index = self
+            .due_batches(today)
+            .into_iter()
+            .find(|&i| !self.batches[i].is_claimed(now))?;
+        self.batches[index].claim = Some(Claim {
+            owner: owner.to_string(),
+            at: now,
+        });
+        Some(index)
+    }
+
+    /// Lets go of a batch `owner` claimed but didn't send, so it's tried again later.
+    pub fn release(&mut self, index: usize, owner: &str) {
+        if let Some(batch) = self.batches.get_mut(index) {
+            if batch
+                .claim
+                .as_ref()
+                .is_some_and(|claim| claim.owner == owner)
+            {
+                batch.claim = None;
+            }
+        }
+    }
+
+    /// Cancels the batches not sent yet, except ones being sent right now. Returns
+    /// how many.
+    pub fn cancel(&mut self, now: DateTime<Utc>) -> usize {
+        let mut count = 0;
+        for batch in &mut self.batches {
+            if batch.is_pending() && !batch.is_claimed(now) {
+                batch.status = BatchStatus::Cancelled { at: now };
+                batch.claim = None;
+                count += 1;
+            }
+        }
+        count
+    }
+
+    /// Notes what became of `email` in batch `index`, renewing the claim.
+    pub fn record(&mut self, index: usize, email: &str, tried: Tried, now: DateTime<Utc>) {
+        let Some(batch) = self.batches.get_mut(index).filter(|b| b.is_pending()) else {
+            return;
+        };
+        if !batch.tried.iter().any(|earlier| earlier == email) {
+            batch.tried.push(email.to_string());
+            match tried {
+                Tried::Sent => {}
+                Tried::Failed => batch.failed += 1,
+                Tried::Skipped => batch.skipped += 1,
+            }
+        }
+        if let Some(claim) = &mut batch.claim {
+            claim.at = now;
+        }
+    }
+
+    /// Marks batch `index` sent, with what was recorded of it. Anyone not tried yet,
+    /// because the batch stopped, goes into a new batch due on `carry_over_to`;
+    /// returns how many.
+    pub fn finish(&mut self, index: usize, now: DateTime<Utc>, carry_over_to: NaiveDate) -> usize {
+        let Some(batch) = self.batches.get_mut(index).filter(|b| b.is_pending()) else {
+            return 0;
+        };
+        batch.status = BatchStatus::Sent {
+            at: now,
+            sent: batch.tried.len() - batch.failed - batch.skipped,
+            failed: batch.failed,
+        };
+        batch.claim = None;
+        let left_over = batch.remaining();
+        let count = left_over.len();
+        batch.recipients.retain(|r| batch.tried.contains(&r.email));
+        if count > 0 {
+            // At the end, so the indices of the others don't move
+            self.batches.push(DripBatch {
+                due: carry_over_to,
+                recipients: left_over,
+                status: BatchStatus::Pending,
+                claim: None,
+                tried: Vec::new(),
+                failed: 0,
+                skipped: 0,
+            });
+        }
+        count
+    }
+
+    /// Reads the schedule file; `None` when there is none.
+    pub fn load() -> Result<Option<Self>, String> {
+        read(&schedule_path()?)
+    }
+}
+
+/// `owner` for claims made by this process, e.g. "send --due (pid 4242)".
+pub fn claimant(role: &str) -> String {
+    format!("{} (pid {})", role, std::process::id())
+}
+
+/// Applies `change` to the schedule in drip_schedule.json, re-read under the lock so
+/// nothing another process wrote is lost, and writes it back. Returns what `change`
+/// did and the schedule as written, `None` once nothing is left to send.
+///
+/// Waits a few seconds for the lock if another process holds it, so async code
+/// calls it through `spawn_blocking`.
+pub fn update<T>(
+    change: impl FnOnce(&mut Option<DripSchedule>) -> T,
+) -> Result<(T, Option<DripSchedule>), String> {
+    update_at(&schedule_path()?, change)
+}
+
+/// [`update`] without waiting, for the UI thread: `None`, with `change` not run,
+/// while another process holds the lock.
+pub fn try_update<T>(
+    change: impl FnOnce(&mut Option<DripSchedule>) -> T,
+) -> Result<Option<(T, Option<DripSchedule>)>, String> {
+    change_at(&schedule_path()?, std::time::Duration::ZERO, change)
+}
+
+fn update_at<T>(
+    path: &Path,
+    change: impl FnOnce(&mut Option<DripSchedule>) -> T,
+) -> Result<(T, Option<DripSchedule>), String> {
+    change_at(path, LOCK_WAIT, change)?.ok_or_else(|| {
+        format!(
+            "{} is being changed by another coffee-chat; try again shortly.",
+            path.display()
+        )
+    })
+}
+
+fn change_at<T>(
+    path: &Path,
+    wait: std::time::Duration,
+    change: impl FnOnce(&mut Option<DripSchedule>) -> T,
+) -> Result<Option<(T, Option<DripSchedule>)>, String> {
+    let Some(_lock) = Lock::take(path, wait)? else {
+        return Ok(None);
+    };
+    let mut schedule = read(path)?;
+    let result = apply(&mut schedule, change);
+    match &schedule {
+        Some(schedule) => {
+            let json = serde_json::to_string_pretty(schedule).map_err(|e| e.to_string())?;
+            fs::write(path, json)
+                .map_err(|e| format!("Couldn't write {}: {}", path.display(), e))?;
+        }
+        None => match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => {
+                return Err(format!("Couldn't remove {}: {}", path.display(), e));
+            }
+            _ => {}
+        },
+    }
+    Ok(Some((result, schedule)))
+}
+
+/// `change`, then drops the schedule if it has nothing left to send. Demo mode
+/// uses this on the schedule it keeps in memory.
+pub fn apply<T>(
+    schedule: &mut Option<DripSchedule>,
+    change: impl FnOnce(&mut Option<DripSchedule>) -> T,
+) -> T {
+    let result = change(schedule);
+    if schedule
+        .as_ref()
+        .is_some_and(|schedule| schedule.pending_count() == 0)
+    {
+        *schedule = None;
+    }
+    result
+}
+
+fn read(path: &Path) -> Result<Option<DripSchedule>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let json =
+        fs::read_to_string(path).map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))
+}
+
+/// drip_schedule.json.lock, held while the schedule is read and written.
+struct Lock(PathBuf);
+
+impl Lock {
+    /// Takes the lock, waiting up to `wait` for another process to let go of it;
+    /// `None` if it still hasn't.
+    fn take(schedule: &Path, wait: std::time::Duration) -> Result<Option<Self>, String> {
+        let path = schedule.with_extension("json.lock");
+        let started = Instant::now();
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => return Ok(Some(Self(path))),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&path)
+                        .and_then(|meta| meta.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_LOCK);
+                    if stale {
+                        fs::remove_file(&path).ok();
+                    } else if started.elapsed() >= wait {
+                        return Ok(None);
+                    } else {
+                        std::thread::sleep(std::time::Duration::from_millis(20));
+                    }
+                }
+                Err(e) => return Err(format!("Couldn't lock {}: {}", schedule.display(), e)),
+            }
+        }
+    }
+}
+
+impl Drop for Lock {
+    fn drop(&mut self) {
+        fs::remove_file(&self.0).ok();
+    }
+}
+
+/// drip_schedule.json, next to app_state.json, creating the directory if needed.
+pub fn schedule_path() -> Result<PathBuf, String> {
+    let proj_dirs = ProjectDirs::from("com", "YourOrg", "CoffeeChatHelper")
+        .ok_or("Could not determine project directory for the drip schedule.")?;
+    let config_dir = proj_dirs.config_dir();
+    fs::create_dir_all(config_dir)
+        .map_err(|e| format!("Failed to create config directory {:?}: {}", config_dir, e))?;
+    Ok(config_dir.join("drip_schedule.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn recipients(count: usize) -> Vec<Recipient> {
+        (0..count)
+            .map(|i| Recipient {
+                name: format!("Person {}", i),
+                email: format!("p{}@example.com", i),
+                fields: BTreeMap::new(),
+            })
+            .collect()
+    }
+
+    fn invitation() -> Invitation {
+        Invitation {
+            sender_name: "Me".to_string(),
+            subject: "Coffee?".to_string(),
+            body: "Hi {{ recipient_name }}".to_string(),
+            cc: String::new(),
+            bcc: String::new(),
+            no_slots_text: String::new(),
+            priority: Priority::Normal,
+            availability_link: String::new(),
+            archive_bcc: String::new(),
+            campaign: None,
+        }
+    }
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 4, day).unwrap()
+    }
+
+    #[test]
+    fn recipients_are_split_in_order_at_the_interval() {
+        let schedule = DripSchedule::new(invitation(), recipients(25), 10, 2, day(7));
+        let sizes: Vec<usize> = schedule
+            .batches
+            .iter()
+            .map(|b| b.recipients.len())
+            .collect();
+        assert_eq!(sizes, vec![10, 10, 5]);
+        let dues: Vec<NaiveDate> = schedule.batches.iter().map(|b| b.due).collect();
+        assert_eq!(dues, vec![day(7), day(9), day(11)]);
+        assert_eq!(schedule.batches[1].recipients[0].email, "p10@example.com");
+    }
+
+    #[test]
+    fn only_pending_batches_that_are_due_are_returned() {
+        let mut schedule = DripSchedule::new(invitation(), recipients(30), 10, 1, day(7));
+        assert_eq!(schedule.due_batches(day(6)), Vec::<usize>::new());
+        assert_eq!(schedule.due_batches(day(8)), vec![0, 1]);
+        for r in schedule.batches[0].remaining() {
+            schedule.record(0, &r.email, Tried::Sent, Utc::now());
+        }
+        assert_eq!(schedule.finish(0, Utc::now(), day(9)), 0);
+        assert_eq!(schedule.due_batches(day(8)), vec![1]);
+        assert_eq!(schedule.pending_count(), 2);
+        // Moved earlier by hand: still sent in date order
+        schedule.batches[2].due = day(5);
+        assert_eq!(schedule.due_batches(day(8)), vec![2, 1]);
+    }
+
+    #[test]
+    fn a_batch_cut_short_goes_on_with_the_rest() {
+        let mut schedule = DripSchedule::new(invitation(), recipients(3), 10, 1, day(7));
+        let now = Utc::now();
+        let index = schedule.claim_next_due(day(7), "gui", now).unwrap();
+        schedule.record(index, "p0@example.com", Tried::Sent, now);
+        schedule.record(index, "p1@example.com", Tried::Failed, now);
+        // The sender crashed; once the claim lapses, another one sends to p2 alone
+        let later = now + CLAIM_TIMEOUT + Duration::minutes(1);
+        assert_eq!(schedule.claim_next_due(day(7), "cron", now), None);
+        assert_eq!(schedule.claim_next_due(day(7), "cron", later), Some(index));
+        let remaining: Vec<String> = schedule.batches[index]
+            .remaining()
+            .into_iter()
+            .map(|r| r.email)
+            .collect();
+        assert_eq!(remaining, vec!["p2@example.com"]);
+        schedule.record(index, "p2@example.com", Tried::Sent, later);
+        assert_eq!(schedule.finish(index, later, day(8)), 0);
+        assert!(matches!(
+            schedule.batches[index].status,
+            BatchStatus::Sent {
+                sent: 2,
+                failed: 1,
+                ..
+            }
+        ));
+        assert_eq!(schedule.batches[index].claim, None);
+    }
+
+    #[test]
+    fn cancelling_keeps_a_claimed_batch_where_its_sender_left_it() {
+        let mut schedule = DripSchedule::new(invitation(), recipients(30), 10, 1, day(7));
+        let now = Utc::now();
+        let index = schedule.claim_next_due(day(8), "cron", now).unwrap();
+        assert_eq!(schedule.cancel(now), 2);
+        assert_eq!(schedule.batches.len(), 3);
+        assert_eq!(schedule.claim_next_due(day(9), "gui", now), None);
+        // The cron sender still finishes the batch it claimed, at the same index
+        schedule.record(index, "p0@example.com", Tried::Sent, now);
+        assert_eq!(schedule.finish(index, now, day(8)), 9);
+        assert!(matches!(
+            schedule.batches[index].status,
+            BatchStatus::Sent { sent: 1, .. }
+        ));
+        // Cancelled batches aren't written to
+        schedule.record(1, "p10@example.com", Tried::Sent, now);
+        assert_eq!(schedule.finish(1, now, day(8)), 0);
+        assert!(schedule.batches[1].tried.is_empty());
+        assert!(matches!(
+            schedule.batches[1].status,
+            BatchStatus::Cancelled { .. }
+        ));
+    }
+
+    #[test]
+    fn two_senders_never_claim_the_same_batch() {
+        let dir = std::env::temp_dir().join(format!("coffee_chat_drip_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("drip_schedule.json");
+        let schedule = DripSchedule::new(invitation(), recipients(30), 10, 1, day(7));
+        update_at(&path, |stored| *stored = Some(schedule)).unwrap();
+
+        let claimers: Vec<_> = ["gui", "cron"]
+            .into_iter()
+            .map(|owner| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut claimed = Vec::new();
+                    while let Some(index) = update_at(&path, |schedule| {
+                        schedule.as_mut()?.claim_next_due(day(9), owner, Utc::now())
+                    })
+                    .unwrap()
+                    .0
+                    {
+                        claimed.push(index);
+                    }
+                    claimed
+                })
+            })
+            .collect();
+        let mut claimed: Vec<usize> = claimers
+            .into_iter()
+            .flat_map(|claimer| claimer.join().unwrap())
+            .collect();
+        claimed.sort();
+        assert_eq!(claimed, vec![0, 1, 2]); // Each batch by one of them
+
+        // Each finishes its own; the file goes once nothing is pending
+        let (_, written) = update_at(&path, |schedule| {
+            let schedule = schedule.as_mut().unwrap();
+            for index in 0..3 {
+                for r in schedule.batches[index].remaining() {
+                    schedule.record(index, &r.email, Tried::Sent, Utc::now());
+                }
+                schedule.finish(index, Utc::now(), day(10));
+            }
+        })
+        .unwrap();
+        assert_eq!(written, None);
+        assert!(!path.exists());
+        assert!(!path.with_extension("json.lock").exists());
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn trying_a_held_lock_gives_up_at_once() {
+        let dir = std::env::temp_dir().join(format!("coffee_chat_busy_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("drip_schedule.json");
+        let no_wait = std::time::Duration::ZERO;
+        let held = Lock::take(&path, no_wait).unwrap().unwrap();
+        let mut changed = false;
+        assert!(change_at(&path, no_wait, |_| changed = true)
+            .unwrap()
+            .is_none());
+        assert!(!changed);
+        drop(held);
+        assert!(change_at(&path, no_wait, |_| changed = true)
+            .unwrap()
+            .is_some());
+        assert!(changed);
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn a_stopped_batch_leaves_its_unsent_recipients_scheduled() {
+        let mut schedule = DripSchedule::new(invitation(), recipients(15), 10, 7, day(7));
+        let index = schedule.claim_next_due(day(7), "gui", Utc::now()).unwrap();
+        schedule.record(index, "p0@example.com", Tried::Sent, Utc::now());
+        schedule.record(index, "p1@example.com", Tried::Skipped, Utc::now());
+        for i in 2..7 {
+            let email = format!("p{}@example.com", i);
+            schedule.record(index, &email, Tried::Failed, Utc::now());
+        }
+        // Five failures in a row stopped it with p7 to p9 left
+        assert_eq!(schedule.finish(index, Utc::now(), day(8)), 3);
+        assert!(matches!(
+            schedule.batches[0].status,
+            BatchStatus::Sent {
+                sent: 1,
+                failed: 5,
+                ..
+            }
+        ));
+        assert_eq!(schedule.batches[0].recipients.len(), 7);
+        assert_eq!(schedule.pending_count(), 2);
+        // Due the next day, ahead of the batch a week later
+        assert_eq!(schedule.due_batches(day(8)), vec![2]);
+        let left: Vec<String> = schedule.batches[2]
+            .recipients
+            .iter()
+            .map(|r| r.email.clone())
+            .collect();
+        assert_eq!(
+            left,
+            vec!["p7@example.com", "p8@example.com", "p9@example.com"]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+// src/email_sender/address.rs
+// Internationalized addresses. IDN domains go out in punycode (xn--…), which every
+// server accepts. A non-ASCII local part can't be rewritten: lettre asks for SMTPUTF8
+// and the send fails if the server doesn't offer it.
+
+use lettre::address::AddressError;
+use lettre::message::Mailbox;
+use lettre::Address;
+
+/// Parses "addr" or "Name <addr>", with the domain in punycode.
+pub fn parse_mailbox(text: &str) -> Result<Mailbox, AddressError> {
+    let mailbox: Mailbox = text.parse()?;
+    Ok(Mailbox::new(mailbox.name, ascii_domain(&mailbox.email)?))
+}
+
+/// Parses a bare address, with the domain in punycode.
+pub fn parse_address(text: &str) -> Result<Address, AddressError> {
+    ascii_domain(&text.parse()?)
+}
+
+/// ASCII domains are kept as typed; punycoding would also lowercase them.
+fn ascii_domain(address: &Address) -> Result<Address, AddressError> {
+    if address.domain().is_ascii() {
+        return Ok(address.clone());
+    }
+    let domain =
+        idna::domain_to_ascii(address.domain()).map_err(|_| AddressError::InvalidDomain)?;
+    Address::new(address.user(), domain)
+}
+
+/// Whether the part before the @ has non-ASCII characters, which only SMTPUTF8 servers accept.
+pub fn needs_smtputf8(email: &str) -> bool {
+    email
+        .trim()
+        .rsplit_once('@')
+        .is_some_and(|(local, _)| !local.is_ascii())
+}
+
+/// Splits what an address-book export puts in an email column, `"Doe, Jane" <jane@x>`,
+/// `<jane@x>` or `jane@x (Jane Doe)`, into the display name, unquoted, and the bare
+/// address. Anything else comes back as the address, trimmed.
+pub fn split_mailbox(text: &str) -> (Option<String>, String) {
+    let text = text.trim();
+    if let Some(open) = text.strip_suffix('>').and_then(|rest| rest.rfind('<')) {
+        let name = unquote(text[..open].trim());
+        let address = text[open + 1..text.len() - 1].trim().to_string();
+        return (Some(name).filter(|name| !name.is_empty()), address);
+    }
+    if let Some((address, comment)) = text
+        .strip_suffix(')')
+        .and_then(|rest| rest.split_once('('))
+        .filter(|(address, _)| address.contains('@'))
+    {
+        let name = comment.trim().to_string();
+        return (
+            Some(name).filter(|name| !name.is_empty()),
+            address.trim().to_string(),
+        );
+    }
+    (None, text.to_string())
+}
+
+/// Splits a list of mailboxes on commas, semicolons and newlines, except inside a
+/// quoted name or angle brackets, so `"Doe, Jane" <jane@x>, bob@x` is two. Blank
+/// entries are dropped.
+pub fn split_list(text: &str) -> Vec<&str> {
+    let mut entries = Vec::new();
+    let (mut start, mut quoted, mut bracketed, mut escaped) = (0, false, false, false);
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '<' if !quoted => bracketed = true,
+            '>' if !quoted => bracketed = false,
+            ',' | ';' | '\n' if !quoted && !bracketed => {
+                entries.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    entries.push(&text[start..]);
+    entries
+        .into_iter()
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// `"Doe, Jane"` to `Doe, Jane`, with `\"` and `\\` un-escaped. Unquoted names are kept as they are.
+fn unquote(name: &str) -> String {
+    let Some(quoted) = name
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    else {
+        return name.to_string();
+    };
+    let mut unquoted = String::with_capacity(quoted.len());
+    let mut chars = quoted.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+    unquoted.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn idn_domains_are_punycoded() {
+        assert_eq!(
+            parse_address("mueller@bücher.example").unwrap().to_string(),
+            "mueller@xn--bcher-kva.example"
+        );
+        let mailbox = parse_mailbox("Jürgen Müller <j@BÜCHER.example>").unwrap();
+        assert_eq!(mailbox.name.as_deref(), Some("Jürgen Müller"));
+        assert_eq!(mailbox.email.to_string(), "j@xn--bcher-kva.example");
+        // ASCII domains are left as they are
+        assert_eq!(
+            parse_address("ada@Example.com").unwrap().to_string(),
+            "ada@Example.com"
+        );
+    }
+
+    #[test]
+    fn non_ascii_local_parts_are_detected() {
+        assert!(needs_smtputf8("müller@example.com"));
+        assert!(!needs_smtputf8("mueller@bücher.example"));
+        assert!(!needs_smtputf8("not an address"));
+    }
+
+    #[test]
+    fn quoted_display_names_keep_their_commas() {
+        assert_eq!(
+            split_mailbox(" \"Doe, Jane\" <jane.doe@example.com> "),
+            (
+                Some("Doe, Jane".to_string()),
+                "jane.doe@example.com".to_string()
+            )
+        );
+        assert_eq!(
+            split_mailbox(r#""Jane \"JD\" Doe" <jd@example.com>"#),
+            (
+                Some(r#"Jane "JD" Doe"#.to_string()),
+                "jd@example.com".to_string()
+            )
+        );
+        assert_eq!(
+            split_mailbox("Jane Doe <jane@example.com>"),
+            (Some("Jane Doe".to_string()), "jane@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn lists_split_outside_quotes_and_brackets() {
+        assert_eq!(
+            split_list("\"Doe, Jane\" <jane@example.com>, bob@example.com;\n\n"),
+            ["\"Doe, Jane\" <jane@example.com>", "bob@example.com"]
+        );
+        assert_eq!(
+            split_list(r#""Al \"A;B\" Smith" <al@example.com>; <odd,one@example.com>"#),
+            [
+                r#""Al \"A;B\" Smith" <al@example.com>"#,
+                "<odd,one@example.com>"
+            ]
+        );
+        assert!(split_list(" , ; ").is_empty());
+    }
+
+    #[test]
+    fn angle_brackets_without_a_name() {
+        assert_eq!(
+            split_mailbox("<jane@example.com>"),
+            (None, "jane@example.com".to_string())
+        );
+        assert_eq!(
+            split_mailbox("\"\" < jane@example.com >"),
+            (None, "jane@example.com".to_string())
+        );
+    }
+
+    #[test]
+    fn plain_addresses_and_comments() {
+        assert_eq!(
+            split_mailbox(" jane@example.com "),
+            (None, "jane@example.com".to_string())
+        );
+        assert_eq!(
+            split_mailbox("jane@example.com (Jane Doe)"),
+            (Some("Jane Doe".to_string()), "jane@example.com".to_string())
+        );
+        // Not an address with a comment, so nothing to split
+        assert_eq!(
+            split_mailbox("Jane (work)"),
+            (None, "Jane (work)".to_string())
+        );
+    }
+}
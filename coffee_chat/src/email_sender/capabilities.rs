@@ -0,0 +1,214 @@
+// src/email_sender/capabilities.rs
+// What the SMTP server advertises in its EHLO reply, read once per server for the
+// session. lettre keeps only the extensions it uses itself, and SIZE (the largest
+// message the server takes) isn't one of them, so the reply is read here.
+// Messages built for a server that's been checked use it: 8-bit bodies only where
+// 8BITMIME is advertised, and anything over SIZE fails before it's sent.
+
+use super::{connect_address, require_localhost_for_plaintext, EmailError};
+use crate::config::{SmtpConfig, SmtpTlsMode};
+use lettre::transport::smtp::client::{SmtpConnection, TlsParameters};
+use lettre::transport::smtp::commands::Ehlo;
+use lettre::transport::smtp::extension::ClientId;
+use log::info;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+// Only the greeting and two EHLOs, so a slow server is a dead one
+const CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+// "host:port" -> what it advertised, for the rest of the session
+static CHECKED: Mutex<BTreeMap<String, ServerCapabilities>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub eight_bit_mime: bool,
+    pub smtp_utf8: bool,
+    pub starttls: bool,
+    pub size: Option<usize>, // Largest message in bytes; `None` when there's no limit given
+    pub auth: Vec<String>,   // Login mechanisms, e.g. PLAIN and LOGIN
+}
+
+impl ServerCapabilities {
+    /// Reads the lines of an EHLO reply: the greeting, then one extension per line.
+    pub fn from_ehlo<'a>(lines: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut capabilities = ServerCapabilities::default();
+        for line in lines.into_iter().skip(1) {
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            match keyword.to_ascii_uppercase().as_str() {
+                "8BITMIME" => capabilities.eight_bit_mime = true,
+                "SMTPUTF8" => capabilities.smtp_utf8 = true,
+                "STARTTLS" => capabilities.starttls = true,
+                // "SIZE 0" and a bare "SIZE" both mean no fixed limit
+                "SIZE" => {
+                    capabilities.size = words
+                        .next()
+                        .and_then(|size| size.parse().ok())
+                        .filter(|&size| size > 0)
+                }
+                "AUTH" => capabilities.auth.extend(words.map(str::to_ascii_uppercase)),
+                _ => {}
+            }
+        }
+        capabilities
+    }
+
+    /// "8BITMIME, SMTPUTF8, STARTTLS, up to 35.0 MB, AUTH PLAIN LOGIN"
+    pub fn summary(&self) -> String {
+        let mut parts: Vec<String> = [
+            (self.eight_bit_mime, "8BITMIME"),
+            (self.smtp_utf8, "SMTPUTF8"),
+            (self.starttls, "STARTTLS"),
+        ]
+        .into_iter()
+        .filter(|(supported, _)| *supported)
+        .map(|(_, name)| name.to_string())
+        .collect();
+        parts.push(match self.size {
+            Some(size) => format!("up to {:.1} MB", size as f64 / 1_000_000.0),
+            None => "no size limit given".to_string(),
+        });
+        if !self.auth.is_empty() {
+            parts.push(format!("AUTH {}", self.auth.join(" ")));
+        }
+        parts.join(", ")
+    }
+
+    /// Fails a message of `bytes` that's over the server's SIZE, which the server would
+    /// otherwise only refuse after it was all uploaded.
+    pub fn check_size(&self, bytes: usize) -> Result<(), EmailError> {
+        match self.size {
+            Some(limit) if bytes > limit => Err(EmailError::TooLarge { bytes, limit }),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn server_key(config: &SmtpConfig) -> String {
+    format!("{}:{}", config.host.trim().to_lowercase(), config.port)
+}
+
+/// What the server in `config` advertised, if it's been checked this session.
+pub fn cached(config: &SmtpConfig) -> Option<ServerCapabilities> {
+    CHECKED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .get(&server_key(config))
+        .cloned()
+}
+
+/// What the server advertises, checking it if this session hasn't yet. Blocks.
+pub fn detect(config: &SmtpConfig) -> Result<ServerCapabilities, EmailError> {
+    match cached(config) {
+        Some(capabilities) => Ok(capabilities),
+        None => refresh(config),
+    }
+}
+
+/// Connects and asks the server what it supports, replacing what was cached. Blocks.
+pub fn refresh(config: &SmtpConfig) -> Result<ServerCapabilities, EmailError> {
+    let capabilities = check(config)?;
+    info!(
+        "SMTP server {}:{} supports: {}",
+        config.host,
+        config.port,
+        capabilities.summary()
+    );
+    CHECKED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(server_key(config), capabilities.clone());
+    Ok(capabilities)
+}
+
+/// Connects the way a send would, without logging in, and reads the EHLO reply.
+/// After STARTTLS, since servers may only list some extensions once it's encrypted.
+fn check(config: &SmtpConfig) -> Result<ServerCapabilities, EmailError> {
+    require_localhost_for_plaintext(config)?;
+    let tls_parameters = || {
+        TlsParameters::new(config.host.clone())
+            .map_err(|e| EmailError::TlsConfig(format!("Invalid SMTP host for TLS: {}", e)))
+    };
+    let implicit_tls = match config.tls_mode {
+        SmtpTlsMode::Implicit => Some(tls_parameters()?),
+        _ => None,
+    };
+    let (host, port) = connect_address(config)?;
+    let hello = ClientId::default();
+    let mut connection = SmtpConnection::connect(
+        (host.as_str(), port),
+        Some(CHECK_TIMEOUT),
+        &hello,
+        implicit_tls.as_ref(),
+        None,
+    )
+    .map_err(EmailError::ServerCheck)?;
+    if config.tls_mode == SmtpTlsMode::Starttls {
+        connection
+            .starttls(&tls_parameters()?, &hello)
+            .map_err(EmailError::ServerCheck)?;
+    }
+    // lettre read the reply already, but only keeps what it uses; asking again is allowed
+    let reply = connection.command(Ehlo::new(hello));
+    connection.quit().ok();
+    let reply = reply.map_err(EmailError::ServerCheck)?;
+    Ok(ServerCapabilities::from_ehlo(reply.message()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_ehlo_reply_is_read() {
+        let capabilities = ServerCapabilities::from_ehlo([
+            "mail.example.com Hello [10.0.0.5]",
+            "SIZE 35882577",
+            "8BITMIME",
+            "AUTH LOGIN PLAIN XOAUTH2",
+            "ENHANCEDSTATUSCODES",
+            "pipelining",
+            "smtputf8",
+        ]);
+        assert_eq!(
+            capabilities,
+            ServerCapabilities {
+                eight_bit_mime: true,
+                smtp_utf8: true,
+                starttls: false,
+                size: Some(35_882_577),
+                auth: vec!["LOGIN".into(), "PLAIN".into(), "XOAUTH2".into()],
+            }
+        );
+        assert_eq!(
+            capabilities.summary(),
+            "8BITMIME, SMTPUTF8, up to 35.9 MB, AUTH LOGIN PLAIN XOAUTH2"
+        );
+    }
+
+    #[test]
+    fn only_a_positive_size_is_a_limit() {
+        for reply in [
+            ["relay", "SIZE 0"],
+            ["relay", "SIZE"],
+            ["relay", "8BITMIME"],
+        ] {
+            let capabilities = ServerCapabilities::from_ehlo(reply);
+            assert_eq!(capabilities.size, None, "{:?}", reply);
+            assert!(capabilities.check_size(usize::MAX).is_ok());
+        }
+        let limited = ServerCapabilities::from_ehlo(["relay", "SIZE 1000"]);
+        assert!(limited.check_size(1000).is_ok());
+        assert!(matches!(
+            limited.check_size(1001),
+            Err(EmailError::TooLarge {
+                bytes: 1001,
+                limit: 1000
+            })
+        ));
+    }
+}
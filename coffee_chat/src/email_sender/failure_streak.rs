@@ -0,0 +1,91 @@
+// src/email_sender/failure_streak.rs
+// Stops a batch once emails keep failing back to back, e.g. when the password
+// changed or the server is down, instead of working through the whole list.
+// Scattered failures (a bad address here and there) don't stop it.
+
+/// Consecutive failures that stop a batch unless configured otherwise.
+pub const DEFAULT_ABORT_AFTER_FAILURES: u32 = 5;
+
+/// Counts failures in a send loop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FailureStreak {
+    limit: u32, // 0 = never stop
+    consecutive: u32,
+    total: u32,
+}
+
+impl FailureStreak {
+    pub fn new(limit: u32) -> Self {
+        Self {
+            limit,
+            consecutive: 0,
+            total: 0,
+        }
+    }
+
+    /// A successful email ends the streak.
+    pub fn success(&mut self) {
+        self.consecutive = 0;
+    }
+
+    pub fn failure(&mut self) {
+        self.consecutive += 1;
+        self.total += 1;
+    }
+
+    /// Whether the loop should stop before the next email.
+    pub fn should_abort(&self) -> bool {
+        self.limit > 0 && self.consecutive >= self.limit
+    }
+
+    pub fn consecutive(&self) -> u32 {
+        self.consecutive
+    }
+
+    pub fn total(&self) -> u32 {
+        self.total
+    }
+
+    /// Why the batch stopped, once it should, with `unsent` emails left over.
+    pub fn abort_reason(&self, unsent: usize) -> Option<String> {
+        self.should_abort().then(|| {
+            format!(
+                "Stopped after {} failures in a row; {} not sent",
+                self.consecutive, unsent
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_failures_in_a_row_stop_the_batch() {
+        let mut streak = FailureStreak::new(3);
+        streak.failure();
+        streak.failure();
+        streak.success();
+        streak.failure();
+        streak.failure();
+        assert!(!streak.should_abort());
+        assert_eq!((streak.consecutive(), streak.total()), (2, 4));
+        streak.failure();
+        assert!(streak.should_abort());
+        assert_eq!(
+            streak.abort_reason(12).as_deref(),
+            Some("Stopped after 3 failures in a row; 12 not sent")
+        );
+    }
+
+    #[test]
+    fn zero_never_stops() {
+        let mut streak = FailureStreak::new(0);
+        for _ in 0..50 {
+            streak.failure();
+        }
+        assert!(!streak.should_abort());
+        assert_eq!(streak.abort_reason(1), None);
+    }
+}
@@ -0,0 +1,262 @@
+// src/email_sender/field_check.rs
+// Which custom fields a template needs from every recipient, and who lacks them.
+// A recipient without a field the template prints fails to render, which
+// otherwise only shows up mid-send, one failed email at a time.
+
+use super::template::BUILT_IN_VARIABLES;
+use std::collections::{BTreeMap, BTreeSet};
+use tera::ast::{Expr, ExprVal, FunctionCall, Node};
+use tera::{Error as TeraError, Tera};
+
+/// Custom fields the template parts need: variables they use that aren't built in,
+/// set or looped over by the template itself, checked by an `if`, or given a
+/// `default` filter. `{% if company %}...{{ company }}...{% endif %}` needs nothing.
+pub fn required_fields(parts: &[&str]) -> Result<BTreeSet<String>, TeraError> {
+    let mut walker = Walker::default();
+    for part in parts {
+        let mut tera = Tera::default();
+        tera.add_raw_template("field_check", part)?;
+        walker.nodes(&tera.get_template("field_check")?.ast);
+    }
+    Ok(walker.required)
+}
+
+/// For each required field, the positions of the recipients that don't have it.
+/// A field set to an empty value counts as provided; it renders blank.
+pub fn missing_fields<'a>(
+    required: &BTreeSet<String>,
+    recipients: impl IntoIterator<Item = &'a BTreeMap<String, String>>,
+) -> BTreeMap<String, Vec<usize>> {
+    let mut missing: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, fields) in recipients.into_iter().enumerate() {
+        for field in required.iter().filter(|field| !fields.contains_key(*field)) {
+            missing.entry(field.clone()).or_default().push(index);
+        }
+    }
+    missing
+}
+
+#[derive(Default)]
+struct Walker {
+    required: BTreeSet<String>,
+    defined: Vec<String>, // Set or looped over; loop variables are dropped after the loop
+    guarded: Vec<String>, // Checked by the `if` around the current branch
+}
+
+impl Walker {
+    fn nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            self.node(node);
+        }
+    }
+
+    fn node(&mut self, node: &Node) {
+        match node {
+            Node::VariableBlock(_, expr) => self.expr(expr),
+            Node::Set(_, set) => {
+                self.expr(&set.value);
+                self.defined.push(set.key.clone());
+            }
+            Node::FilterSection(_, section, _) => {
+                self.call(&section.filter);
+                self.nodes(&section.body);
+            }
+            Node::Block(_, block, _) => self.nodes(&block.body),
+            Node::Forloop(_, forloop, _) => {
+                self.expr(&forloop.container);
+                let defined = self.defined.len();
+                self.defined.push(forloop.value.clone());
+                self.defined.extend(forloop.key.clone());
+                self.nodes(&forloop.body);
+                self.defined.truncate(defined);
+                if let Some(empty_body) = &forloop.empty_body {
+                    self.nodes(empty_body);
+                }
+            }
+            Node::If(branches, _) => {
+                for (_, condition, body) in &branches.conditions {
+                    let guarded = self.guarded.len();
+                    match guards(condition) {
+                        Some(names) => self.guarded.extend(names),
+                        None => self.expr(condition),
+                    }
+                    self.nodes(body);
+                    self.guarded.truncate(guarded);
+                }
+                if let Some((_, body)) = &branches.otherwise {
+                    self.nodes(body);
+                }
+            }
+            // Macros only see their own arguments, and the rest has no variables
+            _ => {}
+        }
+    }
+
+    fn expr(&mut self, expr: &Expr) {
+        for filter in &expr.filters {
+            self.call(filter);
+        }
+        if expr.has_default_filter() && matches!(expr.val, ExprVal::Ident(_)) {
+            return;
+        }
+        self.value(&expr.val);
+    }
+
+    fn value(&mut self, value: &ExprVal) {
+        match value {
+            ExprVal::Ident(name) => self.ident(name),
+            ExprVal::Math(math) => {
+                self.expr(&math.lhs);
+                self.expr(&math.rhs);
+            }
+            ExprVal::Logic(logic) => {
+                self.expr(&logic.lhs);
+                self.expr(&logic.rhs);
+            }
+            ExprVal::In(within) => {
+                self.expr(&within.lhs);
+                self.expr(&within.rhs);
+            }
+            ExprVal::Test(test) => {
+                if !is_definedness_test(&test.name) {
+                    self.ident(&test.ident);
+                }
+                for arg in &test.args {
+                    self.expr(arg);
+                }
+            }
+            ExprVal::MacroCall(call) => {
+                for arg in call.args.values() {
+                    self.expr(arg);
+                }
+            }
+            ExprVal::FunctionCall(call) => self.call(call),
+            ExprVal::Array(items) => {
+                for item in items {
+                    self.expr(item);
+                }
+            }
+            ExprVal::StringConcat(concat) => {
+                for value in &concat.values {
+                    self.value(value);
+                }
+            }
+            ExprVal::String(_) | ExprVal::Int(_) | ExprVal::Float(_) | ExprVal::Bool(_) => {}
+        }
+    }
+
+    fn call(&mut self, call: &FunctionCall) {
+        for arg in call.args.values() {
+            self.expr(arg);
+        }
+    }
+
+    fn ident(&mut self, name: &str) {
+        // `company.city` and `fields["x"]` both need `company` / `fields`
+        let root = name.split(['.', '[']).next().unwrap_or(name);
+        let known = root == "loop"
+            || BUILT_IN_VARIABLES
+                .iter()
+                .any(|variable| variable.name == root)
+            || self.defined.iter().any(|defined| defined == root)
+            || self.guarded.iter().any(|guarded| guarded == root);
+        if !known {
+            self.required.insert(root.to_string());
+        }
+    }
+}
+
+fn is_definedness_test(name: &str) -> bool {
+    name == "defined" || name == "undefined"
+}
+
+/// The variables an `if` condition checks, when it only checks whether they're
+/// set: `company`, `not company`, `company is defined`, joined by `and`/`or`.
+/// Tera treats a missing variable there as false instead of failing.
+fn guards(condition: &Expr) -> Option<Vec<String>> {
+    if !condition.filters.is_empty() {
+        return None;
+    }
+    match &condition.val {
+        ExprVal::Ident(name) => Some(vec![root(name)]),
+        ExprVal::Test(test) if is_definedness_test(&test.name) => Some(vec![root(&test.ident)]),
+        ExprVal::Logic(logic)
+            if matches!(
+                logic.operator,
+                tera::ast::LogicOperator::And | tera::ast::LogicOperator::Or
+            ) =>
+        {
+            let mut names = guards(&logic.lhs)?;
+            names.extend(guards(&logic.rhs)?);
+            Some(names)
+        }
+        _ => None,
+    }
+}
+
+fn root(name: &str) -> String {
+    name.split(['.', '[']).next().unwrap_or(name).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn required(parts: &[&str]) -> Vec<String> {
+        required_fields(parts).unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn only_unguarded_custom_variables_are_required() {
+        let body = "Hi {{ recipient_name }} at {{ company }},\n\
+            {% if title %}As {{ title }}, {% endif %}\
+            {% if city is defined %}in {{ city }}{% else %}{{ region }}{% endif %}\n\
+            {% set sign_off = \"Best\" %}{{ sign_off }}, {{ team | default(value=\"us\") }}\n\
+            {% for time in availabilities %}- {{ time }} ({{ loop.index }}){% endfor %}\n\
+            {% if seniority == \"senior\" %}{{ referral.name | upper }}{% endif %}";
+        assert_eq!(
+            required(&["Coffee at {{ company }}?", body, "{{ assistant_email }}"]),
+            [
+                "assistant_email",
+                "company",
+                "referral",
+                "region",
+                "seniority"
+            ]
+        );
+    }
+
+    #[test]
+    fn loop_variables_end_with_the_loop() {
+        assert_eq!(
+            required(&["{% for slot in slots %}{{ slot.text }}{% endfor %}{{ slot }}"]),
+            ["slot"]
+        );
+    }
+
+    #[test]
+    fn gaps_are_listed_per_field() {
+        let required = required_fields(&["{{ company }} {{ title }}"]).unwrap();
+        let recipients: Vec<BTreeMap<String, String>> = (0..30)
+            .map(|i| {
+                let mut fields = BTreeMap::new();
+                if i % 2 == 0 {
+                    fields.insert("company".to_string(), format!("Company {}", i));
+                }
+                if i < 25 {
+                    fields.insert("title".to_string(), String::new()); // Blank still renders
+                }
+                fields
+            })
+            .collect();
+        let missing = missing_fields(&required, &recipients);
+        assert_eq!(missing["company"].len(), 15);
+        assert_eq!(missing["company"][..3], [1, 3, 5]);
+        assert_eq!(missing["title"], [25, 26, 27, 28, 29]);
+    }
+
+    #[test]
+    fn a_broken_template_is_an_error() {
+        assert!(required_fields(&["{{ company"]).is_err());
+    }
+}
@@ -0,0 +1,387 @@
+// src/email_sender/history.rs
+// Emails the app has sent, kept with the session so a cancellation or reschedule
+// can be sent later as a reply in the same conversation.
+
+use crate::calendar::free_busy::is_slot_line;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+/// Entries kept; older ones are dropped first.
+pub const MAX_HISTORY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailKind {
+    Invitation,
+    Cancellation,
+    Reschedule,
+}
+
+impl fmt::Display for EmailKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            EmailKind::Invitation => "Invitation",
+            EmailKind::Cancellation => "Cancellation",
+            EmailKind::Reschedule => "Reschedule",
+        })
+    }
+}
+
+/// One email and how it went.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SentEmail {
+    pub kind: EmailKind,
+    pub name: String,
+    pub email: String,
+    pub subject: String,
+    pub sent_at: DateTime<Utc>,
+    pub message_id: Option<String>,      // What a follow-up replies to
+    pub outcome: Result<String, String>, // Server reply, or why it failed
+    #[serde(default)]
+    pub event: Option<String>, // What happened to the calendar event, for cancellations
+    #[serde(default)]
+    pub slots: Option<Vec<RecordedSlot>>, // The availability list as sent; `None` for older entries
+    #[serde(default)]
+    pub campaign: Option<String>, // e.g. "Spring alumni outreach"; follow-ups keep the invitation's
+}
+
+/// One line of the availability list an email went out with.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedSlot {
+    pub text: String,
+    /// The free time it was written from; `None` for the other lines and for
+    /// slots pasted in by hand
+    #[serde(default)]
+    pub window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+}
+
+impl SentEmail {
+    /// Whether a follow-up can reply to this one.
+    pub fn can_follow_up(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+/// Adds `entry`, dropping the oldest entries past [`MAX_HISTORY`].
+pub fn record(history: &mut Vec<SentEmail>, entry: SentEmail) {
+    history.push(entry);
+    if history.len() > MAX_HISTORY {
+        let excess = history.len() - MAX_HISTORY;
+        history.drain(..excess);
+    }
+}
+
+/// A campaign name as typed: trimmed, and `None` when that leaves nothing.
+pub fn campaign_name(typed: &str) -> Option<String> {
+    let name = typed.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Every campaign in `history`, the most recently used first.
+pub fn campaigns(history: &[SentEmail]) -> Vec<String> {
+    let mut seen = BTreeSet::new();
+    history
+        .iter()
+        .rev()
+        .filter_map(|entry| entry.campaign.clone())
+        .filter(|campaign| seen.insert(campaign.clone()))
+        .collect()
+}
+
+/// Moves every entry of campaign `from` to `to`, or out of any campaign when `to`
+/// is blank. Renaming onto an existing campaign merges the two. Returns how many
+/// entries changed.
+pub fn rename_campaign(history: &mut [SentEmail], from: &str, to: &str) -> usize {
+    let to = campaign_name(to);
+    let mut renamed = 0;
+    for entry in history
+        .iter_mut()
+        .filter(|entry| entry.campaign.as_deref() == Some(from))
+    {
+        entry.campaign = to.clone();
+        renamed += 1;
+    }
+    renamed
+}
+
+/// How one campaign went, counted from the history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CampaignStats {
+    pub invited: usize,    // People an invitation reached the server for
+    pub failed: usize,     // Emails the server refused
+    pub follow_ups: usize, // Cancellations and reschedules sent
+    pub booked: usize,     // Invited people with a meeting booked from the app
+}
+
+impl CampaignStats {
+    /// Share of the invited who booked, the nearest thing to a reply rate the app sees.
+    pub fn booked_percent(&self) -> Option<usize> {
+        (self.invited > 0).then(|| (self.booked * 100 + self.invited / 2) / self.invited)
+    }
+}
+
+/// Stats per campaign, with `None` for emails sent outside any. `booked` are the
+/// addresses meetings were booked with.
+pub fn campaign_stats<'a>(
+    history: &[SentEmail],
+    booked: impl IntoIterator<Item = &'a str>,
+) -> BTreeMap<Option<String>, CampaignStats> {
+    let booked: BTreeSet<String> = booked.into_iter().map(str::to_lowercase).collect();
+    let mut invited: BTreeMap<Option<String>, BTreeSet<String>> = BTreeMap::new();
+    let mut stats: BTreeMap<Option<String>, CampaignStats> = BTreeMap::new();
+    for entry in history {
+        let campaign = stats.entry(entry.campaign.clone()).or_default();
+        match (&entry.outcome, entry.kind) {
+            (Err(_), _) => campaign.failed += 1,
+            (Ok(_), EmailKind::Invitation) => {
+                invited
+                    .entry(entry.campaign.clone())
+                    .or_default()
+                    .insert(entry.email.to_lowercase());
+            }
+            (Ok(_), _) => campaign.follow_ups += 1,
+        }
+    }
+    for (campaign, people) in invited {
+        let campaign = stats.entry(campaign).or_default();
+        campaign.invited = people.len();
+        campaign.booked = people.intersection(&booked).count();
+    }
+    stats
+}
+
+/// Pairs the lines an email listed with the windows behind its slots, which are
+/// in the same order: "+N more" and the timezone label get no window.
+pub fn record_slots(
+    lines: &[String],
+    windows: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> Vec<RecordedSlot> {
+    let mut windows = windows.iter();
+    lines
+        .iter()
+        .map(|line| RecordedSlot {
+            text: line.clone(),
+            window: if is_slot_line(line) {
+                windows.next().copied()
+            } else {
+                None
+            },
+        })
+        .collect()
+}
+
+/// Whether all of `window` is still inside `free`, e.g. the windows of a fresh
+/// fetch. Touching free windows count as one.
+pub fn still_free(
+    window: (DateTime<Utc>, DateTime<Utc>),
+    free: &[(DateTime<Utc>, DateTime<Utc>)],
+) -> bool {
+    let (start, end) = window;
+    let mut free = free.to_vec();
+    free.sort();
+    let mut covered_to = start;
+    for (free_start, free_end) in free {
+        if free_start <= covered_to && free_end > covered_to {
+            covered_to = free_end;
+        }
+    }
+    covered_to >= end
+}
+
+/// The subject without any leading "Re:"s, so a reply adds exactly one.
+pub fn original_subject(subject: &str) -> &str {
+    let mut subject = subject.trim();
+    while let Some(rest) = subject
+        .get(..3)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("re:"))
+        .map(|_| subject[3..].trim_start())
+    {
+        subject = rest;
+    }
+    subject
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn sent(subject: &str) -> SentEmail {
+        SentEmail {
+            kind: EmailKind::Invitation,
+            name: "Ana".to_string(),
+            email: "ana@example.com".to_string(),
+            subject: subject.to_string(),
+            sent_at: Utc::now(),
+            message_id: Some("<1@host>".to_string()),
+            outcome: Ok("250 Ok".to_string()),
+            event: None,
+            slots: None,
+            campaign: None,
+        }
+    }
+
+    fn in_campaign(campaign: &str, email: &str, kind: EmailKind) -> SentEmail {
+        SentEmail {
+            kind,
+            email: email.to_string(),
+            campaign: campaign_name(campaign),
+            ..sent("Coffee chat?")
+        }
+    }
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 4, 8, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn reply_prefixes_are_stripped() {
+        assert_eq!(original_subject("Coffee chat?"), "Coffee chat?");
+        assert_eq!(original_subject("Re: RE:re: Coffee chat?"), "Coffee chat?");
+        assert_eq!(original_subject("Regarding coffee"), "Regarding coffee");
+        assert_eq!(original_subject("Ré"), "Ré");
+    }
+
+    #[test]
+    fn oldest_entries_are_dropped_first() {
+        let mut history = Vec::new();
+        for n in 0..MAX_HISTORY + 3 {
+            record(&mut history, sent(&n.to_string()));
+        }
+        assert_eq!(history.len(), MAX_HISTORY);
+        assert_eq!(history[0].subject, "3");
+    }
+
+    #[test]
+    fn entries_round_trip_through_json() {
+        let mut failed = sent("Coffee chat?");
+        failed.outcome = Err("550 no such user".to_string());
+        let json = serde_json::to_string(&failed).unwrap();
+        assert!(json.contains("\"kind\":\"invitation\""));
+        let back: SentEmail = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, failed);
+        assert!(!back.can_follow_up());
+    }
+
+    #[test]
+    fn entries_from_before_slots_were_kept_still_load() {
+        let json = r#"{"kind":"invitation","name":"Ana","email":"ana@example.com",
+            "subject":"Coffee chat?","sent_at":"2025-04-08T12:00:00Z",
+            "message_id":null,"outcome":{"Ok":"250 Ok"}}"#;
+        let old: SentEmail = serde_json::from_str(json).unwrap();
+        assert_eq!(old.slots, None);
+    }
+
+    #[test]
+    fn only_slot_lines_get_a_window() {
+        let lines: Vec<String> = [
+            "Tuesday Apr 8: 9am–10am",
+            "Wednesday Apr 9: no availability",
+            "Wednesday Apr 9: 2pm–3pm",
+            "+3 more on request",
+            "(all times UTC)",
+        ]
+        .map(String::from)
+        .to_vec();
+        let windows = [(at(9), at(10)), (at(14), at(15)), (at(16), at(17))];
+        let recorded = record_slots(&lines, &windows);
+        let windows: Vec<_> = recorded.iter().map(|slot| slot.window).collect();
+        assert_eq!(
+            windows,
+            [
+                Some((at(9), at(10))),
+                None,
+                Some((at(14), at(15))),
+                None,
+                None
+            ]
+        );
+        // Pasted slots have no windows to go with them
+        assert!(record_slots(&lines, &[])
+            .iter()
+            .all(|slot| slot.window.is_none()));
+    }
+
+    #[test]
+    fn a_slot_is_free_while_fresh_windows_cover_it() {
+        let slot = (at(9), at(11));
+        assert!(still_free(slot, &[(at(8), at(12))]));
+        assert!(still_free(slot, &[(at(10), at(11)), (at(9), at(10))]));
+        assert!(!still_free(slot, &[(at(9), at(10))]));
+        assert!(!still_free(
+            slot,
+            &[
+                (at(9), at(10)),
+                (at(10) + chrono::Duration::minutes(30), at(11))
+            ]
+        ));
+        assert!(!still_free(slot, &[]));
+    }
+
+    #[test]
+    fn campaigns_are_listed_most_recent_first() {
+        let history = vec![
+            in_campaign("Spring alumni", "a@x.com", EmailKind::Invitation),
+            in_campaign("", "b@x.com", EmailKind::Invitation),
+            in_campaign("Mentors 2025", "c@x.com", EmailKind::Invitation),
+            in_campaign("Spring alumni", "d@x.com", EmailKind::Invitation),
+        ];
+        assert_eq!(campaigns(&history), ["Spring alumni", "Mentors 2025"]);
+    }
+
+    #[test]
+    fn renaming_updates_every_entry_of_the_campaign() {
+        let mut history = vec![
+            in_campaign("Spring", "a@x.com", EmailKind::Invitation),
+            in_campaign("Mentors", "b@x.com", EmailKind::Invitation),
+            in_campaign("Spring", "a@x.com", EmailKind::Reschedule),
+        ];
+        assert_eq!(
+            rename_campaign(&mut history, "Spring", " Spring alumni outreach "),
+            2
+        );
+        assert_eq!(campaigns(&history), ["Spring alumni outreach", "Mentors"]);
+        // Onto an existing name merges, and a blank name takes them out of any campaign
+        assert_eq!(
+            rename_campaign(&mut history, "Mentors", "Spring alumni outreach"),
+            1
+        );
+        assert_eq!(
+            rename_campaign(&mut history, "Spring alumni outreach", "  "),
+            3
+        );
+        assert!(history.iter().all(|entry| entry.campaign.is_none()));
+        assert_eq!(rename_campaign(&mut history, "Nope", "Other"), 0);
+    }
+
+    #[test]
+    fn stats_count_people_follow_ups_and_bookings_per_campaign() {
+        let mut refused = in_campaign("Spring", "e@x.com", EmailKind::Invitation);
+        refused.outcome = Err("550 no such user".to_string());
+        let history = vec![
+            in_campaign("Spring", "a@x.com", EmailKind::Invitation),
+            in_campaign("Spring", "b@x.com", EmailKind::Invitation),
+            in_campaign("Spring", "A@x.com", EmailKind::Invitation), // Sent again
+            in_campaign("Spring", "c@x.com", EmailKind::Invitation),
+            in_campaign("Spring", "a@x.com", EmailKind::Cancellation),
+            refused,
+            in_campaign("", "z@x.com", EmailKind::Invitation),
+        ];
+        let stats = campaign_stats(&history, ["a@X.com", "z@x.com", "q@x.com"]);
+        let spring = &stats[&Some("Spring".to_string())];
+        assert_eq!(
+            *spring,
+            CampaignStats {
+                invited: 3,
+                failed: 1,
+                follow_ups: 1,
+                booked: 1,
+            }
+        );
+        assert_eq!(spring.booked_percent(), Some(33));
+        assert_eq!(stats[&None].booked_percent(), Some(100));
+        assert_eq!(CampaignStats::default().booked_percent(), None);
+    }
+}
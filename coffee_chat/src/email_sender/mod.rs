@@ -1,23 +1,54 @@
 // Now brings in structs from the top-level config module
-use crate::config::{Recipient, SmtpConfig};
+use crate::config::{Recipient, SmtpConfig, SmtpTlsMode, WebhookConfig};
 // Use the new template module
+pub mod address;
+pub mod capabilities;
+pub mod failure_streak;
+pub mod field_check;
+pub mod history;
+pub mod post_process;
+pub mod priority;
+pub mod proxy;
+pub mod readability;
+pub mod receipt;
+pub mod registry;
+pub mod role_account;
+pub mod send_context;
+pub mod send_eta;
+pub mod spam_check;
+pub mod subject;
 pub mod template; // Make template module public if needed elsewhere, or keep private
+pub mod webhook;
+use post_process::EmailPostProcessor;
+use priority::Priority;
+use proxy::SmtpProxy;
+use receipt::SmtpReceipt;
 use template::{EmailTemplate, TemplateError};
 
 use lettre::{
     address::AddressError,
     // Import the general lettre error and address error
     error::Error as LettreError, // Rename to avoid conflict if needed
+    message::{
+        header::{ContentTransferEncoding, ContentType},
+        Body, Mailbox,
+    },
     transport::smtp::{
         authentication::Credentials,
         client::{Tls, TlsParameters},
     },
     Message,
     SmtpTransport,
-    Transport,
+    Transport as _,
 };
+use log::{debug, error, info, warn};
+use std::collections::HashSet;
+use std::time::Duration;
 use thiserror::Error;
 
+// How long a pretend send takes in demo mode, so progress is visible
+const DEMO_SEND_DELAY: Duration = Duration::from_millis(300);
+
 // --- Error Handling ---
 #[derive(Error, Debug)]
 pub enum EmailError {
@@ -42,62 +73,1309 @@ pub enum EmailError {
 
     #[error("General configuration error: {0}")]
     ConfigError(String),
+
+    #[error("Invalid SMTP proxy setting: {0}")]
+    InvalidProxy(String),
+
+    #[error("Couldn't reach the SMTP server through proxy {proxy}: {source}")]
+    Proxy {
+        proxy: String,
+        source: std::io::Error,
+    },
+
+    #[error("Rendered subject contains a line break at position {position}")]
+    SubjectLineBreak { position: usize },
+
+    #[error("'{address}' has non-ASCII characters before the @, which this SMTP server doesn't accept (no SMTPUTF8): {source}")]
+    NeedsSmtpUtf8 {
+        address: String,
+        source: lettre::transport::smtp::Error,
+    },
+
+    #[error("Failed to reach the webhook: {0}")]
+    Webhook(reqwest::Error),
+
+    #[error("Webhook returned HTTP {status}: {body}")]
+    WebhookStatus { status: u16, body: String },
+
+    #[error("The email is {bytes} bytes, more than the {limit} this SMTP server accepts (SIZE)")]
+    TooLarge { bytes: usize, limit: usize },
+
+    #[error("Couldn't check what the SMTP server supports: {0}")]
+    ServerCheck(lettre::transport::smtp::Error),
+
+    #[error("Invalid {field} address '{value}': {source}")]
+    InvalidCopyAddress {
+        field: &'static str,
+        value: String,
+        source: AddressError,
+    },
+}
+
+/// One recipient's email after rendering, ready to send as is.
+/// Quick send lets the user edit this before it goes out.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreparedEmail {
+    pub subject: String,
+    pub body: String,
+    pub cc: String, // Rendered address list, split and checked when building the message
+    pub bcc: String, // Same as `cc`
+    pub priority: Priority,
+    pub in_reply_to: Option<String>, // Message-ID this follows up on, so mail clients thread it
+}
+
+/// Where emails go. Everything that sends takes one of these, so callers don't
+/// care whether it's a real server, a webhook or demo mode.
+#[derive(Debug, Clone)]
+pub enum Transport {
+    Smtp(SmtpConfig),
+    Webhook(WebhookConfig),
+    /// Builds each email with these settings, so mistakes still show, then only pretends to send
+    Demo(SmtpConfig),
 }
 
 // --- Public Function ---
-/// Sends a coffee chat invitation email using loaded configuration and templates.
-pub async fn send_invitation_email(
+/// Sends an email that was rendered (and maybe edited) beforehand, as it is.
+pub async fn send_prepared_email(
+    transport: &Transport,
+    recipient: &Recipient,
+    sender_name: &str,
+    prepared: &PreparedEmail,
+) -> Result<SmtpReceipt, EmailError> {
+    match transport {
+        Transport::Smtp(smtp_config) => {
+            let email = build_prepared_message(smtp_config, recipient, sender_name, prepared)?;
+            deliver(smtp_config, recipient, &email)
+        }
+        Transport::Webhook(webhook_config) => {
+            webhook::deliver(webhook_config, recipient, prepared).await
+        }
+        Transport::Demo(smtp_config) => {
+            let email = build_prepared_message(smtp_config, recipient, sender_name, prepared)?;
+            Ok(pretend_to_deliver(recipient, &email).await)
+        }
+    }
+}
+
+fn deliver(
     smtp_config: &SmtpConfig,
+    recipient: &Recipient,
+    email: &Message,
+) -> Result<SmtpReceipt, EmailError> {
+    deliver_with(&build_transport(smtp_config)?, recipient, email)
+}
+
+/// Sends over a transport that's already set up, like a batch's `SendContext` keeps.
+fn deliver_with(
+    transport: &SmtpTransport,
+    recipient: &Recipient,
+    email: &Message,
+) -> Result<SmtpReceipt, EmailError> {
+    match transport.send(email) {
+        Ok(response) => {
+            let receipt = SmtpReceipt::from_response(&response).with_message_id(message_id(email));
+            info!(
+                "Email sent successfully to {} ({}): {}",
+                recipient.name,
+                recipient.email,
+                receipt.summary()
+            );
+            Ok(receipt)
+        }
+        Err(e) => {
+            error!("Error sending email to {}: {:?}", recipient.email, e);
+            // lettre refuses these itself when the server doesn't advertise SMTPUTF8
+            let envelope = email.envelope();
+            let utf8_address = envelope
+                .from()
+                .into_iter()
+                .chain(envelope.to())
+                .find(|address| !address.user().is_ascii());
+            match utf8_address {
+                Some(address) if e.is_client() => Err(EmailError::NeedsSmtpUtf8 {
+                    address: address.to_string(),
+                    source: e,
+                }),
+                _ => Err(EmailError::Send(e)),
+            }
+        }
+    }
+}
+
+async fn pretend_to_deliver(recipient: &Recipient, email: &Message) -> SmtpReceipt {
+    tokio::time::sleep(DEMO_SEND_DELAY).await;
+    info!(
+        "Demo mode: pretended to send {} bytes to {}",
+        email.formatted().len(),
+        recipient.email
+    );
+    SmtpReceipt {
+        code: "250".to_string(),
+        message: "Demo mode: not actually sent".to_string(),
+        message_id: message_id(email),
+    }
+}
+
+/// The Message-ID header `build_prepared_message` gave the email.
+fn message_id(email: &Message) -> Option<String> {
+    email
+        .headers()
+        .get_raw("Message-ID")
+        .map(|value| value.trim().to_string())
+}
+
+/// Renders the template for one recipient and runs the post-processor.
+pub fn prepare_email(
     recipient: &Recipient,
     sender_name: &str,
     availabilities: &[String],
     template: &EmailTemplate,
-) -> Result<(), EmailError> {
-    // --- Render Email Content ---
-    let (subject, body) = template.render(&recipient.name, sender_name, availabilities)?;
+    post_processor: &dyn EmailPostProcessor,
+) -> Result<PreparedEmail, EmailError> {
+    let (mut subject, mut body) = template.render(recipient, sender_name, availabilities)?;
+    post_processor.process(recipient, &mut subject, &mut body);
+    // CC/BCC may reference per-recipient fields, so they're rendered here too
+    let (cc, bcc) = template.render_copies(recipient, sender_name, availabilities)?;
+    Ok(PreparedEmail {
+        subject,
+        body,
+        cc,
+        bcc,
+        priority: template.priority,
+        in_reply_to: None,
+    })
+}
+
+/// Builds the `lettre::Message` for an already rendered email.
+pub fn build_prepared_message(
+    smtp_config: &SmtpConfig,
+    recipient: &Recipient,
+    sender_name: &str,
+    prepared: &PreparedEmail,
+) -> Result<Message, EmailError> {
+    // A CR or LF here would be folded oddly or rejected by the transport
+    if let Some(position) = subject::line_break_position(&prepared.subject) {
+        return Err(EmailError::SubjectLineBreak { position });
+    }
+    for warning in subject::subject_warnings(&prepared.subject) {
+        warn!("{} (to {})", warning, recipient.email);
+    }
+
+    // Show the sender's name next to the address when one is configured
+    let from_address = address::parse_address(&smtp_config.from_email)?; // Handles AddressError via From
+    let from_name = Some(sender_name.trim())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string);
+
+    let to = address::parse_mailbox(&recipient.email)?; // Handles AddressError via From
+    let (cc, bcc) = dedupe_copies(
+        &to,
+        parse_copy_list("CC", &prepared.cc)?,
+        parse_copy_list("BCC", &prepared.bcc)?,
+    );
 
     // --- Email Construction (lettre::Message) ---
-    let email = Message::builder()
-        .from(smtp_config.from_email.parse()?) // Handles AddressError via From
-        .to(recipient.email.parse()?) // Handles AddressError via From
-        .subject(subject)
-        // --- FIX: Use ? with LettreError ---
-        .body(body)?; // Handles LettreError via From
-
-    // --- SMTP Transport & Sending ---
+    let mut builder = Message::builder()
+        .from(Mailbox::new(from_name, from_address))
+        .to(to)
+        .subject(prepared.subject.clone())
+        .message_id(None) // Generates <uuid@hostname>, lettre doesn't add one on its own
+        // lettre picks a transfer encoding but sends no Content-Type, which readers treat as US-ASCII
+        .header(ContentType::TEXT_PLAIN);
+    for mailbox in cc {
+        builder = builder.cc(mailbox);
+    }
+    for mailbox in bcc {
+        builder = builder.bcc(mailbox);
+    }
+    for header in prepared.priority.headers() {
+        builder = builder.raw_header(header);
+    }
+    if let Some(message_id) = &prepared.in_reply_to {
+        builder = builder
+            .in_reply_to(message_id.clone())
+            .references(message_id.clone());
+    }
+    // lettre picks 7bit, quoted-printable or base64; 8bit only goes to a server
+    // that's been checked and advertises 8BITMIME
+    let capabilities = capabilities::cached(smtp_config);
+    let mut body = Body::new(prepared.body.clone());
+    if body.encoding() != ContentTransferEncoding::SevenBit
+        && capabilities.as_ref().is_some_and(|c| c.eight_bit_mime)
+    {
+        if let Ok(eight_bit) =
+            Body::new_with_encoding(prepared.body.clone(), ContentTransferEncoding::EightBit)
+        {
+            body = eight_bit;
+        }
+    }
+    // --- FIX: Use ? with LettreError ---
+    let email = builder.body(body)?; // Handles LettreError via From
+    if let Some(capabilities) = capabilities {
+        capabilities.check_size(email.formatted().len())?;
+    }
+
+    Ok(email)
+}
+
+/// Splits a rendered CC/BCC list on commas, semicolons and newlines, skipping blanks.
+/// A comma in a quoted name, as in `"Doe, Jane" <jane@x>`, doesn't split it.
+fn parse_copy_list(field: &'static str, rendered: &str) -> Result<Vec<Mailbox>, EmailError> {
+    address::split_list(rendered)
+        .into_iter()
+        .map(|value| {
+            let (name, email) = address::split_mailbox(value);
+            address::parse_address(&email)
+                .map(|email| Mailbox::new(name, email))
+                .map_err(|source| EmailError::InvalidCopyAddress {
+                    field,
+                    value: value.to_string(),
+                    source,
+                })
+        })
+        .collect()
+}
+
+/// Drops repeated addresses so nobody gets the email twice. Each address keeps
+/// its most visible placement (To, then CC, then BCC); comparison ignores case.
+fn dedupe_copies(
+    to: &Mailbox,
+    cc: Vec<Mailbox>,
+    bcc: Vec<Mailbox>,
+) -> (Vec<Mailbox>, Vec<Mailbox>) {
+    let mut seen = HashSet::from([to.email.to_string().to_lowercase()]);
+    let mut keep_first = |field: &str, mailboxes: Vec<Mailbox>| -> Vec<Mailbox> {
+        mailboxes
+            .into_iter()
+            .filter(|mailbox| {
+                let fresh = seen.insert(mailbox.email.to_string().to_lowercase());
+                if !fresh {
+                    debug!("Dropping duplicate {} address {}", field, mailbox.email);
+                }
+                fresh
+            })
+            .collect()
+    };
+    let cc = keep_first("CC", cc);
+    let bcc = keep_first("BCC", bcc);
+    (cc, bcc)
+}
+
+// How many times this thread built a transport, for the tests of reusing one
+#[cfg(test)]
+thread_local! {
+    pub(crate) static TRANSPORTS_BUILT: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+/// Builds the SMTP transport according to the configured TLS mode.
+pub fn build_transport(smtp_config: &SmtpConfig) -> Result<SmtpTransport, EmailError> {
+    #[cfg(test)]
+    TRANSPORTS_BUILT.with(|built| built.set(built.get() + 1));
+    require_localhost_for_plaintext(smtp_config)?;
+    let (connect_host, port) = connect_address(smtp_config)?;
+
+    let builder = match smtp_config.tls_mode {
+        SmtpTlsMode::Starttls | SmtpTlsMode::Implicit => {
+            let tls_parameters = TlsParameters::new(smtp_config.host.clone())
+                .map_err(|e| EmailError::TlsConfig(format!("Invalid SMTP host for TLS: {}", e)))?;
+            let tls = if smtp_config.tls_mode == SmtpTlsMode::Implicit {
+                Tls::Wrapper(tls_parameters)
+            } else {
+                Tls::Required(tls_parameters)
+            };
+            SmtpTransport::relay(&connect_host)
+                .map_err(EmailError::TransportCreation)?
+                .tls(tls)
+        }
+        SmtpTlsMode::PlaintextLocalhost => SmtpTransport::builder_dangerous(connect_host),
+    };
+
+    let builder = builder.port(port);
+    if !smtp_config.uses_auth() {
+        info!("Sending without SMTP authentication.");
+        return Ok(builder.build());
+    }
     let creds = Credentials::new(
         smtp_config.user.clone(),
         smtp_config.get_password().to_string(),
     );
+    Ok(builder.credentials(creds).build())
+}
 
-    let tls_parameters = TlsParameters::new(smtp_config.host.clone())
-        .map_err(|e| EmailError::TlsConfig(format!("Invalid SMTP host for TLS: {}", e)))?;
-
-    let transport = SmtpTransport::relay(&smtp_config.host)
-        .map_err(EmailError::TransportCreation)?
-        .port(smtp_config.port)
-        .credentials(creds)
-        .tls(Tls::Required(tls_parameters))
-        .build();
-
-    match transport.send(&email) {
-        Ok(_) => {
-            println!(
-                "Email sent successfully to {} ({})!",
-                recipient.name, recipient.email
+/// Where to connect for the server in `smtp_config`. Through a proxy, that's a local
+/// forwarder with a fresh tunnel; TLS still checks the real host name.
+fn connect_address(smtp_config: &SmtpConfig) -> Result<(String, u16), EmailError> {
+    Ok(match &smtp_config.proxy {
+        Some(url) => {
+            let proxy = SmtpProxy::parse(url).map_err(EmailError::InvalidProxy)?;
+            let local_port =
+                proxy
+                    .forward(&smtp_config.host, smtp_config.port)
+                    .map_err(|source| EmailError::Proxy {
+                        proxy: proxy.to_string(),
+                        source,
+                    })?;
+            info!(
+                "Connected to {}:{} via proxy {}",
+                smtp_config.host, smtp_config.port, proxy
             );
-            Ok(())
-        }
-        Err(e) => {
-            eprintln!("Error sending email to {}: {:?}", recipient.email, e);
-            Err(EmailError::Send(e))
+            ("127.0.0.1".to_string(), local_port)
         }
+        None => (smtp_config.host.clone(), smtp_config.port),
+    })
+}
+
+/// Plaintext is only allowed to a relay on this machine.
+fn require_localhost_for_plaintext(smtp_config: &SmtpConfig) -> Result<(), EmailError> {
+    if smtp_config.tls_mode == SmtpTlsMode::PlaintextLocalhost && !is_localhost(&smtp_config.host) {
+        return Err(EmailError::TlsConfig(format!(
+            "Plaintext SMTP is only allowed for localhost, not '{}'",
+            smtp_config.host
+        )));
     }
+    Ok(())
+}
+
+fn is_localhost(host: &str) -> bool {
+    matches!(
+        host.trim().to_ascii_lowercase().as_str(),
+        "localhost" | "127.0.0.1" | "::1" | "[::1]"
+    )
 }
 
 // --- Unit Tests ---
 #[cfg(test)]
 mod tests {
     // Basic tests might focus on template rendering logic now.
-    // Testing the full send_invitation_email requires more setup (mocking).
+    // Testing a full send requires more setup (mocking).
+    use super::*;
+    use crate::config::SenderConfig;
+    use post_process::NoopPostProcessor;
+    use secrecy::SecretString;
+    use std::path::PathBuf;
+
+    fn test_config() -> SmtpConfig {
+        SmtpConfig {
+            host: "localhost".to_string(),
+            port: 25,
+            user: String::new(),
+            password: SecretString::new(String::new().into()),
+            from_email: "me@example.com".to_string(),
+            tls_mode: SmtpTlsMode::PlaintextLocalhost,
+            proxy: None,
+            no_auth: false,
+        }
+    }
+
+    fn recipient_with_assistant(assistant: &str) -> Recipient {
+        let mut fields = std::collections::BTreeMap::new();
+        fields.insert("assistant_email".to_string(), assistant.to_string());
+        Recipient {
+            name: "Grace".to_string(),
+            email: "grace@example.com".to_string(),
+            fields,
+        }
+    }
+
+    /// The SMTP path of `SendContext::send_to`, up to the message it would send.
+    fn build_invitation_message(
+        smtp_config: &SmtpConfig,
+        recipient: &Recipient,
+        sender_name: &str,
+        availabilities: &[String],
+        template: &EmailTemplate,
+        post_processor: &dyn EmailPostProcessor,
+    ) -> Result<Message, EmailError> {
+        // --- Render Email Content ---
+        let prepared = prepare_email(
+            recipient,
+            sender_name,
+            availabilities,
+            template,
+            post_processor,
+        )?;
+        build_prepared_message(smtp_config, recipient, sender_name, &prepared)
+    }
+
+    fn header(message: &Message, name: &str) -> Option<String> {
+        message
+            .headers()
+            .get_raw(name)
+            .map(|value| value.to_string())
+    }
+
+    #[test]
+    fn auth_is_skipped_without_credentials_or_when_disabled() {
+        let mut config = test_config();
+        assert!(!config.uses_auth(), "empty user and password");
+        config.password = SecretString::new("secret".to_string().into());
+        assert!(config.uses_auth());
+        config.no_auth = true;
+        assert!(!config.uses_auth());
+    }
+
+    #[test]
+    fn renders_cc_and_bcc_per_recipient() {
+        let template = EmailTemplate::from_content("Hi", "Body", "copies")
+            .unwrap()
+            .with_copies("{{ assistant_email }}", "archive@example.com, ")
+            .unwrap();
+        let message = build_invitation_message(
+            &test_config(),
+            &recipient_with_assistant("assistant@example.com"),
+            "Me",
+            &[],
+            &template,
+            &NoopPostProcessor,
+        )
+        .unwrap();
+
+        assert_eq!(
+            header(&message, "Cc").as_deref(),
+            Some("assistant@example.com")
+        );
+        let envelope: Vec<String> = message
+            .envelope()
+            .to()
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        assert!(envelope.contains(&"archive@example.com".to_string()));
+    }
+
+    #[test]
+    fn archive_bcc_joins_every_individual_email() {
+        let template = EmailTemplate::from_content("Hi {{ recipient_name }}", "Body", "archive")
+            .unwrap()
+            .with_copies("", "{{ assistant_email }}")
+            .unwrap()
+            .with_archive_bcc(" outreach@team.example ");
+        let grace = recipient_with_assistant("assistant@example.com");
+        let prepared = prepare_email(&grace, "Me", &[], &template, &NoopPostProcessor).unwrap();
+        assert_eq!(prepared.subject, "Hi Grace");
+        assert_eq!(prepared.bcc, "assistant@example.com, outreach@team.example");
+
+        // Without a BCC template it's the only copy
+        let plain = EmailTemplate::from_content("Hi {{ recipient_name }}", "Body", "archive")
+            .unwrap()
+            .with_archive_bcc("outreach@team.example");
+        let prepared = prepare_email(&grace, "Me", &[], &plain, &NoopPostProcessor).unwrap();
+        assert_eq!(prepared.bcc, "outreach@team.example");
+        let message = build_prepared_message(&test_config(), &grace, "Me", &prepared).unwrap();
+        let envelope: Vec<String> = message
+            .envelope()
+            .to()
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        assert!(envelope.contains(&"outreach@team.example".to_string()));
+        assert!(header(&message, "Bcc").is_none());
+    }
+
+    #[test]
+    fn edited_emails_are_sent_as_written() {
+        let template = EmailTemplate::from_content("Hi {{ recipient_name }}", "Body", "edited")
+            .unwrap()
+            .with_copies("{{ assistant_email }}", "")
+            .unwrap();
+        let recipient = recipient_with_assistant("assistant@example.com");
+        let mut prepared =
+            prepare_email(&recipient, "Me", &[], &template, &NoopPostProcessor).unwrap();
+        assert_eq!(prepared.subject, "Hi Grace");
+        assert_eq!(prepared.cc, "assistant@example.com");
+
+        // Braces typed while editing are plain text, not template syntax
+        prepared.body = "Edited by hand {{ not_a_variable }}".to_string();
+        let message = build_prepared_message(&test_config(), &recipient, "Me", &prepared).unwrap();
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("Edited by hand {{ not_a_variable }}"));
+        assert_eq!(
+            header(&message, "Cc").as_deref(),
+            Some("assistant@example.com")
+        );
+    }
+
+    #[test]
+    fn follow_ups_thread_onto_the_original_email() {
+        let recipient = recipient_with_assistant("assistant@example.com");
+        let mut prepared = PreparedEmail {
+            subject: "Re: Coffee chat".to_string(),
+            body: "Body".to_string(),
+            ..PreparedEmail::default()
+        };
+        let original = build_prepared_message(&test_config(), &recipient, "Me", &prepared).unwrap();
+        assert!(header(&original, "In-Reply-To").is_none());
+        let original_id = message_id(&original).unwrap();
+
+        prepared.in_reply_to = Some(original_id.clone());
+        let follow_up =
+            build_prepared_message(&test_config(), &recipient, "Me", &prepared).unwrap();
+        assert_eq!(header(&follow_up, "In-Reply-To"), Some(original_id.clone()));
+        assert_eq!(header(&follow_up, "References"), Some(original_id.clone()));
+        assert_ne!(message_id(&follow_up), Some(original_id));
+    }
+
+    #[test]
+    fn priority_sets_headers_only_when_not_normal() {
+        let recipient = recipient_with_assistant("assistant@example.com");
+        let build = |priority| {
+            let template = EmailTemplate::from_content("Hi", "Body", "priority")
+                .unwrap()
+                .with_priority(priority);
+            build_invitation_message(
+                &test_config(),
+                &recipient,
+                "Me",
+                &[],
+                &template,
+                &NoopPostProcessor,
+            )
+            .unwrap()
+        };
+
+        let high = build(Priority::High);
+        assert_eq!(header(&high, "X-Priority").as_deref(), Some("1 (Highest)"));
+        assert_eq!(header(&high, "Importance").as_deref(), Some("High"));
+        assert_eq!(header(&high, "Priority").as_deref(), Some("urgent"));
+        let low = build(Priority::Low);
+        assert_eq!(header(&low, "X-Priority").as_deref(), Some("5 (Lowest)"));
+        assert_eq!(header(&low, "Importance").as_deref(), Some("Low"));
+        let normal = build(Priority::Normal);
+        assert!(header(&normal, "X-Priority").is_none());
+        assert!(header(&normal, "Importance").is_none());
+    }
+
+    #[test]
+    fn multi_line_subjects_are_rejected_per_recipient() {
+        let template =
+            EmailTemplate::from_content("Coffee with {{ team }}?", "Body", "subject").unwrap();
+        let mut recipient = recipient_with_assistant("assistant@example.com");
+        recipient
+            .fields
+            .insert("team".to_string(), "Infra\nand Ops".to_string());
+        let error = build_invitation_message(
+            &test_config(),
+            &recipient,
+            "Me",
+            &[],
+            &template,
+            &NoopPostProcessor,
+        )
+        .unwrap_err();
+        assert!(matches!(
+            error,
+            EmailError::SubjectLineBreak { position: 18 }
+        ));
+        assert_eq!(
+            error.to_string(),
+            "Rendered subject contains a line break at position 18"
+        );
+    }
+
+    #[test]
+    fn duplicate_addresses_keep_their_most_visible_placement() {
+        let template = EmailTemplate::from_content("Hi", "Body", "duplicates")
+            .unwrap()
+            .with_copies(
+                "Assistant@Example.com, boss@example.com, assistant@example.com",
+                "{{ assistant_email }}, BOSS@example.com, ada@example.com, archive@example.com",
+            )
+            .unwrap();
+        let mut recipient = recipient_with_assistant("assistant@example.com");
+        recipient.email = "ada@example.com".to_string();
+        let message = build_invitation_message(
+            &test_config(),
+            &recipient,
+            "Me",
+            &[],
+            &template,
+            &NoopPostProcessor,
+        )
+        .unwrap();
+
+        assert_eq!(
+            header(&message, "Cc").as_deref(),
+            Some("Assistant@Example.com, boss@example.com")
+        );
+        let mut envelope: Vec<String> = message
+            .envelope()
+            .to()
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        envelope.sort();
+        assert_eq!(
+            envelope,
+            vec![
+                "Assistant@Example.com",
+                "ada@example.com",
+                "archive@example.com",
+                "boss@example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn unavailable_days_are_available_to_templates() {
+        let body =
+            "{% if unavailable_days %}Booked: {{ unavailable_days | join(sep=\", \") }}{% endif %}";
+        let template = EmailTemplate::from_content("Hi", body, "unavailable").unwrap();
+        let recipient = recipient_with_assistant("assistant@example.com");
+        let (_, empty) = template.render(&recipient, "Me", &[]).unwrap();
+        assert_eq!(empty, "");
+
+        let template = template.with_unavailable_days(vec![
+            "Wednesday Apr 9".to_string(),
+            "Thursday Apr 10".to_string(),
+        ]);
+        let (_, body) = template.render(&recipient, "Me", &[]).unwrap();
+        assert_eq!(body, "Booked: Wednesday Apr 9, Thursday Apr 10");
+    }
+
+    fn sender(
+        template_path: Option<PathBuf>,
+        subject: Option<&str>,
+        body: Option<&str>,
+    ) -> SenderConfig {
+        SenderConfig {
+            name: "Me".to_string(),
+            template_path,
+            subject: subject.map(str::to_string),
+            body: body.map(str::to_string),
+            no_slots_text: None,
+            priority: None,
+            archive_bcc: None,
+            abort_after_failures: None,
+            role_accounts: None,
+        }
+    }
+
+    #[test]
+    fn empty_availabilities_use_the_fallback_text() {
+        let body = "{% for time in availabilities %}- {{ time }}\n{% endfor %}\
+                    {% if has_availabilities %}Pick one!{% else %}Any time works?{% endif %}";
+        let mut config = sender(None, Some("Hi"), Some(body));
+        let recipient = recipient_with_assistant("assistant@example.com");
+        let render = |config: &SenderConfig, slots: &[String]| {
+            EmailTemplate::from_sender_config(config)
+                .unwrap()
+                .render(&recipient, "Me", slots)
+                .unwrap()
+                .1
+        };
+
+        // No fallback configured: the list is just empty
+        assert_eq!(render(&config, &[]), "Any time works?");
+
+        config.no_slots_text = Some(" No times listed, let me know what works ".to_string());
+        assert_eq!(
+            render(&config, &[]),
+            "- No times listed, let me know what works\nAny time works?"
+        );
+        assert_eq!(
+            render(&config, &["Monday Apr 7: 9am–10am".to_string()]),
+            "- Monday Apr 7: 9am–10am\nPick one!"
+        );
+    }
+
+    #[test]
+    fn inline_template_in_config_needs_no_file() {
+        let missing = Some(PathBuf::from("/nonexistent/email_template.txt"));
+        let template = EmailTemplate::from_sender_config(&sender(
+            missing,
+            Some("Coffee?"),
+            Some("Hi {{ recipient_name }}"),
+        ))
+        .unwrap();
+        assert_eq!(template.subject_template, "Coffee?");
+        assert_eq!(template.body_template, "Hi {{ recipient_name }}");
+
+        assert!(matches!(
+            EmailTemplate::from_sender_config(&sender(None, Some("Coffee?"), None)),
+            Err(template::TemplateError::NoSource)
+        ));
+    }
+
+    #[test]
+    fn inline_subject_overrides_the_file() {
+        let path =
+            std::env::temp_dir().join(format!("coffee_chat_template_{}.txt", std::process::id()));
+        std::fs::write(&path, "Subject: From file\n---\nBody from file").unwrap();
+        let template =
+            EmailTemplate::from_sender_config(&sender(Some(path.clone()), Some("Inline"), None));
+        std::fs::remove_file(&path).ok();
+        let template = template.unwrap();
+        assert_eq!(template.subject_template, "Inline");
+        assert_eq!(template.body_template, "Body from file");
+    }
+
+    #[test]
+    fn blank_rendered_copy_is_skipped() {
+        let template = EmailTemplate::from_content("Hi", "Body", "blank_copies")
+            .unwrap()
+            .with_copies("{{ assistant_email }}", "")
+            .unwrap();
+        let message = build_invitation_message(
+            &test_config(),
+            &recipient_with_assistant(""),
+            "Me",
+            &[],
+            &template,
+            &NoopPostProcessor,
+        )
+        .unwrap();
+        assert!(header(&message, "Cc").is_none());
+    }
+
+    #[test]
+    fn quoted_names_in_a_copy_list_keep_their_commas() {
+        let copies =
+            parse_copy_list("CC", "\"Doe, Jane\" <jane@example.com>, bob@example.com").unwrap();
+        let names: Vec<_> = copies
+            .iter()
+            .map(|mailbox| (mailbox.name.as_deref(), mailbox.email.to_string()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (Some("Doe, Jane"), "jane@example.com".to_string()),
+                (None, "bob@example.com".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_rendered_copy_is_reported() {
+        let template = EmailTemplate::from_content("Hi", "Body", "bad_copies")
+            .unwrap()
+            .with_copies("{{ assistant_email }}", "")
+            .unwrap();
+        let result = build_invitation_message(
+            &test_config(),
+            &recipient_with_assistant("not-an-address"),
+            "Me",
+            &[],
+            &template,
+            &NoopPostProcessor,
+        );
+        assert!(matches!(
+            result,
+            Err(EmailError::InvalidCopyAddress { field: "CC", .. })
+        ));
+    }
+
+    #[test]
+    fn idn_recipient_domains_are_sent_in_punycode() {
+        let template = EmailTemplate::from_content("Hi", "Body", "idn")
+            .unwrap()
+            .with_copies("Jürgen <j@bücher.example>", "")
+            .unwrap();
+        let recipient = Recipient {
+            name: "Müller".to_string(),
+            email: "mueller@bücher.example".to_string(),
+            fields: Default::default(),
+        };
+        let message = build_invitation_message(
+            &test_config(),
+            &recipient,
+            "Me",
+            &[],
+            &template,
+            &NoopPostProcessor,
+        )
+        .unwrap();
+
+        let envelope: Vec<String> = message
+            .envelope()
+            .to()
+            .iter()
+            .map(|address| address.to_string())
+            .collect();
+        assert_eq!(
+            envelope,
+            vec!["mueller@xn--bcher-kva.example", "j@xn--bcher-kva.example"]
+        );
+        assert_eq!(
+            header(&message, "To").as_deref(),
+            Some("mueller@xn--bcher-kva.example")
+        );
+    }
+
+    #[test]
+    fn non_ascii_content_is_declared_and_encoded() {
+        let template = EmailTemplate::from_content(
+            "Café ☕ with {{ recipient_name }}?",
+            "Hi {{ recipient_name }}, ça te dit? 😀",
+            "unicode",
+        )
+        .unwrap();
+        let recipient = Recipient {
+            name: "Zoë".to_string(),
+            email: "zoe@example.com".to_string(),
+            fields: Default::default(),
+        };
+        let message = build_invitation_message(
+            &test_config(),
+            &recipient,
+            "Me",
+            &[],
+            &template,
+            &NoopPostProcessor,
+        )
+        .unwrap();
+
+        assert_eq!(
+            header(&message, "Content-Type").as_deref(),
+            Some("text/plain; charset=utf-8")
+        );
+        let encoding = header(&message, "Content-Transfer-Encoding").unwrap();
+        assert!(
+            encoding == "quoted-printable" || encoding == "base64",
+            "unexpected transfer encoding {}",
+            encoding
+        );
+
+        // Everything on the wire is 7-bit; the subject uses an RFC 2047 encoded word
+        let formatted = message.formatted();
+        assert!(formatted.is_ascii());
+        let formatted = String::from_utf8(formatted).unwrap();
+        assert!(formatted.contains("Subject: =?utf-8?"));
+    }
+
+    struct AppendLink;
+
+    impl EmailPostProcessor for AppendLink {
+        fn process(&self, recipient: &Recipient, subject: &mut String, body: &mut String) {
+            subject.push_str(" [booking]");
+            body.push_str(&format!(
+                "\nBook here: https://example.com/book?who={}",
+                recipient.name
+            ));
+        }
+    }
+
+    #[test]
+    fn post_processor_can_modify_subject_and_body() {
+        let template =
+            EmailTemplate::from_content("Coffee?", "Hi {{ recipient_name }}", "post").unwrap();
+        let message = build_invitation_message(
+            &test_config(),
+            &recipient_with_assistant("assistant@example.com"),
+            "Me",
+            &[],
+            &template,
+            &AppendLink,
+        )
+        .unwrap();
+
+        assert_eq!(
+            header(&message, "Subject").as_deref(),
+            Some("Coffee? [booking]")
+        );
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("Hi Grace\r\nBook here: https://example.com/book?who=Grace"));
+    }
+}
+
+// --- Integration Tests ---
+// Run with `cargo test --features integration-tests`. These talk to a tiny
+// in-process SMTP sink over a real localhost socket.
+#[cfg(all(test, feature = "integration-tests"))]
+mod integration_tests {
+    use super::*;
+    use post_process::NoopPostProcessor;
+    use secrecy::SecretString;
+    use send_context::SendContext;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::Duration;
+
+    /// Accepts one SMTP session on a random port and forwards each DATA payload.
+    fn start_smtp_sink() -> (u16, mpsc::Receiver<String>) {
+        start_smtp_sink_with_auth(true)
+    }
+
+    /// Like [`start_smtp_sink`]; without `auth` it behaves like an internal relay:
+    /// AUTH isn't advertised and is rejected with 503.
+    fn start_smtp_sink_with_auth(auth: bool) -> (u16, mpsc::Receiver<String>) {
+        start_smtp_sink_with(auth, &[], 1)
+    }
+
+    /// Like [`start_smtp_sink_with_auth`], advertising `extensions` in its EHLO reply
+    /// and taking `sessions` connections one after the other.
+    fn start_smtp_sink_with(
+        auth: bool,
+        extensions: &'static [&'static str],
+        sessions: usize,
+    ) -> (u16, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test sink");
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = mpsc::channel();
+        let mut ehlo = vec!["localhost"];
+        if auth {
+            ehlo.push("AUTH PLAIN LOGIN");
+        }
+        ehlo.extend(extensions);
+        let last = ehlo.len() - 1;
+        let ehlo_reply: String = ehlo
+            .iter()
+            .enumerate()
+            .map(|(i, line)| format!("250{}{}\r\n", if i == last { ' ' } else { '-' }, line))
+            .collect();
+
+        thread::spawn(move || {
+            for _ in 0..sessions {
+                serve_smtp_session(&listener, auth, ehlo_reply.as_bytes(), &tx);
+            }
+        });
+
+        (port, rx)
+    }
+
+    fn serve_smtp_session(
+        listener: &TcpListener,
+        auth: bool,
+        ehlo_reply: &[u8],
+        tx: &mpsc::Sender<String>,
+    ) {
+        let (stream, _) = listener.accept().expect("accept SMTP client");
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut writer = stream;
+        writer.write_all(b"220 localhost test sink\r\n").unwrap();
+
+        let mut line = String::new();
+        let mut data = String::new();
+        let mut in_data = false;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 {
+                break;
+            }
+            if in_data {
+                if line == ".\r\n" {
+                    in_data = false;
+                    writer
+                        .write_all(b"250 2.0.0 Ok: queued as TEST123\r\n")
+                        .unwrap();
+                    tx.send(std::mem::take(&mut data)).ok();
+                } else {
+                    data.push_str(&line);
+                }
+                continue;
+            }
+
+            let command = line.to_ascii_uppercase();
+            let reply: &[u8] = if command.starts_with("EHLO") {
+                ehlo_reply
+            } else if command.starts_with("AUTH") {
+                if auth {
+                    b"235 2.7.0 Authentication successful\r\n"
+                } else {
+                    b"503 5.5.1 AUTH not available\r\n"
+                }
+            } else if command.starts_with("DATA") {
+                in_data = true;
+                b"354 End data with <CR><LF>.<CR><LF>\r\n"
+            } else if command.starts_with("QUIT") {
+                writer.write_all(b"221 2.0.0 Bye\r\n").ok();
+                break;
+            } else {
+                b"250 2.0.0 Ok\r\n"
+            };
+            writer.write_all(reply).unwrap();
+        }
+    }
+
+    fn sink_config(port: u16) -> SmtpConfig {
+        SmtpConfig {
+            host: "127.0.0.1".to_string(),
+            port,
+            user: "sender@example.com".to_string(),
+            password: SecretString::new("test-password".to_string().into()),
+            from_email: "sender@example.com".to_string(),
+            tls_mode: SmtpTlsMode::PlaintextLocalhost,
+            proxy: None,
+            no_auth: false,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sends_invitation_through_smtp_sink() {
+        let (port, received) = start_smtp_sink();
+        let template = EmailTemplate::from_content(
+            "Coffee chat with {{ sender_name }}",
+            "Hi {{ recipient_name }},\n{% for time in availabilities %}- {{ time }}\n{% endfor %}",
+            "integration",
+        )
+        .unwrap();
+        let recipient = Recipient {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            fields: Default::default(),
+        };
+        let availabilities = vec![
+            "Monday May 12: 2pm-3pm".to_string(),
+            "Tuesday May 13: 10am-11:30am".to_string(),
+        ];
+
+        let receipt = SendContext::new(
+            Transport::Smtp(sink_config(port)),
+            "Test Sender",
+            availabilities,
+            template,
+        )
+        .send_to(&recipient, &NoopPostProcessor)
+        .await
+        .expect("send through sink");
+        assert_eq!(receipt.code, "250");
+        assert_eq!(receipt.queue_id(), Some("TEST123"));
+
+        let data = received
+            .recv_timeout(Duration::from_secs(10))
+            .expect("sink received DATA");
+
+        assert!(data.contains("From: \"Test Sender\" <sender@example.com>\r\n"));
+        assert!(data.contains("To: ada@example.com\r\n"));
+        assert!(data.contains("Subject: Coffee chat with Test Sender\r\n"));
+        assert!(data.contains("Message-ID: <"));
+        assert!(data.contains("Hi Ada,"));
+        assert!(data.contains("- Monday May 12: 2pm-3pm"));
+        assert!(data.contains("- Tuesday May 13: 10am-11:30am"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn a_batch_shares_one_smtp_session() {
+        // The sink only accepts one connection, so a second one would fail the send
+        let (port, received) = start_smtp_sink();
+        let template =
+            EmailTemplate::from_content("Hi", "Hello {{ recipient_name }}", "batch").unwrap();
+        let context = SendContext::new(
+            Transport::Smtp(sink_config(port)),
+            "Test Sender",
+            vec![],
+            template,
+        );
+        for name in ["Ada", "Grace"] {
+            let recipient = Recipient {
+                name: name.to_string(),
+                email: format!("{}@example.com", name.to_lowercase()),
+                fields: Default::default(),
+            };
+            context
+                .send_to(&recipient, &NoopPostProcessor)
+                .await
+                .expect("send over the shared session");
+            let data = received
+                .recv_timeout(Duration::from_secs(10))
+                .expect("sink received DATA");
+            assert!(data.contains(&format!("Hello {}", name)));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sends_without_auth_to_relay_that_rejects_it() {
+        let (port, received) = start_smtp_sink_with_auth(false);
+        let mut config = sink_config(port);
+        config.no_auth = true;
+        let template =
+            EmailTemplate::from_content("Hi", "Hello {{ recipient_name }}", "relay").unwrap();
+        let recipient = Recipient {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            fields: Default::default(),
+        };
+
+        SendContext::new(Transport::Smtp(config), "Test Sender", vec![], template)
+            .send_to(&recipient, &NoopPostProcessor)
+            .await
+            .expect("send through relay without AUTH");
+
+        let data = received
+            .recv_timeout(Duration::from_secs(10))
+            .expect("relay received DATA");
+        assert!(data.contains("Hello Ada"));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn utf8_local_part_fails_clearly_without_smtputf8() {
+        // The sink doesn't advertise SMTPUTF8
+        let (port, _received) = start_smtp_sink();
+        let template = EmailTemplate::from_content("Hi", "Hello", "utf8").unwrap();
+        let recipient = Recipient {
+            name: "Müller".to_string(),
+            email: "müller@example.com".to_string(),
+            fields: Default::default(),
+        };
+
+        let result = SendContext::new(
+            Transport::Smtp(sink_config(port)),
+            "Test Sender",
+            vec![],
+            template,
+        )
+        .send_to(&recipient, &NoopPostProcessor)
+        .await;
+        match result {
+            Err(EmailError::NeedsSmtpUtf8 { address, .. }) => {
+                assert_eq!(address, "müller@example.com")
+            }
+            other => panic!("expected an SMTPUTF8 error, got {:?}", other),
+        }
+    }
+
+    /// Minimal HTTP proxy: answers one CONNECT with `status` and, on 200, relays to the target.
+    fn start_connect_proxy(status: &'static str) -> (u16, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test proxy");
+        let port = listener.local_addr().unwrap().port();
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (client, _) = listener.accept().expect("accept proxy client");
+            let mut reader = BufReader::new(client.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let mut header = String::new();
+            while reader.read_line(&mut header).unwrap_or(0) > 0 && header != "\r\n" {
+                header.clear();
+            }
+            let target = request_line.split_whitespace().nth(1).unwrap().to_string();
+            tx.send(target.clone()).ok();
+
+            let mut client = client;
+            client
+                .write_all(format!("HTTP/1.1 {}\r\n\r\n", status).as_bytes())
+                .unwrap();
+            if !status.starts_with("200") {
+                return;
+            }
+            let upstream = std::net::TcpStream::connect(target).expect("proxy dials target");
+            let mut upstream_read = upstream.try_clone().unwrap();
+            let mut client_write = client.try_clone().unwrap();
+            thread::spawn(move || std::io::copy(&mut upstream_read, &mut client_write).ok());
+            let mut upstream_write = upstream;
+            std::io::copy(&mut reader, &mut upstream_write).ok();
+        });
+
+        (port, rx)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sends_through_http_connect_proxy() {
+        let (sink_port, received) = start_smtp_sink();
+        let (proxy_port, targets) = start_connect_proxy("200 Connection established");
+        let mut config = sink_config(sink_port);
+        config.proxy = Some(format!("http://127.0.0.1:{}", proxy_port));
+        let template =
+            EmailTemplate::from_content("Hi", "Hello {{ recipient_name }}", "proxy").unwrap();
+        let recipient = Recipient {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            fields: Default::default(),
+        };
+
+        SendContext::new(Transport::Smtp(config), "Test Sender", vec![], template)
+            .send_to(&recipient, &NoopPostProcessor)
+            .await
+            .expect("send through proxy");
+
+        assert_eq!(
+            targets.recv_timeout(Duration::from_secs(10)).unwrap(),
+            format!("127.0.0.1:{}", sink_port)
+        );
+        let data = received
+            .recv_timeout(Duration::from_secs(10))
+            .expect("sink received DATA");
+        assert!(data.contains("Hello Ada"));
+    }
+
+    #[test]
+    fn proxy_refusal_is_reported_as_a_proxy_error() {
+        let (proxy_port, _targets) = start_connect_proxy("403 Forbidden");
+        let mut config = sink_config(2525);
+        config.proxy = Some(format!("http://127.0.0.1:{}", proxy_port));
+        match build_transport(&config) {
+            Err(EmailError::Proxy { proxy, source }) => {
+                assert_eq!(proxy, format!("http://127.0.0.1:{}", proxy_port));
+                assert!(source.to_string().contains("403"));
+            }
+            other => panic!("expected a proxy error, got {:?}", other.err()),
+        }
+    }
+
+    #[test]
+    fn plaintext_mode_refuses_remote_hosts() {
+        let mut config = sink_config(25);
+        config.host = "smtp.example.com".to_string();
+        assert!(matches!(
+            build_transport(&config),
+            Err(EmailError::TlsConfig(_))
+        ));
+    }
+
+    fn ada() -> Recipient {
+        Recipient {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            fields: Default::default(),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn the_body_goes_out_8bit_only_with_8bitmime() {
+        for (extensions, encoding) in [(&["8BITMIME"][..], "8bit"), (&[], "quoted-printable")] {
+            // One session for the check, one for the email
+            let (port, received) = start_smtp_sink_with(true, extensions, 2);
+            let template =
+                EmailTemplate::from_content("Hi", "Grüße, {{ recipient_name }}", "8bit").unwrap();
+            let context = SendContext::new(
+                Transport::Smtp(sink_config(port)),
+                "Test Sender",
+                vec![],
+                template,
+            );
+            let capabilities = context.check_server().unwrap().expect("server check");
+            assert_eq!(capabilities.eight_bit_mime, !extensions.is_empty());
+
+            context
+                .send_to(&ada(), &NoopPostProcessor)
+                .await
+                .expect("send after the check");
+            let data = received
+                .recv_timeout(Duration::from_secs(10))
+                .expect("sink received DATA");
+            assert!(
+                data.contains(&format!("Content-Transfer-Encoding: {}\r\n", encoding)),
+                "{}",
+                data
+            );
+            assert_eq!(data.contains("Grüße, Ada"), encoding == "8bit");
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn an_email_over_the_size_limit_is_never_uploaded() {
+        let (port, received) = start_smtp_sink_with(true, &["SIZE 200"], 2);
+        let template =
+            EmailTemplate::from_content("Hi", &"A long paragraph. ".repeat(20), "size").unwrap();
+        let context = SendContext::new(
+            Transport::Smtp(sink_config(port)),
+            "Test Sender",
+            vec![],
+            template,
+        );
+        assert_eq!(
+            context.check_server().unwrap().expect("server check").size,
+            Some(200)
+        );
+
+        let result = context.send_to(&ada(), &NoopPostProcessor).await;
+        assert!(
+            matches!(result, Err(EmailError::TooLarge { limit: 200, .. })),
+            "{:?}",
+            result
+        );
+        assert!(received.recv_timeout(Duration::from_millis(300)).is_err());
+    }
 }
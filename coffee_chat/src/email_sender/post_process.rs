@@ -0,0 +1,18 @@
+// src/email_sender/post_process.rs
+
+use crate::config::Recipient;
+
+/// Hook for changing an email after the template is rendered and before it's sent,
+/// e.g. to append a booking link computed elsewhere.
+pub trait EmailPostProcessor: Send + Sync {
+    /// Called once per recipient with the rendered subject and body, which may be modified in place.
+    fn process(&self, recipient: &Recipient, subject: &mut String, body: &mut String);
+}
+
+/// Leaves the rendered email unchanged. Used when no processor is registered.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoopPostProcessor;
+
+impl EmailPostProcessor for NoopPostProcessor {
+    fn process(&self, _recipient: &Recipient, _subject: &mut String, _body: &mut String) {}
+}
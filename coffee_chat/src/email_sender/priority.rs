@@ -0,0 +1,52 @@
+// src/email_sender/priority.rs
+// Message priority. Clients disagree on which header they read, so a non-normal
+// priority sets all three: X-Priority (Thunderbird, Apple Mail), Importance
+// (Outlook, Gmail) and Priority (RFC 2156).
+
+use lettre::message::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    /// No priority headers at all, which every client shows as normal
+    #[default]
+    Normal,
+    High,
+    Low,
+}
+
+impl Priority {
+    pub const ALL: [Priority; 3] = [Priority::Normal, Priority::High, Priority::Low];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Priority::Normal => "Normal",
+            Priority::High => "High",
+            Priority::Low => "Low",
+        }
+    }
+
+    /// The headers to add for this priority; none for normal.
+    pub fn headers(self) -> Vec<HeaderValue> {
+        let (x_priority, importance, priority) = match self {
+            Priority::Normal => return Vec::new(),
+            Priority::High => ("1 (Highest)", "High", "urgent"),
+            Priority::Low => ("5 (Lowest)", "Low", "non-urgent"),
+        };
+        vec![
+            HeaderValue::new(
+                HeaderName::new_from_ascii_str("X-Priority"),
+                x_priority.to_string(),
+            ),
+            HeaderValue::new(
+                HeaderName::new_from_ascii_str("Importance"),
+                importance.to_string(),
+            ),
+            HeaderValue::new(
+                HeaderName::new_from_ascii_str("Priority"),
+                priority.to_string(),
+            ),
+        ]
+    }
+}
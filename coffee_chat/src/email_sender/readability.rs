@@ -0,0 +1,128 @@
+// src/email_sender/readability.rs
+// A long slot list turns the invitation into a table. These checks measure each
+// recipient's rendered body so the send can be flagged before it goes out.
+
+use super::template::{EmailTemplate, TemplateError};
+use crate::config::Recipient;
+
+/// Slot lines an email can list before it gets the readability warning; 0 turns it off.
+pub const DEFAULT_MAX_SLOT_LINES: u32 = 10;
+
+/// How much of one rendered body the slot list takes up. Orders by slot lines, then length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BodyMeasure {
+    /// Availability lines that ended up in the body (none if the template doesn't use them)
+    pub slot_lines: usize,
+    pub chars: usize,
+}
+
+impl BodyMeasure {
+    pub fn of(body: &str, availabilities: &[String]) -> Self {
+        Self {
+            slot_lines: availabilities
+                .iter()
+                .filter(|line| body.contains(line.as_str()))
+                .count(),
+            chars: body.chars().count(),
+        }
+    }
+}
+
+/// The warning for one body, if it lists more than `max_slot_lines` slots.
+pub fn body_warning(measure: BodyMeasure, max_slot_lines: usize) -> Option<String> {
+    if max_slot_lines == 0 || measure.slot_lines <= max_slot_lines {
+        return None;
+    }
+    Some(format!(
+        "The email lists {} slots ({} characters); over {} is hard to read. Set Max Per Day or Slots In Email to offer fewer.",
+        measure.slot_lines, measure.chars, max_slot_lines
+    ))
+}
+
+/// Renders the body for every recipient and warns about the longest one.
+pub fn send_warning(
+    template: &EmailTemplate,
+    recipients: &[Recipient],
+    sender_name: &str,
+    availabilities: &[String],
+    max_slot_lines: usize,
+) -> Result<Option<String>, TemplateError> {
+    let mut longest = None;
+    for recipient in recipients {
+        let (_, body) = template.render(recipient, sender_name, availabilities)?;
+        // Their own lines, in case they're in another language
+        let lines = template.availabilities_for(recipient, availabilities);
+        longest = longest.max(Some(BodyMeasure::of(&body, &lines)));
+    }
+    Ok(longest.and_then(|measure| body_warning(measure, max_slot_lines)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::free_busy::cap_summary;
+
+    fn recipient(name: &str) -> Recipient {
+        Recipient {
+            name: name.to_string(),
+            email: format!("{}@example.com", name.to_lowercase()),
+            fields: Default::default(),
+        }
+    }
+
+    fn slots(count: usize) -> Vec<String> {
+        (0..count)
+            .map(|i| format!("Monday May {}: 2pm-3pm", i + 1))
+            .collect()
+    }
+
+    fn template() -> EmailTemplate {
+        EmailTemplate::from_content(
+            "Coffee?",
+            "Hi {{ recipient_name }},\n{% for time in availabilities %}- {{ time }}\n{% endfor %}",
+            "readability",
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn long_slot_list_triggers_the_warning() {
+        let recipients = [recipient("Ada"), recipient("Bartholomew")];
+        let warning = send_warning(&template(), &recipients, "Me", &slots(25), 10)
+            .unwrap()
+            .expect("25 slot lines is over the limit");
+        assert!(warning.starts_with("The email lists 25 slots ("));
+
+        assert_eq!(
+            send_warning(&template(), &recipients, "Me", &slots(10), 10).unwrap(),
+            None
+        );
+        // The cap's "+N more" line counts as one, so capping fixes it
+        let capped = cap_summary(&slots(25), 8);
+        assert_eq!(
+            send_warning(&template(), &recipients, "Me", &capped, 10).unwrap(),
+            None
+        );
+        assert_eq!(
+            send_warning(&template(), &recipients, "Me", &slots(25), 0).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn only_lines_in_the_body_are_counted() {
+        let no_slots =
+            EmailTemplate::from_content("Coffee?", "Free any time?", "no_slots").unwrap();
+        assert_eq!(
+            send_warning(&no_slots, &[recipient("Ada")], "Me", &slots(25), 10).unwrap(),
+            None
+        );
+        assert_eq!(
+            BodyMeasure::of("- a\n- b\n", &["a".to_string(), "c".to_string()]),
+            BodyMeasure {
+                slot_lines: 1,
+                chars: 8
+            }
+        );
+    }
+}
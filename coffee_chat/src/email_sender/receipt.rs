@@ -0,0 +1,88 @@
+// src/email_sender/receipt.rs
+// What the SMTP server said when it accepted an email. The queue ID in it is
+// the only way to trace a message that later vanished at a relay or in spam.
+
+use lettre::transport::smtp::response::Response;
+
+/// The server's reply to one accepted email, e.g. `250` / `2.0.0 Ok: queued as 4FxyZ1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmtpReceipt {
+    pub code: String,
+    pub message: String,            // Multi-line replies are joined with spaces
+    pub message_id: Option<String>, // The email's Message-ID, for threading follow-ups; not from webhooks
+}
+
+impl SmtpReceipt {
+    pub fn from_response(response: &Response) -> Self {
+        Self {
+            code: response.code().to_string(),
+            message: response.message().collect::<Vec<_>>().join(" "),
+            message_id: None,
+        }
+    }
+
+    pub fn with_message_id(mut self, message_id: Option<String>) -> Self {
+        self.message_id = message_id;
+        self
+    }
+
+    /// "250 2.0.0 Ok: queued as 4FxyZ1", as the server sent it.
+    pub fn summary(&self) -> String {
+        if self.message.is_empty() {
+            self.code.clone()
+        } else {
+            format!("{} {}", self.code, self.message)
+        }
+    }
+
+    /// The queue ID, for servers that word it the Postfix/Sendmail way ("queued as ID").
+    /// Other servers put it elsewhere, so the full summary is always kept too.
+    pub fn queue_id(&self) -> Option<&str> {
+        let lower = self.message.to_ascii_lowercase();
+        let start = lower.find("queued as ")? + "queued as ".len();
+        self.message[start..]
+            .split_whitespace()
+            .next()
+            .map(|id| id.trim_matches(|c: char| matches!(c, '<' | '>' | '.' | ',' | ')')))
+            .filter(|id| !id.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use lettre::transport::smtp::response::{Category, Code, Detail, Severity};
+
+    fn receipt(lines: &[&str]) -> SmtpReceipt {
+        let code = Code::new(
+            Severity::PositiveCompletion,
+            Category::MailSystem,
+            Detail::Zero,
+        );
+        let response = Response::new(code, lines.iter().map(|l| l.to_string()).collect());
+        SmtpReceipt::from_response(&response)
+    }
+
+    #[test]
+    fn postfix_queue_ids_are_extracted() {
+        let postfix = receipt(&["2.0.0 Ok: queued as 4FxyZ1abc"]);
+        assert_eq!(postfix.summary(), "250 2.0.0 Ok: queued as 4FxyZ1abc");
+        assert_eq!(postfix.queue_id(), Some("4FxyZ1abc"));
+        assert_eq!(
+            receipt(&["OK queued as <ABC-123>."]).queue_id(),
+            Some("ABC-123")
+        );
+    }
+
+    #[test]
+    fn other_replies_keep_the_full_text() {
+        let gmail = receipt(&["2.0.0 OK  1712345678 d9443c01a7336-1e.5 - gsmtp"]);
+        assert_eq!(gmail.queue_id(), None);
+        assert_eq!(
+            gmail.summary(),
+            "250 2.0.0 OK  1712345678 d9443c01a7336-1e.5 - gsmtp"
+        );
+        assert_eq!(receipt(&[]).summary(), "250");
+        assert_eq!(receipt(&["first", "second"]).message, "first second");
+    }
+}
@@ -0,0 +1,221 @@
+// src/email_sender/registry.rs
+// Who is being emailed right now and when each person was last emailed, shared by
+// every send path (the list, drip batches, quick send) so two of them running at
+// once can't email the same person twice. Kept in memory, and seeded from the saved
+// send history so a restart doesn't reset the cooldown.
+
+use super::history::SentEmail;
+use crate::config::RecipientKey;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Minutes after an email during which the same person isn't emailed again.
+pub const DEFAULT_SEND_COOLDOWN_MINUTES: u32 = 10;
+
+#[derive(Debug, Default)]
+struct Entry {
+    in_flight: bool,
+    last_sent: Option<DateTime<Utc>>, // Wall clock, like the history it's seeded from
+}
+
+/// Cheap to clone; clones share the same entries.
+#[derive(Debug, Clone, Default)]
+pub struct SendRegistry {
+    entries: Arc<Mutex<HashMap<RecipientKey, Entry>>>,
+}
+
+/// Why a send was skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecentlyContacted {
+    /// Another send path is emailing them right now
+    InFlight,
+    /// Emailed this long ago, inside the cooldown
+    SentAgo(Duration),
+}
+
+impl fmt::Display for RecentlyContacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InFlight => write!(f, "Recently contacted, skipped: already being sent to"),
+            Self::SentAgo(ago) => write!(
+                f,
+                "Recently contacted, skipped: emailed {} min ago",
+                ago.as_secs() / 60
+            ),
+        }
+    }
+}
+
+impl SendRegistry {
+    /// A registry that knows when everyone in `history` was last emailed.
+    pub fn from_history(history: &[SentEmail]) -> Self {
+        let registry = Self::default();
+        let mut entries = registry.entries.lock().unwrap_or_else(|e| e.into_inner());
+        for email in history.iter().filter(|email| email.outcome.is_ok()) {
+            let entry = entries.entry(RecipientKey::new(&email.email)).or_default();
+            entry.last_sent = entry.last_sent.max(Some(email.sent_at));
+        }
+        drop(entries);
+        registry
+    }
+
+    /// Marks `key` as being sent to, unless it already is or was emailed within `cooldown`.
+    /// The claim is released when dropped; call [`SendClaim::sent`] once the email is accepted.
+    pub fn claim(
+        &self,
+        key: RecipientKey,
+        cooldown: Duration,
+    ) -> Result<SendClaim, RecentlyContacted> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let entry = entries.entry(key.clone()).or_default();
+        if entry.in_flight {
+            return Err(RecentlyContacted::InFlight);
+        }
+        // A time in the future, after the clock was turned back, counts as just now
+        if let Some(ago) = entry
+            .last_sent
+            .map(|at| (Utc::now() - at).to_std().unwrap_or_default())
+        {
+            if ago < cooldown {
+                return Err(RecentlyContacted::SentAgo(ago));
+            }
+        }
+        entry.in_flight = true;
+        Ok(SendClaim {
+            registry: self.clone(),
+            key,
+            sent: false,
+        })
+    }
+}
+
+/// One send path's hold on a recipient. A failed send leaves no timestamp, so it can be retried.
+pub struct SendClaim {
+    registry: SendRegistry,
+    key: RecipientKey,
+    sent: bool,
+}
+
+impl SendClaim {
+    /// Records the email as sent, starting the cooldown.
+    pub fn sent(mut self) {
+        self.sent = true;
+    }
+}
+
+impl Drop for SendClaim {
+    fn drop(&mut self) {
+        let mut entries = self
+            .registry
+            .entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = entries.get_mut(&self.key) {
+            entry.in_flight = false;
+            if self.sent {
+                entry.last_sent = Some(Utc::now());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email_sender::history::EmailKind;
+
+    const COOLDOWN: Duration = Duration::from_secs(600);
+
+    fn key(email: &str) -> RecipientKey {
+        RecipientKey::new(email)
+    }
+
+    #[test]
+    fn a_sent_email_starts_the_cooldown() {
+        let registry = SendRegistry::default();
+        registry
+            .claim(key("ada@example.com"), COOLDOWN)
+            .unwrap()
+            .sent();
+        assert!(matches!(
+            registry.claim(key("ada@EXAMPLE.com"), COOLDOWN),
+            Err(RecentlyContacted::SentAgo(_))
+        ));
+        // Without a cooldown only a send still in flight is skipped
+        assert!(registry
+            .claim(key("ada@example.com"), Duration::ZERO)
+            .is_ok());
+    }
+
+    #[test]
+    fn the_history_carries_the_cooldown_over_a_restart() {
+        let sent = |email: &str, minutes_ago: i64, outcome: Result<String, String>| SentEmail {
+            kind: EmailKind::Invitation,
+            name: String::new(),
+            email: email.to_string(),
+            subject: "Coffee?".to_string(),
+            sent_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            message_id: None,
+            outcome,
+            event: None,
+            slots: None,
+            campaign: None,
+        };
+        let registry = SendRegistry::from_history(&[
+            sent("ada@example.com", 30, Ok("250 OK".to_string())),
+            sent("ada@example.com", 1, Ok("250 OK".to_string())),
+            sent("bob@example.com", 1, Err("550 No such user".to_string())),
+            sent("cy@example.com", 20, Ok("250 OK".to_string())),
+        ]);
+        match registry.claim(key("ada@EXAMPLE.com"), COOLDOWN) {
+            Err(RecentlyContacted::SentAgo(ago)) => assert!(ago < Duration::from_secs(120)),
+            other => panic!("expected a cooldown, got {:?}", other.err()),
+        }
+        // A failed email doesn't count, and an old one is past the cooldown
+        assert!(registry.claim(key("bob@example.com"), COOLDOWN).is_ok());
+        assert!(registry.claim(key("cy@example.com"), COOLDOWN).is_ok());
+    }
+
+    #[test]
+    fn a_failed_send_can_be_retried() {
+        let registry = SendRegistry::default();
+        let claim = registry.claim(key("ada@example.com"), COOLDOWN).unwrap();
+        assert_eq!(
+            registry.claim(key("ada@example.com"), COOLDOWN).err(),
+            Some(RecentlyContacted::InFlight)
+        );
+        drop(claim);
+        assert!(registry.claim(key("ada@example.com"), COOLDOWN).is_ok());
+    }
+
+    /// Claims each address, "sends" for a while, and returns the ones it sent to.
+    async fn batch(registry: SendRegistry, emails: Vec<&'static str>) -> Vec<&'static str> {
+        let mut sent = Vec::new();
+        for email in emails {
+            let Ok(claim) = registry.claim(key(email), COOLDOWN) else {
+                continue;
+            };
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            claim.sent();
+            sent.push(email);
+        }
+        sent
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn overlapping_batches_email_each_person_once() {
+        let registry = SendRegistry::default();
+        let emails = vec!["a@example.com", "b@example.com", "c@example.com"];
+        let mut reversed = emails.clone();
+        reversed.reverse();
+        let first = tokio::spawn(batch(registry.clone(), emails.clone()));
+        let second = tokio::spawn(batch(registry.clone(), reversed));
+        let mut sent = first.await.unwrap();
+        sent.extend(second.await.unwrap());
+        sent.sort();
+        assert_eq!(sent, emails);
+    }
+}
@@ -0,0 +1,177 @@
+// src/email_sender/role_account.rs
+// Shared mailboxes like info@ or noreply@. Nobody in particular reads them, so an
+// invitation there is wasted, and mail to them hurts the sender's reputation.
+// Flagging them is only advice: the user decides whether to keep them.
+
+use std::collections::BTreeMap;
+
+/// Local parts flagged when config.toml doesn't list its own.
+pub const DEFAULT_ROLE_ACCOUNTS: &[&str] = &[
+    "abuse",
+    "accounts",
+    "admin",
+    "administrator",
+    "billing",
+    "careers",
+    "contact",
+    "donotreply",
+    "enquiries",
+    "help",
+    "hostmaster",
+    "hr",
+    "info",
+    "inquiries",
+    "jobs",
+    "mailerdaemon",
+    "marketing",
+    "newsletter",
+    "noreply",
+    "office",
+    "postmaster",
+    "press",
+    "recruiting",
+    "sales",
+    "security",
+    "support",
+    "webmaster",
+];
+
+/// The local parts that mark a role address.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RoleAccounts {
+    local_parts: Vec<String>, // Normalized, see `normalize`
+}
+
+impl Default for RoleAccounts {
+    fn default() -> Self {
+        Self::new(DEFAULT_ROLE_ACCOUNTS)
+    }
+}
+
+impl RoleAccounts {
+    pub fn new(local_parts: impl IntoIterator<Item = impl AsRef<str>>) -> Self {
+        Self {
+            local_parts: local_parts
+                .into_iter()
+                .map(|part| normalize(part.as_ref()))
+                .filter(|part| !part.is_empty())
+                .collect(),
+        }
+    }
+
+    /// The flagged local parts, normalized.
+    pub fn local_parts(&self) -> &[String] {
+        &self.local_parts
+    }
+
+    /// Whether `email` goes to a role mailbox. Case, `+tags` and the separators in
+    /// `no-reply` or `no_reply` don't matter.
+    pub fn is_role(&self, email: &str) -> bool {
+        self.role_of(email).is_some()
+    }
+
+    // The normalized local part `email` was flagged for
+    fn role_of(&self, email: &str) -> Option<String> {
+        let (local, _) = email.trim().rsplit_once('@')?;
+        let local = local.split_once('+').map_or(local, |(local, _)| local);
+        let local = normalize(local);
+        self.local_parts.contains(&local).then_some(local)
+    }
+
+    /// The role addresses among `emails`, in order.
+    pub fn flagged<'a>(&self, emails: impl IntoIterator<Item = &'a str>) -> Vec<&'a str> {
+        emails
+            .into_iter()
+            .filter(|email| self.is_role(email))
+            .collect()
+    }
+
+    /// The role addresses among `emails` by the mailbox they were flagged for, e.g.
+    /// "noreply" for both no-reply@a.com and noreply@b.com.
+    pub fn grouped<'a>(
+        &self,
+        emails: impl IntoIterator<Item = &'a str>,
+    ) -> BTreeMap<String, Vec<&'a str>> {
+        let mut groups: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for email in emails {
+            if let Some(role) = self.role_of(email) {
+                groups.entry(role).or_default().push(email);
+            }
+        }
+        groups
+    }
+}
+
+// "No-Reply" -> "noreply"
+fn normalize(local: &str) -> String {
+    local
+        .trim()
+        .chars()
+        .filter(|c| !matches!(c, '.' | '-' | '_'))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn common_role_mailboxes_are_flagged() {
+        let roles = RoleAccounts::default();
+        for email in [
+            "info@acme.com",
+            "Careers@Acme.com",
+            "no-reply@acme.com",
+            "no_reply@acme.com",
+            "do.not.reply@acme.com",
+            "support+vip@acme.com",
+            " admin@acme.com ",
+            "Mailer-Daemon@acme.com",
+        ] {
+            assert!(roles.is_role(email), "{}", email);
+        }
+        for email in [
+            "jane@acme.com",
+            "information.desk.jane@acme.com",
+            "info.jane@acme.com",
+            "salesforce@acme.com",
+            "info", // Not an address
+            "",
+        ] {
+            assert!(!roles.is_role(email), "{}", email);
+        }
+    }
+
+    #[test]
+    fn a_configured_list_replaces_the_default() {
+        let roles = RoleAccounts::new(["Talent", "  ", "people-ops"]);
+        assert!(roles.is_role("talent@acme.com"));
+        assert!(roles.is_role("peopleops@acme.com"));
+        assert!(!roles.is_role("info@acme.com"));
+        assert_eq!(
+            roles.flagged(["a@x.com", "talent@x.com", "People.Ops@x.com"]),
+            vec!["talent@x.com", "People.Ops@x.com"]
+        );
+    }
+
+    #[test]
+    fn flagged_addresses_are_grouped_by_mailbox() {
+        let groups = RoleAccounts::default().grouped([
+            "no-reply@a.com",
+            "jane@a.com",
+            "info@b.com",
+            "NoReply@c.com",
+        ]);
+        assert_eq!(
+            groups.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("info".to_string(), vec!["info@b.com"]),
+                (
+                    "noreply".to_string(),
+                    vec!["no-reply@a.com", "NoReply@c.com"]
+                ),
+            ]
+        );
+    }
+}
@@ -0,0 +1,262 @@
+// src/email_sender/send_context.rs
+// What every email in a batch shares, set up once when the batch starts: where
+// emails go, who they're from, the template and the slots offered. Each email
+// borrows from it, so a batch is a snapshot of the settings it started with.
+
+use super::capabilities::{self, ServerCapabilities};
+use super::post_process::EmailPostProcessor;
+use super::receipt::SmtpReceipt;
+use super::template::EmailTemplate;
+use super::{
+    build_prepared_message, build_transport, deliver_with, prepare_email, send_prepared_email,
+    EmailError, PreparedEmail, Transport,
+};
+use crate::config::{Recipient, SmtpConfig};
+use lettre::SmtpTransport;
+use log::warn;
+use std::borrow::Cow;
+
+pub struct SendContext {
+    pub transport: Transport,
+    pub sender_name: String,
+    pub availabilities: Vec<String>,
+    pub template: EmailTemplate,
+    // Built once and reused, connection and all. `None` through a proxy, whose
+    // tunnel only takes one connection, or if building it failed
+    smtp: Option<SmtpTransport>,
+}
+
+impl SendContext {
+    pub fn new(
+        transport: Transport,
+        sender_name: &str,
+        availabilities: Vec<String>,
+        template: EmailTemplate,
+    ) -> Self {
+        let smtp = match &transport {
+            Transport::Smtp(config) if config.proxy.is_none() => build_transport(config)
+                // Each email tries again and reports the error for its recipient
+                .inspect_err(|e| warn!("Couldn't set up the SMTP transport: {}", e))
+                .ok(),
+            _ => None,
+        };
+        SendContext {
+            transport,
+            sender_name: sender_name.to_string(),
+            availabilities,
+            template,
+            smtp,
+        }
+    }
+
+    /// What the SMTP server supports, checked once per server and session. Emails
+    /// built afterwards follow it; if the check fails they're built as before. `None`
+    /// for webhook and demo sends.
+    pub fn check_server(&self) -> Option<Result<ServerCapabilities, EmailError>> {
+        match &self.transport {
+            Transport::Smtp(config) => Some(
+                capabilities::detect(config).inspect_err(|e| warn!("{}; sending without it.", e)),
+            ),
+            _ => None,
+        }
+    }
+
+    /// Renders the email for one recipient.
+    pub fn prepare(
+        &self,
+        recipient: &Recipient,
+        post_processor: &dyn EmailPostProcessor,
+    ) -> Result<PreparedEmail, EmailError> {
+        prepare_email(
+            recipient,
+            &self.sender_name,
+            &self.availabilities,
+            &self.template,
+            post_processor,
+        )
+    }
+
+    /// Sends an email rendered by `prepare`, over the batch's SMTP connection if it has one.
+    pub async fn send(
+        &self,
+        recipient: &Recipient,
+        prepared: &PreparedEmail,
+    ) -> Result<SmtpReceipt, EmailError> {
+        match &self.transport {
+            Transport::Smtp(config) => {
+                let email = build_prepared_message(config, recipient, &self.sender_name, prepared)?;
+                let smtp = self.smtp_transport(config)?;
+                deliver_with(&smtp, recipient, &email)
+            }
+            _ => send_prepared_email(&self.transport, recipient, &self.sender_name, prepared).await,
+        }
+    }
+
+    /// Renders and sends in one go.
+    pub async fn send_to(
+        &self,
+        recipient: &Recipient,
+        post_processor: &dyn EmailPostProcessor,
+    ) -> Result<SmtpReceipt, EmailError> {
+        let prepared = self.prepare(recipient, post_processor)?;
+        self.send(recipient, &prepared).await
+    }
+
+    fn smtp_transport(&self, config: &SmtpConfig) -> Result<Cow<'_, SmtpTransport>, EmailError> {
+        match &self.smtp {
+            Some(smtp) => Ok(Cow::Borrowed(smtp)),
+            None => build_transport(config).map(Cow::Owned),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SmtpTlsMode;
+    use crate::email_sender::post_process::NoopPostProcessor;
+    use crate::email_sender::TRANSPORTS_BUILT;
+    use secrecy::SecretString;
+    use std::collections::BTreeMap;
+
+    fn smtp_config() -> SmtpConfig {
+        SmtpConfig {
+            host: "localhost".to_string(),
+            port: 1, // Nothing listens there, so each send fails fast
+            user: "sender@example.com".to_string(),
+            password: SecretString::new("test-password".to_string().into()),
+            from_email: "sender@example.com".to_string(),
+            tls_mode: SmtpTlsMode::PlaintextLocalhost,
+            proxy: None,
+            no_auth: false,
+        }
+    }
+
+    /// Transports built while sending 20 emails through a context for `config`,
+    /// setting up the context included.
+    async fn transports_built_for_a_batch(config: SmtpConfig) -> usize {
+        let before = TRANSPORTS_BUILT.with(|built| built.get());
+        let template = EmailTemplate::from_content("Hi", "Hello", "context").unwrap();
+        let context = SendContext::new(Transport::Smtp(config), "Ada", vec![], template);
+        for i in 0..20 {
+            let recipient = Recipient {
+                name: format!("Person {}", i),
+                email: format!("p{}@example.com", i),
+                fields: BTreeMap::new(),
+            };
+            let prepared = context.prepare(&recipient, &NoopPostProcessor).unwrap();
+            // Refused; only the transport it went through matters here
+            assert!(context.send(&recipient, &prepared).await.is_err());
+        }
+        TRANSPORTS_BUILT.with(|built| built.get()) - before
+    }
+
+    #[tokio::test]
+    async fn the_smtp_transport_is_set_up_once_per_batch() {
+        assert_eq!(transports_built_for_a_batch(smtp_config()).await, 1);
+    }
+
+    #[tokio::test]
+    async fn a_proxy_gets_a_tunnel_per_email() {
+        let mut config = smtp_config();
+        config.proxy = Some("socks5://127.0.0.1:1".to_string());
+        assert_eq!(transports_built_for_a_batch(config).await, 20);
+    }
+
+    // Run with `cargo test --features count-allocations -- --nocapture allocations`.
+    // Off by default: the allocator it installs counts for every test in the binary.
+    #[cfg(feature = "count-allocations")]
+    mod allocations {
+        use super::*;
+        use std::alloc::{GlobalAlloc, Layout, System};
+        use std::cell::Cell;
+
+        // Counts this thread's allocations, so tests running alongside don't add to them
+        struct CountingAllocator;
+
+        thread_local! {
+            static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        unsafe impl GlobalAlloc for CountingAllocator {
+            unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+                let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+                System.alloc(layout)
+            }
+
+            unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+                System.dealloc(ptr, layout)
+            }
+        }
+
+        #[global_allocator]
+        static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+        fn allocated() -> usize {
+            ALLOCATIONS.with(Cell::get)
+        }
+
+        fn recipients() -> Vec<(Recipient, PreparedEmail)> {
+            let template = EmailTemplate::from_content("Hi", "Hello", "context").unwrap();
+            let context = SendContext::new(Transport::Demo(smtp_config()), "Ada", vec![], template);
+            (0..20)
+                .map(|i| {
+                    let recipient = Recipient {
+                        name: format!("Person {}", i),
+                        email: format!("p{}@example.com", i),
+                        fields: BTreeMap::new(),
+                    };
+                    let prepared = context.prepare(&recipient, &NoopPostProcessor).unwrap();
+                    (recipient, prepared)
+                })
+                .collect()
+        }
+
+        /// Contexts for `count` batches, set up outside the counted part.
+        fn unbuilt(count: usize) -> Vec<(Transport, EmailTemplate)> {
+            (0..count)
+                .map(|_| {
+                    let template = EmailTemplate::from_content("Hi", "Hello", "context").unwrap();
+                    (Transport::Smtp(smtp_config()), template)
+                })
+                .collect()
+        }
+
+        // The default runtime is single-threaded, so everything counted is this test's
+        #[tokio::test]
+        async fn a_batch_allocates_less_with_one_transport() {
+            let emails = recipients();
+            // Warm up whatever lettre sets up once per process
+            build_transport(&smtp_config()).unwrap();
+            let config = smtp_config();
+            let start = allocated();
+            build_transport(&config).unwrap();
+            let per_build = allocated() - start;
+
+            // Before: every email set up its own transport
+            let mut setups = unbuilt(emails.len());
+            let start = allocated();
+            for (recipient, prepared) in &emails {
+                let (transport, template) = setups.pop().unwrap();
+                let context = SendContext::new(transport, "Ada", vec![], template);
+                assert!(context.send(recipient, prepared).await.is_err());
+            }
+            let before = allocated() - start;
+
+            // After: one for the batch
+            let (transport, template) = unbuilt(1).pop().unwrap();
+            let start = allocated();
+            let context = SendContext::new(transport, "Ada", vec![], template);
+            for (recipient, prepared) in &emails {
+                assert!(context.send(recipient, prepared).await.is_err());
+            }
+            let after = allocated() - start;
+
+            println!(
+                "20 emails: {} allocations with a transport each, {} with one per batch ({} per transport)",
+                before, after, per_build
+            );
+            assert!(before >= after + 19 * per_build, "{} vs {}", before, after);
+        }
+    }
+}
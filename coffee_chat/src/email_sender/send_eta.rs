@@ -0,0 +1,153 @@
+// src/email_sender/send_eta.rs
+// How long a batch will take: a guess before it starts, then a running estimate
+// from how long its emails have taken so far. Emails go out one at a time, so the
+// time left is the emails left times the average.
+
+use std::time::{Duration, Instant};
+
+/// Time per email before any has been timed, for a server nothing is known about.
+pub const DEFAULT_TIME_PER_EMAIL: Duration = Duration::from_secs(2);
+
+/// Progress and time left for a batch being sent, fed each outcome as it arrives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SendEta {
+    total: usize,
+    done: usize,
+    guess: Duration, // Per email, until one has been timed
+    timed: u32,
+    timed_total: Duration,
+    last: Instant, // Start of the batch, or the last outcome
+}
+
+impl SendEta {
+    pub fn start(total: usize, guess: Duration, now: Instant) -> Self {
+        Self {
+            total,
+            done: 0,
+            guess,
+            timed: 0,
+            timed_total: Duration::ZERO,
+            last: now,
+        }
+    }
+
+    /// An email was accepted or failed at `at`. It took the time since the previous
+    /// outcome.
+    pub fn sent(&mut self, at: Instant) {
+        self.timed += 1;
+        self.timed_total += at.saturating_duration_since(self.last);
+        self.finished_one(at);
+    }
+
+    /// An email was skipped without sending; it counts as done but isn't timed.
+    pub fn skipped(&mut self, at: Instant) {
+        self.finished_one(at);
+    }
+
+    fn finished_one(&mut self, at: Instant) {
+        self.done = (self.done + 1).min(self.total);
+        self.last = at;
+    }
+
+    /// The average time per email so far, once one has been timed.
+    pub fn measured(&self) -> Option<Duration> {
+        (self.timed > 0).then(|| self.timed_total / self.timed)
+    }
+
+    pub fn per_email(&self) -> Duration {
+        self.measured().unwrap_or(self.guess)
+    }
+
+    pub fn done(&self) -> usize {
+        self.done
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            1.0
+        } else {
+            self.done as f32 / self.total as f32
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        estimate(self.total - self.done, self.per_email())
+    }
+}
+
+/// How long `count` emails take at `per_email` each.
+pub fn estimate(count: usize, per_email: Duration) -> Duration {
+    per_email.saturating_mul(u32::try_from(count).unwrap_or(u32::MAX))
+}
+
+/// "under a minute", "about 11 minutes", "about 1 hour 5 minutes"
+pub fn describe(duration: Duration) -> String {
+    let minutes = (duration.as_secs() + 30) / 60;
+    let plural = |n: u64, unit: &str| format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+    match (minutes / 60, minutes % 60) {
+        (0, 0) => "under a minute".to_string(),
+        (0, minutes) => format!("about {}", plural(minutes, "minute")),
+        (hours, 0) => format!("about {}", plural(hours, "hour")),
+        (hours, minutes) => format!(
+            "about {} {}",
+            plural(hours, "hour"),
+            plural(minutes, "minute")
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn estimate_follows_the_measured_pace() {
+        let start = Instant::now();
+        let mut eta = SendEta::start(200, DEFAULT_TIME_PER_EMAIL, start);
+        assert_eq!(eta.remaining(), Duration::from_secs(400));
+        assert_eq!(describe(eta.remaining()), "about 7 minutes");
+
+        // Slower than the guess: 3s, then 5s
+        eta.sent(start + Duration::from_secs(3));
+        eta.sent(start + Duration::from_secs(8));
+        // Skips take no time and don't pull the average down
+        eta.skipped(start + Duration::from_secs(8));
+        assert_eq!(eta.done(), 3);
+        assert_eq!(eta.measured(), Some(Duration::from_secs(4)));
+        assert_eq!(eta.remaining(), Duration::from_secs(197 * 4));
+        assert_eq!(describe(eta.remaining()), "about 13 minutes");
+        assert_eq!(eta.fraction(), 0.015);
+    }
+
+    #[test]
+    fn nothing_timed_keeps_the_guess() {
+        let start = Instant::now();
+        let mut eta = SendEta::start(2, Duration::from_secs(1), start);
+        eta.skipped(start + Duration::from_secs(30));
+        assert_eq!(eta.measured(), None);
+        assert_eq!(eta.remaining(), Duration::from_secs(1));
+        eta.skipped(start + Duration::from_secs(30));
+        eta.skipped(start + Duration::from_secs(30)); // More outcomes than emails
+        assert_eq!((eta.done(), eta.remaining()), (2, Duration::ZERO));
+        assert_eq!(
+            SendEta::start(0, DEFAULT_TIME_PER_EMAIL, start).fraction(),
+            1.0
+        );
+    }
+
+    #[test]
+    fn durations_read_in_minutes_and_hours() {
+        let secs = Duration::from_secs;
+        assert_eq!(describe(secs(0)), "under a minute");
+        assert_eq!(describe(secs(29)), "under a minute");
+        assert_eq!(describe(secs(30)), "about 1 minute");
+        assert_eq!(describe(secs(11 * 60 + 10)), "about 11 minutes");
+        assert_eq!(describe(secs(3600)), "about 1 hour");
+        assert_eq!(describe(secs(65 * 60)), "about 1 hour 5 minutes");
+        assert_eq!(describe(secs(150 * 60)), "about 2 hours 30 minutes");
+    }
+}
@@ -0,0 +1,371 @@
+// src/email_sender/spam_check.rs
+// A rough, local guess at how spam filters will see an invitation. Each rule adds
+// points like SpamAssassin's do and comes with what to change; nothing is sent
+// anywhere. It can't see the sender's reputation or DNS, which matter more.
+
+use super::priority::Priority;
+use super::PreparedEmail;
+
+/// Scores at or above this are likely to land in spam.
+pub const LIKELY_SPAM_SCORE: f32 = 5.0;
+
+/// Scores at or above this are worth a look before sending.
+pub const BORDERLINE_SCORE: f32 = 2.5;
+
+// Links above this look like marketing rather than a personal note
+const MAX_LINKS: usize = 3;
+
+// Phrases filters weigh heavily, matched case-insensitively on word boundaries
+const SPAMMY_PHRASES: &[&str] = &[
+    "act now",
+    "click here",
+    "limited time",
+    "guaranteed",
+    "risk-free",
+    "risk free",
+    "no obligation",
+    "100% free",
+    "free gift",
+    "winner",
+    "congratulations",
+    "urgent",
+    "cash",
+    "earn money",
+    "special promotion",
+    "exclusive deal",
+    "buy now",
+    "order now",
+    "once in a lifetime",
+    "dear friend",
+];
+
+// Link shorteners hide where a link goes, which filters distrust
+const SHORTENERS: &[&str] = &["bit.ly/", "tinyurl.com/", "t.co/", "goo.gl/", "ow.ly/"];
+
+// Any of these counts as a way to opt out in the body
+const OPT_OUT_PHRASES: &[&str] = &[
+    "unsubscribe",
+    "opt out",
+    "opt-out",
+    "rather not hear",
+    "don't want to hear",
+    "stop emailing",
+];
+
+/// What the check looks at for one email.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CheckedEmail<'a> {
+    pub subject: &'a str,
+    pub body: &'a str,          // text/plain part
+    pub html: Option<&'a str>,  // text/html part, if any
+    pub list_unsubscribe: bool, // Has a List-Unsubscribe header
+    pub high_priority: bool,
+}
+
+impl<'a> CheckedEmail<'a> {
+    /// An email as this app sends it: plain text, no List-Unsubscribe header.
+    pub fn prepared(email: &'a PreparedEmail) -> Self {
+        Self {
+            subject: &email.subject,
+            body: &email.body,
+            html: None,
+            list_unsubscribe: false,
+            high_priority: email.priority == Priority::High,
+        }
+    }
+}
+
+/// One rule that matched.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub points: f32,
+    pub suggestion: String, // What it found and what to change
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SpamReport {
+    pub findings: Vec<Finding>,
+}
+
+impl SpamReport {
+    pub fn score(&self) -> f32 {
+        self.findings.iter().map(|finding| finding.points).sum()
+    }
+
+    /// "low", "borderline" or "high".
+    pub fn risk(&self) -> &'static str {
+        match self.score() {
+            score if score >= LIKELY_SPAM_SCORE => "high",
+            score if score >= BORDERLINE_SCORE => "borderline",
+            _ => "low",
+        }
+    }
+
+    fn add(&mut self, points: f32, suggestion: impl Into<String>) {
+        self.findings.push(Finding {
+            points,
+            suggestion: suggestion.into(),
+        });
+    }
+}
+
+/// Runs every rule over `email`. Findings are listed highest points first.
+pub fn check(email: &CheckedEmail) -> SpamReport {
+    let mut report = SpamReport::default();
+    check_subject(email.subject, &mut report);
+    check_body(email, &mut report);
+    if email.high_priority {
+        report.add(
+            1.0,
+            "High priority headers are common in bulk mail. Use normal priority.",
+        );
+    }
+    report
+        .findings
+        .sort_by(|a, b| b.points.total_cmp(&a.points));
+    report
+}
+
+fn check_subject(subject: &str, report: &mut SpamReport) {
+    let subject = subject.trim();
+    if subject.is_empty() {
+        report.add(1.5, "The subject is empty. Say what the email is about.");
+        return;
+    }
+    if is_shouting(subject) {
+        report.add(
+            2.0,
+            "The subject is mostly capital letters. Write it in sentence case.",
+        );
+    }
+    let exclamations = subject.matches('!').count();
+    if exclamations >= 2 {
+        report.add(
+            1.0,
+            format!(
+                "The subject has {} exclamation marks. One at most.",
+                exclamations
+            ),
+        );
+    }
+    for phrase in spammy_phrases(subject) {
+        report.add(
+            1.5,
+            format!(
+                "The subject says \"{}\", which filters associate with spam.",
+                phrase
+            ),
+        );
+    }
+}
+
+fn check_body(email: &CheckedEmail, report: &mut SpamReport) {
+    let body = email.body;
+    if body.trim().chars().count() < 40 {
+        report.add(
+            1.0,
+            "The body is very short. Add a sentence or two of context.",
+        );
+    }
+    for phrase in spammy_phrases(body) {
+        report.add(
+            1.0,
+            format!(
+                "The body says \"{}\", which filters associate with spam.",
+                phrase
+            ),
+        );
+    }
+
+    let links = count_links(body);
+    if links > MAX_LINKS {
+        report.add(
+            0.5 * (links - MAX_LINKS) as f32,
+            format!(
+                "The body has {} links. Keep it to {} or fewer.",
+                links, MAX_LINKS
+            ),
+        );
+    }
+    let lower = body.to_lowercase();
+    if SHORTENERS.iter().any(|shortener| lower.contains(shortener)) {
+        report.add(
+            1.5,
+            "The body uses a link shortener. Link to the real address instead.",
+        );
+    }
+
+    match email.html {
+        Some(html) => {
+            // A tiny text part next to a big HTML part looks auto-generated
+            let html_text = strip_tags(html);
+            let (text_len, html_len) = (body.trim().len(), html_text.trim().len());
+            if html_len > 0 && text_len * 3 < html_len {
+                report.add(
+                    1.0,
+                    "The plain-text part is much shorter than the HTML part. Make them say the same thing.",
+                );
+            }
+        }
+        None if looks_like_html(body) => {
+            report.add(
+                1.0,
+                "The plain-text body contains HTML tags, which show up as-is. Remove them.",
+            );
+        }
+        None => {}
+    }
+
+    if !email.list_unsubscribe && !OPT_OUT_PHRASES.iter().any(|p| lower.contains(p)) {
+        report.add(
+            0.5,
+            "There's no way to opt out. A line like \"If you'd rather not hear from me, just let me know\" helps with bulk sends.",
+        );
+    }
+}
+
+// Mostly capitals, ignoring short subjects and anything that isn't a letter
+fn is_shouting(text: &str) -> bool {
+    let letters: Vec<char> = text.chars().filter(|c| c.is_alphabetic()).collect();
+    let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+    letters.len() >= 6 && upper * 10 >= letters.len() * 7
+}
+
+fn spammy_phrases(text: &str) -> Vec<&'static str> {
+    let lower = text.to_lowercase();
+    SPAMMY_PHRASES
+        .iter()
+        .copied()
+        .filter(|phrase| contains_words(&lower, phrase))
+        .collect()
+}
+
+// `phrase` in `text` with no letter or digit right before or after it
+fn contains_words(text: &str, phrase: &str) -> bool {
+    text.match_indices(phrase).any(|(start, _)| {
+        let before = text[..start].chars().next_back();
+        let after = text[start + phrase.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+fn count_links(text: &str) -> usize {
+    text.split_whitespace()
+        .filter(|word| {
+            let word = word.trim_start_matches(['(', '<', '"']);
+            word.starts_with("http://") || word.starts_with("https://") || word.starts_with("www.")
+        })
+        .count()
+}
+
+fn looks_like_html(text: &str) -> bool {
+    let lower = text.to_lowercase();
+    ["<html", "<body", "<p>", "<br", "<div", "<a href", "<table"]
+        .iter()
+        .any(|tag| lower.contains(tag))
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PERSONAL: &str = "Hi Ana,\n\nI'd love to hear about your work on GPU benchmarks. Would any of these times suit you for a short chat?\n\n1) Monday Apr 7: 2pm–3pm\n\nIf you'd rather not hear from me, just let me know.\n\nBest,\nSam";
+
+    fn checked<'a>(subject: &'a str, body: &'a str) -> CheckedEmail<'a> {
+        CheckedEmail {
+            subject,
+            body,
+            ..CheckedEmail::default()
+        }
+    }
+
+    #[test]
+    fn a_personal_invitation_scores_low() {
+        let report = check(&checked("Coffee chat next week?", PERSONAL));
+        assert!(report.findings.is_empty(), "{:?}", report.findings);
+        assert_eq!(report.risk(), "low");
+    }
+
+    #[test]
+    fn shouting_and_spammy_subjects_are_flagged() {
+        let report = check(&checked("URGENT: ACT NOW!!!", PERSONAL));
+        assert!(report.score() >= LIKELY_SPAM_SCORE);
+        assert_eq!(report.risk(), "high");
+        assert!(report.findings[0].suggestion.contains("capital letters"));
+        // Short acronyms aren't shouting
+        assert!(check(&checked("GPU chat?", PERSONAL)).findings.is_empty());
+    }
+
+    #[test]
+    fn phrases_match_whole_words_only() {
+        assert_eq!(spammy_phrases("Cash flow matters"), vec!["cash"]);
+        assert!(spammy_phrases("cashew recipes").is_empty());
+        assert!(spammy_phrases("A freelance winnerless race").is_empty());
+    }
+
+    #[test]
+    fn links_shorteners_and_html_in_plain_text_cost_points() {
+        let body = format!(
+            "{}\nhttps://a.example https://b.example www.c.example (https://d.example) https://bit.ly/x <p>hi</p>",
+            PERSONAL
+        );
+        let report = check(&checked("Coffee chat?", &body));
+        let suggestions: Vec<&str> = report
+            .findings
+            .iter()
+            .map(|f| f.suggestion.as_str())
+            .collect();
+        assert!(suggestions.iter().any(|s| s.contains("5 links")));
+        assert!(suggestions.iter().any(|s| s.contains("link shortener")));
+        assert!(suggestions.iter().any(|s| s.contains("HTML tags")));
+    }
+
+    #[test]
+    fn opt_out_can_come_from_the_header_or_the_body() {
+        let body = "Hi Ana, would you have time for a coffee chat next week? Best, Sam";
+        let missing = check(&checked("Coffee chat?", body));
+        assert!(missing.findings[0].suggestion.contains("opt out"));
+        let with_header = CheckedEmail {
+            list_unsubscribe: true,
+            ..checked("Coffee chat?", body)
+        };
+        assert!(check(&with_header).findings.is_empty());
+    }
+
+    #[test]
+    fn thin_text_part_next_to_html_is_flagged() {
+        let html = format!("<html><body><p>{}</p></body></html>", PERSONAL.repeat(4));
+        let report = check(&CheckedEmail {
+            html: Some(&html),
+            ..checked("Coffee chat?", PERSONAL)
+        });
+        assert_eq!(report.findings.len(), 1);
+        assert!(report.findings[0].suggestion.contains("plain-text part"));
+    }
+
+    #[test]
+    fn high_priority_counts_against_the_email() {
+        let prepared = PreparedEmail {
+            subject: "Coffee chat?".to_string(),
+            body: PERSONAL.to_string(),
+            priority: Priority::High,
+            ..PreparedEmail::default()
+        };
+        let report = check(&CheckedEmail::prepared(&prepared));
+        assert_eq!(report.score(), 1.0);
+    }
+}
@@ -0,0 +1,57 @@
+// src/email_sender/subject.rs
+// Checks on the rendered subject. Tera will happily put a line break in it,
+// and lettre or the server then fails with an error that doesn't say why.
+
+/// Longer subjects still send, but get a warning; most clients cut them off well before this.
+pub const MAX_SUBJECT_CHARS: usize = 200;
+
+/// 1-based character position of the first CR or LF, if there is one.
+pub fn line_break_position(subject: &str) -> Option<usize> {
+    subject
+        .chars()
+        .position(|c| c == '\n' || c == '\r')
+        .map(|index| index + 1)
+}
+
+/// Things worth pointing out about a subject that will still send.
+pub fn subject_warnings(subject: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if subject.trim().is_empty() {
+        warnings.push("Subject is empty".to_string());
+    } else if subject.trim() != subject {
+        warnings.push("Subject starts or ends with spaces".to_string());
+    }
+    let length = subject.chars().count();
+    if length > MAX_SUBJECT_CHARS {
+        warnings.push(format!(
+            "Subject is {} characters long; over {} may be cut off",
+            length, MAX_SUBJECT_CHARS
+        ));
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_breaks_are_located_by_character() {
+        assert_eq!(line_break_position("Coffee chat?"), None);
+        assert_eq!(line_break_position("Hi Zoë\nthere"), Some(7));
+        assert_eq!(line_break_position("\r\nHi"), Some(1));
+    }
+
+    #[test]
+    fn warns_about_spaces_and_length() {
+        assert!(subject_warnings("Coffee chat?").is_empty());
+        assert_eq!(
+            subject_warnings(" Coffee chat? "),
+            vec!["Subject starts or ends with spaces"]
+        );
+        assert_eq!(subject_warnings("  "), vec!["Subject is empty"]);
+        let long = "a".repeat(MAX_SUBJECT_CHARS + 1);
+        assert_eq!(subject_warnings(&long).len(), 1);
+        assert!(subject_warnings(&"a".repeat(MAX_SUBJECT_CHARS)).is_empty());
+    }
+}
@@ -1,5 +1,13 @@
+use super::priority::Priority;
+use crate::calendar::format::SlotFormat;
+use crate::calendar::free_busy::is_slot_line;
+use crate::calendar::offered::OfferedSlots;
+use crate::config::{Recipient, SenderConfig};
+use serde::Serialize;
+use std::borrow::Cow;
 use std::fs;
-use std::path::Path;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
 use tera::{Context, Error as TeraError, Tera}; // Templating engine
 use thiserror::Error;
 
@@ -10,12 +18,160 @@ pub enum TemplateError {
         path: String,
         source: std::io::Error,
     },
+    #[error("Template file '{}' doesn't exist; fix sender.template_path or create the file", resolved.display())]
+    Missing { resolved: PathBuf }, // Absolute, so it's clear where it was looked for
+    #[error("No permission to read template file '{}'; check the path and the file's permissions", resolved.display())]
+    PermissionDenied { resolved: PathBuf },
     #[error("Failed to parse template '{name}': {source}")] // Changed path to name for clarity
     ParseError { name: String, source: TeraError },
     #[error("Failed to render template: {0}")]
     RenderError(#[from] TeraError),
     #[error("Template format error: Missing 'Subject:' line or '---' separator")]
     FormatError,
+    #[error("No template: set sender.template_path, or sender.subject and sender.body")]
+    NoSource,
+}
+
+/// What "Create it" writes when the template file is missing: the format `load` reads.
+pub const DEFAULT_TEMPLATE_FILE: &str = "\
+Subject: Coffee Chat Invitation - {{ sender_name }}
+---
+Hi {{ recipient_name }},
+
+I'm {{ sender_name }}, and I'd love to connect for a brief coffee chat sometime soon.
+
+Here are some times that work for me (just reply with the option number):
+{% for slot in slots %}
+- {% if slot.index %}{{ slot.index }}) {% endif %}{{ slot.text }}
+{% endfor %}
+
+If none of these suit you, feel free to suggest another time.
+
+Best,
+{{ sender_name }}
+";
+
+/// `path` made absolute against the working directory, for error messages.
+fn resolved(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// Writes [`DEFAULT_TEMPLATE_FILE`] to `path`, creating missing folders. Never
+/// replaces an existing file.
+pub fn write_default_template(path: &Path) -> io::Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)?;
+    }
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(DEFAULT_TEMPLATE_FILE.as_bytes())
+}
+
+/// Recipient field naming the language their slots are written in, e.g. "fr".
+pub const LANGUAGE_FIELD: &str = "language";
+
+/// A variable every template can use, for the "Insert variable" menu.
+pub struct TemplateVariable {
+    pub name: &'static str,
+    pub snippet: &'static str, // What gets inserted; lists come with a loop
+    pub description: &'static str,
+}
+
+/// Everything `context` provides besides the recipients' own fields.
+pub const BUILT_IN_VARIABLES: &[TemplateVariable] = &[
+    TemplateVariable {
+        name: "recipient_name",
+        snippet: "{{ recipient_name }}",
+        description: "The recipient's name",
+    },
+    TemplateVariable {
+        name: "recipient_email",
+        snippet: "{{ recipient_email }}",
+        description: "The recipient's address",
+    },
+    TemplateVariable {
+        name: "sender_name",
+        snippet: "{{ sender_name }}",
+        description: "Your name",
+    },
+    TemplateVariable {
+        name: "availabilities",
+        snippet: "{% for time in availabilities %}\n- {{ time }}\n{% endfor %}",
+        description: "Your free slots, one line each",
+    },
+    TemplateVariable {
+        name: "availabilities_compact",
+        snippet: "{{ availabilities_compact }}",
+        description: "The first few slots on one line for a subject, e.g. \"Tue 2–3p, Wed 10–11a, +2 more\"",
+    },
+    TemplateVariable {
+        name: "slots",
+        snippet: "{% for slot in slots %}\n- {% if slot.index %}{{ slot.index }}) {% endif %}{{ slot.text }}\n{% endfor %}",
+        description: "The same lines with the slots numbered, so people can reply \"option 3\"",
+    },
+    TemplateVariable {
+        name: "has_availabilities",
+        snippet: "{% if has_availabilities %}\n{% else %}\n{% endif %}",
+        description: "Whether any real slots are offered",
+    },
+    TemplateVariable {
+        name: "unavailable_days",
+        snippet: "{{ unavailable_days | join(sep=\", \") }}",
+        description: "Fully booked days, e.g. \"Wednesday Apr 9\"",
+    },
+    TemplateVariable {
+        name: "availability_link",
+        snippet: "{{ availability_link }}",
+        description: "Where your availability page is hosted, if you set a base URL",
+    },
+];
+
+/// One line of `{{ slots }}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NumberedSlot {
+    /// Option number from 1; `None` for lines that aren't times, like "+3 more on request"
+    pub index: Option<usize>,
+    pub text: String,
+}
+
+/// Numbers the slot lines in `availabilities` in order, skipping the other lines.
+pub fn numbered_slots(availabilities: &[String]) -> Vec<NumberedSlot> {
+    let mut next = 0;
+    availabilities
+        .iter()
+        .map(|line| NumberedSlot {
+            index: is_slot_line(line).then(|| {
+                next += 1;
+                next
+            }),
+            text: line.clone(),
+        })
+        .collect()
+}
+
+/// The slots an email numbered, in order: option `n` is at `n - 1`.
+pub fn slot_options(availabilities: &[String]) -> Vec<String> {
+    numbered_slots(availabilities)
+        .into_iter()
+        .filter(|slot| slot.index.is_some())
+        .map(|slot| slot.text)
+        .collect()
+}
+
+/// The option someone picked in a reply like "Option 3 works" or "#2 please":
+/// the first number in the text.
+pub fn option_number(reply: &str) -> Option<usize> {
+    let digits: String = reply
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok().filter(|&n| n > 0)
 }
 
 /// Represents the parsed email template content.
@@ -24,6 +180,21 @@ pub enum TemplateError {
 pub struct EmailTemplate {
     pub subject_template: String,
     pub body_template: String,
+    // Optional CC/BCC lists, rendered per recipient like the subject/body
+    pub cc_template: String,
+    pub bcc_template: String,
+    // Fully booked days in the lookahead window, e.g. "Wednesday Apr 9"
+    pub unavailable_days: Vec<String>,
+    // Stands in for `availabilities` when there are no slots; empty means leave it empty
+    pub no_slots_text: String,
+    // Sent as X-Priority / Importance / Priority headers unless normal
+    pub priority: Priority,
+    // URL of the generated availability page; empty when there isn't one
+    pub availability_link: String,
+    // Shared mailbox added to every email's BCC, not rendered; empty when there isn't one
+    pub archive_bcc: String,
+    // The slots behind `availabilities`, so they can be rewritten in a recipient's language
+    pub offered_slots: Option<OfferedSlots>,
     // Keep these private, managed by constructors
     tera: Tera,
     template_name: String,
@@ -37,9 +208,17 @@ impl EmailTemplate {
     /// <body template>
     pub fn load(template_path: &Path) -> Result<Self, TemplateError> {
         let path_str = template_path.to_string_lossy().to_string();
-        let content = fs::read_to_string(template_path).map_err(|e| TemplateError::ReadError {
-            path: path_str.clone(),
-            source: e,
+        let content = fs::read_to_string(template_path).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => TemplateError::Missing {
+                resolved: resolved(template_path),
+            },
+            ErrorKind::PermissionDenied => TemplateError::PermissionDenied {
+                resolved: resolved(template_path),
+            },
+            _ => TemplateError::ReadError {
+                path: path_str.clone(),
+                source: e,
+            },
         })?;
 
         // Split subject and body
@@ -61,6 +240,38 @@ impl EmailTemplate {
         Self::from_content(&subject_template, &body_template, "file_template")
     }
 
+    /// Builds the template `[sender]` in config.toml describes: inline `subject`/`body`
+    /// when both are set, otherwise the file at `template_path` with any inline part
+    /// replacing the file's.
+    pub fn from_sender_config(sender: &SenderConfig) -> Result<Self, TemplateError> {
+        let template = Self::from_sender_parts(sender)?
+            .with_priority(sender.priority.unwrap_or_default())
+            .with_archive_bcc(sender.archive_bcc.as_deref().unwrap_or_default());
+        Ok(match &sender.no_slots_text {
+            Some(text) => template.with_no_slots_text(text),
+            None => template,
+        })
+    }
+
+    fn from_sender_parts(sender: &SenderConfig) -> Result<Self, TemplateError> {
+        if let (Some(subject), Some(body)) = (&sender.subject, &sender.body) {
+            return Self::from_content(subject, body, "config_template");
+        }
+        let path = sender
+            .template_path
+            .as_ref()
+            .ok_or(TemplateError::NoSource)?;
+        let file = Self::load(path)?;
+        match (&sender.subject, &sender.body) {
+            (None, None) => Ok(file),
+            (subject, body) => Self::from_content(
+                subject.as_deref().unwrap_or(&file.subject_template),
+                body.as_deref().unwrap_or(&file.body_template),
+                "config_template",
+            ),
+        }
+    }
+
     /// --- NEW CONSTRUCTOR ---
     /// Creates an EmailTemplate directly from subject and body strings.
     /// Useful for creating templates from UI input.
@@ -82,23 +293,139 @@ impl EmailTemplate {
         Ok(EmailTemplate {
             subject_template: subject.to_string(),
             body_template: body.to_string(),
+            cc_template: String::new(),
+            bcc_template: String::new(),
+            unavailable_days: Vec::new(),
+            no_slots_text: String::new(),
+            priority: Priority::Normal,
+            availability_link: String::new(),
+            archive_bcc: String::new(),
+            offered_slots: None,
             tera,
             // Store the base name used for rendering lookups
             template_name: base_name.to_string(),
         })
     }
 
+    /// Adds CC/BCC templates, e.g. `{{ assistant_email }}, team@example.com`.
+    /// Empty strings mean no copies.
+    pub fn with_copies(mut self, cc: &str, bcc: &str) -> Result<Self, TemplateError> {
+        self.tera
+            .add_raw_templates(vec![
+                (format!("{}_cc", self.template_name), cc),
+                (format!("{}_bcc", self.template_name), bcc),
+            ])
+            .map_err(|e| TemplateError::ParseError {
+                name: format!("{} (CC/BCC)", self.template_name),
+                source: e,
+            })?;
+        self.cc_template = cc.to_string();
+        self.bcc_template = bcc.to_string();
+        Ok(self)
+    }
+
+    /// Sets the line `{{ availabilities }}` holds when there are no slots, so
+    /// "My availability:" isn't followed by nothing. `has_availabilities` stays false.
+    pub fn with_no_slots_text(mut self, text: &str) -> Self {
+        self.no_slots_text = text.trim().to_string();
+        self
+    }
+
+    /// Marks every email from this template as high or low priority.
+    pub fn with_priority(mut self, priority: Priority) -> Self {
+        self.priority = priority;
+        self
+    }
+
+    /// Sets the days exposed to templates as `{{ unavailable_days }}`.
+    pub fn with_unavailable_days(mut self, days: Vec<String>) -> Self {
+        self.unavailable_days = days;
+        self
+    }
+
+    /// Sets `{{ availability_link }}`, the hosted availability page.
+    pub fn with_availability_link(mut self, link: &str) -> Self {
+        self.availability_link = link.trim().to_string();
+        self
+    }
+
+    /// BCCs `address` on every email, whatever the BCC template renders to.
+    pub fn with_archive_bcc(mut self, address: &str) -> Self {
+        self.archive_bcc = address.trim().to_string();
+        self
+    }
+
+    /// Keeps the slots `availabilities` was written from, so a recipient with a
+    /// `language` field gets them in that language.
+    pub fn with_offered_slots(mut self, slots: Option<OfferedSlots>) -> Self {
+        self.offered_slots = slots;
+        self
+    }
+
+    /// `availabilities`, or the same slots in the recipient's language when they have one.
+    pub fn availabilities_for<'a>(
+        &self,
+        recipient: &Recipient,
+        availabilities: &'a [String],
+    ) -> Cow<'a, [String]> {
+        let format = recipient
+            .fields
+            .get(LANGUAGE_FIELD)
+            .and_then(|language| SlotFormat::for_language(language));
+        match (&self.offered_slots, format) {
+            (Some(slots), Some(format)) => Cow::Owned(slots.lines(format)),
+            _ => Cow::Borrowed(availabilities),
+        }
+    }
+
+    /// Builds the rendering context shared by every part of the email.
+    fn context(
+        &self,
+        recipient: &Recipient,
+        sender_name: &str,
+        availabilities: &[String],
+    ) -> Context {
+        let availabilities = &*self.availabilities_for(recipient, availabilities);
+        let mut context = Context::new();
+        // Custom fields go in first so they can't shadow the built-in variables
+        for (key, value) in &recipient.fields {
+            context.insert(key.as_str(), value);
+        }
+        context.insert("recipient_name", &recipient.name);
+        context.insert("recipient_email", &recipient.email);
+        context.insert("sender_name", sender_name);
+        context.insert("has_availabilities", &!availabilities.is_empty());
+        if availabilities.is_empty() && !self.no_slots_text.is_empty() {
+            context.insert("availabilities", &[&self.no_slots_text]);
+            // The placeholder isn't a time anyone can pick
+            let placeholder = NumberedSlot {
+                index: None,
+                text: self.no_slots_text.clone(),
+            };
+            context.insert("slots", &[placeholder]);
+        } else {
+            context.insert("availabilities", availabilities);
+            context.insert("slots", &numbered_slots(availabilities));
+        }
+        let compact = self
+            .offered_slots
+            .as_ref()
+            .map(OfferedSlots::compact)
+            .unwrap_or_default();
+        context.insert("availabilities_compact", &compact);
+        context.insert("unavailable_days", &self.unavailable_days);
+        context.insert("availability_link", &self.availability_link);
+        context
+    }
+
     /// Renders the subject and body using the provided context.
     pub fn render(
         &self,
-        recipient_name: &str,
+        recipient: &Recipient,
         sender_name: &str,
         availabilities: &[String], // Assuming availabilities are strings
     ) -> Result<(String, String), TemplateError> {
-        let mut context = Context::new();
-        context.insert("recipient_name", recipient_name);
-        context.insert("sender_name", sender_name);
-        context.insert("availabilities", availabilities);
+        let context = self.context(recipient, sender_name, availabilities);
 
         // Use the stored template_name base to construct the full names for rendering
         let subject = self
@@ -110,4 +437,154 @@ impl EmailTemplate {
 
         Ok((subject, body))
     }
+
+    /// Renders the CC and BCC templates for one recipient.
+    /// Returns the raw address lists; splitting and validation happen when building the message.
+    pub fn render_copies(
+        &self,
+        recipient: &Recipient,
+        sender_name: &str,
+        availabilities: &[String],
+    ) -> Result<(String, String), TemplateError> {
+        let (cc, mut bcc) = if self.cc_template.is_empty() && self.bcc_template.is_empty() {
+            (String::new(), String::new())
+        } else {
+            let context = self.context(recipient, sender_name, availabilities);
+            let cc = self
+                .tera
+                .render(&format!("{}_cc", self.template_name), &context)?;
+            let bcc = self
+                .tera
+                .render(&format!("{}_bcc", self.template_name), &context)?;
+            (cc, bcc)
+        };
+        if !self.archive_bcc.is_empty() {
+            // Repeats are dropped when the message is built, so no check here
+            if !bcc.trim().is_empty() {
+                bcc.push_str(", ");
+            }
+            bcc.push_str(&self.archive_bcc);
+        }
+        Ok((cc, bcc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn built_in_variables_match_the_context() {
+        let template = EmailTemplate::from_content("", "", "variables").unwrap();
+        let recipient = Recipient {
+            name: "Ana".to_string(),
+            email: "ana@example.com".to_string(),
+            fields: BTreeMap::new(),
+        };
+        let context = template.context(&recipient, "Me", &[]).into_json();
+        let mut provided: Vec<&str> = context
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        let mut listed: Vec<&str> = BUILT_IN_VARIABLES.iter().map(|v| v.name).collect();
+        provided.sort();
+        listed.sort();
+        assert_eq!(provided, listed);
+    }
+
+    #[test]
+    fn a_missing_file_can_be_created_from_the_default() {
+        let dir = std::env::temp_dir().join(format!("coffee_chat_default_{}", std::process::id()));
+        let path = dir.join("templates").join("invite.txt");
+        match EmailTemplate::load(&path) {
+            Err(TemplateError::Missing { resolved }) => {
+                assert!(resolved.is_absolute());
+                assert!(resolved.ends_with("templates/invite.txt"));
+            }
+            other => panic!("expected a missing file, got {:?}", other.err()),
+        }
+        write_default_template(&path).unwrap();
+        let loaded = EmailTemplate::load(&path);
+        // Never overwrites, e.g. after a second click
+        let again = write_default_template(&path);
+        fs::remove_dir_all(&dir).ok();
+        let loaded = loaded.unwrap();
+        assert_eq!(
+            loaded.subject_template,
+            "Coffee Chat Invitation - {{ sender_name }}"
+        );
+        assert!(loaded.body_template.contains("{% for slot in slots %}"));
+        assert_eq!(again.unwrap_err().kind(), ErrorKind::AlreadyExists);
+    }
+
+    #[test]
+    fn built_in_snippets_render() {
+        let body: Vec<&str> = BUILT_IN_VARIABLES.iter().map(|v| v.snippet).collect();
+        let template = EmailTemplate::from_content("", &body.join("\n"), "snippets")
+            .unwrap()
+            .with_unavailable_days(vec!["Wednesday Apr 9".to_string()]);
+        let recipient = Recipient {
+            name: "Ana".to_string(),
+            email: "ana@example.com".to_string(),
+            fields: BTreeMap::new(),
+        };
+        let (_, body) = template
+            .render(&recipient, "Me", &["Monday Apr 7: 9am–10am".to_string()])
+            .unwrap();
+        assert!(body.contains("- Monday Apr 7: 9am–10am"));
+        assert!(body.contains("- 1) Monday Apr 7: 9am–10am"));
+        assert!(body.contains("Wednesday Apr 9"));
+    }
+
+    #[test]
+    fn only_slot_lines_are_numbered() {
+        let lines: Vec<String> = [
+            "Monday Apr 7: 9am–10am",
+            "Tuesday Apr 8: no availability",
+            "Wednesday Apr 9: 2pm–3pm",
+            "+4 more on request",
+            "(all times ET)",
+        ]
+        .map(String::from)
+        .to_vec();
+        let indexes: Vec<Option<usize>> = numbered_slots(&lines)
+            .iter()
+            .map(|slot| slot.index)
+            .collect();
+        assert_eq!(indexes, [Some(1), None, Some(2), None, None]);
+        assert_eq!(
+            slot_options(&lines),
+            ["Monday Apr 7: 9am–10am", "Wednesday Apr 9: 2pm–3pm"]
+        );
+    }
+
+    #[test]
+    fn option_number_takes_the_first_number_in_a_reply() {
+        assert_eq!(option_number("option 3 works"), Some(3));
+        assert_eq!(option_number("#12 please, or 4"), Some(12));
+        assert_eq!(option_number("3"), Some(3));
+        assert_eq!(option_number("any time works"), None);
+        assert_eq!(option_number("0"), None);
+    }
+
+    #[test]
+    fn no_slots_text_is_not_numbered() {
+        let template = EmailTemplate::from_content(
+            "",
+            "{% for slot in slots %}{{ slot.index | default(value=\"-\") }} {{ slot.text }}{% endfor %}",
+            "placeholder",
+        )
+        .unwrap()
+        .with_no_slots_text("Tell me what works");
+        let recipient = Recipient {
+            name: "Ana".to_string(),
+            email: "ana@example.com".to_string(),
+            fields: BTreeMap::new(),
+        };
+        let (_, body) = template.render(&recipient, "Me", &[]).unwrap();
+        assert_eq!(body, "- Tell me what works");
+    }
 }
@@ -0,0 +1,252 @@
+// src/email_sender/webhook.rs
+// Sending through an HTTP endpoint instead of SMTP, for setups where mail has to go
+// through an internal API. Each email is one POST of JSON; any 2xx counts as sent.
+
+use super::receipt::SmtpReceipt;
+use super::{address, dedupe_copies, parse_copy_list, subject, EmailError, PreparedEmail};
+use crate::config::{Recipient, WebhookConfig};
+use lettre::message::Mailbox;
+use log::{error, info};
+use serde::Serialize;
+use std::time::Duration;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(30);
+// Error pages can be long; the start is enough to tell what went wrong
+const MAX_ERROR_BODY_CHARS: usize = 500;
+
+/// The JSON body of the POST.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct WebhookPayload {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+    pub cc: Vec<String>,
+    pub bcc: Vec<String>,
+}
+
+impl WebhookPayload {
+    /// Checks the email the same way the SMTP path does before anything is posted.
+    pub fn new(recipient: &Recipient, prepared: &PreparedEmail) -> Result<Self, EmailError> {
+        if let Some(position) = subject::line_break_position(&prepared.subject) {
+            return Err(EmailError::SubjectLineBreak { position });
+        }
+        let to = address::parse_mailbox(&recipient.email)?;
+        let (cc, bcc) = dedupe_copies(
+            &to,
+            parse_copy_list("CC", &prepared.cc)?,
+            parse_copy_list("BCC", &prepared.bcc)?,
+        );
+        let addresses =
+            |mailboxes: Vec<Mailbox>| mailboxes.iter().map(|m| m.email.to_string()).collect();
+        Ok(Self {
+            to: to.email.to_string(),
+            subject: prepared.subject.clone(),
+            body: prepared.body.clone(),
+            cc: addresses(cc),
+            bcc: addresses(bcc),
+        })
+    }
+}
+
+/// Posts one email. The receipt holds the HTTP status and the start of the response body.
+pub async fn deliver(
+    webhook: &WebhookConfig,
+    recipient: &Recipient,
+    prepared: &PreparedEmail,
+) -> Result<SmtpReceipt, EmailError> {
+    let payload = WebhookPayload::new(recipient, prepared)?;
+    let client = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .build()
+        .map_err(EmailError::Webhook)?;
+    let response = client
+        .post(&webhook.url)
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| {
+            error!(
+                "Error posting email for {} to webhook: {}",
+                recipient.email, e
+            );
+            EmailError::Webhook(e)
+        })?;
+    let status = response.status();
+    // A body that can't be read only loses the detail, not the outcome
+    let body = truncate(response.text().await.unwrap_or_default().trim());
+    if !status.is_success() {
+        error!(
+            "Webhook rejected email for {}: {} {}",
+            recipient.email, status, body
+        );
+        return Err(EmailError::WebhookStatus {
+            status: status.as_u16(),
+            body,
+        });
+    }
+    let receipt = SmtpReceipt {
+        code: status.as_u16().to_string(),
+        message: body,
+        message_id: None, // The endpoint builds the email, and its headers
+    };
+    info!(
+        "Email posted to webhook for {} ({}): {}",
+        recipient.name,
+        recipient.email,
+        receipt.summary()
+    );
+    Ok(receipt)
+}
+
+fn truncate(body: &str) -> String {
+    match body.char_indices().nth(MAX_ERROR_BODY_CHARS) {
+        Some((end, _)) => format!("{}…", &body[..end]),
+        None => body.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recipient() -> Recipient {
+        Recipient {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            fields: Default::default(),
+        }
+    }
+
+    #[test]
+    fn payload_lists_copies_without_repeating_the_recipient() {
+        let prepared = PreparedEmail {
+            subject: "Coffee?".to_string(),
+            body: "Hi Ada".to_string(),
+            cc: "Ada <ADA@example.com>, assistant@example.com".to_string(),
+            bcc: "archive@example.com; assistant@example.com".to_string(),
+            ..Default::default()
+        };
+        let payload = WebhookPayload::new(&recipient(), &prepared).unwrap();
+        assert_eq!(
+            payload,
+            WebhookPayload {
+                to: "ada@example.com".to_string(),
+                subject: "Coffee?".to_string(),
+                body: "Hi Ada".to_string(),
+                cc: vec!["assistant@example.com".to_string()],
+                bcc: vec!["archive@example.com".to_string()],
+            }
+        );
+
+        let broken = PreparedEmail {
+            subject: "Coffee\n?".to_string(),
+            ..prepared
+        };
+        assert!(matches!(
+            WebhookPayload::new(&recipient(), &broken),
+            Err(EmailError::SubjectLineBreak { position: 7 })
+        ));
+    }
+
+    #[test]
+    fn long_error_bodies_are_cut() {
+        assert_eq!(truncate("short"), "short");
+        let long = "é".repeat(MAX_ERROR_BODY_CHARS + 10);
+        assert_eq!(truncate(&long).chars().count(), MAX_ERROR_BODY_CHARS + 1);
+    }
+}
+
+// Run with `cargo test --features integration-tests`, against a one-shot HTTP server.
+#[cfg(all(test, feature = "integration-tests"))]
+mod integration_tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    /// Answers one request with `status` and `reply`, forwarding the request body.
+    fn start_webhook(
+        status: &'static str,
+        reply: &'static str,
+    ) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("bind test webhook");
+        let url = format!("http://{}/send", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().expect("accept webhook client");
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap_or(0) > 0 && line != "\r\n" {
+                if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                    content_length = value.trim().parse().unwrap();
+                }
+                line.clear();
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            tx.send(String::from_utf8(body).unwrap()).ok();
+
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                reply.len(),
+                reply
+            )
+            .unwrap();
+        });
+
+        (url, rx)
+    }
+
+    fn prepared() -> PreparedEmail {
+        PreparedEmail {
+            subject: "Coffee?".to_string(),
+            body: "Hi Ada".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn recipient() -> Recipient {
+        Recipient {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+            fields: Default::default(),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn posts_the_email_as_json() {
+        let (url, received) = start_webhook("202 Accepted", "queued 42");
+        let receipt = deliver(&WebhookConfig { url }, &recipient(), &prepared())
+            .await
+            .expect("post to webhook");
+        assert_eq!(receipt.summary(), "202 queued 42");
+
+        let body: serde_json::Value = serde_json::from_str(
+            &received
+                .recv_timeout(Duration::from_secs(10))
+                .expect("webhook received a request"),
+        )
+        .unwrap();
+        assert_eq!(body["to"], "ada@example.com");
+        assert_eq!(body["subject"], "Coffee?");
+        assert_eq!(body["body"], "Hi Ada");
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn non_2xx_is_a_failure_with_the_body() {
+        let (url, _received) = start_webhook("500 Internal Server Error", "mailbox quota exceeded");
+        match deliver(&WebhookConfig { url }, &recipient(), &prepared()).await {
+            Err(EmailError::WebhookStatus { status, body }) => {
+                assert_eq!(status, 500);
+                assert_eq!(body, "mailbox quota exceeded");
+            }
+            other => panic!("expected a webhook status error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,37 @@
+// src/error.rs
+
+use crate::calendar::connect_error::CalendarConnectError;
+use crate::email_sender::template::TemplateError;
+use thiserror::Error;
+
+/// Failures in the headless (command-line) modes, typed so `main` can map them to exit codes.
+#[derive(Error, Debug)]
+pub enum AppError {
+    #[error("Failed to load configuration: {0}")]
+    Config(#[from] ::config::ConfigError),
+
+    #[error("Failed to load template: {0}")]
+    Template(#[from] TemplateError),
+
+    #[error("Calendar connection failed: {0}")]
+    CalendarConnect(#[from] CalendarConnectError),
+
+    #[error("Failed to fetch slots: {0}")]
+    SlotFetch(String),
+
+    #[error("Drip schedule: {0}")]
+    DripSchedule(String),
+
+    #[error("Frozen slots: {0}")]
+    FrozenSlots(String),
+
+    #[error("{failed} of {total} emails failed to send")]
+    PartialSend { failed: usize, total: usize },
+
+    #[error("stopped after {consecutive} failures in a row; {unsent} of {total} emails not sent")]
+    Aborted {
+        consecutive: u32,
+        unsent: usize,
+        total: usize,
+    },
+}
@@ -0,0 +1,137 @@
+// src/frozen_slots.rs
+// A frozen slot list: the slots approved at the start of a campaign, offered by
+// every send until it's unfrozen, however the calendar changes in between. It
+// lives in frozen_slots.json next to the drip schedule, so `coffee-chat send --due`
+// offers the same list as the GUI.
+
+use crate::calendar::offered::OfferedSlots;
+use crate::email_sender::history::{self, RecordedSlot};
+use chrono::{DateTime, Utc};
+use directories_next::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrozenSlots {
+    pub label: String,
+    pub frozen_at: DateTime<Utc>,
+    pub lines: Vec<String>, // `{{ availabilities }}` as approved
+    pub unavailable_days: Vec<String>,
+    // The slots behind `lines`, for other languages and for checking them against the
+    // calendar. `None` when the list was pasted in rather than fetched
+    pub offered: Option<OfferedSlots>,
+}
+
+impl FrozenSlots {
+    /// Each slot line with the window behind it, where there is one.
+    pub fn slots(&self) -> Vec<RecordedSlot> {
+        let windows = self
+            .offered
+            .as_ref()
+            .map(OfferedSlots::slot_windows)
+            .unwrap_or_default();
+        history::record_slots(&self.lines, &windows)
+    }
+
+    /// The frozen slot lines that aren't inside `free` any more, e.g. the windows of a
+    /// fetch made after freezing. Lines without a window can't be checked.
+    pub fn busy_lines(&self, free: &[(DateTime<Utc>, DateTime<Utc>)]) -> Vec<String> {
+        self.slots()
+            .into_iter()
+            .filter(|slot| {
+                slot.window
+                    .is_some_and(|window| !history::still_free(window, free))
+            })
+            .map(|slot| slot.text)
+            .collect()
+    }
+
+    /// Reads frozen_slots.json; `None` when nothing is frozen.
+    pub fn load() -> Result<Option<Self>, String> {
+        let path = frozen_slots_path()?;
+        if !path.exists() {
+            return Ok(None);
+        }
+        let json = fs::read_to_string(&path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|e| format!("Couldn't parse {}: {}", path.display(), e))
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let path = frozen_slots_path()?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(&path, json).map_err(|e| format!("Couldn't write {}: {}", path.display(), e))
+    }
+
+    /// Removes frozen_slots.json, so sends go back to the slots fetched at the time.
+    pub fn unfreeze() -> Result<(), String> {
+        let path = frozen_slots_path()?;
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("Couldn't remove {}: {}", path.display(), e))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// frozen_slots.json, next to app_state.json, creating the directory if needed.
+pub fn frozen_slots_path() -> Result<PathBuf, String> {
+    let proj_dirs = ProjectDirs::from("com", "YourOrg", "CoffeeChatHelper")
+        .ok_or("Could not determine project directory for the frozen slots.")?;
+    let config_dir = proj_dirs.config_dir();
+    fs::create_dir_all(config_dir)
+        .map_err(|e| format!("Failed to create config directory {:?}: {}", config_dir, e))?;
+    Ok(config_dir.join("frozen_slots.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Local
+            .with_ymd_and_hms(2025, 4, day, hour, 0, 0)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn frozen() -> FrozenSlots {
+        let offered = OfferedSlots {
+            windows: vec![(at(8, 14), at(8, 15)), (at(9, 10), at(9, 12))],
+            label: Some("(all times CEST)".to_string()),
+            ..OfferedSlots::default()
+        };
+        FrozenSlots {
+            label: "Spring outreach".to_string(),
+            frozen_at: at(7, 9),
+            lines: offered.lines(Default::default()),
+            unavailable_days: Vec::new(),
+            offered: Some(offered),
+        }
+    }
+
+    #[test]
+    fn slots_that_got_booked_are_found() {
+        let frozen = frozen();
+        // Wednesday's 10–12 shrank to 11–12; Tuesday is untouched
+        let free = [(at(8, 13), at(8, 17)), (at(9, 11), at(9, 12))];
+        let busy = frozen.busy_lines(&free);
+        assert_eq!(busy, [frozen.lines[1].clone()]);
+        assert!(frozen.busy_lines(&[(at(8, 9), at(9, 17))]).is_empty());
+    }
+
+    #[test]
+    fn a_freeze_round_trips_through_json() {
+        let frozen = frozen();
+        let json = serde_json::to_string(&frozen).unwrap();
+        assert_eq!(serde_json::from_str::<FrozenSlots>(&json).unwrap(), frozen);
+        // The label line has no window, so it's never reported busy
+        assert_eq!(frozen.slots().len(), 3);
+        assert!(frozen.slots()[2].window.is_none());
+    }
+}
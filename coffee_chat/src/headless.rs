@@ -0,0 +1,458 @@
+// src/headless.rs
+// Command-line modes that run without the GUI, using config.toml and the template file.
+
+use crate::app::{MyApp, SavedSlotSettings};
+use crate::calendar::format::SlotFormat;
+use crate::calendar::offered::OfferedSlots;
+use crate::calendar::units::{HourOfDay, Minutes};
+use crate::calendar::{self, BusySource, SlotFilters};
+use crate::config::{dedupe_recipients, AppConfig, Recipient, SenderConfig};
+use crate::drip::{self, DripSchedule, Invitation, Tried};
+use crate::email_sender::{
+    failure_streak::{FailureStreak, DEFAULT_ABORT_AFTER_FAILURES},
+    post_process::EmailPostProcessor,
+    send_context::SendContext,
+    template::EmailTemplate,
+    Transport,
+};
+use crate::error::AppError;
+use crate::frozen_slots::FrozenSlots;
+use crate::hooks::{self, HookConfig, HookEvent};
+use chrono::{Duration, Local, NaiveDate, Utc};
+use log::{error, info, warn};
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+
+// Same defaults as the GUI's Calendar Settings
+const CREDENTIALS_PATH: &str = "credentials.json";
+pub const TOKEN_CACHE_PATH: &str = "tokencache.json";
+const BUFFER: Minutes = Minutes(15);
+const DAY_START_HOUR: HourOfDay = HourOfDay::new(9).unwrap();
+const DAY_END_HOUR: HourOfDay = HourOfDay::new(17).unwrap();
+
+fn transport(config: &AppConfig) -> Result<Transport, AppError> {
+    // `AppConfig::load_for_sending` already insists on one of the two
+    config.transport().ok_or_else(|| {
+        AppError::Config(::config::ConfigError::Message(
+            "no [smtp] or [webhook] section".to_string(),
+        ))
+    })
+}
+
+fn abort_after_failures(sender: &SenderConfig) -> u32 {
+    sender
+        .abort_after_failures
+        .unwrap_or(DEFAULT_ABORT_AFTER_FAILURES)
+}
+
+/// Advisory only: role addresses are still sent to, since there's nobody to ask.
+fn warn_about_role_accounts(sender: &SenderConfig, recipients: &[Recipient]) {
+    let flagged = sender
+        .role_accounts()
+        .flagged(recipients.iter().map(|r| r.email.as_str()));
+    if !flagged.is_empty() {
+        warn!(
+            "{} recipients look like shared role mailboxes, which rarely reach a person: {}",
+            flagged.len(),
+            flagged.join(", ")
+        );
+    }
+}
+
+fn aborted(streak: &FailureStreak, unsent: usize, total: usize) -> AppError {
+    error!(
+        "{}; check the SMTP settings before sending again",
+        streak.abort_reason(unsent).unwrap_or_default()
+    );
+    AppError::Aborted {
+        consecutive: streak.consecutive(),
+        unsent,
+        total,
+    }
+}
+
+/// The hooks fired during a run. They're waited for before the run returns, since
+/// the process exits right after; each one is cut off by its own timeout.
+struct RunHooks {
+    hooks: Vec<HookConfig>,
+    pending: Vec<JoinHandle<()>>,
+}
+
+impl RunHooks {
+    fn new(hooks: Vec<HookConfig>) -> Self {
+        Self {
+            hooks,
+            pending: Vec::new(),
+        }
+    }
+
+    fn fire(&mut self, event: HookEvent) {
+        self.pending
+            .extend(hooks::fire(&self.hooks, &event, &Handle::current()));
+    }
+
+    async fn finish(self) {
+        for hook in self.pending {
+            hook.await.ok(); // Failures are logged by the hook itself
+        }
+    }
+}
+
+/// `fetch_offered_slots`, firing `slots_fetched` or `calendar_error`.
+async fn fetch_slots_with_hooks(
+    settings: Option<&SavedSlotSettings>,
+    hooks: &mut RunHooks,
+) -> Result<OfferedSlots, AppError> {
+    let result = fetch_offered_slots(settings).await;
+    hooks.fire(match &result {
+        Ok(offered) => HookEvent::SlotsFetched {
+            slots: offered.lines(SlotFormat::default()).len(),
+        },
+        Err(e) => HookEvent::CalendarError {
+            error: e.to_string(),
+        },
+    });
+    result
+}
+
+/// The slot lines a send offers, and the slots behind them: the frozen list while
+/// the GUI has one frozen, otherwise a fresh fetch (or none without `with_slots`).
+/// Alongside a freeze, the fetch only checks whether frozen slots were booked since.
+async fn slots_for_send(
+    frozen: Option<&FrozenSlots>,
+    with_slots: bool,
+    settings: Option<&SavedSlotSettings>,
+    hooks: &mut RunHooks,
+) -> Result<(Vec<String>, Option<OfferedSlots>), AppError> {
+    let Some(frozen) = frozen else {
+        let offered = if with_slots {
+            Some(fetch_slots_with_hooks(settings, hooks).await?)
+        } else {
+            None
+        };
+        let lines = offered
+            .as_ref()
+            .map(|offered| offered.lines(SlotFormat::default()))
+            .unwrap_or_default();
+        return Ok((lines, offered));
+    };
+    info!("Offering the frozen slots \"{}\".", frozen.label);
+    if with_slots {
+        match fetch_slots_with_hooks(settings, hooks).await {
+            Ok(fetched) => {
+                let busy = frozen.busy_lines(&fetched.windows);
+                if !busy.is_empty() {
+                    warn!(
+                        "{} frozen slots are now busy on your calendar and still offered: {}",
+                        busy.len(),
+                        busy.join("; ")
+                    );
+                }
+            }
+            Err(e) => warn!(
+                "Couldn't check the frozen slots against the calendar: {}",
+                e
+            ),
+        }
+    }
+    Ok((frozen.lines.clone(), frozen.offered.clone()))
+}
+
+/// Connects to Google Calendar and returns the summarized available slots.
+pub async fn fetch_slots() -> Result<Vec<String>, AppError> {
+    Ok(fetch_offered_slots(None)
+        .await?
+        .lines(SlotFormat::default()))
+}
+
+/// The slots `fetch_slots` summarizes, kept as windows so each email can write
+/// them its own way. Uses `settings` where given, otherwise the GUI's defaults.
+async fn fetch_offered_slots(
+    settings: Option<&SavedSlotSettings>,
+) -> Result<OfferedSlots, AppError> {
+    let google = MyApp::setup_calendar_hub(CREDENTIALS_PATH, TOKEN_CACHE_PATH).await?;
+    let defaults = SavedSlotSettings {
+        filters: SlotFilters {
+            buffer: BUFFER.into(),
+            start_hour: DAY_START_HOUR,
+            end_hour: DAY_END_HOUR,
+            ..Default::default()
+        },
+        lookahead_days: calendar::DEFAULT_LOOKAHEAD_DAYS,
+        calendar_ids: Vec::new(),
+        max_in_email: calendar::DEFAULT_MAX_SLOTS_IN_EMAIL as usize,
+    };
+    let settings = settings.unwrap_or(&defaults);
+    let source = BusySource::Google(google);
+    let found = calendar::find_available_slots(
+        &source,
+        &settings.filters,
+        settings.lookahead_days,
+        &settings.calendar_ids,
+    )
+    .await
+    .map_err(|e| AppError::SlotFetch(calendar::describe_fetch_error(&*e)))?;
+    Ok(OfferedSlots {
+        windows: found.windows,
+        max_in_email: settings.max_in_email,
+        ..OfferedSlots::default()
+    })
+}
+
+/// Sends the invitation to every recipient in config.toml, passing each rendered
+/// email through `post_processor`. Fails with `PartialSend` if any single email failed,
+/// or `Aborted` once too many fail in a row.
+pub async fn send_all(
+    with_slots: bool,
+    post_processor: &dyn EmailPostProcessor,
+) -> Result<usize, AppError> {
+    let config = AppConfig::load_for_sending()?;
+    let mut hooks = RunHooks::new(config.hooks.clone());
+    let result = send_to_recipients(config, with_slots, post_processor, &mut hooks).await;
+    hooks.finish().await;
+    result
+}
+
+async fn send_to_recipients(
+    config: AppConfig,
+    with_slots: bool,
+    post_processor: &dyn EmailPostProcessor,
+    hooks: &mut RunHooks,
+) -> Result<usize, AppError> {
+    let transport = transport(&config)?;
+    let frozen = FrozenSlots::load().map_err(AppError::FrozenSlots)?;
+    let (availabilities, offered) =
+        slots_for_send(frozen.as_ref(), with_slots, None, hooks).await?;
+    let mut template =
+        EmailTemplate::from_sender_config(&config.sender)?.with_offered_slots(offered);
+    if let Some(frozen) = frozen {
+        template = template.with_unavailable_days(frozen.unavailable_days);
+    }
+    let (recipients, duplicates) = dedupe_recipients(config.recipients);
+    for duplicate in &duplicates {
+        info!("Skipping duplicate recipient {}", duplicate.email);
+    }
+    warn_about_role_accounts(&config.sender, &recipients);
+    info!(
+        "Sending to {} recipients with {} slots",
+        recipients.len(),
+        availabilities.len()
+    );
+    if !template.archive_bcc.is_empty() {
+        info!(
+            "Archiving a copy of every email to {}",
+            template.archive_bcc
+        );
+    }
+    let context = SendContext::new(transport, &config.sender.name, availabilities, template);
+    context.check_server();
+
+    let total = recipients.len();
+    let mut streak = FailureStreak::new(abort_after_failures(&config.sender));
+    let mut unsent = 0;
+    for (position, recipient) in recipients.iter().enumerate() {
+        if streak.should_abort() {
+            unsent = total - position;
+            break;
+        }
+        match context.send_to(recipient, post_processor).await {
+            Ok(_) => streak.success(),
+            Err(e) => {
+                error!("Failed to send to {}: {}", recipient.email, e);
+                hooks.fire(HookEvent::EmailFailed {
+                    email: recipient.email.clone(),
+                    error: e.to_string(),
+                });
+                streak.failure();
+            }
+        }
+    }
+
+    let failed = streak.total() as usize;
+    hooks.fire(HookEvent::BatchFinished {
+        sent: total - failed - unsent,
+        failed,
+        skipped: duplicates.len(),
+        unsent,
+        aborted: streak.abort_reason(unsent).filter(|_| unsent > 0),
+    });
+    if unsent > 0 {
+        Err(aborted(&streak, unsent, total))
+    } else if failed > 0 {
+        Err(AppError::PartialSend { failed, total })
+    } else {
+        Ok(total)
+    }
+}
+
+/// Sends the drip-schedule batches that are due today or earlier, each with slots
+/// fetched again using the GUI's saved Calendar Settings. Each batch is claimed in the
+/// schedule file first and each recipient marked off as they're sent, so the GUI
+/// never sends the same batch and a run that stops partway is picked up where it left off.
+pub async fn send_due_batches(
+    with_slots: bool,
+    post_processor: &dyn EmailPostProcessor,
+) -> Result<usize, AppError> {
+    let Some(schedule) = DripSchedule::load().map_err(AppError::DripSchedule)? else {
+        info!("No drip schedule; nothing is due.");
+        return Ok(0);
+    };
+    let today = Local::now().date_naive();
+    if schedule.due_batches(today).is_empty() {
+        info!(
+            "No batches due; {} still pending.",
+            schedule.pending_count()
+        );
+        return Ok(0);
+    }
+    // The schedule carries its own template, so only the transport is needed
+    let config = AppConfig::load()?;
+    let settings = MyApp::saved_slot_settings().unwrap_or_else(|e| {
+        warn!("{}; fetching slots with the default Calendar Settings.", e);
+        None
+    });
+    let mut hooks = RunHooks::new(config.hooks.clone());
+    let result = send_batches(
+        &config,
+        &schedule.invitation,
+        today,
+        with_slots,
+        settings.as_ref(),
+        post_processor,
+        &mut hooks,
+    )
+    .await;
+    hooks.finish().await;
+    result
+}
+
+/// Marks `email` off in batch `index`. Not being able to is only logged: the email
+/// is out either way, and failing here would stop the rest of the batch.
+async fn record_sent(index: usize, email: &str, tried: Tried) {
+    let email = email.to_string();
+    let recorded = update_drip(move |schedule| {
+        if let Some(schedule) = schedule {
+            schedule.record(index, &email, tried, Utc::now());
+        }
+    });
+    if let Err(e) = recorded.await {
+        warn!("{}", e);
+    }
+}
+
+/// [`drip::update`] on a blocking thread, since it can wait seconds for the lock.
+async fn update_drip<T: Send + 'static>(
+    change: impl FnOnce(&mut Option<DripSchedule>) -> T + Send + 'static,
+) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(move || drip::update(change).map(|(result, _)| result))
+        .await
+        .map_err(|e| AppError::DripSchedule(e.to_string()))?
+        .map_err(AppError::DripSchedule)
+}
+
+async fn send_batches(
+    config: &AppConfig,
+    invitation: &Invitation,
+    today: NaiveDate,
+    with_slots: bool,
+    settings: Option<&SavedSlotSettings>,
+    post_processor: &dyn EmailPostProcessor,
+    hooks: &mut RunHooks,
+) -> Result<usize, AppError> {
+    let frozen = FrozenSlots::load().map_err(AppError::FrozenSlots)?;
+    let unavailable_days = frozen
+        .as_ref()
+        .map(|frozen| frozen.unavailable_days.clone())
+        .unwrap_or_default();
+    let mut context = SendContext::new(
+        transport(config)?,
+        &invitation.sender_name,
+        Vec::new(),
+        invitation.template(unavailable_days)?,
+    );
+    context.check_server();
+
+    let owner = drip::claimant("send --due");
+    let mut total = 0;
+    let mut failed_total = 0;
+    loop {
+        // Batches the GUI is sending are left to it
+        let owner = owner.clone();
+        let claimed = update_drip(move |schedule| {
+            let schedule = schedule.as_mut()?;
+            let index = schedule.claim_next_due(today, &owner, Utc::now())?;
+            Some((index, schedule.batches[index].remaining()))
+        })
+        .await?;
+        let Some((index, recipients)) = claimed else {
+            break;
+        };
+        let (availabilities, offered) =
+            slots_for_send(frozen.as_ref(), with_slots, settings, hooks).await?;
+        info!(
+            "Sending drip batch {} to {} recipients with {} slots",
+            index + 1,
+            recipients.len(),
+            availabilities.len()
+        );
+        context.availabilities = availabilities;
+        context.template.offered_slots = offered;
+        let mut streak = FailureStreak::new(abort_after_failures(&config.sender));
+        let mut failed = 0;
+        let mut unsent = 0;
+        for (position, recipient) in recipients.iter().enumerate() {
+            if streak.should_abort() {
+                unsent = recipients.len() - position;
+                break;
+            }
+            match context.send_to(recipient, post_processor).await {
+                Ok(_) => {
+                    streak.success();
+                    record_sent(index, &recipient.email, Tried::Sent).await;
+                }
+                Err(e) => {
+                    error!("Failed to send to {}: {}", recipient.email, e);
+                    hooks.fire(HookEvent::EmailFailed {
+                        email: recipient.email.clone(),
+                        error: e.to_string(),
+                    });
+                    streak.failure();
+                    record_sent(index, &recipient.email, Tried::Failed).await;
+                    failed += 1;
+                }
+            }
+        }
+        hooks.fire(HookEvent::BatchFinished {
+            sent: recipients.len() - failed - unsent,
+            failed,
+            skipped: 0,
+            unsent,
+            aborted: streak.abort_reason(unsent).filter(|_| unsent > 0),
+        });
+        // Anyone left over goes into a batch for tomorrow. An error here leaves the
+        // claim to lapse; the recipients marked off stay sent.
+        let tomorrow = today + Duration::days(1);
+        update_drip(move |schedule| {
+            if let Some(schedule) = schedule {
+                schedule.finish(index, Utc::now(), tomorrow);
+            }
+        })
+        .await?;
+        total += recipients.len();
+        failed_total += failed;
+        if unsent > 0 {
+            // The server is likely down for the rest too; they stay unclaimed for next time
+            info!("{} recipients moved to a batch due tomorrow.", unsent);
+            return Err(aborted(&streak, unsent, total));
+        }
+    }
+
+    if failed_total > 0 {
+        Err(AppError::PartialSend {
+            failed: failed_total,
+            total,
+        })
+    } else {
+        Ok(total)
+    }
+}
@@ -2,17 +2,199 @@
 mod app;
 mod calendar;
 mod config;
+mod control;
+mod drip;
 mod email_sender; // <-- Add this
+mod error;
+mod frozen_slots;
+mod headless;
+mod hooks;
 
-use app::MyApp;
+use app::{MyApp, StartMode, APP_TITLE};
 use eframe::egui;
+use email_sender::post_process::NoopPostProcessor;
+use error::AppError;
+use log::LevelFilter;
 use rustls::crypto::ring; // <-- Add for crypto provider installation
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: coffee-chat [--verbose | --quiet] [--demo | --safe-mode | --reset-data | COMMAND]
+
+Commands:
+  (none)             Start the GUI (--demo: sample data, nothing is sent;
+                     --safe-mode: load no saved files, to repair them;
+                     --reset-data: delete everything the app has saved)
+  send [--no-slots]  Send invitations to the recipients in config.toml
+       [--due]       ...or only the drip-schedule batches that are due
+  slots              Print the available slots
+
+Exit codes: 0 success, 2 some emails failed, 3 configuration error,
+            4 calendar/auth error, 64 bad arguments";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Gui { demo: bool, safe_mode: bool },
+    Send { with_slots: bool, due_only: bool },
+    Slots,
+    ResetData,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct CliArgs {
+    command: Command,
+    verbosity: Verbosity,
+}
+
+/// Why a headless run ended; each maps to a fixed process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitReason {
+    Success,
+    PartialFailure,
+    Config,
+    Calendar,
+    Usage,
+}
+
+impl ExitReason {
+    fn code(self) -> u8 {
+        match self {
+            ExitReason::Success => 0,
+            ExitReason::PartialFailure => 2,
+            ExitReason::Config => 3,
+            ExitReason::Calendar => 4,
+            ExitReason::Usage => 64,
+        }
+    }
+}
+
+impl From<&AppError> for ExitReason {
+    fn from(error: &AppError) -> Self {
+        match error {
+            AppError::Config(_)
+            | AppError::Template(_)
+            | AppError::DripSchedule(_)
+            | AppError::FrozenSlots(_) => ExitReason::Config,
+            AppError::CalendarConnect(_) | AppError::SlotFetch(_) => ExitReason::Calendar,
+            AppError::PartialSend { .. } | AppError::Aborted { .. } => ExitReason::PartialFailure,
+        }
+    }
+}
+
+/// Parses the arguments after the program name. Flags may come before or after the command.
+fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<CliArgs, String> {
+    let mut command = None;
+    let mut verbosity = Verbosity::Normal;
+    let mut no_slots = false;
+    let mut due_only = false;
+    let mut demo = false;
+    let mut safe_mode = false;
+    let mut reset_data = false;
+
+    for arg in args {
+        match arg.as_str() {
+            "-v" | "--verbose" | "-q" | "--quiet" => {
+                let requested = if matches!(arg.as_str(), "-v" | "--verbose") {
+                    Verbosity::Verbose
+                } else {
+                    Verbosity::Quiet
+                };
+                if verbosity != Verbosity::Normal && verbosity != requested {
+                    return Err("--verbose and --quiet can't be used together".to_string());
+                }
+                verbosity = requested;
+            }
+            "--no-slots" => no_slots = true,
+            "--due" => due_only = true,
+            "--demo" => demo = true,
+            "--safe-mode" => safe_mode = true,
+            "--reset-data" => reset_data = true,
+            "send" | "slots" if command.is_none() => command = Some(arg),
+            other => return Err(format!("Unexpected argument '{}'", other)),
+        }
+    }
+
+    let command = match command.as_deref() {
+        None => Command::Gui { demo, safe_mode },
+        Some("send") => Command::Send {
+            with_slots: !no_slots,
+            due_only,
+        },
+        Some(_) => Command::Slots,
+    };
+    if no_slots && !matches!(command, Command::Send { .. }) {
+        return Err("--no-slots only applies to 'send'".to_string());
+    }
+    if due_only && !matches!(command, Command::Send { .. }) {
+        return Err("--due only applies to 'send'".to_string());
+    }
+    if demo && !matches!(command, Command::Gui { .. }) {
+        return Err("--demo only applies to the GUI".to_string());
+    }
+    if safe_mode && !matches!(command, Command::Gui { .. }) {
+        return Err("--safe-mode only applies to the GUI".to_string());
+    }
+    if demo && safe_mode {
+        return Err("--demo and --safe-mode can't be used together".to_string());
+    }
+    if reset_data {
+        if command
+            != (Command::Gui {
+                demo: false,
+                safe_mode: false,
+            })
+        {
+            return Err(
+                "--reset-data can't be combined with a command, --demo or --safe-mode".to_string(),
+            );
+        }
+        return Ok(CliArgs {
+            command: Command::ResetData,
+            verbosity,
+        });
+    }
+    Ok(CliArgs { command, verbosity })
+}
+
+fn init_logger(verbosity: Verbosity) {
+    // RUST_LOG still applies unless a flag overrides it
+    let mut builder = env_logger::Builder::from_default_env();
+    match verbosity {
+        Verbosity::Quiet => {
+            builder.filter_level(LevelFilter::Error);
+        }
+        Verbosity::Verbose => {
+            builder.filter_level(LevelFilter::Debug);
+        }
+        Verbosity::Normal => {}
+    }
+    builder.init();
+}
+
+fn main() -> ExitCode {
+    let cli = match parse_args(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return ExitCode::from(ExitReason::Usage.code());
+        }
+    };
+
+    // --- Initialize logger ---
+    init_logger(cli.verbosity);
+    log::info!("Logger initialized."); // Use log crate
 
-fn main() -> Result<(), eframe::Error> {
     // --- Load .env file ---
     match dotenvy::dotenv() {
-        Ok(path) => println!("Loaded .env file from: {:?}", path),
-        Err(_) => println!("Note: .env file not found or failed to load. Relying on config file and existing environment variables."),
+        Ok(path) => log::info!("Loaded .env file from: {:?}", path),
+        Err(_) => log::info!("Note: .env file not found or failed to load. Relying on config file and existing environment variables."),
     }
 
     // --- Install Rustls Crypto Provider ---
@@ -20,10 +202,121 @@ fn main() -> Result<(), eframe::Error> {
         .install_default()
         .expect("Failed to install rustls crypto provider");
 
-    // --- Initialize logger ---
-    env_logger::init();
-    log::info!("Logger initialized."); // Use log crate
+    match cli.command {
+        Command::Gui { demo, safe_mode } => run_gui(match (demo, safe_mode) {
+            (true, _) => StartMode::Demo,
+            (_, true) => StartMode::Safe,
+            _ => StartMode::Normal,
+        }),
+        Command::ResetData => run_reset_data(),
+        command => run_headless(command),
+    }
+}
+
+fn run_headless(command: Command) -> ExitCode {
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
+    let result = runtime.block_on(async {
+        match command {
+            Command::Send {
+                with_slots,
+                due_only: false,
+            } => headless::send_all(with_slots, &NoopPostProcessor)
+                .await
+                .map(|sent| {
+                    log::info!("Sent {} invitations.", sent);
+                }),
+            Command::Send {
+                with_slots,
+                due_only: true,
+            } => headless::send_due_batches(with_slots, &NoopPostProcessor)
+                .await
+                .map(|sent| {
+                    log::info!("Sent {} invitations from due drip batches.", sent);
+                }),
+            Command::Slots => headless::fetch_slots().await.map(|slots| {
+                for slot in slots {
+                    println!("{}", slot);
+                }
+            }),
+            Command::Gui { .. } | Command::ResetData => {
+                unreachable!("GUI mode and --reset-data are handled in main")
+            }
+        }
+    });
+
+    let reason = match &result {
+        Ok(()) => ExitReason::Success,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitReason::from(e)
+        }
+    };
+    ExitCode::from(reason.code())
+}
+
+/// `--reset-data`: lists every file the app has saved, deletes them once RESET is
+/// typed, and reports each one. Exits with 2 if any couldn't be deleted.
+fn run_reset_data() -> ExitCode {
+    let files = app::data_files(headless::TOKEN_CACHE_PATH);
+    println!(
+        "This deletes everything {} has saved on this computer:",
+        APP_TITLE
+    );
+    for file in &files {
+        match &file.path {
+            Ok(path) if path.exists() => println!("  {}: {}", file.label, path.display()),
+            Ok(path) => println!("  {}: {} (not there)", file.label, path.display()),
+            Err(e) => println!("  {}: {}", file.label, e),
+        }
+    }
+    println!("config.toml, your template and credentials.json are yours and stay.");
+    print!("Type {} to confirm: ", app::RESET_CONFIRMATION);
+    std::io::Write::flush(&mut std::io::stdout()).ok();
+    let mut typed = String::new();
+    std::io::stdin().read_line(&mut typed).ok();
+    if typed.trim() != app::RESET_CONFIRMATION {
+        println!("Nothing was deleted.");
+        return ExitCode::from(ExitReason::Success.code());
+    }
+
+    let mut failed = 0;
+    for (label, outcome) in app::delete_data_files(&files) {
+        match outcome {
+            Ok(done) => println!("✔ {}: {}", label, done),
+            Err(e) => {
+                failed += 1;
+                eprintln!("✖ {}: {}", label, e);
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("{} item(s) couldn't be deleted.", failed);
+        return ExitCode::from(ExitReason::PartialFailure.code());
+    }
+    println!("Done. The next start is a first run.");
+    ExitCode::from(ExitReason::Success.code())
+}
+
+/// After the usual panic message, points at safe mode when the GUI crashes before it
+/// has finished starting, which is usually a bad app_state.json or config.toml.
+fn install_safe_mode_hint() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        default_hook(info);
+        if !app::has_started() {
+            eprintln!(
+                "\n{} crashed while starting. Run it with --safe-mode, or hold Shift while it \
+                 opens, to start without the saved session and config.toml and repair them.",
+                APP_TITLE
+            );
+        }
+    }));
+}
 
+fn run_gui(mode: StartMode) -> ExitCode {
+    if mode != StartMode::Safe {
+        install_safe_mode_hint();
+    }
     // --- Native Options ---
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
@@ -34,14 +327,152 @@ fn main() -> Result<(), eframe::Error> {
 
     println!("Starting egui application...");
 
-    eframe::run_native(
-        "Coffee Chat Helper",
+    let result = eframe::run_native(
+        APP_TITLE,
         options,
         Box::new(|cc| {
             // --- Pass CreationContext to MyApp::new ---
             // Ensure MyApp::new accepts cc and potentially sets up styles
-            let app = MyApp::new(cc);
+            let app = MyApp::new(cc, mode);
             Ok(Box::new(app))
         }),
-    )
+    );
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::calendar::connect_error::{CalendarConnectError, ConnectErrorKind};
+    use crate::email_sender::template::TemplateError;
+
+    fn parse(args: &[&str]) -> Result<CliArgs, String> {
+        parse_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn no_arguments_starts_gui() {
+        assert_eq!(
+            parse(&[]),
+            Ok(CliArgs {
+                command: Command::Gui {
+                    demo: false,
+                    safe_mode: false
+                },
+                verbosity: Verbosity::Normal,
+            })
+        );
+    }
+
+    #[test]
+    fn flags_are_accepted_before_and_after_the_command() {
+        assert_eq!(
+            parse(&["--quiet", "send"]),
+            Ok(CliArgs {
+                command: Command::Send {
+                    with_slots: true,
+                    due_only: false
+                },
+                verbosity: Verbosity::Quiet,
+            })
+        );
+        assert_eq!(
+            parse(&["slots", "-v"]),
+            Ok(CliArgs {
+                command: Command::Slots,
+                verbosity: Verbosity::Verbose,
+            })
+        );
+        assert_eq!(
+            parse(&["send", "--no-slots"]).map(|cli| cli.command),
+            Ok(Command::Send {
+                with_slots: false,
+                due_only: false
+            })
+        );
+        assert_eq!(
+            parse(&["send", "--due"]).map(|cli| cli.command),
+            Ok(Command::Send {
+                with_slots: true,
+                due_only: true
+            })
+        );
+        assert_eq!(
+            parse(&["--demo"]).map(|cli| cli.command),
+            Ok(Command::Gui {
+                demo: true,
+                safe_mode: false
+            })
+        );
+        assert_eq!(
+            parse(&["--safe-mode", "-v"]).map(|cli| cli.command),
+            Ok(Command::Gui {
+                demo: false,
+                safe_mode: true
+            })
+        );
+        assert_eq!(
+            parse(&["-q", "--reset-data"]).map(|cli| cli.command),
+            Ok(Command::ResetData)
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_rejected() {
+        assert!(parse(&["--quiet", "--verbose"]).is_err());
+        assert!(parse(&["frobnicate"]).is_err());
+        assert!(parse(&["send", "slots"]).is_err());
+        assert!(parse(&["slots", "--no-slots"]).is_err());
+        assert!(parse(&["send", "--demo"]).is_err());
+        assert!(parse(&["slots", "--due"]).is_err());
+        assert!(parse(&["send", "--safe-mode"]).is_err());
+        assert!(parse(&["--demo", "--safe-mode"]).is_err());
+        assert!(parse(&["send", "--reset-data"]).is_err());
+        assert!(parse(&["--reset-data", "--safe-mode"]).is_err());
+    }
+
+    #[test]
+    fn app_errors_map_to_exit_codes() {
+        let code = |e: AppError| ExitReason::from(&e).code();
+        assert_eq!(
+            code(AppError::Config(::config::ConfigError::Message(
+                "missing".into()
+            ))),
+            3
+        );
+        assert_eq!(code(AppError::Template(TemplateError::FormatError)), 3);
+        let io = std::io::Error::other("boom");
+        assert_eq!(
+            code(AppError::CalendarConnect(CalendarConnectError::new(
+                ConnectErrorKind::Other,
+                &io
+            ))),
+            4
+        );
+        assert_eq!(code(AppError::SlotFetch("quota".into())), 4);
+        assert_eq!(code(AppError::DripSchedule("unreadable".into())), 3);
+        assert_eq!(code(AppError::FrozenSlots("unreadable".into())), 3);
+        assert_eq!(
+            code(AppError::PartialSend {
+                failed: 1,
+                total: 3
+            }),
+            2
+        );
+        assert_eq!(
+            code(AppError::Aborted {
+                consecutive: 5,
+                unsent: 10,
+                total: 15
+            }),
+            2
+        );
+        assert_eq!(ExitReason::Success.code(), 0);
+    }
 }